@@ -0,0 +1,740 @@
+use crate::{
+    fractal_view::{ParameterBlock, View},
+    gpu::Gpu,
+    iteration_budget::MAX_ITERATIONS,
+};
+use bytemuck::{Pod, Zeroable};
+use cgmath::{InnerSpace, Matrix3, Vector2};
+use iced::futures;
+use iced_wgpu::wgpu::{self, util::DeviceExt};
+use std::{f64::consts::FRAC_PI_4, num::NonZeroU64};
+
+const WORKGROUP_SIZE: u32 = 8;
+
+/// The resolutions at which the escape mask is sampled to estimate the box-counting dimension.
+/// Each is one box size in the fit of the scaling law.
+const DIMENSION_RESOLUTIONS: [u32; 5] = [64, 128, 256, 512, 1024];
+
+/// The 97.5th percentile of Student's t-distribution with `DIMENSION_RESOLUTIONS.len() - 2`
+/// degrees of freedom, for a two-sided 95% confidence interval on the fitted slope.
+const T_975_3_DEGREES_OF_FREEDOM: f64 = 3.182;
+
+/// The number of samples along each axis when estimating areas.
+pub(super) const AREA_RESOLUTION: u32 = 2048;
+
+/// The number of bins into which escape times are sorted in an [`IterationHistogram`]. Must match
+/// the constant of the same name in `analysis.wgsl`.
+pub(super) const HISTOGRAM_BINS: usize = 50;
+
+/// The number of samples along each axis when computing histograms.
+pub(super) const HISTOGRAM_RESOLUTION: u32 = 256;
+
+/// The number of samples along the longer side of a probed region.
+pub(super) const PROBE_RESOLUTION: u32 = 256;
+
+// The sum of the iterations of a probed region is accumulated in a `u32`.
+const _: () =
+    assert!((PROBE_RESOLUTION as u64).pow(2) * (MAX_ITERATIONS as u64 + 1) <= u32::MAX as u64);
+
+/// Which pixels of a rendering of the Mandelbrot set lie in the set, in row-major order from the
+/// top-left corner.
+pub(super) struct EscapeMask {
+    width: u32,
+    height: u32,
+    interior: Vec<bool>,
+}
+
+impl EscapeMask {
+    fn is_interior(&self, x: u32, y: u32) -> bool {
+        self.interior[(y * self.width + x) as usize]
+    }
+
+    /// Counts the interior pixels which have an exterior pixel as one of their four neighbours.
+    fn count_boundary_pixels(&self) -> usize {
+        let mut count = 0;
+        for y in 0..self.height {
+            for x in 0..self.width {
+                if !self.is_interior(x, y) {
+                    continue;
+                }
+                let neighbours = [
+                    (x.wrapping_sub(1), y),
+                    (x + 1, y),
+                    (x, y.wrapping_sub(1)),
+                    (x, y + 1),
+                ];
+                if neighbours.into_iter().any(|(nx, ny)| {
+                    nx < self.width && ny < self.height && !self.is_interior(nx, ny)
+                }) {
+                    count += 1;
+                }
+            }
+        }
+        count
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(super) struct DimensionEstimate {
+    pub(super) dimension: f64,
+    /// The half-width of the 95% confidence interval of the dimension.
+    pub(super) confidence: f64,
+}
+
+/// Computes statistics of the Mandelbrot set in a region of the complex plane on the GPU.
+pub(super) struct Analyzer {
+    uniform_bind_group_layout: wgpu::BindGroupLayout,
+    escape_mask_pass: AnalysisPass,
+    count_interior_pass: AnalysisPass,
+    histogram_pass: AnalysisPass,
+    region_statistics_pass: AnalysisPass,
+}
+
+/// A compute shader entry point which samples the region and writes its result to a storage
+/// buffer.
+struct AnalysisPass {
+    pipeline: wgpu::ComputePipeline,
+    output_bind_group_layout: wgpu::BindGroupLayout,
+    output_binding: u32,
+}
+
+impl Analyzer {
+    pub(super) fn new(gpu: &Gpu) -> Self {
+        let uniform_bind_group_layout =
+            gpu.device
+                .create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                    label: Some("Analysis uniform bind group layout"),
+                    entries: &[ParameterBlock::layout_entry()],
+                });
+        let module = gpu
+            .device
+            .create_shader_module(wgpu::ShaderModuleDescriptor {
+                label: Some("shader/analysis.wgsl"),
+                source: wgpu::ShaderSource::Wgsl(
+                    concat!(
+                        include_str!("shader/frag.wgsl"),
+                        include_str!("shader/analysis.wgsl")
+                    )
+                    .into(),
+                ),
+            });
+        let create_pass = |entry_point, output_binding| {
+            let output_bind_group_layout =
+                create_output_bind_group_layout(gpu, entry_point, output_binding);
+            let layout = gpu
+                .device
+                .create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                    label: Some(entry_point),
+                    bind_group_layouts: &[&uniform_bind_group_layout, &output_bind_group_layout],
+                    push_constant_ranges: &[],
+                });
+            let pipeline = gpu
+                .device
+                .create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+                    label: Some(entry_point),
+                    layout: Some(&layout),
+                    module: &module,
+                    entry_point,
+                });
+            AnalysisPass {
+                pipeline,
+                output_bind_group_layout,
+                output_binding,
+            }
+        };
+        let escape_mask_pass = create_pass("escape_mask", 1);
+        let count_interior_pass = create_pass("count_interior", 2);
+        let histogram_pass = create_pass("iteration_histogram", 3);
+        let region_statistics_pass = create_pass("region_statistics", 4);
+        Self {
+            uniform_bind_group_layout,
+            escape_mask_pass,
+            count_interior_pass,
+            histogram_pass,
+            region_statistics_pass,
+        }
+    }
+
+    /// Determines which pixels of a `width` by `height` rendering of the region lie in the Mandelbrot set.
+    #[tracing::instrument(skip(self, gpu, region))]
+    pub(super) fn escape_mask(
+        &self,
+        gpu: &Gpu,
+        region: Region,
+        width: u32,
+        height: u32,
+    ) -> EscapeMask {
+        let interior = self
+            .run(
+                gpu,
+                &self.escape_mask_pass,
+                region,
+                width,
+                height,
+                width * height,
+            )
+            .into_iter()
+            .map(|value| value != 0)
+            .collect();
+        EscapeMask {
+            width,
+            height,
+            interior,
+        }
+    }
+
+    /// Estimates the area of the part of the Mandelbrot set within the region by sampling it on a `resolution` by `resolution` grid.
+    ///
+    /// The interior samples are counted on the GPU with a reduction, so that only the count is
+    /// read back.
+    #[tracing::instrument(skip(self, gpu, region))]
+    pub(super) fn estimate_area(&self, gpu: &Gpu, region: Region, resolution: u32) -> AreaEstimate {
+        let interior_count = self.run(
+            gpu,
+            &self.count_interior_pass,
+            region,
+            resolution,
+            resolution,
+            1,
+        )[0];
+        let samples = (resolution as f64).powi(2);
+        let fraction = interior_count as f64 / samples;
+        // The transform maps the square [-1, 1] x [-1, 1] onto the region.
+        let region_area = 4.0
+            * region
+                .transform
+                .x
+                .truncate()
+                .perp_dot(region.transform.y.truncate())
+                .abs() as f64;
+        AreaEstimate {
+            area: fraction * region_area,
+            standard_error: (fraction * (1.0 - fraction) / samples).sqrt() * region_area,
+        }
+    }
+
+    /// Sorts the samples of a `resolution` by `resolution` grid over the region by the number of iterations after which they escaped.
+    #[tracing::instrument(skip(self, gpu, region))]
+    pub(super) fn iteration_histogram(
+        &self,
+        gpu: &Gpu,
+        region: Region,
+        resolution: u32,
+    ) -> IterationHistogram {
+        let counts = self.run(
+            gpu,
+            &self.histogram_pass,
+            region,
+            resolution,
+            resolution,
+            HISTOGRAM_BINS as u32 + 1,
+        );
+        IterationHistogram {
+            escaped: counts[..HISTOGRAM_BINS].try_into().unwrap(),
+            interior: counts[HISTOGRAM_BINS],
+        }
+    }
+
+    /// Summarises the escape times and the boundary of the Mandelbrot set within the region,
+    /// sampled on a grid with [`PROBE_RESOLUTION`] samples along its longer side.
+    ///
+    /// The samples are reduced on the GPU, so that only the totals are read back.
+    #[tracing::instrument(skip(self, gpu, region))]
+    pub(super) fn region_statistics(&self, gpu: &Gpu, region: Region) -> RegionStatistics {
+        // The extent of the region along its horizontal and vertical axes.
+        let width = 2.0 * region.transform.x.truncate().magnitude() as f64;
+        let height = 2.0 * region.transform.y.truncate().magnitude() as f64;
+        let scale = PROBE_RESOLUTION as f64 / width.max(height);
+        let columns = ((width * scale).round() as u32).max(1);
+        let rows = ((height * scale).round() as u32).max(1);
+        let output = self.run(
+            gpu,
+            &self.region_statistics_pass,
+            region,
+            columns,
+            rows,
+            (std::mem::size_of::<RegionAccumulator>() / std::mem::size_of::<u32>()) as u32,
+        );
+        let accumulator: RegionAccumulator =
+            bytemuck::pod_read_unaligned(bytemuck::cast_slice(&output));
+        let samples = columns * rows;
+        let escaped = accumulator.escaped;
+        // Adjacent samples on either side of the boundary trace it as a staircase, which is on
+        // average 4/π times longer than the curve for boundaries running in every direction.
+        let boundary_length = FRAC_PI_4
+            * (accumulator.horizontal_edges as f64 * height / rows as f64
+                + accumulator.vertical_edges as f64 * width / columns as f64);
+        RegionStatistics {
+            iteration_range: (escaped > 0).then_some((
+                !accumulator.fewest_iterations_complement,
+                accumulator.most_iterations,
+            )),
+            mean_iterations: (escaped > 0)
+                .then(|| accumulator.iteration_sum as f64 / escaped as f64),
+            interior_fraction: (samples - escaped) as f64 / samples as f64,
+            boundary_length,
+        }
+    }
+
+    /// Runs the pass over a `width` by `height` grid of samples of the region and returns the `output_len` values of its output buffer.
+    fn run(
+        &self,
+        gpu: &Gpu,
+        pass: &AnalysisPass,
+        region: Region,
+        width: u32,
+        height: u32,
+        output_len: u32,
+    ) -> Vec<u32> {
+        let uniform_buffer = gpu
+            .device
+            .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("Analysis uniform buffer"),
+                contents: &region.parameter_block().std140_bytes(),
+                usage: wgpu::BufferUsages::UNIFORM,
+            });
+        let size_buffer = gpu
+            .device
+            .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("Analysis size buffer"),
+                contents: bytemuck::cast_slice(&[MaskSize { width, height }]),
+                usage: wgpu::BufferUsages::UNIFORM,
+            });
+        // Buffers are zeroed on creation, which the count relies on.
+        let output_size = output_len as u64 * std::mem::size_of::<u32>() as u64;
+        let output_buffer = gpu.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Analysis output buffer"),
+            size: output_size,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        });
+        let readback_buffer = gpu.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Analysis readback buffer"),
+            size: output_size,
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let uniform_bind_group = gpu.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Analysis uniform bind group"),
+            layout: &self.uniform_bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: uniform_buffer.as_entire_binding(),
+            }],
+        });
+        let output_bind_group = gpu.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Analysis output bind group"),
+            layout: &pass.output_bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: size_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: pass.output_binding,
+                    resource: output_buffer.as_entire_binding(),
+                },
+            ],
+        });
+
+        let mut encoder = gpu
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+        {
+            let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                label: Some("Analysis pass"),
+                timestamp_writes: None,
+            });
+            compute_pass.set_pipeline(&pass.pipeline);
+            compute_pass.set_bind_group(0, &uniform_bind_group, &[]);
+            compute_pass.set_bind_group(1, &output_bind_group, &[]);
+            compute_pass.dispatch_workgroups(
+                width.div_ceil(WORKGROUP_SIZE),
+                height.div_ceil(WORKGROUP_SIZE),
+                1,
+            );
+        }
+        encoder.copy_buffer_to_buffer(&output_buffer, 0, &readback_buffer, 0, output_size);
+        gpu.queue.submit(Some(encoder.finish()));
+
+        read_buffer(gpu, &readback_buffer)
+    }
+
+    /// Estimates the box-counting dimension of the boundary of the Mandelbrot set within the
+    /// region.
+    ///
+    /// The boundary pixels of escape masks at several resolutions are counted and the dimension
+    /// is the slope of the least-squares fit of `log(count)` against `log(resolution)`. Returns
+    /// `None` if the region contains no boundary at some resolution.
+    pub(super) fn box_counting_dimension(
+        &self,
+        gpu: &Gpu,
+        region: Region,
+    ) -> Option<DimensionEstimate> {
+        let samples = DIMENSION_RESOLUTIONS
+            .into_iter()
+            .map(|resolution| {
+                let count = self
+                    .escape_mask(gpu, region, resolution, resolution)
+                    .count_boundary_pixels();
+                (count > 0).then(|| ((resolution as f64).ln(), (count as f64).ln()))
+            })
+            .collect::<Option<Vec<_>>>()?;
+        let (slope, standard_error) = fit_line(&samples);
+        Some(DimensionEstimate {
+            dimension: slope,
+            confidence: T_975_3_DEGREES_OF_FREEDOM * standard_error,
+        })
+    }
+}
+
+/// The distribution of escape times over a region.
+#[derive(Clone, Debug, PartialEq)]
+pub(super) struct IterationHistogram {
+    /// The number of samples which escaped, with the iteration budget split evenly between the
+    /// bins.
+    pub(super) escaped: [u32; HISTOGRAM_BINS],
+    /// The number of samples which did not escape within the iteration budget.
+    pub(super) interior: u32,
+}
+
+/// A summary of the samples of a region, as shown by the probe tool.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(super) struct RegionStatistics {
+    /// The fewest and most iterations after which a sample escaped, or `None` if none did.
+    pub(super) iteration_range: Option<(u32, u32)>,
+    /// The mean number of iterations after which the escaping samples escaped.
+    pub(super) mean_iterations: Option<f64>,
+    /// The fraction of the samples which did not escape within the iteration budget.
+    pub(super) interior_fraction: f64,
+    /// The estimated length of the boundary of the set within the region, which grows without
+    /// limit with the resolution, since the boundary is a fractal.
+    pub(super) boundary_length: f64,
+}
+
+/// The region whose area is estimated.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(super) enum AreaRegion {
+    /// The part of the set in the current view.
+    View,
+    /// The whole Mandelbrot set.
+    WholeSet,
+}
+
+/// A region of the complex plane together with the iteration budget with which it is sampled.
+#[derive(Clone, Copy, Debug)]
+pub(super) struct Region {
+    /// Maps the square `[-1, 1] x [-1, 1]` onto the region, as the view transform of [`View`].
+    pub(super) transform: Matrix3<f32>,
+    pub(super) max_iterations: u32,
+}
+
+impl Region {
+    pub(super) fn of_view(view: &View) -> Self {
+        Self {
+            transform: view.get_view_transform(),
+            max_iterations: view.max_iterations(),
+        }
+    }
+
+    /// The rectangle of the view between the given corners, in the coordinates of the square
+    /// `[-1, 1] x [-1, 1]` which the view transform maps onto the complex plane.
+    pub(super) fn within_view(view: &View, corner: Vector2<f32>, opposite: Vector2<f32>) -> Self {
+        let centre = (corner + opposite) / 2.0;
+        let half_size = (opposite - corner) / 2.0;
+        Self {
+            transform: view.get_view_transform()
+                * Matrix3::from_translation(centre)
+                * Matrix3::from_nonuniform_scale(half_size.x.abs(), half_size.y.abs()),
+            max_iterations: view.max_iterations(),
+        }
+    }
+
+    /// The square `[-2, 0.5] x [-1.25, 1.25]`, which contains the whole Mandelbrot set.
+    pub(super) fn whole_set(max_iterations: u32) -> Self {
+        Self {
+            transform: Matrix3::from_translation(Vector2::new(-0.75, 0.0))
+                * Matrix3::from_scale(1.25),
+            max_iterations,
+        }
+    }
+
+    /// The parameters with which the region is sampled. Analysis always uses the default bailout,
+    /// since every radius of at least two yields the same set.
+    fn parameter_block(&self) -> ParameterBlock {
+        ParameterBlock {
+            transform: self.transform,
+            max_iterations: self.max_iterations,
+            ..Default::default()
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(super) struct AreaEstimate {
+    pub(super) area: f64,
+    pub(super) standard_error: f64,
+}
+
+fn create_output_bind_group_layout(
+    gpu: &Gpu,
+    label: &str,
+    output_binding: u32,
+) -> wgpu::BindGroupLayout {
+    gpu.device
+        .create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some(label),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: NonZeroU64::new(std::mem::size_of::<MaskSize>() as u64),
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: output_binding,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Storage { read_only: false },
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
+        })
+}
+
+/// The output of `region_statistics` in `analysis.wgsl`.
+#[repr(C)]
+#[derive(Clone, Copy, Pod, Zeroable)]
+struct RegionAccumulator {
+    fewest_iterations_complement: u32,
+    most_iterations: u32,
+    iteration_sum: u32,
+    escaped: u32,
+    horizontal_edges: u32,
+    vertical_edges: u32,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Pod, Zeroable)]
+struct MaskSize {
+    width: u32,
+    height: u32,
+}
+
+/// Returns the slope of the least-squares line through the points and its standard error.
+fn fit_line(points: &[(f64, f64)]) -> (f64, f64) {
+    let n = points.len() as f64;
+    let mean_x = points.iter().map(|(x, _)| x).sum::<f64>() / n;
+    let mean_y = points.iter().map(|(_, y)| y).sum::<f64>() / n;
+    let sxx: f64 = points.iter().map(|(x, _)| (x - mean_x).powi(2)).sum();
+    let sxy: f64 = points
+        .iter()
+        .map(|(x, y)| (x - mean_x) * (y - mean_y))
+        .sum();
+    let slope = sxy / sxx;
+    let intercept = mean_y - slope * mean_x;
+    let residual_sum_of_squares: f64 = points
+        .iter()
+        .map(|(x, y)| (y - intercept - slope * x).powi(2))
+        .sum();
+    let standard_error = (residual_sum_of_squares / (n - 2.0) / sxx).sqrt();
+    (slope, standard_error)
+}
+
+fn read_buffer<T: Pod>(gpu: &Gpu, buffer: &wgpu::Buffer) -> Vec<T> {
+    let slice = buffer.slice(..);
+    let (sender, receiver) = futures::channel::oneshot::channel();
+    slice.map_async(wgpu::MapMode::Read, move |result| {
+        let _ = sender.send(result);
+    });
+    gpu.device.poll(wgpu::Maintain::Wait);
+    futures::executor::block_on(receiver)
+        .expect("Readback channel closed")
+        .expect("Failed to map readback buffer");
+    let values = bytemuck::pod_collect_to_vec(&slice.get_mapped_range());
+    buffer.unmap();
+    values
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{fit_line, Analyzer, Region, AREA_RESOLUTION, HISTOGRAM_BINS};
+    use crate::{
+        fractal_view::{View, DEFAULT_MAX_ITERATIONS},
+        gpu::Gpu,
+    };
+    use cgmath::Vector2;
+    use googletest::prelude::*;
+    use std::f64::consts::FRAC_PI_4;
+
+    #[test]
+    fn fits_exact_line_without_error() -> Result<()> {
+        let (slope, standard_error) = fit_line(&[(0.0, 1.0), (1.0, 2.5), (2.0, 4.0), (3.0, 5.5)]);
+
+        verify_that!((slope, standard_error), (approx_eq(1.5), near(0.0, 1e-9)))
+    }
+
+    #[async_std::test]
+    async fn escape_mask_marks_interior_of_main_cardioid() -> Result<()> {
+        let gpu = Gpu::new_without_surface()?;
+        let mut view = View::new(&gpu);
+        // The left half lies in the main cardioid and the right half outside the set.
+        view.look_at(Vector2::new(0.25, 0.0), 0.1);
+
+        let mask = Analyzer::new(&gpu).escape_mask(&gpu, Region::of_view(&view), 4, 4);
+
+        verify_that!(
+            (mask.is_interior(0, 1), mask.is_interior(3, 1)),
+            (eq(true), eq(false))
+        )
+    }
+
+    #[async_std::test]
+    async fn boundary_dimension_lies_between_curve_and_plane() -> Result<()> {
+        let gpu = Gpu::new_without_surface()?;
+        let mut view = View::new(&gpu);
+        // Part of the boundary of the period two disc with its decorations.
+        view.look_at(Vector2::new(-0.865, 0.21), 0.002);
+
+        let estimate = Analyzer::new(&gpu)
+            .box_counting_dimension(&gpu, Region::of_view(&view))
+            .expect("No boundary in view");
+
+        verify_that!(
+            (estimate.dimension, estimate.confidence),
+            (all!(gt(1.0), le(2.0)), lt(0.5))
+        )
+    }
+
+    #[async_std::test]
+    async fn no_dimension_without_boundary_in_view() -> Result<()> {
+        let gpu = Gpu::new_without_surface()?;
+        let mut view = View::new(&gpu);
+        view.look_at(Vector2::new(-0.2, 0.0), 0.1);
+
+        let estimate = Analyzer::new(&gpu).box_counting_dimension(&gpu, Region::of_view(&view));
+
+        verify_that!(estimate, none())
+    }
+
+    #[async_std::test]
+    async fn estimates_area_of_whole_set() -> Result<()> {
+        let gpu = Gpu::new_without_surface()?;
+
+        let estimate = Analyzer::new(&gpu).estimate_area(
+            &gpu,
+            Region::whole_set(DEFAULT_MAX_ITERATIONS),
+            AREA_RESOLUTION,
+        );
+
+        // The best known estimate is 1.5066, which finite iteration counts slightly exceed.
+        verify_that!(
+            (estimate.area, estimate.standard_error),
+            (near(1.5066, 0.03), all!(gt(0.0), lt(0.01)))
+        )
+    }
+
+    #[async_std::test]
+    async fn area_of_region_inside_set_is_region_area() -> Result<()> {
+        let gpu = Gpu::new_without_surface()?;
+        let mut view = View::new(&gpu);
+        view.look_at(Vector2::new(-0.2, 0.0), 0.1);
+
+        let estimate = Analyzer::new(&gpu).estimate_area(&gpu, Region::of_view(&view), 100);
+
+        verify_that!(
+            (estimate.area, estimate.standard_error),
+            (near(0.04, 1e-6), eq(0.0))
+        )
+    }
+
+    #[async_std::test]
+    async fn histogram_accounts_for_every_sample() -> Result<()> {
+        let gpu = Gpu::new_without_surface()?;
+        let analyzer = Analyzer::new(&gpu);
+        let transform = Region::whole_set(DEFAULT_MAX_ITERATIONS);
+
+        let histogram = analyzer.iteration_histogram(&gpu, transform, 100);
+
+        let escaped: u32 = histogram.escaped.iter().sum();
+        let interior_area = analyzer.estimate_area(&gpu, transform, 100).area;
+        verify_that!(
+            (escaped + histogram.interior, histogram.interior as f64),
+            (eq(100 * 100), near(interior_area / 6.25 * 10000.0, 0.5))
+        )
+    }
+
+    #[async_std::test]
+    async fn fast_escaping_region_falls_in_first_bin() -> Result<()> {
+        let gpu = Gpu::new_without_surface()?;
+        let mut view = View::new(&gpu);
+        view.look_at(Vector2::new(10.0, 10.0), 1.0);
+
+        let histogram = Analyzer::new(&gpu).iteration_histogram(&gpu, Region::of_view(&view), 16);
+
+        verify_that!(
+            (
+                histogram.escaped[0],
+                histogram.escaped[1..HISTOGRAM_BINS].iter().sum::<u32>(),
+                histogram.interior
+            ),
+            (eq(256), eq(0), eq(0))
+        )
+    }
+
+    #[async_std::test]
+    async fn probe_of_fast_escaping_region_has_no_interior_or_boundary() -> Result<()> {
+        let gpu = Gpu::new_without_surface()?;
+        let mut view = View::new(&gpu);
+        view.look_at(Vector2::new(10.0, 10.0), 1.0);
+
+        let statistics = Analyzer::new(&gpu).region_statistics(
+            &gpu,
+            Region::within_view(&view, Vector2::new(-0.5, -0.5), Vector2::new(0.5, 0.25)),
+        );
+
+        verify_that!(
+            (
+                statistics.iteration_range,
+                statistics.mean_iterations,
+                statistics.interior_fraction,
+                statistics.boundary_length
+            ),
+            (eq(Some((0, 0))), eq(Some(0.0)), eq(0.0), eq(0.0))
+        )
+    }
+
+    #[async_std::test]
+    async fn probe_measures_boundary_crossing_region() -> Result<()> {
+        let gpu = Gpu::new_without_surface()?;
+        let mut view = View::new(&gpu);
+        // With a single iteration, the set is bounded by the curve |c² + c| = 2, which crosses
+        // the real axis vertically at 1.
+        view.set_max_iterations(1);
+        view.look_at(Vector2::new(1.0, 0.0), 0.01);
+
+        let statistics = Analyzer::new(&gpu).region_statistics(&gpu, Region::of_view(&view));
+
+        // A vertical boundary is measured exactly by the staircase, which is then shortened by
+        // the factor for boundaries in every direction.
+        verify_that!(
+            (
+                statistics.iteration_range,
+                statistics.interior_fraction,
+                statistics.boundary_length
+            ),
+            (
+                eq(Some((1, 1))),
+                near(0.5, 0.01),
+                near(0.02 * FRAC_PI_4, 0.001)
+            )
+        )
+    }
+}
@@ -0,0 +1,235 @@
+use crate::{
+    gpu::Gpu,
+    transform::{self, ViewTransform},
+};
+use cgmath::Vector2;
+use iced::{alignment::Horizontal, Color, Point, Size, Vector};
+use iced_graphics::geometry::Renderer as _;
+use iced_wgpu::{graphics::Viewport, wgpu, Backend, Renderer, Settings};
+use iced_widget::canvas::{Frame, Path, Stroke, Text};
+use serde::{Deserialize, Serialize};
+
+/// The size of the text of notes.
+const TEXT_SIZE: f32 = 18.0;
+
+/// The space between the text of a note and the start of its arrow.
+const TEXT_GAP: f32 = 6.0;
+
+const ARROW_WIDTH: f32 = 2.0;
+const ARROWHEAD_LENGTH: f32 = 12.0;
+
+/// The angle between the shaft of an arrow and each barb of its head, in radians.
+const ARROWHEAD_ANGLE: f32 = 0.45;
+
+/// A note pinned to a point of the complex plane, which pans and zooms with the fractal, with an
+/// optional arrow to another point.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub(super) struct Annotation {
+    pub(super) text: String,
+    /// The point of the plane at which the text is written, and from which the arrow starts.
+    pub(super) position: Vector2<f64>,
+    /// The point of the plane at which the arrow points, if there is one.
+    #[serde(default)]
+    pub(super) target: Option<Vector2<f64>>,
+}
+
+/// Draws the annotations onto a frame which shows the plane through the given transform.
+///
+/// The text of a note with an arrow ends or starts at its position, so that the arrow leads away
+/// from it rather than through it.
+pub(super) fn draw(frame: &mut Frame, annotations: &[Annotation], transform: &ViewTransform) {
+    let size = frame.size();
+    let to_frame = |point| transform::to_screen(transform.offset_of(point), size);
+    for annotation in annotations {
+        let position = to_frame(annotation.position);
+        let tip = annotation.target.map(to_frame);
+        if let Some(tip) = tip {
+            let stroke = Stroke::default()
+                .with_color(Color::WHITE)
+                .with_width(ARROW_WIDTH);
+            let [left, right] = arrowhead(position, tip);
+            frame.stroke(&Path::line(position, tip), stroke.clone());
+            frame.stroke(
+                &Path::new(|builder| {
+                    builder.move_to(left);
+                    builder.line_to(tip);
+                    builder.line_to(right);
+                }),
+                stroke,
+            );
+        }
+        let alignment = text_alignment(position, tip);
+        let anchor = match alignment {
+            Horizontal::Left => position + Vector::new(TEXT_GAP, 0.0),
+            Horizontal::Center => position,
+            Horizontal::Right => position - Vector::new(TEXT_GAP, 0.0),
+        };
+        // A dark shadow keeps the text legible over bright parts of the fractal.
+        for (offset, color) in [(1.0, Color::BLACK), (0.0, Color::WHITE)] {
+            frame.fill_text(Text {
+                content: annotation.text.clone(),
+                position: anchor + Vector::new(offset, offset),
+                color,
+                size: TEXT_SIZE.into(),
+                horizontal_alignment: alignment,
+                vertical_alignment: iced::alignment::Vertical::Center,
+                ..Text::default()
+            });
+        }
+    }
+}
+
+/// How the text of a note at the given point aligns to it: away from the arrow, or centred on the
+/// point if there is none.
+fn text_alignment(position: Point, tip: Option<Point>) -> Horizontal {
+    match tip {
+        Some(tip) if tip.x > position.x => Horizontal::Right,
+        Some(_) => Horizontal::Left,
+        None => Horizontal::Center,
+    }
+}
+
+/// The ends of the two barbs of the head of an arrow from `tail` to `tip`.
+fn arrowhead(tail: Point, tip: Point) -> [Point; 2] {
+    let direction = tail - tip;
+    let angle = direction.y.atan2(direction.x);
+    [angle - ARROWHEAD_ANGLE, angle + ARROWHEAD_ANGLE]
+        .map(|angle| tip + Vector::new(angle.cos(), angle.sin()) * ARROWHEAD_LENGTH)
+}
+
+/// Draws the annotations over the contents of the texture, which shows the plane through the
+/// given transform, as for an export.
+///
+/// This sets up a renderer of its own, so it is meant for single images rather than every frame.
+pub(super) fn render_overlay(
+    gpu: &Gpu,
+    annotations: &[Annotation],
+    transform: &ViewTransform,
+    target: &wgpu::Texture,
+    encoder: &mut wgpu::CommandEncoder,
+) {
+    let backend = Backend::new(
+        &gpu.device,
+        &gpu.queue,
+        Settings::default(),
+        gpu.texture_format,
+    );
+    let mut renderer = iced_widget::renderer::Renderer::Wgpu(Renderer::new(
+        backend,
+        iced::Font::DEFAULT,
+        iced::Pixels::from(TEXT_SIZE),
+    ));
+    let size = Size::new(target.width(), target.height());
+    let mut frame = Frame::new(&renderer, Size::new(size.width as f32, size.height as f32));
+    draw(&mut frame, annotations, transform);
+    renderer.draw(vec![frame.into_geometry()]);
+    let iced_widget::renderer::Renderer::Wgpu(renderer) = &mut renderer else {
+        unreachable!("The renderer was created for wgpu");
+    };
+    let view = target.create_view(&wgpu::TextureViewDescriptor::default());
+    renderer.with_primitives(|backend, primitives| {
+        backend.present::<&str>(
+            &gpu.device,
+            &gpu.queue,
+            encoder,
+            None,
+            gpu.texture_format,
+            &view,
+            primitives,
+            &Viewport::with_physical_size(size, 1.0),
+            &[],
+        );
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{arrowhead, text_alignment, Annotation, ARROWHEAD_LENGTH};
+    use crate::{export, fractal_view::View, gpu::Gpu};
+    use cgmath::Vector2;
+    use googletest::prelude::*;
+    use iced::{alignment::Horizontal, Point};
+
+    const EXPORT_SIZE: u32 = 64;
+
+    #[test]
+    fn arrowhead_barbs_point_back_along_arrow() -> Result<()> {
+        let [left, right] = arrowhead(Point::new(0.0, 0.0), Point::new(100.0, 0.0));
+
+        verify_that!(
+            (
+                left.x,
+                right.x,
+                left.y + right.y,
+                left.distance(Point::new(100.0, 0.0))
+            ),
+            (
+                lt(100.0),
+                lt(100.0),
+                approx_eq(0.0),
+                approx_eq(ARROWHEAD_LENGTH)
+            )
+        )
+    }
+
+    #[test]
+    fn text_aligns_away_from_arrow() -> Result<()> {
+        let position = Point::new(50.0, 50.0);
+
+        verify_that!(
+            (
+                text_alignment(position, Some(Point::new(80.0, 10.0))),
+                text_alignment(position, Some(Point::new(20.0, 90.0))),
+                text_alignment(position, None)
+            ),
+            (
+                eq(Horizontal::Right),
+                eq(Horizontal::Left),
+                eq(Horizontal::Center)
+            )
+        )
+    }
+
+    #[test]
+    fn arrows_are_drawn_into_exports() -> Result<()> {
+        let gpu = Gpu::new_without_surface()?;
+        let mut view = View::new(&gpu);
+        view.wait_for_pipelines();
+        let annotation = Annotation {
+            text: String::new(),
+            position: view.point_at(Vector2::new(-0.3, 0.0)),
+            target: Some(view.point_at(Vector2::new(0.3, 0.0))),
+        };
+
+        let plain = export::render_to_rgba(&gpu, &view, EXPORT_SIZE, EXPORT_SIZE);
+        let annotated =
+            export::render_annotated_to_rgba(&gpu, &view, &[annotation], EXPORT_SIZE, EXPORT_SIZE);
+        let changed_row = |pixels: &[u8], row: u32| {
+            let row = (row * EXPORT_SIZE * 4) as usize..((row + 1) * EXPORT_SIZE * 4) as usize;
+            pixels[row.clone()] != plain[row]
+        };
+
+        verify_that!(
+            (
+                changed_row(&annotated, EXPORT_SIZE / 2),
+                changed_row(&annotated, 2)
+            ),
+            (eq(true), eq(false))
+        )
+    }
+
+    #[test]
+    fn notes_without_arrow_need_no_target_when_read() -> Result<()> {
+        let annotation: Annotation =
+            serde_json::from_str(r#"{"text": "Seahorse valley", "position": [-0.75, 0.1]}"#)?;
+
+        verify_that!(
+            annotation,
+            eq(Annotation {
+                text: "Seahorse valley".into(),
+                position: Vector2::new(-0.75, 0.1),
+                target: None,
+            })
+        )
+    }
+}
@@ -0,0 +1,212 @@
+use crate::{blit::Blit, controls::FractalType, gpu::Gpu, gpu_memory};
+use iced_wgpu::wgpu;
+use std::{cell::RefCell, collections::HashMap, sync::Arc};
+
+const WORKGROUP_SIZE: u32 = 8;
+
+/// The format of the anti-aliased image, which must support storage in compute shaders on every
+/// backend.
+const RESOLVED_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8Unorm;
+
+/// Smooths the edges of the fractal by resampling only the pixels which differ strongly from their
+/// neighbours.
+///
+/// The fractal is first rendered at one sample per pixel. A compute pass then estimates the
+/// variance of each pixel's neighbourhood and evaluates the fractal at several subpixel positions
+/// wherever it is high, which gives nearly the quality of supersampling while only paying for it
+/// along edges. The result is finally copied onto the render target with a [`Blit`].
+pub(super) struct AdaptiveAntialiasing {
+    device: Arc<wgpu::Device>,
+    texture_format: wgpu::TextureFormat,
+    resample_bind_group_layout: wgpu::BindGroupLayout,
+    resample_pipelines: HashMap<FractalType, wgpu::ComputePipeline>,
+    blit: Blit,
+    targets: RefCell<Option<Targets>>,
+}
+
+/// The intermediate textures for one size of render target.
+struct Targets {
+    size: wgpu::Extent3d,
+    initial: wgpu::TextureView,
+    resample_bind_group: wgpu::BindGroup,
+    blit_bind_group: wgpu::BindGroup,
+}
+
+impl AdaptiveAntialiasing {
+    /// Builds the passes, whose first bind group is the view's uniform block with the given
+    /// layout.
+    pub(super) fn new(gpu: &Gpu, uniform_bind_group_layout: &wgpu::BindGroupLayout) -> Self {
+        let resample_bind_group_layout =
+            gpu.device
+                .create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                    label: Some("Resample bind group layout"),
+                    entries: &[
+                        wgpu::BindGroupLayoutEntry {
+                            binding: 0,
+                            visibility: wgpu::ShaderStages::COMPUTE,
+                            ty: wgpu::BindingType::Texture {
+                                sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                                view_dimension: wgpu::TextureViewDimension::D2,
+                                multisampled: false,
+                            },
+                            count: None,
+                        },
+                        wgpu::BindGroupLayoutEntry {
+                            binding: 1,
+                            visibility: wgpu::ShaderStages::COMPUTE,
+                            ty: wgpu::BindingType::StorageTexture {
+                                access: wgpu::StorageTextureAccess::WriteOnly,
+                                format: RESOLVED_FORMAT,
+                                view_dimension: wgpu::TextureViewDimension::D2,
+                            },
+                            count: None,
+                        },
+                    ],
+                });
+        let resample_module = gpu
+            .device
+            .create_shader_module(wgpu::ShaderModuleDescriptor {
+                label: Some("shader/antialiasing.wgsl"),
+                source: wgpu::ShaderSource::Wgsl(
+                    concat!(
+                        include_str!("shader/frag.wgsl"),
+                        include_str!("shader/antialiasing.wgsl")
+                    )
+                    .into(),
+                ),
+            });
+        let resample_layout = gpu
+            .device
+            .create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some("Resample pipeline layout"),
+                bind_group_layouts: &[uniform_bind_group_layout, &resample_bind_group_layout],
+                push_constant_ranges: &[],
+            });
+        let resample_pipelines = FractalType::ALL
+            .into_iter()
+            .map(|fractal_type| {
+                let entry_point = fractal_type.definition().antialiasing_entry_point;
+                let pipeline =
+                    gpu.device
+                        .create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+                            label: Some(entry_point),
+                            layout: Some(&resample_layout),
+                            module: &resample_module,
+                            entry_point,
+                        });
+                (fractal_type, pipeline)
+            })
+            .collect();
+
+        Self {
+            device: gpu.device.clone(),
+            texture_format: gpu.texture_format,
+            resample_bind_group_layout,
+            resample_pipelines,
+            // The resolved image holds colours encoded in sRGB when the target stores linear light.
+            blit: if gpu.stores_linear_light() {
+                Blit::from_srgb(gpu)
+            } else {
+                Blit::new(gpu)
+            },
+            targets: RefCell::new(None),
+        }
+    }
+
+    /// The size in bytes of the intermediate textures, if they have been created.
+    pub(super) fn gpu_memory(&self) -> u64 {
+        self.targets.borrow().as_ref().map_or(0, |targets| {
+            gpu_memory::texture_bytes(targets.size, self.texture_format)
+                + gpu_memory::texture_bytes(targets.size, RESOLVED_FORMAT)
+        })
+    }
+
+    /// Renders the fractal onto the target with anti-aliasing.
+    ///
+    /// `draw` records the ordinary rendering of the fractal into the given texture view, which is
+    /// then resampled with the uniform block in `uniform_bind_group`.
+    pub(super) fn render(
+        &self,
+        target: &wgpu::Texture,
+        fractal_type: FractalType,
+        uniform_bind_group: &wgpu::BindGroup,
+        encoder: &mut wgpu::CommandEncoder,
+        draw: impl FnOnce(&wgpu::TextureView, &mut wgpu::CommandEncoder),
+    ) {
+        let size = target.size();
+        let mut targets = self.targets.borrow_mut();
+        let targets = match &mut *targets {
+            Some(targets) if targets.size == size => targets,
+            targets => targets.insert(self.create_targets(size)),
+        };
+
+        draw(&targets.initial, encoder);
+        {
+            let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                label: Some("Resample pass"),
+                timestamp_writes: None,
+            });
+            compute_pass.set_pipeline(&self.resample_pipelines[&fractal_type]);
+            compute_pass.set_bind_group(0, uniform_bind_group, &[]);
+            compute_pass.set_bind_group(1, &targets.resample_bind_group, &[]);
+            compute_pass.dispatch_workgroups(
+                size.width.div_ceil(WORKGROUP_SIZE),
+                size.height.div_ceil(WORKGROUP_SIZE),
+                1,
+            );
+        }
+        self.blit.draw(
+            encoder,
+            &targets.blit_bind_group,
+            &target.create_view(&wgpu::TextureViewDescriptor::default()),
+        );
+    }
+
+    fn create_targets(&self, size: wgpu::Extent3d) -> Targets {
+        let create_texture = |label, format, usage| {
+            self.device
+                .create_texture(&wgpu::TextureDescriptor {
+                    label: Some(label),
+                    size,
+                    mip_level_count: 1,
+                    sample_count: 1,
+                    dimension: wgpu::TextureDimension::D2,
+                    format,
+                    usage,
+                    view_formats: &[],
+                })
+                .create_view(&wgpu::TextureViewDescriptor::default())
+        };
+        let initial = create_texture(
+            "Initial render",
+            self.texture_format,
+            wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+        );
+        let resolved = create_texture(
+            "Anti-aliased render",
+            RESOLVED_FORMAT,
+            wgpu::TextureUsages::STORAGE_BINDING | wgpu::TextureUsages::TEXTURE_BINDING,
+        );
+        let resample_bind_group = self.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Resample bind group"),
+            layout: &self.resample_bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&initial),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::TextureView(&resolved),
+                },
+            ],
+        });
+        let blit_bind_group = self.blit.bind(&resolved);
+        Targets {
+            size,
+            initial,
+            resample_bind_group,
+            blit_bind_group,
+        }
+    }
+}
@@ -0,0 +1,126 @@
+use crate::audio_reactive::{AudioReactive, Modulation, SpectrumAnalyzer, FFT_SIZE};
+use cpal::{
+    traits::{DeviceTrait, HostTrait, StreamTrait},
+    FromSample, Sample, SampleFormat, SizedSample,
+};
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex},
+    time::Instant,
+};
+
+/// The animation which keeps the view redrawing while it follows the audio.
+pub(super) const AUDIO_ANIMATION: &str = "audio";
+
+/// Drives the view from the default audio input, turning the explorer into a music visualizer.
+pub(super) struct AudioVisualizer {
+    input: AudioInput,
+    analyzer: SpectrumAnalyzer,
+    reactive: AudioReactive,
+    last_update: Instant,
+}
+
+impl AudioVisualizer {
+    pub(super) fn start() -> Result<Self, AudioInputError> {
+        Ok(Self {
+            input: AudioInput::start()?,
+            analyzer: SpectrumAnalyzer::new(),
+            reactive: AudioReactive::default(),
+            last_update: Instant::now(),
+        })
+    }
+
+    /// Analyzes the most recent audio and returns how it modulates the view now.
+    pub(super) fn update(&mut self) -> Modulation {
+        let levels = self
+            .analyzer
+            .analyze(&self.input.recent_samples(), self.input.sample_rate());
+        let now = Instant::now();
+        let elapsed = now - std::mem::replace(&mut self.last_update, now);
+        self.reactive.update(levels, elapsed)
+    }
+}
+
+/// Captures the default audio input, e.g. a microphone or line-in, keeping the most recent
+/// [`FFT_SIZE`] samples mixed down to mono.
+struct AudioInput {
+    _stream: cpal::Stream,
+    samples: Arc<Mutex<VecDeque<f32>>>,
+    sample_rate: u32,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub(super) enum AudioInputError {
+    #[error("There is no audio input device")]
+    NoDevice,
+    #[error("Audio input in format {0} is not supported")]
+    UnsupportedFormat(SampleFormat),
+    #[error(transparent)]
+    Config(#[from] cpal::DefaultStreamConfigError),
+    #[error(transparent)]
+    BuildStream(#[from] cpal::BuildStreamError),
+    #[error(transparent)]
+    PlayStream(#[from] cpal::PlayStreamError),
+}
+
+impl AudioInput {
+    fn start() -> Result<Self, AudioInputError> {
+        let device = cpal::default_host()
+            .default_input_device()
+            .ok_or(AudioInputError::NoDevice)?;
+        let config = device.default_input_config()?;
+        let samples = Arc::new(Mutex::new(VecDeque::with_capacity(FFT_SIZE)));
+        let stream = match config.sample_format() {
+            SampleFormat::F32 => build_stream::<f32>(&device, &config.config(), samples.clone()),
+            SampleFormat::I16 => build_stream::<i16>(&device, &config.config(), samples.clone()),
+            SampleFormat::U16 => build_stream::<u16>(&device, &config.config(), samples.clone()),
+            format => return Err(AudioInputError::UnsupportedFormat(format)),
+        }?;
+        stream.play()?;
+        tracing::info!(
+            "Capturing audio from {} at {} Hz",
+            device.name().unwrap_or_default(),
+            config.sample_rate().0
+        );
+        Ok(Self {
+            _stream: stream,
+            samples,
+            sample_rate: config.sample_rate().0,
+        })
+    }
+
+    /// Returns the most recent samples, oldest first.
+    fn recent_samples(&self) -> Vec<f32> {
+        self.samples.lock().unwrap().iter().copied().collect()
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+}
+
+fn build_stream<T: SizedSample>(
+    device: &cpal::Device,
+    config: &cpal::StreamConfig,
+    samples: Arc<Mutex<VecDeque<f32>>>,
+) -> Result<cpal::Stream, cpal::BuildStreamError>
+where
+    f32: FromSample<T>,
+{
+    let channels = config.channels as usize;
+    device.build_input_stream(
+        config,
+        move |data: &[T], _| {
+            let mut samples = samples.lock().unwrap();
+            for frame in data.chunks(channels) {
+                let sum: f32 = frame.iter().map(|sample| f32::from_sample(*sample)).sum();
+                if samples.len() == FFT_SIZE {
+                    samples.pop_front();
+                }
+                samples.push_back(sum / channels as f32);
+            }
+        },
+        |error| tracing::warn!("Audio input failed: {error}"),
+        None,
+    )
+}
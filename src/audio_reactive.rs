@@ -0,0 +1,190 @@
+use realfft::{num_complex::Complex, RealFftPlanner, RealToComplex};
+use std::{f32::consts::PI, sync::Arc, time::Duration};
+
+/// The number of samples from which the spectrum is computed, which gives a resolution of about
+/// 23 Hz at 48 kHz.
+pub(super) const FFT_SIZE: usize = 2048;
+
+/// The frequency ranges in Hz of the bass, mid and treble bands.
+const BASS: (f32, f32) = (20.0, 250.0);
+const MID: (f32, f32) = (250.0, 4000.0);
+const TREBLE: (f32, f32) = (4000.0, 16000.0);
+
+/// How quickly the envelope of each band follows rising and falling levels, so that beats register
+/// at once but do not flicker.
+const ATTACK_SECONDS: f32 = 0.01;
+const RELEASE_SECONDS: f32 = 0.3;
+
+/// The zoom pulse per unit of bass level, and the largest pulse, as fractions of the view's size.
+const ZOOM_PULSE_GAIN: f32 = 2.0;
+const MAX_ZOOM_PULSE: f32 = 0.5;
+/// The turns per second by which the palette rotates per unit of mid level.
+const PALETTE_TURNS_PER_SECOND: f32 = 2.0;
+/// The largest offset of the palette at full treble level, which makes it shimmer on hi-hats.
+const TREBLE_SHIMMER: f32 = 0.05;
+
+/// The root mean square levels of the bass, mid and treble bands of the audio input.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub(super) struct BandLevels {
+    pub(super) bass: f32,
+    pub(super) mid: f32,
+    pub(super) treble: f32,
+}
+
+/// Computes the levels of the bands of blocks of audio with a windowed FFT.
+pub(super) struct SpectrumAnalyzer {
+    fft: Arc<dyn RealToComplex<f32>>,
+    window: Vec<f32>,
+    input: Vec<f32>,
+    spectrum: Vec<Complex<f32>>,
+}
+
+impl SpectrumAnalyzer {
+    pub(super) fn new() -> Self {
+        let fft = RealFftPlanner::new().plan_fft_forward(FFT_SIZE);
+        let window = (0..FFT_SIZE)
+            .map(|i| 0.5 - 0.5 * (2.0 * PI * i as f32 / FFT_SIZE as f32).cos())
+            .collect();
+        Self {
+            input: fft.make_input_vec(),
+            spectrum: fft.make_output_vec(),
+            fft,
+            window,
+        }
+    }
+
+    /// Returns the levels of the most recent [`FFT_SIZE`] samples, of which any missing at the
+    /// start are taken to be silent.
+    pub(super) fn analyze(&mut self, samples: &[f32], sample_rate: u32) -> BandLevels {
+        let samples = &samples[samples.len().saturating_sub(FFT_SIZE)..];
+        let padding = FFT_SIZE - samples.len();
+        self.input[..padding].fill(0.0);
+        for ((input, sample), weight) in self.input[padding..]
+            .iter_mut()
+            .zip(samples)
+            .zip(&self.window[padding..])
+        {
+            *input = sample * weight;
+        }
+        self.fft
+            .process(&mut self.input, &mut self.spectrum)
+            .expect("Buffers are created by the FFT itself");
+
+        // By Parseval's theorem, this gives the RMS of the signal within the band, independent of
+        // the window.
+        let window_energy: f32 = self.window.iter().map(|weight| weight * weight).sum();
+        let bin_width = sample_rate as f32 / FFT_SIZE as f32;
+        let level = |(low, high): (f32, f32)| {
+            let first = (low / bin_width).ceil() as usize;
+            let last = ((high / bin_width).floor() as usize).min(self.spectrum.len() - 1);
+            let energy: f32 = self
+                .spectrum
+                .get(first..=last)
+                .unwrap_or_default()
+                .iter()
+                .map(Complex::norm_sqr)
+                .sum();
+            (2.0 * energy / (FFT_SIZE as f32 * window_energy)).sqrt()
+        };
+        BandLevels {
+            bass: level(BASS),
+            mid: level(MID),
+            treble: level(TREBLE),
+        }
+    }
+}
+
+/// How the audio currently modulates the view.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub(super) struct Modulation {
+    pub(super) palette_offset: f32,
+    /// How far the view is zoomed in beyond its actual transform, as a fraction of its size.
+    pub(super) zoom_pulse: f32,
+}
+
+/// Turns the levels of the audio input into a [`Modulation`]: the bass pulses the zoom, the mid
+/// range rotates the palette and the treble makes it shimmer.
+#[derive(Default)]
+pub(super) struct AudioReactive {
+    envelope: BandLevels,
+    palette_phase: f32,
+}
+
+impl AudioReactive {
+    /// Advances the modulation by the time since the last update with the current levels.
+    pub(super) fn update(&mut self, levels: BandLevels, elapsed: Duration) -> Modulation {
+        let seconds = elapsed.as_secs_f32();
+        let follow = |envelope: &mut f32, level: f32| {
+            let time_constant = if level > *envelope {
+                ATTACK_SECONDS
+            } else {
+                RELEASE_SECONDS
+            };
+            *envelope += (level - *envelope) * (1.0 - (-seconds / time_constant).exp());
+        };
+        follow(&mut self.envelope.bass, levels.bass);
+        follow(&mut self.envelope.mid, levels.mid);
+        follow(&mut self.envelope.treble, levels.treble);
+
+        self.palette_phase =
+            (self.palette_phase + self.envelope.mid * PALETTE_TURNS_PER_SECOND * seconds).fract();
+        Modulation {
+            palette_offset: self.palette_phase
+                + (self.envelope.treble * TREBLE_SHIMMER).min(TREBLE_SHIMMER),
+            zoom_pulse: (self.envelope.bass * ZOOM_PULSE_GAIN).min(MAX_ZOOM_PULSE),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AudioReactive, BandLevels, SpectrumAnalyzer, FFT_SIZE};
+    use googletest::prelude::*;
+    use std::{f32::consts::PI, time::Duration};
+
+    const SAMPLE_RATE: u32 = 48000;
+
+    fn sine(frequency: f32, amplitude: f32) -> Vec<f32> {
+        (0..FFT_SIZE)
+            .map(|i| amplitude * (2.0 * PI * frequency * i as f32 / SAMPLE_RATE as f32).sin())
+            .collect()
+    }
+
+    #[test]
+    fn tone_is_measured_in_its_band() -> Result<()> {
+        let mut analyzer = SpectrumAnalyzer::new();
+
+        let levels = analyzer.analyze(&sine(100.0, 0.5), SAMPLE_RATE);
+
+        verify_that!(
+            (levels.bass, levels.mid, levels.treble),
+            (near(0.5 / 2.0f32.sqrt(), 0.01), lt(0.01), lt(0.01))
+        )
+    }
+
+    #[test]
+    fn short_input_is_padded_with_silence() -> Result<()> {
+        let mut analyzer = SpectrumAnalyzer::new();
+
+        let levels = analyzer.analyze(&[], SAMPLE_RATE);
+
+        verify_that!(levels, eq(BandLevels::default()))
+    }
+
+    #[test]
+    fn zoom_pulses_on_bass_and_decays_slowly() -> Result<()> {
+        let mut reactive = AudioReactive::default();
+        let beat = BandLevels {
+            bass: 0.2,
+            ..BandLevels::default()
+        };
+
+        let during_beat = reactive.update(beat, Duration::from_millis(50));
+        let after_beat = reactive.update(BandLevels::default(), Duration::from_millis(50));
+
+        verify_that!(
+            (during_beat.zoom_pulse, after_beat.zoom_pulse),
+            (gt(0.39), gt(0.3))
+        )
+    }
+}
@@ -0,0 +1,158 @@
+use crate::{controls::FractalType, error::Error, export, fractal_view::View, gpu::Gpu};
+use cgmath::Vector2;
+use iced_wgpu::wgpu;
+use serde::Serialize;
+use std::{path::PathBuf, time::Instant};
+
+const DEFAULT_FRAMES: u32 = 10;
+const WARM_UP_FRAMES: u32 = 2;
+const RESOLUTIONS: [(u32, u32); 3] = [(640, 480), (1920, 1080), (3840, 2160)];
+
+/// A fixed framing of the complex plane which is rendered for every fractal type and resolution.
+struct Scene {
+    name: &'static str,
+    center: Vector2<f32>,
+    half_width: f32,
+}
+
+const SCENES: [Scene; 2] = [
+    Scene {
+        name: "shallow",
+        center: Vector2::new(-0.5, 0.0),
+        half_width: 2.0,
+    },
+    Scene {
+        name: "deep",
+        center: Vector2::new(-0.743_643_9, 0.131_825_9),
+        half_width: 1e-4,
+    },
+];
+
+pub(super) struct BenchOptions {
+    frames: u32,
+    json_path: Option<PathBuf>,
+}
+
+impl BenchOptions {
+    pub(super) fn parse(args: &[String]) -> Result<Self, String> {
+        let mut options = Self {
+            frames: DEFAULT_FRAMES,
+            json_path: None,
+        };
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--frames" => {
+                    options.frames = args
+                        .next()
+                        .and_then(|frames| frames.parse().ok())
+                        .filter(|frames| *frames > 0)
+                        .ok_or("--frames requires a positive number")?;
+                }
+                "--json" => {
+                    options.json_path = Some(args.next().ok_or("--json requires a path")?.into());
+                }
+                _ => return Err(format!("Unknown argument {arg}")),
+            }
+        }
+        Ok(options)
+    }
+}
+
+#[derive(Serialize)]
+struct Report {
+    version: &'static str,
+    adapter: String,
+    backend: String,
+    frames: u32,
+    results: Vec<SceneResult>,
+}
+
+#[derive(Serialize)]
+struct SceneResult {
+    scene: &'static str,
+    fractal_type: String,
+    width: u32,
+    height: u32,
+    ms_per_frame: f64,
+    pixels_per_second: f64,
+}
+
+/// Renders every scene offscreen, prints the timings and optionally writes them as JSON so that
+/// performance can be compared across versions.
+pub(super) fn run(options: BenchOptions) -> Result<(), Error> {
+    let gpu = Gpu::new_without_surface()?;
+    let mut view = View::new(&gpu);
+    view.wait_for_pipelines();
+    let mut results = vec![];
+
+    println!(
+        "Benchmarking on {} ({:?}), {} frames per scene",
+        gpu.adapter_info.name, gpu.adapter_info.backend, options.frames
+    );
+    for fractal_type in FractalType::ALL {
+        view.set_fractal_type(fractal_type);
+        for scene in &SCENES {
+            view.look_at(scene.center, scene.half_width);
+            for (width, height) in RESOLUTIONS {
+                let seconds_per_frame = time_frames(&gpu, &view, width, height, options.frames);
+                let result = SceneResult {
+                    scene: scene.name,
+                    fractal_type: fractal_type.to_string(),
+                    width,
+                    height,
+                    ms_per_frame: seconds_per_frame * 1000.0,
+                    pixels_per_second: (width * height) as f64 / seconds_per_frame,
+                };
+                println!(
+                    "{:<10} {:<8} {:>4}x{:<4} {:>9.2} ms/frame {:>9.1} Mpixels/s",
+                    result.fractal_type,
+                    result.scene,
+                    width,
+                    height,
+                    result.ms_per_frame,
+                    result.pixels_per_second / 1e6
+                );
+                results.push(result);
+            }
+        }
+    }
+
+    if let Some(json_path) = options.json_path {
+        let report = Report {
+            version: env!("CARGO_PKG_VERSION"),
+            adapter: gpu.adapter_info.name.clone(),
+            backend: format!("{:?}", gpu.adapter_info.backend),
+            frames: options.frames,
+            results,
+        };
+        let json = serde_json::to_string_pretty(&report).map_err(std::io::Error::from)?;
+        std::fs::write(json_path, json)?;
+    }
+    Ok(())
+}
+
+/// Returns the mean wall-clock time in seconds to render and complete a frame.
+fn time_frames(gpu: &Gpu, view: &View, width: u32, height: u32, frames: u32) -> f64 {
+    let target = export::create_render_target(gpu, width, height);
+    view.update_transform(&gpu.queue);
+    let render_frame = || {
+        let mut encoder = gpu
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+        // Otherwise only the first frame would iterate, while the others just recolour.
+        view.discard_iteration_data();
+        view.render(&target, &mut encoder);
+        gpu.queue.submit(Some(encoder.finish()));
+        gpu.device.poll(wgpu::Maintain::Wait);
+    };
+
+    for _ in 0..WARM_UP_FRAMES {
+        render_frame();
+    }
+    let start = Instant::now();
+    for _ in 0..frames {
+        render_frame();
+    }
+    start.elapsed().as_secs_f64() / frames as f64
+}
@@ -0,0 +1,224 @@
+use crate::{gpu::Gpu, gpu_memory};
+use iced_wgpu::wgpu;
+use std::{cell::RefCell, sync::Arc};
+
+/// Copies a texture onto a render target of the view's texture format, scaling it with bilinear
+/// filtering if the sizes differ.
+pub(super) struct Blit {
+    device: Arc<wgpu::Device>,
+    bind_group_layout: wgpu::BindGroupLayout,
+    sampler: wgpu::Sampler,
+    pipeline: wgpu::RenderPipeline,
+}
+
+impl Blit {
+    pub(super) fn new(gpu: &Gpu) -> Self {
+        Self::with_entry_point(gpu, "blit")
+    }
+
+    /// Copies a texture whose colours are encoded in sRGB, though its format does not say so,
+    /// decoding them for a target which stores linear light.
+    pub(super) fn from_srgb(gpu: &Gpu) -> Self {
+        Self::with_entry_point(gpu, "blit_from_srgb")
+    }
+
+    fn with_entry_point(gpu: &Gpu, entry_point: &str) -> Self {
+        let bind_group_layout =
+            gpu.device
+                .create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                    label: Some("Blit bind group layout"),
+                    entries: &[
+                        wgpu::BindGroupLayoutEntry {
+                            binding: 0,
+                            visibility: wgpu::ShaderStages::FRAGMENT,
+                            ty: wgpu::BindingType::Texture {
+                                sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                                view_dimension: wgpu::TextureViewDimension::D2,
+                                multisampled: false,
+                            },
+                            count: None,
+                        },
+                        wgpu::BindGroupLayoutEntry {
+                            binding: 1,
+                            visibility: wgpu::ShaderStages::FRAGMENT,
+                            ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                            count: None,
+                        },
+                    ],
+                });
+        let sampler = gpu.device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("Blit sampler"),
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+        let module = gpu
+            .device
+            .create_shader_module(wgpu::include_wgsl!("shader/blit.wgsl"));
+        let layout = gpu
+            .device
+            .create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some("Blit pipeline layout"),
+                bind_group_layouts: &[&bind_group_layout],
+                push_constant_ranges: &[],
+            });
+        let pipeline = gpu
+            .device
+            .create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                label: Some("Blit pipeline"),
+                layout: Some(&layout),
+                vertex: wgpu::VertexState {
+                    module: &module,
+                    entry_point: "fullscreen",
+                    buffers: &[],
+                },
+                fragment: Some(wgpu::FragmentState {
+                    module: &module,
+                    entry_point,
+                    targets: &[Some(gpu.texture_format.into())],
+                }),
+                primitive: wgpu::PrimitiveState::default(),
+                depth_stencil: None,
+                multisample: wgpu::MultisampleState::default(),
+                multiview: None,
+            });
+        Self {
+            device: gpu.device.clone(),
+            bind_group_layout,
+            sampler,
+            pipeline,
+        }
+    }
+
+    /// Creates the bind group through which [`Self::draw`] reads the source texture.
+    pub(super) fn bind(&self, source: &wgpu::TextureView) -> wgpu::BindGroup {
+        self.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Blit bind group"),
+            layout: &self.bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(source),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(&self.sampler),
+                },
+            ],
+        })
+    }
+
+    pub(super) fn draw(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        source: &wgpu::BindGroup,
+        target: &wgpu::TextureView,
+    ) {
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Blit pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: target,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+        self.draw_in(&mut render_pass, source);
+    }
+
+    /// Draws the source texture over the viewport of a render pass whose target has the view's
+    /// texture format.
+    pub(super) fn draw_in<'a>(
+        &'a self,
+        render_pass: &mut wgpu::RenderPass<'a>,
+        source: &'a wgpu::BindGroup,
+    ) {
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_bind_group(0, source, &[]);
+        render_pass.draw(0..3, 0..1);
+    }
+}
+
+/// Renders into a texture with a fraction of the target's resolution, which is then scaled up onto
+/// the target, to save power.
+pub(super) struct ReducedResolution {
+    divisor: u32,
+    blit: Blit,
+    target: RefCell<Option<ReducedTarget>>,
+}
+
+struct ReducedTarget {
+    size: wgpu::Extent3d,
+    texture: wgpu::Texture,
+    bind_group: wgpu::BindGroup,
+}
+
+impl ReducedResolution {
+    /// Renders with `1 / divisor` of the target's resolution along each axis.
+    pub(super) fn new(gpu: &Gpu, divisor: u32) -> Self {
+        Self {
+            divisor,
+            blit: Blit::new(gpu),
+            target: RefCell::new(None),
+        }
+    }
+
+    /// The size in bytes of the texture of reduced resolution, if it has been created.
+    pub(super) fn gpu_memory(&self) -> u64 {
+        self.target.borrow().as_ref().map_or(0, |reduced| {
+            gpu_memory::texture_bytes(reduced.size, reduced.texture.format())
+        })
+    }
+
+    /// Lets `draw` render into a texture of the reduced resolution and scales the result onto the
+    /// target.
+    pub(super) fn render(
+        &self,
+        target: &wgpu::Texture,
+        encoder: &mut wgpu::CommandEncoder,
+        draw: impl FnOnce(&wgpu::Texture, &mut wgpu::CommandEncoder),
+    ) {
+        let size = wgpu::Extent3d {
+            width: target.width().div_ceil(self.divisor),
+            height: target.height().div_ceil(self.divisor),
+            depth_or_array_layers: 1,
+        };
+        let mut reduced = self.target.borrow_mut();
+        let reduced = match &mut *reduced {
+            Some(reduced) if reduced.size == size => reduced,
+            reduced => reduced.insert(self.create_target(size, target.format())),
+        };
+        draw(&reduced.texture, encoder);
+        self.blit.draw(
+            encoder,
+            &reduced.bind_group,
+            &target.create_view(&wgpu::TextureViewDescriptor::default()),
+        );
+    }
+
+    fn create_target(&self, size: wgpu::Extent3d, format: wgpu::TextureFormat) -> ReducedTarget {
+        let texture = self.blit.device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Reduced resolution render"),
+            size,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+            view_formats: &[],
+        });
+        let bind_group = self
+            .blit
+            .bind(&texture.create_view(&wgpu::TextureViewDescriptor::default()));
+        ReducedTarget {
+            size,
+            texture,
+            bind_group,
+        }
+    }
+}
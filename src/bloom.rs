@@ -0,0 +1,244 @@
+use crate::{gpu::Gpu, gpu_memory};
+use iced_wgpu::wgpu;
+use std::{cell::RefCell, sync::Arc};
+
+/// The factor by which the resolution of the glow is reduced along each axis, which widens the
+/// blur at no extra cost.
+const GLOW_RESOLUTION_DIVISOR: u32 = 2;
+
+/// Makes the bright parts of the rendered image glow, which flatters images made of thin bright
+/// structures on dark backgrounds, such as those which accumulate orbits.
+///
+/// The image is first rendered into an intermediate texture. A bright pass then keeps what exceeds
+/// a threshold at a reduced resolution, which two passes blur horizontally and vertically before
+/// the composite adds it to the image on the target.
+pub(super) struct Bloom {
+    device: Arc<wgpu::Device>,
+    bind_group_layout: wgpu::BindGroupLayout,
+    sampler: wgpu::Sampler,
+    bright_pass: wgpu::RenderPipeline,
+    blur_horizontal: wgpu::RenderPipeline,
+    blur_vertical: wgpu::RenderPipeline,
+    composite: wgpu::RenderPipeline,
+    targets: RefCell<Option<Targets>>,
+}
+
+/// The intermediate textures for one size of render target.
+struct Targets {
+    size: wgpu::Extent3d,
+    scene: wgpu::Texture,
+    scene_bind_group: wgpu::BindGroup,
+    /// The blur alternates between these.
+    glow: [wgpu::TextureView; 2],
+    glow_bind_groups: [wgpu::BindGroup; 2],
+}
+
+impl Bloom {
+    pub(super) fn new(gpu: &Gpu) -> Self {
+        let bind_group_layout =
+            gpu.device
+                .create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                    label: Some("Bloom bind group layout"),
+                    entries: &[
+                        wgpu::BindGroupLayoutEntry {
+                            binding: 0,
+                            visibility: wgpu::ShaderStages::FRAGMENT,
+                            ty: wgpu::BindingType::Texture {
+                                sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                                view_dimension: wgpu::TextureViewDimension::D2,
+                                multisampled: false,
+                            },
+                            count: None,
+                        },
+                        wgpu::BindGroupLayoutEntry {
+                            binding: 1,
+                            visibility: wgpu::ShaderStages::FRAGMENT,
+                            ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                            count: None,
+                        },
+                    ],
+                });
+        let sampler = gpu.device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("Bloom sampler"),
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+        let module = gpu
+            .device
+            .create_shader_module(wgpu::include_wgsl!("shader/bloom.wgsl"));
+        let create_pipeline = |entry_point, inputs| {
+            let layout = gpu
+                .device
+                .create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                    label: Some("Bloom pipeline layout"),
+                    bind_group_layouts: &vec![&bind_group_layout; inputs],
+                    push_constant_ranges: &[],
+                });
+            gpu.device
+                .create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                    label: Some(entry_point),
+                    layout: Some(&layout),
+                    vertex: wgpu::VertexState {
+                        module: &module,
+                        entry_point: "fullscreen",
+                        buffers: &[],
+                    },
+                    fragment: Some(wgpu::FragmentState {
+                        module: &module,
+                        entry_point,
+                        targets: &[Some(gpu.texture_format.into())],
+                    }),
+                    primitive: wgpu::PrimitiveState::default(),
+                    depth_stencil: None,
+                    multisample: wgpu::MultisampleState::default(),
+                    multiview: None,
+                })
+        };
+        Self {
+            device: gpu.device.clone(),
+            bright_pass: create_pipeline("bright_pass", 1),
+            blur_horizontal: create_pipeline("blur_horizontal", 1),
+            blur_vertical: create_pipeline("blur_vertical", 1),
+            composite: create_pipeline("composite", 2),
+            bind_group_layout,
+            sampler,
+            targets: RefCell::new(None),
+        }
+    }
+
+    /// The size in bytes of the intermediate textures, if they have been created.
+    pub(super) fn gpu_memory(&self) -> u64 {
+        self.targets.borrow().as_ref().map_or(0, |targets| {
+            let format = targets.scene.format();
+            gpu_memory::texture_bytes(targets.size, format)
+                + 2 * gpu_memory::texture_bytes(glow_size(targets.size), format)
+        })
+    }
+
+    /// Lets `draw` render into an intermediate texture and composites the result with its glow
+    /// onto the target.
+    pub(super) fn render(
+        &self,
+        target: &wgpu::Texture,
+        encoder: &mut wgpu::CommandEncoder,
+        draw: impl FnOnce(&wgpu::Texture, &mut wgpu::CommandEncoder),
+    ) {
+        let size = target.size();
+        let mut targets = self.targets.borrow_mut();
+        let targets = match &mut *targets {
+            Some(targets) if targets.size == size => targets,
+            targets => targets.insert(self.create_targets(size, target.format())),
+        };
+        draw(&targets.scene, encoder);
+        pass(
+            encoder,
+            &self.bright_pass,
+            &[&targets.scene_bind_group],
+            &targets.glow[0],
+        );
+        pass(
+            encoder,
+            &self.blur_horizontal,
+            &[&targets.glow_bind_groups[0]],
+            &targets.glow[1],
+        );
+        pass(
+            encoder,
+            &self.blur_vertical,
+            &[&targets.glow_bind_groups[1]],
+            &targets.glow[0],
+        );
+        pass(
+            encoder,
+            &self.composite,
+            &[&targets.scene_bind_group, &targets.glow_bind_groups[0]],
+            &target.create_view(&wgpu::TextureViewDescriptor::default()),
+        );
+    }
+
+    fn create_targets(&self, size: wgpu::Extent3d, format: wgpu::TextureFormat) -> Targets {
+        let create_texture = |label, size| {
+            self.device.create_texture(&wgpu::TextureDescriptor {
+                label: Some(label),
+                size,
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format,
+                usage: wgpu::TextureUsages::RENDER_ATTACHMENT
+                    | wgpu::TextureUsages::TEXTURE_BINDING,
+                view_formats: &[],
+            })
+        };
+        let create_bind_group = |view: &wgpu::TextureView| {
+            self.device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("Bloom bind group"),
+                layout: &self.bind_group_layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: wgpu::BindingResource::TextureView(view),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: wgpu::BindingResource::Sampler(&self.sampler),
+                    },
+                ],
+            })
+        };
+        let scene = create_texture("Bloom scene", size);
+        let scene_bind_group =
+            create_bind_group(&scene.create_view(&wgpu::TextureViewDescriptor::default()));
+        let glow = [(); 2].map(|_| {
+            create_texture("Bloom glow", glow_size(size))
+                .create_view(&wgpu::TextureViewDescriptor::default())
+        });
+        let glow_bind_groups = [create_bind_group(&glow[0]), create_bind_group(&glow[1])];
+        Targets {
+            size,
+            scene,
+            scene_bind_group,
+            glow,
+            glow_bind_groups,
+        }
+    }
+}
+
+/// Draws a fullscreen triangle with the given pipeline and inputs onto the target.
+fn pass(
+    encoder: &mut wgpu::CommandEncoder,
+    pipeline: &wgpu::RenderPipeline,
+    inputs: &[&wgpu::BindGroup],
+    target: &wgpu::TextureView,
+) {
+    let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+        label: Some("Bloom pass"),
+        color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+            view: target,
+            resolve_target: None,
+            ops: wgpu::Operations {
+                load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                store: wgpu::StoreOp::Store,
+            },
+        })],
+        depth_stencil_attachment: None,
+        timestamp_writes: None,
+        occlusion_query_set: None,
+    });
+    render_pass.set_pipeline(pipeline);
+    for (index, input) in inputs.iter().enumerate() {
+        render_pass.set_bind_group(index as u32, input, &[]);
+    }
+    render_pass.draw(0..3, 0..1);
+}
+
+fn glow_size(size: wgpu::Extent3d) -> wgpu::Extent3d {
+    wgpu::Extent3d {
+        width: size.width.div_ceil(GLOW_RESOLUTION_DIVISOR),
+        height: size.height.div_ceil(GLOW_RESOLUTION_DIVISOR),
+        depth_or_array_layers: 1,
+    }
+}
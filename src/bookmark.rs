@@ -0,0 +1,260 @@
+use crate::{
+    colour_palette::Palette,
+    controls::FractalType,
+    plane::Plane,
+    transform::{self, Similarity},
+};
+use cgmath::{Matrix3, Vector2, VectorSpace};
+use serde::{Deserialize, Serialize};
+use std::f32::consts::{PI, TAU};
+
+/// Relative differences in scale below which a morph pans in a straight line instead of zooming
+/// towards a fixed point.
+const MIN_RELATIVE_SCALE_CHANGE: f32 = 1e-3;
+
+/// Everything which determines what the view shows, so that it can be restored or morphed into.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub(super) struct Bookmark {
+    pub(super) fractal_type: FractalType,
+    pub(super) view_transform: Matrix3<f32>,
+    pub(super) parameter_values: Vec<f32>,
+    pub(super) max_iterations: u32,
+    pub(super) palette: Palette,
+    pub(super) palette_offset: f32,
+    /// Seeds everything random in the view. Missing from bookmarks stored before it could be set.
+    #[serde(default)]
+    pub(super) seed: u32,
+    /// Which of the critical points of a rational map its orbits start from, as listed by
+    /// [`critical_points::of_rational_map`](crate::critical_points::of_rational_map). Missing
+    /// from bookmarks stored before it could be chosen.
+    #[serde(default)]
+    pub(super) critical_point: usize,
+    /// Missing from bookmarks stored before the dynamic plane could be shown.
+    #[serde(default)]
+    pub(super) plane: Plane,
+    /// The centre of the view with the digits beyond the precision of `view_transform`, so that
+    /// deep locations come back where they were. Missing from bookmarks stored before it was kept.
+    #[serde(default)]
+    pub(super) centre: Option<Vector2<f64>>,
+    /// The power of two by which the scale of `view_transform` is multiplied, as in
+    /// [`ViewTransform`](crate::transform::ViewTransform). Missing from bookmarks stored before
+    /// views could be smaller than `f32` holds.
+    #[serde(default)]
+    pub(super) scale_exponent: i32,
+}
+
+/// Which of the two bookmarks of a morph is meant.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(super) enum MorphEnd {
+    Start,
+    End,
+}
+
+impl Bookmark {
+    /// A bookmark of the Mandelbrot set framed on the given centre, from which tests set the
+    /// fields they need with struct-update syntax.
+    #[cfg(test)]
+    pub(super) fn for_test(centre: Vector2<f32>, half_width: f32) -> Bookmark {
+        Bookmark {
+            fractal_type: FractalType::Mandelbrot,
+            view_transform: Matrix3::from_translation(centre) * Matrix3::from_scale(half_width),
+            parameter_values: vec![],
+            max_iterations: 100,
+            palette: Palette::Greyscale,
+            palette_offset: 0.0,
+            seed: 0,
+            critical_point: 0,
+            plane: Plane::Parameter,
+            centre: None,
+            scale_exponent: 0,
+        }
+    }
+
+    /// The centre of the view, as precisely as the bookmark stores it.
+    pub(super) fn centre(&self) -> Vector2<f64> {
+        self.centre
+            .unwrap_or_else(|| self.view_transform.z.truncate().cast().unwrap())
+    }
+
+    /// The distance from the centre of the view to its edges, including the exponent.
+    pub(super) fn scale(&self) -> f64 {
+        f64::from(Similarity::from(self.view_transform).scale) * 2f64.powi(self.scale_exponent)
+    }
+
+    /// Returns the state part way from `self` to `other`, with `t` running from 0 to 1.
+    ///
+    /// The scale changes geometrically, so that every doubling of the zoom takes equally long, and
+    /// the centre moves in proportion to the scale, so that the view zooms towards the point at
+    /// which the scales would meet instead of losing sight of a deep target. The fractal and
+    /// palette of the nearer bookmark are taken, and parameters are only interpolated between
+    /// bookmarks of the same fractal.
+    pub(super) fn interpolate(&self, other: &Bookmark, t: f32) -> Bookmark {
+        let nearer = if t < 0.5 { self } else { other };
+        let parameter_values = if self.fractal_type == other.fractal_type {
+            self.parameter_values
+                .iter()
+                .zip(&other.parameter_values)
+                .map(|(from, to)| lerp(*from, *to, t))
+                .collect()
+        } else {
+            nearer.parameter_values.clone()
+        };
+        let max_iterations = lerp(
+            (self.max_iterations as f32).ln(),
+            (other.max_iterations as f32).ln(),
+            t,
+        )
+        .exp()
+        .round() as u32;
+        // The constant of the dynamic plane moves between bookmarks which both show it.
+        let plane = match (self.plane, other.plane) {
+            (Plane::Dynamic(from), Plane::Dynamic(to)) => Plane::Dynamic(from.lerp(to, t)),
+            _ => nearer.plane,
+        };
+        let (view_transform, scale_exponent, centre) = interpolate_view(self, other, t);
+        Bookmark {
+            fractal_type: nearer.fractal_type,
+            view_transform,
+            parameter_values,
+            max_iterations,
+            palette: nearer.palette,
+            palette_offset: lerp(self.palette_offset, other.palette_offset, t),
+            seed: nearer.seed,
+            critical_point: nearer.critical_point,
+            plane,
+            centre: Some(centre),
+            scale_exponent,
+        }
+    }
+}
+
+/// The matrix of the view part way between those of two bookmarks with the exponent of its scale,
+/// and its centre in double precision, which the matrix rounds.
+///
+/// The scale and the progress of the centre are computed in double precision, since the centre of
+/// a morph into a deep location would otherwise drift from its target by far more than the width
+/// of the view.
+fn interpolate_view(from: &Bookmark, to: &Bookmark, t: f32) -> (Matrix3<f32>, i32, Vector2<f64>) {
+    let (from_similarity, to_similarity) = (
+        Similarity::from(from.view_transform),
+        Similarity::from(to.view_transform),
+    );
+    let (from_scale, to_scale) = (from.scale(), to.scale());
+    let t_f64 = f64::from(t);
+    let scale = (from_scale.ln() + (to_scale.ln() - from_scale.ln()) * t_f64).exp();
+    let progress =
+        if (from_scale - to_scale).abs() > f64::from(MIN_RELATIVE_SCALE_CHANGE) * from_scale {
+            (from_scale - scale) / (from_scale - to_scale)
+        } else {
+            t_f64
+        };
+    let turn = (to_similarity.angle - from_similarity.angle + PI).rem_euclid(TAU) - PI;
+    let centre = from.centre() + (to.centre() - from.centre()) * progress;
+    let (scale, scale_exponent) = transform::split_scale(scale);
+    let matrix = Similarity {
+        centre: centre.cast().unwrap(),
+        scale,
+        angle: from_similarity.angle + turn * t,
+    }
+    .into();
+    (matrix, scale_exponent, centre)
+}
+
+fn lerp(from: f32, to: f32, t: f32) -> f32 {
+    from + (to - from) * t
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Bookmark;
+    use crate::colour_palette::Palette;
+    use cgmath::{InnerSpace, Vector2};
+    use googletest::prelude::*;
+
+    #[test]
+    fn ends_of_morph_match_bookmarks() -> Result<()> {
+        let start = Bookmark::for_test(Vector2::new(-0.5, 0.0), 2.0);
+        let end = Bookmark {
+            max_iterations: 1600,
+            palette: Palette::Viridis,
+            ..Bookmark::for_test(Vector2::new(-0.75, 0.1), 0.01)
+        };
+
+        let at_start = start.interpolate(&end, 0.0);
+        let at_end = start.interpolate(&end, 1.0);
+
+        verify_that!(
+            (
+                at_start.view_transform.z.x,
+                at_end.view_transform.z.x,
+                at_end.view_transform.x.x,
+                at_end.max_iterations,
+                at_end.palette
+            ),
+            (
+                approx_eq(-0.5),
+                approx_eq(-0.75),
+                approx_eq(0.01),
+                eq(1600),
+                eq(Palette::Viridis)
+            )
+        )
+    }
+
+    #[test]
+    fn zoom_and_iterations_change_geometrically() -> Result<()> {
+        let start = Bookmark::for_test(Vector2::new(0.0, 0.0), 1.0);
+        let end = Bookmark {
+            max_iterations: 400,
+            ..Bookmark::for_test(Vector2::new(0.0, 0.0), 0.01)
+        };
+
+        let halfway = start.interpolate(&end, 0.5);
+
+        verify_that!(
+            (halfway.view_transform.x.x, halfway.max_iterations),
+            (near(0.1, 1e-6), eq(200))
+        )
+    }
+
+    #[test]
+    fn morph_ends_on_precise_centre_of_deep_bookmark() -> Result<()> {
+        let target = Vector2::new(-0.75 + 1e-12, 0.1 - 3e-13);
+        let start = Bookmark::for_test(Vector2::new(-0.5, 0.0), 2.0);
+        let end = Bookmark {
+            centre: Some(target),
+            ..Bookmark::for_test(target.cast().unwrap(), 1e-13)
+        };
+
+        let at_end = start.interpolate(&end, 1.0).centre();
+
+        verify_that!(at_end.x, near(target.x, 1e-16))?;
+        verify_that!(at_end.y, near(target.y, 1e-16))
+    }
+
+    #[test]
+    fn bookmark_stored_without_precise_centre_is_centred_on_its_matrix() -> Result<()> {
+        let bookmark = Bookmark::for_test(Vector2::new(-0.5, 0.25), 2.0);
+        let mut json = serde_json::to_value(&bookmark)?;
+        json.as_object_mut().unwrap().remove("centre");
+
+        let restored: Bookmark = serde_json::from_value(json)?;
+
+        verify_that!(restored.centre(), eq(Vector2::new(-0.5, 0.25)))
+    }
+
+    #[test]
+    fn deep_target_stays_in_view_throughout_morph() -> Result<()> {
+        let target = Vector2::new(-0.7436, 0.1318);
+        let start = Bookmark::for_test(Vector2::new(-0.5, 0.0), 2.0);
+        let end = Bookmark::for_test(target, 1e-4);
+
+        for step in 0..=20 {
+            let transform = start.interpolate(&end, step as f32 / 20.0).view_transform;
+            let half_width = transform.x.magnitude();
+            let distance = (transform.z.truncate() - target).magnitude();
+            verify_that!((step, distance), (anything(), le(half_width)))?;
+        }
+        Ok(())
+    }
+}
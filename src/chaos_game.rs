@@ -0,0 +1,362 @@
+use crate::{gpu::Gpu, pipeline_compiler};
+use bytemuck::{Pod, Zeroable};
+use iced_wgpu::wgpu::{self, util::DeviceExt};
+use std::{cell::RefCell, ops::RangeInclusive, sync::Arc};
+
+pub(super) const VERTICES: RangeInclusive<u32> = 3..=8;
+pub(super) const JUMP_RATIOS: RangeInclusive<f32> = 0.1..=0.9;
+pub(super) const JUMP_RATIO_STEP: f32 = 0.01;
+
+/// How many hits the walkers land per pixel of the target on average, which is enough for the
+/// sparse parts of the attractor to show without speckles.
+const HITS_PER_PIXEL: u64 = 32;
+
+/// The number of jumps which each walker counts, unless the target is so large that more walkers
+/// than fit in one dispatch would be needed.
+const STEPS_PER_WALKER: u64 = 256;
+
+/// The workgroup size of `chaos_game_walk` in `chaos_game_walk.wgsl`.
+const WALKERS_PER_WORKGROUP: u64 = 64;
+
+/// The most workgroups which wgpu dispatches along one dimension by default.
+const MAX_WORKGROUPS: u64 = 65535;
+
+/// The workgroup size of `chaos_game_most_hits` along each axis.
+const MOST_HITS_WORKGROUP_SIZE: u32 = 8;
+
+/// The chaos game, which starts anywhere in a regular polygon and jumps part of the way to one of
+/// its vertices at random, again and again. The points on which it lands fill an attractor of the
+/// Sierpinski family, such as Sierpinski's triangle for three vertices and a jump ratio of one
+/// half.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(super) struct ChaosGame {
+    pub(super) vertices: u32,
+    /// The fraction of the way to the vertex which each jump covers.
+    pub(super) jump_ratio: f32,
+}
+
+impl Default for ChaosGame {
+    fn default() -> Self {
+        Self {
+            vertices: 3,
+            jump_ratio: 0.5,
+        }
+    }
+}
+
+/// The settings of the game for one size of target, matching `ChaosGame` in `chaos_game.wgsl`.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+struct ChaosGameBlock {
+    size: [u32; 2],
+    vertices: u32,
+    jump_ratio: f32,
+    steps: u32,
+    _padding: u32,
+}
+
+/// Plays the [`ChaosGame`] on the GPU instead of shading the fractal.
+///
+/// Thousands of walkers play the game at once, each counting its hits on the pixels of the target
+/// in a buffer with atomic additions. A second pass finds the most hits on any pixel, by which the
+/// colouring scales the logarithm of the hits onto the palette. The game is played afresh each
+/// frame, so that the sliders change it live.
+pub(super) struct ChaosGameRenderer {
+    device: Arc<wgpu::Device>,
+    game: ChaosGame,
+    walk_bind_group_layout: wgpu::BindGroupLayout,
+    colouring_bind_group_layout: wgpu::BindGroupLayout,
+    walk: wgpu::ComputePipeline,
+    most_hits: wgpu::ComputePipeline,
+    colouring: wgpu::RenderPipeline,
+    targets: RefCell<Option<Targets>>,
+}
+
+/// The buffers of the game for one size of target.
+struct Targets {
+    size: wgpu::Extent3d,
+    /// The number of workgroups of walkers.
+    walk_workgroups: u32,
+    /// The hits on each pixel, row by row.
+    hits: wgpu::Buffer,
+    most_hits: wgpu::Buffer,
+    walk_bind_group: wgpu::BindGroup,
+    colouring_bind_group: wgpu::BindGroup,
+}
+
+impl ChaosGameRenderer {
+    /// Builds the pipelines, which read the uniform block of the view through a bind group with
+    /// the given layout.
+    pub(super) fn new(gpu: &Gpu, bind_group_layout: &wgpu::BindGroupLayout) -> Self {
+        let create_bind_group_layout = |label, visibility, read_only| {
+            let storage = wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Storage { read_only },
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            };
+            gpu.device
+                .create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                    label: Some(label),
+                    entries: &[
+                        wgpu::BindGroupLayoutEntry {
+                            binding: 0,
+                            visibility,
+                            ty: wgpu::BindingType::Buffer {
+                                ty: wgpu::BufferBindingType::Uniform,
+                                has_dynamic_offset: false,
+                                min_binding_size: None,
+                            },
+                            count: None,
+                        },
+                        wgpu::BindGroupLayoutEntry {
+                            binding: 1,
+                            visibility,
+                            ty: storage,
+                            count: None,
+                        },
+                        wgpu::BindGroupLayoutEntry {
+                            binding: 2,
+                            visibility,
+                            ty: storage,
+                            count: None,
+                        },
+                    ],
+                })
+        };
+        let walk_bind_group_layout = create_bind_group_layout(
+            "Chaos game walk bind group layout",
+            wgpu::ShaderStages::COMPUTE,
+            false,
+        );
+        let colouring_bind_group_layout = create_bind_group_layout(
+            "Chaos game colouring bind group layout",
+            wgpu::ShaderStages::FRAGMENT,
+            true,
+        );
+        let create_pipeline_layout = |layout| {
+            gpu.device
+                .create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                    label: Some("Chaos game pipeline layout"),
+                    bind_group_layouts: &[bind_group_layout, layout],
+                    push_constant_ranges: &[],
+                })
+        };
+        let walk_module = pipeline_compiler::CHAOS_GAME_WALK.create_module(&gpu.device);
+        let walk_layout = create_pipeline_layout(&walk_bind_group_layout);
+        let create_compute_pipeline = |entry_point| {
+            gpu.device
+                .create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+                    label: Some(entry_point),
+                    layout: Some(&walk_layout),
+                    module: &walk_module,
+                    entry_point,
+                })
+        };
+        let colouring_module = pipeline_compiler::CHAOS_GAME_COLOURING.create_module(&gpu.device);
+        let colouring = gpu
+            .device
+            .create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                label: Some("chaos_game_colouring"),
+                layout: Some(&create_pipeline_layout(&colouring_bind_group_layout)),
+                vertex: wgpu::VertexState {
+                    module: &colouring_module,
+                    entry_point: "chaos_game_fullscreen",
+                    buffers: &[],
+                },
+                fragment: Some(wgpu::FragmentState {
+                    module: &colouring_module,
+                    entry_point: "chaos_game_colouring",
+                    targets: &[Some(gpu.texture_format.into())],
+                }),
+                primitive: wgpu::PrimitiveState::default(),
+                depth_stencil: None,
+                multisample: wgpu::MultisampleState::default(),
+                multiview: None,
+            });
+        Self {
+            device: gpu.device.clone(),
+            game: ChaosGame::default(),
+            walk: create_compute_pipeline("chaos_game_walk"),
+            most_hits: create_compute_pipeline("chaos_game_most_hits"),
+            colouring,
+            walk_bind_group_layout,
+            colouring_bind_group_layout,
+            targets: RefCell::new(None),
+        }
+    }
+
+    pub(super) fn set(&mut self, game: ChaosGame) {
+        if game != self.game {
+            self.game = game;
+            *self.targets.get_mut() = None;
+        }
+    }
+
+    /// The size in bytes of the hits, if they have been counted.
+    pub(super) fn gpu_memory(&self) -> u64 {
+        self.targets
+            .borrow()
+            .as_ref()
+            .map_or(0, |targets| targets.hits.size())
+    }
+
+    /// Plays the game in the view which the given bind group describes and colours the hits onto
+    /// the target.
+    pub(super) fn render(
+        &self,
+        target: &wgpu::Texture,
+        bind_group: &wgpu::BindGroup,
+        encoder: &mut wgpu::CommandEncoder,
+    ) {
+        let size = target.size();
+        let mut targets = self.targets.borrow_mut();
+        let targets = match &mut *targets {
+            Some(targets) if targets.size == size => targets,
+            targets => targets.insert(self.create_targets(size)),
+        };
+        encoder.clear_buffer(&targets.hits, 0, None);
+        encoder.clear_buffer(&targets.most_hits, 0, None);
+        {
+            let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                label: Some("Chaos game pass"),
+                timestamp_writes: None,
+            });
+            compute_pass.set_bind_group(0, bind_group, &[]);
+            compute_pass.set_bind_group(1, &targets.walk_bind_group, &[]);
+            compute_pass.set_pipeline(&self.walk);
+            compute_pass.dispatch_workgroups(targets.walk_workgroups, 1, 1);
+            compute_pass.set_pipeline(&self.most_hits);
+            compute_pass.dispatch_workgroups(
+                size.width.div_ceil(MOST_HITS_WORKGROUP_SIZE),
+                size.height.div_ceil(MOST_HITS_WORKGROUP_SIZE),
+                1,
+            );
+        }
+        let target = target.create_view(&wgpu::TextureViewDescriptor::default());
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Chaos game colouring pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: &target,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+        render_pass.set_pipeline(&self.colouring);
+        render_pass.set_bind_group(0, bind_group, &[]);
+        render_pass.set_bind_group(1, &targets.colouring_bind_group, &[]);
+        render_pass.draw(0..3, 0..1);
+    }
+
+    fn create_targets(&self, size: wgpu::Extent3d) -> Targets {
+        let (walk_workgroups, steps) = walk_dispatch(size);
+        let block = self
+            .device
+            .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("Chaos game uniform buffer"),
+                contents: bytemuck::bytes_of(&ChaosGameBlock {
+                    size: [size.width, size.height],
+                    vertices: self.game.vertices,
+                    jump_ratio: self.game.jump_ratio,
+                    steps,
+                    _padding: 0,
+                }),
+                usage: wgpu::BufferUsages::UNIFORM,
+            });
+        let create_buffer = |label, size| {
+            self.device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some(label),
+                size,
+                usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            })
+        };
+        let hits = create_buffer(
+            "Chaos game hits",
+            u64::from(size.width) * u64::from(size.height) * 4,
+        );
+        let most_hits = create_buffer("Chaos game most hits", 4);
+        let create_bind_group = |layout| {
+            self.device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("Chaos game bind group"),
+                layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: block.as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: hits.as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 2,
+                        resource: most_hits.as_entire_binding(),
+                    },
+                ],
+            })
+        };
+        Targets {
+            size,
+            walk_workgroups,
+            walk_bind_group: create_bind_group(&self.walk_bind_group_layout),
+            colouring_bind_group: create_bind_group(&self.colouring_bind_group_layout),
+            hits,
+            most_hits,
+        }
+    }
+}
+
+/// The number of workgroups of walkers and the jumps which each counts, so that they land about
+/// [`HITS_PER_PIXEL`] hits per pixel of a target of the given size. Targets too large for
+/// [`MAX_WORKGROUPS`] of walkers with [`STEPS_PER_WALKER`] each lengthen the walks instead.
+fn walk_dispatch(size: wgpu::Extent3d) -> (u32, u32) {
+    let hits = u64::from(size.width) * u64::from(size.height) * HITS_PER_PIXEL;
+    let workgroups = hits
+        .div_ceil(WALKERS_PER_WORKGROUP * STEPS_PER_WALKER)
+        .clamp(1, MAX_WORKGROUPS);
+    let steps = hits.div_ceil(workgroups * WALKERS_PER_WORKGROUP);
+    (workgroups as u32, steps as u32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{walk_dispatch, HITS_PER_PIXEL, MAX_WORKGROUPS, STEPS_PER_WALKER};
+    use googletest::prelude::*;
+    use iced_wgpu::wgpu;
+
+    fn hits((workgroups, steps): (u32, u32)) -> u64 {
+        u64::from(workgroups) * 64 * u64::from(steps)
+    }
+
+    #[test]
+    fn large_target_lengthens_walks_beyond_most_workgroups() -> Result<()> {
+        let [small, large] = [128, 16384].map(|side| wgpu::Extent3d {
+            width: side,
+            height: side,
+            depth_or_array_layers: 1,
+        });
+
+        let (small_dispatch, large_dispatch) = (walk_dispatch(small), walk_dispatch(large));
+
+        verify_that!(
+            (
+                small_dispatch,
+                large_dispatch.0,
+                large_dispatch.1,
+                hits(large_dispatch)
+            ),
+            (
+                eq((32, STEPS_PER_WALKER as u32)),
+                eq(MAX_WORKGROUPS as u32),
+                gt(STEPS_PER_WALKER as u32),
+                ge(16384 * 16384 * HITS_PER_PIXEL)
+            )
+        )
+    }
+}
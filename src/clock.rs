@@ -0,0 +1,75 @@
+use iced::time::Instant;
+
+/// The time of the uniform block, which only runs while it plays, so that an animated fractal
+/// holds still when paused and carries on from there.
+pub(super) struct Clock {
+    /// The seconds which played before the current run.
+    seconds: f32,
+    /// When the current run started, while the time plays.
+    playing_since: Option<Instant>,
+}
+
+impl Clock {
+    pub(super) fn new() -> Self {
+        Self {
+            seconds: 0.0,
+            playing_since: None,
+        }
+    }
+
+    pub(super) fn is_playing(&self) -> bool {
+        self.playing_since.is_some()
+    }
+
+    pub(super) fn play(&mut self, now: Instant) {
+        if self.playing_since.is_none() {
+            self.playing_since = Some(now);
+        }
+    }
+
+    pub(super) fn pause(&mut self, now: Instant) {
+        self.seconds = self.seconds(now);
+        self.playing_since = None;
+    }
+
+    /// The seconds for which the time has played by the given time.
+    pub(super) fn seconds(&self, now: Instant) -> f32 {
+        self.seconds
+            + self
+                .playing_since
+                .map_or(0.0, |since| (now - since).as_secs_f32())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Clock;
+    use googletest::prelude::*;
+    use iced::time::Instant;
+    use std::time::Duration;
+
+    #[test]
+    fn holds_while_paused_and_resumes_from_there() -> Result<()> {
+        let start = Instant::now();
+        let mut clock = Clock::new();
+
+        clock.play(start);
+        clock.pause(start + Duration::from_secs(2));
+        let paused = clock.seconds(start + Duration::from_secs(5));
+        clock.play(start + Duration::from_secs(5));
+        let resumed = clock.seconds(start + Duration::from_secs(6));
+
+        verify_that!((paused, resumed), (eq(2.0), eq(3.0)))
+    }
+
+    #[test]
+    fn playing_again_does_not_restart_run() -> Result<()> {
+        let start = Instant::now();
+        let mut clock = Clock::new();
+
+        clock.play(start);
+        clock.play(start + Duration::from_secs(1));
+
+        verify_that!(clock.seconds(start + Duration::from_secs(2)), eq(2.0))
+    }
+}
@@ -0,0 +1,444 @@
+use crate::{cosine_palette::CosinePalette, oklab::Oklab};
+use serde::{Deserialize, Serialize};
+use std::{fmt::Display, str::FromStr};
+
+/// The number of colours in the gradient of a palette. Must match the size of `gradient` in
+/// `frag.wgsl`.
+pub(super) const GRADIENT_STOPS: usize = 8;
+
+/// The colours with which the fractals are drawn.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub(super) enum Palette {
+    #[default]
+    Greyscale,
+    Viridis,
+    Cividis,
+    BlueOrange,
+    /// Colours made at runtime, e.g. by the palette randomizer, rather than built in.
+    Custom(CustomPalette),
+    /// Colours given by the coefficients of a cosine for each channel, which are edited live.
+    Cosine(CosinePalette),
+}
+
+impl Palette {
+    pub(super) const ALL: [Palette; 4] = [
+        Self::Greyscale,
+        Self::Viridis,
+        Self::Cividis,
+        Self::BlueOrange,
+    ];
+
+    fn source(self) -> Source {
+        match self {
+            Palette::Greyscale => Source::BuiltIn(&GREYSCALE),
+            Palette::Viridis => Source::BuiltIn(&VIRIDIS),
+            Palette::Cividis => Source::BuiltIn(&CIVIDIS),
+            Palette::BlueOrange => Source::BuiltIn(&BLUE_ORANGE),
+            Palette::Custom(custom) => Source::Custom(custom),
+            Palette::Cosine(cosine) => Source::Custom(cosine.sample()),
+        }
+    }
+
+    /// Evenly spaced colours in sRGB between which the escape time of the Mandelbrot set is
+    /// interpolated. The first is also the colour of the interior of the set.
+    pub(super) fn gradient(self) -> [[f32; 3]; GRADIENT_STOPS] {
+        match self.source() {
+            Source::BuiltIn(definition) => definition.gradient,
+            Source::Custom(custom) => custom.gradient.map(from_srgb8),
+        }
+    }
+
+    /// The colour in 8-bit sRGB at `t` from 0 to 1 along the gradient, interpolated in linear light
+    /// between the stops like `gradient_colour` in `frag.wgsl`.
+    pub(super) fn colour_at(self, t: f32) -> [u8; 3] {
+        let position = t.clamp(0.0, 1.0) * (GRADIENT_STOPS - 1) as f32;
+        let stop = (position as usize).min(GRADIENT_STOPS - 2);
+        let gradient = self.gradient();
+        let linear = |stop: usize| {
+            Oklab::from_srgb8(gradient[stop].map(|channel| (channel * 255.0).round() as u8))
+                .to_linear_srgb()
+        };
+        let (from, to) = (linear(stop), linear(stop + 1));
+        let fraction = position - stop as f32;
+        Oklab::from_linear_srgb(std::array::from_fn(|channel| {
+            from[channel] + (to[channel] - from[channel]) * fraction
+        }))
+        .to_srgb8()
+    }
+
+    /// The colours in sRGB of the basins of attraction of the three roots of the Newton fractal.
+    pub(super) fn roots(self) -> [[f32; 3]; 3] {
+        match self.source() {
+            Source::BuiltIn(definition) => definition.roots,
+            Source::Custom(custom) => custom.roots.map(from_srgb8),
+        }
+    }
+
+    /// Who can read the palette, which is only known of the built-in ones.
+    pub(super) fn accessibility(self) -> Option<Accessibility> {
+        match self.source() {
+            Source::BuiltIn(definition) => Some(definition.accessibility),
+            Source::Custom(_) => None,
+        }
+    }
+
+    /// Returns the gradient and root colours padded to `vec4`s for the uniform block.
+    pub(super) fn pack(self) -> ([[f32; 4]; GRADIENT_STOPS], [[f32; 4]; 3]) {
+        let pad = |[r, g, b]: [f32; 3]| [r, g, b, 1.0];
+        (self.gradient().map(pad), self.roots().map(pad))
+    }
+
+    /// Returns the packed colours blended towards those of `other` by `amount` from 0 to 1.
+    pub(super) fn pack_blended(
+        self,
+        other: Palette,
+        amount: f32,
+    ) -> ([[f32; 4]; GRADIENT_STOPS], [[f32; 4]; 3]) {
+        let ((gradient, roots), (other_gradient, other_roots)) = (self.pack(), other.pack());
+        let blend = |from: [f32; 4], to: [f32; 4]| {
+            std::array::from_fn(|channel| from[channel] + (to[channel] - from[channel]) * amount)
+        };
+        (
+            std::array::from_fn(|stop| blend(gradient[stop], other_gradient[stop])),
+            std::array::from_fn(|root| blend(roots[root], other_roots[root])),
+        )
+    }
+}
+
+impl Display for Palette {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Palette::Cosine(cosine) = self {
+            return write!(f, "{cosine}");
+        }
+        match self.source() {
+            Source::BuiltIn(definition) => write!(f, "{}", definition.name),
+            Source::Custom(custom) => write!(f, "{custom}"),
+        }
+    }
+}
+
+impl FromStr for Palette {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|palette| palette.to_string().eq_ignore_ascii_case(s))
+            .ok_or(())
+    }
+}
+
+/// Where the colours of a palette are kept.
+enum Source {
+    BuiltIn(&'static PaletteDefinition),
+    Custom(CustomPalette),
+}
+
+/// The colours of a palette made at runtime, like those of [`PaletteDefinition`] but in 8-bit
+/// sRGB, so that palettes can be compared and stored compactly.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub(super) struct CustomPalette {
+    pub(super) gradient: [[u8; 3]; GRADIENT_STOPS],
+    pub(super) roots: [[u8; 3]; 3],
+}
+
+impl Display for CustomPalette {
+    /// Names the palette by the first and last colours of its gradient, e.g. "Custom #1c0f3a to
+    /// #f4e9c1".
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Custom {} to {}",
+            hex(self.gradient[0]),
+            hex(self.gradient[GRADIENT_STOPS - 1])
+        )
+    }
+}
+
+/// Adds the palette to the saved palettes unless it is among them already.
+pub(super) fn keep(saved: &mut Vec<Palette>, palette: Palette) {
+    if !saved.contains(&palette) {
+        saved.push(palette);
+    }
+}
+
+/// Writes the colour in hexadecimal notation, e.g. "#1c0f3a".
+pub(super) fn hex([r, g, b]: [u8; 3]) -> String {
+    format!("#{r:02x}{g:02x}{b:02x}")
+}
+
+fn from_srgb8(colour: [u8; 3]) -> [f32; 3] {
+    colour.map(|channel| f32::from(channel) / 255.0)
+}
+
+/// The colours of a built-in palette in sRGB, which the shaders read from the uniform block.
+#[derive(Debug)]
+struct PaletteDefinition {
+    name: &'static str,
+    /// See [`Palette::gradient`].
+    gradient: [[f32; 3]; GRADIENT_STOPS],
+    /// See [`Palette::roots`].
+    roots: [[f32; 3]; 3],
+    accessibility: Accessibility,
+}
+
+/// Who can read a palette, as checked by the tests of this module.
+#[derive(Clone, Copy, Debug)]
+pub(super) struct Accessibility {
+    /// Whether neighbouring colours of the gradient and the colours of the roots remain
+    /// distinguishable with deuteranopia, in which the green cones are missing.
+    pub(super) deuteranopia_safe: bool,
+    /// Likewise with protanopia, in which the red cones are missing.
+    pub(super) protanopia_safe: bool,
+    /// Whether the lightness increases along the gradient, so that it reads correctly without
+    /// any colour vision at all, e.g. when printed in greyscale.
+    pub(super) monotonic_lightness: bool,
+}
+
+impl Display for Accessibility {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match (self.deuteranopia_safe, self.protanopia_safe) {
+            (true, true) => write!(f, "Colour-blind safe")?,
+            (true, false) => write!(f, "Safe with deuteranopia")?,
+            (false, true) => write!(f, "Safe with protanopia")?,
+            (false, false) => write!(f, "Not colour-blind safe")?,
+        }
+        if self.monotonic_lightness {
+            write!(f, ", readable in greyscale")?;
+        }
+        Ok(())
+    }
+}
+
+const GREYSCALE: PaletteDefinition = PaletteDefinition {
+    name: "Greyscale",
+    gradient: [
+        [0.0, 0.0, 0.0],
+        [1.0 / 7.0, 1.0 / 7.0, 1.0 / 7.0],
+        [2.0 / 7.0, 2.0 / 7.0, 2.0 / 7.0],
+        [3.0 / 7.0, 3.0 / 7.0, 3.0 / 7.0],
+        [4.0 / 7.0, 4.0 / 7.0, 4.0 / 7.0],
+        [5.0 / 7.0, 5.0 / 7.0, 5.0 / 7.0],
+        [6.0 / 7.0, 6.0 / 7.0, 6.0 / 7.0],
+        [1.0, 1.0, 1.0],
+    ],
+    // Red and green are hard to tell apart with the most common forms of colour blindness.
+    roots: [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]],
+    accessibility: Accessibility {
+        deuteranopia_safe: false,
+        protanopia_safe: false,
+        monotonic_lightness: true,
+    },
+};
+
+/// Samples of matplotlib's viridis colour map.
+const VIRIDIS: PaletteDefinition = PaletteDefinition {
+    name: "Viridis",
+    gradient: [
+        [0.267, 0.004, 0.329],
+        [0.275, 0.196, 0.494],
+        [0.212, 0.361, 0.553],
+        [0.153, 0.498, 0.557],
+        [0.122, 0.631, 0.529],
+        [0.290, 0.757, 0.427],
+        [0.627, 0.855, 0.224],
+        [0.992, 0.906, 0.145],
+    ],
+    roots: [
+        [0.267, 0.004, 0.329],
+        [0.129, 0.569, 0.549],
+        [0.992, 0.906, 0.145],
+    ],
+    accessibility: Accessibility {
+        deuteranopia_safe: true,
+        protanopia_safe: true,
+        monotonic_lightness: true,
+    },
+};
+
+/// An approximation of the cividis colour map, which varies only along the blue-yellow axis
+/// which all common forms of colour blindness preserve.
+const CIVIDIS: PaletteDefinition = PaletteDefinition {
+    name: "Cividis",
+    gradient: [
+        [0.0, 0.135, 0.305],
+        [0.13, 0.24, 0.43],
+        [0.29, 0.33, 0.43],
+        [0.42, 0.43, 0.45],
+        [0.54, 0.53, 0.47],
+        [0.67, 0.64, 0.46],
+        [0.82, 0.76, 0.40],
+        [1.0, 0.914, 0.271],
+    ],
+    roots: [
+        [0.0, 0.135, 0.305],
+        [0.486, 0.482, 0.471],
+        [1.0, 0.914, 0.271],
+    ],
+    accessibility: Accessibility {
+        deuteranopia_safe: true,
+        protanopia_safe: true,
+        monotonic_lightness: true,
+    },
+};
+
+/// A diverging map through the blue and orange of the Okabe-Ito palette, whose lightness peaks in
+/// the middle.
+const BLUE_ORANGE: PaletteDefinition = PaletteDefinition {
+    name: "Blue-orange",
+    gradient: [
+        [0.0, 0.0, 0.0],
+        [0.0, 0.2, 0.36],
+        [0.0, 0.447, 0.698],
+        [0.337, 0.706, 0.914],
+        [0.95, 0.95, 0.95],
+        [0.941, 0.894, 0.259],
+        [0.902, 0.624, 0.0],
+        [0.6, 0.3, 0.0],
+    ],
+    roots: [[0.0, 0.447, 0.698], [0.902, 0.624, 0.0], [0.95, 0.95, 0.95]],
+    accessibility: Accessibility {
+        deuteranopia_safe: true,
+        protanopia_safe: true,
+        monotonic_lightness: false,
+    },
+};
+
+#[cfg(test)]
+mod tests {
+    use super::{CustomPalette, Palette, GRADIENT_STOPS};
+    use googletest::prelude::*;
+    use palette::{color_difference::Ciede2000, IntoColor, Lab, LinSrgb, Srgb};
+
+    /// The smallest CIEDE2000 difference at which colours are considered distinguishable.
+    const MIN_DIFFERENCE: f32 = 5.0;
+
+    /// Simulates dichromacy in linear RGB with the matrices of Machado, Oliveira and Fernandes
+    /// (2009) at full severity.
+    const DEUTERANOPIA: [[f32; 3]; 3] = [
+        [0.367322, 0.860646, -0.227968],
+        [0.280085, 0.672501, 0.047413],
+        [-0.011820, 0.042940, 0.968881],
+    ];
+    const PROTANOPIA: [[f32; 3]; 3] = [
+        [0.152286, 1.052583, -0.204868],
+        [0.114503, 0.786281, 0.099216],
+        [-0.003882, -0.048116, 1.051998],
+    ];
+
+    #[test]
+    fn colour_blind_safe_palettes_remain_distinguishable() -> Result<()> {
+        for palette in Palette::ALL {
+            let accessibility = palette.accessibility().unwrap();
+            for (simulation, applies) in [
+                (DEUTERANOPIA, accessibility.deuteranopia_safe),
+                (PROTANOPIA, accessibility.protanopia_safe),
+            ] {
+                if !applies {
+                    continue;
+                }
+                let gradient = palette
+                    .gradient()
+                    .map(|colour| simulate(simulation, colour));
+                for pair in gradient.windows(2) {
+                    verify_that!(
+                        (palette, pair[0].difference(pair[1])),
+                        (anything(), ge(MIN_DIFFERENCE))
+                    )?;
+                }
+                let roots = palette.roots().map(|colour| simulate(simulation, colour));
+                for (i, j) in [(0, 1), (1, 2), (0, 2)] {
+                    verify_that!(
+                        (palette, roots[i].difference(roots[j])),
+                        (anything(), ge(MIN_DIFFERENCE))
+                    )?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn lightness_increases_along_monotonic_gradients() -> Result<()> {
+        for palette in Palette::ALL {
+            if !palette.accessibility().unwrap().monotonic_lightness {
+                continue;
+            }
+            let lightness = palette.gradient().map(|colour| lab(colour).l);
+            for pair in lightness.windows(2) {
+                verify_that!((palette, pair[1]), (anything(), gt(pair[0])))?;
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn palettes_are_found_by_name() -> Result<()> {
+        verify_that!("cividis".parse::<Palette>(), ok(eq(Palette::Cividis)))
+    }
+
+    #[test]
+    fn blending_halfway_averages_colours() -> Result<()> {
+        let (gradient, roots) = Palette::Greyscale.pack_blended(Palette::Viridis, 0.5);
+
+        verify_that!(
+            (gradient[7][0], roots[0][0], roots[0][3]),
+            (approx_eq(0.996), approx_eq(0.6335), approx_eq(1.0))
+        )
+    }
+
+    #[test]
+    fn custom_palette_is_named_by_ends_of_gradient() -> Result<()> {
+        let mut gradient = [[128; 3]; GRADIENT_STOPS];
+        gradient[0] = [0x1c, 0x0f, 0x3a];
+        gradient[GRADIENT_STOPS - 1] = [0xf4, 0xe9, 0xc1];
+        let palette = Palette::Custom(CustomPalette {
+            gradient,
+            roots: [[255, 0, 0]; 3],
+        });
+
+        verify_that!(
+            (
+                palette.to_string(),
+                palette.gradient()[0][0],
+                palette.roots()[0]
+            ),
+            (
+                eq("Custom #1c0f3a to #f4e9c1"),
+                approx_eq(28.0 / 255.0),
+                eq([1.0, 0.0, 0.0])
+            )
+        )
+    }
+
+    #[test]
+    fn colour_between_stops_is_interpolated_in_linear_light() -> Result<()> {
+        let mut gradient = [[255; 3]; GRADIENT_STOPS];
+        gradient[0] = [0; 3];
+        let palette = Palette::Custom(CustomPalette {
+            gradient,
+            roots: [[0; 3]; 3],
+        });
+
+        verify_that!(
+            (
+                palette.colour_at(0.0),
+                palette.colour_at(0.5 / (GRADIENT_STOPS - 1) as f32),
+                palette.colour_at(1.0)
+            ),
+            (eq([0; 3]), eq([188; 3]), eq([255; 3]))
+        )
+    }
+
+    fn lab([r, g, b]: [f32; 3]) -> Lab {
+        Srgb::new(r, g, b).into_linear::<f32>().into_color()
+    }
+
+    fn simulate(matrix: [[f32; 3]; 3], [r, g, b]: [f32; 3]) -> Lab {
+        let linear: LinSrgb = Srgb::new(r, g, b).into_linear();
+        let [r, g, b] = matrix.map(|row| {
+            (row[0] * linear.red + row[1] * linear.green + row[2] * linear.blue).clamp(0.0, 1.0)
+        });
+        LinSrgb::new(r, g, b).into_color()
+    }
+}
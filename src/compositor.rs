@@ -0,0 +1,173 @@
+use serde::{Deserialize, Serialize};
+use std::{fmt::Display, ops::RangeInclusive};
+
+/// Colours escaping points by two layers, each of which maps a quantity of the orbit onto the
+/// palette, with the overlay blended onto the base. Applied by `composited_colour` in
+/// `colouring.wgsl`, before the orbit trap's image and the post-processing.
+///
+/// The stripe average and the distance estimate are computed alongside the iteration data in
+/// `f32` for fractals which iterate `z^d + c`, like the quantities of the debug view, and only
+/// while a layer shows them. They are zero for other fractals.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub(super) struct Compositor {
+    pub(super) base: Layer,
+    pub(super) overlay: Layer,
+    pub(super) blend_mode: BlendMode,
+    /// How much of the blended overlay covers the base, from zero, which leaves the base alone,
+    /// to one.
+    pub(super) opacity: f32,
+}
+
+impl Default for Compositor {
+    fn default() -> Self {
+        Self {
+            base: Layer::SmoothIteration,
+            overlay: Layer::StripeAverage,
+            blend_mode: BlendMode::Normal,
+            opacity: 0.0,
+        }
+    }
+}
+
+impl Compositor {
+    pub(super) const OPACITY_RANGE: RangeInclusive<f32> = 0.0..=1.0;
+    /// The increment of the slider of the opacity, which is also how far an arrow key moves it.
+    pub(super) const OPACITY_STEP: f32 = 0.05;
+
+    /// Returns the compositor with the given opacity, clamped to its range.
+    pub(super) fn with_opacity(self, opacity: f32) -> Self {
+        Self {
+            opacity: opacity.clamp(*Self::OPACITY_RANGE.start(), *Self::OPACITY_RANGE.end()),
+            ..self
+        }
+    }
+
+    /// Whether a visible layer shows a quantity besides the escape time, which must then be stored
+    /// with the iteration data.
+    pub(super) fn needs_statistics(&self) -> bool {
+        self.base != Layer::SmoothIteration
+            || (self.opacity > 0.0 && self.overlay != Layer::SmoothIteration)
+    }
+}
+
+/// What a layer of the [`Compositor`] maps onto the palette.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub(super) enum Layer {
+    /// The escape time, interpolated between whole iterations, shifted by the palette offset.
+    SmoothIteration,
+    /// The average over the orbit of the sine of a multiple of its angle, which draws stripes
+    /// following the field lines.
+    StripeAverage,
+    /// The estimated distance from the set, in pixels on a logarithmic scale, which shades the
+    /// filaments.
+    DistanceEstimate,
+}
+
+impl Layer {
+    pub(super) const ALL: [Layer; 3] = [
+        Self::SmoothIteration,
+        Self::StripeAverage,
+        Self::DistanceEstimate,
+    ];
+
+    /// The value of `base_layer` and `overlay_layer` in the uniform block. Must match the
+    /// constants `LAYER_*` in `frag.wgsl`.
+    pub(super) fn code(self) -> u32 {
+        match self {
+            Layer::SmoothIteration => 0,
+            Layer::StripeAverage => 1,
+            Layer::DistanceEstimate => 2,
+        }
+    }
+}
+
+impl Display for Layer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Layer::SmoothIteration => write!(f, "Smooth iteration"),
+            Layer::StripeAverage => write!(f, "Stripe average"),
+            Layer::DistanceEstimate => write!(f, "Distance estimate"),
+        }
+    }
+}
+
+/// How the colour of the overlay combines with that of the base, in linear light.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub(super) enum BlendMode {
+    /// The overlay covers the base.
+    #[default]
+    Normal,
+    /// The product of the colours, which only darkens.
+    Multiply,
+    /// The inverse of the product of the inverses, which only lightens.
+    Screen,
+    /// Multiplies the dark parts of the base and screens its light ones, which adds contrast.
+    Overlay,
+    /// A gentler overlay, which never reaches pure black or white.
+    SoftLight,
+}
+
+impl BlendMode {
+    pub(super) const ALL: [BlendMode; 5] = [
+        Self::Normal,
+        Self::Multiply,
+        Self::Screen,
+        Self::Overlay,
+        Self::SoftLight,
+    ];
+
+    /// The value of `blend_mode` in the uniform block. Must match the constants `BLEND_*` in
+    /// `colouring.wgsl`.
+    pub(super) fn code(self) -> u32 {
+        match self {
+            BlendMode::Normal => 0,
+            BlendMode::Multiply => 1,
+            BlendMode::Screen => 2,
+            BlendMode::Overlay => 3,
+            BlendMode::SoftLight => 4,
+        }
+    }
+}
+
+impl Display for BlendMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BlendMode::Normal => write!(f, "Normal"),
+            BlendMode::Multiply => write!(f, "Multiply"),
+            BlendMode::Screen => write!(f, "Screen"),
+            BlendMode::Overlay => write!(f, "Overlay"),
+            BlendMode::SoftLight => write!(f, "Soft light"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Compositor, Layer};
+    use googletest::prelude::*;
+
+    #[test]
+    fn transparent_overlay_needs_no_statistics() -> Result<()> {
+        let transparent = Compositor {
+            overlay: Layer::DistanceEstimate,
+            ..Compositor::default()
+        };
+        let opaque = Compositor {
+            opacity: 0.5,
+            ..transparent
+        };
+        let base = Compositor {
+            base: Layer::StripeAverage,
+            ..Compositor::default()
+        };
+
+        verify_that!(
+            (
+                transparent.needs_statistics(),
+                opaque.needs_statistics(),
+                base.needs_statistics()
+            ),
+            (eq(false), eq(true), eq(true))
+        )
+    }
+}
@@ -0,0 +1,2146 @@
+use crate::{
+    analysis::{Analyzer, AreaRegion, Region, AREA_RESOLUTION, HISTOGRAM_RESOLUTION},
+    annotation::Annotation,
+    bookmark::{Bookmark, MorphEnd},
+    clock::Clock,
+    colour_palette::{self, Palette},
+    controls::{self, CanvasMessage, FractalType, LayoutSetting, Message, UiScale},
+    cosine_palette::CosinePalette,
+    dive::{Dive, DiveTarget, Framing},
+    explorer_grid::GridAxis,
+    fractal_definition::EXPONENT,
+    fractal_view::View,
+    gpu::Gpu,
+    gpu_memory::GPU_MEMORY_BUDGET,
+    history::{self, History},
+    image_palette, isolines, iteration_budget,
+    jobs::{self, JobHandle, JobStatus, Poll},
+    locator::{self, LandmarkKind},
+    minibrot,
+    modulation::{ModulationMatrix, ModulationOffsets, Sweep},
+    notification::CompletionWatcher,
+    orbit_trap,
+    precision::{self, NumericMode, Precision},
+    presentation::{self, Presentation, PresentationFrame},
+    random_palette,
+    render_scheduler::{Change, RenderScheduler},
+    session::{Session, SessionStore},
+    snippet,
+    storage::Storage,
+    svg_export::{self, SvgStroke},
+    transform,
+    user_config::UserConfig,
+    workspace::{self, Workspace},
+};
+use cgmath::Vector2;
+use iced::{time::Instant, Point, Size};
+use iced_wgpu::graphics::Viewport;
+use iced_winit::{style::Theme, winit::dpi::PhysicalSize};
+use std::time::Duration;
+
+const ZOOM_SCROLL_FACTOR: f32 = 40.0;
+
+const COMPILING_ANIMATION: &str = "compiling";
+const MODULATION_ANIMATION: &str = "modulation";
+const DIVE_ANIMATION: &str = "dive";
+const JOYSTICK_ANIMATION: &str = "joystick";
+const JOBS_ANIMATION: &str = "jobs";
+const SESSION_ANIMATION: &str = "session";
+const THUMBNAILS_ANIMATION: &str = "thumbnails";
+const TILE_ANIMATION: &str = "tile";
+const REPLAY_ANIMATION: &str = "replay";
+const PRESENTATION_ANIMATION: &str = "presentation";
+const TIME_ANIMATION: &str = "time";
+const ANALYSIS_ANIMATION: &str = "analysis";
+
+/// How long the view must rest before the iteration budget is tuned to it and its histogram is
+/// computed, which read histograms back from the GPU and would stall every frame of a pan or zoom.
+const ANALYSIS_SETTLE_TIME: Duration = Duration::from_millis(300);
+const SPINNER_TURNS_PER_SECOND: f32 = 1.0;
+
+/// How many window widths per second the view pans while the joystick is pushed all the way.
+const JOYSTICK_SPEED: f32 = 1.0;
+
+/// How much larger the touch layout draws the controls, so that fingers can hit them.
+const TOUCH_UI_FACTOR: f64 = 1.5;
+
+/// Magnifications from which the window title switches to scientific notation.
+const SCIENTIFIC_MAGNIFICATION: f64 = 1000.0;
+
+/// Applies the messages emitted by the controls to the fractal view.
+///
+/// All messages which arrived since the last pass through the event loop are applied in order, so
+/// that fast interaction which produces several pans or zooms per update is not lost.
+pub(super) struct Controller {
+    /// The window as the controls see it, in whose logical pixels the canvas reports positions.
+    viewport: Viewport,
+    analyzer: Analyzer,
+    fractal_type: FractalType,
+    show_histogram: bool,
+    auto_iterations: bool,
+    /// When the view last changed while the iteration budget is tuned automatically, until it is
+    /// tuned once the view rests.
+    iterations_stale_since: Option<Instant>,
+    /// When the view last changed while the histogram is shown, until it is computed once the view
+    /// rests.
+    histogram_stale_since: Option<Instant>,
+    /// When the controls started waiting for the current fractal's pipeline.
+    compiling_since: Option<Instant>,
+    /// Whether the window title must be updated to reflect the fractal or the view.
+    title_stale: bool,
+    ui_scale: UiScale,
+    layout: LayoutSetting,
+    /// Whether the controls take the touch layout, as last resolved from `layout`.
+    touch_layout: bool,
+    high_contrast: bool,
+    theme: Theme,
+    modulation: ModulationMatrix,
+    /// The time from which the LFOs of the modulation matrix count their cycles.
+    modulation_epoch: Instant,
+    morph_start: Option<Bookmark>,
+    morph_end: Option<Bookmark>,
+    dive: Option<Dive>,
+    /// How far the joystick of the on-screen navigation is pushed, and when the view last panned
+    /// accordingly.
+    joystick: Vector2<f32>,
+    joystick_moved_at: Instant,
+    /// The point of the plane on which all zooms centre while the zoom target is locked, wherever
+    /// the cursor is.
+    zoom_target: Option<Vector2<f64>>,
+    /// The mode in which the view is rendered, which switches automatically with the depth.
+    numeric_mode: NumericMode,
+    /// The precision last reported to the controls.
+    reported_precision: Option<Precision>,
+    /// The GPU memory held by the view, in whole mebibytes, as last reported to the controls.
+    reported_gpu_memory: Option<u64>,
+    /// Jobs on the worker pool whose results are delivered to the controls once they finish.
+    jobs: Vec<JobHandle<Message>>,
+    /// The statuses of the running jobs last reported to the controls.
+    reported_jobs: Vec<JobStatus>,
+    completions: CompletionWatcher,
+    /// Whether the notification of a completed job comes with a sound.
+    notification_sound: bool,
+    history: History,
+    /// When the replay of the history in progress started, if any.
+    replay_started: Option<Instant>,
+    workspaces: Vec<Workspace>,
+    presentation: Option<Presentation>,
+    /// The notes pinned to the plane, which are stored with the session.
+    annotations: Vec<Annotation>,
+    /// The palettes kept from the randomizer, which are stored with the session.
+    saved_palettes: Vec<Palette>,
+    /// The time of the uniform block, which animates a spinning variation.
+    clock: Clock,
+    /// The settings of the configuration file, or `None` until it has loaded.
+    user_config: Option<UserConfig>,
+    /// Whether a session stored by an earlier run was restored, whose fractal then takes precedence
+    /// over the default one of the configuration file.
+    session_restored: bool,
+}
+
+impl Controller {
+    pub(super) fn new(gpu: &Gpu, window_size: PhysicalSize<u32>) -> Self {
+        Self {
+            viewport: Viewport::with_physical_size(
+                Size::new(window_size.width, window_size.height),
+                1.0,
+            ),
+            analyzer: Analyzer::new(gpu),
+            fractal_type: FractalType::Mandelbrot,
+            show_histogram: false,
+            auto_iterations: false,
+            iterations_stale_since: None,
+            histogram_stale_since: None,
+            compiling_since: None,
+            title_stale: true,
+            ui_scale: UiScale::default(),
+            layout: LayoutSetting::default(),
+            touch_layout: LayoutSetting::default().is_touch(),
+            high_contrast: false,
+            theme: Theme::Dark,
+            modulation: ModulationMatrix::default(),
+            modulation_epoch: Instant::now(),
+            morph_start: None,
+            morph_end: None,
+            dive: None,
+            joystick: Vector2::new(0.0, 0.0),
+            joystick_moved_at: Instant::now(),
+            zoom_target: None,
+            numeric_mode: NumericMode::F32,
+            reported_precision: None,
+            reported_gpu_memory: None,
+            jobs: vec![],
+            reported_jobs: vec![],
+            completions: CompletionWatcher::default(),
+            notification_sound: false,
+            history: History::new(Instant::now()),
+            replay_started: None,
+            workspaces: vec![],
+            presentation: None,
+            annotations: vec![],
+            saved_palettes: vec![],
+            clock: Clock::new(),
+            user_config: None,
+            session_restored: false,
+        }
+    }
+
+    /// Follows the size of the window and the scale of the controls, so that positions on the
+    /// canvas map onto the view whatever its size.
+    pub(super) fn set_viewport(&mut self, viewport: &Viewport) {
+        self.viewport = viewport.clone();
+    }
+
+    /// Chooses whether the notification of a job which finished while the window was out of
+    /// focus comes with a sound.
+    #[cfg_attr(not(feature = "notifications"), allow(dead_code))]
+    pub(super) fn set_notification_sound(&mut self, sound: bool) {
+        self.notification_sound = sound;
+    }
+
+    /// Swaps in the pipelines which finished compiling in the background and animates the spinner
+    /// in the controls while the current fractal's pipeline is still being compiled.
+    pub(super) fn poll_pipelines(
+        &mut self,
+        fractal_view: &mut View,
+        scheduler: &mut RenderScheduler,
+    ) -> Vec<Message> {
+        if fractal_view.receive_pipelines() {
+            if self.compiling_since.take().is_none() {
+                return vec![];
+            }
+            scheduler.stop_animation(COMPILING_ANIMATION);
+            scheduler.invalidate(Change::Parameters);
+            return vec![Message::CompilingShader(None)];
+        }
+        let since = *self.compiling_since.get_or_insert_with(Instant::now);
+        scheduler.start_animation(COMPILING_ANIMATION);
+        vec![Message::CompilingShader(Some(
+            since.elapsed().as_secs_f32() * SPINNER_TURNS_PER_SECOND,
+        ))]
+    }
+
+    /// Moves the modulated values of the view to where the LFOs are now.
+    pub(super) fn apply_modulation(
+        &self,
+        fractal_view: &mut View,
+        scheduler: &mut RenderScheduler,
+    ) {
+        if self.modulation.is_active() {
+            let seconds = self.modulation_epoch.elapsed().as_secs_f32();
+            let offsets = self.modulation.evaluate(seconds, fractal_view.seed());
+            fractal_view.set_modulation(offsets);
+            scheduler.invalidate(Change::Parameters);
+        }
+    }
+
+    /// Moves the time of the view on to now while it plays.
+    pub(super) fn advance_time(&self, fractal_view: &mut View, scheduler: &mut RenderScheduler) {
+        if self.clock.is_playing() {
+            fractal_view.set_time(self.clock.seconds(Instant::now()));
+            scheduler.invalidate(Change::Parameters);
+        }
+    }
+
+    /// Zooms further in on the target of the dive in progress, if any, raising the iteration
+    /// budget with the magnification. The dive ends once none of the available numeric modes can
+    /// resolve the pixels.
+    pub(super) fn advance_dive(
+        &mut self,
+        fractal_view: &mut View,
+        scheduler: &mut RenderScheduler,
+    ) -> Vec<Message> {
+        let Some(dive) = &mut self.dive else {
+            return vec![];
+        };
+        let framing = dive.step(framing_of(fractal_view));
+        let available_modes = precision::available_modes(fractal_view.definition());
+        if !Precision::of_view(framing, self.viewport.physical_width(), &available_modes)
+            .is_sufficient()
+        {
+            self.dive = None;
+            scheduler.stop_animation(DIVE_ANIMATION);
+            return vec![Message::DiveEnded];
+        }
+        fractal_view.look_at_precisely(framing.centre, framing.half_width);
+        scheduler.invalidate(Change::View);
+        self.title_stale = true;
+        let mut replies = vec![update_view_transform(fractal_view)];
+        replies.extend(raise_max_iterations(
+            fractal_view,
+            scheduler,
+            framing.half_width,
+        ));
+        replies
+    }
+
+    /// Pans the view as far as the joystick of the on-screen navigation has moved it since the last
+    /// frame, while it is pushed.
+    pub(super) fn advance_joystick(
+        &mut self,
+        fractal_view: &mut View,
+        scheduler: &mut RenderScheduler,
+    ) -> Option<Message> {
+        if self.joystick == Vector2::new(0.0, 0.0) {
+            return None;
+        }
+        let now = Instant::now();
+        let seconds = (now - self.joystick_moved_at).as_secs_f32();
+        self.joystick_moved_at = now;
+        let size = self.viewport.logical_size();
+        let aspect_ratio = size.width / size.height;
+        let displacement = JOYSTICK_SPEED
+            * seconds
+            * Vector2::new(self.joystick.x, self.joystick.y * aspect_ratio);
+        fractal_view.translate(displacement);
+        scheduler.invalidate(Change::View);
+        self.title_stale = true;
+        Some(update_view_transform(fractal_view))
+    }
+
+    /// Morphs the view on through the history while it is replayed, ending on its last entry.
+    pub(super) fn advance_replay(
+        &mut self,
+        fractal_view: &mut View,
+        scheduler: &mut RenderScheduler,
+    ) -> Vec<Message> {
+        let Some(replay_started) = self.replay_started else {
+            return vec![];
+        };
+        let entries = self.history.entries();
+        let mut replies = vec![];
+        let bookmark = match self
+            .history
+            .replay_at(replay_started.elapsed().as_secs_f32())
+        {
+            Some((index, t)) => {
+                fractal_view.morph(&entries[index].bookmark, &entries[index + 1].bookmark, t)
+            }
+            None => {
+                let Some(last) = entries.last() else {
+                    self.replay_started = None;
+                    scheduler.stop_animation(REPLAY_ANIMATION);
+                    return vec![Message::HistoryReplayToggled(false)];
+                };
+                fractal_view.apply_bookmark(&last.bookmark);
+                self.replay_started = None;
+                scheduler.stop_animation(REPLAY_ANIMATION);
+                replies.push(Message::HistoryReplayToggled(false));
+                last.bookmark.clone()
+            }
+        };
+        replies.extend(self.show_animated(bookmark, fractal_view, scheduler));
+        replies
+    }
+
+    /// Morphs the view on towards the current stop of the presentation in progress, if any, or on
+    /// to the next once the hold time of the current one has passed.
+    pub(super) fn advance_presentation(
+        &mut self,
+        fractal_view: &mut View,
+        scheduler: &mut RenderScheduler,
+    ) -> Vec<Message> {
+        let Some(presentation) = &mut self.presentation else {
+            return vec![];
+        };
+        let stop = presentation.status().stop;
+        let frame = presentation.frame(Instant::now());
+        if presentation.is_moving() {
+            scheduler.start_animation(PRESENTATION_ANIMATION);
+        } else {
+            scheduler.stop_animation(PRESENTATION_ANIMATION);
+        }
+        let status = presentation.status();
+        let mut replies = vec![];
+        if status.stop != stop {
+            replies.push(Message::PresentationChanged(Some(status)));
+        }
+        let bookmark = match frame {
+            PresentationFrame::Morph(from, to, t) => fractal_view.morph(&from, &to, t),
+            PresentationFrame::Arrive(bookmark) => {
+                fractal_view.apply_bookmark(&bookmark);
+                bookmark
+            }
+            PresentationFrame::Hold => return replies,
+        };
+        replies.extend(self.show_animated(bookmark, fractal_view, scheduler));
+        replies
+    }
+
+    /// Switches to the cheapest numeric mode which resolves the view, and returns the message which
+    /// reports the precision of the view to the controls if it changed since the last call.
+    pub(super) fn update_precision(
+        &mut self,
+        fractal_view: &mut View,
+        scheduler: &mut RenderScheduler,
+    ) -> Option<Message> {
+        let precision = Precision::of_view(
+            framing_of(fractal_view),
+            self.viewport.physical_width(),
+            &precision::available_modes(fractal_view.definition()),
+        );
+        if precision.mode != self.numeric_mode {
+            tracing::info!("Switched from {} to {}", self.numeric_mode, precision.mode);
+            self.numeric_mode = precision.mode;
+            fractal_view.set_numeric_mode(precision.mode);
+            scheduler.invalidate(Change::Parameters);
+        }
+        (self.reported_precision != Some(precision)).then(|| {
+            self.reported_precision = Some(precision);
+            Message::PrecisionChanged(precision)
+        })
+    }
+
+    /// Evicts what the view can render again while it holds more GPU memory than
+    /// [`GPU_MEMORY_BUDGET`], and returns the message which reports its usage to the controls if
+    /// it changed by a mebibyte or more since the last call.
+    pub(super) fn update_gpu_memory(&mut self, fractal_view: &View) -> Option<Message> {
+        let mut usage = fractal_view.gpu_memory();
+        if usage.total() > GPU_MEMORY_BUDGET {
+            fractal_view.relieve_memory_pressure(usage.total() - GPU_MEMORY_BUDGET);
+            usage = fractal_view.gpu_memory();
+        }
+        (self.reported_gpu_memory != Some(usage.total_mib())).then(|| {
+            self.reported_gpu_memory = Some(usage.total_mib());
+            Message::GpuMemoryMeasured(usage)
+        })
+    }
+
+    /// Binds a reference orbit which serves the view while it is rendered by perturbation. A closer
+    /// one computed in the background is picked up once ready, since [`Controller::poll_jobs`]
+    /// keeps frames coming meanwhile.
+    pub(super) fn update_reference_orbit(
+        &self,
+        fractal_view: &mut View,
+        scheduler: &mut RenderScheduler,
+    ) {
+        if fractal_view.update_reference_orbit() {
+            scheduler.invalidate(Change::Parameters);
+        }
+    }
+
+    /// Returns the results of the jobs which finished since the last call, followed by the
+    /// statuses of those still running if they changed, and keeps rendering frames while any run
+    /// so that their progress is shown.
+    pub(super) fn poll_jobs(
+        &mut self,
+        fractal_view: &View,
+        scheduler: &mut RenderScheduler,
+    ) -> Vec<Message> {
+        let mut replies = vec![];
+        self.jobs.retain(|job| match job.poll() {
+            Poll::Running => true,
+            Poll::Finished(reply) => {
+                replies.push(reply);
+                false
+            }
+            Poll::Failed => {
+                tracing::error!("Job \"{}\" failed", job.status().name);
+                false
+            }
+        });
+        let statuses: Vec<_> = self
+            .jobs
+            .iter()
+            .map(JobHandle::status)
+            .chain(fractal_view.reference_orbit_job())
+            .collect();
+        if statuses.is_empty() {
+            scheduler.stop_animation(JOBS_ANIMATION);
+        } else {
+            scheduler.start_animation(JOBS_ANIMATION);
+        }
+        for completion in self
+            .completions
+            .update(&statuses, scheduler.is_focused(), Instant::now())
+        {
+            completion.show(self.notification_sound);
+        }
+        if statuses != self.reported_jobs {
+            self.reported_jobs = statuses.clone();
+            replies.push(Message::JobsProgressed(statuses));
+        }
+        replies
+    }
+
+    /// Tunes the iteration budget to the view and computes its histogram once it rested for
+    /// [`ANALYSIS_SETTLE_TIME`] since it last changed, and returns the messages which report them
+    /// to the controls.
+    pub(super) fn update_analysis(
+        &mut self,
+        gpu: &Gpu,
+        fractal_view: &mut View,
+        scheduler: &mut RenderScheduler,
+        now: Instant,
+    ) -> Vec<Message> {
+        let settled =
+            |since: Option<Instant>| since.is_some_and(|since| now - since >= ANALYSIS_SETTLE_TIME);
+        let mut replies = vec![];
+        let mut histogram_due = settled(self.histogram_stale_since);
+        if settled(self.iterations_stale_since) {
+            self.iterations_stale_since = None;
+            if let Some(reply) = self.tune_iterations(gpu, fractal_view, scheduler) {
+                histogram_due = true;
+                replies.push(reply);
+            }
+        }
+        if histogram_due {
+            self.histogram_stale_since = None;
+            if self.shows_histogram() {
+                let histogram = self.analyzer.iteration_histogram(
+                    gpu,
+                    Region::of_view(fractal_view),
+                    HISTOGRAM_RESOLUTION,
+                );
+                replies.push(Message::HistogramComputed(histogram));
+            }
+        }
+        if self.iterations_stale_since.is_none() && self.histogram_stale_since.is_none() {
+            scheduler.stop_animation(ANALYSIS_ANIMATION);
+        }
+        replies
+    }
+
+    /// Tunes the iteration budget to the view if it is tuned automatically, and returns the
+    /// message which reports it to the controls if it changed.
+    fn tune_iterations(
+        &self,
+        gpu: &Gpu,
+        fractal_view: &mut View,
+        scheduler: &mut RenderScheduler,
+    ) -> Option<Message> {
+        if !self.auto_iterations {
+            return None;
+        }
+        let max_iterations =
+            iteration_budget::auto_tune(&self.analyzer, gpu, Region::of_view(fractal_view));
+        (max_iterations != fractal_view.max_iterations()).then(|| {
+            fractal_view.set_max_iterations(max_iterations);
+            scheduler.invalidate(Change::Parameters);
+            Message::MaxIterationsApplied(max_iterations)
+        })
+    }
+
+    /// Whether the histogram of escape times is shown, which is only defined for the Mandelbrot
+    /// set.
+    fn shows_histogram(&self) -> bool {
+        self.show_histogram && self.fractal_type == FractalType::Mandelbrot
+    }
+
+    /// Records the state of the view in the history if it changed, and returns the message which
+    /// reports the number of entries to the controls if so.
+    pub(super) fn record_history(&mut self, fractal_view: &View) -> Option<Message> {
+        self.history
+            .observe(&fractal_view.bookmark(), Instant::now())
+            .then(|| Message::HistoryRecorded(self.history.entries().len()))
+    }
+
+    /// Keeps rendering frames while thumbnails wait to be rendered, a few on each.
+    pub(super) fn poll_thumbnails(&self, fractal_view: &View, scheduler: &mut RenderScheduler) {
+        if fractal_view.thumbnails_pending() {
+            scheduler.start_animation(THUMBNAILS_ANIMATION);
+        } else {
+            scheduler.stop_animation(THUMBNAILS_ANIMATION);
+        }
+    }
+
+    /// Writes the iteration data of a deep view to the tile cache once the view rests on it, so
+    /// that revisiting it in a later session needs no computation.
+    pub(super) fn persist_tile(
+        &self,
+        gpu: &Gpu,
+        fractal_view: &View,
+        scheduler: &mut RenderScheduler,
+    ) {
+        if fractal_view.persist_tile(gpu, Instant::now()) {
+            scheduler.start_animation(TILE_ANIMATION);
+        } else {
+            scheduler.stop_animation(TILE_ANIMATION);
+        }
+    }
+
+    /// Passes the session stored by an earlier run to the controls once it has loaded, and
+    /// afterwards stores the session whenever it changes, so that bookmarks and settings survive a
+    /// restart or, on the web, a reload of the page.
+    pub(super) fn persist_session<S: Storage + Clone + 'static>(
+        &self,
+        store: &mut SessionStore<S>,
+        fractal_view: &View,
+        scheduler: &mut RenderScheduler,
+    ) -> Option<Message> {
+        if let Some(session) = store.take_restored() {
+            return Some(Message::SessionRestored(Box::new(session)));
+        }
+        // Frames keep coming while a save is pending, since nothing else may wake the event loop.
+        if store.update(self.session(fractal_view), Instant::now()) {
+            scheduler.start_animation(SESSION_ANIMATION);
+        } else {
+            scheduler.stop_animation(SESSION_ANIMATION);
+        }
+        None
+    }
+
+    /// Saves any change to the session which is still pending, as when the window closes.
+    pub(super) fn flush_session<S: Storage + Clone + 'static>(
+        &self,
+        store: &mut SessionStore<S>,
+        fractal_view: &View,
+    ) {
+        store.flush(self.session(fractal_view));
+    }
+
+    fn session(&self, fractal_view: &View) -> Session {
+        Session {
+            view: fractal_view.bookmark(),
+            morph_start: self.morph_start.clone(),
+            morph_end: self.morph_end.clone(),
+            ui_scale: self.ui_scale,
+            layout: self.layout,
+            high_contrast: self.high_contrast,
+            workspaces: self.workspaces.clone(),
+            annotations: self.annotations.clone(),
+            saved_palettes: self.saved_palettes.clone(),
+        }
+    }
+
+    /// How far each step of a zoom zooms, as a power of the default step.
+    fn zoom_speed(&self) -> f32 {
+        self.user_config
+            .as_ref()
+            .map_or(1.0, |config| config.zoom_speed)
+    }
+
+    /// Returns the window title if the fractal or the view changed since the last call.
+    pub(super) fn take_window_title(&mut self, fractal_view: &View) -> Option<String> {
+        std::mem::take(&mut self.title_stale).then(|| window_title(fractal_view))
+    }
+
+    /// The factor by which the controls are scaled in addition to the scale factor of the display.
+    pub(super) fn ui_scale(&self) -> f64 {
+        let layout_factor = if self.touch_layout {
+            TOUCH_UI_FACTOR
+        } else {
+            1.0
+        };
+        self.ui_scale.factor() * layout_factor
+    }
+
+    /// The theme with which the controls are drawn.
+    pub(super) fn theme(&self) -> &Theme {
+        &self.theme
+    }
+
+    /// Applies the messages to the view and reports the resulting changes to the scheduler.
+    ///
+    /// Returns the messages which must be sent back to the controls so that they reflect the new
+    /// state.
+    pub(super) fn handle_messages(
+        &mut self,
+        messages: impl IntoIterator<Item = Message>,
+        gpu: &Gpu,
+        fractal_view: &mut View,
+        scheduler: &mut RenderScheduler,
+    ) -> Vec<Message> {
+        let mut replies = vec![];
+        let mut view_changed = false;
+        let mut histogram_stale = false;
+        let mut iterations_stale = false;
+        let mut modulation_changed = false;
+        for message in messages {
+            match message {
+                Message::Canvas(CanvasMessage::Pan(x, y)) => {
+                    let size = self.viewport.logical_size();
+                    let displacement = Vector2::new(x / size.width, y / size.height);
+                    fractal_view.translate(displacement);
+                    view_changed = true;
+                }
+                Message::Canvas(CanvasMessage::Zoom(y, on_point)) => {
+                    let factor = (y / ZOOM_SCROLL_FACTOR + 1.0).powf(self.zoom_speed());
+                    fractal_view.zoom(factor, self.zoom_offset(fractal_view, on_point));
+                    view_changed = true;
+                }
+                Message::Canvas(CanvasMessage::Pinch(factor, on_point)) => {
+                    fractal_view.zoom(factor, self.zoom_offset(fractal_view, on_point));
+                    view_changed = true;
+                }
+                Message::Canvas(CanvasMessage::Joystick(deflection)) => {
+                    self.steer(deflection, scheduler);
+                }
+                Message::Canvas(CanvasMessage::Home) => {
+                    fractal_view.reset_transform();
+                    view_changed = true;
+                }
+                Message::NavigationControlsToggled(shown) => {
+                    if !shown {
+                        self.steer(Vector2::new(0.0, 0.0), scheduler);
+                    }
+                }
+                Message::TargetLockToggled(locked) => {
+                    self.zoom_target =
+                        locked.then(|| fractal_view.point_at(Vector2::new(0.0, 0.0)));
+                    replies.push(Message::TargetLocked(self.zoom_target));
+                }
+                Message::Canvas(CanvasMessage::Hover(point)) => {
+                    fractal_view.set_julia_inset(
+                        point.map(|point| fractal_view.point_at(self.to_view_offset(point))),
+                    );
+                }
+                Message::Canvas(CanvasMessage::LockTarget(point)) => {
+                    if self.zoom_target.is_some() {
+                        self.zoom_target = Some(fractal_view.point_at(self.to_view_offset(point)));
+                        replies.push(Message::TargetLocked(self.zoom_target));
+                    }
+                }
+                Message::FractalTypeSelected(fractal_type) => {
+                    fractal_view.set_fractal_type(fractal_type);
+                    scheduler.invalidate(Change::Parameters);
+                    scheduler.invalidate(Change::View);
+                    replies.push(update_view_transform(fractal_view));
+                    self.fractal_type = fractal_type;
+                    self.modulation.retain_targets(fractal_type);
+                    modulation_changed = true;
+                    self.title_stale = true;
+                    histogram_stale = true;
+                }
+                Message::ParameterChanged(index, value) => {
+                    fractal_view.set_parameter(index, value);
+                    scheduler.invalidate(Change::Parameters);
+                }
+                Message::CriticalPointSelected(index) => {
+                    fractal_view.set_critical_point(index);
+                    scheduler.invalidate(Change::Parameters);
+                }
+                Message::PlaneChanged(plane) => {
+                    fractal_view.set_plane(plane);
+                    scheduler.invalidate(Change::Parameters);
+                    scheduler.invalidate(Change::View);
+                    replies.push(update_view_transform(fractal_view));
+                    histogram_stale = true;
+                }
+                Message::ExponentSweepChanged(enabled, speed) => {
+                    let definition = fractal_view.definition();
+                    self.modulation.sweep = definition
+                        .parameter_index(EXPONENT)
+                        .filter(|_| enabled)
+                        .map(|index| Sweep {
+                            index,
+                            name: EXPONENT,
+                            speed,
+                        });
+                    modulation_changed = true;
+                }
+                Message::VariationChanged(variation) => {
+                    fractal_view.set_variation(variation);
+                    scheduler.invalidate(Change::Parameters);
+                    // Nothing but spinning moves with the time, so there is no point in playing it
+                    // on.
+                    if !variation.spin && self.clock.is_playing() {
+                        replies.push(Message::TimePlayingToggled(false));
+                    }
+                }
+                Message::TimePlayingToggled(playing) => {
+                    if playing {
+                        self.clock.play(Instant::now());
+                        scheduler.start_animation(TIME_ANIMATION);
+                    } else {
+                        self.clock.pause(Instant::now());
+                        scheduler.stop_animation(TIME_ANIMATION);
+                    }
+                }
+                Message::Dive(target) => {
+                    let framing = framing_of(fractal_view);
+                    let minibrot = match target {
+                        DiveTarget::ViewCentre => None,
+                        DiveTarget::NearestMinibrot => locator::find_nearest(
+                            LandmarkKind::Center,
+                            framing.centre,
+                            framing.half_width,
+                        ),
+                    };
+                    let target = minibrot.map_or(framing.centre, |minibrot| minibrot.position);
+                    self.dive = Some(Dive::new(target));
+                    scheduler.start_animation(DIVE_ANIMATION);
+                }
+                Message::StopDive => {
+                    self.dive = None;
+                    scheduler.stop_animation(DIVE_ANIMATION);
+                }
+                Message::LocateLandmark(kind) => {
+                    let framing = framing_of(fractal_view);
+                    let landmark = locator::find_nearest(kind, framing.centre, framing.half_width);
+                    if let Some(landmark) = landmark {
+                        fractal_view.look_at_precisely(landmark.position, framing.half_width);
+                        view_changed = true;
+                    }
+                    replies.push(Message::LandmarkLocated(landmark));
+                }
+                Message::CancelJobs => {
+                    for job in self.jobs.drain(..) {
+                        job.cancel();
+                    }
+                    fractal_view.cancel_reference_orbit();
+                    self.completions.forget();
+                }
+                Message::FindMinibrot => {
+                    let job = minibrot::FindMinibrot {
+                        near: fractal_view.centre(),
+                        radius: framing_of(fractal_view).half_width,
+                    };
+                    self.jobs.push(jobs::spawn(job, Message::MinibrotFound));
+                }
+                Message::ZoomToMinibrot(minibrot) => {
+                    let (centre, half_width) = minibrot.framing();
+                    // Minibrots of high period may be smaller than the available numeric modes can
+                    // resolve, in which case the view zooms in as far as it can.
+                    let half_width = half_width.max(precision::min_half_width(
+                        centre,
+                        self.viewport.physical_width(),
+                        &precision::available_modes(fractal_view.definition()),
+                    ));
+                    fractal_view.look_at_precisely(centre, half_width);
+                    view_changed = true;
+                    replies.extend(raise_max_iterations(fractal_view, scheduler, half_width));
+                }
+                Message::EstimateDimension => {
+                    let estimate = self
+                        .analyzer
+                        .box_counting_dimension(gpu, Region::of_view(fractal_view));
+                    replies.push(Message::DimensionEstimated(estimate));
+                }
+                Message::EstimateArea(region) => {
+                    let sampled_region = match region {
+                        AreaRegion::View => Region::of_view(fractal_view),
+                        AreaRegion::WholeSet => Region::whole_set(fractal_view.max_iterations()),
+                    };
+                    let estimate =
+                        self.analyzer
+                            .estimate_area(gpu, sampled_region, AREA_RESOLUTION);
+                    replies.push(Message::AreaEstimated(region, estimate));
+                }
+                Message::MaxIterationsChanged(max_iterations) => {
+                    self.auto_iterations = false;
+                    fractal_view.set_max_iterations(max_iterations);
+                    scheduler.invalidate(Change::Parameters);
+                    histogram_stale = true;
+                }
+                Message::AutoIterationsToggled(auto_iterations) => {
+                    self.auto_iterations = auto_iterations;
+                    iterations_stale = true;
+                }
+                Message::AntialiasingToggled(antialiasing) => {
+                    fractal_view.set_antialiasing(gpu, antialiasing);
+                    scheduler.invalidate(Change::Parameters);
+                }
+                Message::BloomToggled(bloom) => {
+                    fractal_view.set_bloom(gpu, bloom);
+                    scheduler.invalidate(Change::Parameters);
+                }
+                Message::LowPowerToggled(low_power) => {
+                    fractal_view.set_low_power(gpu, low_power);
+                    scheduler.set_low_power(low_power);
+                    scheduler.invalidate(Change::Parameters);
+                }
+                Message::PresentModeSelected(present_mode) => {
+                    scheduler.set_present_mode(present_mode);
+                }
+                Message::FrameRateCapSelected(frame_rate_cap) => {
+                    scheduler.set_frame_rate_cap(frame_rate_cap);
+                }
+                Message::UiScaleSelected(ui_scale) => {
+                    self.ui_scale = ui_scale;
+                    scheduler.invalidate(Change::Window);
+                }
+                Message::UserConfigLoaded(config) => {
+                    let previous = self.user_config.replace((*config).clone());
+                    // The default fractal is switched to whenever it changes, except when the
+                    // explorer starts with the fractal of a restored session.
+                    let starting = previous.is_none();
+                    let previous = previous.unwrap_or_default();
+                    if config.default_fractal != previous.default_fractal
+                        && !(starting && self.session_restored)
+                    {
+                        replies.extend(config.default_fractal.map(Message::FractalTypeSelected));
+                    }
+                    if config.ui_scale != previous.ui_scale {
+                        replies.extend(config.ui_scale.map(Message::UiScaleSelected));
+                    }
+                }
+                Message::LayoutSelected(layout) => {
+                    self.set_layout(layout);
+                    scheduler.invalidate(Change::Window);
+                }
+                Message::PaletteSelected(palette) | Message::UndoGeneratedPalette(palette) => {
+                    fractal_view.set_palette(palette);
+                    scheduler.invalidate(Change::Parameters);
+                }
+                Message::RandomizePalette => {
+                    let seed = random_palette::fresh_seed();
+                    let palette = match fractal_view.palette() {
+                        Palette::Cosine(_) => {
+                            Palette::Cosine(random_palette::generate_cosine(seed))
+                        }
+                        _ => Palette::Custom(random_palette::generate(seed)),
+                    };
+                    fractal_view.set_palette(palette);
+                    scheduler.invalidate(Change::Parameters);
+                    replies.push(Message::PaletteGenerated(palette));
+                }
+                Message::CreateCosinePalette => {
+                    let palette = Palette::Cosine(CosinePalette::default());
+                    fractal_view.set_palette(palette);
+                    scheduler.invalidate(Change::Parameters);
+                    replies.push(Message::PaletteGenerated(palette));
+                }
+                Message::LoadPaletteImage => {
+                    #[cfg(not(target_arch = "wasm32"))]
+                    if let Some(path) = image_palette::choose_image(
+                        self.user_config
+                            .as_ref()
+                            .and_then(|config| config.palette_directory.as_deref()),
+                    ) {
+                        match image_palette::load(&path) {
+                            Ok(palette) => {
+                                let palette = Palette::Custom(palette);
+                                fractal_view.set_palette(palette);
+                                scheduler.invalidate(Change::Parameters);
+                                replies.push(Message::PaletteGenerated(palette));
+                            }
+                            Err(error) => {
+                                let name = path.file_name().unwrap_or_default().to_string_lossy();
+                                replies.push(Message::PaletteImageFailed(format!(
+                                    "Could not load {name}: {error}"
+                                )));
+                            }
+                        }
+                    }
+                }
+                Message::KeepPalette(palette) => {
+                    colour_palette::keep(&mut self.saved_palettes, palette);
+                }
+                Message::ForgetPalette(palette) => {
+                    self.saved_palettes.retain(|saved| *saved != palette);
+                }
+                Message::PostProcessingChanged(post_processing) => {
+                    fractal_view.set_post_processing(post_processing);
+                    scheduler.invalidate(Change::Parameters);
+                }
+                Message::CompositorChanged(compositor) => {
+                    fractal_view.set_compositor(compositor);
+                    scheduler.invalidate(Change::Parameters);
+                }
+                Message::DebugViewSelected(debug_view) => {
+                    fractal_view.set_debug_view(debug_view);
+                    scheduler.invalidate(Change::Parameters);
+                }
+                Message::SaveWorkspace(name, panels) => {
+                    workspace::save(
+                        &mut self.workspaces,
+                        Workspace {
+                            name,
+                            view: fractal_view.bookmark(),
+                            ui_scale: self.ui_scale,
+                            layout: self.layout,
+                            panels,
+                        },
+                    );
+                    replies.push(Message::WorkspacesChanged(workspace::names(
+                        &self.workspaces,
+                    )));
+                }
+                Message::WorkspaceSelected(name) => {
+                    let Some(workspace) = self
+                        .workspaces
+                        .iter()
+                        .find(|workspace| workspace.name == name)
+                        .cloned()
+                    else {
+                        continue;
+                    };
+                    fractal_view.apply_bookmark(&workspace.view);
+                    self.fractal_type = workspace.view.fractal_type;
+                    self.modulation.retain_targets(workspace.view.fractal_type);
+                    self.ui_scale = workspace.ui_scale;
+                    self.set_layout(workspace.layout);
+                    self.show_histogram = workspace.panels.histogram;
+                    if !workspace.panels.navigation {
+                        self.steer(Vector2::new(0.0, 0.0), scheduler);
+                    }
+                    scheduler.invalidate(Change::Parameters);
+                    scheduler.invalidate(Change::Window);
+                    modulation_changed = true;
+                    view_changed = true;
+                    histogram_stale = true;
+                    replies.push(Message::WorkspaceApplied(Box::new(workspace)));
+                }
+                Message::DeleteWorkspace(name) => {
+                    self.workspaces.retain(|workspace| workspace.name != name);
+                    replies.push(Message::WorkspacesChanged(workspace::names(
+                        &self.workspaces,
+                    )));
+                }
+                Message::HighContrastToggled(high_contrast) => {
+                    self.set_high_contrast(high_contrast);
+                }
+                Message::SeedEdited(text) => {
+                    if let Ok(seed) = text.trim().parse() {
+                        fractal_view.set_seed(seed);
+                        scheduler.invalidate(Change::Parameters);
+                    }
+                }
+                Message::VectorModeChanged(vector_mode, depth) => {
+                    fractal_view.set_vector_mode(gpu, vector_mode, depth);
+                    scheduler.invalidate(Change::Parameters);
+                    scheduler.invalidate(Change::View);
+                    replies.push(update_view_transform(fractal_view));
+                }
+                Message::ExportSvg => {
+                    #[cfg(not(target_arch = "wasm32"))]
+                    if let Some(path) =
+                        svg_export::choose_export_path(&fractal_view.vector_mode().to_string())
+                    {
+                        let name = path.file_name().unwrap_or_default().to_string_lossy();
+                        let size = self.viewport.physical_size();
+                        let result = svg_export::svg(
+                            fractal_view,
+                            size.width,
+                            size.height,
+                            SvgStroke::default(),
+                        )
+                        .ok_or_else(|| "No curve to export".to_string())
+                        .and_then(|svg| {
+                            std::fs::write(&path, svg).map_err(|error| error.to_string())
+                        })
+                        .map(|()| name.to_string())
+                        .map_err(|error| format!("Could not export {name}: {error}"));
+                        replies.push(Message::SvgExported(result));
+                    }
+                }
+                Message::ChaosGameChanged(playing, game) => {
+                    fractal_view.set_chaos_game(gpu, playing.then_some(game));
+                    scheduler.invalidate(Change::Parameters);
+                    scheduler.invalidate(Change::View);
+                    replies.push(update_view_transform(fractal_view));
+                }
+                Message::IsolinesEdited(text) => {
+                    fractal_view.set_isolines(gpu, isolines::parse_levels(&text));
+                    scheduler.invalidate(Change::Parameters);
+                }
+                Message::OrbitTrapChanged(trap) => {
+                    fractal_view.set_orbit_trap(trap);
+                    scheduler.invalidate(Change::Parameters);
+                }
+                Message::LoadTrapImage =>
+                {
+                    #[cfg(not(target_arch = "wasm32"))]
+                    if let Some(path) = orbit_trap::choose_image() {
+                        let name = path.file_name().unwrap_or_default().to_string_lossy();
+                        let result = match orbit_trap::load_image(&path) {
+                            Ok(image) => {
+                                fractal_view.set_trap_image(gpu, &image);
+                                scheduler.invalidate(Change::Parameters);
+                                Ok(name.into_owned())
+                            }
+                            Err(error) => Err(format!("Could not load {name}: {error}")),
+                        };
+                        replies.push(Message::TrapImageLoaded(result));
+                    }
+                }
+                Message::HistogramToggled(show) => {
+                    self.show_histogram = show;
+                    histogram_stale = true;
+                }
+                Message::CompareToggled(compare) => {
+                    fractal_view.set_comparison(compare.then(|| fractal_view.bookmark()));
+                    if compare {
+                        let centre = self.viewport.logical_size().width / 2.0;
+                        fractal_view.set_divider(0.5);
+                        replies.push(Message::Canvas(CanvasMessage::MoveDivider(centre)));
+                    }
+                    scheduler.invalidate(Change::Parameters);
+                }
+                Message::ExplorerGridToggled(shown) => {
+                    fractal_view.set_explorer_grid(shown);
+                    scheduler.invalidate(Change::Parameters);
+                }
+                Message::Canvas(CanvasMessage::PickCell(point)) => {
+                    let size = self.viewport.logical_size();
+                    let Some((grid, index)) = fractal_view
+                        .explorer_grid()
+                        .and_then(|grid| Some((grid, grid.cell_at(point, size)?)))
+                    else {
+                        continue;
+                    };
+                    for (axis, value) in grid.values(index) {
+                        match axis {
+                            GridAxis::Parameter(index) => {
+                                fractal_view.set_parameter(index, value);
+                                replies.push(Message::ParameterChanged(index, value));
+                            }
+                            GridAxis::MaxIterations => {
+                                self.auto_iterations = false;
+                                fractal_view.set_max_iterations(value as u32);
+                                histogram_stale = true;
+                                replies.push(Message::MaxIterationsChanged(value as u32));
+                            }
+                            GridAxis::Bailout => fractal_view.set_bailout(value),
+                        }
+                    }
+                    fractal_view.set_explorer_grid(false);
+                    scheduler.invalidate(Change::Parameters);
+                    replies.push(Message::ExplorerGridToggled(false));
+                }
+                Message::Canvas(CanvasMessage::MoveDivider(x)) => {
+                    fractal_view.set_divider(x / self.viewport.logical_size().width);
+                    scheduler.invalidate(Change::Parameters);
+                }
+                Message::Canvas(CanvasMessage::Probe(corner, opposite)) => {
+                    // Escape times are only defined for the Mandelbrot set.
+                    let statistics = (self.fractal_type == FractalType::Mandelbrot).then(|| {
+                        let to_view = |point: Point| 2.0 * self.to_view_offset(point);
+                        self.analyzer.region_statistics(
+                            gpu,
+                            Region::within_view(fractal_view, to_view(corner), to_view(opposite)),
+                        )
+                    });
+                    replies.push(Message::RegionProbed(statistics));
+                }
+                Message::SaveMorphEnd(end) => {
+                    let bookmark = Some(fractal_view.bookmark());
+                    match end {
+                        MorphEnd::Start => self.morph_start = bookmark,
+                        MorphEnd::End => self.morph_end = bookmark,
+                    }
+                }
+                Message::MorphChanged(t) => {
+                    let (Some(start), Some(end)) = (&self.morph_start, &self.morph_end) else {
+                        continue;
+                    };
+                    let bookmark = fractal_view.morph(start, end, t);
+                    if bookmark.fractal_type != self.fractal_type {
+                        self.fractal_type = bookmark.fractal_type;
+                        self.modulation.retain_targets(bookmark.fractal_type);
+                        modulation_changed = true;
+                    }
+                    scheduler.invalidate(Change::Parameters);
+                    view_changed = true;
+                    histogram_stale = true;
+                    replies.push(Message::MorphApplied(bookmark));
+                }
+                Message::HistoryBrowsed(index) => {
+                    let Some(entry) = self.history.entries().get(index) else {
+                        continue;
+                    };
+                    let bookmark = entry.bookmark.clone();
+                    fractal_view.apply_bookmark(&bookmark);
+                    self.history.skip(&bookmark);
+                    if bookmark.fractal_type != self.fractal_type {
+                        self.fractal_type = bookmark.fractal_type;
+                        self.modulation.retain_targets(bookmark.fractal_type);
+                        modulation_changed = true;
+                    }
+                    scheduler.invalidate(Change::Parameters);
+                    view_changed = true;
+                    histogram_stale = true;
+                    replies.push(Message::MorphApplied(bookmark));
+                }
+                Message::HistoryReplayToggled(replaying) => {
+                    if replaying {
+                        self.replay_started = Some(Instant::now());
+                        scheduler.start_animation(REPLAY_ANIMATION);
+                    } else {
+                        self.replay_started = None;
+                        scheduler.stop_animation(REPLAY_ANIMATION);
+                    }
+                }
+                Message::StartPresentation =>
+                {
+                    #[cfg(not(target_arch = "wasm32"))]
+                    if let Some(path) = presentation::choose_tour() {
+                        match presentation::load_tour(&path) {
+                            Ok(tour) => {
+                                let presentation = Presentation::new(
+                                    tour,
+                                    fractal_view.bookmark(),
+                                    Instant::now(),
+                                );
+                                replies.push(Message::PresentationChanged(Some(
+                                    presentation.status(),
+                                )));
+                                self.presentation = Some(presentation);
+                                scheduler.start_animation(PRESENTATION_ANIMATION);
+                            }
+                            Err(error) => {
+                                let name = path.file_name().unwrap_or_default().to_string_lossy();
+                                replies.push(Message::PresentationFailed(format!(
+                                    "Could not load {name}: {error}"
+                                )));
+                            }
+                        }
+                    }
+                }
+                Message::PresentationNext | Message::PresentationPrevious => {
+                    let Some(presentation) = &mut self.presentation else {
+                        continue;
+                    };
+                    let from = fractal_view.bookmark();
+                    let moved = if matches!(message, Message::PresentationNext) {
+                        presentation.next(from, Instant::now())
+                    } else {
+                        presentation.previous(from, Instant::now())
+                    };
+                    if moved {
+                        scheduler.start_animation(PRESENTATION_ANIMATION);
+                        replies.push(Message::PresentationChanged(Some(presentation.status())));
+                    }
+                }
+                Message::AddAnnotation(annotation) => {
+                    self.annotations.push(annotation);
+                }
+                Message::RemoveAnnotation => {
+                    self.annotations.pop();
+                }
+                Message::ClearAnnotations => {
+                    self.annotations.clear();
+                }
+                Message::StopPresentation => {
+                    self.presentation = None;
+                    scheduler.stop_animation(PRESENTATION_ANIMATION);
+                }
+                Message::ExportSnippet(language) => {
+                    #[cfg(not(target_arch = "wasm32"))]
+                    if let Some(path) =
+                        snippet::choose_export_path(fractal_view.bookmark().fractal_type, language)
+                    {
+                        let name = path.file_name().unwrap_or_default().to_string_lossy();
+                        let snippet = snippet::generate(
+                            fractal_view.bookmark().fractal_type,
+                            &fractal_view.parameter_block(),
+                            language,
+                        );
+                        let result = std::fs::write(&path, snippet)
+                            .map(|()| name.to_string())
+                            .map_err(|error| format!("Could not export {name}: {error}"));
+                        replies.push(Message::SnippetExported(result));
+                    }
+                }
+                Message::ExportHistory =>
+                {
+                    #[cfg(not(target_arch = "wasm32"))]
+                    if let Some(path) = history::choose_export_path() {
+                        let name = path.file_name().unwrap_or_default().to_string_lossy();
+                        let result = self
+                            .history
+                            .to_json()
+                            .map_err(|error| error.to_string())
+                            .and_then(|json| {
+                                std::fs::write(&path, json).map_err(|error| error.to_string())
+                            })
+                            .map(|()| name.to_string())
+                            .map_err(|error| format!("Could not export {name}: {error}"));
+                        replies.push(Message::HistoryExported(result));
+                    }
+                }
+                Message::ModulationChanged(slot, lfo) => {
+                    self.modulation.lfos[slot] = lfo;
+                    modulation_changed = true;
+                }
+                Message::SessionRestored(session) => {
+                    let Session {
+                        view,
+                        morph_start,
+                        morph_end,
+                        ui_scale,
+                        layout,
+                        high_contrast,
+                        workspaces,
+                        annotations,
+                        saved_palettes,
+                    } = *session;
+                    fractal_view.apply_bookmark(&view);
+                    self.fractal_type = view.fractal_type;
+                    self.modulation.retain_targets(view.fractal_type);
+                    self.morph_start = morph_start;
+                    self.morph_end = morph_end;
+                    self.ui_scale = ui_scale;
+                    self.set_layout(layout);
+                    self.set_high_contrast(high_contrast);
+                    self.workspaces = workspaces;
+                    self.annotations = annotations;
+                    self.saved_palettes = saved_palettes;
+                    self.session_restored = true;
+                    if let Some(ui_scale) =
+                        self.user_config.as_ref().and_then(|config| config.ui_scale)
+                    {
+                        replies.push(Message::UiScaleSelected(ui_scale));
+                    }
+                    scheduler.invalidate(Change::Parameters);
+                    scheduler.invalidate(Change::Window);
+                    modulation_changed = true;
+                    view_changed = true;
+                    histogram_stale = true;
+                }
+                Message::Canvas(CanvasMessage::UpdateViewTransform(_))
+                | Message::Canvas(CanvasMessage::Key(..))
+                | Message::Canvas(CanvasMessage::Annotate(..))
+                | Message::Canvas(CanvasMessage::Measure(_))
+                | Message::PaletteGenerated(_)
+                | Message::PaletteImageFailed(_)
+                | Message::UserConfigFailed(_)
+                | Message::MeasuringToggled(_)
+                | Message::LandmarkLocated(_)
+                | Message::MinibrotFound(_)
+                | Message::DimensionEstimated(_)
+                | Message::AreaEstimated(..)
+                | Message::HistogramComputed(_)
+                | Message::RegionProbed(_)
+                | Message::MaxIterationsApplied(_)
+                | Message::CompilingShader(_)
+                | Message::MorphApplied(_)
+                | Message::TrapImageLoaded(_)
+                | Message::HistoryRecorded(_)
+                | Message::HistoryExported(_)
+                | Message::SnippetExported(_)
+                | Message::SvgExported(_)
+                | Message::PresentationChanged(_)
+                | Message::PresentationFailed(_)
+                | Message::DiveEnded
+                | Message::PrecisionChanged(_)
+                | Message::GpuMemoryMeasured(_)
+                | Message::JobsProgressed(_)
+                | Message::PanelToggled(_)
+                | Message::WorkspaceNameEdited(_)
+                | Message::AnnotationTextEdited(_)
+                | Message::AnnotatingToggled(_)
+                | Message::WorkspacesChanged(_)
+                | Message::WorkspaceApplied(_)
+                | Message::TargetLocked(_)
+                | Message::AboutToggled => {}
+            }
+        }
+
+        if modulation_changed {
+            self.update_modulation(fractal_view, scheduler);
+        }
+        if view_changed {
+            scheduler.invalidate(Change::View);
+            self.title_stale = true;
+            replies.push(update_view_transform(fractal_view));
+        }
+        if self.auto_iterations && (view_changed || iterations_stale) {
+            self.iterations_stale_since = Some(Instant::now());
+            scheduler.start_animation(ANALYSIS_ANIMATION);
+        }
+        if self.shows_histogram() && (view_changed || histogram_stale) {
+            self.histogram_stale_since = Some(Instant::now());
+            scheduler.start_animation(ANALYSIS_ANIMATION);
+        }
+        replies
+    }
+
+    /// The offset of the given point in the window from the centre of the view, in units of the
+    /// size of the window.
+    fn to_view_offset(&self, point: Point) -> Vector2<f32> {
+        transform::from_screen(point, self.viewport.logical_size())
+    }
+
+    /// The offset from the centre of the view on which to zoom when asked to zoom on the given
+    /// point in the window, which is the locked zoom target if there is one.
+    fn zoom_offset(&self, fractal_view: &View, point: Point) -> Vector2<f32> {
+        match self.zoom_target {
+            Some(target) => fractal_view.offset_of(target),
+            None => self.to_view_offset(point),
+        }
+    }
+
+    /// Takes the state which an animation applied to the view as the current one, without recording
+    /// it in the history, and returns the messages which report it to the controls.
+    fn show_animated(
+        &mut self,
+        bookmark: Bookmark,
+        fractal_view: &mut View,
+        scheduler: &mut RenderScheduler,
+    ) -> Vec<Message> {
+        self.history.skip(&bookmark);
+        if bookmark.fractal_type != self.fractal_type {
+            self.fractal_type = bookmark.fractal_type;
+            self.modulation.retain_targets(bookmark.fractal_type);
+            self.update_modulation(fractal_view, scheduler);
+        }
+        scheduler.invalidate(Change::Parameters);
+        scheduler.invalidate(Change::View);
+        self.title_stale = true;
+        vec![
+            Message::MorphApplied(bookmark),
+            update_view_transform(fractal_view),
+        ]
+    }
+
+    /// Animates the modulation matrix while any of its LFOs is active, or else removes its offsets
+    /// from the view.
+    fn update_modulation(&self, fractal_view: &mut View, scheduler: &mut RenderScheduler) {
+        if self.modulation.is_active() {
+            scheduler.start_animation(MODULATION_ANIMATION);
+        } else {
+            scheduler.stop_animation(MODULATION_ANIMATION);
+            fractal_view.set_modulation(ModulationOffsets::default());
+            scheduler.invalidate(Change::Parameters);
+        }
+    }
+
+    /// Starts, changes or, at zero deflection, stops the panning by the joystick.
+    fn steer(&mut self, deflection: Vector2<f32>, scheduler: &mut RenderScheduler) {
+        if self.joystick == Vector2::new(0.0, 0.0) {
+            self.joystick_moved_at = Instant::now();
+        }
+        self.joystick = deflection;
+        if deflection == Vector2::new(0.0, 0.0) {
+            scheduler.stop_animation(JOYSTICK_ANIMATION);
+        } else {
+            scheduler.start_animation(JOYSTICK_ANIMATION);
+        }
+    }
+
+    fn set_layout(&mut self, layout: LayoutSetting) {
+        self.layout = layout;
+        self.touch_layout = layout.is_touch();
+    }
+
+    fn set_high_contrast(&mut self, high_contrast: bool) {
+        self.high_contrast = high_contrast;
+        self.theme = if high_contrast {
+            controls::high_contrast_theme()
+        } else {
+            Theme::Dark
+        };
+    }
+}
+
+/// Raises the maximum number of iterations to what a view of the given half width needs, returning
+/// the message which reports it if it was too low.
+fn raise_max_iterations(
+    fractal_view: &mut View,
+    scheduler: &mut RenderScheduler,
+    half_width: f64,
+) -> Option<Message> {
+    let max_iterations = iteration_budget::for_half_width(half_width);
+    (max_iterations > fractal_view.max_iterations()).then(|| {
+        fractal_view.set_max_iterations(max_iterations);
+        scheduler.invalidate(Change::Parameters);
+        Message::MaxIterationsApplied(max_iterations)
+    })
+}
+
+/// Tells the canvas where the view now is.
+fn update_view_transform(fractal_view: &View) -> Message {
+    Message::Canvas(CanvasMessage::UpdateViewTransform(fractal_view.transform()))
+}
+
+/// The part of the plane which the view shows.
+fn framing_of(fractal_view: &View) -> Framing {
+    Framing {
+        centre: fractal_view.centre(),
+        half_width: fractal_view.transform().half_width(),
+    }
+}
+
+/// Names the fractal and how far the view is zoomed in, e.g. "Mandelbrot — 1.4e9× —
+/// fractal_explorer".
+fn window_title(fractal_view: &View) -> String {
+    let magnification = fractal_view.magnification();
+    let magnification = if magnification < SCIENTIFIC_MAGNIFICATION {
+        format!("{magnification:.1}×")
+    } else {
+        format!("{magnification:.1e}×")
+    };
+    format!(
+        "{} — {magnification} — {}",
+        fractal_view.definition().name,
+        env!("CARGO_PKG_NAME")
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Controller, ANALYSIS_SETTLE_TIME, COMPILING_ANIMATION};
+    use crate::{
+        controls::{CanvasMessage, FractalType, LayoutSetting, Message, UiScale},
+        dive::DiveTarget,
+        fractal_view::View,
+        gpu::Gpu,
+        modulation::{Lfo, ModulationTarget},
+        presentation::{Presentation, TourStop},
+        render_scheduler::RenderScheduler,
+        workspace::VisiblePanels,
+    };
+    use cgmath::Vector2;
+    use googletest::prelude::*;
+    use iced::{time::Instant, Point, Size};
+    use iced_wgpu::graphics::Viewport;
+    use iced_winit::winit::dpi::PhysicalSize;
+    use std::time::Duration;
+
+    #[async_std::test]
+    async fn applies_every_pan_in_a_batch() -> Result<()> {
+        let gpu = Gpu::new_without_surface()?;
+        let mut view = View::new(&gpu);
+        let mut scheduler = RenderScheduler::default();
+        let mut controller = Controller::new(&gpu, PhysicalSize::new(100, 100));
+        let initial_translation = view.get_view_transform().z;
+
+        controller.handle_messages(
+            [
+                Message::Canvas(CanvasMessage::Pan(10.0, 0.0)),
+                Message::Canvas(CanvasMessage::Pan(10.0, 0.0)),
+            ],
+            &gpu,
+            &mut view,
+            &mut scheduler,
+        );
+
+        let mut single_pan_view = View::new(&gpu);
+        controller.handle_messages(
+            [Message::Canvas(CanvasMessage::Pan(20.0, 0.0))],
+            &gpu,
+            &mut single_pan_view,
+            &mut scheduler,
+        );
+        verify_that!(
+            initial_translation.x,
+            not(approx_eq(view.get_view_transform().z.x))
+        )?;
+        verify_that!(
+            view.get_view_transform().z.x,
+            approx_eq(single_pan_view.get_view_transform().z.x)
+        )
+    }
+
+    #[async_std::test]
+    async fn reports_view_transform_once_per_batch() -> Result<()> {
+        let gpu = Gpu::new_without_surface()?;
+        let mut view = View::new(&gpu);
+        let mut scheduler = RenderScheduler::default();
+        let mut controller = Controller::new(&gpu, PhysicalSize::new(100, 100));
+
+        let reply = controller.handle_messages(
+            [
+                Message::Canvas(CanvasMessage::Pan(10.0, 0.0)),
+                Message::Canvas(CanvasMessage::Pan(0.0, 10.0)),
+            ],
+            &gpu,
+            &mut view,
+            &mut scheduler,
+        );
+
+        verify_that!(
+            matches!(
+                reply[..],
+                [Message::Canvas(CanvasMessage::UpdateViewTransform(transform))]
+                    if transform == view.transform()
+            ),
+            eq(true)
+        )?;
+        verify_that!(scheduler.begin_frame().view, eq(true))
+    }
+
+    #[async_std::test]
+    async fn stops_spinner_once_pipeline_is_compiled() -> Result<()> {
+        let gpu = Gpu::new_without_surface()?;
+        let mut view = View::new(&gpu);
+        let mut scheduler = RenderScheduler::default();
+        let mut controller = Controller::new(&gpu, PhysicalSize::new(100, 100));
+        controller.handle_messages(
+            [Message::FractalTypeSelected(FractalType::Newton)],
+            &gpu,
+            &mut view,
+            &mut scheduler,
+        );
+        controller.compiling_since = Some(Instant::now());
+        scheduler.start_animation(COMPILING_ANIMATION);
+
+        view.wait_for_pipelines();
+        let reply = controller.poll_pipelines(&mut view, &mut scheduler);
+
+        verify_that!(
+            matches!(reply[..], [Message::CompilingShader(None)]),
+            eq(true)
+        )?;
+        verify_that!(scheduler.is_animating(), eq(false))
+    }
+
+    #[async_std::test]
+    async fn pan_follows_cursor_after_resize() -> Result<()> {
+        let gpu = Gpu::new_without_surface()?;
+        let mut view = View::new(&gpu);
+        let mut scheduler = RenderScheduler::default();
+        let mut controller = Controller::new(&gpu, PhysicalSize::new(100, 100));
+        // 200 by 100 logical pixels, across which the view spans 2 on both axes.
+        controller.set_viewport(&Viewport::with_physical_size(Size::new(400, 200), 2.0));
+        view.look_at(Vector2::new(-0.75, 0.0), 1.0);
+
+        controller.handle_messages(
+            [Message::Canvas(CanvasMessage::Pan(50.0, 20.0))],
+            &gpu,
+            &mut view,
+            &mut scheduler,
+        );
+
+        verify_that!(
+            (view.centre().x, view.centre().y),
+            (near(-0.25, 1e-6), near(0.4, 1e-6))
+        )
+    }
+
+    #[async_std::test]
+    async fn zoom_keeps_point_under_cursor_in_place() -> Result<()> {
+        let gpu = Gpu::new_without_surface()?;
+        let mut view = View::new(&gpu);
+        let mut scheduler = RenderScheduler::default();
+        let mut controller = Controller::new(&gpu, PhysicalSize::new(100, 100));
+        controller.set_viewport(&Viewport::with_physical_size(Size::new(300, 150), 1.5));
+        view.look_at(Vector2::new(-0.75, 0.1), 0.01);
+        // A quarter of the width right of the centre and a quarter of the height above it.
+        let cursor = Point::new(150.0, 25.0);
+        let under_cursor = view.point_at(Vector2::new(0.25, 0.25));
+
+        controller.handle_messages(
+            [Message::Canvas(CanvasMessage::Zoom(-20.0, cursor))],
+            &gpu,
+            &mut view,
+            &mut scheduler,
+        );
+        let offset = view.offset_of(under_cursor);
+
+        verify_that!(
+            (offset.x, offset.y, view.get_view_transform().x.x),
+            (near(0.25, 1e-4), near(0.25, 1e-4), near(0.005, 1e-9))
+        )
+    }
+
+    #[async_std::test]
+    async fn zooms_on_locked_target_wherever_cursor_is() -> Result<()> {
+        let gpu = Gpu::new_without_surface()?;
+        let mut view = View::new(&gpu);
+        let mut scheduler = RenderScheduler::default();
+        let mut controller = Controller::new(&gpu, PhysicalSize::new(100, 100));
+        view.look_at(Vector2::new(-0.75, 0.1), 0.01);
+
+        let replies = controller.handle_messages(
+            [
+                Message::TargetLockToggled(true),
+                Message::Canvas(CanvasMessage::LockTarget(Point::new(75.0, 50.0))),
+            ],
+            &gpu,
+            &mut view,
+            &mut scheduler,
+        );
+        let target = replies
+            .iter()
+            .rev()
+            .find_map(|reply| match reply {
+                Message::TargetLocked(target) => *target,
+                _ => None,
+            })
+            .unwrap();
+        controller.handle_messages(
+            [Message::Canvas(CanvasMessage::Zoom(-20.0, Point::ORIGIN))],
+            &gpu,
+            &mut view,
+            &mut scheduler,
+        );
+        let offset = view.offset_of(target);
+
+        verify_that!(
+            (target.x, target.y, offset.x, offset.y),
+            (
+                near(-0.745, 1e-6),
+                near(0.1, 1e-6),
+                near(0.25, 1e-4),
+                near(0.0, 1e-4)
+            )
+        )
+    }
+
+    #[async_std::test]
+    async fn updates_window_title_after_zoom() -> Result<()> {
+        let gpu = Gpu::new_without_surface()?;
+        let mut view = View::new(&gpu);
+        let mut scheduler = RenderScheduler::default();
+        let mut controller = Controller::new(&gpu, PhysicalSize::new(100, 100));
+        let initial_title = controller.take_window_title(&view);
+        view.look_at(Vector2::new(-0.75, 0.1), 2e-9);
+
+        controller.handle_messages(
+            [Message::Canvas(CanvasMessage::Zoom(0.0, Point::ORIGIN))],
+            &gpu,
+            &mut view,
+            &mut scheduler,
+        );
+
+        verify_that!(
+            (
+                initial_title,
+                controller.take_window_title(&view),
+                controller.take_window_title(&view)
+            ),
+            (
+                some(eq("Mandelbrot — 1.0× — fractal_explorer")),
+                some(eq("Mandelbrot — 1.0e9× — fractal_explorer")),
+                none()
+            )
+        )
+    }
+
+    #[async_std::test]
+    async fn changing_ui_scale_recreates_viewport() -> Result<()> {
+        let gpu = Gpu::new_without_surface()?;
+        let mut view = View::new(&gpu);
+        let mut scheduler = RenderScheduler::default();
+        let mut controller = Controller::new(&gpu, PhysicalSize::new(100, 100));
+
+        controller.handle_messages(
+            [Message::UiScaleSelected(UiScale(150))],
+            &gpu,
+            &mut view,
+            &mut scheduler,
+        );
+
+        verify_that!(
+            (controller.ui_scale(), scheduler.begin_frame().window),
+            (approx_eq(1.5), eq(true))
+        )
+    }
+
+    #[async_std::test]
+    async fn touch_layout_enlarges_controls() -> Result<()> {
+        let gpu = Gpu::new_without_surface()?;
+        let mut view = View::new(&gpu);
+        let mut scheduler = RenderScheduler::default();
+        let mut controller = Controller::new(&gpu, PhysicalSize::new(100, 100));
+
+        controller.handle_messages(
+            [
+                Message::UiScaleSelected(UiScale(150)),
+                Message::LayoutSelected(LayoutSetting::Touch),
+            ],
+            &gpu,
+            &mut view,
+            &mut scheduler,
+        );
+
+        verify_that!(
+            (controller.ui_scale(), scheduler.begin_frame().window),
+            (approx_eq(2.25), eq(true))
+        )
+    }
+
+    #[async_std::test]
+    async fn animates_while_modulation_is_active() -> Result<()> {
+        let gpu = Gpu::new_without_surface()?;
+        let mut view = View::new(&gpu);
+        let mut scheduler = RenderScheduler::default();
+        let mut controller = Controller::new(&gpu, PhysicalSize::new(100, 100));
+        let lfo = Lfo {
+            target: ModulationTarget::PaletteOffset,
+            ..Lfo::default()
+        };
+
+        controller.handle_messages(
+            [Message::ModulationChanged(0, lfo)],
+            &gpu,
+            &mut view,
+            &mut scheduler,
+        );
+        let animating_with_lfo = scheduler.is_animating();
+        controller.handle_messages(
+            [Message::FractalTypeSelected(FractalType::Newton)],
+            &gpu,
+            &mut view,
+            &mut scheduler,
+        );
+        let animating_after_switch = scheduler.is_animating();
+        controller.handle_messages(
+            [Message::ModulationChanged(0, Lfo::default())],
+            &gpu,
+            &mut view,
+            &mut scheduler,
+        );
+
+        verify_that!(
+            (
+                animating_with_lfo,
+                animating_after_switch,
+                scheduler.is_animating()
+            ),
+            (eq(true), eq(true), eq(false))
+        )
+    }
+
+    #[async_std::test]
+    async fn joystick_pans_view_until_released() -> Result<()> {
+        let gpu = Gpu::new_without_surface()?;
+        let mut view = View::new(&gpu);
+        let mut scheduler = RenderScheduler::default();
+        let mut controller = Controller::new(&gpu, PhysicalSize::new(100, 100));
+        let initial_centre = view.centre();
+
+        controller.handle_messages(
+            [Message::Canvas(CanvasMessage::Joystick(Vector2::new(
+                1.0, 0.0,
+            )))],
+            &gpu,
+            &mut view,
+            &mut scheduler,
+        );
+        std::thread::sleep(std::time::Duration::from_millis(50));
+        controller.advance_joystick(&mut view, &mut scheduler);
+        let animating_while_held = scheduler.is_animating();
+        let panned_centre = view.centre();
+        controller.handle_messages(
+            [Message::Canvas(CanvasMessage::Joystick(Vector2::new(
+                0.0, 0.0,
+            )))],
+            &gpu,
+            &mut view,
+            &mut scheduler,
+        );
+        let reply = controller.advance_joystick(&mut view, &mut scheduler);
+
+        verify_that!(
+            (
+                animating_while_held,
+                panned_centre.x,
+                panned_centre.y,
+                reply.is_none(),
+                scheduler.is_animating()
+            ),
+            (
+                eq(true),
+                gt(initial_centre.x),
+                approx_eq(initial_centre.y),
+                eq(true),
+                eq(false)
+            )
+        )
+    }
+
+    #[async_std::test]
+    async fn picking_cell_of_explorer_grid_adopts_its_settings() -> Result<()> {
+        let gpu = Gpu::new_without_surface()?;
+        let mut view = View::new(&gpu);
+        let mut scheduler = RenderScheduler::default();
+        let mut controller = Controller::new(&gpu, PhysicalSize::new(100, 100));
+        controller.handle_messages(
+            [
+                Message::FractalTypeSelected(FractalType::Multibrot),
+                Message::ExplorerGridToggled(true),
+            ],
+            &gpu,
+            &mut view,
+            &mut scheduler,
+        );
+        let shown = view.explorer_grid().is_some();
+
+        // The top right cell has the largest exponent.
+        let replies = controller.handle_messages(
+            [Message::Canvas(CanvasMessage::PickCell(Point::new(
+                90.0, 10.0,
+            )))],
+            &gpu,
+            &mut view,
+            &mut scheduler,
+        );
+        let adopted_exponent = replies.iter().find_map(|reply| match reply {
+            Message::ParameterChanged(0, value) => Some(*value),
+            _ => None,
+        });
+        let hidden = replies
+            .iter()
+            .any(|reply| matches!(reply, Message::ExplorerGridToggled(false)));
+
+        verify_that!(view.bookmark().parameter_values, eq(vec![8.0]))?;
+        verify_that!(
+            (
+                shown,
+                adopted_exponent,
+                hidden,
+                view.explorer_grid().is_none()
+            ),
+            (eq(true), some(approx_eq(8.0)), eq(true), eq(true))
+        )
+    }
+
+    #[async_std::test]
+    async fn dive_zooms_in_until_stopped() -> Result<()> {
+        let gpu = Gpu::new_without_surface()?;
+        let mut view = View::new(&gpu);
+        let mut scheduler = RenderScheduler::default();
+        let mut controller = Controller::new(&gpu, PhysicalSize::new(100, 100));
+        let initial_magnification = view.magnification();
+
+        controller.handle_messages(
+            [Message::Dive(DiveTarget::ViewCentre)],
+            &gpu,
+            &mut view,
+            &mut scheduler,
+        );
+        std::thread::sleep(std::time::Duration::from_millis(50));
+        controller.advance_dive(&mut view, &mut scheduler);
+        let animating_while_diving = scheduler.is_animating();
+        let dived_magnification = view.magnification();
+        controller.handle_messages([Message::StopDive], &gpu, &mut view, &mut scheduler);
+        controller.advance_dive(&mut view, &mut scheduler);
+
+        verify_that!(
+            (
+                animating_while_diving,
+                dived_magnification,
+                view.magnification(),
+                scheduler.is_animating()
+            ),
+            (
+                eq(true),
+                gt(initial_magnification),
+                eq(dived_magnification),
+                eq(false)
+            )
+        )
+    }
+
+    #[async_std::test]
+    async fn dive_keeps_target_beyond_precision_of_f32() -> Result<()> {
+        let gpu = Gpu::new_without_surface()?;
+        let mut view = View::new(&gpu);
+        let mut scheduler = RenderScheduler::default();
+        let mut controller = Controller::new(&gpu, PhysicalSize::new(100, 100));
+        let target = Vector2::new(-0.75 + 3e-12, 0.1 - 2e-12);
+        view.look_at_precisely(target, 1e-10);
+
+        controller.handle_messages(
+            [Message::Dive(DiveTarget::ViewCentre)],
+            &gpu,
+            &mut view,
+            &mut scheduler,
+        );
+        std::thread::sleep(std::time::Duration::from_millis(50));
+        controller.advance_dive(&mut view, &mut scheduler);
+
+        verify_that!(
+            (view.centre().x, view.centre().y),
+            (near(target.x, 1e-16), near(target.y, 1e-16))
+        )
+    }
+
+    #[async_std::test]
+    async fn iteration_budget_is_tuned_once_view_rests() -> Result<()> {
+        let gpu = Gpu::new_without_surface()?;
+        let mut view = View::new(&gpu);
+        let mut scheduler = RenderScheduler::default();
+        let mut controller = Controller::new(&gpu, PhysicalSize::new(100, 100));
+        view.look_at(Vector2::new(-0.75, 0.1), 1e-4);
+        let initial_iterations = view.max_iterations();
+
+        let replies = controller.handle_messages(
+            [
+                Message::AutoIterationsToggled(true),
+                Message::Canvas(CanvasMessage::Pan(10.0, 0.0)),
+            ],
+            &gpu,
+            &mut view,
+            &mut scheduler,
+        );
+        let changed = Instant::now();
+        let while_moving = controller.update_analysis(&gpu, &mut view, &mut scheduler, changed);
+        let waiting = scheduler.is_animating();
+        let tuned = controller
+            .update_analysis(
+                &gpu,
+                &mut view,
+                &mut scheduler,
+                changed + ANALYSIS_SETTLE_TIME,
+            )
+            .into_iter()
+            .find_map(|reply| match reply {
+                Message::MaxIterationsApplied(max_iterations) => Some(max_iterations),
+                _ => None,
+            });
+
+        verify_that!(
+            (
+                replies
+                    .iter()
+                    .any(|reply| matches!(reply, Message::MaxIterationsApplied(_))),
+                while_moving.len(),
+                waiting,
+                tuned,
+                scheduler.is_animating()
+            ),
+            (
+                eq(false),
+                eq(0),
+                eq(true),
+                some(gt(initial_iterations)),
+                eq(false)
+            )
+        )
+    }
+
+    #[async_std::test]
+    async fn histogram_is_computed_once_view_rests() -> Result<()> {
+        let gpu = Gpu::new_without_surface()?;
+        let mut view = View::new(&gpu);
+        let mut scheduler = RenderScheduler::default();
+        let mut controller = Controller::new(&gpu, PhysicalSize::new(100, 100));
+        let is_histogram = |reply: &Message| matches!(reply, Message::HistogramComputed(_));
+
+        let replies = controller.handle_messages(
+            [
+                Message::HistogramToggled(true),
+                Message::Canvas(CanvasMessage::Pan(10.0, 0.0)),
+            ],
+            &gpu,
+            &mut view,
+            &mut scheduler,
+        );
+        let changed = Instant::now();
+        let while_moving = controller.update_analysis(&gpu, &mut view, &mut scheduler, changed);
+        let at_rest = controller.update_analysis(
+            &gpu,
+            &mut view,
+            &mut scheduler,
+            changed + ANALYSIS_SETTLE_TIME,
+        );
+
+        verify_that!(
+            (
+                replies.iter().any(is_histogram),
+                while_moving.iter().any(is_histogram),
+                at_rest.iter().filter(|reply| is_histogram(reply)).count(),
+                scheduler.is_animating()
+            ),
+            (eq(false), eq(false), eq(1), eq(false))
+        )
+    }
+
+    #[async_std::test]
+    async fn browsing_history_shows_entry_without_recording_it() -> Result<()> {
+        let gpu = Gpu::new_without_surface()?;
+        let mut view = View::new(&gpu);
+        let mut scheduler = RenderScheduler::default();
+        let mut controller = Controller::new(&gpu, PhysicalSize::new(100, 100));
+        let initial = view.bookmark();
+        let start = Instant::now();
+        controller.history.observe(&initial, start);
+        view.look_at(Vector2::new(-0.75, 0.1), 0.01);
+        controller
+            .history
+            .observe(&view.bookmark(), start + Duration::from_secs(1));
+
+        let replies = controller.handle_messages(
+            [Message::HistoryBrowsed(0)],
+            &gpu,
+            &mut view,
+            &mut scheduler,
+        );
+        let recorded = controller.record_history(&view);
+
+        verify_that!(view.bookmark(), eq(initial.clone()))?;
+        verify_that!(
+            (
+                replies.iter().any(
+                    |reply| matches!(reply, Message::MorphApplied(bookmark) if *bookmark == initial)
+                ),
+                recorded.is_none(),
+                controller.history.entries().len()
+            ),
+            (eq(true), eq(true), eq(2))
+        )
+    }
+
+    #[async_std::test]
+    async fn switching_workspace_restores_its_view_and_layout() -> Result<()> {
+        let gpu = Gpu::new_without_surface()?;
+        let mut view = View::new(&gpu);
+        let mut scheduler = RenderScheduler::default();
+        let mut controller = Controller::new(&gpu, PhysicalSize::new(100, 100));
+        let panels = VisiblePanels {
+            histogram: true,
+            ..Default::default()
+        };
+        controller.handle_messages(
+            [
+                Message::FractalTypeSelected(FractalType::Multibrot),
+                Message::LayoutSelected(LayoutSetting::Touch),
+                Message::SaveWorkspace("Multibrot".into(), panels),
+                Message::FractalTypeSelected(FractalType::Mandelbrot),
+                Message::LayoutSelected(LayoutSetting::Desktop),
+            ],
+            &gpu,
+            &mut view,
+            &mut scheduler,
+        );
+
+        let replies = controller.handle_messages(
+            [Message::WorkspaceSelected("Multibrot".into())],
+            &gpu,
+            &mut view,
+            &mut scheduler,
+        );
+
+        verify_that!(
+            (
+                view.bookmark().fractal_type,
+                controller.layout,
+                controller.show_histogram,
+                replies
+                    .iter()
+                    .any(|reply| matches!(reply, Message::WorkspaceApplied(workspace) if workspace.panels == panels))
+            ),
+            (
+                eq(FractalType::Multibrot),
+                eq(LayoutSetting::Touch),
+                eq(true),
+                eq(true)
+            )
+        )
+    }
+
+    #[async_std::test]
+    async fn next_stop_morphs_towards_it_and_reports_it() -> Result<()> {
+        let gpu = Gpu::new_without_surface()?;
+        let mut view = View::new(&gpu);
+        let mut scheduler = RenderScheduler::default();
+        let mut controller = Controller::new(&gpu, PhysicalSize::new(100, 100));
+        let initial = view.bookmark();
+        view.look_at(Vector2::new(-0.75, 0.1), 0.01);
+        let tour = [initial.clone(), view.bookmark()]
+            .into_iter()
+            .map(|bookmark| TourStop {
+                bookmark,
+                caption: Some("Seahorse valley".into()),
+                transition_seconds: 0.0,
+                hold_seconds: None,
+            })
+            .collect();
+        controller.presentation = Some(Presentation::new(tour, initial, Instant::now()));
+        controller.advance_presentation(&mut view, &mut scheduler);
+
+        let replies = controller.handle_messages(
+            [Message::PresentationNext],
+            &gpu,
+            &mut view,
+            &mut scheduler,
+        );
+        controller.advance_presentation(&mut view, &mut scheduler);
+
+        verify_that!(
+            (
+                view.bookmark().view_transform.x.x,
+                replies.iter().any(|reply| matches!(
+                    reply,
+                    Message::PresentationChanged(Some(status)) if status.stop == 1
+                ))
+            ),
+            (eq(0.01), eq(true))
+        )
+    }
+}
@@ -1,106 +1,3091 @@
-use cgmath::{Matrix3, Vector2, Vector3};
+use crate::{
+    analysis::{AreaEstimate, AreaRegion, DimensionEstimate, IterationHistogram, RegionStatistics},
+    annotation::{self, Annotation},
+    bookmark::{Bookmark, MorphEnd},
+    chaos_game::{self, ChaosGame},
+    colour_palette::{self, Palette},
+    compositor::{BlendMode, Compositor, Layer},
+    cosine_palette::{Coefficient, CosinePalette},
+    critical_points::{self, CriticalPoint},
+    debug_view::DebugView,
+    dive::DiveTarget,
+    explorer_grid::ExplorerGrid,
+    fractal_view::DEFAULT_MAX_ITERATIONS,
+    gpu_memory::GpuMemoryUsage,
+    iteration_budget::{MAX_ITERATIONS, MIN_ITERATIONS},
+    jobs::JobStatus,
+    locator::{Landmark, LandmarkKind},
+    measurement::Measurement,
+    minibrot::Minibrot,
+    modulation::{Lfo, ModulationMatrix, ModulationTarget, Waveform},
+    navigation::{NavigationButton, NavigationLayout, Target},
+    orbit_trap::OrbitTrap,
+    plane::{self, Plane},
+    post_processing::{Adjustment, PostProcessing, Tonemapper},
+    precision::{NumericMode, Precision},
+    presentation::PresentationStatus,
+    render_scheduler::{FrameRateCap, PresentMode},
+    session::Session,
+    shortcuts::{Direction, KeyAction, Keymap},
+    snippet::SnippetLanguage,
+    throughput,
+    transform::{self, ViewTransform},
+    user_config::UserConfig,
+    variation::{Variation, VariationFlag},
+    vector_mode::VectorMode,
+    workspace::{VisiblePanels, Workspace},
+};
+use cgmath::{InnerSpace, Matrix3, Vector2};
 use iced::{
+    keyboard,
     mouse::{self, Button, Cursor, ScrollDelta},
-    widget::{pick_list, Row},
-    Color, Length, Point, Rectangle,
+    touch::{self, Finger},
+    widget::{
+        button, checkbox, container, pick_list, scrollable, slider, text, text_input, Column, Row,
+    },
+    Border, Color, Length, Point, Radians, Rectangle, Size, Vector,
 };
+use iced_wgpu::wgpu;
 use iced_widget::{
-    canvas::{self, event::Status, Event, Frame, Geometry, Text},
+    canvas::{self, event::Status, path, Event, Frame, Geometry, Path, Stroke, Text},
     Canvas,
 };
 use iced_winit::{core::Element, runtime::Program, style::Theme};
-use std::{cell::Cell, fmt::Display};
+use serde::{Deserialize, Serialize};
+use std::{cell::Cell, f32::consts::TAU, fmt::Display, str::FromStr};
 
 pub(super) struct Controls {
     canvas: FractalCanvas,
     current_type: FractalType,
-    last_message: Cell<Option<Message>>,
+    located_landmark: Option<Option<Landmark>>,
+    /// The result of the last [`Message::FindMinibrot`].
+    found_minibrot: Option<Option<Minibrot>>,
+    dimension_estimate: Option<Option<DimensionEstimate>>,
+    area_estimate: Option<(AreaRegion, AreaEstimate)>,
+    show_histogram: bool,
+    histogram: Option<IterationHistogram>,
+    /// The iteration counts at which isolines are drawn, as typed.
+    isolines: String,
+    /// The seed of everything random in the view, as typed.
+    seed: String,
+    trap: OrbitTrap,
+    /// The name of the last image loaded into the orbit trap, or why it could not be loaded.
+    trap_image: Option<Result<String, String>>,
+    max_iterations: u32,
+    auto_iterations: bool,
+    parameter_values: Vec<f32>,
+    /// The index of the critical point from which the orbits of a rational map start.
+    critical_point: usize,
+    plane: Plane,
+    variation: Variation,
+    time_playing: bool,
+    /// Whether the exponent of the Multibrot set is animated, and how many times per second it
+    /// sweeps its range there and back.
+    exponent_sweep: bool,
+    sweep_speed: f32,
+    /// The geometric curve drawn instead of the fractal, and the depth to which it recurses.
+    vector_mode: VectorMode,
+    curve_depth: u32,
+    /// Whether the chaos game is played instead of rendering the fractal, and its settings, which
+    /// are kept while it is not.
+    chaos_game_playing: bool,
+    chaos_game: ChaosGame,
+    compiling_shader: Option<f32>,
+    antialiasing: bool,
+    low_power: bool,
+    bloom: bool,
+    present_mode: PresentMode,
+    frame_rate_cap: FrameRateCap,
+    ui_scale: UiScale,
+    /// Why the configuration file could not be applied when it last changed.
+    config_error: Option<String>,
+    layout: LayoutSetting,
+    /// The panel which the touch layout shows above its toolbar, if any.
+    open_panel: Option<Panel>,
+    /// The name under which the workspace is saved, as typed.
+    workspace_name: String,
+    /// The names of the saved workspaces, and the one switched to last.
+    workspaces: Vec<String>,
+    workspace: Option<String>,
+    palette: Palette,
+    /// The palettes which generated ones replaced, the last most recently.
+    replaced_palettes: Vec<Palette>,
+    /// The palettes kept from the randomizer or images, which are offered after the built-in ones.
+    saved_palettes: Vec<Palette>,
+    /// Why the last image could not be made into a palette.
+    palette_image_error: Option<String>,
+    post_processing: PostProcessing,
+    compositor: Compositor,
+    debug_view: DebugView,
+    high_contrast: bool,
+    modulation: ModulationMatrix,
+    /// Whether the settings at the time compare mode was turned on are shown right of the
+    /// divider.
+    compare: bool,
+    /// The text of the next note placed on the view.
+    annotation_text: String,
+    /// Whether the start and end of the morph have been saved.
+    morph_saved: (bool, bool),
+    /// How far the view is morphed from the start to the end.
+    morph: f32,
+    /// The number of changes recorded in the history, and the index of the one browsed to last.
+    history_len: usize,
+    history_position: usize,
+    replaying_history: bool,
+    /// The name of the file to which the history was exported last, or why it could not be.
+    history_export: Option<Result<String, String>>,
+    /// The name of the file to which a snippet was exported last, or why it could not be.
+    snippet_export: Option<Result<String, String>>,
+    /// The name of the file to which the curve was exported last as SVG, or why it could not be.
+    svg_export: Option<Result<String, String>>,
+    /// Where the presentation in progress is, during which the view fills the window.
+    presentation: Option<PresentationStatus>,
+    /// Why the last tour could not be presented.
+    presentation_error: Option<String>,
+    diving: bool,
+    /// Whether the last dive stopped by itself at the limit of precision.
+    dive_ended: bool,
+    /// The control which keyboard input operates, or `None` if it pans and zooms the view.
+    focus: Option<Focusable>,
+    /// The text of the About panel, or `None` while it is hidden.
+    about: Option<String>,
+    adapter_info: wgpu::AdapterInfo,
+    pending_messages: Cell<Vec<Message>>,
+}
+
+#[derive(Debug, Clone)]
+pub(super) enum Message {
+    Canvas(CanvasMessage),
+    FractalTypeSelected(FractalType),
+    /// Sets the parameter with the given index in the current fractal's definition.
+    ParameterChanged(usize, f32),
+    /// Starts the orbits of a rational map from the critical point with the given index.
+    CriticalPointSelected(usize),
+    /// Shows the given plane of escape-time fractals.
+    PlaneChanged(Plane),
+    VariationChanged(Variation),
+    /// Plays or pauses the time, by which the spinning variation rotates.
+    TimePlayingToggled(bool),
+    /// Turns the animation of the Multibrot set's exponent on or off, with the given number of
+    /// sweeps per second.
+    ExponentSweepChanged(bool, f32),
+    /// Draws the curve of the vector mode to the given depth instead of the fractal.
+    VectorModeChanged(VectorMode, u32),
+    /// Asks where to export the curve of the vector mode as SVG.
+    ExportSvg,
+    /// The name of the file to which the curve was exported after [`Message::ExportSvg`], or why
+    /// it could not be.
+    SvgExported(Result<String, String>),
+    /// Plays the chaos game with the given settings instead of rendering the fractal, or stops it.
+    ChaosGameChanged(bool, ChaosGame),
+    LocateLandmark(LandmarkKind),
+    /// The result of the last [`Message::LocateLandmark`], or `None` if nothing was found.
+    LandmarkLocated(Option<Landmark>),
+    FindMinibrot,
+    /// The result of the last [`Message::FindMinibrot`], or `None` if there is no minibrot in
+    /// view.
+    MinibrotFound(Option<Minibrot>),
+    /// Frames the view on the given minibrot.
+    ZoomToMinibrot(Minibrot),
+    EstimateDimension,
+    /// The result of the last [`Message::EstimateDimension`], or `None` if there is no boundary
+    /// in view.
+    DimensionEstimated(Option<DimensionEstimate>),
+    EstimateArea(AreaRegion),
+    AreaEstimated(AreaRegion, AreaEstimate),
+    HistogramToggled(bool),
+    /// The histogram of the current view, sent whenever the view changes while the histogram is
+    /// shown.
+    HistogramComputed(IterationHistogram),
+    /// Draws isolines at the iteration counts in the given list.
+    IsolinesEdited(String),
+    OrbitTrapChanged(OrbitTrap),
+    /// Asks for an image with which to texture the orbit trap.
+    LoadTrapImage,
+    /// The name of the image loaded after [`Message::LoadTrapImage`], or why it could not be
+    /// loaded.
+    TrapImageLoaded(Result<String, String>),
+    /// The statistics of the region of the last [`CanvasMessage::Probe`], or `None` if they are
+    /// not defined for the current fractal.
+    RegionProbed(Option<RegionStatistics>),
+    MaxIterationsChanged(u32),
+    AutoIterationsToggled(bool),
+    /// The iteration budget chosen while [`Message::AutoIterationsToggled`] is on.
+    MaxIterationsApplied(u32),
+    /// The phase of the spinner in turns while the current fractal's pipeline is compiling, or
+    /// `None` once it is ready.
+    CompilingShader(Option<f32>),
+    AntialiasingToggled(bool),
+    /// Seeds the samples of anti-aliasing and the noise of the modulation with the number typed,
+    /// once it is one.
+    SeedEdited(String),
+    LowPowerToggled(bool),
+    BloomToggled(bool),
+    PresentModeSelected(PresentMode),
+    FrameRateCapSelected(FrameRateCap),
+    UiScaleSelected(UiScale),
+    LayoutSelected(LayoutSetting),
+    /// Shows or hides the joystick and buttons with which the view can be navigated without a
+    /// mouse wheel.
+    NavigationControlsToggled(bool),
+    /// Locks the target of all zooms to the centre of the view, which a right click then moves,
+    /// or unlocks it.
+    TargetLockToggled(bool),
+    /// The point of the plane on which all zooms centre, whenever it is locked or moved, or
+    /// `None` once it is unlocked.
+    TargetLocked(Option<Vector2<f64>>),
+    /// Opens the given panel of the touch layout, or closes it if it is open.
+    PanelToggled(Panel),
+    WorkspaceNameEdited(String),
+    /// Saves the layout and visible panels of the controls, the fractal and the view as a
+    /// workspace of the given name, replacing any of the same name.
+    SaveWorkspace(String, VisiblePanels),
+    /// Switches to the workspace of the given name.
+    WorkspaceSelected(String),
+    DeleteWorkspace(String),
+    /// The names of the saved workspaces, whenever one is saved or deleted.
+    WorkspacesChanged(Vec<String>),
+    /// The workspace switched to after [`Message::WorkspaceSelected`].
+    WorkspaceApplied(Box<Workspace>),
+    PaletteSelected(Palette),
+    /// Replaces the palette with a harmonious one generated at random, which is a cosine palette
+    /// if the current one is.
+    RandomizePalette,
+    /// Replaces the palette with a cosine palette to edit, starting from Quilez's rainbow.
+    CreateCosinePalette,
+    /// Asks for an image from which to extract a palette.
+    LoadPaletteImage,
+    /// The palette generated after [`Message::RandomizePalette`], [`Message::CreateCosinePalette`]
+    /// or [`Message::LoadPaletteImage`].
+    PaletteGenerated(Palette),
+    /// Why the image chosen after [`Message::LoadPaletteImage`] could not be made into a palette.
+    PaletteImageFailed(String),
+    /// Returns to the given palette, which the last generated one replaced.
+    UndoGeneratedPalette(Palette),
+    /// Adds the palette to the saved palettes.
+    KeepPalette(Palette),
+    /// Removes the palette from the saved palettes.
+    ForgetPalette(Palette),
+    PostProcessingChanged(PostProcessing),
+    /// Shows one of the quantities behind the colouring instead of the palette.
+    DebugViewSelected(DebugView),
+    /// Colours escape-time fractals by two layers with the given compositor.
+    CompositorChanged(Compositor),
+    /// Asks where to export the current fractal and colouring as a standalone shader in the given
+    /// language.
+    ExportSnippet(SnippetLanguage),
+    /// The name of the file to which the snippet was exported after [`Message::ExportSnippet`], or
+    /// why it could not be.
+    SnippetExported(Result<String, String>),
+    /// Switches between the dark theme and one with more contrast.
+    HighContrastToggled(bool),
+    /// Replaces the LFO in the given slot of the modulation matrix.
+    ModulationChanged(usize, Lfo),
+    /// Freezes the current settings, so that they are shown right of a divider while the
+    /// settings are changed, or ends the comparison.
+    CompareToggled(bool),
+    /// Replaces the view with a grid of thumbnails which vary one or two of its settings, a click
+    /// on which adopts them, or returns to the view.
+    ExplorerGridToggled(bool),
+    /// Turns on or off measuring between two points clicked on the view.
+    MeasuringToggled(bool),
+    AnnotationTextEdited(String),
+    /// Turns on or off placing notes with the mouse, which otherwise pans the view.
+    AnnotatingToggled(bool),
+    AddAnnotation(Annotation),
+    /// Removes the note placed last.
+    RemoveAnnotation,
+    ClearAnnotations,
+    /// Saves the current state of the view as one end of the morph.
+    SaveMorphEnd(MorphEnd),
+    /// Shows the state the given fraction of the way from the start of the morph to its end.
+    MorphChanged(f32),
+    /// The state shown after the last [`Message::MorphChanged`], or the state of the history shown
+    /// after [`Message::HistoryBrowsed`] or during its replay.
+    MorphApplied(Bookmark),
+    /// The number of changes in the history, whenever one is recorded.
+    HistoryRecorded(usize),
+    /// Shows the state after the change with the given index in the history.
+    HistoryBrowsed(usize),
+    /// Starts morphing through the history from its first change to its last, or stops.
+    HistoryReplayToggled(bool),
+    /// Asks where to export the history as JSON.
+    ExportHistory,
+    /// The name of the file to which the history was exported after [`Message::ExportHistory`],
+    /// or why it could not be.
+    HistoryExported(Result<String, String>),
+    /// Asks for a tour and presents it, morphing from stop to stop with the view filling the
+    /// window.
+    StartPresentation,
+    PresentationNext,
+    PresentationPrevious,
+    StopPresentation,
+    /// Where the presentation is, whenever it starts or moves to another stop.
+    PresentationChanged(Option<PresentationStatus>),
+    /// Why the tour asked for after [`Message::StartPresentation`] could not be presented.
+    PresentationFailed(String),
+    /// Zooms in continuously on the given target until [`Message::StopDive`].
+    Dive(DiveTarget),
+    StopDive,
+    /// The dive stopped by itself, since the view reached the limit of precision.
+    DiveEnded,
+    /// The view was zoomed or resized, or switched to another numeric mode.
+    PrecisionChanged(Precision),
+    /// The GPU memory held by the view, whenever it changes by a mebibyte or more.
+    GpuMemoryMeasured(GpuMemoryUsage),
+    /// The jobs running on the worker pool, whenever one starts, progresses or finishes.
+    JobsProgressed(Vec<JobStatus>),
+    /// Stops the running jobs, discarding what they would have found.
+    CancelJobs,
+    /// The session stored by an earlier run, once it has loaded.
+    SessionRestored(Box<Session>),
+    /// The configuration file, whenever it was loaded after a change.
+    UserConfigLoaded(Box<UserConfig>),
+    /// Why the configuration file could not be loaded after a change, in which case the settings
+    /// loaded before stay.
+    UserConfigFailed(String),
+    AboutToggled,
+}
+
+/// A control in the sidebar which can be operated with the keyboard.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Focusable {
+    FractalType,
+    VectorMode,
+    CurveDepth,
+    ExportSvg,
+    ChaosGame,
+    ChaosGameVertices,
+    ChaosGameJumpRatio,
+    Antialiasing,
+    LowPower,
+    Bloom,
+    PresentMode,
+    FrameRateCap,
+    UiScale,
+    Layout,
+    NavigationControls,
+    TargetLock,
+    HighContrast,
+    SaveWorkspace,
+    Workspace,
+    DeleteWorkspace,
+    Palette,
+    RandomPalette,
+    CosinePalette,
+    UndoGeneratedPalette,
+    KeepPalette,
+    /// The slider of the given channel of a coefficient of a cosine palette.
+    CosineCoefficient(Coefficient, usize),
+    PostProcessing(Adjustment),
+    Tonemapper,
+    DebugView,
+    BaseLayer,
+    OverlayLayer,
+    BlendMode,
+    OverlayOpacity,
+    ExportSnippet(SnippetLanguage),
+    Parameter(usize),
+    CriticalPoint,
+    DynamicPlane,
+    FixedCReal,
+    FixedCImaginary,
+    Variation(VariationFlag),
+    PlayTime,
+    ExponentSweep,
+    SweepSpeed,
+    MaxIterations,
+    AutoIterations,
+    Landmark(LandmarkKind),
+    FindMinibrot,
+    ZoomToMinibrot,
+    EstimateDimension,
+    EstimateArea(AreaRegion),
+    Histogram,
+    Compare,
+    ExplorerGrid,
+    Measure,
+    Annotate,
+    RemoveAnnotation,
+    ClearAnnotations,
+    SaveMorph(MorphEnd),
+    Morph,
+    History,
+    ReplayHistory,
+    ExportHistory,
+    StartPresentation,
+    Dive(DiveTarget),
+    StopDive,
+    ModulationTarget(usize),
+    About,
+    CancelJobs,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum FractalType {
+    Mandelbrot,
+    Newton,
+    /// Newton's method with each step shortened by a relaxation factor.
+    DampedNewton,
+    /// The secant method, which steps along the slope through the last two points.
+    Secant,
+    /// Iterates `z^d + c` with a real exponent `d`.
+    Multibrot,
+    /// Iterates `p(z) / q(z) + c` with polynomials `p` and `q` of at most the third degree.
+    Rational,
+    /// Fractional Brownian motion of gradient noise, coloured like plasma.
+    Plasma,
+    /// A heightmap of ridged multifractal noise, tinted by height and hillshaded.
+    Terrain,
+}
+
+impl FractalType {
+    pub(super) const ALL: [FractalType; 8] = [
+        Self::Mandelbrot,
+        Self::Newton,
+        Self::DampedNewton,
+        Self::Secant,
+        Self::Multibrot,
+        Self::Rational,
+        Self::Plasma,
+        Self::Terrain,
+    ];
+
+    /// Whether the fractal is drawn by how fast points escape, so that its bailout matters.
+    pub(super) fn escapes(self) -> bool {
+        matches!(self, Self::Mandelbrot | Self::Multibrot | Self::Rational)
+    }
+}
+
+/// The size of the controls relative to the scale factor of the display, in percent.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub(super) struct UiScale(pub(super) u32);
+
+impl UiScale {
+    pub(super) const ALL: [Self; 5] = [Self(75), Self(100), Self(125), Self(150), Self(200)];
+
+    pub(super) fn factor(self) -> f64 {
+        self.0 as f64 / 100.0
+    }
+}
+
+/// How the controls are arranged.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub(super) enum LayoutSetting {
+    /// The touch layout on devices whose primary pointer is a finger, such as tablets, and the
+    /// desktop layout elsewhere.
+    #[default]
+    Automatic,
+    /// All controls in a sidebar.
+    Desktop,
+    /// Larger controls in panels which a toolbar along the bottom opens one at a time.
+    Touch,
+}
+
+impl LayoutSetting {
+    pub(super) const ALL: [Self; 3] = [Self::Automatic, Self::Desktop, Self::Touch];
+
+    pub(super) fn is_touch(self) -> bool {
+        match self {
+            LayoutSetting::Automatic => touch_is_primary(),
+            LayoutSetting::Desktop => false,
+            LayoutSetting::Touch => true,
+        }
+    }
+}
+
+impl Display for LayoutSetting {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LayoutSetting::Automatic => write!(f, "Automatic layout"),
+            LayoutSetting::Desktop => write!(f, "Desktop layout"),
+            LayoutSetting::Touch => write!(f, "Touch layout"),
+        }
+    }
+}
+
+/// Whether the primary pointer of the device is as coarse as a finger. Desktop platforms are taken
+/// to have a mouse, since winit does not tell.
+#[cfg(not(target_arch = "wasm32"))]
+fn touch_is_primary() -> bool {
+    false
+}
+
+#[cfg(target_arch = "wasm32")]
+fn touch_is_primary() -> bool {
+    web_sys::window()
+        .and_then(|window| window.match_media("(pointer: coarse)").ok().flatten())
+        .is_some_and(|query| query.matches())
+}
+
+/// The groups of controls which the touch layout shows one at a time.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub(super) enum Panel {
+    Fractal,
+    Colour,
+    Explore,
+    Animation,
+    Settings,
+}
+
+impl Panel {
+    const ALL: [Self; 5] = [
+        Self::Fractal,
+        Self::Colour,
+        Self::Explore,
+        Self::Animation,
+        Self::Settings,
+    ];
+}
+
+impl Display for Panel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Panel::Fractal => write!(f, "Fractal"),
+            Panel::Colour => write!(f, "Colour"),
+            Panel::Explore => write!(f, "Explore"),
+            Panel::Animation => write!(f, "Animate"),
+            Panel::Settings => write!(f, "Settings"),
+        }
+    }
+}
+
+impl Default for UiScale {
+    fn default() -> Self {
+        Self(100)
+    }
+}
+
+impl Display for UiScale {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "UI {}%", self.0)
+    }
+}
+
+impl Display for FractalType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.definition().name)
+    }
+}
+
+impl FromStr for FractalType {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|fractal_type| fractal_type.to_string().eq_ignore_ascii_case(s))
+            .ok_or(())
+    }
+}
+
+impl Controls {
+    /// Creates the controls, with low-power mode shown as on if the application started in it.
+    ///
+    /// The adapter is described in the About panel.
+    pub(super) fn new(low_power: bool, adapter_info: &wgpu::AdapterInfo) -> Self {
+        Self {
+            canvas: FractalCanvas::new(),
+            current_type: FractalType::Mandelbrot,
+            located_landmark: None,
+            found_minibrot: None,
+            dimension_estimate: None,
+            area_estimate: None,
+            show_histogram: false,
+            histogram: None,
+            isolines: String::new(),
+            seed: 0.to_string(),
+            trap: OrbitTrap::default(),
+            trap_image: None,
+            max_iterations: DEFAULT_MAX_ITERATIONS,
+            auto_iterations: false,
+            parameter_values: FractalType::Mandelbrot.definition().default_values(),
+            critical_point: 0,
+            plane: Plane::Parameter,
+            variation: Variation::default(),
+            time_playing: false,
+            exponent_sweep: false,
+            sweep_speed: DEFAULT_SWEEP_SPEED,
+            vector_mode: VectorMode::Off,
+            curve_depth: DEFAULT_CURVE_DEPTH,
+            chaos_game_playing: false,
+            chaos_game: ChaosGame::default(),
+            compiling_shader: None,
+            antialiasing: false,
+            low_power,
+            bloom: false,
+            present_mode: PresentMode::default(),
+            frame_rate_cap: FrameRateCap::default(),
+            ui_scale: UiScale::default(),
+            config_error: None,
+            layout: LayoutSetting::default(),
+            open_panel: None,
+            workspace_name: String::new(),
+            workspaces: vec![],
+            workspace: None,
+            palette: Palette::default(),
+            replaced_palettes: vec![],
+            palette_image_error: None,
+            saved_palettes: vec![],
+            post_processing: PostProcessing::default(),
+            compositor: Compositor::default(),
+            debug_view: DebugView::Off,
+            high_contrast: false,
+            modulation: ModulationMatrix::default(),
+            compare: false,
+            annotation_text: String::new(),
+            morph_saved: (false, false),
+            morph: 0.0,
+            history_len: 0,
+            history_position: 0,
+            replaying_history: false,
+            history_export: None,
+            snippet_export: None,
+            svg_export: None,
+            presentation: None,
+            presentation_error: None,
+            diving: false,
+            dive_ended: false,
+            focus: None,
+            about: None,
+            adapter_info: adapter_info.clone(),
+            pending_messages: Cell::new(vec![]),
+        }
+    }
+
+    /// Adds the switch of the orbit trap and, while it is on, the controls which place it and
+    /// choose its image.
+    fn push_orbit_trap_controls<'a>(
+        &self,
+        mut sidebar: Column<'a, Message, Theme, iced_widget::renderer::Renderer>,
+    ) -> Column<'a, Message, Theme, iced_widget::renderer::Renderer> {
+        let trap = self.trap;
+        sidebar = sidebar.push(
+            checkbox("Orbit trap", trap.enabled)
+                .on_toggle(move |enabled| Message::OrbitTrapChanged(OrbitTrap { enabled, ..trap })),
+        );
+        if !trap.enabled {
+            return sidebar;
+        }
+        sidebar = sidebar
+            .push(text(format!("Trap centre x: {:.2}", trap.centre.x)).style(Color::WHITE))
+            .push(
+                slider(TRAP_CENTRE_RANGE, trap.centre.x, move |x| {
+                    Message::OrbitTrapChanged(OrbitTrap {
+                        centre: Vector2::new(x, trap.centre.y),
+                        ..trap
+                    })
+                })
+                .step(TRAP_STEP),
+            )
+            .push(text(format!("Trap centre y: {:.2}", trap.centre.y)).style(Color::WHITE))
+            .push(
+                slider(TRAP_CENTRE_RANGE, trap.centre.y, move |y| {
+                    Message::OrbitTrapChanged(OrbitTrap {
+                        centre: Vector2::new(trap.centre.x, y),
+                        ..trap
+                    })
+                })
+                .step(TRAP_STEP),
+            )
+            .push(text(format!("Trap size: {:.2}", trap.half_size * 2.0)).style(Color::WHITE))
+            .push(
+                slider(TRAP_HALF_SIZE_RANGE, trap.half_size, move |half_size| {
+                    Message::OrbitTrapChanged(OrbitTrap { half_size, ..trap })
+                })
+                .step(TRAP_STEP),
+            );
+        // There is no file dialog on the web.
+        if cfg!(not(target_arch = "wasm32")) {
+            sidebar = sidebar.push(button("Load trap image").on_press(Message::LoadTrapImage));
+        }
+        match &self.trap_image {
+            Some(Ok(name)) => sidebar.push(text(format!("Trap image: {name}")).style(Color::WHITE)),
+            Some(Err(error)) => sidebar.push(text(error).style(Color::WHITE)),
+            None => sidebar,
+        }
+    }
+
+    /// The buttons which start a dive, or the one which stops it while diving.
+    fn dive_controls(&self) -> Element<'_, Message, Theme, iced_widget::renderer::Renderer> {
+        if self.diving {
+            return self.focus_frame(
+                Focusable::StopDive,
+                button("Stop dive").on_press(Message::StopDive),
+            );
+        }
+        let mut dive = Row::new().spacing(5).push(self.focus_frame(
+            Focusable::Dive(DiveTarget::ViewCentre),
+            button("Dive").on_press(Message::Dive(DiveTarget::ViewCentre)),
+        ));
+        // Minibrots are only located in the Mandelbrot set.
+        if self.current_type == FractalType::Mandelbrot {
+            dive = dive.push(self.focus_frame(
+                Focusable::Dive(DiveTarget::NearestMinibrot),
+                button("Dive to minibrot").on_press(Message::Dive(DiveTarget::NearestMinibrot)),
+            ));
+        }
+        let mut column = Column::new().spacing(5).push(dive);
+        if self.dive_ended {
+            column =
+                column.push(text("Dive stopped at the limit of precision").style(Color::WHITE));
+        }
+        column.into()
+    }
+
+    /// The controls which can be focused, in the order in which Tab moves through them.
+    fn focusable_controls(&self) -> Vec<Focusable> {
+        let mut controls = vec![Focusable::FractalType, Focusable::VectorMode];
+        if self.vector_mode != VectorMode::Off {
+            controls.push(Focusable::CurveDepth);
+            if cfg!(not(target_arch = "wasm32")) {
+                controls.push(Focusable::ExportSvg);
+            }
+        }
+        controls.push(Focusable::ChaosGame);
+        if self.chaos_game_playing {
+            controls.extend([Focusable::ChaosGameVertices, Focusable::ChaosGameJumpRatio]);
+        }
+        controls.extend([
+            Focusable::Antialiasing,
+            Focusable::LowPower,
+            Focusable::Bloom,
+            Focusable::PresentMode,
+            Focusable::FrameRateCap,
+            Focusable::UiScale,
+            Focusable::Layout,
+            Focusable::NavigationControls,
+            Focusable::TargetLock,
+            Focusable::HighContrast,
+            Focusable::SaveWorkspace,
+        ]);
+        if !self.workspaces.is_empty() {
+            controls.extend([Focusable::Workspace, Focusable::DeleteWorkspace]);
+        }
+        controls.extend([
+            Focusable::Palette,
+            Focusable::RandomPalette,
+            Focusable::CosinePalette,
+        ]);
+        if !self.replaced_palettes.is_empty() {
+            controls.push(Focusable::UndoGeneratedPalette);
+        }
+        if let Palette::Custom(_) | Palette::Cosine(_) = self.palette {
+            controls.push(Focusable::KeepPalette);
+        }
+        if let Palette::Cosine(_) = self.palette {
+            controls.extend(Coefficient::ALL.into_iter().flat_map(|coefficient| {
+                (0..3).map(move |channel| Focusable::CosineCoefficient(coefficient, channel))
+            }));
+        }
+        controls.extend(Adjustment::ALL.map(Focusable::PostProcessing));
+        controls.extend([Focusable::Tonemapper, Focusable::DebugView]);
+        if self.current_type.escapes() {
+            controls.extend([
+                Focusable::BaseLayer,
+                Focusable::OverlayLayer,
+                Focusable::BlendMode,
+                Focusable::OverlayOpacity,
+            ]);
+        }
+        if cfg!(not(target_arch = "wasm32")) {
+            controls.extend(SnippetLanguage::ALL.map(Focusable::ExportSnippet));
+        }
+        controls.extend((0..self.parameter_values.len()).map(Focusable::Parameter));
+        if self.current_type == FractalType::Rational {
+            controls.push(Focusable::CriticalPoint);
+        }
+        if self.current_type.escapes() {
+            controls.push(Focusable::DynamicPlane);
+            if let Plane::Dynamic(_) = self.plane {
+                controls.extend([Focusable::FixedCReal, Focusable::FixedCImaginary]);
+            }
+        }
+        if self.current_type == FractalType::Multibrot {
+            controls.extend([Focusable::ExponentSweep, Focusable::SweepSpeed]);
+        }
+        controls.extend(VariationFlag::ALL.map(Focusable::Variation));
+        if self.variation.spin {
+            controls.push(Focusable::PlayTime);
+        }
+        if self.current_type == FractalType::Mandelbrot {
+            controls.extend([
+                Focusable::MaxIterations,
+                Focusable::AutoIterations,
+                Focusable::Landmark(LandmarkKind::Center),
+                Focusable::Landmark(LandmarkKind::Misiurewicz),
+                Focusable::FindMinibrot,
+            ]);
+            if let Some(Some(_)) = self.found_minibrot {
+                controls.push(Focusable::ZoomToMinibrot);
+            }
+            controls.extend([
+                Focusable::EstimateDimension,
+                Focusable::EstimateArea(AreaRegion::View),
+                Focusable::EstimateArea(AreaRegion::WholeSet),
+                Focusable::Histogram,
+            ]);
+        }
+        controls.extend([
+            Focusable::Compare,
+            Focusable::ExplorerGrid,
+            Focusable::Measure,
+            Focusable::Annotate,
+        ]);
+        if !self.canvas.annotations.is_empty() {
+            controls.extend([Focusable::RemoveAnnotation, Focusable::ClearAnnotations]);
+        }
+        controls.extend([
+            Focusable::SaveMorph(MorphEnd::Start),
+            Focusable::SaveMorph(MorphEnd::End),
+        ]);
+        if self.morph_saved == (true, true) {
+            controls.push(Focusable::Morph);
+        }
+        if self.history_len > 1 {
+            controls.extend([Focusable::History, Focusable::ReplayHistory]);
+        }
+        if cfg!(not(target_arch = "wasm32")) {
+            controls.extend([Focusable::ExportHistory, Focusable::StartPresentation]);
+        }
+        if self.diving {
+            controls.push(Focusable::StopDive);
+        } else {
+            controls.push(Focusable::Dive(DiveTarget::ViewCentre));
+            if self.current_type == FractalType::Mandelbrot {
+                controls.push(Focusable::Dive(DiveTarget::NearestMinibrot));
+            }
+        }
+        controls.extend((0..self.modulation.lfos.len()).map(Focusable::ModulationTarget));
+        controls.push(Focusable::About);
+        if !self.canvas.jobs.is_empty() {
+            controls.insert(0, Focusable::CancelJobs);
+        }
+        controls
+    }
+
+    /// Applies a key press to the focus and the help overlay, and returns the message with which
+    /// it operates the focused control or the view, zooming on `centre` if it zooms.
+    fn handle_key(&mut self, action: KeyAction, centre: Point) -> Option<Message> {
+        match action {
+            KeyAction::FocusNext | KeyAction::FocusPrevious => {
+                let controls = self.focusable_controls();
+                let current = self
+                    .focus
+                    .and_then(|focus| controls.iter().position(|control| *control == focus));
+                let next = match (current, action) {
+                    (None, KeyAction::FocusNext) => 0,
+                    (None, _) => controls.len() - 1,
+                    (Some(index), KeyAction::FocusNext) => (index + 1) % controls.len(),
+                    (Some(index), _) => (index + controls.len() - 1) % controls.len(),
+                };
+                self.focus = Some(controls[next]);
+                None
+            }
+            KeyAction::ClearFocus => {
+                self.focus = None;
+                self.canvas.show_help = false;
+                self.canvas.probe = None;
+                self.presentation
+                    .is_some()
+                    .then_some(Message::StopPresentation)
+            }
+            KeyAction::NextStop => self
+                .presentation
+                .is_some()
+                .then_some(Message::PresentationNext),
+            KeyAction::PreviousStop => self
+                .presentation
+                .is_some()
+                .then_some(Message::PresentationPrevious),
+            KeyAction::ToggleHelp => {
+                self.canvas.show_help = !self.canvas.show_help;
+                None
+            }
+            KeyAction::ZoomIn => Some(Message::Canvas(CanvasMessage::Zoom(-KEY_ZOOM, centre))),
+            KeyAction::ZoomOut => Some(Message::Canvas(CanvasMessage::Zoom(KEY_ZOOM, centre))),
+            KeyAction::Move(direction) => match self.focus {
+                Some(focus) => {
+                    let step = match direction {
+                        Direction::Right | Direction::Up => 1,
+                        Direction::Left | Direction::Down => -1,
+                    };
+                    self.adjust(focus, step)
+                }
+                None => {
+                    let (x, y) = match direction {
+                        Direction::Left => (-KEY_PAN_PIXELS, 0.0),
+                        Direction::Right => (KEY_PAN_PIXELS, 0.0),
+                        Direction::Up => (0.0, KEY_PAN_PIXELS),
+                        Direction::Down => (0.0, -KEY_PAN_PIXELS),
+                    };
+                    Some(Message::Canvas(CanvasMessage::Pan(x, y)))
+                }
+            },
+            KeyAction::Activate => self.focus.and_then(|focus| self.activate(focus)),
+        }
+    }
+
+    /// Moves the value of a slider or pick list by the given number of steps.
+    fn adjust(&self, control: Focusable, step: i32) -> Option<Message> {
+        match control {
+            Focusable::FractalType => Some(Message::FractalTypeSelected(cycle(
+                &FractalType::ALL,
+                self.current_type,
+                step,
+            ))),
+            Focusable::VectorMode => {
+                let vector_mode = cycle(&VectorMode::ALL, self.vector_mode, step);
+                Some(Message::VectorModeChanged(
+                    vector_mode,
+                    vector_mode.clamp_depth(self.curve_depth),
+                ))
+            }
+            Focusable::CurveDepth => Some(Message::VectorModeChanged(
+                self.vector_mode,
+                self.vector_mode
+                    .clamp_depth(self.curve_depth.saturating_add_signed(step)),
+            )),
+            Focusable::ChaosGameVertices => Some(Message::ChaosGameChanged(
+                self.chaos_game_playing,
+                ChaosGame {
+                    vertices: self
+                        .chaos_game
+                        .vertices
+                        .saturating_add_signed(step)
+                        .clamp(*chaos_game::VERTICES.start(), *chaos_game::VERTICES.end()),
+                    ..self.chaos_game
+                },
+            )),
+            Focusable::ChaosGameJumpRatio => Some(Message::ChaosGameChanged(
+                self.chaos_game_playing,
+                ChaosGame {
+                    jump_ratio: (self.chaos_game.jump_ratio
+                        + step as f32 * chaos_game::JUMP_RATIO_STEP)
+                        .clamp(
+                            *chaos_game::JUMP_RATIOS.start(),
+                            *chaos_game::JUMP_RATIOS.end(),
+                        ),
+                    ..self.chaos_game
+                },
+            )),
+            Focusable::PresentMode => Some(Message::PresentModeSelected(cycle(
+                &PresentMode::ALL,
+                self.present_mode,
+                step,
+            ))),
+            Focusable::FrameRateCap => Some(Message::FrameRateCapSelected(cycle(
+                &FrameRateCap::ALL,
+                self.frame_rate_cap,
+                step,
+            ))),
+            Focusable::UiScale => Some(Message::UiScaleSelected(cycle(
+                &UiScale::ALL,
+                self.ui_scale,
+                step,
+            ))),
+            Focusable::Layout => Some(Message::LayoutSelected(cycle(
+                &LayoutSetting::ALL,
+                self.layout,
+                step,
+            ))),
+            Focusable::Workspace => {
+                let current = self
+                    .workspace
+                    .as_ref()
+                    .and_then(|name| self.workspaces.iter().position(|other| other == name))
+                    .unwrap_or(0) as i32;
+                let index = (current + step).rem_euclid(self.workspaces.len().max(1) as i32);
+                self.workspaces
+                    .get(index as usize)
+                    .cloned()
+                    .map(Message::WorkspaceSelected)
+            }
+            Focusable::Palette => Some(Message::PaletteSelected(cycle(
+                &self.palette_options(),
+                self.palette,
+                step,
+            ))),
+            Focusable::CosineCoefficient(coefficient, channel) => match self.palette {
+                Palette::Cosine(cosine) => {
+                    let value = cosine.get(coefficient)[channel] + step as f32 * coefficient.step();
+                    Some(Message::PaletteSelected(Palette::Cosine(cosine.with(
+                        coefficient,
+                        channel,
+                        value,
+                    ))))
+                }
+                _ => None,
+            },
+            Focusable::PostProcessing(adjustment) => {
+                let value = self.post_processing.get(adjustment) + step as f32 * adjustment.step();
+                Some(Message::PostProcessingChanged(
+                    self.post_processing.with(adjustment, value),
+                ))
+            }
+            Focusable::Tonemapper => Some(Message::PostProcessingChanged(PostProcessing {
+                tonemapper: cycle(&Tonemapper::ALL, self.post_processing.tonemapper, step),
+                ..self.post_processing
+            })),
+            Focusable::DebugView => Some(Message::DebugViewSelected(cycle(
+                &DebugView::ALL,
+                self.debug_view,
+                step,
+            ))),
+            Focusable::BaseLayer => Some(Message::CompositorChanged(Compositor {
+                base: cycle(&Layer::ALL, self.compositor.base, step),
+                ..self.compositor
+            })),
+            Focusable::OverlayLayer => Some(Message::CompositorChanged(Compositor {
+                overlay: cycle(&Layer::ALL, self.compositor.overlay, step),
+                ..self.compositor
+            })),
+            Focusable::BlendMode => Some(Message::CompositorChanged(Compositor {
+                blend_mode: cycle(&BlendMode::ALL, self.compositor.blend_mode, step),
+                ..self.compositor
+            })),
+            Focusable::OverlayOpacity => Some(Message::CompositorChanged(
+                self.compositor
+                    .with_opacity(self.compositor.opacity + step as f32 * Compositor::OPACITY_STEP),
+            )),
+            Focusable::ExportSnippet(language) => Some(Message::ExportSnippet(language)),
+            Focusable::Parameter(index) => {
+                let parameter = &self.current_type.definition().parameters[index];
+                let value = self.parameter_values[index] + step as f32 * parameter.step;
+                Some(Message::ParameterChanged(
+                    index,
+                    value.clamp(parameter.min, parameter.max),
+                ))
+            }
+            Focusable::CriticalPoint => {
+                let points = self.critical_points();
+                (!points.is_empty()).then(|| {
+                    let current = points.get(self.critical_point).unwrap_or(&points[0]);
+                    Message::CriticalPointSelected(cycle(&points, *current, step).index)
+                })
+            }
+            Focusable::FixedCReal | Focusable::FixedCImaginary => {
+                let Plane::Dynamic(mut c) = self.plane else {
+                    return None;
+                };
+                let part = if control == Focusable::FixedCReal {
+                    &mut c.x
+                } else {
+                    &mut c.y
+                };
+                *part = (*part + step as f32 * plane::FIXED_C_STEP)
+                    .clamp(*plane::FIXED_C_PARTS.start(), *plane::FIXED_C_PARTS.end());
+                Some(Message::PlaneChanged(Plane::Dynamic(c)))
+            }
+            Focusable::SweepSpeed => Some(Message::ExponentSweepChanged(
+                self.exponent_sweep,
+                (self.sweep_speed + step as f32 * SWEEP_SPEED_STEP)
+                    .clamp(MIN_SWEEP_SPEED, MAX_SWEEP_SPEED),
+            )),
+            Focusable::Morph => Some(Message::MorphChanged(
+                (self.morph + step as f32 * MORPH_KEY_STEP).clamp(0.0, 1.0),
+            )),
+            Focusable::History => Some(Message::HistoryBrowsed(
+                self.history_position
+                    .saturating_add_signed(step as isize)
+                    .min(self.history_len.saturating_sub(1)),
+            )),
+            Focusable::ModulationTarget(slot) => {
+                let lfo = self.modulation.lfos[slot];
+                Some(Message::ModulationChanged(
+                    slot,
+                    Lfo {
+                        target: cycle(
+                            &ModulationTarget::available(self.current_type),
+                            lfo.target,
+                            step,
+                        ),
+                        ..lfo
+                    },
+                ))
+            }
+            Focusable::MaxIterations => {
+                let max_iterations = self
+                    .max_iterations
+                    .saturating_add_signed(step * MAX_ITERATIONS_STEP as i32);
+                Some(Message::MaxIterationsChanged(
+                    max_iterations.clamp(MIN_ITERATIONS, MAX_ITERATIONS),
+                ))
+            }
+            _ => None,
+        }
+    }
+
+    /// Presses a button, toggles a checkbox or selects the next option of a pick list.
+    fn activate(&self, control: Focusable) -> Option<Message> {
+        match control {
+            Focusable::Antialiasing => Some(Message::AntialiasingToggled(!self.antialiasing)),
+            Focusable::LowPower => Some(Message::LowPowerToggled(!self.low_power)),
+            Focusable::Bloom => Some(Message::BloomToggled(!self.bloom)),
+            Focusable::NavigationControls => {
+                Some(Message::NavigationControlsToggled(!self.canvas.navigation))
+            }
+            Focusable::TargetLock => Some(Message::TargetLockToggled(!self.canvas.target_lock)),
+            Focusable::HighContrast => Some(Message::HighContrastToggled(!self.high_contrast)),
+            Focusable::SaveWorkspace => self.save_workspace(),
+            Focusable::DeleteWorkspace => self.workspace.clone().map(Message::DeleteWorkspace),
+            Focusable::RandomPalette => Some(Message::RandomizePalette),
+            Focusable::CosinePalette => Some(Message::CreateCosinePalette),
+            Focusable::ExportSvg => Some(Message::ExportSvg),
+            Focusable::UndoGeneratedPalette => self.undo_generated_palette(),
+            Focusable::KeepPalette => self.keep_palette(),
+            Focusable::AutoIterations => {
+                Some(Message::AutoIterationsToggled(!self.auto_iterations))
+            }
+            Focusable::Landmark(kind) => Some(Message::LocateLandmark(kind)),
+            Focusable::FindMinibrot => Some(Message::FindMinibrot),
+            Focusable::ZoomToMinibrot => self.found_minibrot.flatten().map(Message::ZoomToMinibrot),
+            Focusable::EstimateDimension => Some(Message::EstimateDimension),
+            Focusable::EstimateArea(region) => Some(Message::EstimateArea(region)),
+            Focusable::Histogram => Some(Message::HistogramToggled(!self.show_histogram)),
+            Focusable::ExponentSweep => Some(Message::ExponentSweepChanged(
+                !self.exponent_sweep,
+                self.sweep_speed,
+            )),
+            Focusable::ChaosGame => Some(Message::ChaosGameChanged(
+                !self.chaos_game_playing,
+                self.chaos_game,
+            )),
+            Focusable::DynamicPlane => Some(Message::PlaneChanged(self.toggled_plane())),
+            Focusable::Variation(flag) => Some(Message::VariationChanged(
+                self.variation.with(flag, !self.variation.get(flag)),
+            )),
+            Focusable::PlayTime => Some(Message::TimePlayingToggled(!self.time_playing)),
+            Focusable::Compare => Some(Message::CompareToggled(!self.compare)),
+            Focusable::ExplorerGrid => {
+                Some(Message::ExplorerGridToggled(!self.canvas.explorer_grid))
+            }
+            Focusable::Measure => Some(Message::MeasuringToggled(!self.canvas.measuring)),
+            Focusable::Annotate => Some(Message::AnnotatingToggled(!self.canvas.annotating)),
+            Focusable::RemoveAnnotation => Some(Message::RemoveAnnotation),
+            Focusable::ClearAnnotations => Some(Message::ClearAnnotations),
+            Focusable::SaveMorph(end) => Some(Message::SaveMorphEnd(end)),
+            Focusable::ReplayHistory => {
+                Some(Message::HistoryReplayToggled(!self.replaying_history))
+            }
+            Focusable::ExportHistory => Some(Message::ExportHistory),
+            Focusable::StartPresentation => Some(Message::StartPresentation),
+            Focusable::Dive(target) => Some(Message::Dive(target)),
+            Focusable::StopDive => Some(Message::StopDive),
+            Focusable::About => Some(Message::AboutToggled),
+            Focusable::CancelJobs => Some(Message::CancelJobs),
+            control => self.adjust(control, 1),
+        }
+    }
+
+    /// Draws a frame around the control while it has the keyboard focus.
+    fn focus_frame<'a>(
+        &self,
+        control: Focusable,
+        content: impl Into<Element<'a, Message, Theme, iced_widget::renderer::Renderer>>,
+    ) -> Element<'a, Message, Theme, iced_widget::renderer::Renderer> {
+        let framed = container(content).padding(2);
+        if self.focus == Some(control) {
+            framed
+                .style(|_: &Theme| container::Appearance {
+                    border: Border {
+                        color: FOCUS_COLOUR,
+                        width: 2.0,
+                        radius: 2.0.into(),
+                    },
+                    ..Default::default()
+                })
+                .into()
+        } else {
+            framed.into()
+        }
+    }
+
+    /// Returns all messages received since the last call, in the order in which they arrived.
+    pub(super) fn take_messages(&self) -> Vec<Message> {
+        self.pending_messages.take()
+    }
+}
+
+/// Returns the option `step` places after `current` in `options`, wrapping around at either end.
+fn cycle<T: Copy + PartialEq>(options: &[T], current: T, step: i32) -> T {
+    let index = options
+        .iter()
+        .position(|option| *option == current)
+        .unwrap_or(0) as i32;
+    options[(index + step).rem_euclid(options.len() as i32) as usize]
+}
+
+impl Program for Controls {
+    type Renderer = iced_widget::renderer::Renderer;
+    type Message = Message;
+    type Theme = Theme;
+
+    fn update(&mut self, message: Self::Message) -> iced::Command<Self::Message> {
+        match message {
+            Message::Canvas(CanvasMessage::UpdateViewTransform(transform)) => {
+                self.canvas.transform = transform;
+                self.canvas.probe = None;
+                self.dimension_estimate = None;
+                if let Some((AreaRegion::View, _)) = self.area_estimate {
+                    self.area_estimate = None;
+                }
+            }
+            Message::Canvas(CanvasMessage::Key(action, centre)) => {
+                // Key presses are turned into the messages of the controls they operate, so the
+                // controller never sees them.
+                return match self.handle_key(action, centre) {
+                    Some(message) => self.update(message),
+                    None => iced::Command::none(),
+                };
+            }
+            Message::Canvas(CanvasMessage::MoveDivider(x)) => {
+                self.canvas.divider = Some(x);
+            }
+            Message::Canvas(CanvasMessage::Measure(measurement)) => {
+                self.canvas.measurement = Some(measurement);
+            }
+            Message::Canvas(CanvasMessage::Annotate(position, target)) => {
+                // Notes take their text from the controls, so the controller only sees them
+                // complete.
+                let text = self.annotation_text.trim();
+                if text.is_empty() && target.is_none() {
+                    return iced::Command::none();
+                }
+                return self.update(Message::AddAnnotation(Annotation {
+                    text: text.into(),
+                    position,
+                    target,
+                }));
+            }
+            Message::Canvas(CanvasMessage::Probe(corner, opposite)) => {
+                self.canvas.probe = Some(Probe {
+                    rectangle: Rectangle::new(
+                        Point::new(corner.x.min(opposite.x), corner.y.min(opposite.y)),
+                        iced::Size::new(
+                            (corner.x - opposite.x).abs(),
+                            (corner.y - opposite.y).abs(),
+                        ),
+                    ),
+                    statistics: None,
+                });
+            }
+            Message::Canvas(_) => {}
+            Message::FractalTypeSelected(selected_type) => {
+                let definition = selected_type.definition();
+                self.current_type = selected_type;
+                self.parameter_values = definition.default_values();
+                self.max_iterations = definition.profile.max_iterations;
+                self.palette = definition.profile.palette;
+                self.modulation.retain_targets(selected_type);
+                self.exponent_sweep &= selected_type == FractalType::Multibrot;
+                self.critical_point = 0;
+                self.located_landmark = None;
+                self.found_minibrot = None;
+                self.dimension_estimate = None;
+                self.area_estimate = None;
+                self.histogram = None;
+                self.canvas.probe = None;
+            }
+            Message::ParameterChanged(index, value) => {
+                self.parameter_values[index] = value;
+            }
+            Message::CriticalPointSelected(index) => {
+                self.critical_point = index;
+            }
+            Message::PlaneChanged(plane) => {
+                self.plane = plane;
+            }
+            Message::VariationChanged(variation) => {
+                self.variation = variation;
+            }
+            Message::TimePlayingToggled(playing) => {
+                self.time_playing = playing;
+            }
+            Message::ExponentSweepChanged(exponent_sweep, sweep_speed) => {
+                self.exponent_sweep = exponent_sweep;
+                self.sweep_speed = sweep_speed;
+            }
+            Message::VectorModeChanged(vector_mode, curve_depth) => {
+                self.vector_mode = vector_mode;
+                self.curve_depth = curve_depth;
+                // The view draws the curve instead of the chaos game.
+                if vector_mode != VectorMode::Off {
+                    self.chaos_game_playing = false;
+                }
+            }
+            Message::ExportSvg => {}
+            Message::SvgExported(ref result) => {
+                self.svg_export = Some(result.clone());
+            }
+            Message::ChaosGameChanged(playing, chaos_game) => {
+                self.chaos_game_playing = playing;
+                self.chaos_game = chaos_game;
+                if playing {
+                    self.vector_mode = VectorMode::Off;
+                }
+            }
+            Message::LocateLandmark(_)
+            | Message::FindMinibrot
+            | Message::ZoomToMinibrot(_)
+            | Message::EstimateDimension
+            | Message::EstimateArea(_) => {}
+            Message::HistogramToggled(show) => {
+                self.show_histogram = show;
+                if !show {
+                    self.histogram = None;
+                }
+            }
+            Message::HistogramComputed(ref histogram) => {
+                self.histogram = Some(histogram.clone());
+            }
+            Message::IsolinesEdited(ref isolines) => {
+                self.isolines = isolines.clone();
+            }
+            Message::SeedEdited(ref seed) => {
+                self.seed = seed.clone();
+            }
+            Message::OrbitTrapChanged(trap) => {
+                self.trap = trap;
+            }
+            Message::LoadTrapImage => {}
+            Message::TrapImageLoaded(ref result) => {
+                self.trap_image = Some(result.clone());
+            }
+            Message::RegionProbed(statistics) => {
+                if let Some(probe) = &mut self.canvas.probe {
+                    probe.statistics = Some(statistics);
+                }
+            }
+            Message::MaxIterationsChanged(max_iterations) => {
+                self.max_iterations = max_iterations;
+                self.auto_iterations = false;
+            }
+            Message::AutoIterationsToggled(auto_iterations) => {
+                self.auto_iterations = auto_iterations;
+            }
+            Message::MaxIterationsApplied(max_iterations) => {
+                self.max_iterations = max_iterations;
+            }
+            Message::LandmarkLocated(landmark) => {
+                self.located_landmark = Some(landmark);
+            }
+            Message::MinibrotFound(minibrot) => {
+                self.found_minibrot = Some(minibrot);
+            }
+            Message::DimensionEstimated(estimate) => {
+                self.dimension_estimate = Some(estimate);
+            }
+            Message::AreaEstimated(region, estimate) => {
+                self.area_estimate = Some((region, estimate));
+            }
+            Message::CompilingShader(phase) => {
+                self.compiling_shader = phase;
+            }
+            Message::AntialiasingToggled(antialiasing) => {
+                self.antialiasing = antialiasing;
+            }
+            Message::LowPowerToggled(low_power) => {
+                self.low_power = low_power;
+            }
+            Message::BloomToggled(bloom) => {
+                self.bloom = bloom;
+            }
+            Message::PresentModeSelected(present_mode) => {
+                self.present_mode = present_mode;
+            }
+            Message::FrameRateCapSelected(frame_rate_cap) => {
+                self.frame_rate_cap = frame_rate_cap;
+            }
+            Message::UiScaleSelected(ui_scale) => {
+                self.ui_scale = ui_scale;
+            }
+            Message::LayoutSelected(layout) => {
+                self.layout = layout;
+            }
+            Message::NavigationControlsToggled(navigation) => {
+                self.canvas.navigation = navigation;
+            }
+            Message::TargetLockToggled(target_lock) => {
+                self.canvas.target_lock = target_lock;
+            }
+            Message::TargetLocked(target) => {
+                self.canvas.target = target;
+            }
+            Message::PanelToggled(panel) => {
+                self.open_panel = (self.open_panel != Some(panel)).then_some(panel);
+            }
+            Message::WorkspaceNameEdited(ref name) => {
+                self.workspace_name = name.clone();
+            }
+            Message::SaveWorkspace(ref name, _) | Message::WorkspaceSelected(ref name) => {
+                self.workspace = Some(name.clone());
+            }
+            Message::DeleteWorkspace(_) => {}
+            Message::WorkspacesChanged(ref names) => {
+                self.workspaces = names.clone();
+                if !self
+                    .workspace
+                    .as_ref()
+                    .is_some_and(|name| names.contains(name))
+                {
+                    self.workspace = None;
+                }
+            }
+            Message::WorkspaceApplied(ref workspace) => {
+                self.current_type = workspace.view.fractal_type;
+                self.parameter_values = workspace.view.parameter_values.clone();
+                self.critical_point = workspace.view.critical_point;
+                self.plane = workspace.view.plane;
+                self.max_iterations = workspace.view.max_iterations;
+                self.palette = workspace.view.palette;
+                self.seed = workspace.view.seed.to_string();
+                self.modulation.retain_targets(workspace.view.fractal_type);
+                self.ui_scale = workspace.ui_scale;
+                self.layout = workspace.layout;
+                self.open_panel = workspace.panels.open_panel;
+                self.canvas.navigation = workspace.panels.navigation;
+                self.show_histogram = workspace.panels.histogram;
+                if !workspace.panels.histogram {
+                    self.histogram = None;
+                }
+                self.workspace_name = workspace.name.clone();
+            }
+            Message::PaletteSelected(palette) => {
+                self.palette = palette;
+            }
+            Message::RandomizePalette
+            | Message::CreateCosinePalette
+            | Message::LoadPaletteImage => {}
+            Message::PaletteGenerated(palette) => {
+                self.palette_image_error = None;
+                self.replaced_palettes.push(self.palette);
+                if self.replaced_palettes.len() > MAX_REPLACED_PALETTES {
+                    self.replaced_palettes.remove(0);
+                }
+                self.palette = palette;
+            }
+            Message::PaletteImageFailed(ref error) => {
+                self.palette_image_error = Some(error.clone());
+            }
+            Message::UndoGeneratedPalette(palette) => {
+                self.replaced_palettes.pop();
+                self.palette = palette;
+            }
+            Message::KeepPalette(palette) => {
+                colour_palette::keep(&mut self.saved_palettes, palette);
+            }
+            Message::ForgetPalette(palette) => {
+                self.saved_palettes.retain(|saved| *saved != palette);
+            }
+            Message::PostProcessingChanged(post_processing) => {
+                self.post_processing = post_processing;
+            }
+            Message::CompositorChanged(compositor) => {
+                self.compositor = compositor;
+            }
+            Message::DebugViewSelected(debug_view) => {
+                self.debug_view = debug_view;
+            }
+            Message::ExportSnippet(_) => {}
+            Message::SnippetExported(ref result) => {
+                self.snippet_export = Some(result.clone());
+            }
+            Message::HighContrastToggled(high_contrast) => {
+                self.high_contrast = high_contrast;
+            }
+            Message::ModulationChanged(slot, lfo) => {
+                self.modulation.lfos[slot] = lfo;
+            }
+            Message::CompareToggled(compare) => {
+                self.compare = compare;
+                if !compare {
+                    self.canvas.divider = None;
+                }
+            }
+            Message::ExplorerGridToggled(explorer_grid) => {
+                self.canvas.explorer_grid = explorer_grid;
+            }
+            Message::AnnotationTextEdited(ref text) => {
+                self.annotation_text = text.clone();
+            }
+            Message::MeasuringToggled(measuring) => {
+                self.canvas.measuring = measuring;
+                self.canvas.annotating &= !measuring;
+                if !measuring {
+                    self.canvas.measurement = None;
+                }
+            }
+            Message::AnnotatingToggled(annotating) => {
+                self.canvas.annotating = annotating;
+                self.canvas.measuring &= !annotating;
+            }
+            Message::AddAnnotation(ref annotation) => {
+                self.canvas.annotations.push(annotation.clone());
+            }
+            Message::RemoveAnnotation => {
+                self.canvas.annotations.pop();
+            }
+            Message::ClearAnnotations => {
+                self.canvas.annotations.clear();
+            }
+            Message::SaveMorphEnd(end) => match end {
+                MorphEnd::Start => self.morph_saved.0 = true,
+                MorphEnd::End => self.morph_saved.1 = true,
+            },
+            Message::MorphChanged(morph) => {
+                self.morph = morph;
+            }
+            Message::MorphApplied(ref bookmark) => {
+                self.current_type = bookmark.fractal_type;
+                self.parameter_values = bookmark.parameter_values.clone();
+                self.critical_point = bookmark.critical_point;
+                self.plane = bookmark.plane;
+                self.max_iterations = bookmark.max_iterations;
+                self.palette = bookmark.palette;
+                self.seed = bookmark.seed.to_string();
+                self.modulation.retain_targets(bookmark.fractal_type);
+            }
+            Message::HistoryRecorded(len) => {
+                self.history_len = len;
+                self.history_position = len - 1;
+            }
+            Message::HistoryBrowsed(index) => {
+                self.history_position = index;
+            }
+            Message::HistoryReplayToggled(replaying) => {
+                self.replaying_history = replaying;
+            }
+            Message::ExportHistory => {}
+            Message::HistoryExported(ref result) => {
+                self.history_export = Some(result.clone());
+            }
+            Message::StartPresentation => {
+                self.presentation_error = None;
+            }
+            Message::PresentationNext | Message::PresentationPrevious => {}
+            Message::StopPresentation => {
+                self.presentation = None;
+                self.canvas.caption = None;
+            }
+            Message::PresentationChanged(ref status) => {
+                self.presentation.clone_from(status);
+                self.canvas.caption = status.as_ref().and_then(|status| status.caption.clone());
+            }
+            Message::PresentationFailed(ref error) => {
+                self.presentation_error = Some(error.clone());
+            }
+            Message::SessionRestored(ref session) => {
+                self.current_type = session.view.fractal_type;
+                self.parameter_values = session.view.parameter_values.clone();
+                self.critical_point = session.view.critical_point;
+                self.plane = session.view.plane;
+                self.max_iterations = session.view.max_iterations;
+                self.palette = session.view.palette;
+                self.seed = session.view.seed.to_string();
+                self.modulation.retain_targets(session.view.fractal_type);
+                self.morph_saved = (session.morph_start.is_some(), session.morph_end.is_some());
+                self.ui_scale = session.ui_scale;
+                self.layout = session.layout;
+                self.high_contrast = session.high_contrast;
+                self.canvas.annotations = session.annotations.clone();
+                self.saved_palettes.clone_from(&session.saved_palettes);
+                self.workspaces = session
+                    .workspaces
+                    .iter()
+                    .map(|workspace| workspace.name.clone())
+                    .collect();
+            }
+            Message::Dive(_) => {
+                self.diving = true;
+                self.dive_ended = false;
+            }
+            Message::StopDive => {
+                self.diving = false;
+            }
+            Message::DiveEnded => {
+                self.diving = false;
+                self.dive_ended = true;
+            }
+            Message::PrecisionChanged(precision) => {
+                if let Some(previous) = self.canvas.precision {
+                    if previous.mode != precision.mode {
+                        self.canvas.switched_from = Some(previous.mode);
+                    }
+                }
+                self.canvas.precision = Some(precision);
+            }
+            Message::JobsProgressed(ref jobs) => {
+                self.canvas.jobs = jobs.clone();
+            }
+            Message::CancelJobs => {
+                self.canvas.jobs.clear();
+            }
+            Message::GpuMemoryMeasured(usage) => {
+                self.canvas.gpu_memory = Some(usage);
+            }
+            Message::UserConfigLoaded(ref config) => {
+                self.canvas.keymap = config.keymap.clone();
+                self.config_error = None;
+            }
+            Message::UserConfigFailed(ref error) => {
+                self.config_error = Some(error.clone());
+            }
+            Message::AboutToggled => {
+                self.about = match self.about {
+                    Some(_) => None,
+                    None => Some(describe_application(&self.adapter_info)),
+                };
+            }
+        }
+        self.pending_messages.get_mut().push(message);
+        iced::Command::none()
+    }
+
+    fn view(&self) -> Element<'_, Self::Message, Self::Theme, Self::Renderer> {
+        if self.presentation.is_some() {
+            return self.canvas.view().map(Message::Canvas);
+        }
+        if self.layout.is_touch() {
+            return self.touch_layout();
+        }
+        let mut sidebar = Column::new().spacing(5);
+        sidebar = sidebar.push_maybe(self.cancel_controls());
+        sidebar = self.push_fractal_type_controls(sidebar);
+        sidebar = self.push_rendering_controls(sidebar);
+        sidebar = self.push_workspace_controls(sidebar);
+        sidebar = self.push_palette_controls(sidebar);
+        sidebar = self.push_parameter_controls(sidebar);
+        sidebar = self.push_analysis_controls(sidebar);
+        if self.current_type == FractalType::Mandelbrot {
+            sidebar = self.push_isoline_controls(sidebar);
+            sidebar = self.push_orbit_trap_controls(sidebar);
+        }
+        sidebar = self.push_analysis_results(sidebar);
+        sidebar = self.push_annotation_controls(sidebar);
+        sidebar = self.push_animation_controls(sidebar);
+        sidebar = self.push_about(sidebar);
+        let mut sidebar = container(sidebar);
+        if self.high_contrast {
+            sidebar = sidebar.padding(5).style(|_: &Theme| container::Appearance {
+                background: Some(Color::BLACK.into()),
+                border: Border::with_radius(0),
+                ..Default::default()
+            });
+        }
+        Row::new()
+            .push(self.canvas.view().map(Message::Canvas))
+            .push(sidebar)
+            .into()
+    }
 }
 
-#[derive(Debug, Clone)]
-pub(super) enum Message {
-    Canvas(CanvasMessage),
-    FractalTypeSelected(FractalType),
+impl Controls {
+    /// Arranges the controls for fingers: the view fills the window above a toolbar, whose
+    /// buttons open one panel of controls at a time between the two.
+    fn touch_layout(&self) -> Element<'_, Message, Theme, iced_widget::renderer::Renderer> {
+        let mut layout = Column::new().push(self.canvas.view().map(Message::Canvas));
+        if let Some(panel) = self.open_panel {
+            let mut content = Column::new().spacing(5);
+            content = match panel {
+                Panel::Fractal => {
+                    content = self.push_fractal_type_controls(content);
+                    self.push_parameter_controls(content)
+                }
+                Panel::Colour => {
+                    content = self.push_palette_controls(content);
+                    if self.current_type == FractalType::Mandelbrot {
+                        content = self.push_isoline_controls(content);
+                        content = self.push_orbit_trap_controls(content);
+                    }
+                    content
+                }
+                Panel::Explore => {
+                    content = self.push_analysis_controls(content);
+                    content = self.push_analysis_results(content);
+                    self.push_annotation_controls(content)
+                }
+                Panel::Animation => self.push_animation_controls(content),
+                Panel::Settings => {
+                    content = self.push_rendering_controls(content);
+                    content = self.push_workspace_controls(content);
+                    self.push_about(content)
+                }
+            };
+            let high_contrast = self.high_contrast;
+            layout = layout.push(
+                container(scrollable(container(content).padding(TOUCH_PADDING)))
+                    .width(Length::Fill)
+                    .height(TOUCH_PANEL_HEIGHT)
+                    .style(move |_: &Theme| container::Appearance {
+                        background: Some(
+                            Color {
+                                a: if high_contrast { 1.0 } else { 0.8 },
+                                ..Color::BLACK
+                            }
+                            .into(),
+                        ),
+                        ..Default::default()
+                    }),
+            );
+        }
+        let toolbar =
+            Panel::ALL
+                .into_iter()
+                .fold(Row::new().spacing(TOUCH_PADDING), |toolbar, panel| {
+                    let style = if self.open_panel == Some(panel) {
+                        iced::theme::Button::Primary
+                    } else {
+                        iced::theme::Button::Secondary
+                    };
+                    toolbar.push(
+                        button(
+                            text(panel.to_string())
+                                .width(Length::Fill)
+                                .horizontal_alignment(iced::alignment::Horizontal::Center),
+                        )
+                        .padding(TOUCH_PADDING)
+                        .width(Length::Fill)
+                        .style(style)
+                        .on_press(Message::PanelToggled(panel)),
+                    )
+                });
+        layout
+            .push_maybe(
+                self.cancel_controls()
+                    .map(|controls| container(controls).padding(TOUCH_PADDING)),
+            )
+            .push(container(toolbar).padding(TOUCH_PADDING))
+            .into()
+    }
+
+    /// The jobs running in the background and the button which cancels them, while there are
+    /// any. It leads the controls, so that it is at hand when a job turns out to take too long.
+    fn cancel_controls(
+        &self,
+    ) -> Option<Element<'_, Message, Theme, iced_widget::renderer::Renderer>> {
+        if self.canvas.jobs.is_empty() {
+            return None;
+        }
+        let names: Vec<_> = self.canvas.jobs.iter().map(|job| job.name).collect();
+        Some(
+            Row::new()
+                .spacing(5)
+                .align_items(iced::Alignment::Center)
+                .push(
+                    self.focus_frame(
+                        Focusable::CancelJobs,
+                        button("Cancel")
+                            .style(iced::theme::Button::Destructive)
+                            .on_press(Message::CancelJobs),
+                    ),
+                )
+                .push(text(format!("{}…", names.join(", "))).style(Color::WHITE))
+                .into(),
+        )
+    }
+
+    /// Adds the choice of fractal and, while its shader compiles, a spinner, then the choice of
+    /// geometric curve drawn instead and its depth, and the chaos game with its settings.
+    fn push_fractal_type_controls<'a>(
+        &'a self,
+        mut column: Column<'a, Message, Theme, iced_widget::renderer::Renderer>,
+    ) -> Column<'a, Message, Theme, iced_widget::renderer::Renderer> {
+        column = column.push(self.focus_frame(
+            Focusable::FractalType,
+            pick_list(
+                &FractalType::ALL[..],
+                Some(self.current_type),
+                Message::FractalTypeSelected,
+            ),
+        ));
+        if let Some(phase) = self.compiling_shader {
+            column = column.push(
+                Row::new()
+                    .spacing(5)
+                    .push(
+                        Canvas::new(Spinner(phase))
+                            .width(SPINNER_SIZE)
+                            .height(SPINNER_SIZE),
+                    )
+                    .push(text("Compiling shader").style(Color::WHITE)),
+            );
+        }
+        let (vector_mode, curve_depth) = (self.vector_mode, self.curve_depth);
+        column = column.push(self.focus_frame(
+            Focusable::VectorMode,
+            pick_list(
+                &VectorMode::ALL[..],
+                Some(vector_mode),
+                move |vector_mode| {
+                    Message::VectorModeChanged(vector_mode, vector_mode.clamp_depth(curve_depth))
+                },
+            ),
+        ));
+        if vector_mode != VectorMode::Off {
+            column = column
+                .push(text(format!("Depth: {curve_depth}")).style(Color::WHITE))
+                .push(self.focus_frame(
+                    Focusable::CurveDepth,
+                    slider(1..=vector_mode.max_depth(), curve_depth, move |depth| {
+                        Message::VectorModeChanged(vector_mode, depth)
+                    }),
+                ));
+            // There is no file dialog on the web.
+            if cfg!(not(target_arch = "wasm32")) {
+                column = column.push(self.focus_frame(
+                    Focusable::ExportSvg,
+                    button("Export SVG").on_press(Message::ExportSvg),
+                ));
+                match &self.svg_export {
+                    Some(Ok(name)) => {
+                        column = column.push(text(format!("Exported {name}")).style(Color::WHITE));
+                    }
+                    Some(Err(error)) => column = column.push(text(error).style(Color::WHITE)),
+                    None => {}
+                }
+            }
+        }
+        let (playing, game) = (self.chaos_game_playing, self.chaos_game);
+        column = column.push(
+            self.focus_frame(
+                Focusable::ChaosGame,
+                checkbox("Chaos game", playing)
+                    .on_toggle(move |playing| Message::ChaosGameChanged(playing, game)),
+            ),
+        );
+        if playing {
+            column = column
+                .push(text(format!("Polygon vertices: {}", game.vertices)).style(Color::WHITE))
+                .push(self.focus_frame(
+                    Focusable::ChaosGameVertices,
+                    slider(chaos_game::VERTICES, game.vertices, move |vertices| {
+                        Message::ChaosGameChanged(true, ChaosGame { vertices, ..game })
+                    }),
+                ))
+                .push(text(format!("Jump ratio: {:.2}", game.jump_ratio)).style(Color::WHITE))
+                .push(
+                    self.focus_frame(
+                        Focusable::ChaosGameJumpRatio,
+                        slider(
+                            chaos_game::JUMP_RATIOS,
+                            game.jump_ratio,
+                            move |jump_ratio| {
+                                Message::ChaosGameChanged(true, ChaosGame { jump_ratio, ..game })
+                            },
+                        )
+                        .step(chaos_game::JUMP_RATIO_STEP),
+                    ),
+                );
+        }
+        column
+    }
+
+    /// Adds the input with which the current setup is saved as a workspace, and the choice of
+    /// saved workspaces.
+    fn push_workspace_controls<'a>(
+        &'a self,
+        mut column: Column<'a, Message, Theme, iced_widget::renderer::Renderer>,
+    ) -> Column<'a, Message, Theme, iced_widget::renderer::Renderer> {
+        column = column.push(text("Workspaces").style(Color::WHITE)).push(
+            Row::new()
+                .spacing(5)
+                .push(
+                    text_input("Name", &self.workspace_name)
+                        .on_input(Message::WorkspaceNameEdited)
+                        .width(WORKSPACE_NAME_WIDTH),
+                )
+                .push(self.focus_frame(
+                    Focusable::SaveWorkspace,
+                    button("Save").on_press_maybe(self.save_workspace()),
+                )),
+        );
+        if self.workspaces.is_empty() {
+            return column;
+        }
+        column.push(
+            Row::new()
+                .spacing(5)
+                .push(self.focus_frame(
+                    Focusable::Workspace,
+                    pick_list(
+                        &self.workspaces[..],
+                        self.workspace.clone(),
+                        Message::WorkspaceSelected,
+                    ),
+                ))
+                .push(
+                    self.focus_frame(
+                        Focusable::DeleteWorkspace,
+                        button("Delete")
+                            .on_press_maybe(self.workspace.clone().map(Message::DeleteWorkspace)),
+                    ),
+                ),
+        )
+    }
+
+    /// The built-in palettes followed by the saved ones.
+    fn palette_options(&self) -> Vec<Palette> {
+        Palette::ALL
+            .into_iter()
+            .chain(self.saved_palettes.iter().copied())
+            .collect()
+    }
+
+    /// The message which returns to the palette which the last generated one replaced, if any.
+    fn undo_generated_palette(&self) -> Option<Message> {
+        self.replaced_palettes
+            .last()
+            .copied()
+            .map(Message::UndoGeneratedPalette)
+    }
+
+    /// The message which keeps the current custom or cosine palette among the saved ones, or
+    /// forgets it if it is kept already. Built-in palettes are always offered.
+    fn keep_palette(&self) -> Option<Message> {
+        match self.palette {
+            Palette::Custom(_) | Palette::Cosine(_)
+                if self.saved_palettes.contains(&self.palette) =>
+            {
+                Some(Message::ForgetPalette(self.palette))
+            }
+            Palette::Custom(_) | Palette::Cosine(_) => Some(Message::KeepPalette(self.palette)),
+            _ => None,
+        }
+    }
+
+    /// The message which saves the current setup under the name typed, unless it is blank.
+    fn save_workspace(&self) -> Option<Message> {
+        let name = self.workspace_name.trim();
+        (!name.is_empty()).then(|| {
+            Message::SaveWorkspace(
+                name.into(),
+                VisiblePanels {
+                    open_panel: self.open_panel,
+                    navigation: self.canvas.navigation,
+                    histogram: self.show_histogram,
+                },
+            )
+        })
+    }
+
+    /// Adds the settings of the renderer and of the controls themselves.
+    fn push_rendering_controls<'a>(
+        &'a self,
+        column: Column<'a, Message, Theme, iced_widget::renderer::Renderer>,
+    ) -> Column<'a, Message, Theme, iced_widget::renderer::Renderer> {
+        column
+            .push(
+                self.focus_frame(
+                    Focusable::Antialiasing,
+                    checkbox("Adaptive anti-aliasing", self.antialiasing)
+                        .on_toggle(Message::AntialiasingToggled),
+                ),
+            )
+            .push(self.seed_controls())
+            .push(self.focus_frame(
+                Focusable::LowPower,
+                checkbox("Low-power mode", self.low_power).on_toggle(Message::LowPowerToggled),
+            ))
+            .push(self.focus_frame(
+                Focusable::Bloom,
+                checkbox("Bloom", self.bloom).on_toggle(Message::BloomToggled),
+            ))
+            .push(
+                Row::new()
+                    .spacing(5)
+                    .push(self.focus_frame(
+                        Focusable::PresentMode,
+                        pick_list(
+                            &PresentMode::ALL[..],
+                            Some(self.present_mode),
+                            Message::PresentModeSelected,
+                        ),
+                    ))
+                    .push(self.focus_frame(
+                        Focusable::FrameRateCap,
+                        pick_list(
+                            &FrameRateCap::ALL[..],
+                            Some(self.frame_rate_cap),
+                            Message::FrameRateCapSelected,
+                        ),
+                    ))
+                    .push(self.focus_frame(
+                        Focusable::UiScale,
+                        pick_list(
+                            &UiScale::ALL[..],
+                            Some(self.ui_scale),
+                            Message::UiScaleSelected,
+                        ),
+                    )),
+            )
+            .push(self.focus_frame(
+                Focusable::Layout,
+                pick_list(
+                    &LayoutSetting::ALL[..],
+                    Some(self.layout),
+                    Message::LayoutSelected,
+                ),
+            ))
+            .push(
+                self.focus_frame(
+                    Focusable::NavigationControls,
+                    checkbox("On-screen navigation", self.canvas.navigation)
+                        .on_toggle(Message::NavigationControlsToggled),
+                ),
+            )
+            .push(
+                self.focus_frame(
+                    Focusable::TargetLock,
+                    checkbox("Lock zoom target", self.canvas.target_lock)
+                        .on_toggle(Message::TargetLockToggled),
+                ),
+            )
+            .push(
+                self.focus_frame(
+                    Focusable::HighContrast,
+                    checkbox("High-contrast controls", self.high_contrast)
+                        .on_toggle(Message::HighContrastToggled),
+                ),
+            )
+            .push_maybe(
+                self.config_error
+                    .as_ref()
+                    .map(|error| text(error).style(Color::WHITE)),
+            )
+    }
+
+    /// Adds the sliders of the coefficients of the cosine palette being edited, under a preview of
+    /// the colours at the stops of its gradient.
+    fn push_cosine_palette_controls<'a>(
+        &'a self,
+        mut column: Column<'a, Message, Theme, iced_widget::renderer::Renderer>,
+        cosine: CosinePalette,
+    ) -> Column<'a, Message, Theme, iced_widget::renderer::Renderer> {
+        column = column.push(Palette::Cosine(cosine).gradient().into_iter().fold(
+            Row::new(),
+            |row, [r, g, b]| {
+                row.push(
+                    container(text(""))
+                        .width(Length::Fill)
+                        .height(COSINE_SWATCH_HEIGHT)
+                        .style(move |_: &Theme| container::Appearance {
+                            background: Some(Color::from_rgb(r, g, b).into()),
+                            ..Default::default()
+                        }),
+                )
+            },
+        ));
+        for coefficient in Coefficient::ALL {
+            let values = cosine.get(coefficient);
+            column = column.push(
+                text(format!(
+                    "{coefficient}: {:.2}, {:.2}, {:.2}",
+                    values[0], values[1], values[2]
+                ))
+                .style(Color::WHITE),
+            );
+            let sliders = (0..3).fold(Row::new().spacing(5), |row, channel| {
+                row.push(
+                    self.focus_frame(
+                        Focusable::CosineCoefficient(coefficient, channel),
+                        slider(coefficient.range(), values[channel], move |value| {
+                            Message::PaletteSelected(Palette::Cosine(cosine.with(
+                                coefficient,
+                                channel,
+                                value,
+                            )))
+                        })
+                        .step(coefficient.step()),
+                    ),
+                )
+            });
+            column = column.push(sliders);
+        }
+        column
+    }
+
+    /// Adds the choice of the layers of the colouring of escape-time fractals and how they are
+    /// blended.
+    fn push_compositor_controls<'a>(
+        &'a self,
+        column: Column<'a, Message, Theme, iced_widget::renderer::Renderer>,
+    ) -> Column<'a, Message, Theme, iced_widget::renderer::Renderer> {
+        let compositor = self.compositor;
+        column
+            .push(text("Base layer").style(Color::WHITE))
+            .push(self.focus_frame(
+                Focusable::BaseLayer,
+                pick_list(&Layer::ALL[..], Some(compositor.base), move |base| {
+                    Message::CompositorChanged(Compositor { base, ..compositor })
+                }),
+            ))
+            .push(text("Overlay").style(Color::WHITE))
+            .push(
+                Row::new()
+                    .spacing(5)
+                    .push(self.focus_frame(
+                        Focusable::OverlayLayer,
+                        pick_list(&Layer::ALL[..], Some(compositor.overlay), move |overlay| {
+                            Message::CompositorChanged(Compositor {
+                                overlay,
+                                ..compositor
+                            })
+                        }),
+                    ))
+                    .push(self.focus_frame(
+                        Focusable::BlendMode,
+                        pick_list(
+                            &BlendMode::ALL[..],
+                            Some(compositor.blend_mode),
+                            move |blend_mode| {
+                                Message::CompositorChanged(Compositor {
+                                    blend_mode,
+                                    ..compositor
+                                })
+                            },
+                        ),
+                    )),
+            )
+            .push(text(format!("Overlay opacity: {:.2}", compositor.opacity)).style(Color::WHITE))
+            .push(
+                self.focus_frame(
+                    Focusable::OverlayOpacity,
+                    slider(
+                        Compositor::OPACITY_RANGE,
+                        compositor.opacity,
+                        move |opacity| Message::CompositorChanged(compositor.with_opacity(opacity)),
+                    )
+                    .step(Compositor::OPACITY_STEP),
+                ),
+            )
+    }
+
+    /// Adds the choice of palette and the adjustments applied after colouring.
+    fn push_palette_controls<'a>(
+        &'a self,
+        mut column: Column<'a, Message, Theme, iced_widget::renderer::Renderer>,
+    ) -> Column<'a, Message, Theme, iced_widget::renderer::Renderer> {
+        column = column
+            .push(self.focus_frame(
+                Focusable::Palette,
+                pick_list(
+                    self.palette_options(),
+                    Some(self.palette),
+                    Message::PaletteSelected,
+                ),
+            ))
+            .push(
+                Row::new()
+                    .spacing(5)
+                    .push(self.focus_frame(
+                        Focusable::RandomPalette,
+                        button("Random").on_press(Message::RandomizePalette),
+                    ))
+                    .push(self.focus_frame(
+                        Focusable::CosinePalette,
+                        button("Cosine").on_press(Message::CreateCosinePalette),
+                    ))
+                    .push(self.focus_frame(
+                        Focusable::UndoGeneratedPalette,
+                        button("Undo").on_press_maybe(self.undo_generated_palette()),
+                    ))
+                    .push(
+                        self.focus_frame(
+                            Focusable::KeepPalette,
+                            button(if self.saved_palettes.contains(&self.palette) {
+                                "Forget"
+                            } else {
+                                "Keep"
+                            })
+                            .on_press_maybe(self.keep_palette()),
+                        ),
+                    ),
+            );
+        // There is no file dialog on the web.
+        if cfg!(not(target_arch = "wasm32")) {
+            column = column.push(button("Palette from image").on_press(Message::LoadPaletteImage));
+        }
+        if let Some(error) = &self.palette_image_error {
+            column = column.push(text(error).style(Color::WHITE));
+        }
+        if let Some(accessibility) = self.palette.accessibility() {
+            column = column.push(text(accessibility.to_string()).style(Color::WHITE));
+        }
+        if let Palette::Cosine(cosine) = self.palette {
+            column = self.push_cosine_palette_controls(column, cosine);
+        }
+        let post_processing = self.post_processing;
+        for adjustment in Adjustment::ALL {
+            let value = post_processing.get(adjustment);
+            column = column
+                .push(text(format!("{adjustment}: {value:.2}")).style(Color::WHITE))
+                .push(
+                    self.focus_frame(
+                        Focusable::PostProcessing(adjustment),
+                        slider(adjustment.range(), value, move |value| {
+                            Message::PostProcessingChanged(post_processing.with(adjustment, value))
+                        })
+                        .step(adjustment.step()),
+                    ),
+                );
+        }
+        column = column.push(self.focus_frame(
+            Focusable::Tonemapper,
+            pick_list(
+                &Tonemapper::ALL[..],
+                Some(post_processing.tonemapper),
+                move |tonemapper| {
+                    Message::PostProcessingChanged(PostProcessing {
+                        tonemapper,
+                        ..post_processing
+                    })
+                },
+            ),
+        ));
+        column = column.push(self.focus_frame(
+            Focusable::DebugView,
+            pick_list(
+                &DebugView::ALL[..],
+                Some(self.debug_view),
+                Message::DebugViewSelected,
+            ),
+        ));
+        if self.current_type.escapes() {
+            column = self.push_compositor_controls(column);
+        }
+        // There is no file dialog on the web.
+        if cfg!(not(target_arch = "wasm32")) {
+            let mut buttons = Row::new().spacing(5);
+            for language in SnippetLanguage::ALL {
+                buttons = buttons.push(
+                    self.focus_frame(
+                        Focusable::ExportSnippet(language),
+                        button(text(format!("Export {language}")))
+                            .on_press(Message::ExportSnippet(language)),
+                    ),
+                );
+            }
+            column = column.push(buttons);
+            match &self.snippet_export {
+                Some(Ok(name)) => {
+                    column = column.push(text(format!("Exported {name}")).style(Color::WHITE));
+                }
+                Some(Err(error)) => column = column.push(text(error).style(Color::WHITE)),
+                None => {}
+            }
+        }
+        column
+    }
+
+    /// The plane to which the dynamic plane checkbox switches: the dynamic plane of the point at
+    /// the centre of the view, or back to the parameter plane.
+    fn toggled_plane(&self) -> Plane {
+        match self.plane {
+            Plane::Parameter => Plane::dynamic_at(self.canvas.transform.centre()),
+            Plane::Dynamic(_) => Plane::Parameter,
+        }
+    }
+
+    /// The critical points of the current rational map, from which its orbits may start.
+    fn critical_points(&self) -> Vec<CriticalPoint> {
+        if self.current_type != FractalType::Rational {
+            return vec![];
+        }
+        critical_points::of_rational_map(
+            &self.current_type.definition().pack(&self.parameter_values),
+        )
+    }
+
+    /// Adds the parameters, variation and iteration budget of the current fractal.
+    fn push_parameter_controls<'a>(
+        &'a self,
+        mut column: Column<'a, Message, Theme, iced_widget::renderer::Renderer>,
+    ) -> Column<'a, Message, Theme, iced_widget::renderer::Renderer> {
+        for (index, (parameter, value)) in self
+            .current_type
+            .definition()
+            .parameters
+            .iter()
+            .zip(&self.parameter_values)
+            .enumerate()
+        {
+            column = column
+                .push(text(format!("{}: {value}", parameter.name)).style(Color::WHITE))
+                .push(
+                    self.focus_frame(
+                        Focusable::Parameter(index),
+                        slider(parameter.min..=parameter.max, *value, move |value| {
+                            Message::ParameterChanged(index, value)
+                        })
+                        .step(parameter.step),
+                    ),
+                );
+        }
+        if self.current_type == FractalType::Rational {
+            let points = self.critical_points();
+            column = column.push(text("Critical point").style(Color::WHITE));
+            column = match points.get(self.critical_point).or(points.first()) {
+                Some(selected) => column.push(self.focus_frame(
+                    Focusable::CriticalPoint,
+                    pick_list(points.clone(), Some(*selected), |point| {
+                        Message::CriticalPointSelected(point.index)
+                    }),
+                )),
+                None => column.push(text("None: orbits start from zero").style(Color::WHITE)),
+            };
+        }
+        if self.current_type.escapes() {
+            column = column.push(
+                self.focus_frame(
+                    Focusable::DynamicPlane,
+                    checkbox("Dynamic plane", self.plane != Plane::Parameter)
+                        .on_toggle(|_| Message::PlaneChanged(self.toggled_plane())),
+                ),
+            );
+        }
+        if let (true, Plane::Dynamic(c)) = (self.current_type.escapes(), self.plane) {
+            column = column
+                .push(text(format!("c: {:.3} {:+.3}i", c.x, c.y)).style(Color::WHITE))
+                .push(
+                    self.focus_frame(
+                        Focusable::FixedCReal,
+                        slider(plane::FIXED_C_PARTS, c.x, move |x| {
+                            Message::PlaneChanged(Plane::Dynamic(Vector2::new(x, c.y)))
+                        })
+                        .step(plane::FIXED_C_STEP),
+                    ),
+                )
+                .push(
+                    self.focus_frame(
+                        Focusable::FixedCImaginary,
+                        slider(plane::FIXED_C_PARTS, c.y, move |y| {
+                            Message::PlaneChanged(Plane::Dynamic(Vector2::new(c.x, y)))
+                        })
+                        .step(plane::FIXED_C_STEP),
+                    ),
+                );
+        }
+        if self.current_type == FractalType::Multibrot {
+            let sweep_speed = self.sweep_speed;
+            column = column
+                .push(self.focus_frame(
+                    Focusable::ExponentSweep,
+                    checkbox("Animate exponent", self.exponent_sweep).on_toggle(move |enabled| {
+                        Message::ExponentSweepChanged(enabled, sweep_speed)
+                    }),
+                ))
+                .push(text(format!("Sweeps per second: {sweep_speed:.2}")).style(Color::WHITE))
+                .push(
+                    self.focus_frame(
+                        Focusable::SweepSpeed,
+                        slider(MIN_SWEEP_SPEED..=MAX_SWEEP_SPEED, sweep_speed, {
+                            let exponent_sweep = self.exponent_sweep;
+                            move |speed| Message::ExponentSweepChanged(exponent_sweep, speed)
+                        })
+                        .step(SWEEP_SPEED_STEP),
+                    ),
+                );
+        }
+        let variation = self.variation;
+        for flag in VariationFlag::ALL {
+            column =
+                column.push(self.focus_frame(
+                    Focusable::Variation(flag),
+                    checkbox(flag.to_string(), variation.get(flag)).on_toggle(move |value| {
+                        Message::VariationChanged(variation.with(flag, value))
+                    }),
+                ));
+        }
+        if variation.spin {
+            let play = if self.time_playing {
+                button("Pause").on_press(Message::TimePlayingToggled(false))
+            } else {
+                button("Play").on_press(Message::TimePlayingToggled(true))
+            };
+            column = column.push(self.focus_frame(Focusable::PlayTime, play));
+        }
+        if self.current_type == FractalType::Mandelbrot {
+            column = column
+                .push(text(format!("Max iterations: {}", self.max_iterations)).style(Color::WHITE))
+                .push(
+                    self.focus_frame(
+                        Focusable::MaxIterations,
+                        slider(
+                            MIN_ITERATIONS..=MAX_ITERATIONS,
+                            self.max_iterations,
+                            Message::MaxIterationsChanged,
+                        )
+                        .step(MAX_ITERATIONS_STEP),
+                    ),
+                )
+                .push(
+                    self.focus_frame(
+                        Focusable::AutoIterations,
+                        checkbox("Auto iterations", self.auto_iterations)
+                            .on_toggle(Message::AutoIterationsToggled),
+                    ),
+                );
+        }
+        column
+    }
+
+    /// Adds the buttons which locate landmarks and estimate properties of the Mandelbrot set, and
+    /// the switch of the histogram.
+    fn push_analysis_controls<'a>(
+        &'a self,
+        mut column: Column<'a, Message, Theme, iced_widget::renderer::Renderer>,
+    ) -> Column<'a, Message, Theme, iced_widget::renderer::Renderer> {
+        if self.current_type != FractalType::Mandelbrot {
+            return column;
+        }
+        column = column
+            .push(self.focus_frame(
+                Focusable::Landmark(LandmarkKind::Center),
+                button("Nearest centre").on_press(Message::LocateLandmark(LandmarkKind::Center)),
+            ))
+            .push(
+                self.focus_frame(
+                    Focusable::Landmark(LandmarkKind::Misiurewicz),
+                    button("Nearest Misiurewicz point")
+                        .on_press(Message::LocateLandmark(LandmarkKind::Misiurewicz)),
+                ),
+            )
+            .push(self.focus_frame(
+                Focusable::FindMinibrot,
+                button("Find minibrot").on_press(Message::FindMinibrot),
+            ));
+        if let Some(minibrot) = self.found_minibrot {
+            column = column.push(text(describe_minibrot(minibrot)).style(Color::WHITE));
+            if let Some(minibrot) = minibrot {
+                column = column.push(self.focus_frame(
+                    Focusable::ZoomToMinibrot,
+                    button("Zoom to minibrot").on_press(Message::ZoomToMinibrot(minibrot)),
+                ));
+            }
+        }
+        column = column
+            .push(self.focus_frame(
+                Focusable::EstimateDimension,
+                button("Boundary dimension").on_press(Message::EstimateDimension),
+            ))
+            .push(self.focus_frame(
+                Focusable::EstimateArea(AreaRegion::View),
+                button("Area in view").on_press(Message::EstimateArea(AreaRegion::View)),
+            ))
+            .push(self.focus_frame(
+                Focusable::EstimateArea(AreaRegion::WholeSet),
+                button("Area of whole set").on_press(Message::EstimateArea(AreaRegion::WholeSet)),
+            ))
+            .push(
+                self.focus_frame(
+                    Focusable::Histogram,
+                    checkbox("Iteration histogram", self.show_histogram)
+                        .on_toggle(Message::HistogramToggled),
+                ),
+            );
+        if let Some(histogram) = &self.histogram {
+            column = column.push(
+                Canvas::new(HistogramChart(histogram))
+                    .width(HISTOGRAM_CHART_WIDTH)
+                    .height(HISTOGRAM_CHART_HEIGHT),
+            );
+        }
+        column
+    }
+
+    /// The input of the seed and a button which advances it, so that renders can be varied
+    /// deliberately and reproduced by the seed which they were rendered with.
+    fn seed_controls(&self) -> Row<'_, Message, Theme, iced_widget::renderer::Renderer> {
+        let next = self
+            .seed
+            .trim()
+            .parse::<u32>()
+            .ok()
+            .map(|seed| Message::SeedEdited(seed.wrapping_add(1).to_string()));
+        Row::new()
+            .spacing(5)
+            .push(text("Seed").style(Color::WHITE))
+            .push(
+                text_input("0", &self.seed)
+                    .on_input(Message::SeedEdited)
+                    .width(SEED_INPUT_WIDTH),
+            )
+            .push(button("Next").on_press_maybe(next))
+    }
+
+    /// Adds the input of the iteration counts at which isolines are drawn.
+    fn push_isoline_controls<'a>(
+        &'a self,
+        column: Column<'a, Message, Theme, iced_widget::renderer::Renderer>,
+    ) -> Column<'a, Message, Theme, iced_widget::renderer::Renderer> {
+        column
+            .push(text("Isolines at iterations").style(Color::WHITE))
+            .push(
+                text_input("e.g. 10, 20, 50", &self.isolines)
+                    .on_input(Message::IsolinesEdited)
+                    .width(HISTOGRAM_CHART_WIDTH),
+            )
+    }
+
+    /// Adds the landmark located and the estimates made last, the switches of compare mode and the
+    /// explorer grid, and the measuring tool with its last measurement.
+    fn push_analysis_results<'a>(
+        &'a self,
+        mut column: Column<'a, Message, Theme, iced_widget::renderer::Renderer>,
+    ) -> Column<'a, Message, Theme, iced_widget::renderer::Renderer> {
+        if let Some(landmark) = self.located_landmark {
+            column = column.push(text(describe_landmark(landmark)).style(Color::WHITE));
+        }
+        if let Some(estimate) = self.dimension_estimate {
+            let description = match estimate {
+                Some(DimensionEstimate {
+                    dimension,
+                    confidence,
+                }) => format!("Boundary dimension\n{dimension:.3} ± {confidence:.3} (95%)"),
+                None => "No boundary in view".into(),
+            };
+            column = column.push(text(description).style(Color::WHITE));
+        }
+        if let Some((region, estimate)) = self.area_estimate {
+            let label = match region {
+                AreaRegion::View => "Area in view",
+                AreaRegion::WholeSet => "Area of whole set",
+            };
+            column = column.push(
+                text(format!(
+                    "{label}\n{:.6} ± {:.6}",
+                    estimate.area, estimate.standard_error
+                ))
+                .style(Color::WHITE),
+            );
+        }
+        column = column
+            .push(
+                self.focus_frame(
+                    Focusable::Compare,
+                    checkbox("Compare with current settings", self.compare)
+                        .on_toggle(Message::CompareToggled),
+                ),
+            )
+            .push(
+                self.focus_frame(
+                    Focusable::ExplorerGrid,
+                    checkbox("Explorer grid", self.canvas.explorer_grid)
+                        .on_toggle(Message::ExplorerGridToggled),
+                ),
+            );
+        if self.canvas.explorer_grid {
+            column = column.push(
+                text(format!(
+                    "{}\nClick a cell to adopt its settings",
+                    ExplorerGrid::of(self.current_type).describe()
+                ))
+                .style(Color::WHITE),
+            );
+        }
+        column = column.push(self.focus_frame(
+            Focusable::Measure,
+            checkbox("Measure", self.canvas.measuring).on_toggle(Message::MeasuringToggled),
+        ));
+        if self.canvas.measuring {
+            let description = match self.canvas.measurement {
+                Some(measurement) => measurement.describe(),
+                None => "Click two points to measure between them".into(),
+            };
+            column = column.push(text(description).style(Color::WHITE));
+        }
+        column
+    }
+
+    /// Adds the text of the next note, the switch which places notes with the mouse and the buttons
+    /// which remove them.
+    fn push_annotation_controls<'a>(
+        &'a self,
+        mut column: Column<'a, Message, Theme, iced_widget::renderer::Renderer>,
+    ) -> Column<'a, Message, Theme, iced_widget::renderer::Renderer> {
+        column = column
+            .push(text("Notes").style(Color::WHITE))
+            .push(
+                text_input("Text of the next note", &self.annotation_text)
+                    .on_input(Message::AnnotationTextEdited)
+                    .width(HISTOGRAM_CHART_WIDTH),
+            )
+            .push(
+                self.focus_frame(
+                    Focusable::Annotate,
+                    checkbox("Place notes", self.canvas.annotating)
+                        .on_toggle(Message::AnnotatingToggled),
+                ),
+            );
+        if self.canvas.annotating {
+            column = column.push(
+                text("Click to place the note, or drag from what it\ndescribes to draw an arrow")
+                    .style(Color::WHITE),
+            );
+        }
+        if self.canvas.annotations.is_empty() {
+            return column;
+        }
+        column.push(
+            Row::new()
+                .spacing(5)
+                .push(self.focus_frame(
+                    Focusable::RemoveAnnotation,
+                    button("Remove last").on_press(Message::RemoveAnnotation),
+                ))
+                .push(self.focus_frame(
+                    Focusable::ClearAnnotations,
+                    button("Clear notes").on_press(Message::ClearAnnotations),
+                )),
+        )
+    }
+
+    /// Adds the controls of the morph, the dive and the modulation matrix.
+    fn push_animation_controls<'a>(
+        &'a self,
+        mut column: Column<'a, Message, Theme, iced_widget::renderer::Renderer>,
+    ) -> Column<'a, Message, Theme, iced_widget::renderer::Renderer> {
+        column = column.push(text("Morph").style(Color::WHITE)).push(
+            Row::new()
+                .spacing(5)
+                .push(self.focus_frame(
+                    Focusable::SaveMorph(MorphEnd::Start),
+                    button("Set start").on_press(Message::SaveMorphEnd(MorphEnd::Start)),
+                ))
+                .push(self.focus_frame(
+                    Focusable::SaveMorph(MorphEnd::End),
+                    button("Set end").on_press(Message::SaveMorphEnd(MorphEnd::End)),
+                )),
+        );
+        if self.morph_saved == (true, true) {
+            column = column.push(self.focus_frame(
+                Focusable::Morph,
+                slider(0.0..=1.0, self.morph, Message::MorphChanged).step(MORPH_STEP),
+            ));
+        }
+        column = self.push_history_controls(column);
+        // There is no file dialog on the web.
+        if cfg!(not(target_arch = "wasm32")) {
+            column = column.push(self.focus_frame(
+                Focusable::StartPresentation,
+                button("Present tour").on_press(Message::StartPresentation),
+            ));
+        }
+        if let Some(error) = &self.presentation_error {
+            column = column.push(text(error).style(Color::WHITE));
+        }
+        column = column.push(self.dive_controls());
+        column = column.push(text("Modulation").style(Color::WHITE));
+        for (slot, lfo) in self.modulation.lfos.into_iter().enumerate() {
+            column = column.push(
+                Row::new()
+                    .spacing(5)
+                    .push(self.focus_frame(
+                        Focusable::ModulationTarget(slot),
+                        pick_list(
+                            ModulationTarget::available(self.current_type),
+                            Some(lfo.target),
+                            move |target| Message::ModulationChanged(slot, Lfo { target, ..lfo }),
+                        ),
+                    ))
+                    .push(pick_list(
+                        &Waveform::ALL[..],
+                        Some(lfo.waveform),
+                        move |waveform| Message::ModulationChanged(slot, Lfo { waveform, ..lfo }),
+                    )),
+            );
+            if lfo.target == ModulationTarget::Off {
+                continue;
+            }
+            column = column
+                .push(
+                    text(format!(
+                        "{:.2} Hz, depth {:.0}%",
+                        lfo.frequency,
+                        lfo.depth * 100.0
+                    ))
+                    .style(Color::WHITE),
+                )
+                .push(
+                    slider(
+                        MIN_LFO_FREQUENCY..=MAX_LFO_FREQUENCY,
+                        lfo.frequency,
+                        move |frequency| Message::ModulationChanged(slot, Lfo { frequency, ..lfo }),
+                    )
+                    .step(LFO_FREQUENCY_STEP),
+                )
+                .push(
+                    slider(0.0..=1.0, lfo.depth, move |depth| {
+                        Message::ModulationChanged(slot, Lfo { depth, ..lfo })
+                    })
+                    .step(0.01),
+                );
+        }
+        column
+    }
+
+    /// Adds the timeline of the history, with which its changes can be browsed, and the buttons
+    /// which replay and export it.
+    fn push_history_controls<'a>(
+        &'a self,
+        mut column: Column<'a, Message, Theme, iced_widget::renderer::Renderer>,
+    ) -> Column<'a, Message, Theme, iced_widget::renderer::Renderer> {
+        column = column.push(text("History").style(Color::WHITE));
+        let mut buttons = Row::new().spacing(5);
+        if self.history_len > 1 {
+            column = column
+                .push(
+                    text(format!(
+                        "Change {} of {}",
+                        self.history_position + 1,
+                        self.history_len
+                    ))
+                    .style(Color::WHITE),
+                )
+                .push(self.focus_frame(
+                    Focusable::History,
+                    slider(
+                        0..=(self.history_len - 1) as u32,
+                        self.history_position as u32,
+                        |index| Message::HistoryBrowsed(index as usize),
+                    ),
+                ));
+            let replay = if self.replaying_history {
+                button("Stop replay").on_press(Message::HistoryReplayToggled(false))
+            } else {
+                button("Replay").on_press(Message::HistoryReplayToggled(true))
+            };
+            buttons = buttons.push(self.focus_frame(Focusable::ReplayHistory, replay));
+        }
+        // There is no file dialog on the web.
+        if cfg!(not(target_arch = "wasm32")) {
+            buttons = buttons.push(self.focus_frame(
+                Focusable::ExportHistory,
+                button("Export").on_press(Message::ExportHistory),
+            ));
+        }
+        column = column.push(buttons);
+        match &self.history_export {
+            Some(Ok(name)) => column.push(text(format!("Exported {name}")).style(Color::WHITE)),
+            Some(Err(error)) => column.push(text(error).style(Color::WHITE)),
+            None => column,
+        }
+    }
+
+    /// Adds the button which shows the About panel, and the panel while it is shown.
+    fn push_about<'a>(
+        &'a self,
+        column: Column<'a, Message, Theme, iced_widget::renderer::Renderer>,
+    ) -> Column<'a, Message, Theme, iced_widget::renderer::Renderer> {
+        let column = column.push(self.focus_frame(
+            Focusable::About,
+            button("About").on_press(Message::AboutToggled),
+        ));
+        match &self.about {
+            Some(about) => column.push(text(about).style(Color::WHITE)),
+            None => column,
+        }
+    }
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
-pub enum FractalType {
-    Mandelbrot,
-    Newton,
+/// The distance the view is panned by an arrow key.
+const KEY_PAN_PIXELS: f32 = 50.0;
+
+/// The scroll distance to which a press of + or - corresponds.
+const KEY_ZOOM: f32 = 4.0;
+
+const MAX_ITERATIONS_STEP: u32 = 100;
+
+/// The range, default and resolution of the speed of the exponent animation, in sweeps there and
+/// back per second.
+const MIN_SWEEP_SPEED: f32 = 0.01;
+const MAX_SWEEP_SPEED: f32 = 1.0;
+const DEFAULT_SWEEP_SPEED: f32 = 0.05;
+
+/// The depth to which the curves of the vector mode first recurse.
+const DEFAULT_CURVE_DEPTH: u32 = 4;
+const SWEEP_SPEED_STEP: f32 = 0.01;
+
+/// The ranges of the centre and half size of the orbit trap on the plane, and the resolution of
+/// their sliders.
+const TRAP_CENTRE_RANGE: std::ops::RangeInclusive<f32> = -2.0..=2.0;
+const TRAP_HALF_SIZE_RANGE: std::ops::RangeInclusive<f32> = 0.01..=2.0;
+const TRAP_STEP: f32 = 0.01;
+
+/// The resolution of the morph slider, and the distance it moves per arrow key.
+const MORPH_STEP: f32 = 0.001;
+const MORPH_KEY_STEP: f32 = 0.05;
+
+/// The range of the frequency sliders of the LFOs in the modulation matrix, in Hz.
+const MIN_LFO_FREQUENCY: f32 = 0.01;
+const MAX_LFO_FREQUENCY: f32 = 5.0;
+const LFO_FREQUENCY_STEP: f32 = 0.01;
+
+/// The width of the input of the name of a workspace.
+const WORKSPACE_NAME_WIDTH: f32 = 150.0;
+
+/// The height of the preview of each colour of a cosine palette.
+const COSINE_SWATCH_HEIGHT: f32 = 16.0;
+
+/// How many palettes replaced by generated ones can be returned to.
+const MAX_REPLACED_PALETTES: usize = 32;
+
+/// The width of the input of the seed, which fits the largest seed.
+const SEED_INPUT_WIDTH: f32 = 100.0;
+
+/// The padding of the toolbar and panels of the touch layout, which is also the gap between the
+/// buttons of the toolbar.
+const TOUCH_PADDING: f32 = 10.0;
+/// The height of the panels of the touch layout, which scroll if their controls need more.
+const TOUCH_PANEL_HEIGHT: f32 = 320.0;
+
+/// The colour of the frame around the control with the keyboard focus.
+const FOCUS_COLOUR: Color = Color::from_rgb(1.0, 0.85, 0.0);
+
+/// A theme with a black background, white text and a saturated yellow for interactive elements,
+/// for users who find the dark theme hard to read.
+pub(super) fn high_contrast_theme() -> Theme {
+    Theme::custom(
+        "High contrast".into(),
+        iced::theme::Palette {
+            background: Color::BLACK,
+            text: Color::WHITE,
+            primary: Color::from_rgb(1.0, 0.85, 0.0),
+            success: Color::from_rgb(0.0, 1.0, 0.5),
+            danger: Color::from_rgb(1.0, 0.3, 0.3),
+        },
+    )
 }
 
-impl FractalType {
-    const ALL: [FractalType; 2] = [Self::Mandelbrot, Self::Newton];
+/// The text of the About panel, with the version, the adapter on which the fractal is rendered
+/// and credits.
+fn describe_application(adapter_info: &wgpu::AdapterInfo) -> String {
+    let wgpu::AdapterInfo {
+        name,
+        backend,
+        driver,
+        driver_info,
+        ..
+    } = adapter_info;
+    format!(
+        "{} {}\nRendering on {name} ({backend:?})\nDriver: {driver} {driver_info}\n\
+         Built with wgpu, winit and iced",
+        env!("CARGO_PKG_NAME"),
+        env!("CARGO_PKG_VERSION")
+    )
 }
 
-impl Display for FractalType {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            FractalType::Mandelbrot => write!(f, "Mandelbrot"),
-            FractalType::Newton => write!(f, "Newton"),
-        }
+fn describe_landmark(landmark: Option<Landmark>) -> String {
+    match landmark {
+        Some(Landmark {
+            position,
+            preperiod: 0,
+            period,
+        }) => format!(
+            "Period {period} centre at\n{:.10}{:+.10}i",
+            position.x, position.y
+        ),
+        Some(Landmark {
+            position,
+            preperiod,
+            period,
+        }) => format!(
+            "Misiurewicz point M{preperiod},{period} at\n{:.10}{:+.10}i",
+            position.x, position.y
+        ),
+        None => "Nothing found in view".into(),
     }
 }
 
-impl Controls {
-    pub(super) fn new() -> Self {
-        Self {
-            canvas: FractalCanvas::new(),
-            current_type: FractalType::Mandelbrot,
-            last_message: Cell::new(None),
+fn describe_minibrot(minibrot: Option<Minibrot>) -> String {
+    match minibrot {
+        Some(minibrot) => {
+            let nucleus = minibrot.nucleus();
+            format!(
+                "Period {} minibrot at\n{:.10}{:+.10}i\nof size {:.2e}",
+                minibrot.period,
+                nucleus.x,
+                nucleus.y,
+                minibrot.size.magnitude()
+            )
         }
-    }
-
-    pub(super) fn take_last_message(&self) -> Option<Message> {
-        self.last_message.take()
+        None => "No minibrot in view".into(),
     }
 }
 
-impl Program for Controls {
-    type Renderer = iced_widget::renderer::Renderer;
-    type Message = Message;
-    type Theme = Theme;
+const HISTOGRAM_CHART_WIDTH: f32 = 200.0;
+const HISTOGRAM_CHART_HEIGHT: f32 = 80.0;
 
-    fn update(&mut self, message: Self::Message) -> iced::Command<Self::Message> {
-        match message {
-            Message::Canvas(CanvasMessage::UpdateViewTransform(view_transform)) => {
-                self.canvas.view_transform = view_transform;
-            }
-            Message::Canvas(_) => {}
-            Message::FractalTypeSelected(selected_type) => {
-                self.current_type = selected_type;
+/// Plots the escape time histogram with logarithmically scaled bars, so that sparsely populated
+/// bins remain visible. The bar on the right counts the samples which did not escape.
+struct HistogramChart<'a>(&'a IterationHistogram);
+
+impl<'a> canvas::Program<Message, Theme, iced_widget::renderer::Renderer> for HistogramChart<'a> {
+    type State = ();
+
+    fn draw(
+        &self,
+        _state: &Self::State,
+        renderer: &iced_widget::renderer::Renderer,
+        _theme: &Theme,
+        bounds: Rectangle,
+        _cursor: Cursor,
+    ) -> Vec<Geometry> {
+        let IterationHistogram { escaped, interior } = self.0;
+        let bars: Vec<(u32, Color)> = escaped
+            .iter()
+            .map(|count| (*count, Color::WHITE))
+            .chain([(*interior, Color::from_rgb(0.4, 0.6, 1.0))])
+            .collect();
+        let scale = |count: u32| (count as f32).ln_1p();
+        let max = bars
+            .iter()
+            .map(|(count, _)| scale(*count))
+            .fold(0.0, f32::max);
+        let bar_width = bounds.width / bars.len() as f32;
+        let mut frame = Frame::new(renderer, bounds.size());
+        if max > 0.0 {
+            for (index, (count, colour)) in bars.into_iter().enumerate() {
+                let height = scale(count) / max * bounds.height;
+                frame.fill_rectangle(
+                    Point::new(index as f32 * bar_width, bounds.height - height),
+                    iced::Size::new(bar_width, height),
+                    colour,
+                );
             }
         }
-        self.last_message.set(Some(message));
-        iced::Command::none()
+        vec![frame.into_geometry()]
     }
+}
 
-    fn view(&self) -> Element<'_, Self::Message, Self::Theme, Self::Renderer> {
-        Row::new()
-            .push(self.canvas.view().map(Message::Canvas))
-            .push(pick_list(
-                &FractalType::ALL[..],
-                Some(self.current_type),
-                Message::FractalTypeSelected,
-            ))
-            .into()
+const SPINNER_SIZE: f32 = 16.0;
+
+/// An arc which rotates by one turn as its phase goes from 0 to 1.
+struct Spinner(f32);
+
+impl canvas::Program<Message, Theme, iced_widget::renderer::Renderer> for Spinner {
+    type State = ();
+
+    fn draw(
+        &self,
+        _state: &Self::State,
+        renderer: &iced_widget::renderer::Renderer,
+        _theme: &Theme,
+        bounds: Rectangle,
+        _cursor: Cursor,
+    ) -> Vec<Geometry> {
+        let start_angle = self.0.fract() * TAU;
+        let arc = Path::new(|builder| {
+            builder.arc(path::Arc {
+                center: Point::new(bounds.width / 2.0, bounds.height / 2.0),
+                radius: bounds.width.min(bounds.height) / 2.0 - 2.0,
+                start_angle: Radians(start_angle),
+                end_angle: Radians(start_angle + 0.75 * TAU),
+            })
+        });
+        let mut frame = Frame::new(renderer, bounds.size());
+        frame.stroke(
+            &arc,
+            Stroke::default().with_color(Color::WHITE).with_width(2.0),
+        );
+        vec![frame.into_geometry()]
     }
 }
 
 struct FractalCanvas {
-    view_transform: Matrix3<f32>,
+    transform: ViewTransform,
+    /// Whether the keyboard shortcuts are listed on top of the fractal.
+    show_help: bool,
+    /// The keys which the configuration file binds to actions.
+    keymap: Keymap,
+    /// The horizontal position of the divider between the current and compared settings, while
+    /// comparing.
+    divider: Option<f32>,
+    probe: Option<Probe>,
+    /// The precision shown in the status bar, and the mode which was active before the last
+    /// automatic switch.
+    precision: Option<Precision>,
+    switched_from: Option<NumericMode>,
+    /// The jobs running on the worker pool, which are listed after the precision.
+    jobs: Vec<JobStatus>,
+    /// The GPU memory held by the view, which is shown after the precision.
+    gpu_memory: Option<GpuMemoryUsage>,
+    /// Whether the joystick and buttons of the on-screen navigation are shown.
+    navigation: bool,
+    /// Whether zooms centre on the locked target, which a right click moves, rather than on the
+    /// cursor, which is then shown as a crosshair.
+    target_lock: bool,
+    /// The point of the plane on which zooms centre while the target is locked.
+    target: Option<Vector2<f64>>,
+    /// Whether the view is replaced by the explorer grid, whose cells a click picks.
+    explorer_grid: bool,
+    /// The caption of the stop of the presentation, shown along the bottom.
+    caption: Option<String>,
+    /// The notes pinned to the plane, drawn over the fractal.
+    annotations: Vec<Annotation>,
+    /// Whether the left mouse button places notes rather than panning the view.
+    annotating: bool,
+    /// Whether clicks on the view pick the points between which to measure.
+    measuring: bool,
+    /// The last measurement, drawn while measuring.
+    measurement: Option<Measurement>,
+}
+
+/// A rectangle of the canvas which was probed, and the statistics of the fractal within it once
+/// they arrive.
+struct Probe {
+    rectangle: Rectangle,
+    statistics: Option<Option<RegionStatistics>>,
 }
 
 #[derive(Debug, Clone)]
 pub(super) enum CanvasMessage {
     Pan(f32, f32),
     Zoom(f32, Point),
-    UpdateViewTransform(Matrix3<f32>),
+    /// Scales the view by the given factor about the given point, as two fingers pinch it.
+    Pinch(f32, Point),
+    /// How far the joystick of the on-screen navigation is pushed, up to one in any direction,
+    /// which pans the view at a proportional speed until it is released to zero.
+    Joystick(Vector2<f32>),
+    /// Returns to the initial view.
+    Home,
+    /// Moves the locked zoom target to the given point.
+    LockTarget(Point),
+    /// The cursor rests over the given point of the canvas, or has left it.
+    Hover(Option<Point>),
+    /// Adopts the settings of the cell of the explorer grid at the given point.
+    PickCell(Point),
+    UpdateViewTransform(ViewTransform),
+    /// A key press bound to an action, with the centre of the canvas on which to zoom.
+    Key(KeyAction, Point),
+    /// Moves the divider of the comparison to the given horizontal position.
+    MoveDivider(f32),
+    /// Asks for the statistics of the rectangle between the given corners.
+    Probe(Point, Point),
+    /// Shows the distance and angle between two points clicked on the plane.
+    Measure(Measurement),
+    /// Places a note at the given point of the plane, with an arrow to the other if there is one.
+    Annotate(Vector2<f64>, Option<Vector2<f64>>),
 }
 
 #[derive(Debug, Default)]
 struct State {
     mode: Mode,
+    modifiers: keyboard::Modifiers,
+    /// The fingers on the canvas and where they last were, of which the first two count.
+    fingers: Vec<(Finger, Point)>,
+    steering: Option<Steering>,
+    /// The point of the plane clicked first while measuring, until the second is.
+    measure_start: Option<Vector2<f64>>,
+}
+
+/// The joystick of the on-screen navigation while it is held, by the given finger or else by the
+/// mouse.
+#[derive(Debug)]
+struct Steering {
+    finger: Option<Finger>,
+    deflection: Vector2<f32>,
 }
 
 #[derive(Debug, Default)]
@@ -110,17 +3095,60 @@ enum Mode {
     Panning {
         start_position: iced::Point,
     },
+    MovingDivider,
+    /// Dragging out the rectangle to probe from the given corner.
+    Probing {
+        start_position: Point,
+    },
+    /// Dragging out the arrow of a note from the point at which it points.
+    Annotating {
+        start_position: Point,
+    },
 }
 
 impl FractalCanvas {
     fn new() -> Self {
         Self {
-            view_transform: Matrix3::from_scale(2.0)
-                * Matrix3::from_translation(Vector2::new(-0.25, 0.0)),
+            transform: ViewTransform::new(
+                Matrix3::from_scale(2.0) * Matrix3::from_translation(Vector2::new(-0.25, 0.0)),
+            ),
+            show_help: false,
+            keymap: Keymap::default(),
+            divider: None,
+            probe: None,
+            precision: None,
+            switched_from: None,
+            jobs: vec![],
+            gpu_memory: None,
+            navigation: LayoutSetting::default().is_touch(),
+            target_lock: false,
+            target: None,
+            explorer_grid: false,
+            caption: None,
+            annotations: vec![],
+            annotating: false,
+            measuring: false,
+            measurement: None,
         }
     }
 
-    fn view(&self) -> Element<CanvasMessage, Theme, iced_widget::renderer::Renderer> {
+    /// Where the given point of the plane lies on a canvas of the given size.
+    fn to_canvas(&self, point: Vector2<f64>, size: Size) -> Point {
+        transform::to_screen(self.transform.offset_of(point), size)
+    }
+
+    /// The point of the plane at the given point of a canvas of the given size, the inverse of
+    /// [`Self::to_canvas`].
+    fn to_plane(&self, point: Point, size: Size) -> Vector2<f64> {
+        self.transform.point_at(transform::from_screen(point, size))
+    }
+
+    /// The distance on the plane between adjacent pixels of a canvas of the given width.
+    fn pixel_size(&self, width: f32) -> f64 {
+        self.transform.half_width() * 2.0 / width as f64
+    }
+
+    fn view(&self) -> Element<'_, CanvasMessage, Theme, iced_widget::renderer::Renderer> {
         Canvas::new(self)
             .width(Length::Fill)
             .height(Length::Fill)
@@ -133,31 +3161,107 @@ impl canvas::Program<CanvasMessage, Theme, iced_widget::renderer::Renderer> for
 
     fn draw(
         &self,
-        _state: &Self::State,
+        state: &Self::State,
         renderer: &iced_widget::renderer::Renderer,
         _theme: &Theme,
         bounds: Rectangle,
         cursor: Cursor,
     ) -> Vec<Geometry> {
-        if let Some(cursor_position) = cursor.position() {
-            let transfromed_position = self.view_transform
-                * Vector3::new(
-                    cursor_position.x / bounds.width * 2.0 - 1.0,
-                    cursor_position.y / bounds.height * 2.0 - 1.0,
-                    1.0,
-                );
-            let mut position_text: Text = format!(
-                "{:.4}+{:.4}i",
-                transfromed_position.x, -transfromed_position.y
+        let mut frame = Frame::new(renderer, bounds.size());
+        if let (Mode::Probing { start_position }, Some(position)) =
+            (&state.mode, cursor.position_in(bounds))
+        {
+            stroke_rectangle(&mut frame, *start_position, position);
+        }
+        if !self.explorer_grid {
+            annotation::draw(&mut frame, &self.annotations, &self.transform);
+        }
+        if self.measuring {
+            match (state.measure_start, cursor.position_in(bounds)) {
+                (Some(start), Some(position)) => Measurement {
+                    start,
+                    end: self.to_plane(position, bounds.size()),
+                }
+                .draw(&mut frame, &self.transform),
+                _ => {
+                    if let Some(measurement) = self.measurement {
+                        measurement.draw(&mut frame, &self.transform);
+                    }
+                }
+            }
+        }
+        if let (Mode::Annotating { start_position }, Some(position)) =
+            (&state.mode, cursor.position_in(bounds))
+        {
+            frame.stroke(
+                &Path::line(*start_position, position),
+                Stroke::default().with_color(Color::WHITE).with_width(1.0),
+            );
+        }
+        if let Some(probe) = &self.probe {
+            draw_probe(&mut frame, probe);
+        }
+        if let Some(x) = self.divider {
+            frame.stroke(
+                &Path::line(Point::new(x, 0.0), Point::new(x, bounds.height)),
+                Stroke::default()
+                    .with_color(Color::WHITE)
+                    .with_width(DIVIDER_WIDTH),
+            );
+            frame.fill_text(Text {
+                content: "Compared".into(),
+                position: Point::new(x + 10.0, bounds.height - 30.0),
+                color: Color::WHITE,
+                ..Text::default()
+            });
+        }
+        if self.target_lock {
+            if let Some(position) = cursor.position_in(bounds) {
+                draw_crosshair(&mut frame, position, bounds.size());
+            }
+            if let Some(target) = self.target {
+                draw_target(&mut frame, self.to_canvas(target, bounds.size()));
+            }
+        }
+        if let Some(cursor_position) = cursor.position_in(bounds) {
+            let mut position_text: Text = describe_coordinates(
+                self.to_plane(cursor_position, bounds.size()),
+                self.pixel_size(bounds.width),
             )
             .into();
             position_text.color = Color::WHITE;
-            let mut frame = Frame::new(renderer, bounds.size());
             frame.fill_text(position_text);
-            vec![frame.into_geometry()]
-        } else {
-            vec![]
         }
+        if let Some(precision) = self.precision {
+            frame.fill_text(Text {
+                content: format!(
+                    "{}{}{}",
+                    describe_precision(precision, self.switched_from),
+                    self.gpu_memory
+                        .map(|usage| format!(" · GPU memory {} MiB", usage.total_mib()))
+                        .unwrap_or_default(),
+                    describe_jobs(&self.jobs)
+                ),
+                position: Point::new(STATUS_BAR_MARGIN, bounds.height - STATUS_BAR_MARGIN),
+                color: Color::WHITE,
+                vertical_alignment: iced::alignment::Vertical::Bottom,
+                ..Text::default()
+            });
+        }
+        if self.navigation {
+            let deflection = state
+                .steering
+                .as_ref()
+                .map_or(Vector2::new(0.0, 0.0), |steering| steering.deflection);
+            NavigationLayout::within(bounds.size()).draw(&mut frame, deflection);
+        }
+        if let Some(caption) = &self.caption {
+            draw_caption(&mut frame, caption, bounds.size());
+        }
+        if self.show_help {
+            draw_help(&mut frame, &self.keymap.help());
+        }
+        vec![frame.into_geometry()]
     }
 
     fn update(
@@ -170,10 +3274,38 @@ impl canvas::Program<CanvasMessage, Theme, iced_widget::renderer::Renderer> for
         match event {
             Event::Mouse(event) => match event {
                 mouse::Event::CursorEntered => (Status::Ignored, None),
-                mouse::Event::CursorLeft => (Status::Ignored, None),
+                mouse::Event::CursorLeft => (Status::Ignored, Some(CanvasMessage::Hover(None))),
                 mouse::Event::CursorMoved { position } => {
+                    let local = Point::new(position.x - bounds.x, position.y - bounds.y);
+                    if let Some(message) = steer(state, None, local, bounds) {
+                        return (Status::Captured, Some(message));
+                    }
                     let (result, new_mode) = match state.mode {
-                        Mode::None => ((Status::Ignored, None), Mode::None),
+                        Mode::None => (
+                            (
+                                Status::Ignored,
+                                Some(CanvasMessage::Hover(
+                                    cursor.position_in(bounds).map(|_| position),
+                                )),
+                            ),
+                            Mode::None,
+                        ),
+                        Mode::Probing { start_position } => {
+                            ((Status::Captured, None), Mode::Probing { start_position })
+                        }
+                        Mode::Annotating { start_position } => (
+                            (Status::Captured, None),
+                            Mode::Annotating { start_position },
+                        ),
+                        Mode::MovingDivider => (
+                            (
+                                Status::Captured,
+                                Some(CanvasMessage::MoveDivider(
+                                    (position.x - bounds.x).clamp(0.0, bounds.width),
+                                )),
+                            ),
+                            Mode::MovingDivider,
+                        ),
                         Mode::Panning { start_position } => (
                             (
                                 Status::Captured,
@@ -192,7 +3324,53 @@ impl canvas::Program<CanvasMessage, Theme, iced_widget::renderer::Renderer> for
                 }
                 mouse::Event::ButtonPressed(button) => {
                     if button == Button::Left {
-                        if let Some(position) = cursor.position() {
+                        let on_divider = |x: f32| {
+                            cursor.position_in(bounds).is_some_and(|position| {
+                                (position.x - x).abs() <= DIVIDER_GRAB_DISTANCE
+                            })
+                        };
+                        let navigation = cursor
+                            .position_in(bounds)
+                            .filter(|_| self.navigation)
+                            .and_then(|position| press_navigation(state, position, bounds, None));
+                        if let Some(message) = navigation {
+                            (Status::Captured, Some(message))
+                        } else if let (true, Some(position)) =
+                            (self.explorer_grid, cursor.position_over(bounds))
+                        {
+                            (Status::Captured, Some(CanvasMessage::PickCell(position)))
+                        } else if let (true, Some(position)) =
+                            (self.measuring, cursor.position_in(bounds))
+                        {
+                            let point = self.to_plane(position, bounds.size());
+                            match state.measure_start.take() {
+                                Some(start) => (
+                                    Status::Captured,
+                                    Some(CanvasMessage::Measure(Measurement { start, end: point })),
+                                ),
+                                None => {
+                                    state.measure_start = Some(point);
+                                    (Status::Captured, None)
+                                }
+                            }
+                        } else if let (true, Some(position)) =
+                            (self.annotating, cursor.position_in(bounds))
+                        {
+                            state.mode = Mode::Annotating {
+                                start_position: position,
+                            };
+                            (Status::Captured, None)
+                        } else if let (true, Some(position)) =
+                            (state.modifiers.shift(), cursor.position_in(bounds))
+                        {
+                            state.mode = Mode::Probing {
+                                start_position: position,
+                            };
+                            (Status::Captured, None)
+                        } else if self.divider.is_some_and(on_divider) {
+                            state.mode = Mode::MovingDivider;
+                            (Status::Captured, None)
+                        } else if let Some(position) = cursor.position() {
                             state.mode = Mode::Panning {
                                 start_position: position,
                             };
@@ -200,14 +3378,43 @@ impl canvas::Program<CanvasMessage, Theme, iced_widget::renderer::Renderer> for
                         } else {
                             (Status::Ignored, None)
                         }
+                    } else if button == Button::Right && self.target_lock {
+                        match cursor.position_over(bounds) {
+                            Some(position) => {
+                                (Status::Captured, Some(CanvasMessage::LockTarget(position)))
+                            }
+                            None => (Status::Ignored, None),
+                        }
                     } else {
                         (Status::Ignored, None)
                     }
                 }
                 mouse::Event::ButtonReleased(button) => {
                     if button == Button::Left {
+                        if let Some(message) = release_joystick(state, None) {
+                            return (Status::Captured, Some(message));
+                        }
+                        let message = match (&state.mode, cursor.position_in(bounds)) {
+                            (Mode::Probing { start_position }, Some(position))
+                                if start_position.x != position.x
+                                    && start_position.y != position.y =>
+                            {
+                                Some(CanvasMessage::Probe(*start_position, position))
+                            }
+                            (Mode::Annotating { start_position }, Some(position)) => {
+                                let size = bounds.size();
+                                let target = (start_position.distance(position)
+                                    >= MIN_ARROW_LENGTH)
+                                    .then(|| self.to_plane(*start_position, size));
+                                Some(CanvasMessage::Annotate(
+                                    self.to_plane(position, size),
+                                    target,
+                                ))
+                            }
+                            _ => None,
+                        };
                         state.mode = Mode::None;
-                        (Status::Captured, None)
+                        (Status::Captured, message)
                     } else {
                         (Status::Ignored, None)
                     }
@@ -226,8 +3433,685 @@ impl canvas::Program<CanvasMessage, Theme, iced_widget::renderer::Renderer> for
                     (Status::Captured, Some(CanvasMessage::Zoom(y, on_point)))
                 }
             },
-            Event::Touch(_) => (Status::Ignored, None),
+            Event::Touch(event) => handle_touch(state, event, bounds, self.navigation),
+            Event::Keyboard(keyboard::Event::KeyPressed { key, modifiers, .. }) => {
+                match self.keymap.action_for(key.as_ref(), modifiers) {
+                    Some(action) => (
+                        Status::Captured,
+                        Some(CanvasMessage::Key(action, bounds.center())),
+                    ),
+                    None => (Status::Ignored, None),
+                }
+            }
+            Event::Keyboard(keyboard::Event::ModifiersChanged(modifiers)) => {
+                state.modifiers = modifiers;
+                (Status::Ignored, None)
+            }
             Event::Keyboard(_) => (Status::Ignored, None),
         }
     }
 }
+
+/// Pans the view while one finger drags it, and zooms it about the midpoint of two fingers while
+/// they pinch it.
+fn handle_touch(
+    state: &mut State,
+    event: touch::Event,
+    bounds: Rectangle,
+    navigation: bool,
+) -> (Status, Option<CanvasMessage>) {
+    let local = |position: Point| Point::new(position.x - bounds.x, position.y - bounds.y);
+    match event {
+        touch::Event::FingerPressed { id, position } => {
+            if !bounds.contains(position) {
+                return (Status::Ignored, None);
+            }
+            if navigation && state.steering.is_none() {
+                if let Some(message) = press_navigation(state, local(position), bounds, Some(id)) {
+                    return (Status::Captured, Some(message));
+                }
+            }
+            if state.fingers.len() >= 2 {
+                return (Status::Ignored, None);
+            }
+            state.fingers.push((id, position));
+            (Status::Captured, None)
+        }
+        touch::Event::FingerMoved { id, position } => {
+            if let Some(message) = steer(state, Some(id), local(position), bounds) {
+                return (Status::Captured, Some(message));
+            }
+            let Some(index) = state.fingers.iter().position(|(finger, _)| *finger == id) else {
+                return (Status::Ignored, None);
+            };
+            let previous = std::mem::replace(&mut state.fingers[index].1, position);
+            let message = match state.fingers[..] {
+                [_] => Some(CanvasMessage::Pan(
+                    previous.x - position.x,
+                    position.y - previous.y,
+                )),
+                [(_, first), (_, second)] => {
+                    let other = if index == 0 { second } else { first };
+                    let (before, after) = (previous.distance(other), position.distance(other));
+                    (before > 0.0 && after > 0.0).then(|| {
+                        CanvasMessage::Pinch(
+                            before / after,
+                            Point::new((position.x + other.x) / 2.0, (position.y + other.y) / 2.0),
+                        )
+                    })
+                }
+                _ => None,
+            };
+            (Status::Captured, message)
+        }
+        touch::Event::FingerLifted { id, .. } | touch::Event::FingerLost { id, .. } => {
+            if let Some(message) = release_joystick(state, Some(id)) {
+                return (Status::Captured, Some(message));
+            }
+            let count = state.fingers.len();
+            state.fingers.retain(|(finger, _)| *finger != id);
+            if state.fingers.len() < count {
+                (Status::Captured, None)
+            } else {
+                (Status::Ignored, None)
+            }
+        }
+    }
+}
+
+/// Presses the control of the on-screen navigation at the given point of the canvas, returning the
+/// message it emits, or `None` if there is no control.
+fn press_navigation(
+    state: &mut State,
+    position: Point,
+    bounds: Rectangle,
+    finger: Option<Finger>,
+) -> Option<CanvasMessage> {
+    let layout = NavigationLayout::within(bounds.size());
+    match layout.target_at(position)? {
+        Target::Joystick => {
+            let deflection = layout.deflection(position);
+            state.steering = Some(Steering { finger, deflection });
+            Some(CanvasMessage::Joystick(deflection))
+        }
+        Target::Button(NavigationButton::ZoomIn) => {
+            Some(CanvasMessage::Zoom(-KEY_ZOOM, bounds.center()))
+        }
+        Target::Button(NavigationButton::ZoomOut) => {
+            Some(CanvasMessage::Zoom(KEY_ZOOM, bounds.center()))
+        }
+        Target::Button(NavigationButton::Home) => Some(CanvasMessage::Home),
+    }
+}
+
+/// Moves the joystick if the given finger, or the mouse if `None`, holds it.
+fn steer(
+    state: &mut State,
+    finger: Option<Finger>,
+    position: Point,
+    bounds: Rectangle,
+) -> Option<CanvasMessage> {
+    let steering = state
+        .steering
+        .as_mut()
+        .filter(|steering| steering.finger == finger)?;
+    steering.deflection = NavigationLayout::within(bounds.size()).deflection(position);
+    Some(CanvasMessage::Joystick(steering.deflection))
+}
+
+/// Lets go of the joystick if the given finger, or the mouse if `None`, holds it.
+fn release_joystick(state: &mut State, finger: Option<Finger>) -> Option<CanvasMessage> {
+    state
+        .steering
+        .take_if(|steering| steering.finger == finger)?;
+    Some(CanvasMessage::Joystick(Vector2::new(0.0, 0.0)))
+}
+
+/// The width of the line between the current and compared settings, and how far from it it can
+/// be grabbed.
+const DIVIDER_WIDTH: f32 = 2.0;
+const STATUS_BAR_MARGIN: f32 = 5.0;
+/// The most decimals of the coordinates under the cursor, beyond which `f64` has no digits left
+/// near the Mandelbrot set.
+const MAX_COORDINATE_DECIMALS: f64 = 17.0;
+
+/// Describes the status of the view, e.g. "Pixel size 2.4e-9 · double-double (switched from
+/// f32)", warning if no available mode resolves it.
+fn describe_precision(precision: Precision, switched_from: Option<NumericMode>) -> String {
+    let mut description = format!(
+        "Pixel size {:.1e} · {}",
+        precision.pixel_size, precision.mode
+    );
+    if let Some(previous) = switched_from {
+        description += &format!(" (switched from {previous})");
+    }
+    match precision.required {
+        Some(required) if !precision.is_sufficient() => {
+            description += &format!(" · needs {required} to resolve pixels");
+        }
+        None => description += " · too deep to resolve pixels",
+        _ => {}
+    }
+    description
+}
+
+/// Writes the point as a complex number with as many decimals as distinguish adjacent pixels of the
+/// given size, e.g. "-0.745+0.113i", up to the precision of `f64`.
+fn describe_coordinates(point: Vector2<f64>, pixel_size: f64) -> String {
+    let decimals = (-pixel_size.log10())
+        .ceil()
+        .clamp(1.0, MAX_COORDINATE_DECIMALS) as usize;
+    format!("{:.*}{:+.*}i", decimals, point.x, decimals, point.y)
+}
+
+/// Lists the running jobs for the status bar, with their progress and the time they have left
+/// where they report it.
+fn describe_jobs(jobs: &[JobStatus]) -> String {
+    jobs.iter()
+        .map(|job| match (job.percent, job.remaining) {
+            (Some(percent), Some(remaining)) => format!(
+                " · {}… {percent}%, {} left",
+                job.name,
+                throughput::describe_duration(remaining)
+            ),
+            (Some(percent), None) => format!(" · {}… {percent}%", job.name),
+            (None, _) => format!(" · {}…", job.name),
+        })
+        .collect()
+}
+
+const DIVIDER_GRAB_DISTANCE: f32 = 6.0;
+
+const CROSSHAIR_COLOUR: Color = Color {
+    a: 0.5,
+    ..Color::WHITE
+};
+/// The radius of the ring which marks the locked zoom target.
+const TARGET_RADIUS: f32 = 10.0;
+const TARGET_COLOUR: Color = Color::from_rgb(1.0, 0.3, 0.3);
+
+/// Draws lines across the canvas through the cursor, with which to aim before locking the zoom
+/// target.
+fn draw_crosshair(frame: &mut Frame, position: Point, size: Size) {
+    let stroke = Stroke::default()
+        .with_color(CROSSHAIR_COLOUR)
+        .with_width(1.0);
+    frame.stroke(
+        &Path::line(
+            Point::new(0.0, position.y),
+            Point::new(size.width, position.y),
+        ),
+        stroke.clone(),
+    );
+    frame.stroke(
+        &Path::line(
+            Point::new(position.x, 0.0),
+            Point::new(position.x, size.height),
+        ),
+        stroke,
+    );
+}
+
+/// Marks the locked zoom target with a ring with ticks pointing at its centre.
+fn draw_target(frame: &mut Frame, centre: Point) {
+    let stroke = Stroke::default().with_color(TARGET_COLOUR).with_width(2.0);
+    frame.stroke(&Path::circle(centre, TARGET_RADIUS), stroke.clone());
+    let ticks = Path::new(|builder| {
+        for direction in [
+            Vector::new(1.0, 0.0),
+            Vector::new(-1.0, 0.0),
+            Vector::new(0.0, 1.0),
+            Vector::new(0.0, -1.0),
+        ] {
+            builder.move_to(centre + direction * (TARGET_RADIUS * 0.4));
+            builder.line_to(centre + direction * (TARGET_RADIUS * 1.6));
+        }
+    });
+    frame.stroke(&ticks, stroke);
+}
+
+const PROBE_COLOUR: Color = Color::from_rgb(0.0, 1.0, 1.0);
+const PROBE_BOX_WIDTH: f32 = 260.0;
+
+fn stroke_rectangle(frame: &mut Frame, corner: Point, opposite: Point) {
+    let top_left = Point::new(corner.x.min(opposite.x), corner.y.min(opposite.y));
+    let size = iced::Size::new((corner.x - opposite.x).abs(), (corner.y - opposite.y).abs());
+    frame.stroke(
+        &Path::rectangle(top_left, size),
+        Stroke::default().with_color(PROBE_COLOUR).with_width(1.0),
+    );
+}
+
+/// Outlines the probed rectangle and lists its statistics in a box below it.
+fn draw_probe(frame: &mut Frame, probe: &Probe) {
+    let Rectangle {
+        x,
+        y,
+        width,
+        height,
+    } = probe.rectangle;
+    stroke_rectangle(frame, Point::new(x, y), Point::new(x + width, y + height));
+    let Some(statistics) = probe.statistics else {
+        return;
+    };
+    let lines = describe_region(statistics);
+    let origin = Point::new(x, y + height + 5.0);
+    frame.fill_rectangle(
+        origin,
+        iced::Size::new(
+            PROBE_BOX_WIDTH,
+            HELP_LINE_HEIGHT * lines.len() as f32 + 10.0,
+        ),
+        Color::from_rgba(0.0, 0.0, 0.0, 0.85),
+    );
+    for (row, line) in lines.into_iter().enumerate() {
+        frame.fill_text(Text {
+            content: line,
+            position: Point::new(
+                origin.x + 5.0,
+                origin.y + 5.0 + row as f32 * HELP_LINE_HEIGHT,
+            ),
+            color: Color::WHITE,
+            ..Text::default()
+        });
+    }
+}
+
+fn describe_region(statistics: Option<RegionStatistics>) -> Vec<String> {
+    let Some(statistics) = statistics else {
+        return vec!["Only available for the Mandelbrot set".into()];
+    };
+    let iterations = match (statistics.iteration_range, statistics.mean_iterations) {
+        (Some((fewest, most)), Some(mean)) => {
+            format!("Iterations {fewest}–{most}, mean {mean:.1}")
+        }
+        _ => "No samples escaped".into(),
+    };
+    vec![
+        iterations,
+        format!("Interior {:.1}%", statistics.interior_fraction * 100.0),
+        format!("Boundary length {:.3e}", statistics.boundary_length),
+    ]
+}
+
+/// Writes the caption of a stop of the presentation large and centred on a band along the bottom
+/// of the canvas.
+fn draw_caption(frame: &mut Frame, caption: &str, size: Size) {
+    let lines = caption.lines().count().max(1) as f32;
+    let height = CAPTION_SIZE * 1.3 * lines + 2.0 * CAPTION_MARGIN;
+    frame.fill_rectangle(
+        Point::new(0.0, size.height - height),
+        Size::new(size.width, height),
+        Color::from_rgba(0.0, 0.0, 0.0, 0.6),
+    );
+    frame.fill_text(Text {
+        content: caption.into(),
+        position: Point::new(size.width / 2.0, size.height - height / 2.0),
+        color: Color::WHITE,
+        size: CAPTION_SIZE.into(),
+        horizontal_alignment: iced::alignment::Horizontal::Center,
+        vertical_alignment: iced::alignment::Vertical::Center,
+        ..Text::default()
+    });
+}
+
+/// How far the mouse must be dragged while placing a note for the note to get an arrow, in
+/// logical pixels.
+const MIN_ARROW_LENGTH: f32 = 10.0;
+
+/// The size of the text of captions, and the space above and below it.
+const CAPTION_SIZE: f32 = 32.0;
+const CAPTION_MARGIN: f32 = 20.0;
+
+const HELP_LINE_HEIGHT: f32 = 20.0;
+const HELP_KEY_COLUMN_WIDTH: f32 = 140.0;
+
+/// Lists the keyboard shortcuts in a box in the top left corner.
+fn draw_help(frame: &mut Frame, shortcuts: &[(String, &str)]) {
+    let origin = Point::new(20.0, 20.0);
+    let size = iced::Size::new(460.0, HELP_LINE_HEIGHT * (shortcuts.len() + 2) as f32);
+    frame.fill_rectangle(origin, size, Color::from_rgba(0.0, 0.0, 0.0, 0.85));
+    let line = |row: usize, column: f32, content: &str| Text {
+        content: content.into(),
+        position: Point::new(
+            origin.x + 10.0 + column,
+            origin.y + 10.0 + row as f32 * HELP_LINE_HEIGHT,
+        ),
+        color: Color::WHITE,
+        ..Text::default()
+    };
+    frame.fill_text(line(0, 0.0, "Keyboard shortcuts"));
+    for (row, (keys, description)) in shortcuts.iter().enumerate() {
+        frame.fill_text(line(row + 1, 0.0, keys));
+        frame.fill_text(line(row + 1, HELP_KEY_COLUMN_WIDTH, description));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        describe_coordinates, describe_jobs, describe_precision, handle_touch, CanvasMessage,
+        Controls, Focusable, FractalCanvas, Message, Panel, State,
+    };
+    use crate::{
+        jobs::JobStatus,
+        precision::{NumericMode, Precision},
+        shortcuts::{Direction, KeyAction},
+        transform::ViewTransform,
+    };
+    use cgmath::Vector2;
+    use googletest::prelude::*;
+    use iced::{
+        mouse::{self, Cursor},
+        touch::{self, Finger},
+        Point, Rectangle, Size,
+    };
+    use iced_wgpu::wgpu;
+    use iced_widget::canvas::{self, Event};
+    use iced_winit::runtime::Program;
+    use std::time::Duration;
+
+    #[test]
+    fn tab_moves_focus_through_controls_and_wraps() -> Result<()> {
+        let mut controls = create_controls();
+
+        press(&mut controls, KeyAction::FocusNext);
+        let first = controls.focus;
+        press(&mut controls, KeyAction::FocusPrevious);
+        press(&mut controls, KeyAction::FocusPrevious);
+        let wrapped = controls.focus;
+
+        verify_that!(
+            (first, wrapped),
+            (
+                some(eq(Focusable::FractalType)),
+                some(eq(Focusable::ModulationTarget(2)))
+            )
+        )
+    }
+
+    #[test]
+    fn enter_toggles_focused_checkbox() -> Result<()> {
+        let mut controls = create_controls();
+        controls.focus = Some(Focusable::Antialiasing);
+
+        press(&mut controls, KeyAction::Activate);
+
+        verify_that!(controls.antialiasing, eq(true))?;
+        verify_that!(
+            matches!(
+                controls.take_messages()[..],
+                [Message::AntialiasingToggled(true)]
+            ),
+            eq(true)
+        )
+    }
+
+    #[test]
+    fn arrows_adjust_focused_slider_within_range() -> Result<()> {
+        let mut controls = create_controls();
+        controls.focus = Some(Focusable::MaxIterations);
+        let initial = controls.max_iterations;
+
+        press(&mut controls, KeyAction::Move(Direction::Right));
+
+        verify_that!(controls.max_iterations, eq(initial + 100))
+    }
+
+    #[test]
+    fn arrows_pan_view_without_focus() -> Result<()> {
+        let mut controls = create_controls();
+
+        press(&mut controls, KeyAction::Move(Direction::Up));
+
+        verify_that!(
+            matches!(
+                controls.take_messages()[..],
+                [Message::Canvas(CanvasMessage::Pan(x, y))] if x == 0.0 && y > 0.0
+            ),
+            eq(true)
+        )
+    }
+
+    #[test]
+    fn escape_clears_focus_and_closes_help() -> Result<()> {
+        let mut controls = create_controls();
+        press(&mut controls, KeyAction::FocusNext);
+        press(&mut controls, KeyAction::ToggleHelp);
+        let help_shown = controls.canvas.show_help;
+
+        press(&mut controls, KeyAction::ClearFocus);
+
+        verify_that!(
+            (help_shown, controls.canvas.show_help, controls.focus),
+            (eq(true), eq(false), none())
+        )
+    }
+
+    #[test]
+    fn status_bar_reports_automatic_mode_switch() -> Result<()> {
+        let mut controls = create_controls();
+        let precision = |pixel_size, mode| Precision {
+            pixel_size,
+            mode,
+            required: Some(mode),
+        };
+
+        let _ = controls.update(Message::PrecisionChanged(precision(1e-6, NumericMode::F32)));
+        let _ = controls.update(Message::PrecisionChanged(precision(
+            2.5e-9,
+            NumericMode::DoubleDouble,
+        )));
+
+        verify_that!(
+            describe_precision(
+                controls.canvas.precision.unwrap(),
+                controls.canvas.switched_from
+            ),
+            eq("Pixel size 2.5e-9 · double-double (switched from f32)")
+        )
+    }
+
+    #[test]
+    fn status_bar_lists_running_jobs() -> Result<()> {
+        let mut controls = create_controls();
+
+        let _ = controls.update(Message::JobsProgressed(vec![
+            JobStatus {
+                name: "Computing reference orbit",
+                percent: Some(40),
+                remaining: Some(Duration::from_secs(12)),
+            },
+            JobStatus {
+                name: "Finding minibrot",
+                percent: None,
+                remaining: None,
+            },
+        ]));
+
+        verify_that!(
+            describe_jobs(&controls.canvas.jobs),
+            eq(" · Computing reference orbit… 40%, 12 s left · Finding minibrot…")
+        )
+    }
+
+    #[test]
+    fn cancel_comes_first_while_jobs_run() -> Result<()> {
+        let mut controls = create_controls();
+        let _ = controls.update(Message::JobsProgressed(vec![JobStatus {
+            name: "Computing reference orbit",
+            percent: Some(40),
+            remaining: None,
+        }]));
+        let _ = controls.take_messages();
+
+        press(&mut controls, KeyAction::FocusNext);
+        press(&mut controls, KeyAction::Activate);
+
+        verify_that!(
+            matches!(controls.take_messages()[..], [Message::CancelJobs]),
+            eq(true)
+        )?;
+        verify_that!(controls.canvas.jobs.is_empty(), eq(true))
+    }
+
+    #[test]
+    fn readout_resolves_adjacent_pixels_beyond_transform() -> Result<()> {
+        let mut canvas = FractalCanvas::new();
+        canvas.transform = ViewTransform::looking_at(Vector2::new(-0.75 + 3.5e-13, 0.1), 1e-12);
+        let size = Size::new(1000.0, 500.0);
+
+        let shallow = describe_coordinates(
+            FractalCanvas::new().to_plane(Point::new(750.0, 125.0), size),
+            FractalCanvas::new().pixel_size(size.width),
+        );
+        let deep = describe_coordinates(
+            canvas.to_plane(Point::new(750.0, 250.0), size),
+            canvas.pixel_size(size.width),
+        );
+
+        verify_that!(
+            (shallow.as_str(), deep.as_str()),
+            (
+                eq("0.500+1.000i"),
+                eq("-0.749999999999150+0.100000000000000i")
+            )
+        )
+    }
+
+    #[test]
+    fn toolbar_opens_one_panel_at_a_time() -> Result<()> {
+        let mut controls = create_controls();
+
+        let _ = controls.update(Message::PanelToggled(Panel::Fractal));
+        let _ = controls.update(Message::PanelToggled(Panel::Colour));
+        let switched = controls.open_panel;
+        let _ = controls.update(Message::PanelToggled(Panel::Colour));
+
+        verify_that!(
+            (switched, controls.open_panel),
+            (some(eq(Panel::Colour)), none())
+        )
+    }
+
+    #[test]
+    fn one_finger_pans_and_two_fingers_pinch() -> Result<()> {
+        let mut state = State::default();
+        let bounds = Rectangle::new(Point::ORIGIN, Size::new(100.0, 100.0));
+        let mut touch = |event| handle_touch(&mut state, event, bounds, false).1;
+
+        touch(touch::Event::FingerPressed {
+            id: Finger(0),
+            position: Point::new(50.0, 50.0),
+        });
+        let pan = touch(touch::Event::FingerMoved {
+            id: Finger(0),
+            position: Point::new(40.0, 60.0),
+        });
+        touch(touch::Event::FingerPressed {
+            id: Finger(1),
+            position: Point::new(60.0, 60.0),
+        });
+        let pinch = touch(touch::Event::FingerMoved {
+            id: Finger(1),
+            position: Point::new(80.0, 60.0),
+        });
+
+        verify_that!(
+            matches!(pan, Some(CanvasMessage::Pan(x, y)) if x == 10.0 && y == 10.0),
+            eq(true)
+        )?;
+        verify_that!(
+            matches!(
+                pinch,
+                Some(CanvasMessage::Pinch(factor, centre))
+                    if factor == 0.5 && centre == Point::new(60.0, 60.0)
+            ),
+            eq(true)
+        )
+    }
+
+    #[test]
+    fn finger_on_joystick_steers_until_lifted() -> Result<()> {
+        let mut state = State::default();
+        let bounds = Rectangle::new(Point::ORIGIN, Size::new(800.0, 600.0));
+        let mut touch = |event| handle_touch(&mut state, event, bounds, true).1;
+        let deflection = |message| match message {
+            Some(CanvasMessage::Joystick(deflection)) => Some((deflection.x, deflection.y)),
+            _ => None,
+        };
+
+        let pressed = touch(touch::Event::FingerPressed {
+            id: Finger(0),
+            position: Point::new(760.0, 540.0),
+        });
+        let moved = touch(touch::Event::FingerMoved {
+            id: Finger(0),
+            position: Point::new(740.0, 500.0),
+        });
+        let lifted = touch(touch::Event::FingerLifted {
+            id: Finger(0),
+            position: Point::new(740.0, 500.0),
+        });
+
+        verify_that!(
+            (deflection(pressed), deflection(moved), deflection(lifted)),
+            (
+                some(eq((0.5, 0.0))),
+                some(eq((0.0, 1.0))),
+                some(eq((0.0, 0.0)))
+            )
+        )
+    }
+
+    #[test]
+    fn second_click_while_measuring_measures_from_first() -> Result<()> {
+        let mut canvas = FractalCanvas::new();
+        canvas.transform = ViewTransform::looking_at(Vector2::new(0.0, 0.0), 1.0);
+        canvas.measuring = true;
+        let mut state = State::default();
+        let bounds = Rectangle::new(Point::ORIGIN, Size::new(100.0, 100.0));
+        let mut click = |position| {
+            let event = Event::Mouse(mouse::Event::ButtonPressed(mouse::Button::Left));
+            canvas::Program::update(
+                &canvas,
+                &mut state,
+                event,
+                bounds,
+                Cursor::Available(position),
+            )
+            .1
+        };
+
+        let first = click(Point::new(50.0, 50.0));
+        let second = click(Point::new(75.0, 25.0));
+
+        verify_that!(first.is_none(), eq(true))?;
+        let Some(CanvasMessage::Measure(measurement)) = second else {
+            return fail!("Expected a measurement, got {second:?}");
+        };
+        verify_that!(
+            (measurement.distance(), measurement.angle()),
+            (approx_eq(0.5_f64.sqrt()), approx_eq(45.0))
+        )
+    }
+
+    fn press(controls: &mut Controls, action: KeyAction) {
+        let _ = controls.update(Message::Canvas(CanvasMessage::Key(action, Point::ORIGIN)));
+    }
+
+    fn create_controls() -> Controls {
+        Controls::new(
+            false,
+            &wgpu::AdapterInfo {
+                name: "Test adapter".into(),
+                vendor: 0,
+                device: 0,
+                device_type: wgpu::DeviceType::Other,
+                driver: String::new(),
+                driver_info: String::new(),
+                backend: wgpu::Backend::Empty,
+            },
+        )
+    }
+}
@@ -0,0 +1,175 @@
+use crate::colour_palette::{hex, CustomPalette, GRADIENT_STOPS};
+use serde::{Deserialize, Serialize};
+use std::{f32::consts::TAU, fmt::Display, ops::RangeInclusive};
+
+/// Inigo Quilez's cosine palette, in which each channel of the colour at `t` from 0 to 1 is
+/// `offset + amplitude * cos(2π (frequency * t + phase))`, so that twelve numbers describe a
+/// smooth palette.
+///
+/// The fractals are drawn with the colours at the stops of the gradient, between which they are
+/// interpolated, so that the palette works wherever the others do, e.g. in exported shaders. The
+/// curve is therefore followed more coarsely the higher the frequency.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub(super) struct CosinePalette {
+    /// The mean of each channel in sRGB.
+    pub(super) offset: [f32; 3],
+    /// How far each channel swings about its mean.
+    pub(super) amplitude: [f32; 3],
+    /// How many times each channel oscillates along the gradient.
+    pub(super) frequency: [f32; 3],
+    /// Where along its oscillation each channel starts, as a fraction of a cycle.
+    pub(super) phase: [f32; 3],
+}
+
+impl Default for CosinePalette {
+    /// The rainbow from Quilez's article.
+    fn default() -> Self {
+        Self {
+            offset: [0.5, 0.5, 0.5],
+            amplitude: [0.5, 0.5, 0.5],
+            frequency: [1.0, 1.0, 1.0],
+            phase: [0.0, 0.33, 0.67],
+        }
+    }
+}
+
+impl CosinePalette {
+    /// The colour in sRGB at `t`, clipped to the gamut.
+    pub(super) fn colour(&self, t: f32) -> [f32; 3] {
+        std::array::from_fn(|channel| {
+            let angle = TAU * (self.frequency[channel] * t + self.phase[channel]);
+            (self.offset[channel] + self.amplitude[channel] * angle.cos()).clamp(0.0, 1.0)
+        })
+    }
+
+    /// Samples the palette at the stops of the gradient. The roots of the Newton fractal take the
+    /// colours at the middles of the thirds of the gradient.
+    pub(super) fn sample(&self) -> CustomPalette {
+        let srgb8 = |colour: [f32; 3]| colour.map(|channel| (channel * 255.0).round() as u8);
+        CustomPalette {
+            gradient: std::array::from_fn(|stop| {
+                srgb8(self.colour(stop as f32 / (GRADIENT_STOPS - 1) as f32))
+            }),
+            roots: std::array::from_fn(|root| srgb8(self.colour((2 * root + 1) as f32 / 6.0))),
+        }
+    }
+
+    pub(super) fn get(&self, coefficient: Coefficient) -> [f32; 3] {
+        match coefficient {
+            Coefficient::Offset => self.offset,
+            Coefficient::Amplitude => self.amplitude,
+            Coefficient::Frequency => self.frequency,
+            Coefficient::Phase => self.phase,
+        }
+    }
+
+    /// Returns the palette with the given channel of the coefficient set to `value`, clamped to
+    /// the coefficient's range.
+    pub(super) fn with(mut self, coefficient: Coefficient, channel: usize, value: f32) -> Self {
+        let value = value.clamp(*coefficient.range().start(), *coefficient.range().end());
+        let vector = match coefficient {
+            Coefficient::Offset => &mut self.offset,
+            Coefficient::Amplitude => &mut self.amplitude,
+            Coefficient::Frequency => &mut self.frequency,
+            Coefficient::Phase => &mut self.phase,
+        };
+        vector[channel] = value;
+        self
+    }
+}
+
+impl Display for CosinePalette {
+    /// Names the palette by the first and last colours of its gradient, like [`CustomPalette`].
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let gradient = self.sample().gradient;
+        write!(
+            f,
+            "Cosine {} to {}",
+            hex(gradient[0]),
+            hex(gradient[GRADIENT_STOPS - 1])
+        )
+    }
+}
+
+/// One of the vectors of [`CosinePalette`], each of which has a slider per channel.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(super) enum Coefficient {
+    Offset,
+    Amplitude,
+    Frequency,
+    Phase,
+}
+
+impl Coefficient {
+    pub(super) const ALL: [Coefficient; 4] =
+        [Self::Offset, Self::Amplitude, Self::Frequency, Self::Phase];
+
+    pub(super) fn range(self) -> RangeInclusive<f32> {
+        match self {
+            Coefficient::Offset | Coefficient::Amplitude | Coefficient::Phase => 0.0..=1.0,
+            // Beyond two cycles, the eight stops of the gradient would alias.
+            Coefficient::Frequency => 0.0..=2.0,
+        }
+    }
+
+    /// The increment of the sliders, which is also how far an arrow key moves them.
+    pub(super) fn step(self) -> f32 {
+        0.01
+    }
+}
+
+impl Display for Coefficient {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Coefficient::Offset => write!(f, "Offset"),
+            Coefficient::Amplitude => write!(f, "Amplitude"),
+            Coefficient::Frequency => write!(f, "Frequency"),
+            Coefficient::Phase => write!(f, "Phase"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Coefficient, CosinePalette};
+    use googletest::prelude::*;
+
+    #[test]
+    fn colour_follows_cosine_of_each_channel() -> Result<()> {
+        let palette = CosinePalette {
+            offset: [0.5, 0.4, 0.3],
+            amplitude: [0.5, 0.2, 0.1],
+            frequency: [1.0, 2.0, 0.5],
+            phase: [0.0, 0.25, 0.5],
+        };
+
+        verify_that!(
+            palette.colour(0.5),
+            elements_are![near(0.0, 1e-6), near(0.4, 1e-6), near(0.3, 1e-6)]
+        )
+    }
+
+    #[test]
+    fn gradient_runs_from_start_to_end_of_curve() -> Result<()> {
+        let palette = CosinePalette::default();
+
+        let sampled = palette.sample();
+
+        verify_that!(
+            (sampled.gradient[0], sampled.gradient[7], sampled.roots[1]),
+            (eq([255, 66, 66]), eq([255, 66, 66]), eq([0, 189, 189]))
+        )
+    }
+
+    #[test]
+    fn coefficients_are_clamped_to_range() -> Result<()> {
+        let palette = CosinePalette::default()
+            .with(Coefficient::Frequency, 1, 5.0)
+            .with(Coefficient::Offset, 2, -1.0);
+
+        verify_that!(
+            (palette.frequency, palette.offset),
+            (eq([1.0, 2.0, 1.0]), eq([0.5, 0.5, 0.0]))
+        )
+    }
+}
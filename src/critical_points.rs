@@ -0,0 +1,213 @@
+use crate::fractal_definition::MAX_PARAMETERS;
+use cgmath::Vector2;
+use num_complex::Complex64;
+
+/// The most steps which the Durand–Kerner iteration takes before settling for its current roots.
+/// Multiple roots, such as the double critical point of z³ / (z + 0.3) at zero, only converge
+/// linearly.
+const MAX_ROOT_FINDING_STEPS: usize = 1000;
+/// Coefficients smaller than this fraction of the largest are taken as cancelled by rounding.
+const COEFFICIENT_TOLERANCE: f64 = 1e-9;
+/// Roots closer than this are taken as one multiple root.
+const MERGE_DISTANCE: f64 = 1e-4;
+/// Critical points at which the denominator is smaller than this are poles rather than points
+/// from which an orbit can start.
+const POLE_TOLERANCE: f64 = 1e-6;
+
+/// A critical point of the iteration, at which its derivative vanishes, as offered by the chooser
+/// of the controls.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(super) struct CriticalPoint {
+    /// The position of the point in the list returned by [`of_rational_map`].
+    pub(super) index: usize,
+    pub(super) position: Vector2<f64>,
+}
+
+impl std::fmt::Display for CriticalPoint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // Leaves out the sign of what is only rounding error around zero.
+        let tidy = |x: f64| if x.abs() < 5e-4 { 0.0 } else { x };
+        let (re, im) = (tidy(self.position.x), tidy(self.position.y));
+        let sign = if im < 0.0 { '-' } else { '+' };
+        write!(f, "{re:.3} {sign} {:.3}i", im.abs())
+    }
+}
+
+/// Finds the finite critical points of the rational map p(z) / q(z) whose coefficients are packed
+/// as by the definition of [`FractalType::Rational`](crate::controls::FractalType::Rational):
+/// those of p from the cube down to the constant, then those of q.
+///
+/// The critical points are the roots of p'(z) q(z) - p(z) q'(z) which are not also poles. Every
+/// attracting cycle captures the orbit of one of them, so the parameter plane is only
+/// Mandelbrot-like when the orbits start from one. They are sorted by their distance from
+/// the origin, so that the first is zero for maps such as z² which the Mandelbrot set iterates.
+pub(super) fn of_rational_map(params: &[f32; MAX_PARAMETERS]) -> Vec<CriticalPoint> {
+    let ascending = |coefficients: &[f32]| {
+        coefficients
+            .iter()
+            .rev()
+            .map(|&c| f64::from(c))
+            .collect::<Vec<_>>()
+    };
+    let (p, q) = (ascending(&params[0..4]), ascending(&params[4..8]));
+    let derivative = subtract(
+        &multiply(&differentiate(&p), &q),
+        &multiply(&p, &differentiate(&q)),
+    );
+    let mut points: Vec<Complex64> = merge(roots(&derivative))
+        .into_iter()
+        .filter(|&z| evaluate(&q, z).norm() >= POLE_TOLERANCE)
+        .collect();
+    points.sort_by(|a, b| {
+        a.norm()
+            .total_cmp(&b.norm())
+            .then(a.arg().total_cmp(&b.arg()))
+    });
+    points
+        .into_iter()
+        .enumerate()
+        .map(|(index, z)| CriticalPoint {
+            index,
+            position: Vector2::new(z.re, z.im),
+        })
+        .collect()
+}
+
+/// The coefficients of polynomials here run from the constant upwards.
+fn differentiate(poly: &[f64]) -> Vec<f64> {
+    poly.iter()
+        .enumerate()
+        .skip(1)
+        .map(|(power, c)| power as f64 * c)
+        .collect()
+}
+
+fn multiply(a: &[f64], b: &[f64]) -> Vec<f64> {
+    let mut product = vec![0.0; (a.len() + b.len()).saturating_sub(1)];
+    for (i, x) in a.iter().enumerate() {
+        for (j, y) in b.iter().enumerate() {
+            product[i + j] += x * y;
+        }
+    }
+    product
+}
+
+fn subtract(a: &[f64], b: &[f64]) -> Vec<f64> {
+    (0..a.len().max(b.len()))
+        .map(|i| a.get(i).unwrap_or(&0.0) - b.get(i).unwrap_or(&0.0))
+        .collect()
+}
+
+fn evaluate(poly: &[f64], z: Complex64) -> Complex64 {
+    poly.iter()
+        .rev()
+        .fold(Complex64::new(0.0, 0.0), |sum, &c| sum * z + c)
+}
+
+/// Finds all complex roots of the polynomial with the Durand–Kerner method, with multiple roots
+/// repeated. A constant polynomial has none.
+fn roots(poly: &[f64]) -> Vec<Complex64> {
+    let largest = poly.iter().fold(0.0_f64, |largest, c| largest.max(c.abs()));
+    let Some(degree) = poly
+        .iter()
+        .rposition(|c| c.abs() > COEFFICIENT_TOLERANCE * largest)
+    else {
+        return vec![];
+    };
+    let monic: Vec<f64> = poly[..=degree].iter().map(|c| c / poly[degree]).collect();
+    // Powers of a point which is neither real nor a root of unity, so that no two guesses are
+    // symmetric about the real axis.
+    let seed = Complex64::new(0.4, 0.9);
+    let mut roots: Vec<Complex64> = (0..degree).map(|i| seed.powu(i as u32 + 1)).collect();
+    for _ in 0..MAX_ROOT_FINDING_STEPS {
+        let mut largest_step = 0.0_f64;
+        for i in 0..degree {
+            let others = (0..degree)
+                .filter(|&j| j != i)
+                .fold(Complex64::new(1.0, 0.0), |product, j| {
+                    product * (roots[i] - roots[j])
+                });
+            let step = evaluate(&monic, roots[i]) / others;
+            if step.is_finite() {
+                roots[i] -= step;
+                largest_step = largest_step.max(step.norm());
+            }
+        }
+        if largest_step < 1e-15 {
+            break;
+        }
+    }
+    roots
+}
+
+/// Replaces the roots which lie within [`MERGE_DISTANCE`] of each other by their mean, which is
+/// closer to a multiple root than any of its approximations.
+fn merge(roots: Vec<Complex64>) -> Vec<Complex64> {
+    let mut clusters: Vec<Vec<Complex64>> = vec![];
+    for root in roots {
+        match clusters
+            .iter_mut()
+            .find(|cluster| (cluster[0] - root).norm() < MERGE_DISTANCE)
+        {
+            Some(cluster) => cluster.push(root),
+            None => clusters.push(vec![root]),
+        }
+    }
+    clusters
+        .iter()
+        .map(|cluster| cluster.iter().sum::<Complex64>() / cluster.len() as f64)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::of_rational_map;
+    use googletest::prelude::*;
+
+    fn positions(params: [f32; 8]) -> Vec<(f64, f64)> {
+        of_rational_map(&params)
+            .into_iter()
+            .map(|point| (point.position.x, point.position.y))
+            .collect()
+    }
+
+    #[test]
+    fn square_has_critical_point_at_zero() -> Result<()> {
+        verify_that!(
+            positions([0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 0.0, 1.0]),
+            elements_are![(near(0.0, 1e-9), near(0.0, 1e-9))]
+        )
+    }
+
+    #[test]
+    fn double_critical_point_is_found_once_before_farther_points() -> Result<()> {
+        // The derivative of z³ / (z + 0.3) vanishes where z² (2z + 0.9) does.
+        verify_that!(
+            positions([1.0, 0.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.3]),
+            elements_are![
+                (near(0.0, 1e-6), near(0.0, 1e-6)),
+                (near(-0.45, 1e-6), near(0.0, 1e-6))
+            ]
+        )
+    }
+
+    #[test]
+    fn finds_complex_critical_points() -> Result<()> {
+        // z³ + 3z has critical points where z² = -1.
+        verify_that!(
+            positions([1.0, 0.0, 3.0, 0.0, 0.0, 0.0, 0.0, 1.0]),
+            elements_are![
+                (near(0.0, 1e-6), near(-1.0, 1e-6)),
+                (near(0.0, 1e-6), near(1.0, 1e-6))
+            ]
+        )
+    }
+
+    #[test]
+    fn leaves_out_double_poles() -> Result<()> {
+        verify_that!(
+            positions([0.0, 0.0, 0.0, 1.0, 0.0, 1.0, 0.0, 0.0]),
+            elements_are![]
+        )
+    }
+}
@@ -0,0 +1,52 @@
+use std::fmt::Display;
+
+/// Shows one of the raw quantities from which escape-time fractals are coloured instead of the
+/// palette, so that the effect of a bailout, an iteration budget or a variation on the colouring
+/// can be seen directly.
+///
+/// The escape radius and the distance estimate are computed alongside the iteration data in
+/// `f32`, so they break up in views deeper than `f32` resolves. The Newton fractal, which does not
+/// escape, is coloured as usual.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(super) enum DebugView {
+    #[default]
+    Off,
+    /// The smooth iteration count, in bands which alternate with every whole iteration.
+    IterationBands,
+    /// The modulus of the orbit when it escaped, from the bailout in black to its square in
+    /// white.
+    EscapeRadius,
+    /// The estimated distance from the set, in pixels on a logarithmic scale.
+    DistanceEstimate,
+}
+
+impl DebugView {
+    pub(super) const ALL: [DebugView; 4] = [
+        Self::Off,
+        Self::IterationBands,
+        Self::EscapeRadius,
+        Self::DistanceEstimate,
+    ];
+
+    /// The value of `debug_view` in the uniform block. Must match the constants `DEBUG_*` in
+    /// `frag.wgsl`.
+    pub(super) fn code(self) -> u32 {
+        match self {
+            DebugView::Off => 0,
+            DebugView::IterationBands => 1,
+            DebugView::EscapeRadius => 2,
+            DebugView::DistanceEstimate => 3,
+        }
+    }
+}
+
+impl Display for DebugView {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DebugView::Off => write!(f, "Palette colours"),
+            DebugView::IterationBands => write!(f, "Iteration bands"),
+            DebugView::EscapeRadius => write!(f, "Escape radius"),
+            DebugView::DistanceEstimate => write!(f, "Distance estimate"),
+        }
+    }
+}
@@ -0,0 +1,15 @@
+use cgmath::Vector2;
+
+/// Splits a value into the pair of `f32` of the double-float arithmetic in `df64.wgsl`, whose sum
+/// approximates it with about 48 significant bits.
+pub(super) fn split(value: f64) -> [f32; 2] {
+    let hi = value as f32;
+    [hi, (value - hi as f64) as f32]
+}
+
+/// Splits both coordinates of a point, returning the leading parts and the residuals.
+pub(super) fn split_point(point: Vector2<f64>) -> (Vector2<f32>, Vector2<f32>) {
+    let [x, x_residual] = split(point.x);
+    let [y, y_residual] = split(point.y);
+    (Vector2::new(x, y), Vector2::new(x_residual, y_residual))
+}
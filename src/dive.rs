@@ -0,0 +1,80 @@
+use cgmath::Vector2;
+use iced::time::Instant;
+
+/// How many times per second the magnification doubles while diving, which looks like a constant
+/// speed at any depth.
+const DOUBLINGS_PER_SECOND: f32 = 0.5;
+
+/// Where a dive heads.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(super) enum DiveTarget {
+    /// The point in the centre of the view when the dive starts.
+    ViewCentre,
+    /// The centre of the nearest minibrot in the view, or the centre of the view if there is
+    /// none.
+    NearestMinibrot,
+}
+
+/// Zooms in continuously on a point of the plane, which stays where it is in the viewport while
+/// everything else moves away from it, so that the view closes in on the point.
+pub(super) struct Dive {
+    target: Vector2<f64>,
+    last_step: Instant,
+}
+
+/// The part of the plane which a view shows.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(super) struct Framing {
+    pub(super) centre: Vector2<f64>,
+    /// The distance from the centre to the left and right edges.
+    pub(super) half_width: f64,
+}
+
+impl Dive {
+    pub(super) fn new(target: Vector2<f64>) -> Self {
+        Self {
+            target,
+            last_step: Instant::now(),
+        }
+    }
+
+    /// Returns the framing after zooming in on the target from the given one for the time since
+    /// the last step.
+    pub(super) fn step(&mut self, framing: Framing) -> Framing {
+        let now = Instant::now();
+        let seconds = (now - self.last_step).as_secs_f32();
+        self.last_step = now;
+        zoom_towards(framing, self.target, seconds)
+    }
+}
+
+/// Returns the framing after zooming in on the target for the given time.
+fn zoom_towards(framing: Framing, target: Vector2<f64>, seconds: f32) -> Framing {
+    let factor = (-DOUBLINGS_PER_SECOND * seconds).exp2();
+    Framing {
+        centre: target + (framing.centre - target) * factor as f64,
+        half_width: framing.half_width * factor as f64,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{zoom_towards, Framing};
+    use cgmath::Vector2;
+    use googletest::prelude::*;
+
+    #[test]
+    fn magnification_doubles_at_constant_rate_towards_target() -> Result<()> {
+        let framing = Framing {
+            centre: Vector2::new(0.0, 0.0),
+            half_width: 2.0,
+        };
+
+        let zoomed = zoom_towards(framing, Vector2::new(1.0, 0.0), 2.0);
+
+        verify_that!(
+            (zoomed.centre.x, zoomed.centre.y, zoomed.half_width),
+            (approx_eq(0.5), eq(0.0), approx_eq(1.0))
+        )
+    }
+}
@@ -0,0 +1,110 @@
+use serde::{Deserialize, Serialize};
+use std::ops::{Add, Div, Mul, Neg, Sub};
+
+/// A number represented by the unevaluated sum of two `f64`, the second of which holds the
+/// rounding error of the first. This doubles the precision of `f64` to about 32 significant digits
+/// at a small multiple of its cost.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub(super) struct DoubleDouble {
+    hi: f64,
+    lo: f64,
+}
+
+impl DoubleDouble {
+    /// Rounds the number to the nearest `f64`.
+    pub(super) fn to_f64(self) -> f64 {
+        self.hi + self.lo
+    }
+}
+
+impl From<f64> for DoubleDouble {
+    fn from(value: f64) -> Self {
+        Self { hi: value, lo: 0.0 }
+    }
+}
+
+impl Add for DoubleDouble {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        let (sum, error) = two_sum(self.hi, other.hi);
+        quick_two_sum(sum, error + self.lo + other.lo)
+    }
+}
+
+impl Sub for DoubleDouble {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self {
+        self + -other
+    }
+}
+
+impl Neg for DoubleDouble {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        Self {
+            hi: -self.hi,
+            lo: -self.lo,
+        }
+    }
+}
+
+impl Mul for DoubleDouble {
+    type Output = Self;
+
+    fn mul(self, other: Self) -> Self {
+        let product = self.hi * other.hi;
+        let error = self.hi.mul_add(other.hi, -product);
+        quick_two_sum(product, error + self.hi * other.lo + self.lo * other.hi)
+    }
+}
+
+impl Div for DoubleDouble {
+    type Output = Self;
+
+    /// Refines the quotient of the leading parts with one step of long division.
+    fn div(self, other: Self) -> Self {
+        let quotient = self.hi / other.hi;
+        let remainder = self - other * quotient.into();
+        quick_two_sum(quotient, remainder.hi / other.hi)
+    }
+}
+
+/// Returns `a + b` and the rounding error of the sum.
+fn two_sum(a: f64, b: f64) -> (f64, f64) {
+    let sum = a + b;
+    let b_part = sum - a;
+    (sum, (a - (sum - b_part)) + (b - b_part))
+}
+
+/// Like [`two_sum`] for `|a| >= |b|`, which makes it cheaper.
+fn quick_two_sum(a: f64, b: f64) -> DoubleDouble {
+    let sum = a + b;
+    DoubleDouble {
+        hi: sum,
+        lo: b - (sum - a),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::DoubleDouble;
+    use googletest::prelude::*;
+
+    #[test]
+    fn keeps_digits_beyond_f64() -> Result<()> {
+        let one = DoubleDouble::from(1.0);
+        let tiny = DoubleDouble::from(2f64.powi(-80));
+        let third = one / DoubleDouble::from(3.0);
+
+        verify_that!(
+            (
+                ((one + tiny) - one).to_f64(),
+                (third * DoubleDouble::from(3.0) - one).to_f64().abs()
+            ),
+            (eq(2f64.powi(-80)), le(1e-30))
+        )
+    }
+}
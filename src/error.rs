@@ -0,0 +1,50 @@
+use iced_wgpu::wgpu;
+use iced_winit::winit;
+
+/// Failures which prevent the explorer from starting or from continuing to render.
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("Could not create the event loop: {0}")]
+    EventLoop(#[from] winit::error::EventLoopError),
+    #[error("Could not create the window: {0}")]
+    Window(#[from] winit::error::OsError),
+    #[cfg(target_arch = "wasm32")]
+    #[error("The page has no canvas element with the id `iced_canvas` to render into")]
+    MissingCanvas,
+    #[error("Could not create a rendering surface for the window: {0}")]
+    CreateSurface(#[from] wgpu::CreateSurfaceError),
+    #[error(
+        "No suitable GPU adapter was found. Make sure that graphics drivers with Vulkan, Metal, \
+        DirectX 12 or OpenGL support are installed, or choose a different backend with the \
+        WGPU_BACKEND environment variable."
+    )]
+    NoAdapter,
+    #[error("The GPU adapter could not provide a device: {0}")]
+    RequestDevice(#[from] wgpu::RequestDeviceError),
+    #[error("The GPU adapter cannot present to the window's surface")]
+    IncompatibleSurface,
+    #[error("The GPU ran out of memory while presenting a frame. Rendering cannot continue.")]
+    OutOfMemory,
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+}
+
+/// Reports an error which ends the interactive session, both on stderr and in a dialog, since
+/// users who started the explorer from a desktop environment would not see stderr.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn report_fatal(error: &Error) {
+    eprintln!("Error: {error}");
+    let _ = native_dialog::MessageDialog::new()
+        .set_type(native_dialog::MessageType::Error)
+        .set_title("Fractal explorer")
+        .set_text(&error.to_string())
+        .show_alert();
+}
+
+#[cfg(target_arch = "wasm32")]
+pub fn report_fatal(error: &Error) {
+    log::error!("{error}");
+    if let Some(window) = web_sys::window() {
+        let _ = window.alert_with_message(&error.to_string());
+    }
+}
@@ -0,0 +1,367 @@
+use crate::{
+    controls::FractalType,
+    fractal_definition::FractalDefinition,
+    fractal_view::ParameterBlock,
+    thumbnail::{ThumbnailRenderer, ThumbnailRequest},
+};
+use iced::{Point, Rectangle, Size};
+use iced_wgpu::wgpu;
+use std::cell::RefCell;
+
+/// The number of cells along each axis of the grid.
+const CELLS_PER_AXIS: usize = 4;
+
+/// The smallest and largest iteration budgets shown, between which the budget grows
+/// geometrically.
+const MAX_ITERATIONS_RANGE: (f32, f32) = (100.0, 3000.0);
+
+/// The smallest and largest escape radii shown, between which the radius grows geometrically.
+const BAILOUT_RANGE: (f32, f32) = (2.0, 16.0);
+
+/// The width in pixels of the black lines between the cells.
+const CELL_GAP: f32 = 2.0;
+
+/// A setting which the explorer grid varies along one of its axes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(super) enum GridAxis {
+    /// The parameter with the given index in the fractal's definition, across its whole range.
+    Parameter(usize),
+    MaxIterations,
+    Bailout,
+}
+
+impl GridAxis {
+    fn name(self, definition: &FractalDefinition) -> &'static str {
+        match self {
+            GridAxis::Parameter(index) => definition.parameters[index].name,
+            GridAxis::MaxIterations => "Iterations",
+            GridAxis::Bailout => "Bailout",
+        }
+    }
+
+    /// The value of the setting the given fraction of the way along the axis, from its smallest
+    /// value at zero to its largest at one.
+    fn value(self, definition: &FractalDefinition, t: f32) -> f32 {
+        match self {
+            GridAxis::Parameter(index) => {
+                let parameter = &definition.parameters[index];
+                let value = parameter.min + t * (parameter.max - parameter.min);
+                (value / parameter.step).round() * parameter.step
+            }
+            GridAxis::MaxIterations => geometric(MAX_ITERATIONS_RANGE, t).round(),
+            GridAxis::Bailout => geometric(BAILOUT_RANGE, t),
+        }
+    }
+
+    /// Replaces the setting in the uniform block with the given value.
+    fn apply(self, definition: &FractalDefinition, value: f32, block: &mut ParameterBlock) {
+        match self {
+            GridAxis::Parameter(index) => {
+                block.params[definition.parameters[index].uniform_offset] = value
+            }
+            GridAxis::MaxIterations => block.max_iterations = value as u32,
+            GridAxis::Bailout => block.bailout = value,
+        }
+    }
+}
+
+/// The value the fraction `t` of the way from the start of the range to its end, with each step
+/// multiplying it by the same factor.
+fn geometric((start, end): (f32, f32), t: f32) -> f32 {
+    start * (end / start).powf(t)
+}
+
+/// A grid of thumbnails of the view which vary one of its settings across the columns and, if
+/// the fractal has a second one worth varying, another up the rows.
+///
+/// Cells are numbered row by row from the top left corner.
+#[derive(Clone, Copy, Debug)]
+pub(super) struct ExplorerGrid {
+    fractal_type: FractalType,
+    definition: &'static FractalDefinition,
+    columns: GridAxis,
+    rows: Option<GridAxis>,
+}
+
+impl ExplorerGrid {
+    /// Varies the first parameter of the fractal, or else the iteration budget, across the columns
+    /// and the bailout up the rows. Fractals which do not escape, such as Newton's method, which
+    /// converges, have a single row.
+    pub(super) fn of(fractal_type: FractalType) -> Self {
+        let definition = fractal_type.definition();
+        let columns = if definition.parameters.is_empty() {
+            GridAxis::MaxIterations
+        } else {
+            GridAxis::Parameter(0)
+        };
+        Self {
+            fractal_type,
+            definition,
+            columns,
+            rows: fractal_type.escapes().then_some(GridAxis::Bailout),
+        }
+    }
+
+    fn row_count(&self) -> usize {
+        if self.rows.is_some() {
+            CELLS_PER_AXIS
+        } else {
+            1
+        }
+    }
+
+    pub(super) fn cell_count(&self) -> usize {
+        CELLS_PER_AXIS * self.row_count()
+    }
+
+    /// The cell at the given point of an area of the given size which the grid fills, if any.
+    pub(super) fn cell_at(&self, point: Point, size: Size) -> Option<usize> {
+        if !Rectangle::with_size(size).contains(point) {
+            return None;
+        }
+        let column =
+            ((point.x / size.width * CELLS_PER_AXIS as f32) as usize).min(CELLS_PER_AXIS - 1);
+        let row =
+            ((point.y / size.height * self.row_count() as f32) as usize).min(self.row_count() - 1);
+        Some(row * CELLS_PER_AXIS + column)
+    }
+
+    /// The part of an area of the given size which the cell with the given index fills.
+    pub(super) fn cell_bounds(&self, index: usize, size: Size) -> Rectangle {
+        let cell_size = Size::new(
+            size.width / CELLS_PER_AXIS as f32,
+            size.height / self.row_count() as f32,
+        );
+        Rectangle::new(
+            Point::new(
+                (index % CELLS_PER_AXIS) as f32 * cell_size.width,
+                (index / CELLS_PER_AXIS) as f32 * cell_size.height,
+            ),
+            cell_size,
+        )
+    }
+
+    /// The settings which the cell with the given index varies, and their values there. Values
+    /// grow to the right and upwards.
+    pub(super) fn values(&self, index: usize) -> Vec<(GridAxis, f32)> {
+        let fraction = |position: usize, count: usize| {
+            if count > 1 {
+                position as f32 / (count - 1) as f32
+            } else {
+                0.0
+            }
+        };
+        let column = index % CELLS_PER_AXIS;
+        let row = index / CELLS_PER_AXIS;
+        let mut values = vec![(
+            self.columns,
+            self.columns
+                .value(self.definition, fraction(column, CELLS_PER_AXIS)),
+        )];
+        if let Some(rows) = self.rows {
+            let row_count = self.row_count();
+            values.push((
+                rows,
+                rows.value(self.definition, 1.0 - fraction(row, row_count)),
+            ));
+        }
+        values
+    }
+
+    /// Names the settings varied and their ranges, e.g. "Exponent 2 to 8 across, Bailout 2 to 16
+    /// upwards".
+    pub(super) fn describe(&self) -> String {
+        let describe = |axis: GridAxis, direction: &str| {
+            format!(
+                "{} {} to {} {direction}",
+                axis.name(self.definition),
+                axis.value(self.definition, 0.0),
+                axis.value(self.definition, 1.0)
+            )
+        };
+        let mut description = describe(self.columns, "across");
+        if let Some(rows) = self.rows {
+            description.push_str(&format!(", {}", describe(rows, "upwards")));
+        }
+        description
+    }
+}
+
+/// Draws the cells of an explorer grid as thumbnails of the view with each cell's values, leaving
+/// those which are still being rendered black.
+pub(super) struct GridRenderer {
+    /// The thumbnails of the cells, as last uploaded.
+    cells: RefCell<Vec<ThumbnailRequest>>,
+}
+
+impl GridRenderer {
+    pub(super) fn new() -> Self {
+        Self {
+            cells: RefCell::new(vec![]),
+        }
+    }
+
+    /// Asks for the thumbnails of the cells, each rendered with the view's uniform block with the
+    /// cell's values.
+    pub(super) fn update(
+        &self,
+        grid: &ExplorerGrid,
+        block: ParameterBlock,
+        thumbnails: &ThumbnailRenderer,
+    ) {
+        let cells: Vec<_> = (0..grid.cell_count())
+            .map(|index| {
+                let mut cell_block = block;
+                for (axis, value) in grid.values(index) {
+                    axis.apply(grid.definition, value, &mut cell_block);
+                }
+                ThumbnailRequest {
+                    fractal_type: grid.fractal_type,
+                    block: cell_block,
+                }
+            })
+            .collect();
+        for cell in &cells {
+            thumbnails.request(*cell);
+        }
+        self.cells.replace(cells);
+    }
+
+    /// Draws the thumbnails of the cells which have been rendered onto the target.
+    pub(super) fn render(
+        &self,
+        grid: &ExplorerGrid,
+        thumbnails: &ThumbnailRenderer,
+        target: &wgpu::Texture,
+        encoder: &mut wgpu::CommandEncoder,
+    ) {
+        let cells: Vec<_> = self
+            .cells
+            .borrow()
+            .iter()
+            .map(|cell| thumbnails.request(*cell))
+            .collect();
+        let view = target.create_view(&wgpu::TextureViewDescriptor::default());
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Explorer grid pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: &view,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+        let size = Size::new(target.width() as f32, target.height() as f32);
+        for (index, thumbnail) in cells.iter().enumerate() {
+            let Some(thumbnail) = thumbnail else {
+                continue;
+            };
+            let bounds = grid.cell_bounds(index, size);
+            render_pass.set_viewport(
+                bounds.x + CELL_GAP / 2.0,
+                bounds.y + CELL_GAP / 2.0,
+                (bounds.width - CELL_GAP).max(1.0),
+                (bounds.height - CELL_GAP).max(1.0),
+                0.0,
+                1.0,
+            );
+            thumbnails.draw(&mut render_pass, thumbnail);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ExplorerGrid, GridAxis};
+    use crate::controls::FractalType;
+    use googletest::prelude::*;
+    use iced::{Point, Size};
+
+    #[test]
+    fn finds_cell_under_point() -> Result<()> {
+        let grid = ExplorerGrid::of(FractalType::Multibrot);
+        let size = Size::new(400.0, 200.0);
+
+        verify_that!(
+            (
+                grid.cell_at(Point::new(150.0, 20.0), size),
+                grid.cell_at(Point::new(399.0, 199.0), size),
+                grid.cell_at(Point::new(401.0, 20.0), size)
+            ),
+            (some(eq(1)), some(eq(15)), none())
+        )
+    }
+
+    #[test]
+    fn cell_bounds_contain_their_cells() -> Result<()> {
+        let grid = ExplorerGrid::of(FractalType::Multibrot);
+        let size = Size::new(400.0, 200.0);
+
+        let bounds = grid.cell_bounds(6, size);
+
+        verify_that!(
+            (bounds.x, bounds.y, bounds.width, bounds.height),
+            (eq(200.0), eq(50.0), eq(100.0), eq(50.0))
+        )?;
+        verify_that!(grid.cell_at(bounds.center(), size), some(eq(6)))
+    }
+
+    #[test]
+    fn values_grow_to_the_right_and_upwards() -> Result<()> {
+        let grid = ExplorerGrid::of(FractalType::Multibrot);
+
+        verify_that!(
+            (grid.values(12), grid.values(3)),
+            (
+                eq(vec![
+                    (GridAxis::Parameter(0), 2.0),
+                    (GridAxis::Bailout, 2.0)
+                ]),
+                eq(vec![
+                    (GridAxis::Parameter(0), 8.0),
+                    (GridAxis::Bailout, 16.0)
+                ])
+            )
+        )
+    }
+
+    #[test]
+    fn mandelbrot_grid_varies_iterations_geometrically() -> Result<()> {
+        let grid = ExplorerGrid::of(FractalType::Mandelbrot);
+
+        let iterations: Vec<_> = (12..16).map(|index| grid.values(index)[0].1).collect();
+
+        verify_that!(iterations, eq(vec![100.0, 311.0, 965.0, 3000.0]))
+    }
+
+    #[test]
+    fn newton_grid_is_a_single_row_of_whole_iterations() -> Result<()> {
+        let grid = ExplorerGrid::of(FractalType::Newton);
+
+        let iterations: Vec<_> = (0..4).map(|index| grid.values(index)).collect();
+
+        verify_that!(grid.cell_count(), eq(4))?;
+        verify_that!(
+            iterations,
+            eq(vec![
+                vec![(GridAxis::Parameter(0), 1.0)],
+                vec![(GridAxis::Parameter(0), 67.0)],
+                vec![(GridAxis::Parameter(0), 134.0)],
+                vec![(GridAxis::Parameter(0), 200.0)]
+            ])
+        )
+    }
+
+    #[test]
+    fn describes_axes_and_ranges() -> Result<()> {
+        verify_that!(
+            ExplorerGrid::of(FractalType::Multibrot).describe(),
+            eq("Exponent 2 to 8 across, Bailout 2 to 16 upwards")
+        )
+    }
+}
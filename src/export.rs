@@ -0,0 +1,278 @@
+use crate::{
+    annotation::{self, Annotation},
+    fractal_view::View,
+    gpu::Gpu,
+    iteration_budget,
+    render_farm::{Tile, TILE_SIZE},
+    tiff,
+};
+use iced::futures;
+use iced_wgpu::wgpu;
+use std::{
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::Path,
+};
+
+const BYTES_PER_PIXEL: u32 = 4;
+
+/// The resolution written into TIFF images for which none is given, which TIFF requires.
+const DEFAULT_TIFF_DPI: u32 = 72;
+
+const METRES_PER_INCH: f64 = 0.0254;
+
+/// Renders the view into an offscreen texture of the given size and returns the tightly packed
+/// RGBA pixels.
+///
+/// The GPU must have been created with an RGBA8 texture format, as is the case for
+/// [`Gpu::new_without_surface`].
+#[cfg_attr(not(feature = "ndi"), allow(dead_code))]
+pub(super) fn render_to_rgba(gpu: &Gpu, view: &View, width: u32, height: u32) -> Vec<u8> {
+    render_annotated_to_rgba(gpu, view, &[], width, height)
+}
+
+/// Renders the view with the annotations drawn over it, like [`render_to_rgba`].
+#[tracing::instrument(skip(gpu, view, annotations))]
+pub(super) fn render_annotated_to_rgba(
+    gpu: &Gpu,
+    view: &View,
+    annotations: &[Annotation],
+    width: u32,
+    height: u32,
+) -> Vec<u8> {
+    let texture = create_render_target(gpu, width, height);
+    let padded_bytes_per_row = padded_bytes_per_row(width);
+    let readback_buffer = gpu.device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("Export readback buffer"),
+        size: (padded_bytes_per_row * height) as u64,
+        usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    });
+
+    view.update_transform(&gpu.queue);
+    let mut encoder = gpu
+        .device
+        .create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+    view.render(&texture, &mut encoder);
+    if !annotations.is_empty() {
+        annotation::render_overlay(gpu, annotations, &view.transform(), &texture, &mut encoder);
+    }
+    encoder.copy_texture_to_buffer(
+        texture.as_image_copy(),
+        wgpu::ImageCopyBuffer {
+            buffer: &readback_buffer,
+            layout: wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(padded_bytes_per_row),
+                rows_per_image: None,
+            },
+        },
+        texture.size(),
+    );
+    gpu.queue.submit(Some(encoder.finish()));
+
+    let buffer_slice = readback_buffer.slice(..);
+    let (sender, receiver) = futures::channel::oneshot::channel();
+    buffer_slice.map_async(wgpu::MapMode::Read, move |result| {
+        let _ = sender.send(result);
+    });
+    gpu.device.poll(wgpu::Maintain::Wait);
+    futures::executor::block_on(receiver)
+        .expect("Readback channel closed")
+        .expect("Failed to map readback buffer");
+
+    let data = buffer_slice.get_mapped_range();
+    let mut pixels = Vec::with_capacity((width * height * BYTES_PER_PIXEL) as usize);
+    for row in data.chunks(padded_bytes_per_row as usize) {
+        pixels.extend_from_slice(&row[..(width * BYTES_PER_PIXEL) as usize]);
+    }
+    drop(data);
+    readback_buffer.unmap();
+    pixels
+}
+
+/// Whether an image of the given size can be rendered in a single pass: its render target and
+/// readback buffer lie within the limits of the device, and it cannot keep the GPU busy for long
+/// enough that the driver resets it.
+pub(super) fn fits_in_one_pass(gpu: &Gpu, width: u32, height: u32, max_iterations: u32) -> bool {
+    let limits = gpu.device.limits();
+    width <= limits.max_texture_dimension_2d
+        && height <= limits.max_texture_dimension_2d
+        && u64::from(padded_bytes_per_row(width)) * u64::from(height) <= limits.max_buffer_size
+        && !iteration_budget::is_too_expensive(width, height, max_iterations)
+}
+
+/// Renders the view with the annotations drawn over it like [`render_annotated_to_rgba`], but in
+/// tiles of at most `tile_size` along either side, each cropped from the view as the workers of a
+/// render farm do, so that images which do not fit in one pass can be rendered on one GPU.
+pub(super) fn render_tiled_to_rgba(
+    gpu: &Gpu,
+    view: &mut View,
+    annotations: &[Annotation],
+    width: u32,
+    height: u32,
+    tile_size: u32,
+) -> Vec<u8> {
+    let row_length = (width * BYTES_PER_PIXEL) as usize;
+    let mut pixels = vec![0; row_length * height as usize];
+    for tile in Tile::split(width, height, tile_size) {
+        let (bottom_left, top_right) = tile.viewport_corners(width, height);
+        let tile_pixels = view.with_crop(bottom_left, top_right, |view| {
+            render_annotated_to_rgba(gpu, view, annotations, tile.width, tile.height)
+        });
+        let tile_row_length = (tile.width * BYTES_PER_PIXEL) as usize;
+        for (index, source) in tile_pixels.chunks_exact(tile_row_length).enumerate() {
+            let start =
+                (tile.y as usize + index) * row_length + (tile.x * BYTES_PER_PIXEL) as usize;
+            pixels[start..start + tile_row_length].copy_from_slice(source);
+        }
+    }
+    pixels
+}
+
+/// Creates an offscreen texture which the view can render into and which can be copied out.
+pub(super) fn create_render_target(gpu: &Gpu, width: u32, height: u32) -> wgpu::Texture {
+    gpu.device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("Offscreen render target"),
+        size: wgpu::Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format: gpu.texture_format,
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+        view_formats: &[],
+    })
+}
+
+/// Renders the view offscreen with the annotations drawn over it and writes the result as
+/// [`save_image`] does. Images which do not fit in one pass are rendered in tiles of
+/// [`TILE_SIZE`].
+pub(super) fn export_image(
+    gpu: &Gpu,
+    view: &mut View,
+    annotations: &[Annotation],
+    path: impl AsRef<Path>,
+    width: u32,
+    height: u32,
+    dpi: Option<u32>,
+) -> io::Result<()> {
+    let pixels = if fits_in_one_pass(gpu, width, height, view.max_iterations()) {
+        render_annotated_to_rgba(gpu, view, annotations, width, height)
+    } else {
+        render_tiled_to_rgba(gpu, view, annotations, width, height, TILE_SIZE)
+    };
+    save_image(path, &pixels, width, height, dpi)
+}
+
+/// Writes the tightly packed RGBA pixels as a TIFF image if the path ends in `.tif` or `.tiff`,
+/// and as a PNG image otherwise, with the resolution in dots per inch if it is given, so that
+/// print software lays the image out at its intended size.
+pub(super) fn save_image(
+    path: impl AsRef<Path>,
+    pixels: &[u8],
+    width: u32,
+    height: u32,
+    dpi: Option<u32>,
+) -> io::Result<()> {
+    let path = path.as_ref();
+    let is_tiff = path.extension().is_some_and(|extension| {
+        extension.eq_ignore_ascii_case("tif") || extension.eq_ignore_ascii_case("tiff")
+    });
+    let mut writer = BufWriter::new(File::create(path)?);
+    if is_tiff {
+        tiff::write(
+            &mut writer,
+            pixels,
+            width,
+            height,
+            dpi.unwrap_or(DEFAULT_TIFF_DPI),
+        )?;
+    } else {
+        let mut encoder = png::Encoder::new(&mut writer, width, height);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder.set_pixel_dims(dpi.map(|dpi| {
+            let per_metre = (f64::from(dpi) / METRES_PER_INCH).round() as u32;
+            png::PixelDimensions {
+                xppu: per_metre,
+                yppu: per_metre,
+                unit: png::Unit::Meter,
+            }
+        }));
+        encoder.write_header()?.write_image_data(pixels)?;
+    }
+    writer.flush()
+}
+
+pub(super) fn padded_bytes_per_row(width: u32) -> u32 {
+    let unpadded = width * BYTES_PER_PIXEL;
+    let alignment = wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+    unpadded.div_ceil(alignment) * alignment
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{export_image, fits_in_one_pass, render_tiled_to_rgba, render_to_rgba};
+    use crate::{
+        fractal_view::View,
+        gpu::Gpu,
+        print_preset::{Orientation, PaperSize, PrintPreset},
+    };
+    use cgmath::Vector2;
+    use googletest::prelude::*;
+
+    #[test]
+    fn tiled_render_matches_render_in_one_pass() -> Result<()> {
+        let (width, height) = (40, 24);
+        let gpu = Gpu::new_without_surface()?;
+        let mut view = View::new(&gpu);
+        view.wait_for_pipelines();
+        view.look_at(Vector2::new(-0.75, 0.1), 0.5);
+
+        let whole = render_to_rgba(&gpu, &view, width, height);
+        let tiled = render_tiled_to_rgba(&gpu, &mut view, &[], width, height, 16);
+
+        // Each tile maps its pixels onto the plane with a transform of its own, which rounds
+        // differently, so a few pixels on the boundary may land in another band.
+        let differing = whole
+            .chunks(4)
+            .zip(tiled.chunks(4))
+            .filter(|(whole, tiled)| whole != tiled)
+            .count();
+        verify_that!(tiled.len(), eq(whole.len()))?;
+        verify_that!(differing, le((width * height / 20) as usize))
+    }
+
+    #[test]
+    fn print_wider_than_a_texture_is_rendered_in_tiles() -> Result<()> {
+        let gpu = Gpu::new_without_surface()?;
+        let mut view = View::new(&gpu);
+        view.wait_for_pipelines();
+        view.set_max_iterations(10);
+        let preset = PrintPreset {
+            paper: PaperSize::A3,
+            orientation: Orientation::Landscape,
+            dpi: 600,
+        };
+        let (width, height) = preset.pixel_size();
+        let path = std::env::temp_dir().join(format!(
+            "fractal_explorer_wide_print_{}.png",
+            std::process::id()
+        ));
+
+        verify_that!(width, gt(gpu.device.limits().max_texture_dimension_2d))?;
+        verify_that!(
+            fits_in_one_pass(&gpu, width, height, view.max_iterations()),
+            eq(false)
+        )?;
+        // A strip of the page is enough to need tiles, without rendering the whole page.
+        export_image(&gpu, &mut view, &[], &path, width, 2, Some(preset.dpi))?;
+        let image = image::open(&path)?;
+        std::fs::remove_file(&path)?;
+        verify_that!((image.width(), image.height()), eq((width, 2)))
+    }
+}
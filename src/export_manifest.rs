@@ -0,0 +1,240 @@
+use crate::{annotation::Annotation, fractal_view::View, tile_cache};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    ffi::OsString,
+    io,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+/// Identifies an image by a hash of everything on which its pixels depend, so that an image
+/// written before an interruption counts as written only if the script still asks for the same.
+pub(super) fn frame_key(view: &View, annotations: &[Annotation], width: u32, height: u32) -> u64 {
+    let description = serde_json::json!({
+        "view": view.bookmark(),
+        "bailout": view.bailout(),
+        "antialiasing": view.antialiasing(),
+        "compositor": view.compositor(),
+        "transparency": view.transparency().to_string(),
+        "vector_mode": view.vector_mode(),
+        "curve_depth": view.curve_depth(),
+        "annotations": annotations,
+        "width": width,
+        "height": height,
+    });
+    tile_cache::fnv1a(&description.to_string())
+}
+
+/// Records which images of an export have been written, in a file beside the script, so that an
+/// export which is interrupted resumes where it left off when the script runs again.
+///
+/// The file is rewritten after every image and deleted once the export completes, so that it only
+/// exists while there is something to resume.
+pub(super) struct ExportManifest {
+    path: PathBuf,
+    progress: Mutex<Progress>,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+struct Progress {
+    /// The key of each image written, by its path.
+    written: HashMap<String, u64>,
+}
+
+impl ExportManifest {
+    /// Opens the manifest of the script at the given path, which is empty unless an earlier run
+    /// of the script was interrupted.
+    pub(super) fn for_script(script: impl AsRef<Path>) -> Self {
+        let mut path = OsString::from(script.as_ref());
+        path.push(".progress.json");
+        Self::open(PathBuf::from(path))
+    }
+
+    pub(super) fn open(path: PathBuf) -> Self {
+        let progress = std::fs::read(&path)
+            .ok()
+            .and_then(|contents| serde_json::from_slice(&contents).ok())
+            .unwrap_or_default();
+        Self {
+            path,
+            progress: Mutex::new(progress),
+        }
+    }
+
+    /// The number of images recorded as written.
+    pub(super) fn written(&self) -> usize {
+        self.progress.lock().unwrap().written.len()
+    }
+
+    /// Whether the image at the path was written with the given key and is still there.
+    pub(super) fn is_written(&self, image: impl AsRef<Path>, key: u64) -> bool {
+        let image = image.as_ref();
+        self.progress
+            .lock()
+            .unwrap()
+            .written
+            .get(&*image.to_string_lossy())
+            == Some(&key)
+            && image.exists()
+    }
+
+    /// Records that the image at the path was written with the given key.
+    pub(super) fn record(&self, image: impl AsRef<Path>, key: u64) -> io::Result<()> {
+        let mut progress = self.progress.lock().unwrap();
+        progress
+            .written
+            .insert(image.as_ref().to_string_lossy().into_owned(), key);
+        // Renaming the complete file keeps a partly written one from being read if the export
+        // stops while writing.
+        let temporary = self.path.with_extension("json.tmp");
+        std::fs::write(&temporary, serde_json::to_vec(&*progress)?)?;
+        std::fs::rename(temporary, &self.path)
+    }
+
+    /// Deletes the manifest once the export is complete.
+    pub(super) fn remove(&self) -> io::Result<()> {
+        match std::fs::remove_file(&self.path) {
+            Err(error) if error.kind() != io::ErrorKind::NotFound => Err(error),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// The tiles of an image which a render farm has assembled so far, kept as files in a directory
+/// beside the image, so that a large image which is interrupted resumes from the tiles already
+/// rendered.
+#[derive(Clone, Debug)]
+pub(super) struct PartialFrame {
+    directory: PathBuf,
+}
+
+impl PartialFrame {
+    /// Opens the tiles kept for the image at the path, discarding those kept for an image with
+    /// another key.
+    pub(super) fn open(image: impl AsRef<Path>, key: u64) -> io::Result<Self> {
+        let mut directory = OsString::from(image.as_ref());
+        directory.push(".partial");
+        let partial = Self {
+            directory: PathBuf::from(directory),
+        };
+        let key = format!("{key:016x}");
+        if std::fs::read_to_string(partial.key_path()).ok().as_ref() != Some(&key) {
+            match std::fs::remove_dir_all(&partial.directory) {
+                Err(error) if error.kind() != io::ErrorKind::NotFound => return Err(error),
+                _ => {}
+            }
+            std::fs::create_dir_all(&partial.directory)?;
+            std::fs::write(partial.key_path(), key)?;
+        }
+        Ok(partial)
+    }
+
+    /// Returns the pixels of the tile at the given position, if they were kept and are as long as
+    /// expected.
+    pub(super) fn load(&self, x: u32, y: u32, length: usize) -> Option<Vec<u8>> {
+        std::fs::read(self.tile_path(x, y))
+            .ok()
+            .filter(|pixels| pixels.len() == length)
+    }
+
+    pub(super) fn save(&self, x: u32, y: u32, pixels: &[u8]) -> io::Result<()> {
+        let path = self.tile_path(x, y);
+        let temporary = path.with_extension("tmp");
+        std::fs::write(&temporary, pixels)?;
+        std::fs::rename(temporary, path)
+    }
+
+    /// Deletes the tiles once the image is written.
+    pub(super) fn remove(self) -> io::Result<()> {
+        std::fs::remove_dir_all(self.directory)
+    }
+
+    fn key_path(&self) -> PathBuf {
+        self.directory.join("key")
+    }
+
+    fn tile_path(&self, x: u32, y: u32) -> PathBuf {
+        self.directory.join(format!("{x}_{y}.rgba"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{frame_key, ExportManifest, PartialFrame};
+    use crate::{
+        compositor::{Compositor, Layer},
+        fractal_view::View,
+        gpu::Gpu,
+        vector_mode::VectorMode,
+    };
+    use googletest::prelude::*;
+    use std::path::PathBuf;
+
+    fn temporary_path(name: &str, extension: &str) -> PathBuf {
+        std::env::temp_dir().join(format!(
+            "fractal_explorer_manifest_{name}_{}.{extension}",
+            std::process::id()
+        ))
+    }
+
+    #[test]
+    fn written_image_counts_for_same_key_while_it_exists() -> Result<()> {
+        let manifest_path = temporary_path("round_trip", "json");
+        let _ = std::fs::remove_file(&manifest_path);
+        let [kept, deleted] = ["kept", "deleted"].map(|name| temporary_path(name, "png"));
+        std::fs::write(&kept, [])?;
+        let manifest = ExportManifest::open(manifest_path.clone());
+        manifest.record(&kept, 1)?;
+        manifest.record(&deleted, 1)?;
+
+        let reopened = ExportManifest::open(manifest_path);
+
+        verify_that!(
+            (
+                reopened.is_written(&kept, 1),
+                reopened.is_written(&kept, 2),
+                reopened.is_written(&deleted, 1)
+            ),
+            (eq(true), eq(false), eq(false))
+        )
+    }
+
+    #[test]
+    fn kept_tiles_are_discarded_for_another_key() -> Result<()> {
+        let image = temporary_path("partial", "png");
+        let pixels = vec![7; 16];
+        PartialFrame::open(&image, 1)?.save(0, 16, &pixels)?;
+
+        let same = PartialFrame::open(&image, 1)?.load(0, 16, pixels.len());
+        let other = PartialFrame::open(&image, 2)?.load(0, 16, pixels.len());
+
+        verify_that!((same, other), (some(eq(pixels.clone())), none()))
+    }
+
+    #[test]
+    fn key_depends_on_colouring_layers() -> Result<()> {
+        let gpu = Gpu::new_without_surface()?;
+        let mut view = View::new(&gpu);
+        let plain = frame_key(&view, &[], 40, 24);
+
+        view.set_compositor(Compositor {
+            base: Layer::DistanceEstimate,
+            ..Compositor::default()
+        });
+
+        verify_that!(frame_key(&view, &[], 40, 24), not(eq(plain)))
+    }
+
+    #[test]
+    fn key_depends_on_curve_and_its_depth() -> Result<()> {
+        let gpu = Gpu::new_without_surface()?;
+        let mut view = View::new(&gpu);
+        view.set_vector_mode(&gpu, VectorMode::Hilbert, 3);
+        let shallow = frame_key(&view, &[], 40, 24);
+
+        view.set_vector_mode(&gpu, VectorMode::Hilbert, 4);
+
+        verify_that!(frame_key(&view, &[], 40, 24), not(eq(shallow)))
+    }
+}
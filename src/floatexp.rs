@@ -0,0 +1,14 @@
+use cgmath::Vector2;
+
+/// Splits a point into the mantissa and exponent of the floatexp numbers in `floatexp.wgsl`, whose
+/// mantissa times two to the power of the exponent is the point with the precision of `f32` however
+/// small it is. The larger coordinate of the mantissa has a magnitude in `[0.5, 1)`.
+pub(super) fn split_point(point: Vector2<f64>) -> (Vector2<f32>, i32) {
+    let larger = point.x.abs().max(point.y.abs());
+    if larger == 0.0 || !larger.is_finite() {
+        return (Vector2::new(0.0, 0.0), 0);
+    }
+    let exponent = larger.log2().floor() as i32 + 1;
+    let mantissa = point * 2f64.powi(-exponent);
+    (mantissa.cast().unwrap(), exponent)
+}
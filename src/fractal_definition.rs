@@ -0,0 +1,445 @@
+use crate::{
+    colour_palette::Palette,
+    controls::FractalType,
+    fractal_view::{DEFAULT_BAILOUT, DEFAULT_MAX_ITERATIONS},
+};
+use cgmath::{Matrix3, Vector2};
+
+/// The number of per-fractal parameters which fit in the uniform block. Must match the size of
+/// `params` in `frag.wgsl`, which packs them four to a `vec4`.
+pub(super) const MAX_PARAMETERS: usize = 8;
+
+/// The name of the Multibrot set's exponent, which the exponent animation sweeps.
+pub(super) const EXPONENT: &str = "Exponent";
+
+/// Describes how a fractal is rendered and which parameters the user can adjust.
+///
+/// Controls, the uniform block and scripts are all driven from the definition, so adding a fractal
+/// only needs shader entry points and an entry in [`FractalType::definition`].
+#[derive(Debug)]
+pub(super) struct FractalDefinition {
+    pub(super) name: &'static str,
+    /// The fragment shader entry point in `frag.wgsl`.
+    pub(super) entry_point: &'static str,
+    /// The compute shader entry point in `antialiasing.wgsl` which resamples edges of the fractal.
+    pub(super) antialiasing_entry_point: &'static str,
+    /// The compute shader entry point in `iteration_data.wgsl` which stores the result of
+    /// iterating each pixel.
+    pub(super) iteration_data_entry_point: &'static str,
+    /// Like `iteration_data_entry_point`, iterating in double-float arithmetic, in
+    /// `iteration_data_df64.wgsl`, or `None` if the fractal is only iterated in `f32`.
+    pub(super) df64_iteration_data_entry_point: Option<&'static str>,
+    /// Like `iteration_data_entry_point`, iterating by perturbation from a reference orbit, in
+    /// `iteration_data_perturbation.wgsl`, or `None` if the fractal has no such variant.
+    pub(super) perturbation_iteration_data_entry_point: Option<&'static str>,
+    /// The fragment shader entry point in `colouring.wgsl` which colours each pixel by the stored
+    /// result.
+    pub(super) colouring_entry_point: &'static str,
+    pub(super) parameters: &'static [ParameterDefinition],
+    /// The settings with which the fractal starts when it is selected.
+    pub(super) profile: FractalProfile,
+}
+
+/// Defaults which suit a fractal, so that it starts out framed and coloured sensibly rather than
+/// as the Mandelbrot set was.
+#[derive(Debug)]
+pub(super) struct FractalProfile {
+    /// The point of the plane at the centre of the view.
+    pub(super) centre: [f32; 2],
+    /// Half the width of the part of the plane which the view shows.
+    pub(super) half_width: f32,
+    pub(super) bailout: f32,
+    pub(super) max_iterations: u32,
+    pub(super) palette: Palette,
+}
+
+impl FractalProfile {
+    /// Maps the square `[-1, 1] x [-1, 1]` onto the framing of the profile.
+    pub(super) fn view_transform(&self) -> Matrix3<f32> {
+        Matrix3::from_translation(Vector2::from(self.centre)) * Matrix3::from_scale(self.half_width)
+    }
+}
+
+#[derive(Debug)]
+pub(super) struct ParameterDefinition {
+    pub(super) name: &'static str,
+    pub(super) min: f32,
+    pub(super) max: f32,
+    pub(super) default: f32,
+    /// The increment of the slider in the controls.
+    pub(super) step: f32,
+    /// The index of the parameter in `params` in the uniform block, read in the shader with
+    /// `parameter(offset)`.
+    pub(super) uniform_offset: usize,
+}
+
+impl FractalDefinition {
+    pub(super) fn default_values(&self) -> Vec<f32> {
+        self.parameters
+            .iter()
+            .map(|parameter| parameter.default)
+            .collect()
+    }
+
+    /// Returns the index of the parameter with the given name, ignoring case.
+    pub(super) fn parameter_index(&self, name: &str) -> Option<usize> {
+        self.parameters
+            .iter()
+            .position(|parameter| parameter.name.eq_ignore_ascii_case(name))
+    }
+
+    /// Places the values of the parameters at their offsets in the uniform block.
+    pub(super) fn pack(&self, values: &[f32]) -> [f32; MAX_PARAMETERS] {
+        let mut packed = [0.0; MAX_PARAMETERS];
+        for (parameter, value) in self.parameters.iter().zip(values) {
+            packed[parameter.uniform_offset] = *value;
+        }
+        packed
+    }
+}
+
+const MANDELBROT: FractalDefinition = FractalDefinition {
+    name: "Mandelbrot",
+    entry_point: "mandelbrot",
+    antialiasing_entry_point: "mandelbrot_antialiased",
+    iteration_data_entry_point: "mandelbrot_iteration_data",
+    df64_iteration_data_entry_point: Some("mandelbrot_iteration_data_df64"),
+    perturbation_iteration_data_entry_point: Some("mandelbrot_iteration_data_perturbation"),
+    colouring_entry_point: "mandelbrot_colouring",
+    parameters: &[],
+    profile: FractalProfile {
+        centre: [-0.5, 0.0],
+        half_width: 2.0,
+        bailout: DEFAULT_BAILOUT,
+        max_iterations: DEFAULT_MAX_ITERATIONS,
+        palette: Palette::Greyscale,
+    },
+};
+
+/// The number of steps after which the root-finding fractals give up on converging, which is the
+/// first parameter of each.
+const ROOT_FINDING_ITERATIONS: ParameterDefinition = ParameterDefinition {
+    name: "Iterations",
+    min: 1.0,
+    max: 200.0,
+    default: 100.0,
+    step: 1.0,
+    uniform_offset: 0,
+};
+
+/// The roots of `z^3 - 1` lie on the unit circle. The methods run for the number of iterations in
+/// their first parameter, and do not escape, so neither the bailout nor the iteration budget
+/// matter.
+const ROOT_FINDING_PROFILE: FractalProfile = FractalProfile {
+    centre: [0.0, 0.0],
+    half_width: 1.5,
+    bailout: DEFAULT_BAILOUT,
+    max_iterations: DEFAULT_MAX_ITERATIONS,
+    palette: Palette::Viridis,
+};
+
+const NEWTON: FractalDefinition = FractalDefinition {
+    name: "Newton",
+    entry_point: "newton",
+    antialiasing_entry_point: "newton_antialiased",
+    iteration_data_entry_point: "newton_iteration_data",
+    df64_iteration_data_entry_point: None,
+    perturbation_iteration_data_entry_point: None,
+    colouring_entry_point: "newton_colouring",
+    parameters: &[ROOT_FINDING_ITERATIONS],
+    profile: ROOT_FINDING_PROFILE,
+};
+
+const DAMPED_NEWTON: FractalDefinition = FractalDefinition {
+    name: "Damped Newton",
+    entry_point: "damped_newton",
+    antialiasing_entry_point: "damped_newton_antialiased",
+    iteration_data_entry_point: "damped_newton_iteration_data",
+    df64_iteration_data_entry_point: None,
+    perturbation_iteration_data_entry_point: None,
+    colouring_entry_point: "root_finding_colouring",
+    parameters: &[
+        ROOT_FINDING_ITERATIONS,
+        ParameterDefinition {
+            name: "Relaxation",
+            min: 0.1,
+            max: 2.0,
+            default: 0.5,
+            step: 0.01,
+            uniform_offset: 1,
+        },
+    ],
+    profile: ROOT_FINDING_PROFILE,
+};
+
+const SECANT: FractalDefinition = FractalDefinition {
+    name: "Secant method",
+    entry_point: "secant",
+    antialiasing_entry_point: "secant_antialiased",
+    iteration_data_entry_point: "secant_iteration_data",
+    df64_iteration_data_entry_point: None,
+    perturbation_iteration_data_entry_point: None,
+    colouring_entry_point: "root_finding_colouring",
+    parameters: &[
+        ROOT_FINDING_ITERATIONS,
+        ParameterDefinition {
+            name: "Second point offset",
+            min: -1.0,
+            max: 1.0,
+            default: 0.1,
+            step: 0.01,
+            uniform_offset: 1,
+        },
+    ],
+    profile: ROOT_FINDING_PROFILE,
+};
+
+const MULTIBROT: FractalDefinition = FractalDefinition {
+    name: "Multibrot",
+    entry_point: "multibrot",
+    antialiasing_entry_point: "multibrot_antialiased",
+    iteration_data_entry_point: "multibrot_iteration_data",
+    df64_iteration_data_entry_point: None,
+    perturbation_iteration_data_entry_point: None,
+    // The iteration data has the same form as the Mandelbrot set's.
+    colouring_entry_point: "mandelbrot_colouring",
+    parameters: &[ParameterDefinition {
+        name: EXPONENT,
+        min: 2.0,
+        max: 8.0,
+        default: 3.0,
+        step: 0.01,
+        uniform_offset: 0,
+    }],
+    // Higher powers pull the set in towards the unit disc about the origin, and escape so fast
+    // that fewer iterations resolve it. A larger bailout smooths the bands of the escape time.
+    profile: FractalProfile {
+        centre: [0.0, 0.0],
+        half_width: 1.5,
+        bailout: 4.0,
+        max_iterations: 500,
+        palette: Palette::Cividis,
+    },
+};
+
+/// The coefficient of the given power of `z` in the numerator or denominator of the rational map,
+/// which `rational_smooth_iterations` in `frag.wgsl` reads as two `vec4`s from the cube down to
+/// the constant.
+const fn coefficient(
+    name: &'static str,
+    default: f32,
+    uniform_offset: usize,
+) -> ParameterDefinition {
+    ParameterDefinition {
+        name,
+        min: -2.0,
+        max: 2.0,
+        default,
+        step: 0.01,
+        uniform_offset,
+    }
+}
+
+const RATIONAL: FractalDefinition = FractalDefinition {
+    name: "Rational map",
+    entry_point: "rational",
+    antialiasing_entry_point: "rational_antialiased",
+    iteration_data_entry_point: "rational_iteration_data",
+    df64_iteration_data_entry_point: None,
+    perturbation_iteration_data_entry_point: None,
+    // The iteration data has the same form as the Mandelbrot set's.
+    colouring_entry_point: "mandelbrot_colouring",
+    parameters: &[
+        coefficient("Numerator z³", 1.0, 0),
+        coefficient("Numerator z²", 0.0, 1),
+        coefficient("Numerator z", 0.0, 2),
+        coefficient("Numerator 1", 0.0, 3),
+        coefficient("Denominator z³", 0.0, 4),
+        coefficient("Denominator z²", 0.0, 5),
+        coefficient("Denominator z", 1.0, 6),
+        coefficient("Denominator 1", 0.3, 7),
+    ],
+    // Starts with z³ / (z + 0.3) + c, which is quadratic far from the pole, so that the orbit of
+    // the critical point at zero draws a lopsided relative of the Mandelbrot set.
+    profile: FractalProfile {
+        centre: [-0.25, 0.0],
+        half_width: 1.75,
+        bailout: 4.0,
+        max_iterations: 200,
+        palette: Palette::Viridis,
+    },
+};
+
+/// The parameters which shape the fractal noise of the plasma and terrain, read by `fbm` and
+/// `ridged_multifractal` in `frag.wgsl`.
+const OCTAVES: ParameterDefinition = ParameterDefinition {
+    name: "Octaves",
+    min: 1.0,
+    max: 12.0,
+    default: 6.0,
+    step: 1.0,
+    uniform_offset: 0,
+};
+const LACUNARITY: ParameterDefinition = ParameterDefinition {
+    name: "Lacunarity",
+    min: 1.5,
+    max: 4.0,
+    default: 2.0,
+    step: 0.01,
+    uniform_offset: 1,
+};
+const GAIN: ParameterDefinition = ParameterDefinition {
+    name: "Gain",
+    min: 0.1,
+    max: 0.9,
+    default: 0.5,
+    step: 0.01,
+    uniform_offset: 2,
+};
+const SEED: ParameterDefinition = ParameterDefinition {
+    name: "Seed",
+    min: 0.0,
+    max: 1000.0,
+    default: 0.0,
+    step: 1.0,
+    uniform_offset: 3,
+};
+
+const PLASMA: FractalDefinition = FractalDefinition {
+    name: "Plasma",
+    entry_point: "plasma",
+    antialiasing_entry_point: "plasma_antialiased",
+    iteration_data_entry_point: "plasma_iteration_data",
+    df64_iteration_data_entry_point: None,
+    perturbation_iteration_data_entry_point: None,
+    colouring_entry_point: "plasma_colouring",
+    parameters: &[OCTAVES, LACUNARITY, GAIN, SEED],
+    // The noise has features about a unit apart at its coarsest octave. Nothing is iterated, so
+    // neither the bailout nor the iteration budget matter.
+    profile: FractalProfile {
+        centre: [0.0, 0.0],
+        half_width: 4.0,
+        bailout: DEFAULT_BAILOUT,
+        max_iterations: DEFAULT_MAX_ITERATIONS,
+        palette: Palette::BlueOrange,
+    },
+};
+
+const TERRAIN: FractalDefinition = FractalDefinition {
+    name: "Terrain",
+    entry_point: "terrain",
+    antialiasing_entry_point: "terrain_antialiased",
+    iteration_data_entry_point: "terrain_iteration_data",
+    df64_iteration_data_entry_point: None,
+    perturbation_iteration_data_entry_point: None,
+    colouring_entry_point: "terrain_colouring",
+    parameters: &[
+        OCTAVES,
+        LACUNARITY,
+        GAIN,
+        SEED,
+        ParameterDefinition {
+            name: "Relief",
+            min: 0.0,
+            max: 4.0,
+            default: 1.0,
+            step: 0.01,
+            uniform_offset: 4,
+        },
+        ParameterDefinition {
+            name: "Sun azimuth",
+            min: 0.0,
+            max: 360.0,
+            default: 315.0,
+            step: 1.0,
+            uniform_offset: 5,
+        },
+        ParameterDefinition {
+            name: "Sun elevation",
+            min: 5.0,
+            max: 90.0,
+            default: 45.0,
+            step: 1.0,
+            uniform_offset: 6,
+        },
+    ],
+    // Framed closer than the plasma, so that the finest octaves span a few pixels rather than
+    // speckling, and lit from the north-west as cartographers light maps.
+    profile: FractalProfile {
+        centre: [0.0, 0.0],
+        half_width: 1.5,
+        bailout: DEFAULT_BAILOUT,
+        max_iterations: DEFAULT_MAX_ITERATIONS,
+        palette: Palette::Viridis,
+    },
+};
+
+impl FractalType {
+    pub(super) fn definition(self) -> &'static FractalDefinition {
+        match self {
+            FractalType::Mandelbrot => &MANDELBROT,
+            FractalType::Newton => &NEWTON,
+            FractalType::DampedNewton => &DAMPED_NEWTON,
+            FractalType::Secant => &SECANT,
+            FractalType::Multibrot => &MULTIBROT,
+            FractalType::Rational => &RATIONAL,
+            FractalType::Plasma => &PLASMA,
+            FractalType::Terrain => &TERRAIN,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::MAX_PARAMETERS;
+    use crate::{
+        controls::FractalType,
+        iteration_budget::{MAX_ITERATIONS, MIN_ITERATIONS},
+    };
+    use googletest::prelude::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn parameters_have_distinct_offsets_within_uniform_block() -> Result<()> {
+        for fractal_type in FractalType::ALL {
+            let parameters = fractal_type.definition().parameters;
+            let offsets: HashSet<usize> = parameters
+                .iter()
+                .map(|parameter| parameter.uniform_offset)
+                .collect();
+
+            verify_that!(offsets.len(), eq(parameters.len()))?;
+            for offset in offsets {
+                verify_that!(offset, lt(MAX_PARAMETERS))?;
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn defaults_lie_within_range() -> Result<()> {
+        for fractal_type in FractalType::ALL {
+            for parameter in fractal_type.definition().parameters {
+                verify_that!(
+                    parameter.default,
+                    all!(ge(parameter.min), le(parameter.max))
+                )?;
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn profiles_lie_within_limits_of_controls() -> Result<()> {
+        for fractal_type in FractalType::ALL {
+            let profile = &fractal_type.definition().profile;
+            verify_that!(
+                profile.max_iterations,
+                all!(ge(MIN_ITERATIONS), le(MAX_ITERATIONS))
+            )?;
+            // Escape times are only smoothed correctly beyond a radius of two.
+            verify_that!(profile.bailout, ge(2.0))?;
+        }
+        Ok(())
+    }
+}
@@ -1,27 +1,138 @@
-use crate::{controls::FractalType, gpu::Gpu};
-use bytemuck::{Pod, Zeroable};
-use cgmath::{Matrix, Matrix3, Vector2};
+use crate::{
+    antialiasing::AdaptiveAntialiasing,
+    blit::ReducedResolution,
+    bloom::Bloom,
+    bookmark::Bookmark,
+    chaos_game::{ChaosGame, ChaosGameRenderer},
+    colour_palette::{Palette, GRADIENT_STOPS},
+    compositor::Compositor,
+    controls::FractalType,
+    critical_points,
+    debug_view::DebugView,
+    explorer_grid::{ExplorerGrid, GridRenderer},
+    floatexp,
+    fractal_definition::{FractalDefinition, MAX_PARAMETERS},
+    gpu::Gpu,
+    gpu_memory::GpuMemoryUsage,
+    isolines::Isolines,
+    iteration_budget::MAX_ITERATIONS,
+    iteration_data::{IterationData, IterationInputs},
+    jobs::JobStatus,
+    julia_inset::JuliaInset,
+    modulation::ModulationOffsets,
+    orbit_trap::OrbitTrap,
+    pipeline_compiler::{CompiledPipelines, FractalPipelines, PipelineCompiler, PipelineLayouts},
+    plane::Plane,
+    post_processing::{PostProcessing, Tonemapper},
+    precision::NumericMode,
+    reference_orbit::ReferenceOrbits,
+    std140::Std140Writer,
+    thumbnail::ThumbnailRenderer,
+    tile_cache::{TileCache, TileKey, TilePersistence},
+    transform::ViewTransform,
+    transparency::Transparency,
+    variation::Variation,
+    vector_mode::{CurveRenderer, VectorMode},
+};
+use cgmath::{Matrix3, Vector2};
+use iced::time::Instant;
 use iced_wgpu::wgpu::{self, util::DeviceExt};
-use std::num::NonZeroU64;
+use std::{collections::HashMap, num::NonZeroU64};
 
 // Two triangles which form a square [-1,-1] - [1,1]
 const VERTICES: &[[f32; 2]] = &[[-1.0, -1.0], [1.0, -1.0], [-1.0, 1.0], [1.0, 1.0]];
 const INDICES: &[[u16; 3]] = &[[0, 1, 2], [1, 2, 3]];
 
-const ORIGINAL_VIEWPORT_WIDTH: f32 = 4.0;
+/// The number of iterations after which a point of the Mandelbrot set is considered not to escape,
+/// unless changed with [`View::set_max_iterations`].
+pub(super) const DEFAULT_MAX_ITERATIONS: u32 = 1000;
+
+/// The escape radius, unless changed with [`View::set_bailout`].
+pub(super) const DEFAULT_BAILOUT: f32 = 2.0;
+
+/// The half width of the view on which the curves of the vector mode and the polygon of the chaos
+/// game are framed, which leaves a margin around the square into which they fit.
+const FRAMED_HALF_WIDTH: f64 = 1.1;
+
+/// The factor by which the resolution is reduced along each axis in low-power mode.
+const LOW_POWER_RESOLUTION_DIVISOR: u32 = 2;
 
 pub(super) struct View {
-    pipeline_layout: wgpu::PipelineLayout,
-    fs_module: wgpu::ShaderModule,
-    vs_module: wgpu::ShaderModule,
-    pipeline: wgpu::RenderPipeline,
+    /// The pipelines of the fractal types which have been compiled so far.
+    pipelines: HashMap<FractalType, FractalPipelines>,
+    compiled_pipelines: CompiledPipelines,
+    fractal_type: FractalType,
     vertex_buffer: wgpu::Buffer,
     index_buffer: wgpu::Buffer,
     bind_group: wgpu::BindGroup,
-    #[cfg(test)]
     bind_group_layout: wgpu::BindGroupLayout,
+    antialiasing: Option<AdaptiveAntialiasing>,
+    /// The result of iterating each pixel, from which the fractal is coloured.
+    iteration_data: IterationData,
+    /// Present in low-power mode, in which the fractal is rendered at a reduced resolution.
+    reduced_resolution: Option<ReducedResolution>,
+    /// Present while the bright parts of the image glow.
+    bloom: Option<Bloom>,
+    /// Present while isolines are drawn over the fractal.
+    isolines: Option<Isolines>,
+    /// Present while a geometric curve is drawn instead of the fractal.
+    curve: Option<CurveRenderer>,
+    /// Present while the chaos game is played instead of rendering the fractal.
+    chaos_game: Option<ChaosGameRenderer>,
+    /// Present while the view is replaced by an explorer grid of thumbnails which vary its
+    /// settings.
+    explorer_grid: Option<GridRenderer>,
+    /// Renders the thumbnails of the explorer grid and the Julia inset offscreen.
+    thumbnails: ThumbnailRenderer,
+    /// Previews the Julia set of the point under the cursor.
+    julia_inset: JuliaInset,
+    /// Present while the iteration data of deep views is kept on disk.
+    tiles: Option<TilePersistence>,
     uniform_buffer: wgpu::Buffer,
-    view_transform: Matrix3<f32>,
+    transform: ViewTransform,
+    /// The arithmetic with which the iteration data is computed.
+    numeric_mode: NumericMode,
+    /// The orbit from which the iteration data is computed by perturbation.
+    reference_orbits: ReferenceOrbits,
+    max_iterations: u32,
+    bailout: f32,
+    palette_offset: f32,
+    palette: Palette,
+    post_processing: PostProcessing,
+    compositor: Compositor,
+    transparency: Transparency,
+    trap: OrbitTrap,
+    variation: Variation,
+    /// The seconds for which the time has played, which animates a spinning variation.
+    time: f32,
+    debug_view: DebugView,
+    /// Seeds everything random in the view, so that it can be reproduced exactly.
+    seed: u32,
+    /// Which critical point the orbits of a rational map start from.
+    critical_point: usize,
+    plane: Plane,
+    /// The framing of the parameter plane from which the dynamic plane was entered, to which
+    /// leaving it returns.
+    parameter_plane_transform: Option<ViewTransform>,
+    /// Whether the targets into which the view renders store linear light.
+    linear_output: bool,
+    /// Samples the image of the orbit trap, whose texture is bound alongside the uniform block.
+    trap_sampler: wgpu::Sampler,
+    /// Another palette towards which `palette` is blended, and by how much, during a morph.
+    palette_blend: Option<(Palette, f32)>,
+    parameter_values: Vec<f32>,
+    /// How far the view is zoomed in beyond its transform for effects, as a fraction of its
+    /// size.
+    zoom_pulse: f32,
+    modulation: ModulationOffsets,
+    /// Settings with which the part of the view right of `divider` is rendered, at the same
+    /// location, so that they can be compared side by side with the current ones.
+    comparison: Option<Bookmark>,
+    /// The position of the divider between the current and compared settings, as a fraction of
+    /// the width of the view.
+    divider: f32,
+    comparison_uniform_buffer: wgpu::Buffer,
+    comparison_bind_group: wgpu::BindGroup,
 }
 
 impl View {
@@ -40,230 +151,2960 @@ impl View {
                 contents: bytemuck::cast_slice(INDICES),
                 usage: wgpu::BufferUsages::INDEX,
             });
-        let view_transform =
-            Matrix3::from_scale(2.0) * Matrix3::from_translation(Vector2::new(-0.25, 0.0));
         let uniform_buffer = gpu.device.create_buffer(&wgpu::BufferDescriptor {
             label: Some("Uniform buffer"),
             usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::UNIFORM,
-            size: std::mem::size_of::<Uniform>() as u64,
+            size: ParameterBlock::layout().size as u64,
             mapped_at_creation: false,
         });
         let bind_group_layout =
             gpu.device
                 .create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
                     label: Some("Bind group layout"),
-                    entries: &[Uniform::layout_entry()],
+                    entries: &[
+                        ParameterBlock::layout_entry(),
+                        wgpu::BindGroupLayoutEntry {
+                            binding: 1,
+                            visibility: wgpu::ShaderStages::FRAGMENT | wgpu::ShaderStages::COMPUTE,
+                            ty: wgpu::BindingType::Texture {
+                                sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                                view_dimension: wgpu::TextureViewDimension::D2,
+                                multisampled: false,
+                            },
+                            count: None,
+                        },
+                        wgpu::BindGroupLayoutEntry {
+                            binding: 2,
+                            visibility: wgpu::ShaderStages::FRAGMENT | wgpu::ShaderStages::COMPUTE,
+                            ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                            count: None,
+                        },
+                    ],
                 });
-        let pipeline_layout = gpu
-            .device
-            .create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
-                label: None,
-                push_constant_ranges: &[],
-                bind_group_layouts: &[&bind_group_layout],
-            });
-        let bind_group = gpu.device.create_bind_group(&wgpu::BindGroupDescriptor {
-            label: Some("Bind group"),
-            layout: &bind_group_layout,
-            entries: &[wgpu::BindGroupEntry {
-                binding: 0,
-                resource: uniform_buffer.as_entire_binding(),
-            }],
-        });
-        let (vs_module, fs_module) = (
-            gpu.device
-                .create_shader_module(wgpu::include_wgsl!("shader/vert.wgsl")),
+        let iteration_data = IterationData::new(gpu.device.clone());
+        let reference_orbits = ReferenceOrbits::new(gpu.device.clone());
+        let create_pipeline_layout = |others: &[&wgpu::BindGroupLayout]| {
             gpu.device
-                .create_shader_module(wgpu::include_wgsl!("shader/frag.wgsl")),
-        );
-        let pipeline = Self::build_pipeline(
+                .create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                    label: None,
+                    push_constant_ranges: &[],
+                    bind_group_layouts: &[&bind_group_layout]
+                        .into_iter()
+                        .chain(others.iter().copied())
+                        .collect::<Vec<_>>(),
+                })
+        };
+        let pipeline_layouts = PipelineLayouts {
+            render: create_pipeline_layout(&[]),
+            iteration_data: create_pipeline_layout(&[iteration_data.storage_bind_group_layout()]),
+            perturbation_iteration_data: create_pipeline_layout(&[
+                iteration_data.storage_bind_group_layout(),
+                reference_orbits.bind_group_layout(),
+            ]),
+            colouring: create_pipeline_layout(&[iteration_data.sampled_bind_group_layout()]),
+        };
+        let trap_sampler = gpu.device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("Orbit trap sampler"),
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+        // Until an image is loaded, the trap is plain white.
+        let trap_image = create_trap_image(
             gpu,
-            &pipeline_layout,
-            &vs_module,
-            &fs_module,
-            Self::entry_point_for_fractal_type(FractalType::Mandelbrot),
+            &image::RgbaImage::from_pixel(1, 1, image::Rgba([255; 4])),
+        );
+        let bind_group = create_bind_group(
+            &gpu.device,
+            &bind_group_layout,
+            "Bind group",
+            &uniform_buffer,
+            &trap_image,
+            &trap_sampler,
+        );
+        let comparison_uniform_buffer = gpu.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Comparison uniform buffer"),
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::UNIFORM,
+            size: ParameterBlock::layout().size as u64,
+            mapped_at_creation: false,
+        });
+        let comparison_bind_group = create_bind_group(
+            &gpu.device,
+            &bind_group_layout,
+            "Comparison bind group",
+            &comparison_uniform_buffer,
+            &trap_image,
+            &trap_sampler,
+        );
+        let thumbnails =
+            create_thumbnail_renderer(gpu, &bind_group_layout, &trap_image, &trap_sampler);
+        let compiler =
+            PipelineCompiler::new(gpu.device.clone(), gpu.texture_format, pipeline_layouts);
+        let fractal_type = FractalType::Mandelbrot;
+        // The initial fractal is needed for the first frame, while the others are only needed once
+        // the user switches to them.
+        let pipelines = HashMap::from([(fractal_type, compiler.compile(fractal_type))]);
+        let compiled_pipelines = compiler.compile_in_background(
+            FractalType::ALL
+                .into_iter()
+                .filter(|other| *other != fractal_type)
+                .collect(),
         );
         Self {
-            pipeline_layout,
-            fs_module,
-            vs_module,
-            pipeline,
+            pipelines,
+            compiled_pipelines,
+            fractal_type,
             vertex_buffer,
             index_buffer,
             uniform_buffer,
-            view_transform,
+            transform: ViewTransform::new(fractal_type.definition().profile.view_transform()),
+            numeric_mode: NumericMode::F32,
+            reference_orbits,
+            max_iterations: DEFAULT_MAX_ITERATIONS,
+            bailout: DEFAULT_BAILOUT,
+            palette_offset: 0.0,
+            palette: Palette::default(),
+            post_processing: PostProcessing::default(),
+            compositor: Compositor::default(),
+            transparency: Transparency::default(),
+            trap: OrbitTrap::default(),
+            variation: Variation::default(),
+            time: 0.0,
+            debug_view: DebugView::Off,
+            seed: 0,
+            critical_point: 0,
+            plane: Plane::Parameter,
+            parameter_plane_transform: None,
+            linear_output: gpu.stores_linear_light(),
+            trap_sampler,
+            palette_blend: None,
+            parameter_values: fractal_type.definition().default_values(),
+            zoom_pulse: 0.0,
+            modulation: ModulationOffsets::default(),
+            comparison: None,
+            divider: 0.5,
+            comparison_uniform_buffer,
+            comparison_bind_group,
             bind_group,
-            #[cfg(test)]
             bind_group_layout,
+            antialiasing: None,
+            iteration_data,
+            reduced_resolution: None,
+            bloom: None,
+            isolines: None,
+            curve: None,
+            chaos_game: None,
+            tiles: None,
+            explorer_grid: None,
+            thumbnails,
+            julia_inset: JuliaInset::new(),
+        }
+    }
+
+    pub(super) fn render(&self, target: &wgpu::Texture, encoder: &mut wgpu::CommandEncoder) {
+        self.thumbnails.render(
+            encoder,
+            &self.pipelines,
+            &self.vertex_buffer,
+            &self.index_buffer,
+        );
+        if let Some(explorer_grid) = &self.explorer_grid {
+            explorer_grid.render(
+                &ExplorerGrid::of(self.fractal_type),
+                &self.thumbnails,
+                target,
+                encoder,
+            );
+            return;
+        }
+        match &self.bloom {
+            Some(bloom) => bloom.render(target, encoder, |scene, encoder| {
+                self.render_without_bloom(scene, encoder)
+            }),
+            None => self.render_without_bloom(target, encoder),
+        }
+    }
+
+    /// Draws the preview of the Julia set of the point under the cursor over a corner of the
+    /// target, which only the window does after rendering the view.
+    pub(super) fn render_julia_inset(
+        &self,
+        target: &wgpu::Texture,
+        encoder: &mut wgpu::CommandEncoder,
+    ) {
+        if self.shows_parameter_plane() {
+            self.julia_inset.render(&self.thumbnails, target, encoder);
+        }
+    }
+
+    fn render_without_bloom(&self, target: &wgpu::Texture, encoder: &mut wgpu::CommandEncoder) {
+        match &self.reduced_resolution {
+            Some(reduced_resolution) => {
+                reduced_resolution.render(target, encoder, |reduced, encoder| {
+                    self.render_full_resolution(reduced, encoder)
+                })
+            }
+            None => self.render_full_resolution(target, encoder),
+        }
+    }
+
+    fn render_full_resolution(&self, target: &wgpu::Texture, encoder: &mut wgpu::CommandEncoder) {
+        if let Some(curve) = &self.curve {
+            let pixel_size =
+                (2.0 * self.transform.half_width() / target.size().width as f64) as f32;
+            curve.render(target, &self.bind_group, pixel_size, encoder);
+            return;
+        }
+        if let Some(chaos_game) = &self.chaos_game {
+            chaos_game.render(target, &self.bind_group, encoder);
+            return;
+        }
+        // The resampling pass only knows the current settings, so anti-aliasing is paused while
+        // comparing, which also keeps both sides alike. It colours with the palette, so it is
+        // paused in debug views as well.
+        match self
+            .antialiasing
+            .as_ref()
+            .filter(|_| self.comparison.is_none() && self.debug_view == DebugView::Off)
+        {
+            Some(antialiasing) => antialiasing.render(
+                target,
+                self.fractal_type,
+                &self.bind_group,
+                encoder,
+                |initial, encoder| self.draw(initial, target.size(), encoder),
+            ),
+            None => self.draw(
+                &target.create_view(&wgpu::TextureViewDescriptor::default()),
+                target.size(),
+                encoder,
+            ),
+        }
+        // Escape times are only defined for the Mandelbrot set.
+        if let Some(isolines) = &self.isolines {
+            if self.fractal_type == FractalType::Mandelbrot {
+                if let Some(iteration_data) = self.iteration_data.current(target.size()) {
+                    isolines.render(target, &iteration_data, encoder);
+                }
+            }
+        }
+    }
+
+    /// Renders the fractal with one sample per pixel onto a target of the given size.
+    ///
+    /// Pixels are only iterated again if the iteration data is out of date, so that only the
+    /// colouring pass runs when just the colouring changed.
+    fn draw(
+        &self,
+        target: &wgpu::TextureView,
+        size: wgpu::Extent3d,
+        encoder: &mut wgpu::CommandEncoder,
+    ) {
+        let pipelines = self.pipelines.get(&self.fractal_type);
+        if let Some(tiles) = &self.tiles {
+            if self.iteration_data.is_stale(size) {
+                if let Some(data) = self.tile_key(size).and_then(|key| tiles.restore(key, size)) {
+                    self.iteration_data.restore(size, &data, encoder);
+                }
+            }
+        }
+        let iteration_data = pipelines.map(|pipelines| {
+            let (pipeline, reference_orbit) = self.iteration_data_pipeline(pipelines);
+            self.iteration_data
+                .compute(pipeline, &self.bind_group, reference_orbit, size, encoder)
+        });
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: None,
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: target,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+
+        // Until the pipelines are compiled, the view is left blank.
+        let (Some(pipelines), Some(iteration_data)) = (pipelines, &iteration_data) else {
+            return;
+        };
+        render_pass.set_pipeline(&pipelines.colouring);
+        render_pass.set_bind_group(0, &self.bind_group, &[]);
+        render_pass.set_bind_group(1, iteration_data, &[]);
+        render_pass.draw(0..3, 0..1);
+
+        // The compared side is rendered in a single pass, since it has iteration data of its own.
+        let Some(pipelines) = self
+            .comparison
+            .as_ref()
+            .and_then(|comparison| self.pipelines.get(&comparison.fractal_type))
+        else {
+            return;
+        };
+        let divider = (self.divider * size.width as f32).round() as u32;
+        if divider >= size.width {
+            return;
+        }
+        render_pass.set_scissor_rect(divider, 0, size.width - divider, size.height);
+        render_pass.set_pipeline(&pipelines.render);
+        render_pass.set_bind_group(0, &self.comparison_bind_group, &[]);
+        render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
+        render_pass.set_index_buffer(self.index_buffer.slice(..), wgpu::IndexFormat::Uint16);
+        render_pass.draw_indexed(0..6, 0, 0..1);
+    }
+
+    /// Returns the pipeline which computes the iteration data in the numeric mode, and the bind
+    /// group of the reference orbit if it iterates by perturbation.
+    ///
+    /// Perturbation does not apply to variations, which render in double-float instead, as does
+    /// the view until the reference orbit is bound. Fractals without either variant and the
+    /// dynamic plane use `f32`.
+    fn iteration_data_pipeline<'a>(
+        &'a self,
+        pipelines: &'a FractalPipelines,
+    ) -> (&'a wgpu::ComputePipeline, Option<&'a wgpu::BindGroup>) {
+        if self.plane != Plane::Parameter {
+            return (&pipelines.iteration_data, None);
+        }
+        if self.numeric_mode == NumericMode::Perturbation && self.variation == Variation::default()
+        {
+            if let (Some(pipeline), Some((_, bind_group))) = (
+                &pipelines.iteration_data_perturbation,
+                self.reference_orbits.bound(),
+            ) {
+                return (pipeline, Some(bind_group));
+            }
+        }
+        match (self.numeric_mode, &pipelines.iteration_data_df64) {
+            (NumericMode::DoubleDouble | NumericMode::Perturbation, Some(pipeline)) => {
+                (pipeline, None)
+            }
+            _ => (&pipelines.iteration_data, None),
+        }
+    }
+
+    /// Uploads the view transform, iteration budget and fractal parameters to the GPU.
+    pub(super) fn update_transform(&self, queue: &iced_wgpu::wgpu::Queue) {
+        let inputs = self.iteration_inputs();
+        let transform = inputs.transform;
+        let block = self.parameter_block();
+        queue.write_buffer(&self.uniform_buffer, 0, &block.std140_bytes());
+        if let Some(explorer_grid) = &self.explorer_grid {
+            explorer_grid.update(
+                &ExplorerGrid::of(self.fractal_type),
+                block,
+                &self.thumbnails,
+            );
+        }
+        if self.shows_parameter_plane() {
+            self.julia_inset.update(
+                self.fractal_type,
+                block,
+                self.definition().profile.half_width,
+                &self.thumbnails,
+            );
+        }
+        self.thumbnails.upload(queue);
+        self.iteration_data.set_uploaded(inputs);
+        if let Some(isolines) = &self.isolines {
+            isolines.update_levels(queue);
+        }
+        if let Some(comparison) = &self.comparison {
+            let (gradient, root_colours) = comparison.palette.pack();
+            let params = comparison
+                .fractal_type
+                .definition()
+                .pack(&comparison.parameter_values);
+            queue.write_buffer(
+                &self.comparison_uniform_buffer,
+                0,
+                &ParameterBlock {
+                    transform,
+                    max_iterations: comparison.max_iterations,
+                    bailout: self.bailout,
+                    palette_offset: comparison.palette_offset,
+                    params,
+                    gradient,
+                    root_colours,
+                    post_processing: self.post_processing.pack(),
+                    trap: self.trap.pack(),
+                    variation: self.variation.bits(),
+                    centre_residual: self.transform.centre_residual().into(),
+                    reference_offset: inputs.reference_offset.into(),
+                    time: self.time,
+                    debug_view: self.debug_view.code(),
+                    seed: comparison.seed,
+                    linear_output: self.linear_output.into(),
+                    tonemapper: self.post_processing.tonemapper.code(),
+                    critical_point: critical_point(
+                        comparison.fractal_type,
+                        &params,
+                        comparison.critical_point,
+                    )
+                    .into(),
+                    fixed_c: comparison.plane.fixed_c().into(),
+                    plane: comparison.plane.code(),
+                    base_layer: self.compositor.base.code(),
+                    overlay_layer: self.compositor.overlay.code(),
+                    blend_mode: self.compositor.blend_mode.code(),
+                    overlay_opacity: self.compositor.opacity,
+                    transparency: self.transparency.code(),
+                    scale_exponent: inputs.scale_exponent,
+                    reference_offset_exponent: inputs.reference_offset_exponent,
+                }
+                .std140_bytes(),
+            );
+        }
+    }
+
+    /// The inputs from which the iteration data of the current view is computed.
+    fn iteration_inputs(&self) -> IterationInputs {
+        let params = self.definition().pack(&self.modulated_parameters());
+        let (reference_offset, reference_offset_exponent) = self.reference_offset();
+        IterationInputs {
+            fractal_type: self.fractal_type,
+            transform: self.transform.matrix()
+                * Matrix3::from_scale(1.0 / (1.0 + self.zoom_pulse + self.modulation.zoom)),
+            max_iterations: self.max_iterations,
+            bailout: self.bailout,
+            params,
+            critical_point: critical_point(self.fractal_type, &params, self.critical_point),
+            plane: self.plane,
+            trap: self.trap,
+            variation: self.variation,
+            centre_residual: self.transform.centre_residual(),
+            scale_exponent: self.transform.scale_exponent(),
+            reference_offset,
+            reference_offset_exponent,
+            numeric_mode: self.numeric_mode,
+            // Only the spinning variation depends on the time, which must not recompute the
+            // iteration data of other fractals while it plays.
+            time: if self.variation.spin { self.time } else { 0.0 },
+            debug_view: self.debug_view,
+            layer_statistics: self.compositor.needs_statistics(),
+        }
+    }
+
+    /// The uniform block which draws the current fractal, view and colouring, including the
+    /// effects of animations and modulation.
+    pub(super) fn parameter_block(&self) -> ParameterBlock {
+        let (gradient, root_colours) = match self.palette_blend {
+            Some((other, amount)) => self.palette.pack_blended(other, amount),
+            None => self.palette.pack(),
+        };
+        let inputs = self.iteration_inputs();
+        ParameterBlock {
+            transform: inputs.transform,
+            max_iterations: inputs.max_iterations,
+            bailout: inputs.bailout,
+            palette_offset: self.palette_offset + self.modulation.palette_offset,
+            params: inputs.params,
+            gradient,
+            root_colours,
+            post_processing: self.post_processing.pack(),
+            trap: self.trap.pack(),
+            variation: self.variation.bits(),
+            centre_residual: self.transform.centre_residual().into(),
+            reference_offset: inputs.reference_offset.into(),
+            time: self.time,
+            debug_view: self.debug_view.code(),
+            seed: self.seed,
+            linear_output: self.linear_output.into(),
+            tonemapper: self.post_processing.tonemapper.code(),
+            critical_point: inputs.critical_point.into(),
+            fixed_c: inputs.plane.fixed_c().into(),
+            plane: inputs.plane.code(),
+            base_layer: self.compositor.base.code(),
+            overlay_layer: self.compositor.overlay.code(),
+            blend_mode: self.compositor.blend_mode.code(),
+            overlay_opacity: self.compositor.opacity,
+            transparency: self.transparency.code(),
+            scale_exponent: inputs.scale_exponent,
+            reference_offset_exponent: inputs.reference_offset_exponent,
+        }
+    }
+
+    /// Moves the view by the given fraction of the width and height of the viewport.
+    pub(super) fn translate(&mut self, displacement: Vector2<f32>) {
+        self.transform.translate(displacement);
+    }
+
+    /// Scales the view by the given factor about the given point, as an offset from the centre of
+    /// the viewport in fractions of its width and height.
+    pub(super) fn zoom(&mut self, factor: f32, on_point: Vector2<f32>) {
+        self.transform.zoom(factor, on_point);
+    }
+
+    /// The centre of the view on the plane, including the residual beyond the precision of the
+    /// transform.
+    pub(super) fn centre(&self) -> Vector2<f64> {
+        self.transform.centre()
+    }
+
+    /// The point of the plane at the given offset from the centre of the view, in the units in
+    /// which [`Self::zoom`] takes the point to zoom on.
+    pub(super) fn point_at(&self, offset: Vector2<f32>) -> Vector2<f64> {
+        self.transform.point_at(offset)
+    }
+
+    /// The offset of the given point of the plane from the centre of the view, the inverse of
+    /// [`Self::point_at`].
+    pub(super) fn offset_of(&self, point: Vector2<f64>) -> Vector2<f32> {
+        self.transform.offset_of(point)
+    }
+
+    pub(super) fn transform(&self) -> ViewTransform {
+        self.transform
+    }
+
+    pub(super) fn get_view_transform(&self) -> Matrix3<f32> {
+        self.transform.matrix()
+    }
+
+    /// Frames the view on the given point of the complex plane, with `half_width` being the
+    /// distance from the centre to the edges of the viewport.
+    pub(super) fn look_at(&mut self, center: Vector2<f32>, half_width: f32) {
+        self.transform = ViewTransform::looking_at(center.cast().unwrap(), half_width.into());
+    }
+
+    /// Like [`Self::look_at`], keeping the precision of the centre beyond that of `f32` for the
+    /// double-float mode.
+    pub(super) fn look_at_precisely(&mut self, center: Vector2<f64>, half_width: f64) {
+        self.transform = ViewTransform::looking_at(center, half_width);
+    }
+
+    /// Narrows the view to the rectangle of the viewport between the given corners, so that a tile
+    /// of a larger image can be rendered on its own.
+    pub(super) fn crop(&mut self, bottom_left: Vector2<f32>, top_right: Vector2<f32>) {
+        self.transform.crop(bottom_left, top_right);
+    }
+
+    /// Calls `f` with the view narrowed to the rectangle as [`Self::crop`] does, and then returns to
+    /// the framing shown before.
+    pub(super) fn with_crop<T>(
+        &mut self,
+        bottom_left: Vector2<f32>,
+        top_right: Vector2<f32>,
+        f: impl FnOnce(&Self) -> T,
+    ) -> T {
+        let transform = self.transform;
+        self.transform.crop(bottom_left, top_right);
+        let result = f(self);
+        self.transform = transform;
+        result
+    }
+
+    /// Switches the arithmetic with which the iteration data is computed. Fractals without a
+    /// double-float variant, anti-aliasing and the compared side always use `f32`.
+    pub(super) fn set_numeric_mode(&mut self, numeric_mode: NumericMode) {
+        self.numeric_mode = numeric_mode;
+    }
+
+    /// Binds a reference orbit which serves the view while it is rendered by perturbation, and
+    /// returns whether the bound orbit changed.
+    pub(super) fn update_reference_orbit(&mut self) -> bool {
+        if self.numeric_mode != NumericMode::Perturbation || self.plane != Plane::Parameter {
+            return false;
+        }
+        let half_width = self.transform.half_width();
+        let changed = self
+            .reference_orbits
+            .update(self.centre(), half_width, self.max_iterations);
+        if changed {
+            // The uploaded inputs cannot tell orbits at the same point apart.
+            self.iteration_data.discard();
+        }
+        changed
+    }
+
+    /// The progress of the reference orbit closer to the view which is being computed in the
+    /// background, if any.
+    pub(super) fn reference_orbit_job(&self) -> Option<JobStatus> {
+        self.reference_orbits.computing()
+    }
+
+    /// Stops computing the reference orbit in the background, if any, leaving the view rendered
+    /// with the bound orbit until it moves on.
+    pub(super) fn cancel_reference_orbit(&mut self) {
+        self.reference_orbits.cancel();
+    }
+
+    /// The offset of the centre of the view from the point of the bound reference orbit, split
+    /// into the mantissa and exponent of a floatexp number.
+    fn reference_offset(&self) -> (Vector2<f32>, i32) {
+        self.reference_orbits
+            .bound()
+            .map_or((Vector2::new(0.0, 0.0), 0), |(orbit, _)| {
+                floatexp::split_point(self.centre() - orbit.point())
+            })
+    }
+
+    /// How many times larger the fractal appears than in the initial view of its profile.
+    pub(super) fn magnification(&self) -> f64 {
+        f64::from(self.definition().profile.half_width) / self.transform.half_width()
+    }
+
+    pub(super) fn max_iterations(&self) -> u32 {
+        self.max_iterations
+    }
+
+    /// Sets the iteration budget, which is capped at [`MAX_ITERATIONS`] however it was set.
+    pub(super) fn set_max_iterations(&mut self, max_iterations: u32) {
+        self.max_iterations = max_iterations.min(MAX_ITERATIONS);
+    }
+
+    pub(super) fn bailout(&self) -> f32 {
+        self.bailout
+    }
+
+    pub(super) fn set_bailout(&mut self, bailout: f32) {
+        self.bailout = bailout;
+    }
+
+    pub(super) fn palette_offset(&self) -> f32 {
+        self.palette_offset
+    }
+
+    /// Rotates the palette by the given fraction, so that the colour bands move across the set.
+    pub(super) fn set_palette_offset(&mut self, palette_offset: f32) {
+        self.palette_offset = palette_offset;
+    }
+
+    /// Zooms in on the centre by the given fraction without changing the view transform, so that
+    /// the view can pulse without drifting.
+    #[cfg_attr(not(feature = "audio"), allow(dead_code))]
+    pub(super) fn set_zoom_pulse(&mut self, zoom_pulse: f32) {
+        self.zoom_pulse = zoom_pulse;
+    }
+
+    /// Moves the palette offset, zoom and parameters away from their values by the given offsets
+    /// until they are changed again.
+    pub(super) fn set_modulation(&mut self, modulation: ModulationOffsets) {
+        self.modulation = modulation;
+    }
+
+    /// Returns the values of the parameters with the modulation applied, within their ranges.
+    fn modulated_parameters(&self) -> Vec<f32> {
+        self.definition()
+            .parameters
+            .iter()
+            .zip(&self.parameter_values)
+            .zip(self.modulation.parameters)
+            .enumerate()
+            .map(|(index, ((parameter, value), offset))| {
+                let range = parameter.max - parameter.min;
+                let value = match self.modulation.sweep {
+                    Some((swept, position)) if swept == index => parameter.min + position * range,
+                    _ => *value,
+                };
+                (value + offset * range).clamp(parameter.min, parameter.max)
+            })
+            .collect()
+    }
+
+    /// Adjusts the colours of the palette, for both sides of a comparison.
+    pub(super) fn set_post_processing(&mut self, post_processing: PostProcessing) {
+        self.post_processing = post_processing;
+    }
+
+    pub(super) fn compositor(&self) -> Compositor {
+        self.compositor
+    }
+
+    pub(super) fn set_compositor(&mut self, compositor: Compositor) {
+        self.compositor = compositor;
+    }
+
+    pub(super) fn transparency(&self) -> Transparency {
+        self.transparency
+    }
+
+    /// Makes the interior or exterior of escape-time fractals transparent in the rendered image.
+    /// Only meant for exports, since a window may show through it.
+    pub(super) fn set_transparency(&mut self, transparency: Transparency) {
+        self.transparency = transparency;
+    }
+
+    /// Varies the iteration of every fractal, on both sides of a comparison.
+    pub(super) fn set_variation(&mut self, variation: Variation) {
+        self.variation = variation;
+    }
+
+    /// Sets the time of the uniform block, in seconds.
+    pub(super) fn set_time(&mut self, seconds: f32) {
+        self.time = seconds;
+    }
+
+    /// Shows one of the quantities from which the fractal is coloured instead of the palette.
+    pub(super) fn set_debug_view(&mut self, debug_view: DebugView) {
+        self.debug_view = debug_view;
+    }
+
+    pub(super) fn seed(&self) -> u32 {
+        self.seed
+    }
+
+    /// Starts the orbits of a rational map from the critical point with the given index in the
+    /// list of [`critical_points::of_rational_map`], or the first if there are fewer.
+    pub(super) fn set_critical_point(&mut self, index: usize) {
+        self.critical_point = index;
+    }
+
+    /// Reseeds the positions of the samples of adaptive anti-aliasing and the noise of the
+    /// modulation.
+    pub(super) fn set_seed(&mut self, seed: u32) {
+        self.seed = seed;
+    }
+
+    /// Places the orbit trap, which applies to both sides of a comparison.
+    pub(super) fn set_orbit_trap(&mut self, trap: OrbitTrap) {
+        self.trap = trap;
+    }
+
+    /// Textures the orbit trap with the given image, which is scaled down if the GPU cannot hold
+    /// it at full size.
+    #[cfg_attr(target_arch = "wasm32", allow(dead_code))]
+    pub(super) fn set_trap_image(&mut self, gpu: &Gpu, image: &image::RgbaImage) {
+        let trap_image = create_trap_image(gpu, image);
+        let create_bind_group = |label, uniform_buffer| {
+            create_bind_group(
+                &gpu.device,
+                &self.bind_group_layout,
+                label,
+                uniform_buffer,
+                &trap_image,
+                &self.trap_sampler,
+            )
+        };
+        self.bind_group = create_bind_group("Bind group", &self.uniform_buffer);
+        self.comparison_bind_group =
+            create_bind_group("Comparison bind group", &self.comparison_uniform_buffer);
+        // Thumbnails rendered with the old image are not told apart from new ones, so they are
+        // discarded.
+        self.thumbnails = create_thumbnail_renderer(
+            gpu,
+            &self.bind_group_layout,
+            &trap_image,
+            &self.trap_sampler,
+        );
+    }
+
+    pub(super) fn set_palette(&mut self, palette: Palette) {
+        self.palette = palette;
+        self.palette_blend = None;
+    }
+
+    pub(super) fn palette(&self) -> Palette {
+        self.palette
+    }
+
+    /// Captures the current fractal, view and colouring.
+    pub(super) fn bookmark(&self) -> Bookmark {
+        Bookmark {
+            fractal_type: self.fractal_type,
+            view_transform: self.transform.matrix(),
+            parameter_values: self.parameter_values.clone(),
+            max_iterations: self.max_iterations,
+            palette: self.palette,
+            palette_offset: self.palette_offset,
+            seed: self.seed,
+            critical_point: self.critical_point,
+            plane: self.plane,
+            centre: Some(self.transform.centre()),
+            scale_exponent: self.transform.scale_exponent(),
+        }
+    }
+
+    pub(super) fn apply_bookmark(&mut self, bookmark: &Bookmark) {
+        self.fractal_type = bookmark.fractal_type;
+        self.transform = ViewTransform::centred_on(
+            bookmark.view_transform,
+            bookmark.scale_exponent,
+            bookmark.centre(),
+        );
+        // Bookmarks are read from files, so their loop bounds are held to what the controls allow.
+        // Clamping lets NaN through, so values which are not finite fall back to the default.
+        let definition = self.definition();
+        self.parameter_values = bookmark
+            .parameter_values
+            .iter()
+            .zip(definition.parameters)
+            .map(|(value, parameter)| {
+                if value.is_finite() {
+                    value.clamp(parameter.min, parameter.max)
+                } else {
+                    parameter.default
+                }
+            })
+            .collect();
+        self.set_max_iterations(bookmark.max_iterations);
+        self.set_palette(bookmark.palette);
+        self.palette_offset = bookmark.palette_offset;
+        self.seed = bookmark.seed;
+        self.critical_point = bookmark.critical_point;
+        self.plane = bookmark.plane;
+        self.parameter_plane_transform = None;
+    }
+
+    /// Shows the state part way from one bookmark to another, blending their palettes, and
+    /// returns it.
+    pub(super) fn morph(&mut self, from: &Bookmark, to: &Bookmark, t: f32) -> Bookmark {
+        let bookmark = from.interpolate(to, t);
+        self.apply_bookmark(&bookmark);
+        self.palette = from.palette;
+        self.palette_blend = Some((to.palette, t));
+        bookmark
+    }
+
+    /// Renders the part of the view right of the divider with the fractal, parameters, iteration
+    /// budget and colouring of the given bookmark, whose location is ignored, or ends the
+    /// comparison.
+    pub(super) fn set_comparison(&mut self, comparison: Option<Bookmark>) {
+        self.comparison = comparison;
+    }
+
+    /// Moves the divider of the comparison to the given fraction of the width of the view.
+    pub(super) fn set_divider(&mut self, divider: f32) {
+        self.divider = divider.clamp(0.0, 1.0);
+    }
+
+    pub(super) fn antialiasing(&self) -> bool {
+        self.antialiasing.is_some()
+    }
+
+    /// Turns adaptive anti-aliasing on or off, building its passes when it is first turned on.
+    pub(super) fn set_antialiasing(&mut self, gpu: &Gpu, enabled: bool) {
+        match (enabled, &self.antialiasing) {
+            (true, None) => {
+                self.antialiasing = Some(AdaptiveAntialiasing::new(gpu, &self.bind_group_layout))
+            }
+            (false, Some(_)) => self.antialiasing = None,
+            _ => {}
+        }
+    }
+
+    /// Turns the bloom effect on or off, building its passes when it is first turned on.
+    pub(super) fn set_bloom(&mut self, gpu: &Gpu, enabled: bool) {
+        match (enabled, &self.bloom) {
+            (true, None) => self.bloom = Some(Bloom::new(gpu)),
+            (false, Some(_)) => self.bloom = None,
+            _ => {}
+        }
+    }
+
+    /// Draws isolines at the given iteration counts over the Mandelbrot set, building their
+    /// passes when they are first needed, or none if there are no levels.
+    pub(super) fn set_isolines(&mut self, gpu: &Gpu, levels: Vec<u32>) {
+        if levels.is_empty() {
+            self.isolines = None;
+            return;
+        }
+        self.isolines
+            .get_or_insert_with(|| {
+                Isolines::new(gpu, self.iteration_data.sampled_bind_group_layout())
+            })
+            .set_levels(levels);
+    }
+
+    /// Draws the curve of the vector mode to the given depth instead of the fractal, building its
+    /// pipeline when it is first needed. Switching to another curve frames it and stops the chaos
+    /// game.
+    pub(super) fn set_vector_mode(&mut self, gpu: &Gpu, mode: VectorMode, depth: u32) {
+        if mode == VectorMode::Off {
+            self.curve = None;
+            return;
+        }
+        if self.vector_mode() != mode {
+            self.transform = ViewTransform::looking_at(Vector2::new(0.0, 0.0), FRAMED_HALF_WIDTH);
+            self.chaos_game = None;
+        }
+        self.curve
+            .get_or_insert_with(|| CurveRenderer::new(gpu, &self.bind_group_layout))
+            .set(gpu, mode, depth);
+    }
+
+    pub(super) fn vector_mode(&self) -> VectorMode {
+        self.curve
+            .as_ref()
+            .map_or(VectorMode::Off, CurveRenderer::mode)
+    }
+
+    pub(super) fn curve_depth(&self) -> u32 {
+        self.curve.as_ref().map_or(0, CurveRenderer::depth)
+    }
+
+    /// Plays the chaos game instead of rendering the fractal, or stops it if `None`, building its
+    /// pipelines when it is first needed. Starting the game frames its polygon and stops drawing
+    /// any curve.
+    pub(super) fn set_chaos_game(&mut self, gpu: &Gpu, game: Option<ChaosGame>) {
+        let Some(game) = game else {
+            self.chaos_game = None;
+            return;
+        };
+        if self.chaos_game.is_none() {
+            self.transform = ViewTransform::looking_at(Vector2::new(0.0, 0.0), FRAMED_HALF_WIDTH);
+            self.curve = None;
+        }
+        self.chaos_game
+            .get_or_insert_with(|| ChaosGameRenderer::new(gpu, &self.bind_group_layout))
+            .set(game);
+    }
+
+    /// Replaces the view with an explorer grid of thumbnails, each with one or two of its settings
+    /// varied, or returns to the view.
+    pub(super) fn set_explorer_grid(&mut self, enabled: bool) {
+        self.explorer_grid = enabled.then(GridRenderer::new);
+    }
+
+    /// The explorer grid which replaces the view, if it is shown.
+    pub(super) fn explorer_grid(&self) -> Option<ExplorerGrid> {
+        self.explorer_grid
+            .as_ref()
+            .map(|_| ExplorerGrid::of(self.fractal_type))
+    }
+
+    /// Whether thumbnails are still waiting to be rendered on later frames.
+    pub(super) fn thumbnails_pending(&self) -> bool {
+        self.thumbnails.is_pending()
+            || (self.shows_parameter_plane() && self.julia_inset.is_pending())
+    }
+
+    /// The GPU memory which the view holds on to between frames.
+    pub(super) fn gpu_memory(&self) -> GpuMemoryUsage {
+        GpuMemoryUsage {
+            iteration_data: self.iteration_data.gpu_memory(),
+            render_targets: self
+                .antialiasing
+                .as_ref()
+                .map_or(0, AdaptiveAntialiasing::gpu_memory)
+                + self.bloom.as_ref().map_or(0, Bloom::gpu_memory)
+                + self
+                    .chaos_game
+                    .as_ref()
+                    .map_or(0, ChaosGameRenderer::gpu_memory)
+                + self
+                    .reduced_resolution
+                    .as_ref()
+                    .map_or(0, ReducedResolution::gpu_memory),
+            thumbnails: self.thumbnails.gpu_memory(),
+            reference_orbit: self
+                .reference_orbits
+                .bound()
+                .map_or(0, |(orbit, _)| std::mem::size_of_val(orbit.points()) as u64),
+        }
+    }
+
+    /// Frees at least the given number of bytes of GPU memory, as far as the view holds on to any
+    /// which it can do without, by evicting cached thumbnails.
+    pub(super) fn relieve_memory_pressure(&self, bytes: u64) {
+        self.thumbnails.evict(bytes);
+    }
+
+    /// Turns low-power mode on or off, in which the fractal is rendered at a reduced resolution and
+    /// scaled up onto the target.
+    pub(super) fn set_low_power(&mut self, gpu: &Gpu, enabled: bool) {
+        match (enabled, &self.reduced_resolution) {
+            (true, None) => {
+                self.reduced_resolution =
+                    Some(ReducedResolution::new(gpu, LOW_POWER_RESOLUTION_DIVISOR))
+            }
+            (false, Some(_)) => self.reduced_resolution = None,
+            _ => {}
+        }
+    }
+
+    /// Keeps the iteration data of deep views in the cache, from which it is restored whenever the
+    /// same view is rendered again, even after a restart.
+    pub(super) fn set_tile_cache(&mut self, cache: Option<TileCache>) {
+        self.tiles = cache.map(TilePersistence::new);
+    }
+
+    /// Writes the iteration data to the tile cache once the view rested on it for a moment, and
+    /// returns whether frames must keep coming until it is written.
+    pub(super) fn persist_tile(&self, gpu: &Gpu, now: Instant) -> bool {
+        let Some(tiles) = &self.tiles else {
+            return false;
+        };
+        let current = self
+            .iteration_data
+            .current_size()
+            .and_then(|size| Some((self.tile_key(size)?, size)));
+        tiles.poll(gpu, &self.iteration_data, current, now)
+    }
+
+    /// Identifies the iteration data for a target of the given size in the tile cache. Only views
+    /// deep enough to need more than `f32` are worth keeping, and compared settings are not kept.
+    fn tile_key(&self, size: wgpu::Extent3d) -> Option<TileKey> {
+        if self.numeric_mode == NumericMode::F32 || self.comparison.is_some() {
+            return None;
+        }
+        let reference_orbit = self
+            .reference_orbits
+            .bound()
+            .map(|(orbit, _)| (orbit.point(), orbit.points().len()));
+        Some(TileKey::of(&format!(
+            "{:?} {:?} {}x{}",
+            self.iteration_inputs(),
+            reference_orbit,
+            size.width,
+            size.height
+        )))
+    }
+
+    /// Frames the current fractal as its profile does, or its dynamic plane about the origin with
+    /// the width of the profile.
+    pub(super) fn reset_transform(&mut self) {
+        self.transform = self.initial_transform();
+    }
+
+    fn initial_transform(&self) -> ViewTransform {
+        let profile = &self.definition().profile;
+        match self.plane {
+            Plane::Dynamic(_) if self.fractal_type.escapes() => {
+                ViewTransform::new(Matrix3::from_scale(profile.half_width))
+            }
+            _ => ViewTransform::new(profile.view_transform()),
         }
     }
 
-    pub(super) fn render(&self, target: &wgpu::TextureView, encoder: &mut wgpu::CommandEncoder) {
-        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
-            label: None,
-            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                view: target,
-                resolve_target: None,
-                ops: wgpu::Operations {
-                    load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
-                    store: wgpu::StoreOp::Store,
-                },
-            })],
-            depth_stencil_attachment: None,
-            timestamp_writes: None,
-            occlusion_query_set: None,
+    /// Previews the Julia set of the given point of the plane in an inset, or hides the inset.
+    /// Only the parameter plane of an escape-time fractal shows the inset.
+    pub(super) fn set_julia_inset(&mut self, point: Option<Vector2<f64>>) {
+        let point = point
+            .filter(|_| self.shows_parameter_plane())
+            .map(|point| point.cast().unwrap());
+        self.julia_inset.set_point(point);
+    }
+
+    /// Whether the view shows the parameter plane of an escape-time fractal rather than its
+    /// dynamic plane, a curve, the chaos game or the explorer grid.
+    fn shows_parameter_plane(&self) -> bool {
+        self.fractal_type.escapes()
+            && self.plane == Plane::Parameter
+            && self.curve.is_none()
+            && self.chaos_game.is_none()
+            && self.explorer_grid.is_none()
+    }
+
+    /// Shows the given plane of escape-time fractals. Entering the dynamic plane frames it whole,
+    /// and leaving it returns to the framing of the parameter plane from which it was entered.
+    pub(super) fn set_plane(&mut self, plane: Plane) {
+        let previous = std::mem::replace(&mut self.plane, plane);
+        if !self.fractal_type.escapes() {
+            return;
+        }
+        match (previous, plane) {
+            (Plane::Parameter, Plane::Dynamic(_)) => {
+                self.parameter_plane_transform = Some(self.transform);
+                self.transform = self.initial_transform();
+            }
+            (Plane::Dynamic(_), Plane::Parameter) => {
+                self.transform = self
+                    .parameter_plane_transform
+                    .take()
+                    .unwrap_or_else(|| self.initial_transform());
+            }
+            _ => {}
+        }
+    }
+
+    /// Calls `f` with the view showing the dynamic plane of the constant `c`, framed whole, and
+    /// then returns to the plane and framing shown before. Returns `None` without calling it
+    /// unless the view shows an escape-time fractal, which alone has a dynamic plane.
+    pub(super) fn with_dynamic_plane<T>(
+        &mut self,
+        c: Vector2<f32>,
+        f: impl FnOnce(&Self) -> T,
+    ) -> Option<T> {
+        if !self.fractal_type.escapes()
+            || self.curve.is_some()
+            || self.chaos_game.is_some()
+            || self.explorer_grid.is_some()
+        {
+            return None;
+        }
+        let plane = std::mem::replace(&mut self.plane, Plane::Dynamic(c));
+        let framing = self.initial_transform();
+        let transform = std::mem::replace(&mut self.transform, framing);
+        let result = f(self);
+        self.plane = plane;
+        self.transform = transform;
+        Some(result)
+    }
+
+    /// Switches to another fractal, whose parameters start at their defaults and whose framing,
+    /// bailout, iteration budget and palette start as its profile has them.
+    pub(super) fn set_fractal_type(&mut self, fractal_type: FractalType) {
+        let definition = fractal_type.definition();
+        self.fractal_type = fractal_type;
+        self.parameter_values = definition.default_values();
+        self.transform = self.initial_transform();
+        self.parameter_plane_transform = None;
+        self.bailout = definition.profile.bailout;
+        self.max_iterations = definition.profile.max_iterations;
+        self.set_palette(definition.profile.palette);
+        self.critical_point = 0;
+    }
+
+    /// Takes the pipelines which finished compiling in the background and returns whether the
+    /// current fractal can be rendered.
+    pub(super) fn receive_pipelines(&mut self) -> bool {
+        self.pipelines.extend(self.compiled_pipelines.ready());
+        self.pipelines.contains_key(&self.fractal_type)
+    }
+
+    /// Blocks until the pipelines of all fractals are compiled, for uses without an event loop.
+    pub(super) fn wait_for_pipelines(&mut self) {
+        self.pipelines.extend(self.compiled_pipelines.all());
+    }
+
+    /// Makes the next frame iterate every pixel again even if nothing changed, so that it can be
+    /// benchmarked.
+    pub(super) fn discard_iteration_data(&self) {
+        self.iteration_data.discard();
+    }
+
+    pub(super) fn definition(&self) -> &'static FractalDefinition {
+        self.fractal_type.definition()
+    }
+
+    /// Sets the parameter with the given index in the current fractal's definition.
+    pub(super) fn set_parameter(&mut self, index: usize, value: f32) {
+        self.parameter_values[index] = value;
+    }
+}
+
+/// The point from which the orbits of the given fractal start: the critical point with the given
+/// index if it is a rational map with one, or else zero.
+fn critical_point(
+    fractal_type: FractalType,
+    params: &[f32; MAX_PARAMETERS],
+    index: usize,
+) -> Vector2<f32> {
+    if fractal_type != FractalType::Rational {
+        return Vector2::new(0.0, 0.0);
+    }
+    let points = critical_points::of_rational_map(params);
+    points
+        .get(index)
+        .or(points.first())
+        .map_or(Vector2::new(0.0, 0.0), |point| {
+            point.position.cast().unwrap()
+        })
+}
+
+/// Creates the thumbnail renderer, whose uniform blocks are bound alongside the orbit trap's image.
+fn create_thumbnail_renderer(
+    gpu: &Gpu,
+    layout: &wgpu::BindGroupLayout,
+    trap_image: &wgpu::TextureView,
+    trap_sampler: &wgpu::Sampler,
+) -> ThumbnailRenderer {
+    ThumbnailRenderer::new(gpu, |uniform_buffer| {
+        create_bind_group(
+            &gpu.device,
+            layout,
+            "Thumbnail bind group",
+            uniform_buffer,
+            trap_image,
+            trap_sampler,
+        )
+    })
+}
+
+/// Creates the bind group of the uniform block `u` in `frag.wgsl` and the orbit trap's image.
+fn create_bind_group(
+    device: &wgpu::Device,
+    layout: &wgpu::BindGroupLayout,
+    label: &str,
+    uniform_buffer: &wgpu::Buffer,
+    trap_image: &wgpu::TextureView,
+    trap_sampler: &wgpu::Sampler,
+) -> wgpu::BindGroup {
+    device.create_bind_group(&wgpu::BindGroupDescriptor {
+        label: Some(label),
+        layout,
+        entries: &[
+            wgpu::BindGroupEntry {
+                binding: 0,
+                resource: uniform_buffer.as_entire_binding(),
+            },
+            wgpu::BindGroupEntry {
+                binding: 1,
+                resource: wgpu::BindingResource::TextureView(trap_image),
+            },
+            wgpu::BindGroupEntry {
+                binding: 2,
+                resource: wgpu::BindingResource::Sampler(trap_sampler),
+            },
+        ],
+    })
+}
+
+/// Uploads the image of the orbit trap, scaling it down to the largest size the GPU supports.
+fn create_trap_image(gpu: &Gpu, image: &image::RgbaImage) -> wgpu::TextureView {
+    let max_dimension = gpu.device.limits().max_texture_dimension_2d;
+    let scaled;
+    let image = if image.width() > max_dimension || image.height() > max_dimension {
+        let scale = max_dimension as f32 / image.width().max(image.height()) as f32;
+        scaled = image::imageops::resize(
+            image,
+            ((image.width() as f32 * scale) as u32).max(1),
+            ((image.height() as f32 * scale) as u32).max(1),
+            image::imageops::FilterType::Triangle,
+        );
+        &scaled
+    } else {
+        image
+    };
+    gpu.device
+        .create_texture_with_data(
+            &gpu.queue,
+            &wgpu::TextureDescriptor {
+                label: Some("Orbit trap image"),
+                size: wgpu::Extent3d {
+                    width: image.width(),
+                    height: image.height(),
+                    depth_or_array_layers: 1,
+                },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                // Like the palette's colours, the image's are passed to the target as they are.
+                format: wgpu::TextureFormat::Rgba8Unorm,
+                usage: wgpu::TextureUsages::TEXTURE_BINDING,
+                view_formats: &[],
+            },
+            wgpu::util::TextureDataOrder::LayerMajor,
+            image.as_raw(),
+        )
+        .create_view(&wgpu::TextureViewDescriptor::default())
+}
+
+/// The contents of the uniform block `u` in `frag.wgsl`, which is shared by all fractals.
+#[derive(Clone, Copy, Debug)]
+pub(super) struct ParameterBlock {
+    /// Maps the square `[-1, 1] x [-1, 1]` onto the complex plane.
+    pub(super) transform: Matrix3<f32>,
+    pub(super) max_iterations: u32,
+    /// The radius beyond which an orbit is considered to have escaped.
+    pub(super) bailout: f32,
+    /// Shifts the colours of escaping points, as a fraction of the palette.
+    pub(super) palette_offset: f32,
+    /// The fractal's own parameters, placed at their offsets by [`FractalDefinition::pack`].
+    pub(super) params: [f32; MAX_PARAMETERS],
+    /// The colours of the palette's gradient, padded to `vec4`s.
+    pub(super) gradient: [[f32; 4]; GRADIENT_STOPS],
+    /// The colours of the roots of the Newton fractal, padded to `vec4`s.
+    pub(super) root_colours: [[f32; 4]; 3],
+    /// Packed by [`PostProcessing::pack`].
+    pub(super) post_processing: [f32; 4],
+    /// Packed by [`OrbitTrap::pack`].
+    pub(super) trap: [f32; 4],
+    /// Packed by [`Variation::bits`].
+    pub(super) variation: u32,
+    pub(super) centre_residual: [f32; 2],
+    /// The mantissa of the offset of the centre from the reference orbit, whose exponent is
+    /// `reference_offset_exponent`.
+    pub(super) reference_offset: [f32; 2],
+    /// The seconds for which the time has played.
+    pub(super) time: f32,
+    /// Packed by [`DebugView::code`].
+    pub(super) debug_view: u32,
+    pub(super) seed: u32,
+    /// One if the render target stores linear light, as told by [`Gpu::stores_linear_light`], so
+    /// that the colouring outputs it rather than colours encoded in sRGB.
+    pub(super) linear_output: u32,
+    /// Packed by [`Tonemapper::code`].
+    pub(super) tonemapper: u32,
+    /// The point from which the orbits of a rational map start.
+    pub(super) critical_point: [f32; 2],
+    /// The constant `c` of the dynamic plane.
+    pub(super) fixed_c: [f32; 2],
+    /// Packed by [`Plane::code`].
+    pub(super) plane: u32,
+    /// Packed by [`Layer::code`](crate::compositor::Layer::code).
+    pub(super) base_layer: u32,
+    pub(super) overlay_layer: u32,
+    /// Packed by [`BlendMode::code`](crate::compositor::BlendMode::code).
+    pub(super) blend_mode: u32,
+    pub(super) overlay_opacity: f32,
+    /// Packed by [`Transparency::code`].
+    pub(super) transparency: u32,
+    /// The power of two by which the scale and rotation of `transform` are multiplied, as in
+    /// [`ViewTransform`].
+    pub(super) scale_exponent: i32,
+    pub(super) reference_offset_exponent: i32,
+}
+
+/// The offsets in bytes of the fields of [`ParameterBlock`] in the uniform block.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(super) struct ParameterBlockLayout {
+    pub(super) transform: usize,
+    pub(super) max_iterations: usize,
+    pub(super) bailout: usize,
+    pub(super) palette_offset: usize,
+    pub(super) params: usize,
+    pub(super) gradient: usize,
+    pub(super) root_colours: usize,
+    pub(super) post_processing: usize,
+    pub(super) trap: usize,
+    pub(super) variation: usize,
+    pub(super) centre_residual: usize,
+    pub(super) reference_offset: usize,
+    pub(super) time: usize,
+    pub(super) debug_view: usize,
+    pub(super) seed: usize,
+    pub(super) linear_output: usize,
+    pub(super) tonemapper: usize,
+    pub(super) critical_point: usize,
+    pub(super) fixed_c: usize,
+    pub(super) plane: usize,
+    pub(super) base_layer: usize,
+    pub(super) overlay_layer: usize,
+    pub(super) blend_mode: usize,
+    pub(super) overlay_opacity: usize,
+    pub(super) transparency: usize,
+    pub(super) scale_exponent: usize,
+    pub(super) reference_offset_exponent: usize,
+    pub(super) size: usize,
+}
+
+impl Default for ParameterBlock {
+    fn default() -> Self {
+        Self::with_palette(Palette::default())
+    }
+}
+
+impl ParameterBlock {
+    /// The default parameters drawn with the colours of the given palette.
+    pub(super) fn with_palette(palette: Palette) -> Self {
+        let (gradient, root_colours) = palette.pack();
+        Self {
+            transform: FractalType::Mandelbrot
+                .definition()
+                .profile
+                .view_transform(),
+            max_iterations: DEFAULT_MAX_ITERATIONS,
+            bailout: DEFAULT_BAILOUT,
+            palette_offset: 0.0,
+            params: [0.0; MAX_PARAMETERS],
+            gradient,
+            root_colours,
+            post_processing: PostProcessing::default().pack(),
+            tonemapper: Tonemapper::default().code(),
+            trap: OrbitTrap::default().pack(),
+            variation: Variation::default().bits(),
+            centre_residual: [0.0; 2],
+            reference_offset: [0.0; 2],
+            time: 0.0,
+            debug_view: DebugView::Off.code(),
+            seed: 0,
+            linear_output: 0,
+            critical_point: [0.0; 2],
+            fixed_c: [0.0; 2],
+            plane: Plane::Parameter.code(),
+            base_layer: Compositor::default().base.code(),
+            overlay_layer: Compositor::default().overlay.code(),
+            blend_mode: Compositor::default().blend_mode.code(),
+            overlay_opacity: Compositor::default().opacity,
+            transparency: Transparency::default().code(),
+            scale_exponent: 0,
+            reference_offset_exponent: 0,
+        }
+    }
+
+    pub(super) fn layout_entry() -> wgpu::BindGroupLayoutEntry {
+        wgpu::BindGroupLayoutEntry {
+            binding: 0,
+            // The vertices of the curves of the vector mode are placed by the view's transform.
+            visibility: wgpu::ShaderStages::VERTEX_FRAGMENT | wgpu::ShaderStages::COMPUTE,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Uniform,
+                has_dynamic_offset: false,
+                min_binding_size: NonZeroU64::new(Self::layout().size as u64),
+            },
+            count: None,
+        }
+    }
+
+    pub(super) fn layout() -> ParameterBlockLayout {
+        Self::default().pack().1
+    }
+
+    /// Packs the block with the std140 layout which WGSL uses for uniforms.
+    pub(super) fn std140_bytes(&self) -> Vec<u8> {
+        self.pack().0
+    }
+
+    fn pack(&self) -> (Vec<u8>, ParameterBlockLayout) {
+        let mut writer = Std140Writer::default();
+        let transform = writer.write_mat3x3(self.transform);
+        let max_iterations = writer.write_u32(self.max_iterations);
+        let bailout = writer.write_f32(self.bailout);
+        let palette_offset = writer.write_f32(self.palette_offset);
+        let params = writer.write_vec4_array(bytemuck::cast_slice(&self.params));
+        let gradient = writer.write_vec4_array(&self.gradient);
+        let root_colours = writer.write_vec4_array(&self.root_colours);
+        let post_processing = writer.write_vec4_array(&[self.post_processing]);
+        let trap = writer.write_vec4_array(&[self.trap]);
+        let variation = writer.write_u32(self.variation);
+        let centre_residual = writer.write_vec2(self.centre_residual);
+        let reference_offset = writer.write_vec2(self.reference_offset);
+        let time = writer.write_f32(self.time);
+        let debug_view = writer.write_u32(self.debug_view);
+        let seed = writer.write_u32(self.seed);
+        let linear_output = writer.write_u32(self.linear_output);
+        let tonemapper = writer.write_u32(self.tonemapper);
+        let critical_point = writer.write_vec2(self.critical_point);
+        let fixed_c = writer.write_vec2(self.fixed_c);
+        let plane = writer.write_u32(self.plane);
+        let base_layer = writer.write_u32(self.base_layer);
+        let overlay_layer = writer.write_u32(self.overlay_layer);
+        let blend_mode = writer.write_u32(self.blend_mode);
+        let overlay_opacity = writer.write_f32(self.overlay_opacity);
+        let transparency = writer.write_u32(self.transparency);
+        let scale_exponent = writer.write_i32(self.scale_exponent);
+        let reference_offset_exponent = writer.write_i32(self.reference_offset_exponent);
+        let bytes = writer.finish();
+        let layout = ParameterBlockLayout {
+            transform,
+            max_iterations,
+            bailout,
+            palette_offset,
+            params,
+            gradient,
+            root_colours,
+            post_processing,
+            trap,
+            variation,
+            centre_residual,
+            reference_offset,
+            time,
+            debug_view,
+            seed,
+            linear_output,
+            tonemapper,
+            critical_point,
+            fixed_c,
+            plane,
+            base_layer,
+            overlay_layer,
+            blend_mode,
+            overlay_opacity,
+            transparency,
+            scale_exponent,
+            reference_offset_exponent,
+            size: bytes.len(),
+        };
+        (bytes, layout)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ParameterBlock, ParameterBlockLayout, View, DEFAULT_BAILOUT};
+    use crate::{
+        bookmark::Bookmark,
+        chaos_game::ChaosGame,
+        colour_palette::Palette,
+        compositor::{BlendMode, Compositor, Layer},
+        controls::FractalType,
+        debug_view::DebugView,
+        export,
+        gpu::Gpu,
+        orbit_trap::OrbitTrap,
+        plane::Plane,
+        post_processing::{PostProcessing, Tonemapper},
+        precision::NumericMode,
+        transparency::Transparency,
+        variation::Variation,
+        vector_mode::VectorMode,
+        wgpu_test::{compare_with_snapshot, DescribableStruct, GpuTestHarness, SnapshotTolerance},
+        wgsl_shader_test,
+    };
+    use bytemuck::{Pod, Zeroable};
+    use cgmath::{InnerSpace, Matrix3, Vector2, Vector3};
+    use googletest::matchers::__internal_unstable_do_not_depend_on_these::ElementsAre;
+    use googletest::prelude::*;
+    use iced_wgpu::wgpu::{self, util::DeviceExt};
+    use num_complex::Complex32;
+    use proptest::{
+        collection, prop_assert,
+        strategy::Strategy,
+        test_runner::{Config, TestCaseError, TestRunner},
+    };
+    use std::{f32::consts::TAU, ops::Range};
+
+    #[async_std::test]
+    async fn transform_is_transferred_correctly() -> Result<()> {
+        let gpu = Gpu::new_without_surface()?;
+        let input = MappableVector(Vector3::new(1.0, 2.0, 1.0).into());
+        let view = create_view(&gpu);
+        let harness = GpuTestHarness::new(&gpu.device, &gpu.queue, &input).with_bind_group(
+            0,
+            &view.bind_group,
+            &view.bind_group_layout,
+        );
+        let test_shader = wgsl_shader_test!(
+            "shader/frag.wgsl",
+            "
+                @group(1) @binding(0) var<storage, read_write> v: vec3<f32>;
+
+                @compute
+                @workgroup_size(1)
+                fn apply_uniform() {
+                    let v_out = u.transform * v;
+                    v = v_out;
+                }
+            "
+        );
+
+        harness.run_compute_shader(test_shader, "apply_uniform");
+
+        verify_that!(
+            harness.fetch_result(&gpu.device).await,
+            eq(MappableVector([1.5, 4.0, 1.0]))
+        )
+    }
+
+    #[async_std::test]
+    async fn parameters_are_transferred_to_their_offsets() -> Result<()> {
+        let gpu = Gpu::new_without_surface()?;
+        let input = MappableVector([0.0, 0.0, 0.0]);
+        let mut view = View::new(&gpu);
+        view.set_fractal_type(FractalType::Newton);
+        view.set_parameter(0, 42.0);
+        view.update_transform(&gpu.queue);
+        let harness = GpuTestHarness::new(&gpu.device, &gpu.queue, &input).with_bind_group(
+            0,
+            &view.bind_group,
+            &view.bind_group_layout,
+        );
+        let test_shader = wgsl_shader_test!(
+            "shader/frag.wgsl",
+            "
+                @group(1) @binding(0) var<storage, read_write> v: vec3<f32>;
+
+                @compute
+                @workgroup_size(1)
+                fn read_parameters() {
+                    v = vec3(parameter(0u), parameter(1u), f32(u.max_iterations));
+                }
+            "
+        );
+
+        harness.run_compute_shader(test_shader, "read_parameters");
+
+        verify_that!(
+            harness.fetch_result(&gpu.device).await,
+            eq(MappableVector([42.0, 0.0, 1000.0]))
+        )
+    }
+
+    #[async_std::test]
+    async fn variation_flags_are_applied_in_order() -> Result<()> {
+        let gpu = Gpu::new_without_surface()?;
+        let input = MappableVector([-1.0, -2.0, 0.0]);
+        let mut view = create_view(&gpu);
+        view.set_variation(Variation {
+            abs_re: true,
+            abs_im: true,
+            conjugate: true,
+            ..Variation::default()
+        });
+        view.update_transform(&gpu.queue);
+        let harness = GpuTestHarness::new(&gpu.device, &gpu.queue, &input).with_bind_group(
+            0,
+            &view.bind_group,
+            &view.bind_group_layout,
+        );
+        let test_shader = wgsl_shader_test!(
+            "shader/frag.wgsl",
+            "
+                @group(1) @binding(0) var<storage, read_write> v: vec3<f32>;
+
+                @compute
+                @workgroup_size(1)
+                fn run_vary() {
+                    v = vec3(vary(v.xy), 0.0);
+                }
+            "
+        );
+
+        harness.run_compute_shader(test_shader, "run_vary");
+
+        verify_that!(
+            harness.fetch_result(&gpu.device).await,
+            eq(MappableVector([1.0, -2.0, 0.0]))
+        )
+    }
+
+    #[async_std::test]
+    async fn spin_rotates_by_time() -> Result<()> {
+        let gpu = Gpu::new_without_surface()?;
+        let input = MappableVector([2.0, 0.0, 0.0]);
+        let mut view = create_view(&gpu);
+        view.set_variation(Variation {
+            spin: true,
+            ..Variation::default()
+        });
+        view.set_time(std::f32::consts::FRAC_PI_2);
+        view.update_transform(&gpu.queue);
+        let harness = GpuTestHarness::new(&gpu.device, &gpu.queue, &input).with_bind_group(
+            0,
+            &view.bind_group,
+            &view.bind_group_layout,
+        );
+        let test_shader = wgsl_shader_test!(
+            "shader/frag.wgsl",
+            "
+                @group(1) @binding(0) var<storage, read_write> v: vec3<f32>;
+
+                @compute
+                @workgroup_size(1)
+                fn run_vary() {
+                    v = vec3(vary(v.xy), 0.0);
+                }
+            "
+        );
+
+        harness.run_compute_shader(test_shader, "run_vary");
+
+        let MappableVector([re, im, _]) = harness.fetch_result(&gpu.device).await;
+        verify_that!((re, im), (near(0.0, 1e-6), near(2.0, 1e-6)))
+    }
+
+    #[test]
+    fn parameter_block_follows_std140_layout() -> Result<()> {
+        verify_that!(
+            ParameterBlock::layout(),
+            eq(ParameterBlockLayout {
+                transform: 0,
+                max_iterations: 48,
+                bailout: 52,
+                palette_offset: 56,
+                params: 64,
+                gradient: 96,
+                root_colours: 224,
+                post_processing: 272,
+                trap: 288,
+                variation: 304,
+                centre_residual: 312,
+                reference_offset: 320,
+                time: 328,
+                debug_view: 332,
+                seed: 336,
+                linear_output: 340,
+                tonemapper: 344,
+                critical_point: 352,
+                fixed_c: 360,
+                plane: 368,
+                base_layer: 372,
+                overlay_layer: 376,
+                blend_mode: 380,
+                overlay_opacity: 384,
+                transparency: 388,
+                scale_exponent: 392,
+                reference_offset_exponent: 396,
+                size: 400,
+            })
+        )
+    }
+
+    #[async_std::test]
+    async fn parameter_block_fields_are_read_from_their_offsets() -> Result<()> {
+        let gpu = Gpu::new_without_surface()?;
+        let block = ParameterBlock {
+            transform: Matrix3::new(1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0, 9.0),
+            max_iterations: 10,
+            bailout: 11.0,
+            palette_offset: 12.0,
+            params: [13.0, 14.0, 15.0, 16.0, 17.0, 18.0, 19.0, 20.0],
+            ..Default::default()
+        };
+        let uniform_buffer = gpu
+            .device
+            .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: None,
+                contents: &block.std140_bytes(),
+                usage: wgpu::BufferUsages::UNIFORM,
+            });
+        let bind_group_layout =
+            gpu.device
+                .create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                    label: None,
+                    entries: &[ParameterBlock::layout_entry()],
+                });
+        let bind_group = gpu.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: None,
+            layout: &bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: uniform_buffer.as_entire_binding(),
+            }],
+        });
+        let input = BlockFields([0.0; 20]);
+        let harness = GpuTestHarness::new(&gpu.device, &gpu.queue, &input).with_bind_group(
+            0,
+            &bind_group,
+            &bind_group_layout,
+        );
+        let test_shader = wgsl_shader_test!(
+            "shader/frag.wgsl",
+            "
+                @group(1) @binding(0) var<storage, read_write> v: array<f32, 20>;
+
+                @compute
+                @workgroup_size(1)
+                fn read_fields() {
+                    for (var column = 0; column < 3; column++) {
+                        for (var row = 0; row < 3; row++) {
+                            v[column * 3 + row] = u.transform[column][row];
+                        }
+                    }
+                    v[9] = f32(u.max_iterations);
+                    v[10] = u.bailout;
+                    v[11] = u.palette_offset;
+                    for (var i = 0u; i < 8u; i++) {
+                        v[12u + i] = parameter(i);
+                    }
+                }
+            "
+        );
+
+        harness.run_compute_shader(test_shader, "read_fields");
+
+        verify_that!(
+            harness.fetch_result(&gpu.device).await,
+            eq(BlockFields(std::array::from_fn(|i| i as f32 + 1.0)))
+        )
+    }
+
+    #[async_std::test]
+    async fn mandelbrot_iteration_is_applied_correctly_inside_set() -> Result<()> {
+        let gpu = Gpu::new_without_surface()?;
+        let input = MappableVector(Vector3::new(-0.5, 0.5, 0.0).into());
+        let view = create_view(&gpu);
+        let harness = GpuTestHarness::new(&gpu.device, &gpu.queue, &input).with_bind_group(
+            0,
+            &view.bind_group,
+            &view.bind_group_layout,
+        );
+        let test_shader = wgsl_shader_test!(
+            "shader/frag.wgsl",
+            "
+                @group(1) @binding(0) var<storage, read_write> v: vec3<f32>;
+
+                @compute
+                @workgroup_size(1)
+                fn run_mandelbrot_iteration() {
+                    let i = mandelbrot_iterations(vec2(v.x, v.y));
+                    v = vec3(i, 0.0, 0.0);
+                }
+            "
+        );
+
+        harness.run_compute_shader(test_shader, "run_mandelbrot_iteration");
+
+        verify_that!(
+            harness.fetch_result(&gpu.device).await,
+            eq(MappableVector([0.0, 0.0, 0.0]))
+        )
+    }
+
+    #[async_std::test]
+    async fn dynamic_plane_of_zero_fills_unit_disc() -> Result<()> {
+        let gpu = Gpu::new_without_surface()?;
+        let input = MappableVector(Vector3::new(0.9, 1.1, 0.0).into());
+        let mut view = create_view(&gpu);
+        view.set_plane(Plane::Dynamic(Vector2::new(0.0, 0.0)));
+        view.update_transform(&gpu.queue);
+        let harness = GpuTestHarness::new(&gpu.device, &gpu.queue, &input).with_bind_group(
+            0,
+            &view.bind_group,
+            &view.bind_group_layout,
+        );
+        let test_shader = wgsl_shader_test!(
+            "shader/frag.wgsl",
+            "
+                @group(1) @binding(0) var<storage, read_write> v: vec3<f32>;
+
+                @compute
+                @workgroup_size(1)
+                fn run_dynamic_orbits() {
+                    let inside = mandelbrot_orbit(vec2(v.x, 0.0)).x;
+                    let outside = mandelbrot_orbit(vec2(0.0, v.y)).x;
+                    v = vec3(inside, outside, 0.0);
+                }
+            "
+        );
+
+        harness.run_compute_shader(test_shader, "run_dynamic_orbits");
+
+        let MappableVector([inside, outside, _]) = harness.fetch_result(&gpu.device).await;
+        verify_that!((inside, outside), (eq(-1.0), gt(0.0)))
+    }
+
+    #[test]
+    fn leaving_dynamic_plane_restores_framing_of_parameter_plane() -> Result<()> {
+        let gpu = Gpu::new_without_surface()?;
+        let mut view = create_view(&gpu);
+        view.zoom(4.0, Vector2::new(0.25, 0.25));
+        let framing = view.transform();
+
+        view.set_plane(Plane::Dynamic(Vector2::new(-0.8, 0.156)));
+        let dynamic_centre = view.centre();
+        view.set_plane(Plane::Parameter);
+
+        verify_that!(
+            (dynamic_centre, view.transform()),
+            (eq(Vector2::new(0.0, 0.0)), eq(framing))
+        )
+    }
+
+    #[async_std::test]
+    async fn mandelbrot_iteration_is_applied_correctly_outside_set() -> Result<()> {
+        let gpu = Gpu::new_without_surface()?;
+        let input = MappableVector(Vector3::new(0.5, 0.6, 0.0).into());
+        let view = create_view(&gpu);
+        let harness = GpuTestHarness::new(&gpu.device, &gpu.queue, &input).with_bind_group(
+            0,
+            &view.bind_group,
+            &view.bind_group_layout,
+        );
+        let test_shader = wgsl_shader_test!(
+            "shader/frag.wgsl",
+            "
+                @group(1) @binding(0) var<storage, read_write> v: vec3<f32>;
+
+                @compute
+                @workgroup_size(1)
+                fn run_mandelbrot_iteration() {
+                    let i = mandelbrot_iterations(vec2(v.x, v.y));
+                    v = vec3(i, 0.0, 0.0);
+                }
+            "
+        );
+
+        harness.run_compute_shader(test_shader, "run_mandelbrot_iteration");
+
+        verify_that!(
+            harness.fetch_result(&gpu.device).await,
+            // TODO: Using the elements_are! macro directly causes the Rust
+            // compiler to be utterly confused with type inference, inferring
+            // the container type to be an infinitely recursive type coming from
+            // the palette crate for some reason. So we have to use the actual
+            // struct and add an explicit type argument. Figure out why this is
+            // happening and solve the root cause, if possible.
+            matches_pattern!(MappableVector(ElementsAre::<[f32; 3], _>::new(vec![
+                Box::new(gt(0.0)),
+                Box::new(eq(0.0)),
+                Box::new(eq(0.0)),
+            ])))
+        )
+    }
+
+    #[async_std::test]
+    async fn eval_poly_evaluates_correctly_at_root() -> Result<()> {
+        let gpu = Gpu::new_without_surface()?;
+        let input = MappableVector(Vector3::new(-0.5, 3.0f32.sqrt() / 2.0, 0.0).into());
+        let view = create_view(&gpu);
+        let harness = GpuTestHarness::new(&gpu.device, &gpu.queue, &input).with_bind_group(
+            0,
+            &view.bind_group,
+            &view.bind_group_layout,
+        );
+        let test_shader = wgsl_shader_test!(
+            "shader/frag.wgsl",
+            "
+                @group(1) @binding(0) var<storage, read_write> v: vec3<f32>;
+
+                @compute
+                @workgroup_size(1)
+                fn run_eval_poly() {
+                    let result = eval_poly(vec2(v.x, v.y), COEFFS);
+                    v = vec3(result, 0.0);
+                }
+            "
+        );
+
+        harness.run_compute_shader(test_shader, "run_eval_poly");
+
+        verify_that!(
+            harness.fetch_result(&gpu.device).await,
+            matches_pattern!(MappableVector(ElementsAre::<[f32; 3], _>::new(vec![
+                Box::new(approx_eq(0.0)),
+                Box::new(approx_eq(0.0)),
+                Box::new(eq(0.0)),
+            ])))
+        )
+    }
+
+    #[async_std::test]
+    async fn eval_poly_on_derivative_evaluates_correctly() -> Result<()> {
+        let gpu = Gpu::new_without_surface()?;
+        let input = MappableVector(Vector3::new(2.0, 0.0, 0.0).into());
+        let view = create_view(&gpu);
+        let harness = GpuTestHarness::new(&gpu.device, &gpu.queue, &input).with_bind_group(
+            0,
+            &view.bind_group,
+            &view.bind_group_layout,
+        );
+        let test_shader = wgsl_shader_test!(
+            "shader/frag.wgsl",
+            "
+                @group(1) @binding(0) var<storage, read_write> v: vec3<f32>;
+
+                @compute
+                @workgroup_size(1)
+                fn run_eval_poly_df() {
+                    let result = eval_poly(vec2(v.x, v.y), DERIVATIVE_COEFFS);
+                    v = vec3(result, 0.0);
+                }
+            "
+        );
+
+        harness.run_compute_shader(test_shader, "run_eval_poly_df");
+
+        verify_that!(
+            harness.fetch_result(&gpu.device).await,
+            matches_pattern!(MappableVector(ElementsAre::<[f32; 3], _>::new(vec![
+                Box::new(approx_eq(12.0)),
+                Box::new(approx_eq(0.0)),
+                Box::new(eq(0.0)),
+            ])))
+        )
+    }
+
+    #[async_std::test]
+    async fn inv_calculates_correct_inverse() -> Result<()> {
+        let gpu = Gpu::new_without_surface()?;
+        let input = MappableVector(Vector3::new(-2.0, 1.5, 0.0).into());
+        let view = create_view(&gpu);
+        let harness = GpuTestHarness::new(&gpu.device, &gpu.queue, &input).with_bind_group(
+            0,
+            &view.bind_group,
+            &view.bind_group_layout,
+        );
+        let test_shader = wgsl_shader_test!(
+            "shader/frag.wgsl",
+            "
+                @group(1) @binding(0) var<storage, read_write> v: vec3<f32>;
+
+                @compute
+                @workgroup_size(1)
+                fn run_inv() {
+                    let result = inv(vec2(v.x, v.y));
+                    v = vec3(result, 0.0);
+                }
+            "
+        );
+
+        harness.run_compute_shader(test_shader, "run_inv");
+
+        let inv = harness.fetch_result(&gpu.device).await;
+        let inv_times_input = (
+            inv.0[0] * input.0[0] - inv.0[1] * input.0[1],
+            inv.0[0] * input.0[1] + inv.0[1] * input.0[0],
+        );
+        verify_that!(inv_times_input, (approx_eq(1.0), approx_eq(0.0)))
+    }
+
+    #[async_std::test]
+    async fn newton_converges_to_root() -> Result<()> {
+        let gpu = Gpu::new_without_surface()?;
+        let input = MappableVector(Vector3::new(-2.0, 5.0, 0.0).into());
+        let view = create_newton_view(&gpu);
+        let harness = GpuTestHarness::new(&gpu.device, &gpu.queue, &input).with_bind_group(
+            0,
+            &view.bind_group,
+            &view.bind_group_layout,
+        );
+        let test_shader = wgsl_shader_test!(
+            "shader/frag.wgsl",
+            "
+                @group(1) @binding(0) var<storage, read_write> v: vec3<f32>;
+
+                @compute
+                @workgroup_size(1)
+                fn run_newton() {
+                    let result = newton_iterate(v);
+                    v = vec3(result, 0.0);
+                }
+            "
+        );
+
+        harness.run_compute_shader(test_shader, "run_newton");
+
+        verify_that!(
+            harness.fetch_result(&gpu.device).await,
+            matches_pattern!(MappableVector(ElementsAre::<[f32; 3], _>::new(vec![
+                Box::new(approx_eq(-0.5)),
+                Box::new(approx_eq(3.0f32.sqrt() / 2.0)),
+                Box::new(eq(0.0)),
+            ])))
+        )
+    }
+
+    #[async_std::test]
+    async fn mandelbrot_iteration_is_applied_to_a_batch_of_points() -> Result<()> {
+        let gpu = Gpu::new_without_surface()?;
+        let input = Points([[-0.5, 0.5], [0.0, 0.0], [0.5, 0.6], [1.0, 1.0]]);
+        let view = create_view(&gpu);
+        let harness = GpuTestHarness::new(&gpu.device, &gpu.queue, &input)
+            .with_bind_group(0, &view.bind_group, &view.bind_group_layout)
+            .with_buffer("iterations", &[-1.0f32; 4])
+            .with_workgroups(4, 1, 1);
+        let test_shader = wgsl_shader_test!(
+            "shader/frag.wgsl",
+            "
+                @group(1) @binding(0) var<storage, read_write> points: array<vec2<f32>, 4>;
+                @group(1) @binding(1) var<storage, read_write> iterations: array<f32>;
+
+                @compute
+                @workgroup_size(1)
+                fn run_mandelbrot_iterations(@builtin(global_invocation_id) id: vec3<u32>) {
+                    iterations[id.x] = mandelbrot_iterations(points[id.x]);
+                }
+            "
+        );
+
+        harness.run_compute_shader(test_shader, "run_mandelbrot_iterations");
+
+        let iterations = harness.fetch_buffer::<f32>("iterations").await;
+        verify_that!(
+            (iterations[0], iterations[1], iterations[2], iterations[3]),
+            (eq(0.0), eq(0.0), gt(0.0), gt(0.0))
+        )
+    }
+
+    #[async_std::test]
+    async fn newton_colours_points_by_basin_of_attraction() -> Result<()> {
+        let gpu = Gpu::new_without_surface()?;
+        let input = Points([[1.1, 0.1], [-0.6, 0.9], [-0.4, -0.8], [0.9, -0.05]]);
+        let mut view = create_newton_view(&gpu);
+        // The primary colours of the greyscale palette's roots tell the basins apart at a glance.
+        view.set_palette(Palette::Greyscale);
+        view.update_transform(&gpu.queue);
+        let harness = GpuTestHarness::new(&gpu.device, &gpu.queue, &input)
+            .with_bind_group(0, &view.bind_group, &view.bind_group_layout)
+            .with_output_texture("colours", 4, 1)
+            .with_workgroups(4, 1, 1);
+        let test_shader = wgsl_shader_test!(
+            "shader/frag.wgsl",
+            "
+                @group(1) @binding(0) var<storage, read_write> points: array<vec2<f32>, 4>;
+                @group(1) @binding(1) var colours: texture_storage_2d<rgba8unorm, write>;
+
+                @compute
+                @workgroup_size(1)
+                fn run_newton_colouring(@builtin(global_invocation_id) id: vec3<u32>) {
+                    let z = newton_iterate(vec3(points[id.x], 1.0));
+                    textureStore(colours, vec2(i32(id.x), 0), vec4(point_colour(z), 1.0));
+                }
+            "
+        );
+
+        harness.run_compute_shader(test_shader, "run_newton_colouring");
+
+        let colours: [u8; 16] = harness.fetch_texture("colours").await.try_into().unwrap();
+        verify_that!(
+            colours,
+            eq([
+                255, 0, 0, 255, //
+                0, 255, 0, 255, //
+                0, 0, 255, 255, //
+                255, 0, 0, 255, //
+            ])
+        )
+    }
+
+    #[async_std::test]
+    async fn newton_colours_roots_with_palette() -> Result<()> {
+        let gpu = Gpu::new_without_surface()?;
+        let input = Points([[1.1, 0.1], [-0.6, 0.9], [-0.4, -0.8], [0.9, -0.05]]);
+        let mut view = create_newton_view(&gpu);
+        view.set_palette(Palette::Cividis);
+        view.update_transform(&gpu.queue);
+        let harness = GpuTestHarness::new(&gpu.device, &gpu.queue, &input)
+            .with_bind_group(0, &view.bind_group, &view.bind_group_layout)
+            .with_output_texture("colours", 4, 1)
+            .with_workgroups(4, 1, 1);
+        let test_shader = wgsl_shader_test!(
+            "shader/frag.wgsl",
+            "
+                @group(1) @binding(0) var<storage, read_write> points: array<vec2<f32>, 4>;
+                @group(1) @binding(1) var colours: texture_storage_2d<rgba8unorm, write>;
+
+                @compute
+                @workgroup_size(1)
+                fn run_newton_colouring(@builtin(global_invocation_id) id: vec3<u32>) {
+                    let z = newton_iterate(vec3(points[id.x], 1.0));
+                    textureStore(colours, vec2(i32(id.x), 0), vec4(point_colour(z), 1.0));
+                }
+            "
+        );
+
+        harness.run_compute_shader(test_shader, "run_newton_colouring");
+
+        let colours = harness.fetch_texture("colours").await;
+        let roots = Palette::Cividis
+            .roots()
+            .map(|root| root.map(|channel| (channel * 255.0).round() as u8));
+        verify_that!(
+            (&colours[0..3], &colours[4..7], &colours[8..11]),
+            (eq(&roots[0][..]), eq(&roots[1][..]), eq(&roots[2][..]))
+        )
+    }
+
+    #[async_std::test]
+    async fn srgb_decoding_and_encoding_round_trip() -> Result<()> {
+        let gpu = Gpu::new_without_surface()?;
+        // The centres of the texels of a texture four texels wide.
+        let input = Points([[0.125, 0.5], [0.375, 0.5], [0.625, 0.5], [0.875, 0.5]]);
+        let pixels = [
+            0, 0, 0, 255, //
+            10, 64, 100, 255, //
+            128, 160, 200, 255, //
+            255, 255, 255, 255, //
+        ];
+        let view = create_view(&gpu);
+        let harness = GpuTestHarness::new(&gpu.device, &gpu.queue, &input)
+            .with_bind_group(0, &view.bind_group, &view.bind_group_layout)
+            .with_texture("pixels", 4, 1, &pixels)
+            .with_sampler("pixel_sampler")
+            .with_output_texture("colours", 4, 1)
+            .with_workgroups(4, 1, 1);
+        let test_shader = wgsl_shader_test!(
+            "shader/frag.wgsl",
+            "
+                @group(1) @binding(0) var<storage, read_write> points: array<vec2<f32>, 4>;
+                @group(1) @binding(1) var pixels: texture_2d<f32>;
+                @group(1) @binding(2) var pixel_sampler: sampler;
+                @group(1) @binding(3) var colours: texture_storage_2d<rgba8unorm, write>;
+
+                @compute
+                @workgroup_size(1)
+                fn run_round_trip(@builtin(global_invocation_id) id: vec3<u32>) {
+                    let colour = textureSampleLevel(pixels, pixel_sampler, points[id.x], 0.0);
+                    let round_trip = linear_to_srgb(srgb_to_linear(colour.rgb));
+                    textureStore(colours, vec2(i32(id.x), 0), vec4(round_trip, 1.0));
+                }
+            "
+        );
+
+        harness.run_compute_shader(test_shader, "run_round_trip");
+
+        let colours = harness.fetch_texture("colours").await;
+        let largest_error = colours
+            .iter()
+            .zip(pixels)
+            .map(|(actual, expected)| actual.abs_diff(expected))
+            .max();
+        verify_that!(largest_error, le(Some(1)))
+    }
+
+    #[test]
+    fn mul_agrees_with_reference_on_random_inputs() -> Result<()> {
+        let gpu = Gpu::new_without_surface()?;
+        let view = create_view(&gpu);
+
+        let result = TestRunner::new(Config::with_cases(PROPERTY_TEST_CASES)).run(
+            &(complex_batch(-3.0..3.0), complex_batch(-3.0..3.0)),
+            |(a, b)| {
+                let shader = complex_batch_shader!("mul(a[i], b[i])");
+                let actual = run_complex_batch(&gpu, &view, shader, &a, &b);
+                for i in 0..BATCH_SIZE {
+                    check_close(actual[i], a[i] * b[i], 1e-5, || {
+                        format!("{} * {}", a[i], b[i])
+                    })?;
+                }
+                Ok(())
+            },
+        );
+
+        verify_that!(result, ok(anything()))
+    }
+
+    #[test]
+    fn inv_agrees_with_reference_on_random_inputs() -> Result<()> {
+        let gpu = Gpu::new_without_surface()?;
+        let view = create_view(&gpu);
+
+        let result = TestRunner::new(Config::with_cases(PROPERTY_TEST_CASES)).run(
+            &complex_batch(-3.0..3.0),
+            |z| {
+                let shader = complex_batch_shader!("inv(a[i])");
+                let actual = run_complex_batch(&gpu, &view, shader, &z, &z);
+                for i in 0..BATCH_SIZE {
+                    check_close(actual[i], z[i].inv(), 1e-5, || format!("1 / {}", z[i]))?;
+                }
+                Ok(())
+            },
+        );
+
+        verify_that!(result, ok(anything()))
+    }
+
+    #[test]
+    fn gradient_noise_vanishes_on_integer_lattice() -> Result<()> {
+        let gpu = Gpu::new_without_surface()?;
+        let view = create_view(&gpu);
+
+        let result = TestRunner::new(Config::with_cases(PROPERTY_TEST_CASES)).run(
+            &complex_batch(-1.0..3.0),
+            |z| {
+                let shader = complex_batch_shader!("vec2(gradient_noise(round(a[i]), 7u), 0.0)");
+                let actual = run_complex_batch(&gpu, &view, shader, &z, &z);
+                for i in 0..BATCH_SIZE {
+                    check_close_absolute(actual[i], Complex32::default(), 1e-6, || {
+                        format!("noise at {}", z[i])
+                    })?;
+                }
+                Ok(())
+            },
+        );
+
+        verify_that!(result, ok(anything()))
+    }
+
+    #[test]
+    fn fractal_noise_stays_within_range_on_random_inputs() -> Result<()> {
+        let gpu = Gpu::new_without_surface()?;
+        let mut view = create_view(&gpu);
+        view.set_fractal_type(FractalType::Terrain);
+        view.update_transform(&gpu.queue);
+
+        let result = TestRunner::new(Config::with_cases(PROPERTY_TEST_CASES)).run(
+            &complex_batch(-2.0..3.0),
+            |z| {
+                let shader = complex_batch_shader!("vec2(fbm(a[i]), ridged_multifractal(a[i]))");
+                let actual = run_complex_batch(&gpu, &view, shader, &z, &z);
+                for i in 0..BATCH_SIZE {
+                    let (fbm, ridged) = (actual[i].re, actual[i].im);
+                    prop_assert!(
+                        (-1.0..=1.0).contains(&fbm) && (0.0..=1.0).contains(&ridged),
+                        "noise at {}: fbm {fbm}, ridged {ridged}",
+                        z[i]
+                    );
+                }
+                Ok(())
+            },
+        );
+
+        verify_that!(result, ok(anything()))
+    }
+
+    #[test]
+    fn eval_poly_agrees_with_reference_on_random_inputs() -> Result<()> {
+        let gpu = Gpu::new_without_surface()?;
+        let view = create_view(&gpu);
+
+        let result = TestRunner::new(Config::with_cases(PROPERTY_TEST_CASES)).run(
+            &(
+                complex_batch(-2.0..1.0),
+                collection::vec(collection::vec(-10.0f32..10.0, 4), BATCH_SIZE),
+            ),
+            |(z, coeffs)| {
+                let packed_coeffs: Vec<[f32; 4]> =
+                    coeffs.iter().map(|c| [c[0], c[1], c[2], c[3]]).collect();
+                let shader = complex_batch_shader!("eval_poly(a[i], b[i])", "vec4<f32>");
+                let actual = run_complex_batch(&gpu, &view, shader, &z, &packed_coeffs);
+                for i in 0..BATCH_SIZE {
+                    let expected = coeffs[i]
+                        .iter()
+                        .fold(Complex32::new(0.0, 0.0), |acc, c| acc * z[i] + c);
+                    // Horner's scheme accumulates rounding errors proportional to the largest
+                    // intermediate value rather than to the result.
+                    let scale = coeffs[i].iter().map(|c| c.abs()).sum::<f32>()
+                        * z[i].norm().max(1.0).powi(3);
+                    check_close_absolute(actual[i], expected, 1e-5 * scale, || {
+                        format!("polynomial {:?} at {}", coeffs[i], z[i])
+                    })?;
+                }
+                Ok(())
+            },
+        );
+
+        verify_that!(result, ok(anything()))
+    }
+
+    const SNAPSHOT_SIZE: u32 = 128;
+
+    #[test]
+    fn every_fractal_matches_snapshot() -> Result<()> {
+        let gpu = Gpu::new_without_surface()?;
+        let mut view = create_view(&gpu);
+
+        let mut errors = vec![];
+        for fractal_type in FractalType::ALL {
+            view.set_fractal_type(fractal_type);
+            let pixels = export::render_to_rgba(&gpu, &view, SNAPSHOT_SIZE, SNAPSHOT_SIZE);
+            // The snapshots are named after the entry points, which are valid file names.
+            if let Err(error) = compare_with_snapshot(
+                fractal_type.definition().entry_point,
+                SNAPSHOT_SIZE,
+                SNAPSHOT_SIZE,
+                &pixels,
+                SnapshotTolerance::default(),
+            ) {
+                errors.push(format!("{fractal_type}: {error}"));
+            }
+        }
+
+        verify_that!(errors, empty())
+    }
+
+    #[test]
+    fn root_finding_basins_darken_away_from_roots() -> Result<()> {
+        let gpu = Gpu::new_without_surface()?;
+        let mut view = create_view(&gpu);
+        for fractal_type in [FractalType::DampedNewton, FractalType::Secant] {
+            view.set_fractal_type(fractal_type);
+
+            let pixels = export::render_to_rgba(&gpu, &view, SNAPSHOT_SIZE, SNAPSHOT_SIZE);
+
+            // The brightness of the pixel at the given point of the real axis, which the profile
+            // frames with a half width of 1.5.
+            let brightness = |x: f32| {
+                let column = ((x / 1.5 + 1.0) / 2.0 * SNAPSHOT_SIZE as f32) as u32;
+                let offset = ((SNAPSHOT_SIZE / 2 * SNAPSHOT_SIZE + column) * 4) as usize;
+                pixels[offset..offset + 3]
+                    .iter()
+                    .map(|channel| u32::from(*channel))
+                    .sum::<u32>()
+            };
+            // Both points lie in the basin of the root at one, from the farther of which the
+            // methods take more steps.
+            verify_that!(brightness(1.0), gt(brightness(1.45)))?;
+            verify_that!(brightness(1.45), gt(0))?;
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn multibrot_with_exponent_two_matches_mandelbrot() -> Result<()> {
+        let gpu = Gpu::new_without_surface()?;
+        let mut view = create_view(&gpu);
+        let mandelbrot = export::render_to_rgba(&gpu, &view, SNAPSHOT_SIZE, SNAPSHOT_SIZE);
+        let bookmark = view.bookmark();
+
+        view.set_fractal_type(FractalType::Multibrot);
+        // Compared with the framing and colouring of the Mandelbrot set, not the Multibrot set's
+        // profile.
+        view.apply_bookmark(&Bookmark {
+            fractal_type: FractalType::Multibrot,
+            parameter_values: vec![2.0],
+            ..bookmark
+        });
+        view.set_bailout(DEFAULT_BAILOUT);
+        let multibrot = export::render_to_rgba(&gpu, &view, SNAPSHOT_SIZE, SNAPSHOT_SIZE);
+
+        // Powers in polar form round differently from squaring, which moves a few pixels near
+        // the boundary across a band.
+        let differing = mandelbrot
+            .chunks_exact(4)
+            .zip(multibrot.chunks_exact(4))
+            .filter(|(a, b)| a.iter().zip(*b).any(|(a, b)| a.abs_diff(*b) > 2))
+            .count();
+        verify_that!(
+            differing,
+            lt((SNAPSHOT_SIZE * SNAPSHOT_SIZE / 100) as usize)
+        )
+    }
+
+    #[test]
+    fn bookmark_parameters_which_are_not_finite_take_their_default() -> Result<()> {
+        let gpu = Gpu::new_without_surface()?;
+        let mut view = create_view(&gpu);
+        let bookmark = view.bookmark();
+
+        view.apply_bookmark(&Bookmark {
+            fractal_type: FractalType::Multibrot,
+            parameter_values: vec![f32::NAN],
+            ..bookmark.clone()
+        });
+        let from_nan = view.bookmark().parameter_values;
+        view.apply_bookmark(&Bookmark {
+            fractal_type: FractalType::Multibrot,
+            parameter_values: vec![f32::INFINITY],
+            ..bookmark
+        });
+        let from_infinity = view.bookmark().parameter_values;
+
+        verify_that!(from_nan, eq(vec![3.0]))?;
+        verify_that!(from_infinity, eq(vec![3.0]))
+    }
+
+    #[test]
+    fn rational_map_of_square_over_one_matches_mandelbrot() -> Result<()> {
+        let gpu = Gpu::new_without_surface()?;
+        let mut view = create_view(&gpu);
+        let mandelbrot = export::render_to_rgba(&gpu, &view, SNAPSHOT_SIZE, SNAPSHOT_SIZE);
+        let bookmark = view.bookmark();
+
+        view.set_fractal_type(FractalType::Rational);
+        view.apply_bookmark(&Bookmark {
+            fractal_type: FractalType::Rational,
+            parameter_values: vec![0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 0.0, 1.0],
+            ..bookmark
+        });
+        view.set_bailout(DEFAULT_BAILOUT);
+        let rational = export::render_to_rgba(&gpu, &view, SNAPSHOT_SIZE, SNAPSHOT_SIZE);
+
+        // Dividing by one rounds like squaring, but the iteration is written differently, which
+        // may move a few pixels near the boundary across a band.
+        let differing = mandelbrot
+            .chunks_exact(4)
+            .zip(rational.chunks_exact(4))
+            .filter(|(a, b)| a.iter().zip(*b).any(|(a, b)| a.abs_diff(*b) > 2))
+            .count();
+        verify_that!(
+            differing,
+            lt((SNAPSHOT_SIZE * SNAPSHOT_SIZE / 100) as usize)
+        )
+    }
+
+    #[test]
+    fn rational_map_starts_orbits_from_chosen_critical_point() -> Result<()> {
+        let gpu = Gpu::new_without_surface()?;
+        let mut view = create_view(&gpu);
+        view.set_fractal_type(FractalType::Rational);
+        let render = |view: &View| export::render_to_rgba(&gpu, view, SNAPSHOT_SIZE, SNAPSHOT_SIZE);
+        let from_zero = render(&view);
+
+        // The default z³ / (z + 0.3) has critical points at zero and -0.45.
+        view.set_critical_point(1);
+        let from_second = render(&view);
+        // There is no third, so the first is taken instead.
+        view.set_critical_point(2);
+        let from_missing = render(&view);
+
+        verify_that!(
+            (from_second == from_zero, from_missing == from_zero),
+            (eq(false), eq(true))
+        )
+    }
+
+    #[test]
+    fn selecting_fractal_applies_its_profile() -> Result<()> {
+        let gpu = Gpu::new_without_surface()?;
+        let mut view = create_view(&gpu);
+        view.zoom(4.0, Vector2::new(0.25, 0.25));
+        view.set_max_iterations(5000);
+
+        view.set_fractal_type(FractalType::Multibrot);
+
+        let bookmark = view.bookmark();
+        verify_that!(
+            (bookmark.max_iterations, bookmark.palette, view.bailout),
+            (eq(500), eq(Palette::Cividis), eq(4.0))
+        )?;
+        verify_that!(bookmark.view_transform, eq(Matrix3::from_scale(1.5)))
+    }
+
+    #[test]
+    fn switching_back_to_mandelbrot_renders_mandelbrot() -> Result<()> {
+        let gpu = Gpu::new_without_surface()?;
+        let mut view = create_view(&gpu);
+        view.set_fractal_type(FractalType::Newton);
+        view.set_fractal_type(FractalType::Mandelbrot);
+
+        let pixels = export::render_to_rgba(&gpu, &view, SNAPSHOT_SIZE, SNAPSHOT_SIZE);
+
+        verify_that!(
+            compare_with_snapshot(
+                "mandelbrot",
+                SNAPSHOT_SIZE,
+                SNAPSHOT_SIZE,
+                &pixels,
+                SnapshotTolerance::default()
+            ),
+            ok(anything())
+        )
+    }
+
+    #[test]
+    fn antialiasing_leaves_flat_regions_unchanged() -> Result<()> {
+        let gpu = Gpu::new_without_surface()?;
+        let mut view = create_view(&gpu);
+        view.look_at(Vector2::new(-0.1, 0.0), 0.01);
+        let aliased = export::render_to_rgba(&gpu, &view, SNAPSHOT_SIZE, SNAPSHOT_SIZE);
+
+        view.set_antialiasing(&gpu, true);
+        let antialiased = export::render_to_rgba(&gpu, &view, SNAPSHOT_SIZE, SNAPSHOT_SIZE);
+
+        verify_that!(antialiased == aliased, eq(true))
+    }
+
+    #[test]
+    fn antialiasing_only_changes_pixels_at_edges() -> Result<()> {
+        let gpu = Gpu::new_without_surface()?;
+        let mut view = create_newton_view(&gpu);
+        // Off the real axis, so that a mirrored image would not pass.
+        view.look_at(Vector2::new(0.3, 0.4), 1.0);
+        let aliased = export::render_to_rgba(&gpu, &view, SNAPSHOT_SIZE, SNAPSHOT_SIZE);
+
+        view.set_antialiasing(&gpu, true);
+        let antialiased = export::render_to_rgba(&gpu, &view, SNAPSHOT_SIZE, SNAPSHOT_SIZE);
+
+        let size = SNAPSHOT_SIZE as i32;
+        let pixel = |image: &[u8], x: i32, y: i32| {
+            let offset = 4 * (y.clamp(0, size - 1) * size + x.clamp(0, size - 1)) as usize;
+            image[offset..offset + 4].to_vec()
+        };
+        let mut changed = 0;
+        for y in 0..size {
+            for x in 0..size {
+                if pixel(&aliased, x, y) == pixel(&antialiased, x, y) {
+                    continue;
+                }
+                changed += 1;
+                let is_edge = (-1..=1).any(|dy| {
+                    (-1..=1).any(|dx| pixel(&aliased, x + dx, y + dy) != pixel(&aliased, x, y))
+                });
+                verify_that!((x, y, is_edge), (anything(), anything(), eq(true)))?;
+            }
+        }
+        verify_that!(changed, gt(0))
+    }
+
+    #[test]
+    fn antialiasing_is_reproduced_by_its_seed() -> Result<()> {
+        let gpu = Gpu::new_without_surface()?;
+        let mut view = create_newton_view(&gpu);
+        view.set_antialiasing(&gpu, true);
+        let render = |view: &View| export::render_to_rgba(&gpu, view, SNAPSHOT_SIZE, SNAPSHOT_SIZE);
+
+        view.set_seed(1);
+        let first = render(&view);
+        let again = render(&view);
+        view.set_seed(2);
+        let reseeded = render(&view);
+
+        verify_that!(again == first, eq(true))?;
+        verify_that!(reseeded == first, eq(false))
+    }
+
+    #[test]
+    fn low_power_leaves_flat_regions_unchanged() -> Result<()> {
+        let gpu = Gpu::new_without_surface()?;
+        let mut view = create_view(&gpu);
+        view.look_at(Vector2::new(-0.1, 0.0), 0.01);
+        let full_resolution = export::render_to_rgba(&gpu, &view, SNAPSHOT_SIZE, SNAPSHOT_SIZE);
+
+        view.set_low_power(&gpu, true);
+        let reduced_resolution = export::render_to_rgba(&gpu, &view, SNAPSHOT_SIZE, SNAPSHOT_SIZE);
+
+        verify_that!(reduced_resolution == full_resolution, eq(true))
+    }
+
+    #[test]
+    fn low_power_matches_full_resolution_away_from_edges() -> Result<()> {
+        let gpu = Gpu::new_without_surface()?;
+        let mut view = create_newton_view(&gpu);
+        view.look_at(Vector2::new(0.3, 0.4), 1.0);
+        let full_resolution = export::render_to_rgba(&gpu, &view, SNAPSHOT_SIZE, SNAPSHOT_SIZE);
+
+        view.set_low_power(&gpu, true);
+        let reduced_resolution = export::render_to_rgba(&gpu, &view, SNAPSHOT_SIZE, SNAPSHOT_SIZE);
+
+        let matching = full_resolution
+            .chunks_exact(4)
+            .zip(reduced_resolution.chunks_exact(4))
+            .filter(|(full, reduced)| full == reduced)
+            .count();
+        verify_that!(
+            (reduced_resolution.len(), matching),
+            (
+                eq(full_resolution.len()),
+                gt(SNAPSHOT_SIZE as usize * SNAPSHOT_SIZE as usize / 2)
+            )
+        )
+    }
+
+    #[test]
+    fn comparison_replaces_only_right_of_divider() -> Result<()> {
+        let gpu = Gpu::new_without_surface()?;
+        let mut view = create_view(&gpu);
+        let current = export::render_to_rgba(&gpu, &view, SNAPSHOT_SIZE, SNAPSHOT_SIZE);
+        let mut compared = view.bookmark();
+        compared.palette = Palette::Viridis;
+        view.set_palette(Palette::Viridis);
+        let viridis = export::render_to_rgba(&gpu, &view, SNAPSHOT_SIZE, SNAPSHOT_SIZE);
+        view.set_palette(Palette::default());
+
+        view.set_comparison(Some(compared));
+        view.set_divider(0.25);
+        let split = export::render_to_rgba(&gpu, &view, SNAPSHOT_SIZE, SNAPSHOT_SIZE);
+
+        // In bytes from the start of each row.
+        let divider = 4 * (SNAPSHOT_SIZE / 4) as usize;
+        let rows = |image: &[u8], columns: Range<usize>| -> Vec<u8> {
+            image
+                .chunks_exact(4 * SNAPSHOT_SIZE as usize)
+                .flat_map(|row| row[columns.clone()].to_vec())
+                .collect()
+        };
+        let right = divider..4 * SNAPSHOT_SIZE as usize;
+        verify_that!(
+            (
+                rows(&split, 0..divider) == rows(&current, 0..divider),
+                rows(&split, right.clone()) == rows(&viridis, right.clone()),
+                rows(&current, right.clone()) == rows(&viridis, right)
+            ),
+            (eq(true), eq(true), eq(false))
+        )
+    }
+
+    #[test]
+    fn vector_mode_draws_outline_of_koch_snowflake() -> Result<()> {
+        let gpu = Gpu::new_without_surface()?;
+        let mut view = create_view(&gpu);
+        view.set_vector_mode(&gpu, VectorMode::Koch, 3);
+        view.update_transform(&gpu.queue);
+
+        let pixels = export::render_to_rgba(&gpu, &view, SNAPSHOT_SIZE, SNAPSHOT_SIZE);
+
+        let lit = pixels
+            .chunks_exact(4)
+            .filter(|pixel| pixel[..3] != [0, 0, 0])
+            .count();
+        let centre = ((SNAPSHOT_SIZE / 2 * SNAPSHOT_SIZE + SNAPSHOT_SIZE / 2) * 4) as usize;
+        // Only the outline is drawn, which leaves the inside of the snowflake black.
+        verify_that!(
+            (lit, &pixels[centre..centre + 3]),
+            (
+                all!(gt(SNAPSHOT_SIZE as usize), lt(SNAPSHOT_SIZE as usize * 8)),
+                eq(&[0, 0, 0][..])
+            )
+        )
+    }
+
+    #[test]
+    fn chaos_game_with_three_vertices_leaves_centre_of_sierpinski_triangle_empty() -> Result<()> {
+        let gpu = Gpu::new_without_surface()?;
+        let mut view = create_view(&gpu);
+        view.set_chaos_game(&gpu, Some(ChaosGame::default()));
+        view.update_transform(&gpu.queue);
+
+        let pixels = export::render_to_rgba(&gpu, &view, SNAPSHOT_SIZE, SNAPSHOT_SIZE);
+
+        // The corner lies outside the polygon, where the walkers never land.
+        let empty = &pixels[..4];
+        let hit = pixels
+            .chunks_exact(4)
+            .filter(|pixel| pixel != &empty)
+            .count();
+        let centre = ((SNAPSHOT_SIZE / 2 * SNAPSHOT_SIZE + SNAPSHOT_SIZE / 2) * 4) as usize;
+        let total = (SNAPSHOT_SIZE * SNAPSHOT_SIZE) as usize;
+        verify_that!(
+            (hit, &pixels[centre..centre + 4]),
+            (all!(gt(total / 20), lt(total / 2)), eq(empty))
+        )
+    }
+
+    #[test]
+    fn isolines_lighten_pixels_along_level() -> Result<()> {
+        let gpu = Gpu::new_without_surface()?;
+        let mut view = create_view(&gpu);
+        let plain = export::render_to_rgba(&gpu, &view, SNAPSHOT_SIZE, SNAPSHOT_SIZE);
+
+        view.set_isolines(&gpu, vec![3]);
+        let with_isolines = export::render_to_rgba(&gpu, &view, SNAPSHOT_SIZE, SNAPSHOT_SIZE);
+
+        let changed: Vec<_> = plain
+            .chunks_exact(4)
+            .zip(with_isolines.chunks_exact(4))
+            .filter(|(plain, with_isolines)| plain != with_isolines)
+            .collect();
+        let lightened = changed
+            .iter()
+            .filter(|(plain, with_isolines)| {
+                plain[..3]
+                    .iter()
+                    .zip(&with_isolines[..3])
+                    .all(|(before, after)| after > before)
+            })
+            .count();
+        verify_that!(
+            (changed.len(), lightened),
+            (
+                all!(gt(0), lt(SNAPSHOT_SIZE as usize * 4)),
+                eq(changed.len())
+            )
+        )
+    }
+
+    #[test]
+    fn recolouring_matches_full_render() -> Result<()> {
+        let gpu = Gpu::new_without_surface()?;
+        let mut view = create_view(&gpu);
+        export::render_to_rgba(&gpu, &view, SNAPSHOT_SIZE, SNAPSHOT_SIZE);
+
+        view.set_palette(Palette::Viridis);
+        view.set_palette_offset(0.3);
+        let recoloured = export::render_to_rgba(&gpu, &view, SNAPSHOT_SIZE, SNAPSHOT_SIZE);
+
+        let mut fresh_view = create_view(&gpu);
+        fresh_view.set_palette(Palette::Viridis);
+        fresh_view.set_palette_offset(0.3);
+        let rendered = export::render_to_rgba(&gpu, &fresh_view, SNAPSHOT_SIZE, SNAPSHOT_SIZE);
+        verify_that!(recoloured == rendered, eq(true))
+    }
+
+    #[test]
+    fn only_changes_of_iteration_inputs_make_iteration_data_stale() -> Result<()> {
+        let gpu = Gpu::new_without_surface()?;
+        let mut view = create_view(&gpu);
+        let size = wgpu::Extent3d {
+            width: SNAPSHOT_SIZE,
+            height: SNAPSHOT_SIZE,
+            depth_or_array_layers: 1,
+        };
+        export::render_to_rgba(&gpu, &view, SNAPSHOT_SIZE, SNAPSHOT_SIZE);
+
+        view.set_palette(Palette::Viridis);
+        view.set_palette_offset(0.3);
+        view.update_transform(&gpu.queue);
+        let stale_after_recolouring = view.iteration_data.is_stale(size);
+        view.set_max_iterations(200);
+        view.update_transform(&gpu.queue);
+        let stale_after_changing_iterations = view.iteration_data.is_stale(size);
+
+        verify_that!(
+            (stale_after_recolouring, stale_after_changing_iterations),
+            (eq(false), eq(true))
+        )
+    }
+
+    #[test]
+    fn opaque_normal_overlay_covers_base() -> Result<()> {
+        let gpu = Gpu::new_without_surface()?;
+        let mut view = create_view(&gpu);
+        view.set_palette(Palette::Viridis);
+        view.set_compositor(Compositor {
+            base: Layer::SmoothIteration,
+            overlay: Layer::StripeAverage,
+            blend_mode: BlendMode::Normal,
+            opacity: 1.0,
+        });
+        let overlaid = export::render_to_rgba(&gpu, &view, SNAPSHOT_SIZE, SNAPSHOT_SIZE);
+
+        view.set_compositor(Compositor {
+            base: Layer::StripeAverage,
+            ..Compositor::default()
+        });
+        let base = export::render_to_rgba(&gpu, &view, SNAPSHOT_SIZE, SNAPSHOT_SIZE);
+
+        view.set_compositor(Compositor::default());
+        let smooth = export::render_to_rgba(&gpu, &view, SNAPSHOT_SIZE, SNAPSHOT_SIZE);
+        verify_that!(
+            (overlaid == base, overlaid == smooth),
+            (eq(true), eq(false))
+        )
+    }
+
+    #[test]
+    fn transparent_interior_and_exterior_complement_each_other() -> Result<()> {
+        let gpu = Gpu::new_without_surface()?;
+        let mut view = create_view(&gpu);
+        let alphas = |view: &View| -> Vec<u8> {
+            export::render_to_rgba(&gpu, view, SNAPSHOT_SIZE, SNAPSHOT_SIZE)
+                .chunks_exact(4)
+                .map(|pixel| pixel[3])
+                .collect()
+        };
+
+        view.set_transparency(Transparency::Interior);
+        let interior = alphas(&view);
+        view.set_transparency(Transparency::Exterior);
+        let exterior = alphas(&view);
+
+        let transparent = interior.iter().filter(|&&alpha| alpha == 0).count();
+        verify_that!(
+            (
+                transparent,
+                interior.iter().zip(&exterior).all(|(a, b)| a ^ b == 255)
+            ),
+            (gt(0), eq(true))
+        )
+    }
+
+    #[test]
+    fn post_processing_without_saturation_renders_greys() -> Result<()> {
+        let gpu = Gpu::new_without_surface()?;
+        let mut view = create_view(&gpu);
+        view.set_palette(Palette::Viridis);
+        view.set_post_processing(PostProcessing {
+            exposure: 0.5,
+            saturation: 0.0,
+            ..Default::default()
         });
 
-        render_pass.set_pipeline(&self.pipeline);
-        render_pass.set_bind_group(0, &self.bind_group, &[]);
-        render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
-        render_pass.set_index_buffer(self.index_buffer.slice(..), wgpu::IndexFormat::Uint16);
-        render_pass.draw_indexed(0..6, 0, 0..1);
-    }
+        let pixels = export::render_to_rgba(&gpu, &view, SNAPSHOT_SIZE, SNAPSHOT_SIZE);
 
-    pub(super) fn update_transform(&self, queue: &iced_wgpu::wgpu::Queue) {
-        queue.write_buffer(
-            &self.uniform_buffer,
-            0,
-            bytemuck::cast_slice::<Uniform, _>(&[self.view_transform.into()]),
-        );
+        let coloured = pixels
+            .chunks_exact(4)
+            .filter(|pixel| pixel[0].abs_diff(pixel[1]) > 1 || pixel[1].abs_diff(pixel[2]) > 1)
+            .count();
+        verify_that!(coloured, eq(0))
     }
 
-    pub(super) fn translate(&mut self, displacement: Vector2<f32>) {
-        self.view_transform =
-            self.view_transform * Matrix3::from_translation(ORIGINAL_VIEWPORT_WIDTH * displacement);
+    #[test]
+    fn tone_mapping_keeps_overexposed_highlights_from_clipping() -> Result<()> {
+        let gpu = Gpu::new_without_surface()?;
+        let mut view = create_view(&gpu);
+        view.set_palette(Palette::Viridis);
+        let mut clipped_pixels = |tonemapper| {
+            view.set_post_processing(PostProcessing {
+                exposure: 3.0,
+                tonemapper,
+                ..Default::default()
+            });
+            export::render_to_rgba(&gpu, &view, SNAPSHOT_SIZE, SNAPSHOT_SIZE)
+                .chunks_exact(4)
+                .filter(|pixel| pixel[..3].iter().filter(|channel| **channel == 255).count() >= 2)
+                .count()
+        };
+
+        let clipped = clipped_pixels(Tonemapper::Clip);
+        let tone_mapped = clipped_pixels(Tonemapper::Aces);
+
+        verify_that!((clipped, tone_mapped), (gt(0), eq(0)))
     }
 
-    pub(super) fn zoom(&mut self, factor: f32, on_point: Vector2<f32>) {
-        self.view_transform = self.view_transform
-            * Matrix3::from_translation(ORIGINAL_VIEWPORT_WIDTH / 2.0 * on_point)
-            * Matrix3::from_scale(factor)
-            * Matrix3::from_translation(-ORIGINAL_VIEWPORT_WIDTH / 2.0 * on_point);
+    #[test]
+    fn gpu_memory_counts_iteration_data_once_rendered() -> Result<()> {
+        let gpu = Gpu::new_without_surface()?;
+        let view = create_view(&gpu);
+        let before = view.gpu_memory();
+
+        export::render_to_rgba(&gpu, &view, SNAPSHOT_SIZE, SNAPSHOT_SIZE);
+
+        // One pixel of the iteration data is four 32-bit floats.
+        let pixels = u64::from(SNAPSHOT_SIZE * SNAPSHOT_SIZE);
+        verify_that!(
+            (before.total(), view.gpu_memory().iteration_data),
+            (eq(0), eq(pixels * 16))
+        )
     }
 
-    pub(super) fn get_view_transform(&self) -> Matrix3<f32> {
-        self.view_transform
+    #[test]
+    fn escape_radius_view_renders_greys_outside_set() -> Result<()> {
+        let gpu = Gpu::new_without_surface()?;
+        let mut view = create_view(&gpu);
+        view.set_palette(Palette::Viridis);
+        view.set_debug_view(DebugView::EscapeRadius);
+
+        let pixels = export::render_to_rgba(&gpu, &view, SNAPSHOT_SIZE, SNAPSHOT_SIZE);
+
+        // The corner escapes at once, whereas the centre of the view, near -0.5, lies inside the
+        // set.
+        let centre = ((SNAPSHOT_SIZE / 2 * SNAPSHOT_SIZE + SNAPSHOT_SIZE / 2) * 4) as usize;
+        verify_that!([pixels[1], pixels[2]], eq([pixels[0], pixels[0]]))?;
+        verify_that!(
+            [pixels[centre], pixels[centre + 1], pixels[centre + 2]],
+            elements_are![gt(0), eq(0), eq(0)]
+        )
     }
 
-    pub(super) fn set_fractal_type(&mut self, gpu: &Gpu, fractal_type: FractalType) {
-        self.pipeline = Self::build_pipeline(
-            gpu,
-            &self.pipeline_layout,
-            &self.vs_module,
-            &self.fs_module,
-            Self::entry_point_for_fractal_type(fractal_type),
-        );
+    #[test]
+    fn orbit_trap_colours_points_by_image_where_orbit_landed() -> Result<()> {
+        let gpu = Gpu::new_without_surface()?;
+        let mut view = create_view(&gpu);
+        let mut image = image::RgbaImage::from_pixel(2, 1, image::Rgba([0, 0, 255, 255]));
+        image.put_pixel(0, 0, image::Rgba([255, 0, 0, 255]));
+        view.set_trap_image(&gpu, &image);
+        // The orbit of the centre of the view, near -0.5, first lands in the left half of the
+        // trap.
+        view.set_orbit_trap(OrbitTrap {
+            enabled: true,
+            centre: Vector2::new(0.0, 0.0),
+            half_size: 1.0,
+        });
+
+        let pixels = export::render_to_rgba(&gpu, &view, SNAPSHOT_SIZE, SNAPSHOT_SIZE);
+
+        let centre = ((SNAPSHOT_SIZE / 2 * SNAPSHOT_SIZE + SNAPSHOT_SIZE / 2) * 4) as usize;
+        verify_that!(
+            (pixels[centre], pixels[centre + 1], pixels[centre + 2]),
+            (ge(240), eq(0), le(15))
+        )
     }
 
-    fn build_pipeline(
-        gpu: &Gpu,
-        pipeline_layout: &wgpu::PipelineLayout,
-        vs_module: &wgpu::ShaderModule,
-        fs_module: &wgpu::ShaderModule,
-        entry_point: &'static str,
-    ) -> wgpu::RenderPipeline {
-        gpu.device
-            .create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-                label: None,
-                layout: Some(pipeline_layout),
-                vertex: wgpu::VertexState {
-                    module: vs_module,
-                    entry_point: "main",
-                    buffers: &[wgpu::VertexBufferLayout {
-                        array_stride: std::mem::size_of::<[f32; 2]>() as wgpu::BufferAddress,
-                        step_mode: wgpu::VertexStepMode::Vertex,
-                        attributes: &wgpu::vertex_attr_array![0 => Float32x2],
-                    }],
-                },
-                fragment: Some(wgpu::FragmentState {
-                    module: fs_module,
-                    entry_point,
-                    targets: &[Some(wgpu::ColorTargetState {
-                        format: gpu.texture_format,
-                        blend: Some(wgpu::BlendState {
-                            color: wgpu::BlendComponent::REPLACE,
-                            alpha: wgpu::BlendComponent::REPLACE,
-                        }),
-                        write_mask: wgpu::ColorWrites::ALL,
-                    })],
-                }),
-                primitive: wgpu::PrimitiveState {
-                    topology: wgpu::PrimitiveTopology::TriangleList,
-                    front_face: wgpu::FrontFace::Ccw,
-                    ..Default::default()
-                },
-                depth_stencil: None,
-                multisample: wgpu::MultisampleState {
-                    count: 1,
-                    mask: !0,
-                    alpha_to_coverage_enabled: false,
-                },
-                multiview: None,
+    #[test]
+    fn explorer_grid_renders_its_thumbnails_a_few_per_frame() -> Result<()> {
+        let gpu = Gpu::new_without_surface()?;
+        let mut view = create_view(&gpu);
+        view.set_explorer_grid(true);
+
+        let pending: Vec<_> = (0..5)
+            .map(|_| {
+                export::render_to_rgba(&gpu, &view, SNAPSHOT_SIZE, SNAPSHOT_SIZE);
+                view.thumbnails_pending()
             })
-    }
+            .collect();
 
-    fn entry_point_for_fractal_type(fractal_type: FractalType) -> &'static str {
-        match fractal_type {
-            FractalType::Mandelbrot => "mandelbrot",
-            FractalType::Newton => "newton",
-        }
+        verify_that!(pending, eq(vec![true, true, true, false, false]))
     }
-}
 
-#[repr(C)]
-#[derive(Default, Clone, Copy, Pod, Zeroable)]
-struct Uniform {
-    transform_1: [f32; 3],
-    _padding_1: f32,
-    transform_2: [f32; 3],
-    _padding_2: f32,
-    transform_3: [f32; 3],
-    _padding_3: f32,
-}
+    #[test]
+    fn julia_inset_waits_for_its_thumbnail_and_is_left_out_of_exports() -> Result<()> {
+        let gpu = Gpu::new_without_surface()?;
+        let mut view = create_view(&gpu);
+        let plain = export::render_to_rgba(&gpu, &view, SNAPSHOT_SIZE, SNAPSHOT_SIZE);
+        view.set_julia_inset(Some(Vector2::new(-0.8, 0.156)));
 
-impl Uniform {
-    pub fn layout_entry() -> wgpu::BindGroupLayoutEntry {
-        wgpu::BindGroupLayoutEntry {
-            binding: 0,
-            visibility: wgpu::ShaderStages::FRAGMENT | wgpu::ShaderStages::COMPUTE,
-            ty: wgpu::BindingType::Buffer {
-                ty: wgpu::BufferBindingType::Uniform,
-                has_dynamic_offset: false,
-                min_binding_size: NonZeroU64::new(std::mem::size_of::<Self>() as u64),
-            },
-            count: None,
-        }
-    }
-}
+        let pending: Vec<_> = (0..3)
+            .map(|_| {
+                let pixels = export::render_to_rgba(&gpu, &view, SNAPSHOT_SIZE, SNAPSHOT_SIZE);
+                (view.thumbnails_pending(), pixels == plain)
+            })
+            .collect();
 
-impl From<Matrix3<f32>> for Uniform {
-    fn from(value: Matrix3<f32>) -> Self {
-        let value = value.transpose(); // Input is column-major
-        Self {
-            transform_1: value.row(0).into(),
-            transform_2: value.row(1).into(),
-            transform_3: value.row(2).into(),
-            ..Default::default()
-        }
+        verify_that!(
+            pending,
+            eq(vec![(true, true), (false, true), (false, true)])
+        )
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::View;
-    use crate::{
-        gpu::Gpu,
-        wgpu_test::{DescribableStruct, GpuTestHarness},
-        wgsl_shader_test,
-    };
-    use bytemuck::{Pod, Zeroable};
-    use cgmath::Vector3;
-    use googletest::matchers::__internal_unstable_do_not_depend_on_these::ElementsAre;
-    use googletest::prelude::*;
+    #[test]
+    fn bloom_brightens_surroundings_of_bright_pixels() -> Result<()> {
+        let gpu = Gpu::new_without_surface()?;
+        let mut view = create_view(&gpu);
+        // Moves the escaping points to the bright end of the palette, next to the black interior.
+        view.set_palette_offset(0.8);
+        let plain = export::render_to_rgba(&gpu, &view, SNAPSHOT_SIZE, SNAPSHOT_SIZE);
+
+        view.set_bloom(&gpu, true);
+        let bloomed = export::render_to_rgba(&gpu, &view, SNAPSHOT_SIZE, SNAPSHOT_SIZE);
+
+        let darkened = plain
+            .iter()
+            .zip(&bloomed)
+            .filter(|(plain, bloomed)| bloomed < plain)
+            .count();
+        let brightened = plain
+            .iter()
+            .zip(&bloomed)
+            .filter(|(plain, bloomed)| bloomed > plain)
+            .count();
+        verify_that!((darkened, brightened), (eq(0), gt(0)))
+    }
 
     #[async_std::test]
-    async fn transform_is_transferred_correctly() -> Result<()> {
-        let gpu = Gpu::new_without_surface();
-        let input = MappableVector(Vector3::new(1.0, 2.0, 1.0).into());
+    async fn df64_arithmetic_keeps_digits_beyond_f32() -> Result<()> {
+        let gpu = Gpu::new_without_surface()?;
+        let factor = 1.0 + 2f32.powi(-12);
+        let input = MappableVector([1.0, 2f32.powi(-30), factor]);
         let view = create_view(&gpu);
         let harness = GpuTestHarness::new(&gpu.device, &gpu.queue, &input).with_bind_group(
             0,
@@ -272,31 +3113,38 @@ mod tests {
         );
         let test_shader = wgsl_shader_test!(
             "shader/frag.wgsl",
+            include_str!("shader/df64.wgsl"),
             "
                 @group(1) @binding(0) var<storage, read_write> v: vec3<f32>;
 
                 @compute
                 @workgroup_size(1)
-                fn apply_uniform() {
-                    let v_out = u.transform * v;
-                    v = v_out;
+                fn df64_arithmetic() {
+                    let one = vec2(v.x, 0.0);
+                    let sum = df64_add(one, vec2(v.y, 0.0));
+                    let product = df64_mul(vec2(v.z, 0.0), vec2(v.z, 0.0));
+                    v = vec3(df64_sub(sum, one).x, product);
                 }
             "
         );
 
-        harness.run_compute_shader(test_shader, "apply_uniform");
+        harness.run_compute_shader(test_shader, "df64_arithmetic");
 
+        let [difference, product, product_error] = harness.fetch_result(&gpu.device).await.0;
         verify_that!(
-            harness.fetch_result(&gpu.device).await,
-            eq(MappableVector([1.5, 4.0, 1.0]))
+            (difference, product as f64 + product_error as f64),
+            (eq(2f32.powi(-30)), eq((factor as f64).powi(2)))
         )
     }
 
     #[async_std::test]
-    async fn mandelbrot_iteration_is_applied_correctly_inside_set() -> Result<()> {
-        let gpu = Gpu::new_without_surface();
-        let input = MappableVector(Vector3::new(-0.5, 0.5, 0.0).into());
-        let view = create_view(&gpu);
+    async fn df64_position_includes_centre_residual() -> Result<()> {
+        let gpu = Gpu::new_without_surface()?;
+        let input = MappableVector([0.0, 0.0, 0.0]);
+        let centre = -0.75 + 2f64.powi(-40);
+        let mut view = create_view(&gpu);
+        view.look_at_precisely(Vector2::new(centre, 0.0), 1e-9);
+        view.update_transform(&gpu.queue);
         let harness = GpuTestHarness::new(&gpu.device, &gpu.queue, &input).with_bind_group(
             0,
             &view.bind_group,
@@ -304,179 +3152,260 @@ mod tests {
         );
         let test_shader = wgsl_shader_test!(
             "shader/frag.wgsl",
+            include_str!("shader/df64.wgsl"),
             "
                 @group(1) @binding(0) var<storage, read_write> v: vec3<f32>;
 
                 @compute
                 @workgroup_size(1)
-                fn run_mandelbrot_iteration() {
-                    let i = mandelbrot_iterations(vec2(v.x, v.y));
-                    v = vec3(i, 0.0, 0.0);
+                fn position_of_centre() {
+                    let position = df64_position(vec2(0.0, 0.0));
+                    v = vec3(position.xy, position.z);
                 }
             "
         );
 
-        harness.run_compute_shader(test_shader, "run_mandelbrot_iteration");
+        harness.run_compute_shader(test_shader, "position_of_centre");
 
-        verify_that!(
-            harness.fetch_result(&gpu.device).await,
-            eq(MappableVector([0.0, 0.0, 0.0]))
-        )
+        let [re, re_residual, im] = harness.fetch_result(&gpu.device).await.0;
+        verify_that!((re as f64 + re_residual as f64, im), (eq(centre), eq(0.0)))
     }
 
-    #[async_std::test]
-    async fn mandelbrot_iteration_is_applied_correctly_outside_set() -> Result<()> {
-        let gpu = Gpu::new_without_surface();
-        let input = MappableVector(Vector3::new(0.5, 0.6, 0.0).into());
-        let view = create_view(&gpu);
-        let harness = GpuTestHarness::new(&gpu.device, &gpu.queue, &input).with_bind_group(
-            0,
-            &view.bind_group,
-            &view.bind_group_layout,
-        );
-        let test_shader = wgsl_shader_test!(
-            "shader/frag.wgsl",
-            "
-                @group(1) @binding(0) var<storage, read_write> v: vec3<f32>;
+    #[test]
+    fn small_translations_accumulate_in_centre_residual() -> Result<()> {
+        let gpu = Gpu::new_without_surface()?;
+        let mut view = View::new(&gpu);
+        view.look_at(Vector2::new(-0.75, 0.0), 1e-9);
 
-                @compute
-                @workgroup_size(1)
-                fn run_mandelbrot_iteration() {
-                    let i = mandelbrot_iterations(vec2(v.x, v.y));
-                    v = vec3(i, 0.0, 0.0);
-                }
-            "
-        );
+        for _ in 0..4 {
+            view.translate(Vector2::new(0.25, 0.0));
+        }
 
-        harness.run_compute_shader(test_shader, "run_mandelbrot_iteration");
+        verify_that!(view.centre().x, near(-0.75 + 2e-9, 1e-15))
+    }
+
+    #[test]
+    fn bookmark_restores_centre_beyond_precision_of_f32() -> Result<()> {
+        let gpu = Gpu::new_without_surface()?;
+        let mut view = View::new(&gpu);
+        let centre = Vector2::new(-0.75 + 1e-12, 0.1 - 3e-13);
+        view.look_at_precisely(centre, 1e-13);
+        let bookmark = view.bookmark();
+
+        view.reset_transform();
+        view.apply_bookmark(&bookmark);
+
+        verify_that!(view.centre().x, near(centre.x, 1e-16))?;
+        verify_that!(view.centre().y, near(centre.y, 1e-16))
+    }
+
+    #[test]
+    fn revisited_bookmark_finds_its_tile() -> Result<()> {
+        let gpu = Gpu::new_without_surface()?;
+        let mut view = View::new(&gpu);
+        let size = wgpu::Extent3d {
+            width: 64,
+            height: 64,
+            depth_or_array_layers: 1,
+        };
+        view.set_numeric_mode(NumericMode::Perturbation);
+        view.look_at_precisely(Vector2::new(-0.743_643_887_037, 0.131_825_904_205), 1e-11);
+        view.translate(Vector2::new(0.013, -0.007));
+        view.zoom(0.7, Vector2::new(0.2, 0.1));
+        view.update_reference_orbit();
+        let key = view.tile_key(size);
+        let stored = serde_json::to_string(&view.bookmark())?;
+
+        view.reset_transform();
+        view.update_reference_orbit();
+        view.apply_bookmark(&serde_json::from_str(&stored)?);
+        view.update_reference_orbit();
+
+        verify_that!(view.tile_key(size), some(eq(key.unwrap())))
+    }
+
+    #[test]
+    fn zooming_on_point_keeps_it_in_place() -> Result<()> {
+        let gpu = Gpu::new_without_surface()?;
+        let mut view = View::new(&gpu);
+        view.look_at_precisely(Vector2::new(-0.75 + 1e-12, 0.1), 1e-9);
+        let point = view.point_at(Vector2::new(0.3, -0.2));
+
+        view.zoom(0.5, view.offset_of(point));
+        let offset = view.offset_of(point);
 
         verify_that!(
-            harness.fetch_result(&gpu.device).await,
-            // TODO: Using the elements_are! macro directly causes the Rust
-            // compiler to be utterly confused with type inference, inferring
-            // the container type to be an infinitely recursive type coming from
-            // the palette crate for some reason. So we have to use the actual
-            // struct and add an explicit type argument. Figure out why this is
-            // happening and solve the root cause, if possible.
-            matches_pattern!(MappableVector(ElementsAre::<[f32; 3], _>::new(vec![
-                Box::new(gt(0.0)),
-                Box::new(eq(0.0)),
-                Box::new(eq(0.0)),
-            ])))
+            (offset.x, offset.y, view.centre().x),
+            (
+                near(0.3, 1e-4),
+                near(-0.2, 1e-4),
+                near(-0.75 + 1e-12 + 0.3e-9, 1e-15)
+            )
         )
     }
 
     #[async_std::test]
-    async fn eval_poly_evaluates_correctly_at_root() -> Result<()> {
-        let gpu = Gpu::new_without_surface();
-        let input = MappableVector(Vector3::new(-0.5, 3.0f32.sqrt() / 2.0, 0.0).into());
+    async fn floatexp_products_go_below_f32_range() -> Result<()> {
+        let gpu = Gpu::new_without_surface()?;
+        let input = MappableVector([0.75, -0.5, -100.0]);
         let view = create_view(&gpu);
-        let harness = GpuTestHarness::new(&gpu.device, &gpu.queue, &input).with_bind_group(
-            0,
-            &view.bind_group,
-            &view.bind_group_layout,
-        );
+        let harness = GpuTestHarness::new(&gpu.device, &gpu.queue, &input)
+            .with_bind_group(0, &view.bind_group, &view.bind_group_layout)
+            .with_buffer("results", &[[0.0f32; 4]; 2]);
         let test_shader = wgsl_shader_test!(
             "shader/frag.wgsl",
+            include_str!("shader/floatexp.wgsl"),
             "
                 @group(1) @binding(0) var<storage, read_write> v: vec3<f32>;
+                @group(1) @binding(1) var<storage, read_write> results: array<vec4<f32>>;
 
                 @compute
                 @workgroup_size(1)
-                fn run_eval_poly() {
-                    let result = eval_poly(vec2(v.x, v.y), COEFFS);
-                    v = vec3(result, 0.0);
+                fn floatexp_product() {
+                    let a = floatexp_normalize(v.xy, i32(v.z));
+                    let product = floatexp_mul(a, a);
+                    results[0] = vec4(product.mantissa, f32(product.exponent), 0.0);
+                    results[1] = vec4(floatexp_to_f32(product), floatexp_to_f32(a));
                 }
             "
         );
 
-        harness.run_compute_shader(test_shader, "run_eval_poly");
+        harness.run_compute_shader(test_shader, "floatexp_product");
 
+        let results: Vec<[f32; 4]> = harness.fetch_buffer("results").await;
         verify_that!(
-            harness.fetch_result(&gpu.device).await,
-            matches_pattern!(MappableVector(ElementsAre::<[f32; 3], _>::new(vec![
-                Box::new(approx_eq(0.0)),
-                Box::new(approx_eq(0.0)),
-                Box::new(eq(0.0)),
-            ])))
+            (results[0], results[1]),
+            (
+                eq([0.3125, -0.75, -200.0, 0.0]),
+                eq([0.0, 0.0, 0.75 * 2f32.powi(-100), -0.5 * 2f32.powi(-100)])
+            )
         )
     }
 
     #[async_std::test]
-    async fn eval_poly_on_derivative_evaluates_correctly() -> Result<()> {
-        let gpu = Gpu::new_without_surface();
-        let input = MappableVector(Vector3::new(2.0, 0.0, 0.0).into());
+    async fn floatexp_sums_align_exponents() -> Result<()> {
+        let gpu = Gpu::new_without_surface()?;
+        let input = MappableVector([0.0, 0.0, 0.0]);
         let view = create_view(&gpu);
-        let harness = GpuTestHarness::new(&gpu.device, &gpu.queue, &input).with_bind_group(
-            0,
-            &view.bind_group,
-            &view.bind_group_layout,
-        );
+        let harness = GpuTestHarness::new(&gpu.device, &gpu.queue, &input)
+            .with_bind_group(0, &view.bind_group, &view.bind_group_layout)
+            .with_buffer(
+                "values",
+                &[[0.75f32, -0.5, -100.0, 0.0], [0.5, 0.5, -110.0, 0.0]],
+            )
+            .with_buffer("results", &[[0.0f32; 4]; 3]);
         let test_shader = wgsl_shader_test!(
             "shader/frag.wgsl",
+            include_str!("shader/floatexp.wgsl"),
             "
                 @group(1) @binding(0) var<storage, read_write> v: vec3<f32>;
+                @group(1) @binding(1) var<storage, read_write> values: array<vec4<f32>>;
+                @group(1) @binding(2) var<storage, read_write> results: array<vec4<f32>>;
+
+                fn store(index: u32, value: ComplexExp) {
+                    results[index] = vec4(value.mantissa, f32(value.exponent), 0.0);
+                }
 
                 @compute
                 @workgroup_size(1)
-                fn run_eval_poly_df() {
-                    let result = eval_poly(vec2(v.x, v.y), DERIVATIVE_COEFFS);
-                    v = vec3(result, 0.0);
+                fn floatexp_sums() {
+                    let a = floatexp_normalize(values[0].xy, i32(values[0].z));
+                    let b = floatexp_normalize(values[1].xy, i32(values[1].z));
+                    store(0u, floatexp_add(a, b));
+                    store(1u, floatexp_add(floatexp_zero(), b));
+                    store(2u, floatexp_add(a, floatexp_normalize(values[1].xy, -200)));
                 }
             "
         );
 
-        harness.run_compute_shader(test_shader, "run_eval_poly_df");
+        harness.run_compute_shader(test_shader, "floatexp_sums");
 
+        let results: Vec<[f32; 4]> = harness.fetch_buffer("results").await;
+        let scale = 2f32.powi(-10);
         verify_that!(
-            harness.fetch_result(&gpu.device).await,
-            matches_pattern!(MappableVector(ElementsAre::<[f32; 3], _>::new(vec![
-                Box::new(approx_eq(12.0)),
-                Box::new(approx_eq(0.0)),
-                Box::new(eq(0.0)),
-            ])))
+            (results[0], results[1], results[2]),
+            (
+                eq([0.75 + 0.5 * scale, -0.5 + 0.5 * scale, -100.0, 0.0]),
+                eq([0.5, 0.5, -110.0, 0.0]),
+                eq([0.75, -0.5, -100.0, 0.0])
+            )
         )
     }
 
     #[async_std::test]
-    async fn inv_calculates_correct_inverse() -> Result<()> {
-        let gpu = Gpu::new_without_surface();
-        let input = MappableVector(Vector3::new(-2.0, 1.5, 0.0).into());
+    async fn floatexp_delta_iteration_matches_f64_below_f32_range() -> Result<()> {
+        const ITERATIONS: usize = 20;
+        let gpu = Gpu::new_without_surface()?;
+        let reference_point = Vector2::new(-0.75f32, 0.1);
+        let mut reference_orbit = vec![[0.0f32; 2]; ITERATIONS];
+        let mut z = Vector2::new(0.0f32, 0.0);
+        for point in reference_orbit.iter_mut() {
+            *point = z.into();
+            z = Vector2::new(z.x * z.x - z.y * z.y, 2.0 * z.x * z.y) + reference_point;
+        }
+        // The delta of the pixel from the reference point, 2^-140 * (0.5 + 0.25i), which is about
+        // 1e-42.
+        let delta_c = Vector2::new(0.5, 0.25) * 2f64.powi(-140);
+        let input = MappableVector([0.5, 0.25, -140.0]);
         let view = create_view(&gpu);
-        let harness = GpuTestHarness::new(&gpu.device, &gpu.queue, &input).with_bind_group(
-            0,
-            &view.bind_group,
-            &view.bind_group_layout,
-        );
+        let harness = GpuTestHarness::new(&gpu.device, &gpu.queue, &input)
+            .with_bind_group(0, &view.bind_group, &view.bind_group_layout)
+            .with_buffer("reference_orbit", &reference_orbit);
         let test_shader = wgsl_shader_test!(
             "shader/frag.wgsl",
+            include_str!("shader/floatexp.wgsl"),
             "
                 @group(1) @binding(0) var<storage, read_write> v: vec3<f32>;
+                @group(1) @binding(1) var<storage, read_write> reference_orbit: array<vec2<f32>>;
 
                 @compute
                 @workgroup_size(1)
-                fn run_inv() {
-                    let result = inv(vec2(v.x, v.y));
-                    v = vec3(result, 0.0);
+                fn iterate_delta() {
+                    let delta_c = floatexp_normalize(v.xy, i32(v.z));
+                    var delta = floatexp_zero();
+                    for (var n = 0u; n < arrayLength(&reference_orbit); n++) {
+                        delta = floatexp_mandelbrot_delta_step(delta, reference_orbit[n], delta_c);
+                    }
+                    v = vec3(delta.mantissa, f32(delta.exponent));
                 }
             "
         );
 
-        harness.run_compute_shader(test_shader, "run_inv");
+        harness.run_compute_shader(test_shader, "iterate_delta");
 
-        let inv = harness.fetch_result(&gpu.device).await;
-        let inv_times_input = (
-            inv.0[0] * input.0[0] - inv.0[1] * input.0[1],
-            inv.0[0] * input.0[1] + inv.0[1] * input.0[0],
-        );
-        verify_that!(inv_times_input, (approx_eq(1.0), approx_eq(0.0)))
+        let mut expected = Vector2::new(0.0f64, 0.0);
+        for [x, y] in reference_orbit {
+            let z = Vector2::new(x as f64, y as f64);
+            let factor = 2.0 * z + expected;
+            expected = Vector2::new(
+                expected.x * factor.x - expected.y * factor.y,
+                expected.x * factor.y + expected.y * factor.x,
+            ) + delta_c;
+        }
+        let [re, im, exponent] = harness.fetch_result(&gpu.device).await.0;
+        let actual = Vector2::new(re as f64, im as f64) * 2f64.powi(exponent as i32);
+        let tolerance = 1e-5 * expected.magnitude();
+        verify_that!(
+            (actual.x, actual.y, expected.magnitude()),
+            (
+                near(expected.x, tolerance),
+                near(expected.y, tolerance),
+                lt(f32::MIN_POSITIVE as f64)
+            )
+        )
     }
 
     #[async_std::test]
-    async fn newton_converges_to_root() -> Result<()> {
-        let gpu = Gpu::new_without_surface();
-        let input = MappableVector(Vector3::new(-2.0, 5.0, 0.0).into());
-        let view = create_view(&gpu);
+    async fn delta_c_resolves_view_below_f32_range() -> Result<()> {
+        let gpu = Gpu::new_without_surface()?;
+        let mut view = create_view(&gpu);
+        view.set_numeric_mode(NumericMode::Perturbation);
+        view.look_at_precisely(Vector2::new(0.0, 0.0), 1e-50);
+        view.update_reference_orbit();
+        // Moves the view away from the bound reference orbit by a few times its width.
+        view.look_at_precisely(Vector2::new(3e-50, -1e-50), 1e-50);
+        view.update_transform(&gpu.queue);
+        let input = MappableVector([1.0, -0.5, 0.0]);
         let harness = GpuTestHarness::new(&gpu.device, &gpu.queue, &input).with_bind_group(
             0,
             &view.bind_group,
@@ -484,32 +3413,63 @@ mod tests {
         );
         let test_shader = wgsl_shader_test!(
             "shader/frag.wgsl",
+            include_str!("shader/floatexp.wgsl"),
             "
                 @group(1) @binding(0) var<storage, read_write> v: vec3<f32>;
 
                 @compute
                 @workgroup_size(1)
-                fn run_newton() {
-                    let result = newton_iterate(v);
-                    v = vec3(result, 0.0);
+                fn compute_delta_c() {
+                    let delta_c = floatexp_delta_c(v.xy);
+                    v = vec3(delta_c.mantissa, f32(delta_c.exponent));
                 }
             "
         );
 
-        harness.run_compute_shader(test_shader, "run_newton");
+        harness.run_compute_shader(test_shader, "compute_delta_c");
 
+        let expected = view.point_at(Vector2::new(0.5, -0.25));
+        let [re, im, exponent] = harness.fetch_result(&gpu.device).await.0;
+        let actual = Vector2::new(re as f64, im as f64) * 2f64.powi(exponent as i32);
+        let tolerance = 1e-6 * expected.magnitude();
         verify_that!(
-            harness.fetch_result(&gpu.device).await,
-            matches_pattern!(MappableVector(ElementsAre::<[f32; 3], _>::new(vec![
-                Box::new(approx_eq(-0.5)),
-                Box::new(approx_eq(3.0f32.sqrt() / 2.0)),
-                Box::new(eq(0.0)),
-            ])))
+            (actual.x, actual.y),
+            (near(expected.x, tolerance), near(expected.y, tolerance))
         )
     }
 
+    #[test]
+    fn perturbation_agrees_with_double_float() -> Result<()> {
+        let gpu = Gpu::new_without_surface()?;
+        let mut view = create_view(&gpu);
+        view.look_at_precisely(Vector2::new(-0.743_643_887, 0.131_825_904), 1e-7);
+        view.set_numeric_mode(NumericMode::DoubleDouble);
+        let double_float = export::render_to_rgba(&gpu, &view, SNAPSHOT_SIZE, SNAPSHOT_SIZE);
+
+        view.set_numeric_mode(NumericMode::Perturbation);
+        let bound = view.update_reference_orbit();
+        let perturbed = export::render_to_rgba(&gpu, &view, SNAPSHOT_SIZE, SNAPSHOT_SIZE);
+
+        let differing = double_float
+            .iter()
+            .zip(&perturbed)
+            .filter(|(a, b)| a.abs_diff(**b) > 8)
+            .count();
+        verify_that!((bound, differing), (eq(true), lt(double_float.len() / 100)))
+    }
+
     fn create_view(gpu: &Gpu) -> View {
-        let view = View::new(&gpu);
+        let mut view = View::new(gpu);
+        view.wait_for_pipelines();
+        view.update_transform(&gpu.queue);
+        view
+    }
+
+    /// Creates a view whose uniforms hold the default parameters of the Newton fractal.
+    fn create_newton_view(gpu: &Gpu) -> View {
+        let mut view = View::new(gpu);
+        view.wait_for_pipelines();
+        view.set_fractal_type(FractalType::Newton);
         view.update_transform(&gpu.queue);
         view
     }
@@ -519,4 +3479,107 @@ mod tests {
     struct MappableVector([f32; 3]);
 
     impl DescribableStruct for MappableVector {}
+
+    #[repr(C)]
+    #[derive(Clone, Copy, Pod, Zeroable, Debug, PartialEq)]
+    struct BlockFields([f32; 20]);
+
+    impl DescribableStruct for BlockFields {}
+
+    #[repr(C)]
+    #[derive(Clone, Copy, Pod, Zeroable, Debug, PartialEq)]
+    struct Points([[f32; 2]; 4]);
+
+    impl DescribableStruct for Points {}
+
+    const PROPERTY_TEST_CASES: u32 = 16;
+    const BATCH_SIZE: usize = 64;
+
+    /// Creates a test shader which evaluates the given WGSL expression on each element `a[i]`
+    /// of a batch of complex numbers, with `b[i]` as an optional second argument.
+    macro_rules! complex_batch_shader {
+        ($expression:literal) => {
+            complex_batch_shader!($expression, "vec2<f32>")
+        };
+        ($expression:literal, $b_type:literal) => {
+            wgsl_shader_test!(
+                "shader/frag.wgsl",
+                "
+                    @group(1) @binding(0) var<storage, read_write> a: array<vec2<f32>, 64>;
+                    @group(1) @binding(1) var<storage, read_write> b: array<",
+                $b_type,
+                ">;
+                    @group(1) @binding(2) var<storage, read_write> result: array<vec2<f32>>;
+
+                    @compute
+                    @workgroup_size(64)
+                    fn run_batch(@builtin(global_invocation_id) id: vec3<u32>) {
+                        let i = id.x;
+                        result[i] = ",
+                $expression,
+                ";
+                    }
+                "
+            )
+        };
+    }
+    use complex_batch_shader;
+
+    #[repr(C)]
+    #[derive(Clone, Copy, Pod, Zeroable)]
+    struct ComplexBatch([Complex32; BATCH_SIZE]);
+
+    impl DescribableStruct for ComplexBatch {}
+
+    fn complex_batch(log10_magnitude: Range<f32>) -> impl Strategy<Value = Vec<Complex32>> {
+        collection::vec(
+            (log10_magnitude, 0.0..TAU)
+                .prop_map(|(m, arg)| Complex32::from_polar(10.0f32.powf(m), arg)),
+            BATCH_SIZE,
+        )
+    }
+
+    fn run_complex_batch<U: Pod>(
+        gpu: &Gpu,
+        view: &View,
+        shader: wgpu::ShaderModuleDescriptor,
+        a: &[Complex32],
+        b: &[U],
+    ) -> Vec<Complex32> {
+        let input = ComplexBatch(a.try_into().unwrap());
+        let harness = GpuTestHarness::new(&gpu.device, &gpu.queue, &input)
+            .with_bind_group(0, &view.bind_group, &view.bind_group_layout)
+            .with_buffer("b", b)
+            .with_buffer("result", &[Complex32::default(); BATCH_SIZE]);
+        harness.run_compute_shader(shader, "run_batch");
+        async_std::task::block_on(harness.fetch_buffer("result"))
+    }
+
+    fn check_close(
+        actual: Complex32,
+        expected: Complex32,
+        relative_tolerance: f32,
+        describe: impl Fn() -> String,
+    ) -> std::result::Result<(), TestCaseError> {
+        check_close_absolute(
+            actual,
+            expected,
+            relative_tolerance * expected.norm(),
+            describe,
+        )
+    }
+
+    fn check_close_absolute(
+        actual: Complex32,
+        expected: Complex32,
+        tolerance: f32,
+        describe: impl Fn() -> String,
+    ) -> std::result::Result<(), TestCaseError> {
+        prop_assert!(
+            (actual - expected).norm() <= tolerance,
+            "{}: expected {expected}, got {actual}",
+            describe()
+        );
+        Ok(())
+    }
 }
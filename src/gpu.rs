@@ -1,48 +1,206 @@
+use crate::error::Error;
 use iced::futures;
 use iced_wgpu::wgpu;
 use iced_winit::winit;
+use std::sync::Arc;
 
 pub struct Gpu {
-    pub(super) device: wgpu::Device,
+    /// Shared with the thread which compiles pipelines in the background.
+    pub(super) device: Arc<wgpu::Device>,
     pub(super) queue: wgpu::Queue,
     pub(super) texture_format: wgpu::TextureFormat,
+    pub(super) adapter_info: wgpu::AdapterInfo,
+    /// The present modes which the surface supports, or none without a surface.
+    present_modes: Vec<wgpu::PresentMode>,
 }
 
 impl Gpu {
-    pub fn new<'window>(window: &'window winit::window::Window) -> (Self, wgpu::Surface<'window>) {
+    /// Creates a device which can present to the window, preferring an adapter with the given power
+    /// preference unless `WGPU_POWER_PREF` overrides it. With `wide_gamut`, the surface is given a
+    /// floating-point format where it supports one, whose colours may lie beyond sRGB.
+    ///
+    /// On the web, the device is created in advance by [`Gpu::request_webgpu`] where the browser
+    /// supports WebGPU, and otherwise here on WebGL2.
+    pub fn new<'window>(
+        window: &'window winit::window::Window,
+        power_preference: wgpu::PowerPreference,
+        wide_gamut: bool,
+        #[cfg(target_arch = "wasm32")] webgpu: Option<WebGpuDevice>,
+    ) -> Result<(Self, wgpu::Surface<'window>), Error> {
+        #[cfg(target_arch = "wasm32")]
+        if let Some(webgpu) = webgpu {
+            let surface = webgpu.instance.create_surface(window)?;
+            let surface_support =
+                Self::surface_support(Some(&surface), &webgpu.adapter, wide_gamut)?;
+            let gpu = Self::from_parts(
+                webgpu.device,
+                webgpu.queue,
+                surface_support,
+                webgpu.adapter.get_info(),
+            );
+            gpu.configure_surface(&surface, window.inner_size(), wgpu::PresentMode::AutoVsync);
+            return Ok((gpu, surface));
+        }
         let backend = Self::get_backend();
         let instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
             backends: backend,
             ..Default::default()
         });
-        let surface = instance.create_surface(window).unwrap();
-        let (device, queue, texture_format) = Self::create_device(&instance, Some(&surface));
-        let gpu = Self {
-            texture_format,
+        let surface = instance.create_surface(window)?;
+        let (device, queue, surface_support, adapter_info) =
+            Self::create_device(&instance, Some(&surface), power_preference, wide_gamut)?;
+        let gpu = Self::from_parts(device, queue, surface_support, adapter_info);
+        let physical_size = window.inner_size();
+        gpu.configure_surface(&surface, physical_size, wgpu::PresentMode::AutoVsync);
+        Ok((gpu, surface))
+    }
+
+    /// Requests a WebGPU adapter and device, or returns `None` if the browser lacks WebGPU or has
+    /// no adapter for it, in which case [`Gpu::new`] falls back to WebGL2.
+    ///
+    /// Unlike WebGL2, WebGPU hands out adapters and devices asynchronously, which the page cannot
+    /// block on. They are also requested before the window exists, since a canvas which has once
+    /// given out a WebGPU context cannot give out a WebGL2 context any more.
+    #[cfg(target_arch = "wasm32")]
+    pub async fn request_webgpu(power_preference: wgpu::PowerPreference) -> Option<WebGpuDevice> {
+        let navigator = web_sys::window()?.navigator();
+        let has_webgpu =
+            js_sys::Reflect::get(&navigator, &"gpu".into()).is_ok_and(|gpu| !gpu.is_undefined());
+        if !has_webgpu {
+            log::info!("WebGPU is not supported by the browser, using WebGL2");
+            return None;
+        }
+        let instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
+            backends: wgpu::Backends::BROWSER_WEBGPU,
+            ..Default::default()
+        });
+        let Some(adapter) = instance
+            .request_adapter(&wgpu::RequestAdapterOptions {
+                power_preference,
+                force_fallback_adapter: false,
+                compatible_surface: None,
+            })
+            .await
+        else {
+            log::info!("No WebGPU adapter is available, using WebGL2");
+            return None;
+        };
+        let (device, queue) = Self::request_device(&adapter)
+            .await
+            .inspect_err(|error| log::warn!("{error}, using WebGL2"))
+            .ok()?;
+        Some(WebGpuDevice {
+            instance,
+            adapter,
             device,
             queue,
-        };
-        let physical_size = window.inner_size();
-        gpu.configure_surface(&surface, physical_size);
-        (gpu, surface)
+        })
     }
 
-    #[cfg(test)]
-    pub fn new_without_surface() -> Self {
+    pub fn new_without_surface() -> Result<Self, Error> {
         let backend = Self::get_backend();
         let instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
             backends: backend,
             ..Default::default()
         });
-        let (device, queue, texture_format) = Self::create_device(&instance, None);
-        Self {
+        let (device, queue, surface_support, adapter_info) =
+            Self::create_device(&instance, None, wgpu::PowerPreference::default(), false)?;
+        Ok(Self::from_parts(
+            device,
+            queue,
+            surface_support,
+            adapter_info,
+        ))
+    }
+
+    /// Creates a device for offscreen rendering on each GPU of the machine, so that renders can be
+    /// split across them. Software adapters are only used if there is no GPU, and each GPU is only
+    /// used once even if several backends list it.
+    ///
+    /// On the web, where adapters cannot be listed, this is the one device of
+    /// [`Gpu::new_without_surface`].
+    pub fn all_without_surface() -> Result<Vec<Self>, Error> {
+        #[cfg(target_arch = "wasm32")]
+        return Ok(vec![Self::new_without_surface()?]);
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            let instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
+                backends: Self::get_backend(),
+                ..Default::default()
+            });
+            let adapters = instance.enumerate_adapters(Self::get_backend());
+            let has_gpu = adapters
+                .iter()
+                .any(|adapter| adapter.get_info().device_type != wgpu::DeviceType::Cpu);
+            let mut gpus: Vec<Self> = vec![];
+            for adapter in adapters {
+                let info = adapter.get_info();
+                let is_listed = gpus.iter().any(|gpu| {
+                    (
+                        gpu.adapter_info.vendor,
+                        gpu.adapter_info.device,
+                        &gpu.adapter_info.name,
+                    ) == (info.vendor, info.device, &info.name)
+                });
+                if is_listed || (has_gpu && info.device_type == wgpu::DeviceType::Cpu) {
+                    continue;
+                }
+                let (device, queue) = futures::executor::block_on(Self::request_device(&adapter))?;
+                let surface_support = Self::surface_support(None, &adapter, false)?;
+                gpus.push(Self::from_parts(device, queue, surface_support, info));
+            }
+            if gpus.is_empty() {
+                return Err(Error::NoAdapter);
+            }
+            Ok(gpus)
+        }
+    }
+
+    fn from_parts(
+        device: Arc<wgpu::Device>,
+        queue: wgpu::Queue,
+        SurfaceSupport {
             texture_format,
+            present_modes,
+        }: SurfaceSupport,
+        adapter_info: wgpu::AdapterInfo,
+    ) -> Self {
+        Self {
             device,
             queue,
+            texture_format,
+            adapter_info,
+            present_modes,
         }
     }
 
-    pub fn configure_surface(&self, surface: &wgpu::Surface, size: winit::dpi::PhysicalSize<u32>) {
+    /// Whether the texture format stores colours in linear light, which the GPU encodes for display,
+    /// rather than colours already encoded in sRGB. The colouring of the fractal outputs whichever
+    /// the format stores.
+    pub(super) fn stores_linear_light(&self) -> bool {
+        stores_linear_light(self.texture_format)
+    }
+
+    /// Configures the surface for the given size, falling back to vsync if the surface does not
+    /// support the requested present mode.
+    ///
+    /// Surfaces cannot be empty, so the surface is left as it is while the window has no area, as
+    /// when it is minimized or, on the web, before the canvas has been laid out.
+    pub fn configure_surface(
+        &self,
+        surface: &wgpu::Surface,
+        size: winit::dpi::PhysicalSize<u32>,
+        present_mode: wgpu::PresentMode,
+    ) {
+        if size.width == 0 || size.height == 0 {
+            return;
+        }
+        let present_mode = if self.present_modes.contains(&present_mode) {
+            present_mode
+        } else {
+            tracing::warn!("Present mode {present_mode:?} is not supported, using vsync");
+            wgpu::PresentMode::AutoVsync
+        };
         surface.configure(
             &self.device,
             &wgpu::SurfaceConfiguration {
@@ -50,7 +208,7 @@ impl Gpu {
                 format: self.texture_format,
                 width: size.width,
                 height: size.height,
-                present_mode: wgpu::PresentMode::AutoVsync,
+                present_mode,
                 alpha_mode: wgpu::CompositeAlphaMode::Auto,
                 view_formats: vec![],
                 desired_maximum_frame_latency: 2,
@@ -70,44 +228,158 @@ impl Gpu {
     fn create_device(
         instance: &wgpu::Instance,
         surface: Option<&wgpu::Surface>,
-    ) -> (wgpu::Device, wgpu::Queue, wgpu::TextureFormat) {
-        let ((device, queue), texture_format) = futures::executor::block_on(async {
-            let adapter = wgpu::util::initialize_adapter_from_env_or_default(instance, surface)
-                .await
-                .expect("No suitable GPU adapters found on the system!");
+        power_preference: wgpu::PowerPreference,
+        wide_gamut: bool,
+    ) -> Result<
+        (
+            Arc<wgpu::Device>,
+            wgpu::Queue,
+            SurfaceSupport,
+            wgpu::AdapterInfo,
+        ),
+        Error,
+    > {
+        futures::executor::block_on(async {
+            let adapter = match wgpu::util::initialize_adapter_from_env(instance, surface) {
+                Some(adapter) => adapter,
+                None => instance
+                    .request_adapter(&wgpu::RequestAdapterOptions {
+                        power_preference: wgpu::util::power_preference_from_env()
+                            .unwrap_or(power_preference),
+                        force_fallback_adapter: false,
+                        compatible_surface: surface,
+                    })
+                    .await
+                    .ok_or(Error::NoAdapter)?,
+            };
 
-            let adapter_features = adapter.features();
+            let (device, queue) = Self::request_device(&adapter).await?;
+            let surface_support = Self::surface_support(surface, &adapter, wide_gamut)?;
+            Ok((device, queue, surface_support, adapter.get_info()))
+        })
+    }
 
-            let needed_limits = if cfg!(target_arch = "wasm32") {
+    /// Requests a device with the limits which the backend of the adapter can meet. Browsers only
+    /// guarantee the limits of WebGL2 and WebGPU respectively, though adapters may well offer
+    /// larger textures.
+    async fn request_device(
+        adapter: &wgpu::Adapter,
+    ) -> Result<(Arc<wgpu::Device>, wgpu::Queue), Error> {
+        let required_limits = match adapter.get_info().backend {
+            wgpu::Backend::BrowserWebGpu => {
+                wgpu::Limits::downlevel_defaults().using_resolution(adapter.limits())
+            }
+            wgpu::Backend::Gl if cfg!(target_arch = "wasm32") => {
                 wgpu::Limits::downlevel_webgl2_defaults().using_resolution(adapter.limits())
-            } else {
-                wgpu::Limits::default()
-            };
+            }
+            _ => wgpu::Limits::default(),
+        };
+        let (device, queue) = adapter
+            .request_device(
+                &wgpu::DeviceDescriptor {
+                    label: None,
+                    required_features: adapter.features() & wgpu::Features::default(),
+                    required_limits,
+                },
+                None,
+            )
+            .await?;
+        Ok((Arc::new(device), queue))
+    }
+
+    fn surface_support(
+        surface: Option<&wgpu::Surface>,
+        adapter: &wgpu::Adapter,
+        wide_gamut: bool,
+    ) -> Result<SurfaceSupport, Error> {
+        let Some(surface) = surface else {
+            return Ok(SurfaceSupport {
+                texture_format: wgpu::TextureFormat::Rgba8Unorm,
+                present_modes: vec![],
+            });
+        };
+        let capabilities = surface.get_capabilities(adapter);
+        let texture_format = choose_surface_format(&capabilities.formats, wide_gamut)
+            .ok_or(Error::IncompatibleSurface)?;
+        tracing::info!("Rendering to the surface in {texture_format:?}");
+        // The automatic modes fall back to whatever the surface supports.
+        let mut present_modes = capabilities.present_modes;
+        present_modes.extend([wgpu::PresentMode::AutoVsync, wgpu::PresentMode::AutoNoVsync]);
+        Ok(SurfaceSupport {
+            texture_format,
+            present_modes,
+        })
+    }
+}
+
+/// A WebGPU device requested ahead of the window by [`Gpu::request_webgpu`].
+#[cfg(target_arch = "wasm32")]
+pub struct WebGpuDevice {
+    instance: wgpu::Instance,
+    adapter: wgpu::Adapter,
+    device: Arc<wgpu::Device>,
+    queue: wgpu::Queue,
+}
 
+/// Chooses the format in which to render to a surface which supports the given formats, listed in
+/// the order in which the surface prefers them.
+///
+/// An sRGB format is preferred, since the renderer of the controls writes linear light, as does
+/// the colouring of the fractal onto it. Surfaces such as those of browsers list none, onto which
+/// both write colours encoded in sRGB instead. With `wide_gamut`, a floating-point format, which
+/// also stores linear light, is preferred over both.
+fn choose_surface_format(
+    formats: &[wgpu::TextureFormat],
+    wide_gamut: bool,
+) -> Option<wgpu::TextureFormat> {
+    let wide = formats
+        .iter()
+        .find(|format| wide_gamut && **format == wgpu::TextureFormat::Rgba16Float);
+    wide.or_else(|| formats.iter().find(|format| format.is_srgb()))
+        .or(formats.first())
+        .copied()
+}
+
+fn stores_linear_light(format: wgpu::TextureFormat) -> bool {
+    format.is_srgb() || format == wgpu::TextureFormat::Rgba16Float
+}
+
+/// The format in which to render to the surface and the present modes which it supports.
+struct SurfaceSupport {
+    texture_format: wgpu::TextureFormat,
+    present_modes: Vec<wgpu::PresentMode>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{choose_surface_format, stores_linear_light};
+    use googletest::prelude::*;
+    use iced_wgpu::wgpu::TextureFormat;
+
+    const FORMATS: [TextureFormat; 3] = [
+        TextureFormat::Bgra8Unorm,
+        TextureFormat::Bgra8UnormSrgb,
+        TextureFormat::Rgba16Float,
+    ];
+
+    #[test]
+    fn surface_format_is_srgb_unless_wide_gamut_is_requested() -> Result<()> {
+        verify_that!(
             (
-                adapter
-                    .request_device(
-                        &wgpu::DeviceDescriptor {
-                            label: None,
-                            required_features: adapter_features & wgpu::Features::default(),
-                            required_limits: needed_limits,
-                        },
-                        None,
-                    )
-                    .await
-                    .expect("Request device"),
-                if let Some(surface) = surface {
-                    surface
-                        .get_capabilities(&adapter)
-                        .formats
-                        .first()
-                        .copied()
-                        .expect("Get preferred format")
-                } else {
-                    wgpu::TextureFormat::Rgba8Unorm
-                },
+                choose_surface_format(&FORMATS, false),
+                choose_surface_format(&FORMATS, true),
+                choose_surface_format(&FORMATS[..1], true)
+            ),
+            (
+                some(eq(TextureFormat::Bgra8UnormSrgb)),
+                some(eq(TextureFormat::Rgba16Float)),
+                some(eq(TextureFormat::Bgra8Unorm))
             )
-        });
-        (device, queue, texture_format)
+        )
+    }
+
+    #[test]
+    fn only_srgb_and_float_formats_store_linear_light() -> Result<()> {
+        verify_that!(FORMATS.map(stores_linear_light), eq([false, true, true]))
     }
 }
@@ -0,0 +1,79 @@
+use iced_wgpu::wgpu;
+
+const MIB: u64 = 1 << 20;
+
+/// How much GPU memory the view may hold on to before it evicts what it can render again, such as
+/// cached thumbnails. wgpu does not tell how much memory the GPU has, so this is a conservative
+/// share of what integrated GPUs set aside.
+pub(super) const GPU_MEMORY_BUDGET: u64 = 512 * MIB;
+
+/// The approximate size in bytes of the textures and buffers which the view holds on to between
+/// frames, by what they serve.
+///
+/// Drivers pad and align allocations as they see fit, so the sizes are those of the pixels and
+/// elements alone. Export targets are left out, since they are freed as soon as the export is
+/// read back.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(super) struct GpuMemoryUsage {
+    pub(super) iteration_data: u64,
+    /// The intermediate textures of anti-aliasing, bloom and low-power mode, and the hits of the
+    /// chaos game.
+    pub(super) render_targets: u64,
+    pub(super) thumbnails: u64,
+    pub(super) reference_orbit: u64,
+}
+
+impl GpuMemoryUsage {
+    pub(super) fn total(&self) -> u64 {
+        self.iteration_data + self.render_targets + self.thumbnails + self.reference_orbit
+    }
+
+    /// The total in whole mebibytes, which is as finely as it is reported.
+    pub(super) fn total_mib(&self) -> u64 {
+        self.total().div_ceil(MIB)
+    }
+}
+
+/// The size in bytes of the pixels of a texture with the given size and format.
+pub(super) fn texture_bytes(size: wgpu::Extent3d, format: wgpu::TextureFormat) -> u64 {
+    let bytes_per_pixel = format.block_copy_size(None).unwrap_or(0);
+    u64::from(size.width)
+        * u64::from(size.height)
+        * u64::from(size.depth_or_array_layers)
+        * u64::from(bytes_per_pixel)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{texture_bytes, GpuMemoryUsage, MIB};
+    use googletest::prelude::*;
+    use iced_wgpu::wgpu;
+
+    #[test]
+    fn texture_bytes_follow_format() -> Result<()> {
+        let size = wgpu::Extent3d {
+            width: 100,
+            height: 50,
+            depth_or_array_layers: 1,
+        };
+
+        verify_that!(
+            (
+                texture_bytes(size, wgpu::TextureFormat::Rgba8Unorm),
+                texture_bytes(size, wgpu::TextureFormat::Rgba32Float)
+            ),
+            (eq(20_000), eq(80_000))
+        )
+    }
+
+    #[test]
+    fn total_is_rounded_up_to_whole_mebibytes() -> Result<()> {
+        let usage = GpuMemoryUsage {
+            iteration_data: 2 * MIB,
+            thumbnails: 1,
+            ..GpuMemoryUsage::default()
+        };
+
+        verify_that!(usage.total_mib(), eq(3))
+    }
+}
@@ -0,0 +1,210 @@
+use crate::bookmark::Bookmark;
+use iced::time::Instant;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// Changes which follow the last one within this time replace it, so that dragging the view or a
+/// slider records where it stopped rather than every frame on the way.
+const MERGE_INTERVAL: Duration = Duration::from_millis(500);
+
+/// How many changes are kept before the oldest is discarded.
+const MAX_ENTRIES: usize = 1000;
+
+/// How long a replay takes to morph from one change to the next.
+const REPLAY_STEP_SECONDS: f32 = 1.0;
+
+/// The state of the view after a change, and when it was made.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub(super) struct HistoryEntry {
+    /// The time of the change in seconds since the explorer started.
+    pub(super) seconds: f32,
+    pub(super) bookmark: Bookmark,
+}
+
+/// The log of every change to the location, fractal, parameters and colouring of the view in this
+/// session, which can be browsed, replayed as a tour and exported.
+pub(super) struct History {
+    started: Instant,
+    entries: Vec<HistoryEntry>,
+    /// The state of the view when last observed or skipped.
+    seen: Option<Bookmark>,
+    /// When the last entry was recorded, or `None` if the next change must not be merged into it.
+    last_recorded: Option<Instant>,
+}
+
+impl History {
+    pub(super) fn new(started: Instant) -> Self {
+        Self {
+            started,
+            entries: vec![],
+            seen: None,
+            last_recorded: None,
+        }
+    }
+
+    pub(super) fn entries(&self) -> &[HistoryEntry] {
+        &self.entries
+    }
+
+    /// Records the state of the view if it changed since it was last seen, replacing the last
+    /// entry if that was recorded less than [`MERGE_INTERVAL`] ago. Returns whether it was
+    /// recorded.
+    pub(super) fn observe(&mut self, bookmark: &Bookmark, now: Instant) -> bool {
+        if self.seen.as_ref() == Some(bookmark) {
+            return false;
+        }
+        self.seen = Some(bookmark.clone());
+        let entry = HistoryEntry {
+            seconds: (now - self.started).as_secs_f32(),
+            bookmark: bookmark.clone(),
+        };
+        let merge = self
+            .last_recorded
+            .is_some_and(|last_recorded| now < last_recorded + MERGE_INTERVAL);
+        match self.entries.last_mut() {
+            Some(last) if merge => *last = entry,
+            _ => self.entries.push(entry),
+        }
+        if self.entries.len() > MAX_ENTRIES {
+            self.entries.remove(0);
+        }
+        self.last_recorded = Some(now);
+        true
+    }
+
+    /// Takes the state of the view as seen without recording it, as when it was taken from the
+    /// history itself.
+    pub(super) fn skip(&mut self, bookmark: &Bookmark) {
+        self.seen = Some(bookmark.clone());
+        self.last_recorded = None;
+    }
+
+    /// Where a replay of the history is the given time after it started: the index of the entry
+    /// from which it morphs and how far it is on the way to the next, or `None` once it has
+    /// reached the last entry.
+    pub(super) fn replay_at(&self, seconds: f32) -> Option<(usize, f32)> {
+        let steps = seconds / REPLAY_STEP_SECONDS;
+        let index = steps as usize;
+        (index + 1 < self.entries.len()).then(|| (index, steps.fract()))
+    }
+
+    /// The entries as JSON, in the order in which they were recorded.
+    pub(super) fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string_pretty(&self.entries)
+    }
+}
+
+/// Asks the user where to export the history, returning `None` if they cancel.
+#[cfg(not(target_arch = "wasm32"))]
+pub(super) fn choose_export_path() -> Option<std::path::PathBuf> {
+    native_dialog::FileDialog::new()
+        .set_title("Export history")
+        .set_filename("history.json")
+        .add_filter("JSON", &["json"])
+        .show_save_single_file()
+        .ok()
+        .flatten()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{History, HistoryEntry, MERGE_INTERVAL, REPLAY_STEP_SECONDS};
+    use crate::bookmark::Bookmark;
+    use cgmath::Vector2;
+    use googletest::prelude::*;
+    use iced::time::Instant;
+    use std::time::Duration;
+
+    fn bookmark(half_width: f32) -> Bookmark {
+        Bookmark::for_test(Vector2::new(0.0, 0.0), half_width)
+    }
+
+    #[test]
+    fn records_changes_with_their_times() -> Result<()> {
+        let start = Instant::now();
+        let mut history = History::new(start);
+
+        let first = history.observe(&bookmark(2.0), start);
+        let unchanged = history.observe(&bookmark(2.0), start + Duration::from_secs(1));
+        let second = history.observe(&bookmark(1.0), start + Duration::from_secs(2));
+
+        verify_that!((first, unchanged, second), (eq(true), eq(false), eq(true)))?;
+        verify_that!(
+            history.entries(),
+            eq(&[
+                HistoryEntry {
+                    seconds: 0.0,
+                    bookmark: bookmark(2.0)
+                },
+                HistoryEntry {
+                    seconds: 2.0,
+                    bookmark: bookmark(1.0)
+                }
+            ][..])
+        )
+    }
+
+    #[test]
+    fn merges_changes_in_quick_succession() -> Result<()> {
+        let start = Instant::now();
+        let mut history = History::new(start);
+        history.observe(&bookmark(2.0), start);
+
+        let later = start + MERGE_INTERVAL * 2;
+        history.observe(&bookmark(1.5), later);
+        history.observe(&bookmark(1.0), later + MERGE_INTERVAL / 2);
+
+        verify_that!(
+            history
+                .entries()
+                .iter()
+                .map(|entry| entry.bookmark.view_transform.x.x)
+                .collect::<Vec<_>>(),
+            eq(vec![2.0, 1.0])
+        )
+    }
+
+    #[test]
+    fn skipped_states_are_not_recorded() -> Result<()> {
+        let start = Instant::now();
+        let mut history = History::new(start);
+        history.observe(&bookmark(2.0), start);
+
+        history.skip(&bookmark(1.0));
+        let recorded = history.observe(&bookmark(1.0), start + MERGE_INTERVAL / 2);
+
+        verify_that!((recorded, history.entries().len()), (eq(false), eq(1)))
+    }
+
+    #[test]
+    fn replay_morphs_through_entries_in_turn() -> Result<()> {
+        let start = Instant::now();
+        let mut history = History::new(start);
+        for (index, half_width) in [2.0, 1.0, 0.5].into_iter().enumerate() {
+            history.observe(
+                &bookmark(half_width),
+                start + MERGE_INTERVAL * 2 * index as u32,
+            );
+        }
+
+        verify_that!(
+            (
+                history.replay_at(0.25 * REPLAY_STEP_SECONDS),
+                history.replay_at(1.5 * REPLAY_STEP_SECONDS),
+                history.replay_at(2.0 * REPLAY_STEP_SECONDS)
+            ),
+            (some(eq((0, 0.25))), some(eq((1, 0.5))), none())
+        )
+    }
+
+    #[test]
+    fn exports_entries_as_json() -> Result<()> {
+        let start = Instant::now();
+        let mut history = History::new(start);
+        history.observe(&bookmark(2.0), start);
+
+        let exported: Vec<HistoryEntry> = serde_json::from_str(&history.to_json()?)?;
+
+        verify_that!(exported, eq(history.entries().to_vec()))
+    }
+}
@@ -0,0 +1,58 @@
+use iced_winit::winit::window::Icon;
+
+/// The width and height of the window icon in pixels.
+const ICON_SIZE: u32 = 64;
+
+const ICON_ITERATIONS: u32 = 64;
+
+/// Creates the window icon, which shows the Mandelbrot set rendered on the CPU with the points
+/// outside it fading to transparent.
+pub(super) fn create_icon() -> Icon {
+    Icon::from_rgba(icon_pixels(ICON_SIZE), ICON_SIZE, ICON_SIZE)
+        .expect("Icon has the wrong number of pixels")
+}
+
+fn icon_pixels(size: u32) -> Vec<u8> {
+    let mut pixels = Vec::with_capacity((size * size * 4) as usize);
+    for y in 0..size {
+        for x in 0..size {
+            // The set spans [-2, 0.5] along the real axis, so it is centred a little to the left.
+            let c_re = (x as f32 + 0.5) / size as f32 * 2.6 - 2.05;
+            let c_im = (y as f32 + 0.5) / size as f32 * 2.6 - 1.3;
+            let alpha = match escape_time(c_re, c_im) {
+                Some(iterations) => {
+                    (255.0 * (iterations as f32 / ICON_ITERATIONS as f32).sqrt()) as u8
+                }
+                None => 255,
+            };
+            pixels.extend_from_slice(&[40, 90, 200, alpha]);
+        }
+    }
+    pixels
+}
+
+fn escape_time(c_re: f32, c_im: f32) -> Option<u32> {
+    let (mut z_re, mut z_im) = (0.0f32, 0.0f32);
+    for iteration in 0..ICON_ITERATIONS {
+        if z_re * z_re + z_im * z_im > 4.0 {
+            return Some(iteration);
+        }
+        (z_re, z_im) = (z_re * z_re - z_im * z_im + c_re, 2.0 * z_re * z_im + c_im);
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::icon_pixels;
+    use googletest::prelude::*;
+
+    #[test]
+    fn icon_is_opaque_inside_set_and_transparent_far_outside() -> Result<()> {
+        let pixels = icon_pixels(16);
+        let alpha = |x: usize, y: usize| pixels[4 * (y * 16 + x) + 3];
+
+        // (-0.25, 0) lies in the main cardioid, and the corners lie far outside the set.
+        verify_that!((alpha(11, 8), alpha(0, 0)), (eq(255), lt(64)))
+    }
+}
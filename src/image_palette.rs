@@ -0,0 +1,197 @@
+use crate::{
+    colour_palette::{CustomPalette, GRADIENT_STOPS},
+    oklab::Oklab,
+};
+use std::path::Path;
+
+/// The most pixels which are clustered, taken evenly from the image, which is plenty to find its
+/// main colours.
+const MAX_SAMPLES: usize = 16384;
+/// The most rounds of k-means, which usually settles in far fewer.
+const MAX_ROUNDS: usize = 32;
+/// Pixels more transparent than this are left out, e.g. the background of a cut-out.
+const MIN_ALPHA: u8 = 128;
+
+/// Extracts a palette from the image by clustering its colours in OKLab with k-means, in which
+/// the clusters settle where the colours which make up most of the image are.
+///
+/// The centres of the [`GRADIENT_STOPS`] clusters, ordered from dark to light, form the gradient,
+/// so that the interior takes the darkest colour. The roots of the Newton fractal take the
+/// colours of the three largest clusters. Returns `None` if the image has no opaque pixels.
+pub(super) fn extract(image: &image::RgbaImage) -> Option<CustomPalette> {
+    let opaque: Vec<Oklab> = image
+        .pixels()
+        .filter(|pixel| pixel[3] >= MIN_ALPHA)
+        .map(|pixel| Oklab::from_srgb8([pixel[0], pixel[1], pixel[2]]))
+        .collect();
+    if opaque.is_empty() {
+        return None;
+    }
+    let stride = opaque.len().div_ceil(MAX_SAMPLES);
+    let samples: Vec<Oklab> = opaque.into_iter().step_by(stride).collect();
+    let clusters = k_means(&samples, GRADIENT_STOPS);
+
+    let mut by_lightness = clusters.clone();
+    by_lightness.sort_by(|a, b| a.centre.l.total_cmp(&b.centre.l));
+    let mut by_size = clusters;
+    by_size.sort_by_key(|cluster| std::cmp::Reverse(cluster.size));
+    Some(CustomPalette {
+        gradient: std::array::from_fn(|stop| by_lightness[stop].centre.to_srgb8()),
+        roots: std::array::from_fn(|root| by_size[root].centre.to_srgb8()),
+    })
+}
+
+/// Loads the image and extracts a palette from it as [`extract`] does, describing why if it
+/// cannot.
+pub(super) fn load(path: &Path) -> Result<CustomPalette, String> {
+    let image = image::open(path)
+        .map_err(|error| error.to_string())?
+        .into_rgba8();
+    extract(&image).ok_or_else(|| "The image has no opaque pixels".into())
+}
+
+/// Asks the user for an image from which to extract a palette, opening the dialog in the given
+/// directory if there is one, and returns `None` if they cancel.
+#[cfg(not(target_arch = "wasm32"))]
+pub(super) fn choose_image(directory: Option<&std::path::Path>) -> Option<std::path::PathBuf> {
+    let mut dialog = native_dialog::FileDialog::new()
+        .set_title("Palette image")
+        .add_filter("PNG image", &["png"]);
+    if let Some(directory) = directory {
+        dialog = dialog.set_location(directory);
+    }
+    dialog.show_open_single_file().ok().flatten()
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Cluster {
+    centre: Oklab,
+    /// The number of samples closest to the centre.
+    size: usize,
+}
+
+/// Partitions the samples into `k` clusters with Lloyd's algorithm.
+///
+/// The clusters start at the samples at evenly spaced quantiles of lightness, which spreads them
+/// over the image's range of colours without randomness, so that an image always gives the same
+/// palette. A cluster which loses all its samples keeps its centre, so that an image with fewer
+/// than `k` colours repeats some of them.
+fn k_means(samples: &[Oklab], k: usize) -> Vec<Cluster> {
+    let mut sorted = samples.to_vec();
+    sorted.sort_by(|a, b| a.l.total_cmp(&b.l));
+    let mut clusters: Vec<Cluster> = (0..k)
+        .map(|i| Cluster {
+            centre: sorted[(2 * i + 1) * sorted.len() / (2 * k)],
+            size: 0,
+        })
+        .collect();
+    let mut assignments = vec![usize::MAX; samples.len()];
+    for _ in 0..MAX_ROUNDS {
+        let mut changed = false;
+        for (sample, assignment) in samples.iter().zip(&mut assignments) {
+            let nearest = nearest(&clusters, *sample);
+            changed |= *assignment != nearest;
+            *assignment = nearest;
+        }
+        let mut sums = vec![
+            (
+                Oklab {
+                    l: 0.0,
+                    a: 0.0,
+                    b: 0.0
+                },
+                0
+            );
+            k
+        ];
+        for (sample, &assignment) in samples.iter().zip(&assignments) {
+            let (sum, count) = &mut sums[assignment];
+            sum.l += sample.l;
+            sum.a += sample.a;
+            sum.b += sample.b;
+            *count += 1;
+        }
+        for (cluster, (sum, count)) in clusters.iter_mut().zip(sums) {
+            cluster.size = count;
+            if count > 0 {
+                let count = count as f32;
+                cluster.centre = Oklab {
+                    l: sum.l / count,
+                    a: sum.a / count,
+                    b: sum.b / count,
+                };
+            }
+        }
+        if !changed {
+            break;
+        }
+    }
+    clusters
+}
+
+fn nearest(clusters: &[Cluster], sample: Oklab) -> usize {
+    clusters
+        .iter()
+        .enumerate()
+        .min_by(|(_, a), (_, b)| {
+            a.centre
+                .distance_squared(sample)
+                .total_cmp(&b.centre.distance_squared(sample))
+        })
+        .map_or(0, |(index, _)| index)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::extract;
+    use googletest::prelude::*;
+
+    /// An image of which each colour covers the given number of pixels.
+    fn image(colours: &[([u8; 3], u32)]) -> image::RgbaImage {
+        let pixels: Vec<_> = colours
+            .iter()
+            .flat_map(|&([r, g, b], count)| (0..count).map(move |_| image::Rgba([r, g, b, 255])))
+            .collect();
+        let mut image = image::RgbaImage::new(pixels.len() as u32, 1);
+        for (x, pixel) in pixels.into_iter().enumerate() {
+            image.put_pixel(x as u32, 0, pixel);
+        }
+        image
+    }
+
+    #[test]
+    fn gradient_runs_from_darkest_to_lightest_colour() -> Result<()> {
+        let colours: Vec<([u8; 3], u32)> = (0..8).map(|i| ([i * 30 + 10; 3], 5)).collect();
+
+        let palette = extract(&image(&colours)).unwrap();
+
+        verify_that!(
+            palette.gradient.to_vec(),
+            eq(colours
+                .iter()
+                .map(|(colour, _)| *colour)
+                .collect::<Vec<_>>())
+        )
+    }
+
+    #[test]
+    fn roots_take_most_common_colours() -> Result<()> {
+        let palette = extract(&image(&[
+            ([200, 30, 30], 40),
+            ([30, 200, 30], 5),
+            ([30, 30, 200], 20),
+            ([240, 240, 240], 30),
+        ]))
+        .unwrap();
+
+        verify_that!(
+            palette.roots,
+            eq([[200, 30, 30], [240, 240, 240], [30, 30, 200]])
+        )
+    }
+
+    #[test]
+    fn transparent_image_has_no_palette() -> Result<()> {
+        verify_that!(extract(&image::RgbaImage::new(4, 4)), none())
+    }
+}
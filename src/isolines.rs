@@ -0,0 +1,182 @@
+use crate::gpu::Gpu;
+use bytemuck::{Pod, Zeroable};
+use iced_wgpu::wgpu::{self, util::DeviceExt};
+
+/// The largest number of isolines drawn at once. Must match the size of `values` in
+/// `isolines.wgsl`.
+pub(super) const MAX_ISOLINES: usize = 8;
+
+/// Draws the level sets of the escape time of the Mandelbrot set at selected iteration counts
+/// over the rendered fractal, which shows how the bands of colour are structured.
+///
+/// A fragment pass finds the pixels where the escape time in the view's [`IterationData`] crosses
+/// one of the levels, like an edge detector.
+///
+/// [`IterationData`]: crate::iteration_data::IterationData
+pub(super) struct Isolines {
+    levels: Vec<u32>,
+    levels_buffer: wgpu::Buffer,
+    levels_bind_group: wgpu::BindGroup,
+    pipeline: wgpu::RenderPipeline,
+}
+
+/// The contents of the uniform block `levels` in `isolines.wgsl`.
+#[repr(C)]
+#[derive(Clone, Copy, Pod, Zeroable)]
+struct LevelsBlock {
+    values: [f32; MAX_ISOLINES],
+    count: u32,
+    _padding: [u32; 3],
+}
+
+impl Isolines {
+    /// Builds the pass, which reads the iteration data through a bind group with the given
+    /// layout.
+    pub(super) fn new(gpu: &Gpu, iteration_data_bind_group_layout: &wgpu::BindGroupLayout) -> Self {
+        let levels_bind_group_layout =
+            gpu.device
+                .create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                    label: Some("Isoline bind group layout"),
+                    entries: &[wgpu::BindGroupLayoutEntry {
+                        binding: 0,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Uniform,
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    }],
+                });
+        let module = gpu
+            .device
+            .create_shader_module(wgpu::include_wgsl!("shader/isolines.wgsl"));
+        let layout = gpu
+            .device
+            .create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some("Isoline pipeline layout"),
+                bind_group_layouts: &[iteration_data_bind_group_layout, &levels_bind_group_layout],
+                push_constant_ranges: &[],
+            });
+        let pipeline = gpu
+            .device
+            .create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                label: Some("Isoline pipeline"),
+                layout: Some(&layout),
+                vertex: wgpu::VertexState {
+                    module: &module,
+                    entry_point: "fullscreen",
+                    buffers: &[],
+                },
+                fragment: Some(wgpu::FragmentState {
+                    module: &module,
+                    entry_point: "isolines",
+                    targets: &[Some(wgpu::ColorTargetState {
+                        format: gpu.texture_format,
+                        blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                        write_mask: wgpu::ColorWrites::COLOR,
+                    })],
+                }),
+                primitive: wgpu::PrimitiveState::default(),
+                depth_stencil: None,
+                multisample: wgpu::MultisampleState::default(),
+                multiview: None,
+            });
+        let levels_buffer = gpu
+            .device
+            .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("Isoline levels buffer"),
+                contents: bytemuck::bytes_of(&LevelsBlock::zeroed()),
+                usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::UNIFORM,
+            });
+        let levels_bind_group = gpu.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Isoline bind group"),
+            layout: &levels_bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: levels_buffer.as_entire_binding(),
+            }],
+        });
+
+        Self {
+            levels: vec![],
+            levels_buffer,
+            levels_bind_group,
+            pipeline,
+        }
+    }
+
+    /// Sets the iteration counts at which lines are drawn, of which only the first
+    /// [`MAX_ISOLINES`] are used.
+    pub(super) fn set_levels(&mut self, levels: Vec<u32>) {
+        self.levels = levels;
+    }
+
+    /// Uploads the levels to the GPU.
+    pub(super) fn update_levels(&self, queue: &wgpu::Queue) {
+        let mut block = LevelsBlock::zeroed();
+        for (value, level) in block.values.iter_mut().zip(&self.levels) {
+            *value = *level as f32;
+        }
+        block.count = self.levels.len().min(MAX_ISOLINES) as u32;
+        queue.write_buffer(&self.levels_buffer, 0, bytemuck::bytes_of(&block));
+    }
+
+    /// Draws the isolines of the escape times in the given iteration data, which must have the
+    /// size of the target, over the target.
+    pub(super) fn render(
+        &self,
+        target: &wgpu::Texture,
+        iteration_data_bind_group: &wgpu::BindGroup,
+        encoder: &mut wgpu::CommandEncoder,
+    ) {
+        let target = target.create_view(&wgpu::TextureViewDescriptor::default());
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Isoline pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: &target,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Load,
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_bind_group(0, iteration_data_bind_group, &[]);
+        render_pass.set_bind_group(1, &self.levels_bind_group, &[]);
+        render_pass.draw(0..3, 0..1);
+    }
+}
+
+/// Reads the iteration counts of the isolines from a list separated by commas or spaces,
+/// ignoring anything which is not a positive whole number, in ascending order without
+/// duplicates.
+pub(super) fn parse_levels(text: &str) -> Vec<u32> {
+    let mut levels: Vec<u32> = text
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter_map(|level| level.parse().ok())
+        .filter(|level| *level > 0)
+        .collect();
+    levels.sort_unstable();
+    levels.dedup();
+    levels.truncate(MAX_ISOLINES);
+    levels
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_levels;
+    use googletest::prelude::*;
+
+    #[test]
+    fn parses_levels_in_ascending_order_without_junk() -> Result<()> {
+        verify_that!(
+            parse_levels("50, 10 x,20,, 10 -3 0") == [10, 20, 50],
+            eq(true)
+        )
+    }
+}
@@ -0,0 +1,144 @@
+use crate::{
+    analysis::{Analyzer, IterationHistogram, Region, HISTOGRAM_BINS},
+    fractal_view::DEFAULT_MAX_ITERATIONS,
+    gpu::Gpu,
+};
+use cgmath::InnerSpace;
+
+pub(super) const MIN_ITERATIONS: u32 = 100;
+/// The most iterations which the view accepts from any source, so that no setting can keep each
+/// pixel busy for long enough that the driver resets the GPU.
+pub(super) const MAX_ITERATIONS: u32 = 50_000;
+
+/// The most iterations, summed over every pixel, which a single render may take in the worst case
+/// of every pixel lying in the set. Mid-range GPUs get through this in well under the two seconds
+/// after which Windows resets a GPU which stopped responding.
+const MAX_ITERATIONS_PER_RENDER: u64 = 20_000_000_000;
+
+/// The half-width of the initial view, at which the budget is [`DEFAULT_MAX_ITERATIONS`].
+const INITIAL_HALF_WIDTH: f64 = 2.0;
+
+/// How many iterations are added each time the magnification doubles.
+const ITERATIONS_PER_DOUBLING: f64 = 250.0;
+
+/// The histogram bins at the end of the budget which are checked for samples which escaped late.
+const TAIL_BINS: usize = 5;
+
+/// The fraction of escaping samples in the tail bins above which the budget is considered to cut
+/// off detail.
+const MAX_TAIL_FRACTION: f64 = 0.01;
+
+const MAX_DOUBLINGS: u32 = 4;
+const TUNING_RESOLUTION: u32 = 64;
+
+/// Chooses an iteration budget for the region.
+///
+/// The budget starts from the usual heuristic of growing linearly with the logarithm of the
+/// magnification. It is then doubled while the iteration histogram shows that a noticeable part of
+/// the samples only escaped close to the budget, which suggests that others would escape if given
+/// more iterations.
+pub(super) fn auto_tune(analyzer: &Analyzer, gpu: &Gpu, region: Region) -> u32 {
+    let half_width = region.transform.x.truncate().magnitude() as f64;
+    let mut budget = for_half_width(half_width);
+    for _ in 0..MAX_DOUBLINGS {
+        let histogram = analyzer.iteration_histogram(
+            gpu,
+            Region {
+                max_iterations: budget,
+                ..region
+            },
+            TUNING_RESOLUTION,
+        );
+        if !is_truncated(&histogram) || budget == MAX_ITERATIONS {
+            break;
+        }
+        budget = (budget * 2).min(MAX_ITERATIONS);
+    }
+    budget
+}
+
+/// The budget for a view of the given half-width by the magnification alone.
+pub(super) fn for_half_width(half_width: f64) -> u32 {
+    let doublings = (INITIAL_HALF_WIDTH / half_width).log2().max(0.0);
+    let budget = DEFAULT_MAX_ITERATIONS as f64 + ITERATIONS_PER_DOUBLING * doublings;
+    (budget as u32).clamp(MIN_ITERATIONS, MAX_ITERATIONS)
+}
+
+/// Whether rendering an image of the given size with the budget could take long enough that the
+/// driver resets the GPU.
+pub(super) fn is_too_expensive(width: u32, height: u32, max_iterations: u32) -> bool {
+    (u64::from(width) * u64::from(height)).saturating_mul(u64::from(max_iterations))
+        > MAX_ITERATIONS_PER_RENDER
+}
+
+fn is_truncated(histogram: &IterationHistogram) -> bool {
+    let escaped: u32 = histogram.escaped.iter().sum();
+    let tail: u32 = histogram.escaped[HISTOGRAM_BINS - TAIL_BINS..].iter().sum();
+    escaped > 0 && tail as f64 / escaped as f64 > MAX_TAIL_FRACTION
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{for_half_width, is_too_expensive, is_truncated, HISTOGRAM_BINS, MAX_ITERATIONS};
+    use crate::{analysis::IterationHistogram, fractal_view::DEFAULT_MAX_ITERATIONS};
+    use googletest::prelude::*;
+
+    #[test]
+    fn budget_grows_with_magnification() -> Result<()> {
+        verify_that!(
+            (
+                for_half_width(4.0),
+                for_half_width(2.0),
+                for_half_width(2.0 / 1024.0),
+                for_half_width(1e-100)
+            ),
+            (
+                eq(DEFAULT_MAX_ITERATIONS),
+                eq(DEFAULT_MAX_ITERATIONS),
+                eq(DEFAULT_MAX_ITERATIONS + 2500),
+                eq(MAX_ITERATIONS)
+            )
+        )
+    }
+
+    #[test]
+    fn only_huge_renders_at_high_budgets_are_too_expensive() -> Result<()> {
+        verify_that!(
+            (
+                is_too_expensive(3840, 2160, DEFAULT_MAX_ITERATIONS),
+                is_too_expensive(1920, 1080, 5000),
+                is_too_expensive(3840, 2160, MAX_ITERATIONS),
+                is_too_expensive(u32::MAX, u32::MAX, u32::MAX)
+            ),
+            (eq(false), eq(false), eq(true), eq(true))
+        )
+    }
+
+    #[test]
+    fn histogram_with_late_escapes_is_truncated() -> Result<()> {
+        let mut escaped = [100; HISTOGRAM_BINS];
+        escaped[HISTOGRAM_BINS - 1] = 1000;
+
+        verify_that!(
+            is_truncated(&IterationHistogram {
+                escaped,
+                interior: 0
+            }),
+            eq(true)
+        )
+    }
+
+    #[test]
+    fn histogram_with_early_escapes_is_not_truncated() -> Result<()> {
+        let mut escaped = [0; HISTOGRAM_BINS];
+        escaped[0] = 1000;
+
+        verify_that!(
+            is_truncated(&IterationHistogram {
+                escaped,
+                interior: 5000
+            }),
+            eq(false)
+        )
+    }
+}
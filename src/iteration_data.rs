@@ -0,0 +1,327 @@
+use crate::{
+    controls::FractalType, debug_view::DebugView, fractal_definition::MAX_PARAMETERS, gpu_memory,
+    orbit_trap::OrbitTrap, plane::Plane, precision::NumericMode, variation::Variation,
+};
+use cgmath::{Matrix3, Vector2};
+use iced_wgpu::wgpu::{self, util::DeviceExt};
+use std::{
+    cell::{Cell, Ref, RefCell},
+    sync::Arc,
+};
+
+const WORKGROUP_SIZE: u32 = 8;
+
+/// Holds the escape time in the red channel and, for the Mandelbrot set, where the orbit landed in
+/// the orbit trap in the green and blue channels. Two-channel float textures cannot be written by
+/// compute shaders on all backends.
+const ITERATION_DATA_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba32Float;
+pub(super) const BYTES_PER_PIXEL: u32 = 16;
+
+/// Everything on which the iteration data depends, so that it is only computed again when one of
+/// these changes and not when only the colouring does.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(super) struct IterationInputs {
+    pub(super) fractal_type: FractalType,
+    pub(super) transform: Matrix3<f32>,
+    pub(super) max_iterations: u32,
+    pub(super) bailout: f32,
+    pub(super) params: [f32; MAX_PARAMETERS],
+    pub(super) critical_point: Vector2<f32>,
+    pub(super) plane: Plane,
+    pub(super) trap: OrbitTrap,
+    pub(super) variation: Variation,
+    pub(super) centre_residual: Vector2<f32>,
+    pub(super) scale_exponent: i32,
+    pub(super) reference_offset: Vector2<f32>,
+    pub(super) reference_offset_exponent: i32,
+    pub(super) numeric_mode: NumericMode,
+    pub(super) time: f32,
+    pub(super) debug_view: DebugView,
+    /// Whether the statistics of the layers of the colouring are stored, as told by
+    /// [`Compositor::needs_statistics`](crate::compositor::Compositor::needs_statistics).
+    pub(super) layer_statistics: bool,
+}
+
+/// Holds the result of iterating each pixel of the fractal, such as the smooth escape time of the
+/// Mandelbrot set, in a float texture from which a cheap fragment pass then computes the colours.
+///
+/// Iterating is by far the most expensive part of rendering at high iteration budgets, while the
+/// palette, its offset and the like only affect the second pass. The data is therefore only
+/// computed again when the [`IterationInputs`] uploaded with the uniform block or the size of the
+/// target change, so that recolouring is instant.
+pub(super) struct IterationData {
+    device: Arc<wgpu::Device>,
+    storage_bind_group_layout: wgpu::BindGroupLayout,
+    sampled_bind_group_layout: wgpu::BindGroupLayout,
+    /// The inputs which were last written to the uniform block.
+    uploaded: Cell<Option<IterationInputs>>,
+    targets: RefCell<Option<Targets>>,
+}
+
+/// The iteration data for one size of render target.
+struct Targets {
+    size: wgpu::Extent3d,
+    texture: wgpu::Texture,
+    storage_bind_group: wgpu::BindGroup,
+    sampled_bind_group: wgpu::BindGroup,
+    /// The inputs from which the data in the texture was computed, if it has been.
+    computed_from: Option<IterationInputs>,
+}
+
+impl IterationData {
+    pub(super) fn new(device: Arc<wgpu::Device>) -> Self {
+        let storage_bind_group_layout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("Iteration data bind group layout"),
+                entries: &[wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::StorageTexture {
+                        access: wgpu::StorageTextureAccess::WriteOnly,
+                        format: ITERATION_DATA_FORMAT,
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                    },
+                    count: None,
+                }],
+            });
+        let sampled_bind_group_layout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("Sampled iteration data bind group layout"),
+                entries: &[wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                }],
+            });
+        Self {
+            device,
+            storage_bind_group_layout,
+            sampled_bind_group_layout,
+            uploaded: Cell::new(None),
+            targets: RefCell::new(None),
+        }
+    }
+
+    /// The layout of the bind group through which the compute pass writes the data.
+    pub(super) fn storage_bind_group_layout(&self) -> &wgpu::BindGroupLayout {
+        &self.storage_bind_group_layout
+    }
+
+    /// The layout of the bind group through which fragment passes read the data.
+    pub(super) fn sampled_bind_group_layout(&self) -> &wgpu::BindGroupLayout {
+        &self.sampled_bind_group_layout
+    }
+
+    /// Records the inputs which were just written to the uniform block.
+    pub(super) fn set_uploaded(&self, inputs: IterationInputs) {
+        self.uploaded.set(Some(inputs));
+    }
+
+    /// Forgets the computed data, so that the next frame iterates every pixel again.
+    pub(super) fn discard(&self) {
+        if let Some(targets) = &mut *self.targets.borrow_mut() {
+            targets.computed_from = None;
+        }
+    }
+
+    /// Returns whether the data must be computed again for a target of the given size.
+    pub(super) fn is_stale(&self, size: wgpu::Extent3d) -> bool {
+        match &*self.targets.borrow() {
+            Some(targets) if targets.size == size => {
+                targets.computed_from.is_none() || targets.computed_from != self.uploaded.get()
+            }
+            _ => true,
+        }
+    }
+
+    /// Computes the data for a target of the given size with the given pipeline, unless it is
+    /// already up to date, and returns the bind group from which it can be read. Perturbation
+    /// pipelines also take the bind group of the reference orbit.
+    pub(super) fn compute(
+        &self,
+        pipeline: &wgpu::ComputePipeline,
+        uniform_bind_group: &wgpu::BindGroup,
+        reference_orbit_bind_group: Option<&wgpu::BindGroup>,
+        size: wgpu::Extent3d,
+        encoder: &mut wgpu::CommandEncoder,
+    ) -> Ref<'_, wgpu::BindGroup> {
+        if self.is_stale(size) {
+            let mut targets = self.targets.borrow_mut();
+            let targets = match &mut *targets {
+                Some(targets) if targets.size == size => targets,
+                targets => targets.insert(self.create_targets(size)),
+            };
+            let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                label: Some("Iteration data pass"),
+                timestamp_writes: None,
+            });
+            compute_pass.set_pipeline(pipeline);
+            compute_pass.set_bind_group(0, uniform_bind_group, &[]);
+            compute_pass.set_bind_group(1, &targets.storage_bind_group, &[]);
+            if let Some(reference_orbit_bind_group) = reference_orbit_bind_group {
+                compute_pass.set_bind_group(2, reference_orbit_bind_group, &[]);
+            }
+            compute_pass.dispatch_workgroups(
+                size.width.div_ceil(WORKGROUP_SIZE),
+                size.height.div_ceil(WORKGROUP_SIZE),
+                1,
+            );
+            targets.computed_from = self.uploaded.get();
+        }
+        Ref::map(self.targets.borrow(), |targets| {
+            &targets
+                .as_ref()
+                .expect("Targets are created above")
+                .sampled_bind_group
+        })
+    }
+
+    /// The size in bytes of the texture holding the data, if one has been created.
+    pub(super) fn gpu_memory(&self) -> u64 {
+        self.targets.borrow().as_ref().map_or(0, |targets| {
+            gpu_memory::texture_bytes(targets.size, ITERATION_DATA_FORMAT)
+        })
+    }
+
+    /// Returns the bind group from which the data can be read, if it is up to date for a target
+    /// of the given size.
+    pub(super) fn current(&self, size: wgpu::Extent3d) -> Option<Ref<'_, wgpu::BindGroup>> {
+        if self.is_stale(size) {
+            return None;
+        }
+        Some(Ref::map(self.targets.borrow(), |targets| {
+            &targets
+                .as_ref()
+                .expect("Data is only current once computed")
+                .sampled_bind_group
+        }))
+    }
+
+    /// The size of the target for which the data is up to date, if it is.
+    pub(super) fn current_size(&self) -> Option<wgpu::Extent3d> {
+        let size = self.targets.borrow().as_ref()?.size;
+        (!self.is_stale(size)).then_some(size)
+    }
+
+    /// Copies the data, if it is up to date, into a buffer which can be mapped for reading, with
+    /// each row padded to [`padded_bytes_per_row`].
+    pub(super) fn read_back(&self, encoder: &mut wgpu::CommandEncoder) -> Option<wgpu::Buffer> {
+        let size = self.current_size()?;
+        let targets = self.targets.borrow();
+        let texture = &targets.as_ref()?.texture;
+        let buffer = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Iteration data readback buffer"),
+            size: u64::from(padded_bytes_per_row(size.width) * size.height),
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        encoder.copy_texture_to_buffer(
+            texture.as_image_copy(),
+            wgpu::ImageCopyBuffer {
+                buffer: &buffer,
+                layout: wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(padded_bytes_per_row(size.width)),
+                    rows_per_image: None,
+                },
+            },
+            size,
+        );
+        Some(buffer)
+    }
+
+    /// Replaces the data for a target of the given size with tightly packed data which was read
+    /// back earlier from the inputs which were just uploaded.
+    pub(super) fn restore(
+        &self,
+        size: wgpu::Extent3d,
+        data: &[u8],
+        encoder: &mut wgpu::CommandEncoder,
+    ) {
+        let unpadded_bytes_per_row = (size.width * BYTES_PER_PIXEL) as usize;
+        let padded_bytes_per_row = padded_bytes_per_row(size.width);
+        let mut padded = vec![0; padded_bytes_per_row as usize * size.height as usize];
+        for (padded_row, row) in padded
+            .chunks_exact_mut(padded_bytes_per_row as usize)
+            .zip(data.chunks_exact(unpadded_bytes_per_row))
+        {
+            padded_row[..unpadded_bytes_per_row].copy_from_slice(row);
+        }
+        let buffer = self
+            .device
+            .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("Restored iteration data"),
+                contents: &padded,
+                usage: wgpu::BufferUsages::COPY_SRC,
+            });
+        let mut targets = self.targets.borrow_mut();
+        let targets = match &mut *targets {
+            Some(targets) if targets.size == size => targets,
+            targets => targets.insert(self.create_targets(size)),
+        };
+        encoder.copy_buffer_to_texture(
+            wgpu::ImageCopyBuffer {
+                buffer: &buffer,
+                layout: wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(padded_bytes_per_row),
+                    rows_per_image: None,
+                },
+            },
+            targets.texture.as_image_copy(),
+            size,
+        );
+        targets.computed_from = self.uploaded.get();
+    }
+
+    fn create_targets(&self, size: wgpu::Extent3d) -> Targets {
+        let texture = self.device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Iteration data"),
+            size,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: ITERATION_DATA_FORMAT,
+            usage: wgpu::TextureUsages::STORAGE_BINDING
+                | wgpu::TextureUsages::TEXTURE_BINDING
+                | wgpu::TextureUsages::COPY_SRC
+                | wgpu::TextureUsages::COPY_DST,
+            view_formats: &[],
+        });
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let create_bind_group = |label, layout| {
+            self.device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some(label),
+                layout,
+                entries: &[wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&view),
+                }],
+            })
+        };
+        Targets {
+            size,
+            texture,
+            storage_bind_group: create_bind_group(
+                "Iteration data bind group",
+                &self.storage_bind_group_layout,
+            ),
+            sampled_bind_group: create_bind_group(
+                "Sampled iteration data bind group",
+                &self.sampled_bind_group_layout,
+            ),
+            computed_from: None,
+        }
+    }
+}
+
+/// The bytes per row of a buffer to which the data is copied, which wgpu requires to be aligned.
+pub(super) fn padded_bytes_per_row(width: u32) -> u32 {
+    let alignment = wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+    (width * BYTES_PER_PIXEL).div_ceil(alignment) * alignment
+}
@@ -0,0 +1,334 @@
+use iced::time::Instant;
+use serde::{de::DeserializeOwned, Serialize};
+use std::{
+    sync::{
+        atomic::{AtomicBool, AtomicU32, Ordering},
+        mpsc::{self, TryRecvError},
+        Arc,
+    },
+    time::Duration,
+};
+
+/// The progress of a job which has not reported any, as the bits of an `f32`.
+const NO_PROGRESS: u32 = f32::NAN.to_bits();
+
+/// Work too slow for the event loop, such as computing a reference orbit or finding a minibrot,
+/// which runs on the worker pool so that the controls stay responsive meanwhile.
+///
+/// Jobs are plain data rather than closures, since on the web they run in web workers, which share
+/// no memory with the page and receive the job and return its output as messages.
+pub(super) trait Job: Serialize + DeserializeOwned + Send + 'static {
+    /// What the job does, as shown in the status bar. It also tells web workers which job a message
+    /// holds, so it must be unique.
+    const NAME: &'static str;
+    type Output: Serialize + DeserializeOwned + Send + 'static;
+
+    /// Runs the job, returning early with any output if [`Progress::is_cancelled`], since the
+    /// output of a cancelled job is discarded.
+    fn run(self, progress: &Progress) -> Self::Output;
+}
+
+/// Starts the given job on the worker pool and returns the handle through which its progress and
+/// result arrive. The result is the output of the job passed through `finish`.
+pub(super) fn spawn<J: Job, T: Send + 'static>(job: J, finish: fn(J::Output) -> T) -> JobHandle<T> {
+    let progress = Arc::new(AtomicU32::new(NO_PROGRESS));
+    let cancellation = CancellationToken::default();
+    let (sender, receiver) = mpsc::channel();
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        let reporter = Progress {
+            fraction: progress.clone(),
+            cancellation: cancellation.clone(),
+        };
+        thread_pool::submit(Box::new(move || {
+            let _ = sender.send(finish(job.run(&reporter)));
+        }));
+    }
+    #[cfg(target_arch = "wasm32")]
+    if crate::web_worker::is_available() {
+        crate::web_worker::submit(job, finish, sender, progress.clone());
+    } else {
+        // Without web workers, the job blocks the page until it finishes.
+        let reporter = Progress {
+            fraction: progress.clone(),
+            cancellation: cancellation.clone(),
+        };
+        let _ = sender.send(finish(job.run(&reporter)));
+    }
+    JobHandle {
+        name: J::NAME,
+        started: Instant::now(),
+        progress,
+        cancellation,
+        receiver,
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+mod thread_pool {
+    use std::sync::{mpsc, Mutex, OnceLock};
+
+    pub(super) type Task = Box<dyn FnOnce() + Send>;
+
+    /// Queues the task for the next free thread of the pool, which is started on first use.
+    ///
+    /// One core is left to the event loop and the driver of the GPU.
+    pub(super) fn submit(task: Task) {
+        static POOL: OnceLock<mpsc::Sender<Task>> = OnceLock::new();
+        POOL.get_or_init(start)
+            .send(task)
+            .expect("Worker pool stopped");
+    }
+
+    fn start() -> mpsc::Sender<Task> {
+        let (sender, receiver) = mpsc::channel::<Task>();
+        let receiver: &'static Mutex<_> = Box::leak(Box::new(Mutex::new(receiver)));
+        let workers = std::thread::available_parallelism()
+            .map_or(1, |parallelism| parallelism.get().saturating_sub(1))
+            .max(1);
+        for index in 0..workers {
+            std::thread::Builder::new()
+                .name(format!("worker {index}"))
+                .spawn(move || loop {
+                    let task = match receiver.lock() {
+                        Ok(receiver) => receiver.recv(),
+                        Err(_) => return,
+                    };
+                    let Ok(task) = task else {
+                        return;
+                    };
+                    // A panicking job only fails itself, which its handle reports.
+                    let _ = std::panic::catch_unwind(std::panic::AssertUnwindSafe(task));
+                })
+                .expect("Could not start worker thread");
+        }
+        sender
+    }
+}
+
+/// Asks long-running work, such as a job or the renders of a script, to stop early. Clones share
+/// the request, so that one can be handed to the work and another kept to cancel it.
+#[derive(Clone, Debug, Default)]
+pub(super) struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    pub(super) fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub(super) fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// Through which a job reports how far it has got and learns whether it was cancelled.
+pub(super) struct Progress {
+    fraction: Arc<AtomicU32>,
+    cancellation: CancellationToken,
+}
+
+impl Progress {
+    #[cfg(target_arch = "wasm32")]
+    pub(super) fn new() -> Self {
+        Self {
+            fraction: Arc::new(AtomicU32::new(NO_PROGRESS)),
+            cancellation: CancellationToken::default(),
+        }
+    }
+
+    /// Reports the fraction of the job, between zero and one, which is done.
+    pub(super) fn report(&self, fraction: f32) {
+        let fraction = fraction.clamp(0.0, 1.0);
+        self.fraction.store(fraction.to_bits(), Ordering::Relaxed);
+        #[cfg(target_arch = "wasm32")]
+        crate::web_worker::report_progress(fraction);
+    }
+
+    /// Whether the job was cancelled through its [`JobHandle`], in which case it should return as
+    /// soon as it can.
+    pub(super) fn is_cancelled(&self) -> bool {
+        self.cancellation.is_cancelled()
+    }
+}
+
+/// A job running on the worker pool.
+pub(super) struct JobHandle<T> {
+    name: &'static str,
+    started: Instant,
+    progress: Arc<AtomicU32>,
+    cancellation: CancellationToken,
+    receiver: mpsc::Receiver<T>,
+}
+
+/// The outcome of polling a [`JobHandle`].
+pub(super) enum Poll<T> {
+    Running,
+    Finished(T),
+    /// The job panicked without a result.
+    Failed,
+}
+
+impl<T> JobHandle<T> {
+    /// The name and progress of the job, as shown in the status bar.
+    pub(super) fn status(&self) -> JobStatus {
+        let fraction = f32::from_bits(self.progress.load(Ordering::Relaxed));
+        JobStatus {
+            name: self.name,
+            percent: (!fraction.is_nan()).then_some((fraction * 100.0) as u8),
+            remaining: remaining(self.started.elapsed(), fraction),
+        }
+    }
+
+    /// Returns the result of the job if it has finished, without waiting for it.
+    pub(super) fn poll(&self) -> Poll<T> {
+        match self.receiver.try_recv() {
+            Ok(result) => Poll::Finished(result),
+            Err(TryRecvError::Empty) => Poll::Running,
+            Err(TryRecvError::Disconnected) => Poll::Failed,
+        }
+    }
+
+    /// Asks the job to stop and discards its result.
+    ///
+    /// Web workers share no memory with the page, so there the job runs to the end in its worker
+    /// and only its result is discarded.
+    pub(super) fn cancel(self) {
+        self.cancellation.cancel();
+    }
+
+    /// Waits for the job to finish and returns its result, or `None` if it failed.
+    ///
+    /// The page cannot wait for web workers, whose messages only arrive once it yields.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(super) fn wait(self) -> Option<T> {
+        self.receiver.recv().ok()
+    }
+}
+
+/// What a running job is doing, and how much of it is done in percent if it reports its progress.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(super) struct JobStatus {
+    pub(super) name: &'static str,
+    pub(super) percent: Option<u8>,
+    /// How long the rest of the job will take if it goes on at the pace it went so far, in whole
+    /// seconds so that the status only changes once a second.
+    pub(super) remaining: Option<Duration>,
+}
+
+/// Extrapolates the time a job took for the fraction of it which is done to the rest.
+fn remaining(elapsed: Duration, fraction: f32) -> Option<Duration> {
+    (fraction > 0.0 && fraction < 1.0).then(|| {
+        let seconds = elapsed.as_secs_f64() * f64::from(1.0 - fraction) / f64::from(fraction);
+        Duration::from_secs(seconds.ceil() as u64)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{remaining, spawn, Job, Poll, Progress};
+    use googletest::prelude::*;
+    use serde::{Deserialize, Serialize};
+    use std::{
+        convert::identity,
+        sync::atomic::{AtomicBool, Ordering},
+        time::{Duration, Instant},
+    };
+
+    static REPORTED: AtomicBool = AtomicBool::new(false);
+    static PROCEED: AtomicBool = AtomicBool::new(false);
+    static STOPPED: AtomicBool = AtomicBool::new(false);
+
+    /// Reports some progress and then waits for the test before it finishes.
+    #[derive(Serialize, Deserialize)]
+    struct Counting;
+
+    impl Job for Counting {
+        const NAME: &'static str = "Counting";
+        type Output = u32;
+
+        fn run(self, progress: &Progress) -> u32 {
+            progress.report(0.4);
+            REPORTED.store(true, Ordering::Release);
+            while !PROCEED.load(Ordering::Acquire) {
+                std::thread::yield_now();
+            }
+            42
+        }
+    }
+
+    /// Runs until it is cancelled.
+    #[derive(Serialize, Deserialize)]
+    struct Endless;
+
+    impl Job for Endless {
+        const NAME: &'static str = "Endless";
+        type Output = ();
+
+        fn run(self, progress: &Progress) {
+            while !progress.is_cancelled() {
+                std::thread::yield_now();
+            }
+            STOPPED.store(true, Ordering::Release);
+        }
+    }
+
+    #[derive(Serialize, Deserialize)]
+    struct Panicking;
+
+    impl Job for Panicking {
+        const NAME: &'static str = "Panicking";
+        type Output = u32;
+
+        fn run(self, _: &Progress) -> u32 {
+            panic!("Job failed")
+        }
+    }
+
+    #[test]
+    fn job_reports_progress_and_delivers_result() -> Result<()> {
+        let job = spawn(Counting, |count| count + 1);
+        while !REPORTED.load(Ordering::Acquire) {
+            std::thread::yield_now();
+        }
+
+        let during = job.status().percent;
+        let running = matches!(job.poll(), Poll::Running);
+        PROCEED.store(true, Ordering::Release);
+
+        verify_that!(
+            (during, running, job.wait()),
+            (some(eq(40)), eq(true), some(eq(43)))
+        )
+    }
+
+    #[test]
+    fn remaining_time_is_extrapolated_from_progress() -> Result<()> {
+        verify_that!(
+            (
+                remaining(Duration::from_secs(10), 0.0),
+                remaining(Duration::from_secs(10), 0.25)
+            ),
+            (none(), some(eq(Duration::from_secs(30))))
+        )
+    }
+
+    #[test]
+    fn cancelled_job_stops() -> Result<()> {
+        let job = spawn(Endless, identity);
+
+        job.cancel();
+
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while !STOPPED.load(Ordering::Acquire) && Instant::now() < deadline {
+            std::thread::yield_now();
+        }
+        verify_that!(STOPPED.load(Ordering::Acquire), eq(true))
+    }
+
+    #[test]
+    fn panicking_job_fails() -> Result<()> {
+        let job = spawn(Panicking, |count| count);
+
+        verify_that!(job.wait(), none())
+    }
+}
@@ -0,0 +1,219 @@
+use crate::{
+    annotation::Annotation,
+    export,
+    export_manifest::{self, PartialFrame},
+    fractal_view::View,
+    gpu::Gpu,
+    tile_cache,
+    transform::ViewTransform,
+};
+use cgmath::Vector2;
+
+const BYTES_PER_PIXEL: usize = 4;
+
+/// The width in pixels of the black lines between the cells of an atlas.
+const CELL_GAP: u32 = 2;
+
+/// The most cells along either side of an atlas.
+pub(super) const MAX_CELLS_PER_SIDE: u32 = 32;
+
+/// The distance in pixels from the bottom of a cell to the middle of its label.
+const LABEL_INSET: f32 = 14.0;
+
+/// A grid of Julia sets whose constants are sampled over a rectangle of the parameter plane, laid
+/// out as the plane is, so that it serves as a map of how the Julia set changes with `c`.
+///
+/// Each cell shows the dynamic plane of the constant at its centre, framed whole as the explorer
+/// grid's thumbnails are, and is labelled with that constant.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(super) struct JuliaAtlas {
+    /// The number of cells across, from one to [`MAX_CELLS_PER_SIDE`].
+    pub(super) columns: u32,
+    /// The number of cells down, from one to [`MAX_CELLS_PER_SIDE`].
+    pub(super) rows: u32,
+    /// The corner of the rectangle of constants with the least real and imaginary parts.
+    pub(super) bottom_left: Vector2<f64>,
+    /// The corner of the rectangle of constants with the greatest real and imaginary parts.
+    pub(super) top_right: Vector2<f64>,
+    /// The width and height of every cell in pixels.
+    pub(super) cell_size: u32,
+}
+
+impl JuliaAtlas {
+    /// The width and height of the whole atlas in pixels, or `None` if either does not fit in a
+    /// `u32`.
+    pub(super) fn size(&self) -> Option<(u32, u32)> {
+        let side = |cells: u32| {
+            cells
+                .checked_mul(self.cell_size)?
+                .checked_add((cells - 1).checked_mul(CELL_GAP)?)
+        };
+        Some((side(self.columns)?, side(self.rows)?))
+    }
+
+    /// The constant of the cell in the given column and row, counted from the top left, at the
+    /// centre of the part of the rectangle which the cell covers.
+    pub(super) fn c(&self, column: u32, row: u32) -> Vector2<f64> {
+        let extent = self.top_right - self.bottom_left;
+        Vector2::new(
+            self.bottom_left.x + extent.x * (f64::from(column) + 0.5) / f64::from(self.columns),
+            self.top_right.y - extent.y * (f64::from(row) + 0.5) / f64::from(self.rows),
+        )
+    }
+
+    /// Identifies the atlas of the view by a hash of everything on which its pixels depend, like
+    /// [`export_manifest::frame_key`].
+    pub(super) fn key(&self, view: &View) -> u64 {
+        let cell = export_manifest::frame_key(view, &[], self.cell_size, self.cell_size);
+        tile_cache::fnv1a(&format!("{cell:016x} {self:?}"))
+    }
+
+    /// Renders the atlas of the view's fractal and returns its tightly packed RGBA pixels.
+    ///
+    /// Cells kept in `partial` by an interrupted render are not rendered again, and those rendered
+    /// are kept there. `should_stop` is asked before each cell, and stops the render once it
+    /// returns true.
+    pub(super) fn render(
+        &self,
+        gpu: &Gpu,
+        view: &mut View,
+        partial: Option<&PartialFrame>,
+        should_stop: impl Fn() -> bool,
+    ) -> Result<Vec<u8>, AtlasError> {
+        let (width, height) = self.size().ok_or(AtlasError::TooLarge)?;
+        let mut pixels = vec![0; width as usize * height as usize * BYTES_PER_PIXEL];
+        // The gaps are opaque black, even in transparent exports.
+        for pixel in pixels.chunks_exact_mut(BYTES_PER_PIXEL) {
+            pixel[3] = u8::MAX;
+        }
+        let cell_length = self.cell_size as usize * self.cell_size as usize * BYTES_PER_PIXEL;
+        for row in 0..self.rows {
+            for column in 0..self.columns {
+                let (x, y) = self.cell_origin(column, row);
+                let cell = match partial.and_then(|partial| partial.load(x, y, cell_length)) {
+                    Some(cell) => cell,
+                    None => {
+                        if should_stop() {
+                            return Err(AtlasError::Stopped);
+                        }
+                        let c = self.c(column, row);
+                        let cell = view
+                            .with_dynamic_plane(c.cast().unwrap(), |view| {
+                                let label = label(c, &view.transform(), self.cell_size);
+                                export::render_annotated_to_rgba(
+                                    gpu,
+                                    view,
+                                    &[label],
+                                    self.cell_size,
+                                    self.cell_size,
+                                )
+                            })
+                            .ok_or(AtlasError::NotEscapeTime)?;
+                        if let Some(partial) = partial {
+                            if let Err(error) = partial.save(x, y, &cell) {
+                                tracing::warn!("Could not keep cell of Julia atlas: {error}");
+                            }
+                        }
+                        cell
+                    }
+                };
+                self.paste(&mut pixels, width, column, row, &cell);
+            }
+        }
+        Ok(pixels)
+    }
+
+    /// The position in pixels of the top left of the cell in the given column and row.
+    fn cell_origin(&self, column: u32, row: u32) -> (u32, u32) {
+        let stride = self.cell_size + CELL_GAP;
+        (column * stride, row * stride)
+    }
+
+    /// Copies the pixels of a cell into those of the atlas, which is `width` pixels wide.
+    fn paste(&self, pixels: &mut [u8], width: u32, column: u32, row: u32, cell: &[u8]) {
+        let (x, y) = self.cell_origin(column, row);
+        let atlas_row = width as usize * BYTES_PER_PIXEL;
+        let cell_row = self.cell_size as usize * BYTES_PER_PIXEL;
+        for (index, source) in cell.chunks_exact(cell_row).enumerate() {
+            let start = (y as usize + index) * atlas_row + x as usize * BYTES_PER_PIXEL;
+            pixels[start..start + cell_row].copy_from_slice(source);
+        }
+    }
+}
+
+/// Why an atlas could not be rendered.
+#[derive(Debug, thiserror::Error)]
+pub(super) enum AtlasError {
+    #[error("A Julia atlas needs an escape-time fractal, whose dynamic planes it shows")]
+    NotEscapeTime,
+    #[error("Stopped before the Julia atlas was complete")]
+    Stopped,
+    #[error("The Julia atlas is too large for an image")]
+    TooLarge,
+}
+
+/// The note naming the constant of a cell, centred near its bottom, where Julia sets framed whole
+/// rarely reach.
+fn label(c: Vector2<f64>, transform: &ViewTransform, cell_size: u32) -> Annotation {
+    let inset = LABEL_INSET / cell_size as f32;
+    Annotation {
+        text: format!("{:.3}{:+.3}i", c.x, c.y),
+        position: transform.point_at(Vector2::new(0.0, inset - 0.5)),
+        target: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::JuliaAtlas;
+    use cgmath::Vector2;
+    use googletest::prelude::*;
+
+    fn atlas() -> JuliaAtlas {
+        JuliaAtlas {
+            columns: 4,
+            rows: 2,
+            bottom_left: Vector2::new(-2.0, -1.0),
+            top_right: Vector2::new(2.0, 1.0),
+            cell_size: 10,
+        }
+    }
+
+    #[test]
+    fn constants_are_laid_out_as_the_plane_is() -> Result<()> {
+        let atlas = atlas();
+
+        verify_that!(
+            (atlas.c(0, 0), atlas.c(3, 1)),
+            (eq(Vector2::new(-1.5, 0.5)), eq(Vector2::new(1.5, -0.5)))
+        )
+    }
+
+    #[test]
+    fn cells_are_pasted_between_gaps() -> Result<()> {
+        let atlas = JuliaAtlas {
+            columns: 2,
+            rows: 1,
+            cell_size: 1,
+            ..atlas()
+        };
+        let (width, height) = atlas.size().unwrap();
+        let mut pixels = vec![0; width as usize * height as usize * 4];
+        atlas.paste(&mut pixels, width, 1, 0, &[1, 2, 3, 4]);
+
+        verify_that!((width, height), eq((4, 1)))?;
+        verify_that!(&pixels[12..], eq(&[1, 2, 3, 4]))
+    }
+
+    #[test]
+    fn size_of_atlas_beyond_u32_is_none() -> Result<()> {
+        let atlas = JuliaAtlas {
+            columns: 32,
+            rows: 1,
+            cell_size: u32::MAX / 16,
+            ..atlas()
+        };
+
+        verify_that!(atlas.size(), none())
+    }
+}
@@ -0,0 +1,225 @@
+use crate::{
+    controls::FractalType,
+    fractal_view::ParameterBlock,
+    plane::Plane,
+    thumbnail::{Thumbnail, ThumbnailRenderer, ThumbnailRequest},
+};
+use cgmath::{Matrix3, Vector2};
+use iced_wgpu::wgpu;
+use std::{
+    cell::{Cell, RefCell},
+    rc::Rc,
+};
+
+/// How many frames pass before the inset follows the cursor to another point, so that moving the
+/// mouse does not render a thumbnail on every frame.
+const REFRESH_FRAMES: u32 = 6;
+
+/// The width and height of the inset in pixels of the window.
+const INSET_SIZE: f32 = 200.0;
+
+/// The distance of the inset from the left and bottom edges of the window, which leaves room for
+/// the status bar below it.
+const INSET_MARGIN: f32 = 40.0;
+
+/// Previews the dynamic plane, i.e. the Julia set, of the point of the parameter plane under the
+/// cursor in an inset in the bottom left corner of the window.
+///
+/// The preview is a thumbnail, so it is rendered at a low resolution in `f32`. While the cursor
+/// moves, the inset follows it every [`REFRESH_FRAMES`] frames and keeps showing the last preview
+/// until the next one is rendered.
+pub(super) struct JuliaInset {
+    /// The point under the cursor, if it is over a parameter plane.
+    point: Option<Vector2<f32>>,
+    /// The point whose preview was last asked for.
+    previewed: Cell<Option<Vector2<f32>>>,
+    frames_since_refresh: Cell<u32>,
+    /// The last preview rendered and the point which it shows.
+    shown: RefCell<Option<(Vector2<f32>, Rc<Thumbnail>)>>,
+}
+
+impl JuliaInset {
+    pub(super) fn new() -> Self {
+        Self {
+            point: None,
+            previewed: Cell::new(None),
+            frames_since_refresh: Cell::new(0),
+            shown: RefCell::new(None),
+        }
+    }
+
+    /// Follows the cursor to the given point of the parameter plane, or hides the inset.
+    pub(super) fn set_point(&mut self, point: Option<Vector2<f32>>) {
+        self.point = point;
+        if point.is_none() {
+            self.previewed.set(None);
+            self.shown.replace(None);
+        }
+    }
+
+    /// Whether the inset does not show the point under the cursor yet, so that more frames must be
+    /// rendered.
+    pub(super) fn is_pending(&self) -> bool {
+        self.point.is_some() && self.shown.borrow().as_ref().map(|(shown, _)| *shown) != self.point
+    }
+
+    /// Counts a frame and returns the point to preview on it, which moves to the point under the
+    /// cursor at once if nothing is previewed yet and otherwise only every [`REFRESH_FRAMES`]
+    /// frames.
+    fn refresh(&self) -> Option<Vector2<f32>> {
+        let frames = self.frames_since_refresh.get() + 1;
+        self.frames_since_refresh.set(frames);
+        if self.point != self.previewed.get()
+            && (self.previewed.get().is_none() || frames >= REFRESH_FRAMES)
+        {
+            self.previewed.set(self.point);
+            self.frames_since_refresh.set(0);
+        }
+        self.previewed.get()
+    }
+
+    /// Asks for the preview of the current point, rendered like the view's uniform block, and
+    /// shows it once it is rendered.
+    pub(super) fn update(
+        &self,
+        fractal_type: FractalType,
+        block: ParameterBlock,
+        half_width: f32,
+        thumbnails: &ThumbnailRenderer,
+    ) {
+        let Some(point) = self.refresh() else {
+            return;
+        };
+        let request = ThumbnailRequest {
+            fractal_type,
+            block: preview_block(block, point, half_width),
+        };
+        if let Some(thumbnail) = thumbnails.request(request) {
+            self.shown.replace(Some((point, thumbnail)));
+        }
+    }
+
+    /// Draws the last preview rendered over the bottom left corner of the target.
+    pub(super) fn render(
+        &self,
+        thumbnails: &ThumbnailRenderer,
+        target: &wgpu::Texture,
+        encoder: &mut wgpu::CommandEncoder,
+    ) {
+        let shown = self.shown.borrow();
+        let Some((_, thumbnail)) = shown.as_ref() else {
+            return;
+        };
+        let size = INSET_SIZE
+            .min(target.width() as f32 - INSET_MARGIN)
+            .min(target.height() as f32 - 2.0 * INSET_MARGIN);
+        if size < 1.0 {
+            return;
+        }
+        let view = target.create_view(&wgpu::TextureViewDescriptor::default());
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Julia inset pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: &view,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Load,
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+        render_pass.set_viewport(
+            INSET_MARGIN,
+            target.height() as f32 - INSET_MARGIN - size,
+            size,
+            size,
+            0.0,
+            1.0,
+        );
+        thumbnails.draw(&mut render_pass, thumbnail);
+    }
+}
+
+/// The uniform block which draws the dynamic plane of the given point about the origin with the
+/// given half width, with the view's other settings.
+fn preview_block(block: ParameterBlock, point: Vector2<f32>, half_width: f32) -> ParameterBlock {
+    let plane = Plane::Dynamic(point);
+    ParameterBlock {
+        transform: Matrix3::from_scale(half_width),
+        centre_residual: [0.0, 0.0],
+        reference_offset: [0.0, 0.0],
+        scale_exponent: 0,
+        reference_offset_exponent: 0,
+        fixed_c: plane.fixed_c().into(),
+        plane: plane.code(),
+        ..block
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{preview_block, JuliaInset, REFRESH_FRAMES};
+    use crate::{fractal_view::ParameterBlock, plane::Plane};
+    use cgmath::{Matrix3, Vector2};
+    use googletest::prelude::*;
+
+    #[test]
+    fn follows_cursor_only_every_few_frames() -> Result<()> {
+        let mut inset = JuliaInset::new();
+        inset.set_point(Some(Vector2::new(-0.8, 0.156)));
+        let first = inset.refresh();
+        inset.set_point(Some(Vector2::new(0.3, 0.5)));
+        let before_refresh: Vec<_> = (1..REFRESH_FRAMES).map(|_| inset.refresh()).collect();
+        let after_refresh = inset.refresh();
+
+        verify_that!(first, some(eq(Vector2::new(-0.8, 0.156))))?;
+        verify_that!(
+            before_refresh,
+            eq(vec![
+                Some(Vector2::new(-0.8, 0.156));
+                REFRESH_FRAMES as usize - 1
+            ])
+        )?;
+        verify_that!(after_refresh, some(eq(Vector2::new(0.3, 0.5))))
+    }
+
+    #[test]
+    fn leaving_parameter_plane_hides_inset() -> Result<()> {
+        let mut inset = JuliaInset::new();
+        inset.set_point(Some(Vector2::new(-0.8, 0.156)));
+        inset.refresh();
+
+        inset.set_point(None);
+
+        verify_that!((inset.refresh(), inset.is_pending()), (none(), eq(false)))
+    }
+
+    #[test]
+    fn preview_frames_dynamic_plane_of_point() -> Result<()> {
+        let block = ParameterBlock {
+            transform: Matrix3::from_scale(0.001),
+            centre_residual: [1e-9, 1e-9],
+            ..ParameterBlock::default()
+        };
+
+        let preview = preview_block(block, Vector2::new(-0.8, 0.156), 2.0);
+
+        verify_that!(
+            (
+                preview.transform,
+                preview.centre_residual,
+                preview.fixed_c,
+                preview.plane
+            ),
+            (
+                eq(Matrix3::from_scale(2.0)),
+                eq([0.0, 0.0]),
+                eq([-0.8, 0.156]),
+                eq(Plane::Dynamic(Vector2::new(0.0, 0.0)).code())
+            )
+        )
+    }
+}
@@ -0,0 +1,117 @@
+use cgmath::Vector2;
+
+/// The half-width of the square region of the complex plane shown in a preview. Every Julia set of
+/// the quadratic family lies within the disc of radius 2.
+const PREVIEW_HALF_WIDTH: f64 = 2.0;
+
+/// Approximates the Julia set of `z^2 + c` by inverse iteration (IIM).
+///
+/// Starting from the repelling fixed point, which lies in the Julia set, both preimages of every
+/// point are taken level by level until `max_points` points have been found. Preimages are
+/// attracted to the Julia set, so this is much cheaper than escape-time rendering and suited to
+/// small previews, though it undersamples regions which the inverse map rarely reaches.
+pub(super) fn inverse_iteration(c: Vector2<f64>, max_points: usize) -> Vec<Vector2<f64>> {
+    let mut points = Vec::with_capacity(max_points);
+    let mut level = vec![repelling_fixed_point(c)];
+    while points.len() < max_points && !level.is_empty() {
+        let mut next_level = Vec::with_capacity(level.len() * 2);
+        for z in level {
+            if points.len() >= max_points {
+                break;
+            }
+            points.push(z);
+            let root = csqrt(z - c);
+            next_level.push(root);
+            next_level.push(-root);
+        }
+        level = next_level;
+    }
+    points
+}
+
+/// Draws the points as white pixels on a transparent background and returns the tightly packed
+/// RGBA pixels of a square image covering [`PREVIEW_HALF_WIDTH`] about the origin.
+pub(super) fn render_point_cloud(points: &[Vector2<f64>], size: u32) -> Vec<u8> {
+    let mut pixels = vec![0; (size * size * 4) as usize];
+    let scale = size as f64 / (2.0 * PREVIEW_HALF_WIDTH);
+    for point in points {
+        let x = ((point.x + PREVIEW_HALF_WIDTH) * scale).floor();
+        let y = ((PREVIEW_HALF_WIDTH - point.y) * scale).floor();
+        if x < 0.0 || y < 0.0 || x >= size as f64 || y >= size as f64 {
+            continue;
+        }
+        let offset = ((y as u32 * size + x as u32) * 4) as usize;
+        pixels[offset..offset + 4].copy_from_slice(&[255, 255, 255, 255]);
+    }
+    pixels
+}
+
+/// Renders a preview of the Julia set for `c` and writes it as a square PNG file.
+pub(super) fn export_png(
+    c: Vector2<f64>,
+    path: impl AsRef<std::path::Path>,
+    size: u32,
+) -> image::ImageResult<()> {
+    // Enough points to cover the boundary at roughly a few points per pixel along its length.
+    let points = inverse_iteration(c, (size as usize * 64).max(1024));
+    let pixels = render_point_cloud(&points, size);
+    image::save_buffer(path, &pixels, size, size, image::ColorType::Rgba8)
+}
+
+/// The fixed point `1/2 + sqrt(1/4 - c)` of `z^2 + c`, which is repelling and so in the Julia set.
+fn repelling_fixed_point(c: Vector2<f64>) -> Vector2<f64> {
+    Vector2::new(0.5, 0.0) + csqrt(Vector2::new(0.25, 0.0) - c)
+}
+
+/// The principal square root of a complex number.
+fn csqrt(z: Vector2<f64>) -> Vector2<f64> {
+    let r = z.x.hypot(z.y);
+    let re = ((r + z.x) / 2.0).sqrt();
+    let im = ((r - z.x) / 2.0).sqrt().copysign(z.y);
+    Vector2::new(re, im)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{inverse_iteration, render_point_cloud};
+    use cgmath::{InnerSpace, Vector2};
+    use googletest::prelude::*;
+
+    #[test]
+    fn julia_set_of_zero_is_the_unit_circle() -> Result<()> {
+        let points = inverse_iteration(Vector2::new(0.0, 0.0), 1000);
+
+        verify_that!(points.len(), eq(1000))?;
+        for point in points {
+            verify_that!(point.magnitude(), approx_eq(1.0))?;
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn points_map_into_the_set_under_forward_iteration() -> Result<()> {
+        let c = Vector2::new(-0.123, 0.745);
+        let points = inverse_iteration(c, 64);
+
+        // Each point after the first is a preimage of an earlier one, so z^2 + c lands on a point
+        // already in the set.
+        for z in &points[1..] {
+            let image = Vector2::new(z.x * z.x - z.y * z.y, 2.0 * z.x * z.y) + c;
+            let distance = points
+                .iter()
+                .map(|other| (other - image).magnitude())
+                .fold(f64::INFINITY, f64::min);
+            verify_that!(distance, lt(1e-9))?;
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn point_cloud_marks_the_pixel_containing_each_point() -> Result<()> {
+        let pixels = render_point_cloud(&[Vector2::new(1.5, 1.5), Vector2::new(5.0, 0.0)], 4);
+
+        let lit_pixels = pixels.chunks(4).filter(|pixel| pixel[3] == 255).count();
+        verify_that!(lit_pixels, eq(1))?;
+        verify_that!(pixels[3 * 4 + 3], eq(255))
+    }
+}
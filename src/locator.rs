@@ -0,0 +1,172 @@
+use cgmath::{InnerSpace, Vector2};
+
+const MAX_PERIOD: u32 = 64;
+const MAX_MISIUREWICZ_PREPERIOD: u32 = 8;
+const MAX_MISIUREWICZ_PERIOD: u32 = 8;
+const NEWTON_STEPS: u32 = 64;
+const TOLERANCE: f64 = 1e-12;
+
+/// A kind of point of the Mandelbrot set which makes a good zoom target.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(super) enum LandmarkKind {
+    /// The centre of a hyperbolic component, where the critical orbit is periodic.
+    Center,
+    /// A Misiurewicz point, where the critical orbit is strictly preperiodic and the set is
+    /// asymptotically self-similar.
+    Misiurewicz,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(super) struct Landmark {
+    pub(super) position: Vector2<f64>,
+    pub(super) preperiod: u32,
+    pub(super) period: u32,
+}
+
+/// Finds the landmark of the given kind closest to `near`, considering only those within `radius`.
+///
+/// Newton's method is started at `near` for each candidate period (and preperiod), and the
+/// converged roots are reduced to their exact (pre)periods, since a root of a higher iterate is
+/// often a landmark of lower period.
+pub(super) fn find_nearest(
+    kind: LandmarkKind,
+    near: Vector2<f64>,
+    radius: f64,
+) -> Option<Landmark> {
+    let candidates: Vec<Landmark> = match kind {
+        LandmarkKind::Center => (1..=MAX_PERIOD)
+            .filter_map(|period| newton(near, 0, period))
+            .filter_map(classify_center)
+            .collect(),
+        LandmarkKind::Misiurewicz => (1..=MAX_MISIUREWICZ_PREPERIOD)
+            .flat_map(|preperiod| {
+                (1..=MAX_MISIUREWICZ_PERIOD).map(move |period| (preperiod, period))
+            })
+            .filter_map(|(preperiod, period)| newton(near, preperiod, period))
+            .filter_map(classify_misiurewicz)
+            .collect(),
+    };
+    candidates
+        .into_iter()
+        .filter(|landmark| (landmark.position - near).magnitude() <= radius)
+        .min_by(|a, b| {
+            let distance = |landmark: &Landmark| (landmark.position - near).magnitude2();
+            distance(a).total_cmp(&distance(b))
+        })
+}
+
+/// Solves `z_{preperiod + period}(c) = z_{preperiod}(c)` for `c` by Newton's method, where `z_n` is
+/// the critical orbit `z_0 = 0, z_{n + 1} = z_n^2 + c`. With a preperiod of zero the equation
+/// reduces to `z_period(c) = 0`, whose roots are the centres of hyperbolic components.
+fn newton(start: Vector2<f64>, preperiod: u32, period: u32) -> Option<Vector2<f64>> {
+    let mut c = start;
+    for _ in 0..NEWTON_STEPS {
+        let (z, dz) = orbit(c, preperiod + period);
+        let (value, derivative) = if preperiod == 0 {
+            (z, dz)
+        } else {
+            let (z_pre, dz_pre) = orbit(c, preperiod);
+            (z - z_pre, dz - dz_pre)
+        };
+        let step = div(value, derivative)?;
+        c -= step;
+        if !c.x.is_finite() || !c.y.is_finite() {
+            return None;
+        }
+        if step.magnitude() < TOLERANCE * c.magnitude().max(1.0) {
+            return Some(c);
+        }
+    }
+    None
+}
+
+fn classify_center(c: Vector2<f64>) -> Option<Landmark> {
+    let period = (1..=MAX_PERIOD).find(|&period| orbit(c, period).0.magnitude() < 1e-8)?;
+    Some(Landmark {
+        position: c,
+        preperiod: 0,
+        period,
+    })
+}
+
+fn classify_misiurewicz(c: Vector2<f64>) -> Option<Landmark> {
+    let iterates: Vec<Vector2<f64>> = (0..=MAX_MISIUREWICZ_PREPERIOD + MAX_MISIUREWICZ_PERIOD)
+        .map(|n| orbit(c, n).0)
+        .collect();
+    // The smallest preperiod is found first, then the smallest period for it. A preperiod of zero
+    // would make the orbit periodic, in which case c is a centre rather than a Misiurewicz point.
+    let (preperiod, period) = (0..=MAX_MISIUREWICZ_PREPERIOD)
+        .flat_map(|preperiod| (1..=MAX_MISIUREWICZ_PERIOD).map(move |period| (preperiod, period)))
+        .find(|&(preperiod, period)| {
+            (iterates[(preperiod + period) as usize] - iterates[preperiod as usize]).magnitude()
+                < 1e-8
+        })?;
+    (preperiod > 0).then_some(Landmark {
+        position: c,
+        preperiod,
+        period,
+    })
+}
+
+/// Returns `z_n(c)` and its derivative with respect to `c`.
+fn orbit(c: Vector2<f64>, n: u32) -> (Vector2<f64>, Vector2<f64>) {
+    let mut z = Vector2::new(0.0, 0.0);
+    let mut dz = Vector2::new(0.0, 0.0);
+    for _ in 0..n {
+        dz = 2.0 * mul(z, dz) + Vector2::new(1.0, 0.0);
+        z = mul(z, z) + c;
+    }
+    (z, dz)
+}
+
+fn mul(a: Vector2<f64>, b: Vector2<f64>) -> Vector2<f64> {
+    Vector2::new(a.x * b.x - a.y * b.y, a.x * b.y + a.y * b.x)
+}
+
+fn div(a: Vector2<f64>, b: Vector2<f64>) -> Option<Vector2<f64>> {
+    let denominator = b.magnitude2();
+    (denominator > 0.0)
+        .then(|| Vector2::new(a.x * b.x + a.y * b.y, a.y * b.x - a.x * b.y) / denominator)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{find_nearest, LandmarkKind};
+    use cgmath::Vector2;
+    use googletest::prelude::*;
+
+    #[test]
+    fn finds_centre_of_period_three_component() -> Result<()> {
+        let landmark = find_nearest(LandmarkKind::Center, Vector2::new(-1.7545, 0.0002), 1e-3)
+            .expect("Centre not found");
+
+        verify_that!(
+            (landmark.position.x, landmark.position.y, landmark.period),
+            (near(-1.754_877_666_246_693, 1e-9), near(0.0, 1e-9), eq(3))
+        )
+    }
+
+    #[test]
+    fn finds_misiurewicz_point_at_i() -> Result<()> {
+        let landmark = find_nearest(LandmarkKind::Misiurewicz, Vector2::new(0.05, 0.95), 0.2)
+            .expect("Misiurewicz point not found");
+
+        verify_that!(
+            (
+                landmark.position.x,
+                landmark.position.y,
+                landmark.preperiod,
+                landmark.period
+            ),
+            (near(0.0, 1e-9), near(1.0, 1e-9), eq(2), eq(2))
+        )
+    }
+
+    #[test]
+    fn ignores_landmarks_outside_radius() -> Result<()> {
+        verify_that!(
+            find_nearest(LandmarkKind::Center, Vector2::new(-1.7545, 0.0002), 1e-6),
+            none()
+        )
+    }
+}
@@ -1,15 +1,97 @@
+mod analysis;
+mod annotation;
+mod antialiasing;
+#[cfg(all(feature = "audio", not(target_arch = "wasm32")))]
+mod audio;
+#[cfg(all(feature = "audio", not(target_arch = "wasm32")))]
+mod audio_reactive;
+mod bench;
+mod blit;
+mod bloom;
+mod bookmark;
+mod chaos_game;
+mod clock;
+mod colour_palette;
+mod compositor;
+mod controller;
 mod controls;
+mod cosine_palette;
+mod critical_points;
+mod debug_view;
+mod df64;
+mod dive;
+mod double_double;
+mod error;
+mod explorer_grid;
+mod export;
+mod export_manifest;
+mod floatexp;
+mod fractal_definition;
 mod fractal_view;
 mod gpu;
+mod gpu_memory;
+mod history;
+mod icon;
+mod image_palette;
+mod isolines;
+mod iteration_budget;
+mod iteration_data;
+mod jobs;
+mod julia_atlas;
+mod julia_inset;
+mod julia_preview;
+mod locator;
+mod measurement;
+mod minibrot;
+mod modulation;
+mod navigation;
+#[cfg(all(feature = "ndi", not(target_arch = "wasm32")))]
+mod ndi;
+mod notification;
+mod oklab;
+mod orbit_trap;
+#[cfg(all(feature = "osc", not(target_arch = "wasm32")))]
+mod osc;
+mod pipeline_compiler;
+mod plane;
+mod post_processing;
+mod precision;
+mod presentation;
+mod print_preset;
+mod random_palette;
+mod reference_orbit;
+mod render_farm;
+mod render_scheduler;
+mod script;
+mod session;
+mod shader_source;
+mod shortcuts;
+mod snippet;
+mod std140;
+mod storage;
+mod svg_export;
+mod throughput;
+mod thumbnail;
+mod tiff;
+mod tile_cache;
+mod transform;
+mod transparency;
+mod user_config;
+mod variation;
+mod vector_mode;
+#[cfg(target_arch = "wasm32")]
+mod web_worker;
 #[cfg(test)]
 #[macro_use]
 mod wgpu_test;
+mod workspace;
 
-use cgmath::Vector2;
-use controls::{CanvasMessage, Controls, Message};
+use controller::Controller;
+use controls::Controls;
+use error::Error;
 use fractal_view::View;
 use gpu::Gpu;
-use iced::Color;
+use iced::{time::Instant, Color};
 use iced_core::mouse::Cursor;
 use iced_wgpu::{graphics::Viewport, wgpu, Backend, Renderer, Settings};
 use iced_winit::{
@@ -19,10 +101,12 @@ use iced_winit::{
     winit::{self},
     Clipboard,
 };
+use render_scheduler::{Change, RenderScheduler};
+use session::SessionStore;
 use winit::{
     dpi::PhysicalPosition,
     event::{Event, WindowEvent},
-    event_loop::{ControlFlow, EventLoop},
+    event_loop::EventLoop,
 };
 
 #[cfg(target_arch = "wasm32")]
@@ -32,33 +116,338 @@ use web_sys::HtmlCanvasElement;
 #[cfg(target_arch = "wasm32")]
 use winit::platform::web::WindowBuilderExtWebSys;
 
-const ZOOM_SCROLL_FACTOR: f32 = 40.0;
-
 pub fn main() {
-    init_logging();
-    let event_loop = EventLoop::new().unwrap();
-    let window = create_window(&event_loop);
+    let _trace_guard = init_logging();
+    // Web workers load the same module as the page, and only serve its jobs.
+    #[cfg(target_arch = "wasm32")]
+    if web_worker::is_worker() {
+        web_worker::serve();
+        return;
+    }
+    let args: Vec<String> = std::env::args().collect();
+    match args.get(1).map(String::as_str) {
+        Some("script") => run_script(&args[2..]),
+        Some("bench") => run_bench(&args[2..]),
+        Some("worker") => run_worker(&args[2..]),
+        #[cfg(not(target_arch = "wasm32"))]
+        _ => {
+            if let Err(error) = run_gui(GuiOptions::parse(&args[1..])) {
+                error::report_fatal(&error);
+                std::process::exit(1);
+            }
+        }
+        // The page must yield while the browser hands out a WebGPU device.
+        #[cfg(target_arch = "wasm32")]
+        _ => {
+            let options = GuiOptions::parse(&args[1..]);
+            wasm_bindgen_futures::spawn_local(async move {
+                let webgpu = Gpu::request_webgpu(options.power_preference()).await;
+                if let Err(error) = run_gui(options, webgpu) {
+                    error::report_fatal(&error);
+                }
+            });
+        }
+    }
+}
+
+fn run_bench(args: &[String]) {
+    let options = bench::BenchOptions::parse(args).unwrap_or_else(|error| {
+        eprintln!("{error}\nUsage: fractal_explorer bench [--frames <n>] [--json <file>]");
+        std::process::exit(2);
+    });
+    if let Err(error) = bench::run(options) {
+        eprintln!("Benchmark failed: {error}");
+        std::process::exit(1);
+    }
+}
+
+fn run_script(args: &[String]) {
+    let usage = "Usage: fractal_explorer script <file.rhai> [--farm <address:port>] [--all-gpus]";
+    let Some((path, flags)) = args.split_first() else {
+        eprintln!("{usage}");
+        std::process::exit(2);
+    };
+    let options = FarmOptions::parse(flags).unwrap_or_else(|error| {
+        eprintln!("{error}\n{usage}");
+        std::process::exit(2);
+    });
+    // Renders are split into tiles for the workers of a farm, or for the GPUs of this machine.
+    let farm = match (&options.farm, options.all_gpus) {
+        (None, false) => None,
+        (address, _) => {
+            let address = address.as_deref().unwrap_or("127.0.0.1:0");
+            match render_farm::Coordinator::bind(address, render_farm::TILE_SIZE) {
+                Ok(coordinator) => Some(coordinator),
+                Err(error) => {
+                    eprintln!("Could not listen for workers on {address}: {error}");
+                    std::process::exit(1);
+                }
+            }
+        }
+    };
+    if let Some(coordinator) = &farm {
+        if options.farm.is_some() {
+            eprintln!("Waiting for workers on {}", coordinator.local_addr());
+        }
+        if options.all_gpus {
+            let address = coordinator.local_addr();
+            std::thread::spawn(move || start_workers(address, true));
+        }
+    }
+    let cancellation = jobs::CancellationToken::default();
+    cancel_on_enter(cancellation.clone(), farm.clone());
+    let started = std::time::Instant::now();
+    if let Err(error) = script::run_file(path, farm, cancellation.clone()) {
+        if cancellation.is_cancelled() {
+            eprintln!("Script {path} cancelled; run it again to resume");
+        } else {
+            eprintln!("Script {path} failed: {error}");
+        }
+        std::process::exit(1);
+    }
+    // Long exports are left to run in the background, so their end is announced.
+    let duration = started.elapsed();
+    if duration >= notification::MIN_DURATION {
+        notification::show(
+            &format!("Script {path} finished"),
+            &format!("After {}", throughput::describe_duration(duration)),
+            false,
+        );
+    }
+}
+
+/// Cancels the script once Enter is pressed in the terminal, so that an export started with the
+/// wrong settings stops without losing the images already written.
+fn cancel_on_enter(cancellation: jobs::CancellationToken, farm: Option<render_farm::Coordinator>) {
+    // Without a terminal, as in a pipeline, there is nobody to press Enter.
+    if !std::io::IsTerminal::is_terminal(&std::io::stdin()) {
+        return;
+    }
+    eprintln!("Press Enter to cancel");
+    std::thread::spawn(move || {
+        let mut line = String::new();
+        if std::io::stdin()
+            .read_line(&mut line)
+            .is_ok_and(|read| read > 0)
+        {
+            eprintln!("Cancelling after the render under way");
+            cancellation.cancel();
+            if let Some(farm) = farm {
+                farm.cancel();
+            }
+        }
+    });
+}
+
+fn run_worker(args: &[String]) {
+    let usage = "Usage: fractal_explorer worker <address:port> [--all-gpus]";
+    let Some((address, flags)) = args.split_first() else {
+        eprintln!("{usage}");
+        std::process::exit(2);
+    };
+    let options = FarmOptions::parse(flags)
+        .ok()
+        .filter(|options| options.farm.is_none())
+        .unwrap_or_else(|| {
+            eprintln!("{usage}");
+            std::process::exit(2);
+        });
+    let address = match std::net::ToSocketAddrs::to_socket_addrs(address.as_str()) {
+        Ok(mut addresses) => addresses.next(),
+        Err(error) => {
+            eprintln!("Could not resolve {address}: {error}");
+            std::process::exit(1);
+        }
+    };
+    let Some(address) = address else {
+        eprintln!("{usage}");
+        std::process::exit(2);
+    };
+    start_workers(address, options.all_gpus);
+}
+
+/// Renders tiles for the coordinator at the given address with every GPU of the machine or with
+/// the default one, exiting if none can be used.
+fn start_workers(address: std::net::SocketAddr, all_gpus: bool) {
+    let gpus = if all_gpus {
+        Gpu::all_without_surface()
+    } else {
+        Gpu::new_without_surface().map(|gpu| vec![gpu])
+    };
+    let result = gpus.and_then(|gpus| {
+        for gpu in &gpus {
+            eprintln!("Rendering tiles on {}", gpu.adapter_info.name);
+        }
+        render_farm::run_workers(address, gpus)
+    });
+    if let Err(error) = result {
+        eprintln!("Worker failed: {error}");
+        std::process::exit(1);
+    }
+}
+
+/// The command line flags which split renders across machines or GPUs.
+struct FarmOptions {
+    /// The address on which to listen for workers.
+    farm: Option<String>,
+    /// Whether every GPU of the machine renders tiles, each with a worker of its own.
+    all_gpus: bool,
+}
+
+impl FarmOptions {
+    fn parse(flags: &[String]) -> Result<Self, String> {
+        let mut options = Self {
+            farm: None,
+            all_gpus: false,
+        };
+        let mut flags = flags.iter();
+        while let Some(flag) = flags.next() {
+            match flag.as_str() {
+                "--farm" => {
+                    options.farm = Some(flags.next().ok_or("--farm requires an address")?.clone());
+                }
+                "--all-gpus" => options.all_gpus = true,
+                _ => return Err(format!("Unknown argument {flag}")),
+            }
+        }
+        Ok(options)
+    }
+}
+
+/// The command line flags with which the explorer is run in a window.
+struct GuiOptions {
+    /// Whether a low-power adapter is requested and the fractal is rendered at a reduced
+    /// resolution with throttled redraws.
+    low_power: bool,
+    /// Whether the window is given a surface whose colours may lie beyond sRGB, where supported.
+    wide_gamut: bool,
+    /// Whether the view is modulated by the audio input.
+    #[cfg(all(feature = "audio", not(target_arch = "wasm32")))]
+    audio: bool,
+    /// Whether the fractal is published as an NDI source.
+    #[cfg(all(feature = "ndi", not(target_arch = "wasm32")))]
+    ndi: bool,
+    /// Whether notifications of finished jobs come with a sound.
+    #[cfg(all(feature = "notifications", not(target_arch = "wasm32")))]
+    notification_sound: bool,
+}
+
+impl GuiOptions {
+    fn parse(flags: &[String]) -> Self {
+        let has_flag = |name: &str| flags.iter().any(|flag| flag == name);
+        Self {
+            low_power: has_flag("--low-power"),
+            wide_gamut: has_flag("--wide-gamut"),
+            #[cfg(all(feature = "audio", not(target_arch = "wasm32")))]
+            audio: has_flag("--audio"),
+            #[cfg(all(feature = "ndi", not(target_arch = "wasm32")))]
+            ndi: has_flag("--ndi"),
+            #[cfg(all(feature = "notifications", not(target_arch = "wasm32")))]
+            notification_sound: has_flag("--notification-sound"),
+        }
+    }
+
+    fn power_preference(&self) -> wgpu::PowerPreference {
+        if self.low_power {
+            wgpu::PowerPreference::LowPower
+        } else {
+            wgpu::PowerPreference::default()
+        }
+    }
+}
+
+/// Runs the explorer in a window.
+fn run_gui(
+    options: GuiOptions,
+    #[cfg(target_arch = "wasm32")] webgpu: Option<gpu::WebGpuDevice>,
+) -> Result<(), Error> {
+    let low_power = options.low_power;
+    let event_loop = EventLoop::new()?;
+    let window = create_window(&event_loop)?;
+    window.set_window_icon(Some(icon::create_icon()));
 
     let physical_size = window.inner_size();
-    let mut viewport = Viewport::with_physical_size(
-        Size::new(physical_size.width, physical_size.height),
-        window.scale_factor(),
-    );
+    let mut viewport = create_viewport(&window, 1.0);
     let mut cursor_position = PhysicalPosition::new(-1.0, -1.0);
     let mut clipboard = Clipboard::connect(&window);
 
-    let (gpu, surface) = Gpu::new(&window);
+    let (gpu, surface) = Gpu::new(
+        &window,
+        options.power_preference(),
+        options.wide_gamut,
+        #[cfg(target_arch = "wasm32")]
+        webgpu,
+    )?;
 
     let mut modifiers = winit::keyboard::ModifiersState::default();
 
-    let mut resized = false;
+    let mut scheduler = RenderScheduler::default();
+    scheduler.set_low_power(low_power);
+    // On the web, the canvas is only measured once it has been laid out.
+    scheduler.set_minimized(physical_size.width == 0 || physical_size.height == 0);
+    let mut fatal_error = None;
 
     // Initialize staging belt
     let mut staging_belt = wgpu::util::StagingBelt::new(5 * 1024);
 
+    #[cfg(all(feature = "osc", not(target_arch = "wasm32")))]
+    let osc_server = {
+        let proxy = event_loop.create_proxy();
+        osc::OscServer::start(osc::OSC_PORT, move || {
+            let _ = proxy.send_event(());
+        })
+        .inspect_err(|error| {
+            tracing::warn!(
+                "Could not listen for OSC on port {}: {error}",
+                osc::OSC_PORT
+            )
+        })
+        .ok()
+    };
+
+    #[cfg(not(target_arch = "wasm32"))]
+    let config_watcher = storage::config_directory().and_then(|directory| {
+        let proxy = event_loop.create_proxy();
+        user_config::ConfigWatcher::start(directory.join(user_config::FILE_NAME), move || {
+            let _ = proxy.send_event(());
+        })
+        .inspect_err(|error| tracing::warn!("Could not watch the configuration file: {error}"))
+        .ok()
+    });
+
+    #[cfg(all(feature = "ndi", not(target_arch = "wasm32")))]
+    let ndi_sender = options
+        .ndi
+        .then(|| {
+            ndi::NdiSender::new(gpu.texture_format)
+                .inspect_err(|error| tracing::warn!("Could not publish over NDI: {error}"))
+                .ok()
+        })
+        .flatten();
+
+    #[cfg(all(feature = "audio", not(target_arch = "wasm32")))]
+    let mut audio = options
+        .audio
+        .then(|| {
+            audio::AudioVisualizer::start()
+                .inspect_err(|error| tracing::warn!("Could not capture audio: {error}"))
+                .ok()
+        })
+        .flatten();
+    #[cfg(all(feature = "audio", not(target_arch = "wasm32")))]
+    if audio.is_some() {
+        scheduler.start_animation(audio::AUDIO_ANIMATION);
+    }
+
     // Initialize scene and GUI controls
     let mut fractal_view = View::new(&gpu);
-    let controls = Controls::new();
+    fractal_view.set_low_power(&gpu, low_power);
+    fractal_view.set_tile_cache(tile_cache::TileCache::open());
+    let controls = Controls::new(low_power, &gpu.adapter_info);
+    let mut controller = Controller::new(&gpu, physical_size);
+    #[cfg(all(feature = "notifications", not(target_arch = "wasm32")))]
+    controller.set_notification_sound(options.notification_sound);
+    let mut session_store = storage::open().map(SessionStore::open);
 
     // Initialize iced
     let mut debug = Debug::new();
@@ -82,118 +471,198 @@ pub fn main() {
     );
 
     // Run event loop
-    event_loop
-        .run(|event, event_loop_window| {
-            // You should change this if you want to render continuosly
-            event_loop_window.set_control_flow(ControlFlow::Wait);
-
-            match event {
-                Event::WindowEvent { event, .. } => {
-                    match event {
-                        WindowEvent::CursorMoved { position, .. } => {
-                            cursor_position = position;
-                        }
-                        WindowEvent::ModifiersChanged(new_modifiers) => {
-                            modifiers = new_modifiers.state();
-                        }
-                        WindowEvent::Resized(_) => {
-                            resized = true;
+    event_loop.run(|event, event_loop_window| {
+        match event {
+            Event::WindowEvent { event, .. } => {
+                match event {
+                    WindowEvent::CursorMoved { position, .. } => {
+                        cursor_position = position;
+                    }
+                    WindowEvent::ModifiersChanged(new_modifiers) => {
+                        modifiers = new_modifiers.state();
+                    }
+                    WindowEvent::Resized(size) => {
+                        scheduler.set_minimized(size.width == 0 || size.height == 0);
+                        scheduler.invalidate(Change::Window);
+                    }
+                    WindowEvent::ScaleFactorChanged { .. } => {
+                        scheduler.invalidate(Change::Window);
+                    }
+                    WindowEvent::Occluded(occluded) => {
+                        scheduler.set_occluded(occluded);
+                    }
+                    WindowEvent::Focused(focused) => {
+                        scheduler.set_focused(focused);
+                    }
+                    WindowEvent::CloseRequested => {
+                        if let Some(session_store) = &mut session_store {
+                            controller.flush_session(session_store, &fractal_view);
                         }
-                        WindowEvent::CloseRequested => {
+                        event_loop_window.exit();
+                    }
+                    // The surface cannot be configured with a size of zero while minimized, and
+                    // there is no point in drawing while hidden anyway.
+                    WindowEvent::RedrawRequested if scheduler.is_visible() => {
+                        if let Err(error) = redraw(
+                            &window,
+                            &surface,
+                            &gpu,
+                            &mut fractal_view,
+                            &mut widget_renderer,
+                            &mut state,
+                            &mut viewport,
+                            &mut staging_belt,
+                            &mut debug,
+                            &mut scheduler,
+                            controller.ui_scale(),
+                        ) {
+                            fatal_error = Some(error);
                             event_loop_window.exit();
                         }
-                        WindowEvent::RedrawRequested => {
-                            redraw(
-                                &window,
-                                &surface,
-                                &gpu,
-                                &mut fractal_view,
-                                &mut widget_renderer,
-                                &mut state,
-                                &mut viewport,
-                                &mut staging_belt,
-                                &mut debug,
-                                &mut resized,
-                            );
+                        #[cfg(all(feature = "ndi", not(target_arch = "wasm32")))]
+                        if let Some(ndi_sender) = &ndi_sender {
+                            let size = window.inner_size();
+                            ndi_sender.send(&gpu, &fractal_view, size.width, size.height);
                         }
-                        _ => {}
                     }
+                    _ => {}
+                }
 
-                    // Map window event to iced event
-                    if let Some(event) = iced_winit::conversion::window_event(
-                        iced_core::window::Id::MAIN,
-                        event,
-                        window.scale_factor(),
-                        modifiers,
-                    ) {
-                        state.queue_event(event);
+                // Map window event to iced event
+                if let Some(event) = iced_winit::conversion::window_event(
+                    iced_core::window::Id::MAIN,
+                    event,
+                    viewport.scale_factor(),
+                    modifiers,
+                ) {
+                    state.queue_event(event);
+                }
+            }
+            Event::AboutToWait => {
+                controller.set_viewport(&viewport);
+                for reply in controller.poll_pipelines(&mut fractal_view, &mut scheduler) {
+                    state.queue_message(reply);
+                }
+                controller.apply_modulation(&mut fractal_view, &mut scheduler);
+                controller.advance_time(&mut fractal_view, &mut scheduler);
+                for reply in controller.advance_dive(&mut fractal_view, &mut scheduler) {
+                    state.queue_message(reply);
+                }
+                if let Some(reply) = controller.advance_joystick(&mut fractal_view, &mut scheduler)
+                {
+                    state.queue_message(reply);
+                }
+                for reply in controller.advance_replay(&mut fractal_view, &mut scheduler) {
+                    state.queue_message(reply);
+                }
+                for reply in controller.advance_presentation(&mut fractal_view, &mut scheduler) {
+                    state.queue_message(reply);
+                }
+                if let Some(reply) = controller.update_precision(&mut fractal_view, &mut scheduler)
+                {
+                    state.queue_message(reply);
+                }
+                controller.update_reference_orbit(&mut fractal_view, &mut scheduler);
+                for reply in controller.poll_jobs(&fractal_view, &mut scheduler) {
+                    state.queue_message(reply);
+                }
+                controller.poll_thumbnails(&fractal_view, &mut scheduler);
+                controller.persist_tile(&gpu, &fractal_view, &mut scheduler);
+                for reply in controller.update_analysis(
+                    &gpu,
+                    &mut fractal_view,
+                    &mut scheduler,
+                    Instant::now(),
+                ) {
+                    state.queue_message(reply);
+                }
+                if let Some(reply) = controller.update_gpu_memory(&fractal_view) {
+                    state.queue_message(reply);
+                }
+                if let Some(reply) = controller.record_history(&fractal_view) {
+                    state.queue_message(reply);
+                }
+                if let Some(session_store) = &mut session_store {
+                    if let Some(reply) =
+                        controller.persist_session(session_store, &fractal_view, &mut scheduler)
+                    {
+                        state.queue_message(reply);
+                    }
+                }
+                // After the session, so that a restored session is known when the configuration
+                // file first loads.
+                #[cfg(not(target_arch = "wasm32"))]
+                if let Some(config_watcher) = &config_watcher {
+                    for message in config_watcher.poll() {
+                        state.queue_message(message);
+                    }
+                }
+                #[cfg(all(feature = "audio", not(target_arch = "wasm32")))]
+                if let Some(audio) = &mut audio {
+                    let modulation = audio.update();
+                    fractal_view.set_palette_offset(modulation.palette_offset);
+                    fractal_view.set_zoom_pulse(modulation.zoom_pulse);
+                    scheduler.invalidate(Change::Parameters);
+                }
+                #[cfg(all(feature = "osc", not(target_arch = "wasm32")))]
+                if let Some(osc_server) = &osc_server {
+                    let size = viewport.logical_size();
+                    let centre = iced::Point::new(size.width / 2.0, size.height / 2.0);
+                    for message in osc_server.poll(centre) {
+                        state.queue_message(message);
                     }
                 }
-                Event::AboutToWait => {
-                    // If there are events pending
-                    if !state.is_queue_empty() {
-                        // We update iced
-                        let _ = state.update(
-                            viewport.logical_size(),
-                            Cursor::Available(conversion::cursor_position(
-                                cursor_position,
-                                viewport.scale_factor(),
-                            )),
-                            &mut widget_renderer,
-                            &iced_winit::style::Theme::Dark,
-                            &renderer::Style {
-                                text_color: Color::WHITE,
-                            },
-                            &mut clipboard,
-                            &mut debug,
-                        );
-
-                        let program = state.program();
-
-                        match program.take_last_message() {
-                            Some(Message::Canvas(CanvasMessage::Pan(x, y))) => {
-                                let displacement = Vector2::new(
-                                    x / physical_size.width as f32,
-                                    y / physical_size.height as f32,
-                                );
-                                fractal_view.translate(displacement);
-                                state.queue_message(Message::Canvas(
-                                    CanvasMessage::UpdateViewTransform(
-                                        fractal_view.get_view_transform(),
-                                    ),
-                                ));
-                            }
-                            Some(Message::Canvas(CanvasMessage::Zoom(y, on_point))) => {
-                                let factor = y / ZOOM_SCROLL_FACTOR + 1.0;
-                                fractal_view.zoom(
-                                    factor,
-                                    Vector2::new(
-                                        on_point.x / physical_size.width as f32 - 0.5,
-                                        -on_point.y / physical_size.height as f32 + 0.5,
-                                    ),
-                                );
-                                state.queue_message(Message::Canvas(
-                                    CanvasMessage::UpdateViewTransform(
-                                        fractal_view.get_view_transform(),
-                                    ),
-                                ));
-                            }
-                            Some(Message::FractalTypeSelected(fractal_type)) => {
-                                fractal_view.set_fractal_type(&gpu, fractal_type);
-                            }
-                            _ => {}
-                        }
 
-                        // and request a redraw
-                        window.request_redraw();
+                // If there are events pending
+                if !state.is_queue_empty() {
+                    let _span = tracing::info_span!("handle_events").entered();
+
+                    // We update iced
+                    let _ = state.update(
+                        viewport.logical_size(),
+                        Cursor::Available(conversion::cursor_position(
+                            cursor_position,
+                            viewport.scale_factor(),
+                        )),
+                        &mut widget_renderer,
+                        controller.theme(),
+                        &renderer::Style {
+                            text_color: Color::WHITE,
+                        },
+                        &mut clipboard,
+                        &mut debug,
+                    );
+
+                    let messages = state.program().take_messages();
+                    for reply in controller.handle_messages(
+                        messages,
+                        &gpu,
+                        &mut fractal_view,
+                        &mut scheduler,
+                    ) {
+                        state.queue_message(reply);
                     }
+                    scheduler.invalidate(Change::Ui);
+                }
+
+                // On the web, winit shows the title as that of the document.
+                if let Some(title) = controller.take_window_title(&fractal_view) {
+                    window.set_title(&title);
+                }
+
+                if scheduler.wants_redraw() {
+                    window.request_redraw();
                 }
-                _ => {}
+                event_loop_window.set_control_flow(scheduler.control_flow());
             }
-        })
-        .unwrap();
+            _ => {}
+        }
+    })?;
+    fatal_error.map_or(Ok(()), Err)
 }
 
+#[allow(clippy::too_many_arguments)]
+#[tracing::instrument(skip_all)]
 fn redraw(
     window: &winit::window::Window,
     surface: &wgpu::Surface,
@@ -204,22 +673,21 @@ fn redraw(
     viewport: &mut Viewport,
     staging_belt: &mut wgpu::util::StagingBelt,
     debug: &mut Debug,
-    resized: &mut bool,
-) {
-    if *resized {
-        let size = window.inner_size();
-
-        *viewport =
-            Viewport::with_physical_size(Size::new(size.width, size.height), window.scale_factor());
-
-        gpu.configure_surface(&surface, size);
-
-        *resized = false;
+    scheduler: &mut RenderScheduler,
+    ui_scale: f64,
+) -> Result<(), Error> {
+    let changes = scheduler.begin_frame();
+    if changes.window {
+        *viewport = create_viewport(window, ui_scale);
+        gpu.configure_surface(surface, window.inner_size(), scheduler.present_mode());
     }
 
     match surface.get_current_texture() {
         Ok(frame) => {
-            fractal_view.update_transform(&gpu.queue);
+            // These spans measure the time spent recording commands on the CPU. The GPU work
+            // itself shows up in the "present" span once the swapchain waits for it.
+            tracing::info_span!("upload_uniforms")
+                .in_scope(|| fractal_view.update_transform(&gpu.queue));
 
             let mut encoder = gpu
                 .device
@@ -229,12 +697,15 @@ fn redraw(
                 .texture
                 .create_view(&wgpu::TextureViewDescriptor::default());
 
-            fractal_view.render(&view, &mut encoder);
+            tracing::info_span!("fractal_pass")
+                .in_scope(|| fractal_view.render(&frame.texture, &mut encoder));
+            fractal_view.render_julia_inset(&frame.texture, &mut encoder);
 
             // And then iced on top
             let iced_widget::renderer::Renderer::Wgpu(renderer) = widget_renderer else {
                 panic!("Not the right kind of renderer!")
             };
+            let iced_span = tracing::info_span!("iced_pass").entered();
             renderer.with_primitives(|backend, primitive| {
                 backend.present(
                     &gpu.device,
@@ -244,12 +715,14 @@ fn redraw(
                     gpu.texture_format,
                     &view,
                     primitive,
-                    &viewport,
+                    viewport,
                     &debug.overlay(),
                 );
             });
+            drop(iced_span);
 
             // Then we submit the work
+            let _present_span = tracing::info_span!("present").entered();
             staging_belt.finish();
             gpu.queue.submit(Some(encoder.finish()));
             frame.present();
@@ -263,44 +736,93 @@ fn redraw(
             staging_belt.recall();
         }
         Err(error) => match error {
-            wgpu::SurfaceError::OutOfMemory => {
-                panic!("Swapchain error: {error}. Rendering cannot continue.")
-            }
+            wgpu::SurfaceError::OutOfMemory => return Err(Error::OutOfMemory),
             _ => {
                 // Try rendering again next frame.
-                window.request_redraw();
+                scheduler.invalidate(Change::Window);
             }
         },
     }
+    Ok(())
+}
+
+/// Describes the window to iced, whose controls are scaled by the display's scale factor times
+/// `ui_scale`.
+fn create_viewport(window: &winit::window::Window, ui_scale: f64) -> Viewport {
+    let size = window.inner_size();
+    Viewport::with_physical_size(
+        Size::new(size.width, size.height),
+        window.scale_factor() * ui_scale,
+    )
 }
 
 #[cfg(target_arch = "wasm32")]
-fn init_logging() {
+fn init_logging() -> TraceGuard {
     console_log::init_with_level(log::Level::Debug).expect("could not initialize logger");
     std::panic::set_hook(Box::new(console_error_panic_hook::hook));
+    TraceGuard {}
+}
+
+/// Completes the trace file, if one is being written, when dropped at the end of `main`.
+struct TraceGuard {
+    #[cfg(feature = "chrome-trace")]
+    _flush_guard: tracing_chrome::FlushGuard,
 }
 
+/// Logs to stderr, filtered by `RUST_LOG`, and with the `chrome-trace` feature additionally
+/// records all spans to a trace file, which is completed when the returned guard is dropped.
 #[cfg(not(target_arch = "wasm32"))]
-fn init_logging() {
-    env_logger::init();
+fn init_logging() -> TraceGuard {
+    use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilter, Layer};
+
+    let fmt_layer = tracing_subscriber::fmt::layer().with_filter(EnvFilter::from_default_env());
+    #[cfg(feature = "chrome-trace")]
+    let (chrome_layer, flush_guard) = tracing_chrome::ChromeLayerBuilder::new().build();
+    #[cfg(not(feature = "chrome-trace"))]
+    let chrome_layer = tracing_subscriber::layer::Identity::new();
+    tracing_subscriber::registry()
+        .with(fmt_layer)
+        .with(chrome_layer)
+        .init();
+    TraceGuard {
+        #[cfg(feature = "chrome-trace")]
+        _flush_guard: flush_guard,
+    }
 }
 
+/// Lets the canvas fill the element which contains it, so that the explorer can be embedded in
+/// responsive layouts. The rule matches with zero specificity, so that any rule of the page which
+/// sizes the canvas takes precedence. Touches go to the explorer rather than scrolling or zooming
+/// the page.
 #[cfg(target_arch = "wasm32")]
-fn create_window(event_loop: &EventLoop<()>) -> iced_winit::winit::window::Window {
-    let canvas_element = {
-        web_sys::window()
-            .and_then(|win| win.document())
-            .and_then(|doc| doc.get_element_by_id("iced_canvas"))
-            .and_then(|element| element.dyn_into::<HtmlCanvasElement>().ok())
-            .expect("Canvas with id `iced_canvas` is missing")
-    };
-    winit::window::WindowBuilder::new()
+const DEFAULT_CANVAS_STYLE: &str =
+    ":where(#iced_canvas) { display: block; width: 100%; height: 100%; touch-action: none; }";
+
+/// Renders into the canvas with the id `iced_canvas`.
+///
+/// Winit observes the size of the canvas in device pixels and reports changes to it and to the
+/// device pixel ratio as resize and scale factor events, upon which the surface is reconfigured to
+/// match.
+#[cfg(target_arch = "wasm32")]
+fn create_window(event_loop: &EventLoop<()>) -> Result<iced_winit::winit::window::Window, Error> {
+    let document = web_sys::window()
+        .and_then(|win| win.document())
+        .ok_or(Error::MissingCanvas)?;
+    let canvas_element = document
+        .get_element_by_id("iced_canvas")
+        .and_then(|element| element.dyn_into::<HtmlCanvasElement>().ok())
+        .ok_or(Error::MissingCanvas)?;
+    if let (Ok(style), Some(head)) = (document.create_element("style"), document.head()) {
+        style.set_text_content(Some(DEFAULT_CANVAS_STYLE));
+        // Earlier rules lose ties against those of the page.
+        let _ = head.prepend_with_node_1(&style);
+    }
+    Ok(winit::window::WindowBuilder::new()
         .with_canvas(Some(canvas_element))
-        .build(event_loop)
-        .expect("Failed to build winit window")
+        .build(event_loop)?)
 }
 
 #[cfg(not(target_arch = "wasm32"))]
-fn create_window(event_loop: &EventLoop<()>) -> iced_winit::winit::window::Window {
-    winit::window::Window::new(event_loop).unwrap()
+fn create_window(event_loop: &EventLoop<()>) -> Result<iced_winit::winit::window::Window, Error> {
+    Ok(winit::window::Window::new(event_loop)?)
 }
@@ -0,0 +1,137 @@
+use crate::transform::{self, ViewTransform};
+use cgmath::{InnerSpace, Vector2};
+use iced::{Color, Point, Vector};
+use iced_widget::canvas::{Frame, Path, Stroke, Text};
+
+const LINE_COLOUR: Color = Color::from_rgb(1.0, 0.85, 0.2);
+
+/// The length of the ticks across the ends of the line, in logical pixels.
+const TICK_LENGTH: f32 = 10.0;
+
+/// How far the label is written above the middle of the line, in logical pixels.
+const LABEL_OFFSET: f32 = 8.0;
+
+/// Two points of the plane between which the distance and the angle are measured.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(super) struct Measurement {
+    pub(super) start: Vector2<f64>,
+    pub(super) end: Vector2<f64>,
+}
+
+impl Measurement {
+    pub(super) fn distance(&self) -> f64 {
+        (self.end - self.start).magnitude()
+    }
+
+    /// The angle of the line from the start to the end, anticlockwise from the positive real axis,
+    /// in degrees between -180 and 180.
+    pub(super) fn angle(&self) -> f64 {
+        let difference = self.end - self.start;
+        difference.y.atan2(difference.x).to_degrees()
+    }
+
+    /// The distance and angle on one line, as written next to the line.
+    pub(super) fn label(&self) -> String {
+        format!("{:.4e} at {:.1}°", self.distance(), self.angle())
+    }
+
+    /// The difference, distance and angle, as listed in the controls.
+    pub(super) fn describe(&self) -> String {
+        let difference = self.end - self.start;
+        format!(
+            "Δ {:.4e}{:+.4e}i\nDistance {:.4e}\nAngle {:.1}°",
+            difference.x,
+            difference.y,
+            self.distance(),
+            self.angle()
+        )
+    }
+
+    /// Draws the line between the points with ticks across its ends, and its label above its
+    /// middle, onto a frame which shows the plane through the given transform.
+    pub(super) fn draw(&self, frame: &mut Frame, transform: &ViewTransform) {
+        let size = frame.size();
+        let to_frame = |point| transform::to_screen(transform.offset_of(point), size);
+        let (start, end) = (to_frame(self.start), to_frame(self.end));
+        let stroke = Stroke::default().with_color(LINE_COLOUR).with_width(2.0);
+        frame.stroke(&Path::line(start, end), stroke.clone());
+        let normal = normal(start, end);
+        for point in [start, end] {
+            frame.stroke(
+                &Path::line(
+                    point + normal * (TICK_LENGTH / 2.0),
+                    point - normal * (TICK_LENGTH / 2.0),
+                ),
+                stroke.clone(),
+            );
+        }
+        let middle = Point::new((start.x + end.x) / 2.0, (start.y + end.y) / 2.0);
+        // The normal points to the left of the line, which is above it unless the line runs from
+        // right to left.
+        let above = if normal.y <= 0.0 {
+            normal
+        } else {
+            normal * -1.0
+        };
+        frame.fill_text(Text {
+            content: self.label(),
+            position: middle + above * LABEL_OFFSET,
+            color: LINE_COLOUR,
+            horizontal_alignment: iced::alignment::Horizontal::Center,
+            vertical_alignment: iced::alignment::Vertical::Bottom,
+            ..Text::default()
+        });
+    }
+}
+
+/// The unit vector at right angles to the line from `start` to `end`, or a vertical one if the
+/// points coincide.
+fn normal(start: Point, end: Point) -> Vector {
+    let direction = end - start;
+    let length = direction.x.hypot(direction.y);
+    if length == 0.0 {
+        return Vector::new(0.0, -1.0);
+    }
+    Vector::new(direction.y / length, -direction.x / length)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{normal, Measurement};
+    use cgmath::Vector2;
+    use googletest::prelude::*;
+    use iced::Point;
+
+    #[test]
+    fn measures_distance_and_angle_in_plane_units() -> Result<()> {
+        let measurement = Measurement {
+            start: Vector2::new(-0.75, 0.1),
+            end: Vector2::new(-0.75 - 3e-9, 0.1 + 4e-9),
+        };
+
+        verify_that!(
+            (measurement.distance(), measurement.angle()),
+            (near(5e-9, 1e-15), near(126.87, 0.01))
+        )
+    }
+
+    #[test]
+    fn labels_distance_in_scientific_notation() -> Result<()> {
+        let measurement = Measurement {
+            start: Vector2::new(0.0, 0.0),
+            end: Vector2::new(0.0, -0.25),
+        };
+
+        verify_that!(measurement.label(), eq("2.5000e-1 at -90.0°"))
+    }
+
+    #[test]
+    fn normal_is_unit_length_and_perpendicular() -> Result<()> {
+        let normal = normal(Point::new(10.0, 10.0), Point::new(40.0, 50.0));
+
+        verify_that!(
+            (normal.x * 30.0 + normal.y * 40.0, normal.x.hypot(normal.y)),
+            (approx_eq(0.0), approx_eq(1.0))
+        )
+    }
+}
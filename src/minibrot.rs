@@ -0,0 +1,229 @@
+use crate::{
+    double_double::DoubleDouble,
+    jobs::{Job, Progress},
+    locator::{self, LandmarkKind},
+};
+use cgmath::{InnerSpace, Vector2};
+use serde::{Deserialize, Serialize};
+
+const POLISH_STEPS: u32 = 8;
+const TOLERANCE: f64 = 1e-30;
+
+/// The centre and half width of the initial view of the whole set, by which a minibrot is framed
+/// alike once scaled and rotated by its size.
+const WHOLE_SET_CENTRE: Vector2<f64> = Vector2::new(-0.5, 0.0);
+const WHOLE_SET_HALF_WIDTH: f64 = 2.0;
+
+/// A small copy of the Mandelbrot set, which surrounds the centre (or nucleus) of a hyperbolic
+/// component of the given period.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub(super) struct Minibrot {
+    nucleus: Complex,
+    pub(super) period: u32,
+    /// The complex factor by which the copy is scaled and rotated relative to the whole set.
+    pub(super) size: Vector2<f64>,
+}
+
+impl Minibrot {
+    pub(super) fn nucleus(&self) -> Vector2<f64> {
+        Vector2::new(self.nucleus.re.to_f64(), self.nucleus.im.to_f64())
+    }
+
+    /// Returns the centre and half width of the view which shows the minibrot as the initial view
+    /// shows the whole set.
+    pub(super) fn framing(&self) -> (Vector2<f64>, f64) {
+        let offset = Vector2::new(
+            self.size.x * WHOLE_SET_CENTRE.x - self.size.y * WHOLE_SET_CENTRE.y,
+            self.size.x * WHOLE_SET_CENTRE.y + self.size.y * WHOLE_SET_CENTRE.x,
+        );
+        (
+            self.nucleus() + offset,
+            WHOLE_SET_HALF_WIDTH * self.size.magnitude(),
+        )
+    }
+}
+
+/// Runs [`find_nearest`] on the worker pool.
+#[derive(Serialize, Deserialize)]
+pub(super) struct FindMinibrot {
+    pub(super) near: Vector2<f64>,
+    pub(super) radius: f64,
+}
+
+impl Job for FindMinibrot {
+    const NAME: &'static str = "Finding minibrot";
+    type Output = Option<Minibrot>;
+
+    fn run(self, _: &Progress) -> Option<Minibrot> {
+        find_nearest(self.near, self.radius)
+    }
+}
+
+/// Finds the minibrot whose nucleus lies closest to `near`, considering only those within
+/// `radius`.
+///
+/// The nucleus is located in `f64` by [`locator::find_nearest`] and then polished by Newton's
+/// method on the nucleus equation `z_period(c) = 0` in double-double precision, so that it stays
+/// accurate at magnifications where the minibrot is much smaller than the precision of `f64`.
+pub(super) fn find_nearest(near: Vector2<f64>, radius: f64) -> Option<Minibrot> {
+    let landmark = locator::find_nearest(LandmarkKind::Center, near, radius)?;
+    let nucleus = polish(Complex::from(landmark.position), landmark.period);
+    Some(Minibrot {
+        nucleus,
+        period: landmark.period,
+        size: size(nucleus, landmark.period),
+    })
+}
+
+fn polish(mut c: Complex, period: u32) -> Complex {
+    for _ in 0..POLISH_STEPS {
+        let mut z = Complex::default();
+        let mut dz = Complex::default();
+        for _ in 0..period {
+            dz = (z * dz).scale(2.0) + Complex::from(Vector2::new(1.0, 0.0));
+            z = z * z + c;
+        }
+        let Some(step) = z.div(dz) else {
+            break;
+        };
+        c = c - step;
+        if step.magnitude() <= TOLERANCE * c.magnitude().max(1.0) {
+            break;
+        }
+    }
+    c
+}
+
+/// Estimates the size of the minibrot around the nucleus `c` from the multipliers of its critical
+/// orbit, after Claude Heiland-Allen's "Practical interior distance rendering".
+fn size(c: Complex, period: u32) -> Vector2<f64> {
+    let c = Vector2::new(c.re.to_f64(), c.im.to_f64());
+    let mut z = Vector2::new(0.0, 0.0);
+    let mut multiplier = Vector2::new(1.0, 0.0);
+    let mut sum = Vector2::new(1.0, 0.0);
+    for _ in 1..period {
+        z = mul(z, z) + c;
+        multiplier = 2.0 * mul(z, multiplier);
+        sum += reciprocal(multiplier);
+    }
+    reciprocal(mul(sum, mul(multiplier, multiplier)))
+}
+
+fn mul(a: Vector2<f64>, b: Vector2<f64>) -> Vector2<f64> {
+    Vector2::new(a.x * b.x - a.y * b.y, a.x * b.y + a.y * b.x)
+}
+
+fn reciprocal(a: Vector2<f64>) -> Vector2<f64> {
+    Vector2::new(a.x, -a.y) / a.magnitude2()
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+struct Complex {
+    re: DoubleDouble,
+    im: DoubleDouble,
+}
+
+impl Complex {
+    fn scale(self, factor: f64) -> Self {
+        Self {
+            re: self.re * factor.into(),
+            im: self.im * factor.into(),
+        }
+    }
+
+    fn div(self, other: Self) -> Option<Self> {
+        let denominator = other.re * other.re + other.im * other.im;
+        (denominator.to_f64() > 0.0).then(|| Self {
+            re: (self.re * other.re + self.im * other.im) / denominator,
+            im: (self.im * other.re - self.re * other.im) / denominator,
+        })
+    }
+
+    /// An approximation which suffices for tolerances.
+    fn magnitude(self) -> f64 {
+        self.re.to_f64().hypot(self.im.to_f64())
+    }
+}
+
+impl From<Vector2<f64>> for Complex {
+    fn from(value: Vector2<f64>) -> Self {
+        Self {
+            re: value.x.into(),
+            im: value.y.into(),
+        }
+    }
+}
+
+impl std::ops::Add for Complex {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Self {
+            re: self.re + other.re,
+            im: self.im + other.im,
+        }
+    }
+}
+
+impl std::ops::Sub for Complex {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self {
+        Self {
+            re: self.re - other.re,
+            im: self.im - other.im,
+        }
+    }
+}
+
+impl std::ops::Mul for Complex {
+    type Output = Self;
+
+    fn mul(self, other: Self) -> Self {
+        Self {
+            re: self.re * other.re - self.im * other.im,
+            im: self.re * other.im + self.im * other.re,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{find_nearest, Complex};
+    use cgmath::{InnerSpace, Vector2};
+    use googletest::prelude::*;
+
+    #[test]
+    fn finds_period_three_minibrot_on_real_axis() -> Result<()> {
+        let minibrot =
+            find_nearest(Vector2::new(-1.7545, 0.0002), 1e-3).expect("Minibrot not found");
+        let (centre, half_width) = minibrot.framing();
+
+        verify_that!(
+            (
+                minibrot.period,
+                minibrot.size.magnitude(),
+                centre.x,
+                half_width
+            ),
+            (
+                eq(3),
+                near(0.019, 0.001),
+                lt(minibrot.nucleus().x),
+                near(0.038, 0.002)
+            )
+        )
+    }
+
+    #[test]
+    fn polished_nucleus_solves_nucleus_equation_beyond_f64() -> Result<()> {
+        let minibrot =
+            find_nearest(Vector2::new(-1.7545, 0.0002), 1e-3).expect("Minibrot not found");
+        let mut z = Complex::default();
+        for _ in 0..minibrot.period {
+            z = z * z + minibrot.nucleus;
+        }
+
+        verify_that!(z.magnitude(), lt(1e-25))
+    }
+}
@@ -0,0 +1,320 @@
+use crate::{controls::FractalType, fractal_definition::MAX_PARAMETERS};
+use std::{f32::consts::TAU, fmt::Display};
+
+/// The number of LFOs in the modulation matrix.
+pub(super) const MODULATION_SLOTS: usize = 3;
+
+/// The zoom at full depth, as a fraction of the view's size.
+const MAX_ZOOM_MODULATION: f32 = 0.5;
+
+/// The shape of an LFO's oscillation.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(super) enum Waveform {
+    #[default]
+    Sine,
+    Triangle,
+    /// Smoothly interpolated random values, one per cycle.
+    Noise,
+}
+
+impl Waveform {
+    pub(super) const ALL: [Waveform; 3] = [Self::Sine, Self::Triangle, Self::Noise];
+
+    /// Returns the value of the waveform in [-1, 1] after the given number of cycles.
+    fn sample(self, cycles: f32, seed: u32) -> f32 {
+        match self {
+            Waveform::Sine => (TAU * cycles).sin(),
+            Waveform::Triangle => 1.0 - 4.0 * ((cycles + 0.25).rem_euclid(1.0) - 0.5).abs(),
+            Waveform::Noise => {
+                let cycle = cycles.floor();
+                let t = cycles - cycle;
+                let smooth = t * t * (3.0 - 2.0 * t);
+                let start = noise(seed, cycle as i32);
+                start + (noise(seed, cycle as i32 + 1) - start) * smooth
+            }
+        }
+    }
+}
+
+impl Display for Waveform {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Waveform::Sine => write!(f, "Sine"),
+            Waveform::Triangle => write!(f, "Triangle"),
+            Waveform::Noise => write!(f, "Noise"),
+        }
+    }
+}
+
+/// Returns a pseudo-random value in [-1, 1] which depends only on the arguments.
+fn noise(seed: u32, cycle: i32) -> f32 {
+    let mut hash = seed.wrapping_mul(0x9e37_79b9) ^ (cycle as u32).wrapping_mul(0x85eb_ca6b);
+    hash ^= hash >> 16;
+    hash = hash.wrapping_mul(0x7feb_352d);
+    hash ^= hash >> 15;
+    hash = hash.wrapping_mul(0x846c_a68b);
+    hash ^= hash >> 16;
+    hash as f32 / u32::MAX as f32 * 2.0 - 1.0
+}
+
+/// What an LFO drives.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(super) enum ModulationTarget {
+    #[default]
+    Off,
+    /// Rotates the palette by up to a full turn at full depth.
+    PaletteOffset,
+    /// Zooms in and out on the centre of the view.
+    Zoom,
+    /// Moves a parameter of the current fractal over up to its full range at full depth.
+    Parameter { index: usize, name: &'static str },
+}
+
+impl ModulationTarget {
+    /// Returns the targets which exist for the given fractal.
+    pub(super) fn available(fractal_type: FractalType) -> Vec<Self> {
+        let parameters = fractal_type.definition().parameters.iter().enumerate();
+        [Self::Off, Self::PaletteOffset, Self::Zoom]
+            .into_iter()
+            .chain(parameters.map(|(index, parameter)| Self::Parameter {
+                index,
+                name: parameter.name,
+            }))
+            .collect()
+    }
+}
+
+impl Display for ModulationTarget {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ModulationTarget::Off => write!(f, "Off"),
+            ModulationTarget::PaletteOffset => write!(f, "Palette offset"),
+            ModulationTarget::Zoom => write!(f, "Zoom"),
+            ModulationTarget::Parameter { name, .. } => write!(f, "{name}"),
+        }
+    }
+}
+
+/// A low-frequency oscillator and the target which it drives, making up one row of the
+/// modulation matrix.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(super) struct Lfo {
+    pub(super) target: ModulationTarget,
+    pub(super) waveform: Waveform,
+    /// The number of cycles per second.
+    pub(super) frequency: f32,
+    /// The amplitude as a fraction of the target's range, from 0 to 1.
+    pub(super) depth: f32,
+}
+
+impl Default for Lfo {
+    fn default() -> Self {
+        Self {
+            target: ModulationTarget::Off,
+            waveform: Waveform::Sine,
+            frequency: 0.25,
+            depth: 0.25,
+        }
+    }
+}
+
+/// Sweeps a parameter of the current fractal back and forth over its whole range, easing in and
+/// out at either end, regardless of the value which the user set.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(super) struct Sweep {
+    /// The index of the parameter in the fractal's definition.
+    pub(super) index: usize,
+    /// The name of the parameter, so that the sweep ends when switching to a fractal without it.
+    pub(super) name: &'static str,
+    /// The number of sweeps there and back per second.
+    pub(super) speed: f32,
+}
+
+impl Sweep {
+    /// Returns the position of the parameter within its range, from 0 at its minimum to 1 at its
+    /// maximum, after the given number of sweeps there and back.
+    pub(super) fn position(cycles: f32) -> f32 {
+        0.5 - 0.5 * (TAU * cycles).cos()
+    }
+}
+
+/// How far the modulated values are moved from the values which the user set.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub(super) struct ModulationOffsets {
+    /// In turns of the palette.
+    pub(super) palette_offset: f32,
+    /// As a fraction of the view's size, with positive values zooming in.
+    pub(super) zoom: f32,
+    /// As fractions of the range of each of the fractal's parameters, by index.
+    pub(super) parameters: [f32; MAX_PARAMETERS],
+    /// The index of the swept parameter and its position within its range, which replaces the
+    /// value which the user set.
+    pub(super) sweep: Option<(usize, f32)>,
+}
+
+/// The LFOs which drive the parameters of the view, so that it animates by itself.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub(super) struct ModulationMatrix {
+    pub(super) lfos: [Lfo; MODULATION_SLOTS],
+    pub(super) sweep: Option<Sweep>,
+}
+
+impl ModulationMatrix {
+    pub(super) fn is_active(&self) -> bool {
+        self.sweep.is_some()
+            || self
+                .lfos
+                .iter()
+                .any(|lfo| lfo.target != ModulationTarget::Off && lfo.depth > 0.0)
+    }
+
+    /// Returns the sum of the LFOs on each target at the given time since they started. The noise
+    /// of each LFO follows from the seed and its slot, so that it repeats with the same seed and
+    /// no two seeds share the noise of any LFO.
+    pub(super) fn evaluate(&self, seconds: f32, seed: u32) -> ModulationOffsets {
+        let mut offsets = ModulationOffsets::default();
+        for (slot, lfo) in self.lfos.iter().enumerate() {
+            let seed = seed
+                .wrapping_mul(MODULATION_SLOTS as u32)
+                .wrapping_add(slot as u32);
+            let value = lfo.depth * lfo.waveform.sample(seconds * lfo.frequency, seed);
+            match lfo.target {
+                ModulationTarget::Off => {}
+                ModulationTarget::PaletteOffset => offsets.palette_offset += value,
+                ModulationTarget::Zoom => offsets.zoom += value * MAX_ZOOM_MODULATION,
+                ModulationTarget::Parameter { index, .. } => offsets.parameters[index] += value,
+            }
+        }
+        offsets.sweep = self
+            .sweep
+            .map(|sweep| (sweep.index, Sweep::position(seconds * sweep.speed)));
+        offsets
+    }
+
+    /// Switches off the LFOs whose targets and the sweep whose parameter do not exist for the
+    /// given fractal.
+    pub(super) fn retain_targets(&mut self, fractal_type: FractalType) {
+        let available = ModulationTarget::available(fractal_type);
+        for lfo in &mut self.lfos {
+            if !available.contains(&lfo.target) {
+                lfo.target = ModulationTarget::Off;
+            }
+        }
+        let parameters = fractal_type.definition().parameters;
+        self.sweep = self.sweep.filter(|sweep| {
+            parameters
+                .get(sweep.index)
+                .is_some_and(|parameter| parameter.name == sweep.name)
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Lfo, ModulationMatrix, ModulationTarget, Sweep, Waveform};
+    use crate::{controls::FractalType, fractal_definition::EXPONENT};
+    use googletest::prelude::*;
+
+    #[test]
+    fn waveforms_stay_within_unit_range() -> Result<()> {
+        for waveform in Waveform::ALL {
+            for step in 0..200 {
+                let value = waveform.sample(step as f32 * 0.037, 1);
+                verify_that!((waveform, value), (anything(), ge(-1.0)))?;
+                verify_that!((waveform, value), (anything(), le(1.0)))?;
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn triangle_peaks_at_quarter_cycle() -> Result<()> {
+        verify_that!(
+            (
+                Waveform::Triangle.sample(0.0, 0),
+                Waveform::Triangle.sample(0.25, 0),
+                Waveform::Triangle.sample(0.75, 0)
+            ),
+            (approx_eq(0.0), approx_eq(1.0), approx_eq(-1.0))
+        )
+    }
+
+    #[test]
+    fn lfos_on_same_target_are_summed() -> Result<()> {
+        let lfo = Lfo {
+            target: ModulationTarget::PaletteOffset,
+            waveform: Waveform::Sine,
+            frequency: 1.0,
+            depth: 0.25,
+        };
+        let matrix = ModulationMatrix {
+            lfos: [lfo, lfo, Lfo::default()],
+            sweep: None,
+        };
+
+        verify_that!(matrix.evaluate(0.25, 0).palette_offset, approx_eq(0.5))
+    }
+
+    #[test]
+    fn noise_repeats_with_same_seed_only() -> Result<()> {
+        let mut matrix = ModulationMatrix::default();
+        matrix.lfos[0] = Lfo {
+            target: ModulationTarget::PaletteOffset,
+            waveform: Waveform::Noise,
+            frequency: 1.0,
+            depth: 1.0,
+        };
+        let offset = |seed| matrix.evaluate(2.5, seed).palette_offset;
+
+        verify_that!(offset(7), eq(offset(7)))?;
+        verify_that!(offset(7), not(eq(offset(8))))
+    }
+
+    #[test]
+    fn switching_fractal_turns_off_lfos_on_missing_parameters() -> Result<()> {
+        let mut matrix = ModulationMatrix::default();
+        matrix.lfos[0].target = ModulationTarget::available(FractalType::Newton)[3];
+        matrix.lfos[1].target = ModulationTarget::Zoom;
+
+        matrix.retain_targets(FractalType::Mandelbrot);
+
+        verify_that!(
+            (matrix.lfos[0].target, matrix.lfos[1].target),
+            (eq(ModulationTarget::Off), eq(ModulationTarget::Zoom))
+        )
+    }
+
+    #[test]
+    fn sweep_eases_from_minimum_to_maximum_and_back() -> Result<()> {
+        verify_that!(
+            (
+                Sweep::position(0.0),
+                Sweep::position(0.25),
+                Sweep::position(0.5),
+                Sweep::position(1.0)
+            ),
+            (
+                near(0.0, 1e-6),
+                near(0.5, 1e-6),
+                near(1.0, 1e-6),
+                near(0.0, 1e-6)
+            )
+        )
+    }
+
+    #[test]
+    fn switching_fractal_ends_sweep_of_missing_parameter() -> Result<()> {
+        let mut matrix = ModulationMatrix {
+            sweep: Some(Sweep {
+                index: 0,
+                name: EXPONENT,
+                speed: 0.1,
+            }),
+            ..Default::default()
+        };
+
+        matrix.retain_targets(FractalType::Newton);
+
+        verify_that!(matrix.sweep, none())
+    }
+}
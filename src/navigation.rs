@@ -0,0 +1,176 @@
+use cgmath::{InnerSpace, Vector2};
+use iced::{
+    widget::canvas::{Frame, Path, Stroke},
+    Color, Point, Size, Vector,
+};
+
+/// The radius of the base of the joystick, which is how far its knob can be pushed.
+const JOYSTICK_RADIUS: f32 = 40.0;
+const KNOB_RADIUS: f32 = 16.0;
+const BUTTON_RADIUS: f32 = 18.0;
+/// The distance of the controls from the edges of the canvas and from each other.
+const MARGIN: f32 = 20.0;
+/// The half length of the strokes of the symbols on the buttons.
+const SYMBOL_SIZE: f32 = 7.0;
+
+const BACKGROUND: Color = Color {
+    a: 0.4,
+    ..Color::BLACK
+};
+
+/// A button of the on-screen navigation.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(super) enum NavigationButton {
+    ZoomIn,
+    ZoomOut,
+    /// Returns to the initial view.
+    Home,
+}
+
+/// What lies under a point of the canvas.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(super) enum Target {
+    Joystick,
+    Button(NavigationButton),
+}
+
+/// Where the on-screen navigation lies on the canvas: a joystick in the bottom right corner, which
+/// pans the view, and a column of buttons above it.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(super) struct NavigationLayout {
+    joystick: Point,
+    buttons: [(NavigationButton, Point); 3],
+}
+
+impl NavigationLayout {
+    pub(super) fn within(size: Size) -> Self {
+        let joystick = Point::new(
+            size.width - MARGIN - JOYSTICK_RADIUS,
+            size.height - MARGIN - JOYSTICK_RADIUS,
+        );
+        let button = |index: f32| {
+            Point::new(
+                joystick.x,
+                joystick.y - JOYSTICK_RADIUS - (MARGIN / 2.0 + BUTTON_RADIUS) * (2.0 * index + 1.0),
+            )
+        };
+        Self {
+            joystick,
+            buttons: [
+                (NavigationButton::ZoomOut, button(0.0)),
+                (NavigationButton::ZoomIn, button(1.0)),
+                (NavigationButton::Home, button(2.0)),
+            ],
+        }
+    }
+
+    /// Returns the control at the given point of the canvas, if any.
+    pub(super) fn target_at(&self, point: Point) -> Option<Target> {
+        if point.distance(self.joystick) <= JOYSTICK_RADIUS {
+            return Some(Target::Joystick);
+        }
+        self.buttons
+            .iter()
+            .find(|(_, centre)| point.distance(*centre) <= BUTTON_RADIUS)
+            .map(|(button, _)| Target::Button(*button))
+    }
+
+    /// How far the joystick is pushed while held at the given point of the canvas, up to one in
+    /// any direction, with up being positive.
+    pub(super) fn deflection(&self, point: Point) -> Vector2<f32> {
+        let offset = Vector2::new(point.x - self.joystick.x, self.joystick.y - point.y);
+        let deflection = offset / JOYSTICK_RADIUS;
+        if deflection.magnitude2() > 1.0 {
+            deflection.normalize()
+        } else {
+            deflection
+        }
+    }
+
+    /// Draws the joystick with its knob pushed by the given deflection, and the buttons.
+    pub(super) fn draw(&self, frame: &mut Frame, deflection: Vector2<f32>) {
+        let stroke = Stroke::default().with_color(Color::WHITE).with_width(2.0);
+        let base = Path::circle(self.joystick, JOYSTICK_RADIUS);
+        frame.fill(&base, BACKGROUND);
+        frame.stroke(&base, stroke.clone());
+        let knob = self.joystick
+            + Vector::new(deflection.x, -deflection.y) * (JOYSTICK_RADIUS - KNOB_RADIUS);
+        frame.fill(&Path::circle(knob, KNOB_RADIUS), Color::WHITE);
+
+        for (button, centre) in self.buttons {
+            let circle = Path::circle(centre, BUTTON_RADIUS);
+            frame.fill(&circle, BACKGROUND);
+            frame.stroke(&circle, stroke.clone());
+            let symbol = Path::new(|builder| {
+                let at = |x: f32, y: f32| centre + Vector::new(x * SYMBOL_SIZE, y * SYMBOL_SIZE);
+                match button {
+                    NavigationButton::ZoomIn | NavigationButton::ZoomOut => {
+                        builder.move_to(at(-1.0, 0.0));
+                        builder.line_to(at(1.0, 0.0));
+                        if button == NavigationButton::ZoomIn {
+                            builder.move_to(at(0.0, -1.0));
+                            builder.line_to(at(0.0, 1.0));
+                        }
+                    }
+                    NavigationButton::Home => {
+                        // A house: a roof over the walls.
+                        builder.move_to(at(-1.2, 0.0));
+                        builder.line_to(at(0.0, -1.2));
+                        builder.line_to(at(1.2, 0.0));
+                        builder.move_to(at(-0.8, -0.3));
+                        builder.line_to(at(-0.8, 1.0));
+                        builder.line_to(at(0.8, 1.0));
+                        builder.line_to(at(0.8, -0.3));
+                    }
+                }
+            });
+            frame.stroke(&symbol, stroke.clone());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{NavigationButton, NavigationLayout, Target, JOYSTICK_RADIUS};
+    use googletest::prelude::*;
+    use iced::{Point, Size};
+
+    #[test]
+    fn finds_joystick_and_buttons_in_corner() -> Result<()> {
+        let layout = NavigationLayout::within(Size::new(800.0, 600.0));
+        let joystick = layout.joystick;
+        let (_, zoom_out) = layout.buttons[0];
+
+        verify_that!(
+            (
+                layout.target_at(joystick),
+                layout.target_at(zoom_out),
+                layout.target_at(Point::new(400.0, 300.0))
+            ),
+            (
+                some(eq(Target::Joystick)),
+                some(eq(Target::Button(NavigationButton::ZoomOut))),
+                none()
+            )
+        )
+    }
+
+    #[test]
+    fn deflection_points_up_and_is_limited_to_one() -> Result<()> {
+        let layout = NavigationLayout::within(Size::new(800.0, 600.0));
+        let centre = layout.joystick;
+
+        let half_up = layout.deflection(Point::new(centre.x, centre.y - JOYSTICK_RADIUS / 2.0));
+        let far_right = layout.deflection(Point::new(centre.x + 3.0 * JOYSTICK_RADIUS, centre.y));
+
+        verify_that!(
+            (half_up.x, half_up.y, far_right.x, far_right.y),
+            (
+                approx_eq(0.0),
+                approx_eq(0.5),
+                approx_eq(1.0),
+                approx_eq(0.0)
+            )
+        )
+    }
+}
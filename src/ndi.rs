@@ -0,0 +1,214 @@
+use crate::{export, fractal_view::View, gpu::Gpu};
+use iced_wgpu::wgpu;
+use libloading::Library;
+use std::{
+    ffi::{c_char, c_int, c_void, CString},
+    path::PathBuf,
+};
+
+/// The name under which the explorer appears in NDI receivers such as OBS or Resolume.
+const SOURCE_NAME: &str = "fractal_explorer";
+
+/// The file names of the NDI runtime, newest version first.
+#[cfg(target_os = "windows")]
+const RUNTIME_NAMES: &[&str] = &["Processing.NDI.Lib.x64.dll"];
+#[cfg(target_os = "macos")]
+const RUNTIME_NAMES: &[&str] = &["libndi.dylib"];
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+const RUNTIME_NAMES: &[&str] = &["libndi.so.6", "libndi.so.5"];
+
+/// The environment variables with which the NDI installers point at the runtime's directory.
+const RUNTIME_DIR_VARIABLES: &[&str] = &["NDI_RUNTIME_DIR_V6", "NDI_RUNTIME_DIR_V5"];
+
+const fn four_cc(code: &[u8; 4]) -> u32 {
+    u32::from_le_bytes(*code)
+}
+
+const FOURCC_RGBA: u32 = four_cc(b"RGBA");
+const FOURCC_BGRA: u32 = four_cc(b"BGRA");
+const FRAME_FORMAT_PROGRESSIVE: c_int = 1;
+/// Asks the SDK to derive the timecode from the time at which the frame is sent.
+const TIMECODE_SYNTHESIZE: i64 = i64::MAX;
+
+/// `NDIlib_send_create_t` of the NDI SDK.
+#[repr(C)]
+struct SendCreate {
+    ndi_name: *const c_char,
+    groups: *const c_char,
+    clock_video: bool,
+    clock_audio: bool,
+}
+
+/// `NDIlib_video_frame_v2_t` of the NDI SDK.
+#[repr(C)]
+struct VideoFrame {
+    xres: c_int,
+    yres: c_int,
+    four_cc: u32,
+    frame_rate_n: c_int,
+    frame_rate_d: c_int,
+    picture_aspect_ratio: f32,
+    frame_format_type: c_int,
+    timecode: i64,
+    data: *const u8,
+    line_stride_in_bytes: c_int,
+    metadata: *const c_char,
+    timestamp: i64,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub(super) enum NdiError {
+    #[error("The NDI runtime is not installed")]
+    RuntimeNotFound,
+    #[error("The NDI runtime is incompatible: {0}")]
+    IncompatibleRuntime(#[from] libloading::Error),
+    #[error("NDI is not supported on this CPU")]
+    Unsupported,
+    #[error("Could not create the NDI source")]
+    CreateSender,
+    #[error("NDI cannot send frames in format {0:?}")]
+    UnsupportedFormat(wgpu::TextureFormat),
+}
+
+/// Publishes the rendered fractal as an [NDI](https://ndi.video) source, so that it can be
+/// composited live in OBS, Resolume and the like.
+///
+/// The NDI runtime is loaded when the sender is created rather than linked, so that the explorer
+/// runs without it. Frames are read back from the GPU, which costs some frame rate at large window
+/// sizes. Spout and Syphon would avoid the readback by sharing the texture directly, but wgpu does
+/// not expose the native texture handles which they need.
+pub(super) struct NdiSender {
+    instance: *mut c_void,
+    four_cc: u32,
+    send_video: unsafe extern "C" fn(*mut c_void, *const VideoFrame),
+    send_destroy: unsafe extern "C" fn(*mut c_void),
+    destroy: unsafe extern "C" fn(),
+    /// Keeps the functions above loaded, so must be dropped last.
+    _library: Library,
+}
+
+impl NdiSender {
+    /// Loads the NDI runtime and announces the source on the network, sending frames rendered in
+    /// the given texture format.
+    pub(super) fn new(texture_format: wgpu::TextureFormat) -> Result<Self, NdiError> {
+        let four_cc = four_cc_for(texture_format)?;
+        let library = load_runtime()?;
+        // SAFETY: The signatures match the declarations in `Processing.NDI.Lib.h`, and the
+        // pointers passed to the SDK outlive the calls.
+        unsafe {
+            let initialize =
+                *library.get::<unsafe extern "C" fn() -> bool>(b"NDIlib_initialize")?;
+            let send_create = *library
+                .get::<unsafe extern "C" fn(*const SendCreate) -> *mut c_void>(
+                    b"NDIlib_send_create",
+                )?;
+            let send_video = *library.get(b"NDIlib_send_send_video_v2")?;
+            let send_destroy = *library.get(b"NDIlib_send_destroy")?;
+            let destroy = *library.get::<unsafe extern "C" fn()>(b"NDIlib_destroy")?;
+            if !initialize() {
+                return Err(NdiError::Unsupported);
+            }
+            let name = CString::new(SOURCE_NAME).unwrap();
+            let instance = send_create(&SendCreate {
+                ndi_name: name.as_ptr(),
+                groups: std::ptr::null(),
+                clock_video: false,
+                clock_audio: false,
+            });
+            if instance.is_null() {
+                destroy();
+                return Err(NdiError::CreateSender);
+            }
+            Ok(Self {
+                instance,
+                four_cc,
+                send_video,
+                send_destroy,
+                destroy,
+                _library: library,
+            })
+        }
+    }
+
+    /// Renders the view offscreen at the given size and sends the result as the next frame.
+    pub(super) fn send(&self, gpu: &Gpu, view: &View, width: u32, height: u32) {
+        let pixels = export::render_to_rgba(gpu, view, width, height);
+        let frame = VideoFrame {
+            xres: width as c_int,
+            yres: height as c_int,
+            four_cc: self.four_cc,
+            frame_rate_n: 60000,
+            frame_rate_d: 1000,
+            picture_aspect_ratio: width as f32 / height as f32,
+            frame_format_type: FRAME_FORMAT_PROGRESSIVE,
+            timecode: TIMECODE_SYNTHESIZE,
+            data: pixels.as_ptr(),
+            line_stride_in_bytes: (width * 4) as c_int,
+            metadata: std::ptr::null(),
+            timestamp: 0,
+        };
+        // SAFETY: The SDK copies the frame before returning, so the pixels need not outlive the
+        // call.
+        unsafe { (self.send_video)(self.instance, &frame) };
+    }
+}
+
+impl Drop for NdiSender {
+    fn drop(&mut self) {
+        // SAFETY: The instance was created by this runtime and is not used afterwards.
+        unsafe {
+            (self.send_destroy)(self.instance);
+            (self.destroy)();
+        }
+    }
+}
+
+fn load_runtime() -> Result<Library, NdiError> {
+    let directories = RUNTIME_DIR_VARIABLES
+        .iter()
+        .filter_map(std::env::var_os)
+        .map(PathBuf::from);
+    let candidates = directories
+        .flat_map(|directory| RUNTIME_NAMES.iter().map(move |name| directory.join(name)))
+        .chain(RUNTIME_NAMES.iter().map(PathBuf::from));
+    for candidate in candidates {
+        // SAFETY: The NDI runtime has no initialisers with preconditions.
+        if let Ok(library) = unsafe { Library::new(&candidate) } {
+            tracing::info!("Loaded the NDI runtime from {}", candidate.display());
+            return Ok(library);
+        }
+    }
+    Err(NdiError::RuntimeNotFound)
+}
+
+/// Returns the NDI pixel format matching the texture format in which the view is rendered.
+fn four_cc_for(texture_format: wgpu::TextureFormat) -> Result<u32, NdiError> {
+    match texture_format {
+        wgpu::TextureFormat::Rgba8Unorm | wgpu::TextureFormat::Rgba8UnormSrgb => Ok(FOURCC_RGBA),
+        wgpu::TextureFormat::Bgra8Unorm | wgpu::TextureFormat::Bgra8UnormSrgb => Ok(FOURCC_BGRA),
+        format => Err(NdiError::UnsupportedFormat(format)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{four_cc_for, VideoFrame};
+    use googletest::prelude::*;
+    use iced_wgpu::wgpu;
+
+    #[test]
+    fn four_cc_spells_pixel_format_in_memory_order() -> Result<()> {
+        verify_that!(
+            four_cc_for(wgpu::TextureFormat::Bgra8UnormSrgb)
+                .ok()
+                .map(u32::to_le_bytes),
+            some(eq(*b"BGRA"))
+        )
+    }
+
+    #[cfg(target_pointer_width = "64")]
+    #[test]
+    fn video_frame_matches_layout_of_sdk() -> Result<()> {
+        verify_that!(std::mem::size_of::<VideoFrame>(), eq(72))
+    }
+}
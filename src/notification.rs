@@ -0,0 +1,147 @@
+use crate::{jobs::JobStatus, throughput};
+use iced::time::Instant;
+use std::time::Duration;
+
+/// How long work must have run before its completion is announced, so that quick jobs do not
+/// interrupt.
+pub(super) const MIN_DURATION: Duration = Duration::from_secs(10);
+
+/// A job which finished after running for at least [`MIN_DURATION`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(super) struct Completion {
+    pub(super) name: &'static str,
+    pub(super) duration: Duration,
+}
+
+/// Watches the jobs running in the background for those which finish after a long run while the
+/// window is out of focus, whose completion is then worth a desktop notification.
+#[derive(Default)]
+pub(super) struct CompletionWatcher {
+    /// The name of each running job and when it was first seen.
+    running: Vec<(&'static str, Instant)>,
+}
+
+impl CompletionWatcher {
+    /// Takes note of the jobs running now and returns those which finished since the last call
+    /// and are worth announcing.
+    pub(super) fn update(
+        &mut self,
+        statuses: &[JobStatus],
+        focused: bool,
+        now: Instant,
+    ) -> Vec<Completion> {
+        let mut completions = vec![];
+        self.running.retain(|(name, since)| {
+            let running = statuses.iter().any(|status| status.name == *name);
+            let duration = now - *since;
+            if !running && !focused && duration >= MIN_DURATION {
+                completions.push(Completion { name, duration });
+            }
+            running
+        });
+        for status in statuses {
+            if !self.running.iter().any(|(name, _)| *name == status.name) {
+                self.running.push((status.name, now));
+            }
+        }
+        completions
+    }
+
+    /// Forgets the running jobs, as when they are cancelled, so that their end is not announced.
+    pub(super) fn forget(&mut self) {
+        self.running.clear();
+    }
+}
+
+impl Completion {
+    /// Announces the completion on a thread of its own, since the notification service may take a
+    /// while to answer.
+    pub(super) fn show(&self, sound: bool) {
+        let summary = format!("{} finished", self.name);
+        let body = format!("After {}", throughput::describe_duration(self.duration));
+        #[cfg(not(target_arch = "wasm32"))]
+        std::thread::spawn(move || show(&summary, &body, sound));
+        #[cfg(target_arch = "wasm32")]
+        show(&summary, &body, sound);
+    }
+}
+
+/// Shows a desktop notification, with the sound of the platform for a completed task if asked,
+/// and waits until the notification service has it.
+#[cfg(all(feature = "notifications", not(target_arch = "wasm32")))]
+pub(super) fn show(summary: &str, body: &str, sound: bool) {
+    let mut notification = notify_rust::Notification::new();
+    notification
+        .appname("Fractal Explorer")
+        .summary(summary)
+        .body(body);
+    if sound {
+        notification.sound_name(SOUND);
+    }
+    if let Err(error) = notification.show() {
+        tracing::warn!("Could not show notification: {error}");
+    }
+}
+
+/// Without notification support, completions are only logged.
+#[cfg(not(all(feature = "notifications", not(target_arch = "wasm32"))))]
+pub(super) fn show(summary: &str, body: &str, _sound: bool) {
+    tracing::info!("{summary}: {body}");
+}
+
+/// The sound which each platform plays for a completed task.
+#[cfg(all(feature = "notifications", unix, not(target_os = "macos")))]
+const SOUND: &str = "complete";
+#[cfg(all(feature = "notifications", target_os = "macos"))]
+const SOUND: &str = "Glass";
+#[cfg(all(feature = "notifications", target_os = "windows"))]
+const SOUND: &str = "Default";
+
+#[cfg(test)]
+mod tests {
+    use super::{Completion, CompletionWatcher, MIN_DURATION};
+    use crate::jobs::JobStatus;
+    use googletest::prelude::*;
+    use iced::time::Instant;
+    use std::time::Duration;
+
+    const JOB: JobStatus = JobStatus {
+        name: "Computing reference orbit",
+        percent: None,
+        remaining: None,
+    };
+
+    #[test]
+    fn long_job_finishing_out_of_focus_is_announced() -> Result<()> {
+        let start = Instant::now();
+        let mut watcher = CompletionWatcher::default();
+        watcher.update(&[JOB], false, start);
+
+        let completions = watcher.update(&[], false, start + MIN_DURATION);
+
+        verify_that!(
+            completions,
+            eq(vec![Completion {
+                name: JOB.name,
+                duration: MIN_DURATION
+            }])
+        )
+    }
+
+    #[test]
+    fn quick_job_or_job_finishing_in_focus_is_not_announced() -> Result<()> {
+        let start = Instant::now();
+        let mut quick = CompletionWatcher::default();
+        quick.update(&[JOB], false, start);
+        let mut focused = CompletionWatcher::default();
+        focused.update(&[JOB], true, start);
+
+        let quick_completions = quick.update(&[], false, start + Duration::from_secs(1));
+        let focused_completions = focused.update(&[], true, start + MIN_DURATION);
+
+        verify_that!(
+            (quick_completions.len(), focused_completions.len()),
+            (eq(0), eq(0))
+        )
+    }
+}
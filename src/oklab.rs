@@ -0,0 +1,116 @@
+/// A colour in OKLab, Björn Ottosson's perceptually uniform colour space, in which equal distances
+/// look about equally different and `l` is the perceived lightness.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(super) struct Oklab {
+    pub(super) l: f32,
+    pub(super) a: f32,
+    pub(super) b: f32,
+}
+
+impl Oklab {
+    /// The colour of the given lightness, chroma and hue in degrees in OKLCH, the polar form of
+    /// OKLab.
+    pub(super) fn from_lch(l: f32, chroma: f32, hue: f32) -> Self {
+        let (sin, cos) = hue.to_radians().sin_cos();
+        Self {
+            l,
+            a: chroma * cos,
+            b: chroma * sin,
+        }
+    }
+
+    pub(super) fn from_linear_srgb([r, g, b]: [f32; 3]) -> Self {
+        let l = (0.412_221_46 * r + 0.536_332_55 * g + 0.051_445_995 * b).cbrt();
+        let m = (0.211_903_5 * r + 0.680_699_5 * g + 0.107_396_96 * b).cbrt();
+        let s = (0.088_302_46 * r + 0.281_718_85 * g + 0.629_978_7 * b).cbrt();
+        Self {
+            l: 0.210_454_26 * l + 0.793_617_8 * m - 0.004_072_047 * s,
+            a: 1.977_998_5 * l - 2.428_592_2 * m + 0.450_593_7 * s,
+            b: 0.025_904_037 * l + 0.782_771_77 * m - 0.808_675_77 * s,
+        }
+    }
+
+    /// The colour in linear sRGB, whose channels lie outside `[0, 1]` if it is out of gamut.
+    pub(super) fn to_linear_srgb(self) -> [f32; 3] {
+        let Self { l, a, b } = self;
+        let l_ = (l + 0.396_337_78 * a + 0.215_803_76 * b).powi(3);
+        let m_ = (l - 0.105_561_35 * a - 0.063_854_17 * b).powi(3);
+        let s_ = (l - 0.089_484_18 * a - 1.291_485_5 * b).powi(3);
+        [
+            4.076_741_7 * l_ - 3.307_711_6 * m_ + 0.230_969_94 * s_,
+            -1.268_438 * l_ + 2.609_757_4 * m_ - 0.341_319_4 * s_,
+            -0.004_196_086_3 * l_ - 0.703_418_6 * m_ + 1.707_614_7 * s_,
+        ]
+    }
+
+    pub(super) fn from_srgb8(colour: [u8; 3]) -> Self {
+        Self::from_linear_srgb(colour.map(|channel| {
+            let encoded = f32::from(channel) / 255.0;
+            if encoded <= 0.040_45 {
+                encoded / 12.92
+            } else {
+                ((encoded + 0.055) / 1.055).powf(2.4)
+            }
+        }))
+    }
+
+    /// Encodes the colour as 8-bit sRGB, clipping it to the gamut.
+    pub(super) fn to_srgb8(self) -> [u8; 3] {
+        self.to_linear_srgb().map(|channel| {
+            let channel = channel.clamp(0.0, 1.0);
+            let encoded = if channel <= 0.003_130_8 {
+                12.92 * channel
+            } else {
+                1.055 * channel.powf(1.0 / 2.4) - 0.055
+            };
+            (encoded * 255.0).round() as u8
+        })
+    }
+
+    pub(super) fn distance_squared(self, other: Oklab) -> f32 {
+        (self.l - other.l).powi(2) + (self.a - other.a).powi(2) + (self.b - other.b).powi(2)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Oklab;
+    use googletest::prelude::*;
+    use palette::{IntoColor, LinSrgb, Oklab as ReferenceOklab, Oklch};
+
+    #[test]
+    fn lch_agrees_with_palette_crate() -> Result<()> {
+        let expected: LinSrgb = Oklch::new(0.6, 0.1, 200.0).into_color();
+
+        verify_that!(
+            Oklab::from_lch(0.6, 0.1, 200.0).to_linear_srgb(),
+            elements_are![
+                near(expected.red, 1e-4),
+                near(expected.green, 1e-4),
+                near(expected.blue, 1e-4)
+            ]
+        )
+    }
+
+    #[test]
+    fn linear_srgb_agrees_with_palette_crate() -> Result<()> {
+        let expected: ReferenceOklab = LinSrgb::new(0.8, 0.3, 0.1).into_color();
+
+        verify_that!(
+            Oklab::from_linear_srgb([0.8, 0.3, 0.1]),
+            matches_pattern!(Oklab {
+                l: near(expected.l, 1e-4),
+                a: near(expected.a, 1e-4),
+                b: near(expected.b, 1e-4),
+            })
+        )
+    }
+
+    #[test]
+    fn srgb8_round_trips() -> Result<()> {
+        verify_that!(
+            Oklab::from_srgb8([12, 200, 97]).to_srgb8(),
+            eq([12, 200, 97])
+        )
+    }
+}
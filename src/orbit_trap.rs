@@ -0,0 +1,63 @@
+use cgmath::Vector2;
+use std::path::Path;
+
+/// A square region of the plane which colours the points whose orbits pass through it by an
+/// image, sampled where the orbit first landed in it. Applied by `mandelbrot_orbit` in
+/// `frag.wgsl`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(super) struct OrbitTrap {
+    pub(super) enabled: bool,
+    pub(super) centre: Vector2<f32>,
+    /// The distance from the centre to the edges of the square.
+    pub(super) half_size: f32,
+}
+
+impl Default for OrbitTrap {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            centre: Vector2::new(0.0, 0.0),
+            half_size: 0.5,
+        }
+    }
+}
+
+impl OrbitTrap {
+    /// Packs the trap into the `vec4` `trap` of the uniform block.
+    pub(super) fn pack(&self) -> [f32; 4] {
+        [
+            self.centre.x,
+            self.centre.y,
+            self.half_size,
+            if self.enabled { 1.0 } else { 0.0 },
+        ]
+    }
+}
+
+/// Reads the image with which the trap is textured.
+#[cfg_attr(target_arch = "wasm32", allow(dead_code))]
+pub(super) fn load_image(path: &Path) -> Result<image::RgbaImage, image::ImageError> {
+    Ok(image::open(path)?.into_rgba8())
+}
+
+/// Asks the user for an image with which to texture the trap, returning `None` if they cancel.
+#[cfg(not(target_arch = "wasm32"))]
+pub(super) fn choose_image() -> Option<std::path::PathBuf> {
+    native_dialog::FileDialog::new()
+        .set_title("Orbit trap image")
+        .add_filter("PNG image", &["png"])
+        .show_open_single_file()
+        .ok()
+        .flatten()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::OrbitTrap;
+    use googletest::prelude::*;
+
+    #[test]
+    fn disabled_trap_is_packed_with_zero_flag() -> Result<()> {
+        verify_that!(OrbitTrap::default().pack(), eq([0.0, 0.0, 0.5, 0.0]))
+    }
+}
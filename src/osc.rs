@@ -0,0 +1,324 @@
+use crate::{
+    colour_palette::Palette,
+    controls::{CanvasMessage, FractalType, Message},
+    iteration_budget::{MAX_ITERATIONS, MIN_ITERATIONS},
+};
+use iced::Point;
+use std::{
+    net::UdpSocket,
+    sync::mpsc,
+    thread::{self, JoinHandle},
+};
+
+/// The UDP port on which the explorer listens for OSC messages.
+pub(super) const OSC_PORT: u16 = 9000;
+
+/// The largest packet which is received in full. Longer packets are truncated and then rejected.
+const MAX_PACKET_SIZE: usize = 1536;
+
+/// Receives [OSC](https://opensoundcontrol.stanford.edu/spec-1_0.html) messages over UDP, so that
+/// VJ software or tablets running e.g. TouchOSC can drive the explorer.
+///
+/// The following addresses are understood, where numbers may be sent as integers or floats:
+///
+/// * `/fractal <name>` switches to the fractal with the given name.
+/// * `/palette <name>` switches to the palette with the given name.
+/// * `/parameter/<index> <value>` sets a parameter of the current fractal.
+/// * `/max_iterations <n>` sets the iteration budget.
+/// * `/antialiasing <0 or 1>` turns adaptive anti-aliasing off or on.
+/// * `/pan <x> <y>` pans the view by the given number of pixels.
+/// * `/zoom <amount>` zooms on the centre of the window as a scroll wheel would, with negative
+///   amounts zooming in.
+pub(super) struct OscServer {
+    receiver: mpsc::Receiver<OscMessage>,
+    _worker: JoinHandle<()>,
+}
+
+impl OscServer {
+    /// Listens on the given port on all interfaces, calling `wake` whenever a message arrives so
+    /// that the event loop picks it up with [`OscServer::poll`].
+    pub(super) fn start(port: u16, wake: impl Fn() + Send + 'static) -> std::io::Result<Self> {
+        let socket = UdpSocket::bind(("0.0.0.0", port))?;
+        let (sender, receiver) = mpsc::channel();
+        let worker = thread::Builder::new()
+            .name("OSC server".into())
+            .spawn(move || {
+                let mut buffer = [0; MAX_PACKET_SIZE];
+                loop {
+                    let length = match socket.recv(&mut buffer) {
+                        Ok(length) => length,
+                        Err(error) => {
+                            tracing::warn!("OSC server stopped: {error}");
+                            return;
+                        }
+                    };
+                    match decode_packet(&buffer[..length]) {
+                        Ok(messages) => {
+                            for message in messages {
+                                if sender.send(message).is_err() {
+                                    return;
+                                }
+                            }
+                            wake();
+                        }
+                        Err(error) => tracing::warn!("Ignoring malformed OSC packet: {error}"),
+                    }
+                }
+            })?;
+        Ok(Self {
+            receiver,
+            _worker: worker,
+        })
+    }
+
+    /// Returns the messages for the controls which arrived since the last call, zooming on
+    /// `centre`.
+    pub(super) fn poll(&self, centre: Point) -> Vec<Message> {
+        self.receiver
+            .try_iter()
+            .filter_map(|message| {
+                let result = to_message(&message, centre);
+                if result.is_none() {
+                    tracing::warn!("Ignoring unknown OSC message {message:?}");
+                }
+                result
+            })
+            .collect()
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub(super) enum OscArgument {
+    Int(i32),
+    Float(f32),
+    String(String),
+}
+
+impl OscArgument {
+    fn number(&self) -> Option<f32> {
+        match self {
+            OscArgument::Int(value) => Some(*value as f32),
+            OscArgument::Float(value) => Some(*value),
+            OscArgument::String(_) => None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub(super) struct OscMessage {
+    pub(super) address: String,
+    pub(super) arguments: Vec<OscArgument>,
+}
+
+#[derive(Debug, PartialEq, thiserror::Error)]
+pub(super) enum DecodeError {
+    #[error("the packet ends inside {0}")]
+    Truncated(&'static str),
+    #[error("{0} is not valid UTF-8")]
+    InvalidString(&'static str),
+    #[error("the address {0:?} does not start with '/'")]
+    InvalidAddress(String),
+    #[error("the type tag string does not start with ','")]
+    MissingTypeTags,
+    #[error("arguments of type '{0}' are not supported")]
+    UnsupportedType(char),
+}
+
+const BUNDLE_TAG: &[u8] = b"#bundle\0";
+
+/// Decodes a packet, which is either a single message or a bundle of packets whose time tag is
+/// ignored, so that bundled messages take effect immediately.
+pub(super) fn decode_packet(bytes: &[u8]) -> Result<Vec<OscMessage>, DecodeError> {
+    let Some(mut contents) = bytes.strip_prefix(BUNDLE_TAG) else {
+        return Ok(vec![decode_message(bytes)?]);
+    };
+    contents = contents
+        .get(8..)
+        .ok_or(DecodeError::Truncated("a time tag"))?;
+    let mut messages = vec![];
+    while !contents.is_empty() {
+        let size = read_i32(&mut contents, "a bundle element size")? as usize;
+        let element = contents
+            .get(..size)
+            .ok_or(DecodeError::Truncated("a bundle element"))?;
+        messages.extend(decode_packet(element)?);
+        contents = &contents[size..];
+    }
+    Ok(messages)
+}
+
+fn decode_message(mut bytes: &[u8]) -> Result<OscMessage, DecodeError> {
+    let address = read_string(&mut bytes, "the address")?;
+    if !address.starts_with('/') {
+        return Err(DecodeError::InvalidAddress(address));
+    }
+    // Very old implementations omit the type tags of messages without arguments.
+    if bytes.is_empty() {
+        return Ok(OscMessage {
+            address,
+            arguments: vec![],
+        });
+    }
+    let type_tags = read_string(&mut bytes, "the type tags")?;
+    let type_tags = type_tags
+        .strip_prefix(',')
+        .ok_or(DecodeError::MissingTypeTags)?;
+    let arguments = type_tags
+        .chars()
+        .map(|tag| match tag {
+            'i' => read_i32(&mut bytes, "an int argument").map(OscArgument::Int),
+            'f' => read_i32(&mut bytes, "a float argument")
+                .map(|bits| OscArgument::Float(f32::from_bits(bits as u32))),
+            's' => read_string(&mut bytes, "a string argument").map(OscArgument::String),
+            tag => Err(DecodeError::UnsupportedType(tag)),
+        })
+        .collect::<Result<_, _>>()?;
+    Ok(OscMessage { address, arguments })
+}
+
+fn read_i32(bytes: &mut &[u8], what: &'static str) -> Result<i32, DecodeError> {
+    let (value, rest) = bytes
+        .split_first_chunk::<4>()
+        .ok_or(DecodeError::Truncated(what))?;
+    *bytes = rest;
+    Ok(i32::from_be_bytes(*value))
+}
+
+/// Reads a null-terminated string, which is padded with further nulls to a multiple of four
+/// bytes.
+fn read_string(bytes: &mut &[u8], what: &'static str) -> Result<String, DecodeError> {
+    let length = bytes
+        .iter()
+        .position(|byte| *byte == 0)
+        .ok_or(DecodeError::Truncated(what))?;
+    let padded_length = (length + 1).next_multiple_of(4);
+    let string = std::str::from_utf8(&bytes[..length])
+        .map_err(|_| DecodeError::InvalidString(what))?
+        .to_owned();
+    *bytes = bytes
+        .get(padded_length..)
+        .ok_or(DecodeError::Truncated(what))?;
+    Ok(string)
+}
+
+/// Translates an OSC message into the message of the controls with the same effect, or `None`
+/// if the address or arguments are not understood.
+pub(super) fn to_message(message: &OscMessage, centre: Point) -> Option<Message> {
+    let numbers: Vec<f32> = message
+        .arguments
+        .iter()
+        .filter_map(OscArgument::number)
+        .collect();
+    let name = match message.arguments.first() {
+        Some(OscArgument::String(name)) => Some(name.as_str()),
+        _ => None,
+    };
+    if let Some(index) = message.address.strip_prefix("/parameter/") {
+        return match (index.parse(), &numbers[..]) {
+            (Ok(index), [value]) => Some(Message::ParameterChanged(index, *value)),
+            _ => None,
+        };
+    }
+    match (message.address.as_str(), name, &numbers[..]) {
+        ("/fractal", Some(name), _) => name
+            .parse::<FractalType>()
+            .ok()
+            .map(Message::FractalTypeSelected),
+        ("/palette", Some(name), _) => name.parse::<Palette>().ok().map(Message::PaletteSelected),
+        ("/max_iterations", _, [max_iterations]) => Some(Message::MaxIterationsChanged(
+            (*max_iterations as u32).clamp(MIN_ITERATIONS, MAX_ITERATIONS),
+        )),
+        ("/antialiasing", _, [enabled]) => Some(Message::AntialiasingToggled(*enabled != 0.0)),
+        ("/pan", _, [x, y]) => Some(Message::Canvas(CanvasMessage::Pan(*x, *y))),
+        ("/zoom", _, [amount]) => Some(Message::Canvas(CanvasMessage::Zoom(*amount, centre))),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{decode_packet, to_message, DecodeError, OscArgument, OscMessage};
+    use crate::controls::{CanvasMessage, FractalType, Message};
+    use googletest::prelude::*;
+    use iced::Point;
+
+    #[test]
+    fn decodes_message_with_arguments() -> Result<()> {
+        let packet = [
+            b"/pan\0\0\0\0".as_slice(),
+            b",fi\0",
+            &1.5f32.to_be_bytes(),
+            &(-3i32).to_be_bytes(),
+        ]
+        .concat();
+
+        verify_that!(
+            decode_packet(&packet)
+                == Ok(vec![OscMessage {
+                    address: "/pan".into(),
+                    arguments: vec![OscArgument::Float(1.5), OscArgument::Int(-3)],
+                }]),
+            eq(true)
+        )
+    }
+
+    #[test]
+    fn decodes_every_message_of_bundle() -> Result<()> {
+        let message = [b"/zoom\0\0\0".as_slice(), b",f\0\0", &2.0f32.to_be_bytes()].concat();
+        let packet = [
+            b"#bundle\0".as_slice(),
+            &[0, 0, 0, 0, 0, 0, 0, 1],
+            &(message.len() as i32).to_be_bytes(),
+            &message,
+            &(message.len() as i32).to_be_bytes(),
+            &message,
+        ]
+        .concat();
+
+        verify_that!(
+            decode_packet(&packet).ok().map(|messages| messages.len()),
+            eq(Some(2))
+        )
+    }
+
+    #[test]
+    fn rejects_truncated_arguments() -> Result<()> {
+        let packet = [b"/zoom\0\0\0".as_slice(), b",f\0\0", &[0, 0]].concat();
+
+        verify_that!(
+            matches!(
+                decode_packet(&packet),
+                Err(DecodeError::Truncated("a float argument"))
+            ),
+            eq(true)
+        )
+    }
+
+    #[test]
+    fn translates_fractal_and_zoom() -> Result<()> {
+        let fractal = OscMessage {
+            address: "/fractal".into(),
+            arguments: vec![OscArgument::String("newton".into())],
+        };
+        let zoom = OscMessage {
+            address: "/zoom".into(),
+            arguments: vec![OscArgument::Int(-4)],
+        };
+        let centre = Point::new(50.0, 40.0);
+
+        verify_that!(
+            (
+                matches!(
+                    to_message(&fractal, centre),
+                    Some(Message::FractalTypeSelected(FractalType::Newton))
+                ),
+                matches!(
+                    to_message(&zoom, centre),
+                    Some(Message::Canvas(CanvasMessage::Zoom(amount, point)))
+                        if amount == -4.0 && point == centre
+                )
+            ),
+            (eq(true), eq(true))
+        )
+    }
+}
@@ -0,0 +1,341 @@
+use crate::{controls::FractalType, shader_source::ComposedShader};
+use iced_wgpu::wgpu;
+use std::{
+    sync::{mpsc, Arc},
+    thread::JoinHandle,
+};
+
+const VERTEX: ComposedShader = ComposedShader {
+    label: "shader/vert.wgsl",
+    parts: &[("vert.wgsl", include_str!("shader/vert.wgsl"))],
+};
+const FRAGMENT: ComposedShader = ComposedShader {
+    label: "shader/frag.wgsl",
+    parts: &[("frag.wgsl", include_str!("shader/frag.wgsl"))],
+};
+const ITERATION_DATA: ComposedShader = ComposedShader {
+    label: "shader/iteration_data.wgsl",
+    parts: &[
+        ("frag.wgsl", include_str!("shader/frag.wgsl")),
+        (
+            "iteration_data.wgsl",
+            include_str!("shader/iteration_data.wgsl"),
+        ),
+    ],
+};
+const ITERATION_DATA_DF64: ComposedShader = ComposedShader {
+    label: "shader/iteration_data_df64.wgsl",
+    parts: &[
+        ("frag.wgsl", include_str!("shader/frag.wgsl")),
+        (
+            "iteration_data.wgsl",
+            include_str!("shader/iteration_data.wgsl"),
+        ),
+        ("df64.wgsl", include_str!("shader/df64.wgsl")),
+        (
+            "iteration_data_df64.wgsl",
+            include_str!("shader/iteration_data_df64.wgsl"),
+        ),
+    ],
+};
+const ITERATION_DATA_PERTURBATION: ComposedShader = ComposedShader {
+    label: "shader/iteration_data_perturbation.wgsl",
+    parts: &[
+        ("frag.wgsl", include_str!("shader/frag.wgsl")),
+        (
+            "iteration_data.wgsl",
+            include_str!("shader/iteration_data.wgsl"),
+        ),
+        ("floatexp.wgsl", include_str!("shader/floatexp.wgsl")),
+        (
+            "iteration_data_perturbation.wgsl",
+            include_str!("shader/iteration_data_perturbation.wgsl"),
+        ),
+    ],
+};
+const COLOURING: ComposedShader = ComposedShader {
+    label: "shader/colouring.wgsl",
+    parts: &[
+        ("frag.wgsl", include_str!("shader/frag.wgsl")),
+        ("colouring.wgsl", include_str!("shader/colouring.wgsl")),
+    ],
+};
+
+/// The lines of the curves of the vector mode, built by `CurveRenderer` in `vector_mode.rs`.
+pub(super) const CURVE: ComposedShader = ComposedShader {
+    label: "shader/curve.wgsl",
+    parts: &[
+        ("frag.wgsl", include_str!("shader/frag.wgsl")),
+        ("curve.wgsl", include_str!("shader/curve.wgsl")),
+    ],
+};
+
+/// The walk of the chaos game and the colouring of its hits, built by `ChaosGameRenderer` in
+/// `chaos_game.rs`.
+pub(super) const CHAOS_GAME_WALK: ComposedShader = ComposedShader {
+    label: "shader/chaos_game_walk.wgsl",
+    parts: &[
+        ("frag.wgsl", include_str!("shader/frag.wgsl")),
+        ("chaos_game.wgsl", include_str!("shader/chaos_game.wgsl")),
+        (
+            "chaos_game_walk.wgsl",
+            include_str!("shader/chaos_game_walk.wgsl"),
+        ),
+    ],
+};
+pub(super) const CHAOS_GAME_COLOURING: ComposedShader = ComposedShader {
+    label: "shader/chaos_game_colouring.wgsl",
+    parts: &[
+        ("frag.wgsl", include_str!("shader/frag.wgsl")),
+        ("chaos_game.wgsl", include_str!("shader/chaos_game.wgsl")),
+        (
+            "chaos_game_colouring.wgsl",
+            include_str!("shader/chaos_game_colouring.wgsl"),
+        ),
+    ],
+};
+
+/// Every shader from which the pipelines are built.
+#[cfg(test)]
+pub(super) const COMPOSED_SHADERS: [&ComposedShader; 9] = [
+    &VERTEX,
+    &FRAGMENT,
+    &ITERATION_DATA,
+    &ITERATION_DATA_DF64,
+    &ITERATION_DATA_PERTURBATION,
+    &COLOURING,
+    &CURVE,
+    &CHAOS_GAME_WALK,
+    &CHAOS_GAME_COLOURING,
+];
+
+/// Builds the pipelines of the fractals, which is slow enough to stall the UI since it compiles
+/// the shaders for the driver.
+pub(super) struct PipelineCompiler {
+    device: Arc<wgpu::Device>,
+    texture_format: wgpu::TextureFormat,
+    layouts: PipelineLayouts,
+    vs_module: wgpu::ShaderModule,
+    fs_module: wgpu::ShaderModule,
+    iteration_data_module: wgpu::ShaderModule,
+    df64_iteration_data_module: wgpu::ShaderModule,
+    perturbation_iteration_data_module: wgpu::ShaderModule,
+    colouring_module: wgpu::ShaderModule,
+}
+
+/// The layouts of the pipelines in [`FractalPipelines`], all of which take the view's uniform
+/// block as their first bind group.
+pub(super) struct PipelineLayouts {
+    pub(super) render: wgpu::PipelineLayout,
+    /// Takes the iteration data for writing as its second bind group.
+    pub(super) iteration_data: wgpu::PipelineLayout,
+    /// Like `iteration_data`, also taking the reference orbit as its third bind group.
+    pub(super) perturbation_iteration_data: wgpu::PipelineLayout,
+    /// Takes the iteration data for reading as its second bind group.
+    pub(super) colouring: wgpu::PipelineLayout,
+}
+
+/// The pipelines with which one fractal is rendered.
+pub(super) struct FractalPipelines {
+    /// Renders the fractal in a single pass, for when the iteration data does not apply.
+    pub(super) render: wgpu::RenderPipeline,
+    /// Stores the result of iterating each pixel in the iteration data.
+    pub(super) iteration_data: wgpu::ComputePipeline,
+    /// Like `iteration_data` in double-float arithmetic, for fractals which have a variant in it.
+    pub(super) iteration_data_df64: Option<wgpu::ComputePipeline>,
+    /// Like `iteration_data` by perturbation from a reference orbit, for fractals which have a
+    /// variant iterated so.
+    pub(super) iteration_data_perturbation: Option<wgpu::ComputePipeline>,
+    /// Colours each pixel by its iteration data.
+    pub(super) colouring: wgpu::RenderPipeline,
+}
+
+/// Receives pipelines from [`PipelineCompiler::compile_in_background`] as they become ready.
+pub(super) struct CompiledPipelines {
+    receiver: mpsc::Receiver<(FractalType, FractalPipelines)>,
+    worker: Option<JoinHandle<()>>,
+}
+
+impl CompiledPipelines {
+    /// Returns the pipelines which are ready, without waiting for the others.
+    pub(super) fn ready(&self) -> impl Iterator<Item = (FractalType, FractalPipelines)> + '_ {
+        self.receiver.try_iter()
+    }
+
+    /// Returns all remaining pipelines, waiting for each to be compiled.
+    pub(super) fn all(&self) -> impl Iterator<Item = (FractalType, FractalPipelines)> + '_ {
+        self.receiver.iter()
+    }
+}
+
+impl Drop for CompiledPipelines {
+    fn drop(&mut self) {
+        // The worker must not outlive the view, since the GPU may be torn down after it.
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+    }
+}
+
+impl PipelineCompiler {
+    pub(super) fn new(
+        device: Arc<wgpu::Device>,
+        texture_format: wgpu::TextureFormat,
+        layouts: PipelineLayouts,
+    ) -> Self {
+        let vs_module = VERTEX.create_module(&device);
+        let fs_module = FRAGMENT.create_module(&device);
+        let iteration_data_module = ITERATION_DATA.create_module(&device);
+        let df64_iteration_data_module = ITERATION_DATA_DF64.create_module(&device);
+        let perturbation_iteration_data_module = ITERATION_DATA_PERTURBATION.create_module(&device);
+        let colouring_module = COLOURING.create_module(&device);
+        Self {
+            device,
+            texture_format,
+            layouts,
+            vs_module,
+            fs_module,
+            iteration_data_module,
+            df64_iteration_data_module,
+            perturbation_iteration_data_module,
+            colouring_module,
+        }
+    }
+
+    /// Compiles the pipelines of the given fractal types one after another on a worker thread.
+    ///
+    /// The web has no threads, so there the pipelines are compiled before this returns.
+    pub(super) fn compile_in_background(
+        self,
+        fractal_types: Vec<FractalType>,
+    ) -> CompiledPipelines {
+        let (sender, receiver) = mpsc::channel();
+        let compile_all = move || {
+            for fractal_type in fractal_types {
+                let pipeline = self.compile(fractal_type);
+                if sender.send((fractal_type, pipeline)).is_err() {
+                    break;
+                }
+            }
+        };
+        #[cfg(not(target_arch = "wasm32"))]
+        let worker = Some(
+            std::thread::Builder::new()
+                .name("pipeline compiler".into())
+                .spawn(compile_all)
+                .expect("Could not start pipeline compiler thread"),
+        );
+        #[cfg(target_arch = "wasm32")]
+        let worker = {
+            compile_all();
+            None
+        };
+        CompiledPipelines { receiver, worker }
+    }
+
+    // wgpu 0.19 does not expose the driver's pipeline cache. Once it does (as `PipelineCache`),
+    // its data can be stored on disk and passed in here to skip compilation on later starts.
+    pub(super) fn compile(&self, fractal_type: FractalType) -> FractalPipelines {
+        let definition = fractal_type.definition();
+        let colour_target = wgpu::ColorTargetState {
+            format: self.texture_format,
+            blend: Some(wgpu::BlendState {
+                color: wgpu::BlendComponent::REPLACE,
+                alpha: wgpu::BlendComponent::REPLACE,
+            }),
+            write_mask: wgpu::ColorWrites::ALL,
+        };
+        let render = self
+            .device
+            .create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                label: None,
+                layout: Some(&self.layouts.render),
+                vertex: wgpu::VertexState {
+                    module: &self.vs_module,
+                    entry_point: "main",
+                    buffers: &[wgpu::VertexBufferLayout {
+                        array_stride: std::mem::size_of::<[f32; 2]>() as wgpu::BufferAddress,
+                        step_mode: wgpu::VertexStepMode::Vertex,
+                        attributes: &wgpu::vertex_attr_array![0 => Float32x2],
+                    }],
+                },
+                fragment: Some(wgpu::FragmentState {
+                    module: &self.fs_module,
+                    entry_point: definition.entry_point,
+                    targets: &[Some(colour_target.clone())],
+                }),
+                primitive: wgpu::PrimitiveState {
+                    topology: wgpu::PrimitiveTopology::TriangleList,
+                    front_face: wgpu::FrontFace::Ccw,
+                    ..Default::default()
+                },
+                depth_stencil: None,
+                multisample: wgpu::MultisampleState {
+                    count: 1,
+                    mask: !0,
+                    alpha_to_coverage_enabled: false,
+                },
+                multiview: None,
+            });
+        let create_iteration_data_pipeline = |layout, module, entry_point| {
+            self.device
+                .create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+                    label: Some(entry_point),
+                    layout: Some(layout),
+                    module,
+                    entry_point,
+                })
+        };
+        let iteration_data = create_iteration_data_pipeline(
+            &self.layouts.iteration_data,
+            &self.iteration_data_module,
+            definition.iteration_data_entry_point,
+        );
+        let iteration_data_df64 = definition
+            .df64_iteration_data_entry_point
+            .map(|entry_point| {
+                create_iteration_data_pipeline(
+                    &self.layouts.iteration_data,
+                    &self.df64_iteration_data_module,
+                    entry_point,
+                )
+            });
+        let iteration_data_perturbation =
+            definition
+                .perturbation_iteration_data_entry_point
+                .map(|entry_point| {
+                    create_iteration_data_pipeline(
+                        &self.layouts.perturbation_iteration_data,
+                        &self.perturbation_iteration_data_module,
+                        entry_point,
+                    )
+                });
+        let colouring = self
+            .device
+            .create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                label: Some(definition.colouring_entry_point),
+                layout: Some(&self.layouts.colouring),
+                vertex: wgpu::VertexState {
+                    module: &self.colouring_module,
+                    entry_point: "fullscreen",
+                    buffers: &[],
+                },
+                fragment: Some(wgpu::FragmentState {
+                    module: &self.colouring_module,
+                    entry_point: definition.colouring_entry_point,
+                    targets: &[Some(colour_target)],
+                }),
+                primitive: wgpu::PrimitiveState::default(),
+                depth_stencil: None,
+                multisample: wgpu::MultisampleState::default(),
+                multiview: None,
+            });
+        FractalPipelines {
+            render,
+            iteration_data,
+            iteration_data_df64,
+            iteration_data_perturbation,
+            colouring,
+        }
+    }
+}
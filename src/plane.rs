@@ -0,0 +1,48 @@
+use cgmath::Vector2;
+use serde::{Deserialize, Serialize};
+use std::ops::RangeInclusive;
+
+/// The range of either part of the constant of the dynamic plane which the controls offer.
+pub(super) const FIXED_C_PARTS: RangeInclusive<f32> = -2.0..=2.0;
+pub(super) const FIXED_C_STEP: f32 = 0.001;
+
+/// Which plane of an escape-time fractal the view shows, generalising the duality of the
+/// Mandelbrot set and its Julia sets to every fractal which iterates `z ↦ f(z) + c`.
+///
+/// The root-finding fractals already show the plane in which their iteration starts, and the
+/// noise fractals do not iterate, so only fractals which escape change with the plane.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub(super) enum Plane {
+    /// Each point is the constant `c` and its orbit starts from a critical point, like the
+    /// Mandelbrot set.
+    #[default]
+    Parameter,
+    /// Each point is where its orbit starts and the constant `c` is the given one, like a Julia
+    /// set.
+    Dynamic(Vector2<f32>),
+}
+
+impl Plane {
+    /// The value of `plane` in the uniform block. Must match the constants `PLANE_*` in
+    /// `frag.wgsl`.
+    pub(super) fn code(self) -> u32 {
+        match self {
+            Plane::Parameter => 0,
+            Plane::Dynamic(_) => 1,
+        }
+    }
+
+    /// The dynamic plane of the point of the parameter plane at the given centre of the view.
+    pub(super) fn dynamic_at(centre: Vector2<f64>) -> Self {
+        let clamp = |part: f64| (part as f32).clamp(*FIXED_C_PARTS.start(), *FIXED_C_PARTS.end());
+        Plane::Dynamic(Vector2::new(clamp(centre.x), clamp(centre.y)))
+    }
+
+    /// The constant `c` of the dynamic plane, or zero in the parameter plane.
+    pub(super) fn fixed_c(self) -> Vector2<f32> {
+        match self {
+            Plane::Parameter => Vector2::new(0.0, 0.0),
+            Plane::Dynamic(c) => c,
+        }
+    }
+}
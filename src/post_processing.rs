@@ -0,0 +1,180 @@
+use std::{fmt::Display, ops::RangeInclusive};
+
+/// Adjustments of the colours which the palette yields, so that the final look can be tuned
+/// without editing the palette. Applied by `post_process` in `frag.wgsl`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(super) struct PostProcessing {
+    /// In stops, so that each unit doubles the brightness.
+    pub(super) exposure: f32,
+    pub(super) gamma: f32,
+    /// The factor by which differences from mid-grey are scaled.
+    pub(super) contrast: f32,
+    /// The factor by which differences from the luminance are scaled, with zero giving greys.
+    pub(super) saturation: f32,
+    /// How colours which the exposure brightens beyond white are brought back.
+    pub(super) tonemapper: Tonemapper,
+}
+
+impl Default for PostProcessing {
+    fn default() -> Self {
+        Self {
+            exposure: 0.0,
+            gamma: 1.0,
+            contrast: 1.0,
+            saturation: 1.0,
+            tonemapper: Tonemapper::default(),
+        }
+    }
+}
+
+impl PostProcessing {
+    pub(super) fn get(&self, adjustment: Adjustment) -> f32 {
+        match adjustment {
+            Adjustment::Exposure => self.exposure,
+            Adjustment::Gamma => self.gamma,
+            Adjustment::Contrast => self.contrast,
+            Adjustment::Saturation => self.saturation,
+        }
+    }
+
+    /// Returns the adjustments with the given one set to `value`, clamped to its range.
+    pub(super) fn with(self, adjustment: Adjustment, value: f32) -> Self {
+        let value = value.clamp(*adjustment.range().start(), *adjustment.range().end());
+        match adjustment {
+            Adjustment::Exposure => Self {
+                exposure: value,
+                ..self
+            },
+            Adjustment::Gamma => Self {
+                gamma: value,
+                ..self
+            },
+            Adjustment::Contrast => Self {
+                contrast: value,
+                ..self
+            },
+            Adjustment::Saturation => Self {
+                saturation: value,
+                ..self
+            },
+        }
+    }
+
+    /// Packs the adjustments into the `vec4` `post_processing` of the uniform block.
+    pub(super) fn pack(&self) -> [f32; 4] {
+        [self.exposure, self.gamma, self.contrast, self.saturation]
+    }
+}
+
+/// Maps colours in linear light which the exposure brightened beyond white back into the range
+/// which can be shown, so that highlights keep their detail and hue instead of clipping to white.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(super) enum Tonemapper {
+    /// Clips each channel at white, which leaves colours within the range as they are.
+    #[default]
+    Clip,
+    /// Divides the colour by one more than its luminance, which keeps its hue.
+    Reinhard,
+    /// Narkowicz's fit of the filmic curve of the Academy Color Encoding System, which
+    /// desaturates the brightest highlights towards white like film.
+    Aces,
+}
+
+impl Tonemapper {
+    pub(super) const ALL: [Tonemapper; 3] = [Self::Clip, Self::Reinhard, Self::Aces];
+
+    /// The value of `tonemapper` in the uniform block. Must match the constants `TONEMAP_*` in
+    /// `frag.wgsl`.
+    pub(super) fn code(self) -> u32 {
+        match self {
+            Tonemapper::Clip => 0,
+            Tonemapper::Reinhard => 1,
+            Tonemapper::Aces => 2,
+        }
+    }
+}
+
+impl Display for Tonemapper {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Tonemapper::Clip => write!(f, "Clip highlights"),
+            Tonemapper::Reinhard => write!(f, "Reinhard tone mapping"),
+            Tonemapper::Aces => write!(f, "ACES tone mapping"),
+        }
+    }
+}
+
+/// One of the sliders of [`PostProcessing`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(super) enum Adjustment {
+    Exposure,
+    Gamma,
+    Contrast,
+    Saturation,
+}
+
+impl Adjustment {
+    pub(super) const ALL: [Adjustment; 4] = [
+        Self::Exposure,
+        Self::Gamma,
+        Self::Contrast,
+        Self::Saturation,
+    ];
+
+    pub(super) fn range(self) -> RangeInclusive<f32> {
+        match self {
+            Adjustment::Exposure => -3.0..=3.0,
+            Adjustment::Gamma => 0.2..=5.0,
+            Adjustment::Contrast | Adjustment::Saturation => 0.0..=2.0,
+        }
+    }
+
+    /// The increment of the slider, which is also how far an arrow key moves it.
+    pub(super) fn step(self) -> f32 {
+        match self {
+            Adjustment::Exposure => 0.1,
+            Adjustment::Gamma | Adjustment::Contrast | Adjustment::Saturation => 0.05,
+        }
+    }
+}
+
+impl Display for Adjustment {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Adjustment::Exposure => write!(f, "Exposure"),
+            Adjustment::Gamma => write!(f, "Gamma"),
+            Adjustment::Contrast => write!(f, "Contrast"),
+            Adjustment::Saturation => write!(f, "Saturation"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Adjustment, PostProcessing};
+    use googletest::prelude::*;
+
+    #[test]
+    fn defaults_lie_within_range() -> Result<()> {
+        let defaults = PostProcessing::default();
+        for adjustment in Adjustment::ALL {
+            verify_that!(
+                adjustment.range().contains(&defaults.get(adjustment)),
+                eq(true)
+            )?;
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn values_are_clamped_to_range() -> Result<()> {
+        let adjusted = PostProcessing::default()
+            .with(Adjustment::Gamma, 10.0)
+            .with(Adjustment::Exposure, -1.5);
+
+        verify_that!(
+            (adjusted.gamma, adjusted.exposure, adjusted.contrast),
+            (eq(5.0), eq(-1.5), eq(1.0))
+        )
+    }
+}
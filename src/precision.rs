@@ -0,0 +1,202 @@
+use crate::{dive::Framing, fractal_definition::FractalDefinition};
+use cgmath::{InnerSpace, Vector2};
+use std::fmt::Display;
+
+/// The smallest number of steps of the numeric type by which neighbouring pixels must differ for
+/// the image to remain free of blocky artefacts.
+const MIN_STEPS_PER_PIXEL: f64 = 4.0;
+
+/// Returns the modes in which the shaders can render the given fractal.
+pub(super) fn available_modes(definition: &FractalDefinition) -> Vec<NumericMode> {
+    let mut modes = vec![NumericMode::F32];
+    if definition.df64_iteration_data_entry_point.is_some() {
+        modes.push(NumericMode::DoubleDouble);
+    }
+    if definition.perturbation_iteration_data_entry_point.is_some() {
+        modes.push(NumericMode::Perturbation);
+    }
+    modes
+}
+
+/// The arithmetic with which the shaders iterate, from the cheapest and least precise to the
+/// costliest.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub(super) enum NumericMode {
+    F32,
+    /// Pairs of `f32` whose second holds the rounding error of the first, which gives most of the
+    /// precision of `f64` on hardware without it.
+    DoubleDouble,
+    F64,
+    /// Iterates the difference of each pixel from a reference orbit computed in arbitrary
+    /// precision on the CPU, which keeps the differences small enough for any depth.
+    Perturbation,
+}
+
+impl NumericMode {
+    const ALL: [NumericMode; 4] = [Self::F32, Self::DoubleDouble, Self::F64, Self::Perturbation];
+
+    /// The number of significant bits, or `None` if the precision does not depend on the
+    /// magnitude of the coordinates.
+    fn significant_bits(self) -> Option<i32> {
+        match self {
+            NumericMode::F32 => Some(f32::MANTISSA_DIGITS as i32),
+            NumericMode::DoubleDouble => Some(44),
+            NumericMode::F64 => Some(f64::MANTISSA_DIGITS as i32),
+            NumericMode::Perturbation => None,
+        }
+    }
+
+    /// Returns the smallest half width at which this mode resolves a view centred on the given
+    /// point across the given number of pixels.
+    fn min_half_width(self, centre: Vector2<f64>, width_in_pixels: u32) -> f64 {
+        let Some(bits) = self.significant_bits() else {
+            // The pixels are placed by their deltas from the reference orbit in floatexp, so only
+            // the half width of the view, which is held in `f64`, limits how small they get.
+            return 0.5 * MIN_STEPS_PER_PIXEL * f64::MIN_POSITIVE * width_in_pixels as f64;
+        };
+        // Orbits pass through points of magnitude around one, which limit the precision of the
+        // iteration even close to the origin.
+        let magnitude = centre.magnitude().max(1.0);
+        let epsilon = 2f64.powi(1 - bits);
+        0.5 * MIN_STEPS_PER_PIXEL * magnitude * epsilon * width_in_pixels as f64
+    }
+}
+
+impl Display for NumericMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            NumericMode::F32 => write!(f, "f32"),
+            NumericMode::DoubleDouble => write!(f, "double-double"),
+            NumericMode::F64 => write!(f, "f64"),
+            NumericMode::Perturbation => write!(f, "perturbation"),
+        }
+    }
+}
+
+/// How finely a view samples the plane, and the mode in which it is rendered.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(super) struct Precision {
+    /// The distance in the plane between neighbouring pixels.
+    pub(super) pixel_size: f64,
+    pub(super) mode: NumericMode,
+    /// The cheapest mode which resolves neighbouring pixels, which is more precise than `mode` if
+    /// none of the available modes does, or `None` if no mode does.
+    pub(super) required: Option<NumericMode>,
+}
+
+impl Precision {
+    /// Chooses the cheapest of the available modes which resolves the view across the given number
+    /// of pixels, or the most precise one if none does.
+    pub(super) fn of_view(
+        framing: Framing,
+        width_in_pixels: u32,
+        available: &[NumericMode],
+    ) -> Self {
+        let half_width = framing.half_width;
+        let resolves =
+            |mode: &NumericMode| half_width >= mode.min_half_width(framing.centre, width_in_pixels);
+        let required = NumericMode::ALL.into_iter().find(resolves);
+        let mode = available
+            .iter()
+            .copied()
+            .filter(|mode| required.is_some_and(|required| *mode >= required))
+            .min()
+            .or_else(|| available.iter().copied().max())
+            .unwrap_or(NumericMode::F32);
+        Self {
+            pixel_size: 2.0 * half_width / width_in_pixels as f64,
+            mode,
+            required,
+        }
+    }
+
+    /// Whether the mode resolves neighbouring pixels.
+    pub(super) fn is_sufficient(&self) -> bool {
+        self.required.is_some_and(|required| self.mode >= required)
+    }
+}
+
+/// Returns the smallest half width which the most precise of the available modes resolves for a
+/// view centred on the given point across the given number of pixels.
+pub(super) fn min_half_width(
+    centre: Vector2<f64>,
+    width_in_pixels: u32,
+    available: &[NumericMode],
+) -> f64 {
+    available
+        .iter()
+        .max()
+        .unwrap_or(&NumericMode::F32)
+        .min_half_width(centre, width_in_pixels)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{NumericMode, Precision};
+    use crate::dive::Framing;
+    use cgmath::Vector2;
+    use googletest::prelude::*;
+
+    fn framing(half_width: f64) -> Framing {
+        Framing {
+            centre: Vector2::new(-0.75, 0.1),
+            half_width,
+        }
+    }
+
+    #[test]
+    fn deep_views_away_from_origin_exceed_f32() -> Result<()> {
+        let available = [NumericMode::F32];
+
+        verify_that!(
+            (
+                Precision::of_view(framing(1e-3), 1000, &available).is_sufficient(),
+                Precision::of_view(framing(1e-5), 1000, &available).is_sufficient()
+            ),
+            (eq(true), eq(false))
+        )
+    }
+
+    #[test]
+    fn chooses_cheapest_available_mode_which_resolves_view() -> Result<()> {
+        let available = [
+            NumericMode::F32,
+            NumericMode::F64,
+            NumericMode::Perturbation,
+        ];
+
+        let precision = Precision::of_view(framing(1e-8), 1000, &available);
+
+        verify_that!(
+            (precision.required, precision.mode, precision.pixel_size),
+            (
+                some(eq(NumericMode::DoubleDouble)),
+                eq(NumericMode::F64),
+                near(2e-11, 1e-15)
+            )
+        )
+    }
+
+    #[test]
+    fn perturbation_stops_at_smallest_normal_f64() -> Result<()> {
+        let available = [NumericMode::F32, NumericMode::Perturbation];
+
+        let resolved = Precision::of_view(framing(1e-300), 1000, &available);
+        let too_deep = Precision::of_view(framing(1e-306), 1000, &available);
+
+        verify_that!(
+            (
+                resolved.mode,
+                resolved.is_sufficient(),
+                too_deep.mode,
+                too_deep.is_sufficient()
+            ),
+            (
+                eq(NumericMode::Perturbation),
+                eq(true),
+                eq(NumericMode::Perturbation),
+                eq(false)
+            )
+        )
+    }
+}
@@ -0,0 +1,250 @@
+use crate::bookmark::Bookmark;
+use iced::time::Instant;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// How long the transition to a stop takes unless the tour says otherwise.
+const DEFAULT_TRANSITION_SECONDS: f32 = 3.0;
+
+/// One stop of a tour.
+///
+/// Only the bookmark is required, so that an exported history can be presented as it is.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub(super) struct TourStop {
+    pub(super) bookmark: Bookmark,
+    /// The text shown along the bottom of the view while at the stop.
+    #[serde(default)]
+    pub(super) caption: Option<String>,
+    /// How long the view takes to morph from the previous stop to this one.
+    #[serde(default = "default_transition_seconds")]
+    pub(super) transition_seconds: f32,
+    /// How long the view stays at the stop before moving on by itself, or `None` to wait for the
+    /// next stop to be asked for.
+    #[serde(default)]
+    pub(super) hold_seconds: Option<f32>,
+}
+
+fn default_transition_seconds() -> f32 {
+    DEFAULT_TRANSITION_SECONDS
+}
+
+/// Reads a tour, which is a JSON array of [`TourStop`]s.
+pub(super) fn load_tour(path: &Path) -> Result<Vec<TourStop>, String> {
+    let json = std::fs::read_to_string(path).map_err(|error| error.to_string())?;
+    let tour: Vec<TourStop> = serde_json::from_str(&json).map_err(|error| error.to_string())?;
+    if tour.is_empty() {
+        return Err("The tour has no stops".into());
+    }
+    Ok(tour)
+}
+
+/// Asks the user for a tour to present, returning `None` if they cancel.
+#[cfg(not(target_arch = "wasm32"))]
+pub(super) fn choose_tour() -> Option<std::path::PathBuf> {
+    native_dialog::FileDialog::new()
+        .set_title("Present tour")
+        .add_filter("JSON", &["json"])
+        .show_open_single_file()
+        .ok()
+        .flatten()
+}
+
+/// Where the presentation is, as shown by the controls.
+#[derive(Clone, Debug, PartialEq)]
+pub(super) struct PresentationStatus {
+    /// The index of the stop shown or being moved to.
+    pub(super) stop: usize,
+    pub(super) stops: usize,
+    pub(super) caption: Option<String>,
+}
+
+/// What the view shows on a frame of the presentation.
+#[derive(Clone, Debug, PartialEq)]
+pub(super) enum PresentationFrame {
+    /// The state the given fraction of the way from the first bookmark to the second.
+    Morph(Bookmark, Bookmark, f32),
+    /// The stop, which the view reached on this frame.
+    Arrive(Bookmark),
+    /// The view stays where it is.
+    Hold,
+}
+
+/// Plays a tour: morphs the view from stop to stop, each when asked for or after the stop's hold
+/// time.
+pub(super) struct Presentation {
+    tour: Vec<TourStop>,
+    stop: usize,
+    /// The state from which the view morphs to the stop and when it started, while it does.
+    transition: Option<(Bookmark, Instant)>,
+    /// When the view reached the stop.
+    arrived_at: Option<Instant>,
+}
+
+impl Presentation {
+    /// Starts the tour by morphing from the given state to its first stop.
+    pub(super) fn new(tour: Vec<TourStop>, from: Bookmark, now: Instant) -> Self {
+        Self {
+            tour,
+            stop: 0,
+            transition: Some((from, now)),
+            arrived_at: None,
+        }
+    }
+
+    pub(super) fn status(&self) -> PresentationStatus {
+        PresentationStatus {
+            stop: self.stop,
+            stops: self.tour.len(),
+            caption: self.tour[self.stop].caption.clone(),
+        }
+    }
+
+    /// Morphs from the given state to the next stop, returning whether there is one.
+    pub(super) fn next(&mut self, from: Bookmark, now: Instant) -> bool {
+        self.go_to(self.stop + 1, from, now)
+    }
+
+    /// Morphs from the given state to the previous stop, returning whether there is one.
+    pub(super) fn previous(&mut self, from: Bookmark, now: Instant) -> bool {
+        self.stop > 0 && self.go_to(self.stop - 1, from, now)
+    }
+
+    fn go_to(&mut self, stop: usize, from: Bookmark, now: Instant) -> bool {
+        if stop >= self.tour.len() {
+            return false;
+        }
+        self.stop = stop;
+        self.transition = Some((from, now));
+        self.arrived_at = None;
+        true
+    }
+
+    /// Whether frames must keep coming, since the view is moving or will move by itself.
+    pub(super) fn is_moving(&self) -> bool {
+        self.transition.is_some()
+            || (self.tour[self.stop].hold_seconds.is_some() && self.stop + 1 < self.tour.len())
+    }
+
+    /// Advances the presentation to the given time, moving on from a stop whose hold time has
+    /// passed, and returns what the view shows.
+    pub(super) fn frame(&mut self, now: Instant) -> PresentationFrame {
+        let stop = &self.tour[self.stop];
+        if let (Some(hold_seconds), Some(arrived_at)) = (stop.hold_seconds, self.arrived_at) {
+            if (now - arrived_at).as_secs_f32() >= hold_seconds {
+                let from = stop.bookmark.clone();
+                self.next(from, now);
+            }
+        }
+        let Some((from, started)) = &self.transition else {
+            return PresentationFrame::Hold;
+        };
+        let stop = &self.tour[self.stop];
+        let t = (now - *started).as_secs_f32() / stop.transition_seconds;
+        if t < 1.0 {
+            return PresentationFrame::Morph(from.clone(), stop.bookmark.clone(), t);
+        }
+        self.transition = None;
+        self.arrived_at = Some(now);
+        PresentationFrame::Arrive(stop.bookmark.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Presentation, PresentationFrame, TourStop};
+    use crate::bookmark::Bookmark;
+    use cgmath::Vector2;
+    use googletest::prelude::*;
+    use iced::time::Instant;
+    use std::time::Duration;
+
+    fn bookmark(half_width: f32) -> Bookmark {
+        Bookmark::for_test(Vector2::new(0.0, 0.0), half_width)
+    }
+
+    fn stop(half_width: f32, hold_seconds: Option<f32>) -> TourStop {
+        TourStop {
+            bookmark: bookmark(half_width),
+            caption: Some(format!("{half_width}")),
+            transition_seconds: 2.0,
+            hold_seconds,
+        }
+    }
+
+    #[test]
+    fn morphs_to_first_stop_and_holds_there() -> Result<()> {
+        let start = Instant::now();
+        let mut presentation = Presentation::new(vec![stop(1.0, None)], bookmark(2.0), start);
+
+        let frames =
+            [1, 2, 3].map(|seconds| presentation.frame(start + Duration::from_secs(seconds)));
+
+        verify_that!(
+            frames,
+            eq([
+                PresentationFrame::Morph(bookmark(2.0), bookmark(1.0), 0.5),
+                PresentationFrame::Arrive(bookmark(1.0)),
+                PresentationFrame::Hold
+            ])
+        )?;
+        verify_that!(presentation.is_moving(), eq(false))
+    }
+
+    #[test]
+    fn moves_on_after_hold_time() -> Result<()> {
+        let start = Instant::now();
+        let tour = vec![stop(1.0, Some(5.0)), stop(0.5, None)];
+        let mut presentation = Presentation::new(tour, bookmark(1.0), start);
+        presentation.frame(start + Duration::from_secs(2));
+
+        let holding = presentation.frame(start + Duration::from_secs(6));
+        presentation.frame(start + Duration::from_secs(7));
+        let moving_on = presentation.frame(start + Duration::from_secs(8));
+
+        verify_that!(
+            [holding, moving_on],
+            eq([
+                PresentationFrame::Hold,
+                PresentationFrame::Morph(bookmark(1.0), bookmark(0.5), 0.5)
+            ])
+        )?;
+        verify_that!(presentation.status().stop, eq(1))
+    }
+
+    #[test]
+    fn next_and_previous_stay_within_tour() -> Result<()> {
+        let start = Instant::now();
+        let mut presentation =
+            Presentation::new(vec![stop(1.0, None), stop(0.5, None)], bookmark(2.0), start);
+
+        let previous_from_first = presentation.previous(bookmark(2.0), start);
+        let next = presentation.next(bookmark(2.0), start);
+        let next_from_last = presentation.next(bookmark(2.0), start);
+
+        verify_that!(
+            (previous_from_first, next, next_from_last),
+            (eq(false), eq(true), eq(false))
+        )?;
+        verify_that!(presentation.status().caption, some(eq("0.5")))
+    }
+
+    #[test]
+    fn exported_history_reads_as_tour() -> Result<()> {
+        let json = serde_json::json!([{
+            "seconds": 12.5,
+            "bookmark": bookmark(1.0),
+        }]);
+
+        let tour: Vec<TourStop> = serde_json::from_value(json)?;
+
+        verify_that!(
+            tour,
+            eq(vec![TourStop {
+                bookmark: bookmark(1.0),
+                caption: None,
+                transition_seconds: 3.0,
+                hold_seconds: None,
+            }])
+        )
+    }
+}
@@ -0,0 +1,137 @@
+use std::fmt::Display;
+
+/// The resolution at which prints are usually made, e.g. by print shops.
+pub(super) const DEFAULT_DPI: u32 = 300;
+
+/// The most dots per inch which a print may ask for, beyond which no printer resolves detail.
+pub(super) const MAX_DPI: u32 = 2400;
+
+const MILLIMETRES_PER_INCH: f64 = 25.4;
+
+/// A standard size of paper, whose size in pixels for a print follows from its resolution.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(super) enum PaperSize {
+    A4,
+    A3,
+    A2,
+    A1,
+    /// US Letter.
+    Letter,
+    /// US Tabloid, also known as Ledger.
+    Tabloid,
+}
+
+impl PaperSize {
+    pub(super) const ALL: [PaperSize; 6] = [
+        Self::A4,
+        Self::A3,
+        Self::A2,
+        Self::A1,
+        Self::Letter,
+        Self::Tabloid,
+    ];
+
+    /// The width and height of the paper in portrait orientation, in millimetres.
+    fn millimetres(self) -> (f64, f64) {
+        match self {
+            PaperSize::A4 => (210.0, 297.0),
+            PaperSize::A3 => (297.0, 420.0),
+            PaperSize::A2 => (420.0, 594.0),
+            PaperSize::A1 => (594.0, 841.0),
+            PaperSize::Letter => (215.9, 279.4),
+            PaperSize::Tabloid => (279.4, 431.8),
+        }
+    }
+}
+
+impl Display for PaperSize {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PaperSize::A4 => write!(f, "A4"),
+            PaperSize::A3 => write!(f, "A3"),
+            PaperSize::A2 => write!(f, "A2"),
+            PaperSize::A1 => write!(f, "A1"),
+            PaperSize::Letter => write!(f, "Letter"),
+            PaperSize::Tabloid => write!(f, "Tabloid"),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(super) enum Orientation {
+    #[default]
+    Portrait,
+    Landscape,
+}
+
+impl Orientation {
+    pub(super) const ALL: [Orientation; 2] = [Self::Portrait, Self::Landscape];
+}
+
+impl Display for Orientation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Orientation::Portrait => write!(f, "Portrait"),
+            Orientation::Landscape => write!(f, "Landscape"),
+        }
+    }
+}
+
+/// A page to be printed at the given resolution, which fixes the size of the image in pixels and
+/// the resolution written into it, so that print software lays it out at the size of the paper.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(super) struct PrintPreset {
+    pub(super) paper: PaperSize,
+    pub(super) orientation: Orientation,
+    /// The dots per inch, from one to [`MAX_DPI`].
+    pub(super) dpi: u32,
+}
+
+impl PrintPreset {
+    /// The width and height of the image in pixels, rounded to whole pixels.
+    pub(super) fn pixel_size(&self) -> (u32, u32) {
+        let (width, height) = self.paper.millimetres();
+        let pixels = |millimetres: f64| {
+            (millimetres / MILLIMETRES_PER_INCH * f64::from(self.dpi)).round() as u32
+        };
+        match self.orientation {
+            Orientation::Portrait => (pixels(width), pixels(height)),
+            Orientation::Landscape => (pixels(height), pixels(width)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Orientation, PaperSize, PrintPreset, DEFAULT_DPI};
+    use googletest::prelude::*;
+
+    #[test]
+    fn a4_at_300_dpi_has_usual_pixel_size() -> Result<()> {
+        let portrait = PrintPreset {
+            paper: PaperSize::A4,
+            orientation: Orientation::Portrait,
+            dpi: DEFAULT_DPI,
+        };
+        let landscape = PrintPreset {
+            orientation: Orientation::Landscape,
+            ..portrait
+        };
+
+        verify_that!(
+            (portrait.pixel_size(), landscape.pixel_size()),
+            (eq((2480, 3508)), eq((3508, 2480)))
+        )
+    }
+
+    #[test]
+    fn letter_is_measured_in_inches() -> Result<()> {
+        let preset = PrintPreset {
+            paper: PaperSize::Letter,
+            orientation: Orientation::Portrait,
+            dpi: 200,
+        };
+
+        verify_that!(preset.pixel_size(), eq((1700, 2200)))
+    }
+}
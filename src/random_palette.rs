@@ -0,0 +1,192 @@
+use crate::{
+    colour_palette::{CustomPalette, GRADIENT_STOPS},
+    cosine_palette::CosinePalette,
+    oklab::Oklab,
+};
+use std::{
+    collections::hash_map::RandomState,
+    f32::consts::PI,
+    hash::{BuildHasher, Hasher},
+};
+
+/// The range of the lightness of the first colour of the gradient in OKLab, which is also the
+/// colour of the interior of the Mandelbrot set and so kept dark.
+const START_LIGHTNESS: (f32, f32) = (0.12, 0.25);
+/// The range of the lightness of the last colour of the gradient.
+const END_LIGHTNESS: (f32, f32) = (0.85, 0.97);
+/// The range of the chroma at the middle of the gradient, before it is reduced to fit sRGB.
+const PEAK_CHROMA: (f32, f32) = (0.08, 0.2);
+/// The lightness and chroma of the colours of the roots of the Newton fractal.
+const ROOT_LIGHTNESS: f32 = 0.7;
+const ROOT_CHROMA: f32 = 0.13;
+
+/// How far the hue turns along the gradient, in degrees, for each kind of harmony.
+#[derive(Clone, Copy, Debug)]
+enum Harmony {
+    /// Neighbouring hues.
+    Analogous,
+    /// From one hue to about its opposite.
+    Complementary,
+}
+
+impl Harmony {
+    fn hue_span(self) -> (f32, f32) {
+        match self {
+            Harmony::Analogous => (30.0, 90.0),
+            Harmony::Complementary => (150.0, 210.0),
+        }
+    }
+}
+
+/// Makes a palette from the seed by sampling a path through OKLCH, the polar form of the
+/// perceptually uniform OKLab space, so that the colours go well together.
+///
+/// The lightness rises steadily from dark to light, so that the gradient reads in greyscale, while
+/// the hue turns by an analogous or complementary span and the chroma swells towards the middle.
+/// The roots of the Newton fractal take three hues evenly spaced from the first.
+pub(super) fn generate(seed: u64) -> CustomPalette {
+    let mut random = SplitMix64(seed);
+    let harmony = if random.next_f32() < 0.5 {
+        Harmony::Analogous
+    } else {
+        Harmony::Complementary
+    };
+    let start_hue = random.between((0.0, 360.0));
+    let direction = if random.next_f32() < 0.5 { -1.0 } else { 1.0 };
+    let hue_span = direction * random.between(harmony.hue_span());
+    let start_lightness = random.between(START_LIGHTNESS);
+    let end_lightness = random.between(END_LIGHTNESS);
+    let peak_chroma = random.between(PEAK_CHROMA);
+    let gradient = std::array::from_fn(|stop| {
+        let t = stop as f32 / (GRADIENT_STOPS - 1) as f32;
+        let lightness = start_lightness + (end_lightness - start_lightness) * t;
+        let chroma = peak_chroma * (0.35 + 0.65 * (PI * t).sin());
+        oklch_in_gamut(lightness, chroma, start_hue + hue_span * t).to_srgb8()
+    });
+    let roots = std::array::from_fn(|root| {
+        let hue = start_hue + 120.0 * root as f32;
+        oklch_in_gamut(ROOT_LIGHTNESS, ROOT_CHROMA, hue).to_srgb8()
+    });
+    CustomPalette { gradient, roots }
+}
+
+/// Makes a cosine palette from the seed, so that the randomizer can be used as a starting point
+/// for editing one.
+///
+/// Each channel swings about a mean near mid-grey by no more than keeps it within the gamut, so
+/// that nothing is clipped, at a frequency of a whole number of quarter cycles. The phases are
+/// spread evenly from a random start, which turns the hue like a rainbow.
+pub(super) fn generate_cosine(seed: u64) -> CosinePalette {
+    let mut random = SplitMix64(seed);
+    let offset: [f32; 3] = std::array::from_fn(|_| random.between((0.4, 0.6)));
+    let amplitude = offset.map(|offset| random.between((0.25, offset.min(1.0 - offset))));
+    let frequency = std::array::from_fn(|_| random.between((2.0, 6.0)).round() / 4.0);
+    let start_phase = random.next_f32();
+    let phase_spread = random.between((0.1, 0.35));
+    let phase =
+        std::array::from_fn(|channel| (start_phase + phase_spread * channel as f32).fract());
+    CosinePalette {
+        offset,
+        amplitude,
+        frequency,
+        phase,
+    }
+}
+
+/// A seed for [`generate`] or [`generate_cosine`] which differs from call to call and from run to run.
+pub(super) fn fresh_seed() -> u64 {
+    RandomState::new().build_hasher().finish()
+}
+
+/// The colour of the given lightness, chroma and hue in OKLCH, with its chroma reduced until it
+/// fits into the sRGB gamut so that its lightness and hue are kept.
+fn oklch_in_gamut(lightness: f32, chroma: f32, hue: f32) -> Oklab {
+    let in_gamut = |chroma: f32| {
+        Oklab::from_lch(lightness, chroma, hue)
+            .to_linear_srgb()
+            .iter()
+            .all(|channel| (-1e-4..=1.0 + 1e-4).contains(channel))
+    };
+    if in_gamut(chroma) {
+        return Oklab::from_lch(lightness, chroma, hue);
+    }
+    let (mut inside, mut outside) = (0.0, chroma);
+    for _ in 0..20 {
+        let middle = (inside + outside) / 2.0;
+        if in_gamut(middle) {
+            inside = middle;
+        } else {
+            outside = middle;
+        }
+    }
+    Oklab::from_lch(lightness, inside, hue)
+}
+
+/// A small generator of pseudo-random numbers, which is all the variety a palette needs.
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// A number in `[0, 1)`.
+    fn next_f32(&mut self) -> f32 {
+        (self.next_u64() >> 40) as f32 / (1u64 << 24) as f32
+    }
+
+    fn between(&mut self, (low, high): (f32, f32)) -> f32 {
+        low + (high - low) * self.next_f32()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{generate, generate_cosine};
+    use googletest::prelude::*;
+    use palette::{IntoColor, Lab, Srgb};
+
+    #[test]
+    fn same_seed_makes_same_palette() -> Result<()> {
+        verify_that!(
+            (generate(7) == generate(7), generate(7) == generate(8)),
+            (eq(true), eq(false))
+        )
+    }
+
+    #[test]
+    fn lightness_increases_along_gradient() -> Result<()> {
+        for seed in 0..100 {
+            let lightness = generate(seed).gradient.map(|[r, g, b]| {
+                let lab: Lab = Srgb::new(r, g, b)
+                    .into_format::<f32>()
+                    .into_linear()
+                    .into_color();
+                lab.l
+            });
+            for pair in lightness.windows(2) {
+                verify_that!((seed, pair[1]), (anything(), gt(pair[0])))?;
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn cosine_palettes_stay_within_gamut() -> Result<()> {
+        for seed in 0..100 {
+            let palette = generate_cosine(seed);
+            for channel in 0..3 {
+                let (offset, amplitude) = (palette.offset[channel], palette.amplitude[channel]);
+                verify_that!(
+                    (seed, offset - amplitude, offset + amplitude),
+                    (anything(), ge(0.0), le(1.0))
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
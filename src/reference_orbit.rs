@@ -0,0 +1,437 @@
+use crate::{
+    double_double::DoubleDouble,
+    jobs::{self, Job, JobHandle, JobStatus, Poll, Progress},
+};
+use cgmath::{InnerSpace, Vector2};
+use iced_wgpu::wgpu::{self, util::DeviceExt};
+use serde::{Deserialize, Serialize};
+use std::{convert::identity, sync::Arc};
+
+/// How far the centre of the view may drift from the point of a reference orbit, in half widths
+/// of the view, before an orbit closer to it is computed. Orbits of pixels far from the reference
+/// point need rebasing more often, which slows down iterating them.
+const MAX_DRIFT: f64 = 1.0;
+
+/// The number of reference orbits which are kept, so that returning to a recently visited location
+/// reuses its orbit.
+const CAPACITY: usize = 8;
+
+/// The radius beyond which the reference orbit ends, since the orbits of the pixels around it
+/// escape or are rebased onto its start once they get there.
+const ESCAPE_RADIUS: f64 = 2.0;
+
+/// The number of iterations between reports of the progress of computing an orbit.
+const ITERATIONS_PER_REPORT: u32 = 4096;
+
+/// The orbit of the critical point of the Mandelbrot set at one point of the plane, computed on
+/// the CPU in double-double precision.
+///
+/// The perturbation shader iterates the pixels around the point by their small deltas from this
+/// orbit, which stay precise at depths where the pixels themselves cannot be told apart in the
+/// arithmetic of the GPU.
+#[derive(Debug, Serialize, Deserialize)]
+pub(super) struct ReferenceOrbit {
+    point: Vector2<f64>,
+    max_iterations: u32,
+    /// The orbit rounded to `f32`, from zero until `max_iterations` or until it has escaped.
+    points: Vec<[f32; 2]>,
+}
+
+impl ReferenceOrbit {
+    /// Computes the orbit, which ends early if the job computing it is cancelled.
+    pub(super) fn compute(point: Vector2<f64>, max_iterations: u32, progress: &Progress) -> Self {
+        let c_re = DoubleDouble::from(point.x);
+        let c_im = DoubleDouble::from(point.y);
+        let mut re = DoubleDouble::default();
+        let mut im = DoubleDouble::default();
+        let mut points = vec![[0.0, 0.0]];
+        for iteration in 0..max_iterations {
+            if iteration % ITERATIONS_PER_REPORT == 0 {
+                if progress.is_cancelled() {
+                    break;
+                }
+                progress.report(iteration as f32 / max_iterations as f32);
+            }
+            let cross = re * im;
+            (re, im) = (re * re - im * im + c_re, cross + cross + c_im);
+            let (x, y) = (re.to_f64(), im.to_f64());
+            points.push([x as f32, y as f32]);
+            if x * x + y * y > ESCAPE_RADIUS * ESCAPE_RADIUS {
+                break;
+            }
+        }
+        Self {
+            point,
+            max_iterations,
+            points,
+        }
+    }
+
+    pub(super) fn point(&self) -> Vector2<f64> {
+        self.point
+    }
+
+    pub(super) fn points(&self) -> &[[f32; 2]] {
+        &self.points
+    }
+
+    /// Whether the orbit can serve a view with the given centre, half width and iteration budget
+    /// without slowing it down.
+    fn serves(&self, centre: Vector2<f64>, half_width: f64, max_iterations: u32) -> bool {
+        let escaped = self.points.len() <= self.max_iterations as usize;
+        self.drift(centre) <= MAX_DRIFT * half_width
+            && (escaped || max_iterations <= self.max_iterations)
+    }
+
+    fn drift(&self, centre: Vector2<f64>) -> f64 {
+        (centre - self.point).magnitude()
+    }
+}
+
+/// The point and iteration budget for which an orbit was requested.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct OrbitRequest {
+    point: Vector2<f64>,
+    max_iterations: u32,
+}
+
+impl OrbitRequest {
+    /// Whether the orbit requested would serve a view with the given centre, half width and
+    /// iteration budget, so that there is no need to request another.
+    fn covers(&self, centre: Vector2<f64>, half_width: f64, max_iterations: u32) -> bool {
+        (centre - self.point).magnitude() <= MAX_DRIFT * half_width
+            && max_iterations <= self.max_iterations
+    }
+}
+
+/// Runs [`ReferenceOrbit::compute`] on the worker pool.
+#[derive(Serialize, Deserialize)]
+pub(super) struct ComputeReferenceOrbit {
+    pub(super) point: Vector2<f64>,
+    pub(super) max_iterations: u32,
+}
+
+impl Job for ComputeReferenceOrbit {
+    const NAME: &'static str = "Computing reference orbit";
+    type Output = ReferenceOrbit;
+
+    fn run(self, progress: &Progress) -> ReferenceOrbit {
+        ReferenceOrbit::compute(self.point, self.max_iterations, progress)
+    }
+}
+
+/// Keeps the reference orbits of recently viewed locations, so that panning and zooming a little
+/// reuses the orbit instead of computing it again, and computes new ones on the worker pool.
+#[derive(Default)]
+pub(super) struct ReferenceOrbitCache {
+    /// The most recently used orbit comes first.
+    orbits: Vec<Arc<ReferenceOrbit>>,
+    pending: Option<PendingOrbit>,
+    /// The orbit which was cancelled last, which is not requested again until the view moves
+    /// away from it or needs more iterations.
+    declined: Option<OrbitRequest>,
+}
+
+/// An orbit which is being computed in the background.
+struct PendingOrbit {
+    request: OrbitRequest,
+    job: JobHandle<ReferenceOrbit>,
+}
+
+impl ReferenceOrbitCache {
+    /// Returns the cached orbit closest to the centre of the view among those which serve it.
+    ///
+    /// If none does, an orbit at the centre is computed in the background and the most recently
+    /// used orbit is returned until then. Its pixels are still iterated correctly, only more
+    /// slowly. Returns `None` if there is no orbit yet.
+    pub(super) fn orbit_for(
+        &mut self,
+        centre: Vector2<f64>,
+        half_width: f64,
+        max_iterations: u32,
+    ) -> Option<Arc<ReferenceOrbit>> {
+        self.receive();
+        let serving = self
+            .orbits
+            .iter()
+            .enumerate()
+            .filter(|(_, orbit)| orbit.serves(centre, half_width, max_iterations))
+            .min_by(|(_, a), (_, b)| a.drift(centre).total_cmp(&b.drift(centre)))
+            .map(|(index, _)| index);
+        if let Some(index) = serving {
+            self.orbits[..=index].rotate_right(1);
+        } else if !self
+            .pending
+            .as_ref()
+            .map(|pending| pending.request)
+            .into_iter()
+            .chain(self.declined)
+            .any(|request| request.covers(centre, half_width, max_iterations))
+        {
+            self.start(centre, max_iterations);
+        }
+        self.orbits.first().cloned()
+    }
+
+    /// The progress of the orbit being computed in the background, if any.
+    pub(super) fn computing(&self) -> Option<JobStatus> {
+        self.pending.as_ref().map(|pending| pending.job.status())
+    }
+
+    /// Stops computing the orbit in the background, if any. The view is rendered with the orbits
+    /// at hand until it moves on.
+    pub(super) fn cancel(&mut self) {
+        if let Some(pending) = self.pending.take() {
+            self.declined = Some(pending.request);
+            pending.job.cancel();
+        }
+    }
+
+    /// Waits for the orbit being computed in the background, if any, and adds it to the cache.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(super) fn wait(&mut self) {
+        if let Some(pending) = self.pending.take() {
+            if let Some(orbit) = pending.job.wait() {
+                self.insert(orbit);
+            }
+        }
+    }
+
+    fn receive(&mut self) {
+        let Some(pending) = &self.pending else {
+            return;
+        };
+        match pending.job.poll() {
+            Poll::Finished(orbit) => {
+                self.pending = None;
+                self.insert(orbit);
+            }
+            Poll::Running => {}
+            Poll::Failed => self.pending = None,
+        }
+    }
+
+    fn insert(&mut self, orbit: ReferenceOrbit) {
+        self.orbits.insert(0, Arc::new(orbit));
+        self.orbits.truncate(CAPACITY);
+    }
+
+    /// Starts computing the orbit at the given point, cancelling the one being computed, if any.
+    fn start(&mut self, point: Vector2<f64>, max_iterations: u32) {
+        if let Some(pending) = self.pending.take() {
+            pending.job.cancel();
+        }
+        self.declined = None;
+        let job = jobs::spawn(
+            ComputeReferenceOrbit {
+                point,
+                max_iterations,
+            },
+            identity,
+        );
+        self.pending = Some(PendingOrbit {
+            request: OrbitRequest {
+                point,
+                max_iterations,
+            },
+            job,
+        });
+    }
+}
+
+/// The reference orbit with which the view is rendered by perturbation, uploaded to a storage
+/// buffer which the perturbation pipelines take as their third bind group.
+pub(super) struct ReferenceOrbits {
+    device: Arc<wgpu::Device>,
+    bind_group_layout: wgpu::BindGroupLayout,
+    cache: ReferenceOrbitCache,
+    bound: Option<(Arc<ReferenceOrbit>, wgpu::BindGroup)>,
+}
+
+impl ReferenceOrbits {
+    pub(super) fn new(device: Arc<wgpu::Device>) -> Self {
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Reference orbit bind group layout"),
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::COMPUTE,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Storage { read_only: true },
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }],
+        });
+        Self {
+            device,
+            bind_group_layout,
+            cache: ReferenceOrbitCache::default(),
+            bound: None,
+        }
+    }
+
+    pub(super) fn bind_group_layout(&self) -> &wgpu::BindGroupLayout {
+        &self.bind_group_layout
+    }
+
+    /// Binds the orbit which the cache returns for the given view, and returns whether it changed.
+    ///
+    /// The first orbit is waited for, since there is none to render with in the meantime. The web
+    /// cannot wait for it, so there the view is rendered in double-float precision until it
+    /// arrives.
+    pub(super) fn update(
+        &mut self,
+        centre: Vector2<f64>,
+        half_width: f64,
+        max_iterations: u32,
+    ) -> bool {
+        let orbit = self.cache.orbit_for(centre, half_width, max_iterations);
+        #[cfg(not(target_arch = "wasm32"))]
+        let orbit = orbit.or_else(|| {
+            self.cache.wait();
+            self.cache.orbit_for(centre, half_width, max_iterations)
+        });
+        let Some(orbit) = orbit else {
+            return false;
+        };
+        if self
+            .bound
+            .as_ref()
+            .is_some_and(|(bound, _)| Arc::ptr_eq(bound, &orbit))
+        {
+            return false;
+        }
+        let buffer = self
+            .device
+            .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("Reference orbit"),
+                contents: bytemuck::cast_slice(orbit.points()),
+                usage: wgpu::BufferUsages::STORAGE,
+            });
+        let bind_group = self.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Reference orbit bind group"),
+            layout: &self.bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: buffer.as_entire_binding(),
+            }],
+        });
+        self.bound = Some((orbit, bind_group));
+        true
+    }
+
+    pub(super) fn computing(&self) -> Option<JobStatus> {
+        self.cache.computing()
+    }
+
+    pub(super) fn cancel(&mut self) {
+        self.cache.cancel();
+    }
+
+    /// The bound orbit and its bind group, if any.
+    pub(super) fn bound(&self) -> Option<(&ReferenceOrbit, &wgpu::BindGroup)> {
+        self.bound
+            .as_ref()
+            .map(|(orbit, bind_group)| (orbit.as_ref(), bind_group))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ComputeReferenceOrbit, ReferenceOrbitCache};
+    use crate::jobs;
+    use cgmath::Vector2;
+    use googletest::prelude::*;
+    use std::{convert::identity, sync::Arc};
+
+    const CENTRE: Vector2<f64> = Vector2::new(-0.75, 0.1);
+    const HALF_WIDTH: f64 = 1e-12;
+
+    #[test]
+    fn orbit_ends_once_it_escapes() -> Result<()> {
+        let job = ComputeReferenceOrbit {
+            point: Vector2::new(1.0, 0.0),
+            max_iterations: 100,
+        };
+        let orbit = jobs::spawn(job, identity)
+            .wait()
+            .expect("Orbit not computed");
+
+        // 0, 1, 2, 5, the last of which lies beyond the escape radius.
+        verify_that!(
+            (orbit.points().len(), orbit.points()[3]),
+            (eq(4), eq([5.0, 0.0]))
+        )
+    }
+
+    #[test]
+    fn small_pans_reuse_orbit() -> Result<()> {
+        let mut cache = ReferenceOrbitCache::default();
+        let before = cache.orbit_for(CENTRE, HALF_WIDTH, 1000);
+        cache.wait();
+        let orbit = cache
+            .orbit_for(CENTRE, HALF_WIDTH, 1000)
+            .expect("Orbit not computed");
+
+        let panned = cache
+            .orbit_for(
+                CENTRE + Vector2::new(0.5 * HALF_WIDTH, 0.0),
+                HALF_WIDTH,
+                1000,
+            )
+            .expect("Orbit not reused");
+
+        verify_that!(
+            (
+                before.is_none(),
+                Arc::ptr_eq(&orbit, &panned),
+                cache.computing().is_none()
+            ),
+            (eq(true), eq(true), eq(true))
+        )
+    }
+
+    #[test]
+    fn drifting_away_computes_closer_orbit_in_background() -> Result<()> {
+        let mut cache = ReferenceOrbitCache::default();
+        cache.orbit_for(CENTRE, HALF_WIDTH, 1000);
+        cache.wait();
+        let destination = CENTRE + Vector2::new(3.0 * HALF_WIDTH, 0.0);
+
+        let meanwhile = cache
+            .orbit_for(destination, HALF_WIDTH, 1000)
+            .expect("Previous orbit not returned");
+        let computing = cache.computing().is_some();
+        cache.wait();
+        let closer = cache
+            .orbit_for(destination, HALF_WIDTH, 1000)
+            .expect("Orbit not computed");
+
+        verify_that!(
+            (meanwhile.point(), computing, closer.point()),
+            (eq(CENTRE), eq(true), eq(destination))
+        )
+    }
+
+    #[test]
+    fn cancelled_orbit_is_not_requested_again_until_view_moves_on() -> Result<()> {
+        let mut cache = ReferenceOrbitCache::default();
+        cache.orbit_for(CENTRE, HALF_WIDTH, 1000);
+        cache.wait();
+        let destination = CENTRE + Vector2::new(3.0 * HALF_WIDTH, 0.0);
+        cache.orbit_for(destination, HALF_WIDTH, 1000);
+
+        cache.cancel();
+
+        cache.orbit_for(destination, HALF_WIDTH, 1000);
+        let requested_again = cache.computing().is_some();
+        cache.orbit_for(destination, HALF_WIDTH, 2000);
+        let requested_with_more_iterations = cache.computing().is_some();
+
+        verify_that!(
+            (requested_again, requested_with_more_iterations),
+            (eq(false), eq(true))
+        )
+    }
+}
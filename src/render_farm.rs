@@ -0,0 +1,758 @@
+use crate::export_manifest::{self, ExportManifest, PartialFrame};
+use crate::throughput::{self, Throughput};
+use crate::{
+    annotation::Annotation, bookmark::Bookmark, compositor::Compositor, error::Error, export,
+    fractal_view::View, gpu::Gpu, transparency::Transparency, vector_mode::VectorMode,
+};
+use cgmath::Vector2;
+use iced::time::Instant;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{HashMap, VecDeque},
+    io::{self, BufRead, BufReader, Read, Write},
+    net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs},
+    path::{Path, PathBuf},
+    sync::{Arc, Condvar, Mutex, MutexGuard},
+    time::Duration,
+};
+
+/// The width and height of the tiles handed to workers. Small enough that no tile can keep a GPU
+/// busy for long enough that its driver resets it, even at the largest iteration budget.
+pub(super) const TILE_SIZE: u32 = 512;
+
+/// How many frames may be waiting for tiles at once before submitting another blocks, which
+/// bounds the memory held by the images being assembled.
+const MAX_FRAMES_IN_FLIGHT: usize = 4;
+
+const BYTES_PER_PIXEL: usize = 4;
+
+/// How often the progress of the farm is reported while tiles come back, besides whenever a frame
+/// is written.
+const REPORT_INTERVAL: Duration = Duration::from_secs(1);
+
+/// A rectangle of pixels of an image, counted from its top-left corner.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub(super) struct Tile {
+    pub(super) x: u32,
+    pub(super) y: u32,
+    pub(super) width: u32,
+    pub(super) height: u32,
+}
+
+impl Tile {
+    /// Covers an image of the given size with tiles of at most `tile_size` along either side, row
+    /// by row.
+    pub(super) fn split(width: u32, height: u32, tile_size: u32) -> Vec<Tile> {
+        (0..height)
+            .step_by(tile_size as usize)
+            .flat_map(|y| {
+                (0..width).step_by(tile_size as usize).map(move |x| Tile {
+                    x,
+                    y,
+                    width: tile_size.min(width - x),
+                    height: tile_size.min(height - y),
+                })
+            })
+            .collect()
+    }
+
+    /// The bottom-left and top-right corners of the tile in the viewport of the whole image.
+    pub(super) fn viewport_corners(&self, width: u32, height: u32) -> (Vector2<f32>, Vector2<f32>) {
+        let (width, height) = (width as f32, height as f32);
+        (
+            Vector2::new(
+                self.x as f32 / width - 0.5,
+                0.5 - (self.y + self.height) as f32 / height,
+            ),
+            Vector2::new(
+                (self.x + self.width) as f32 / width - 0.5,
+                0.5 - self.y as f32 / height,
+            ),
+        )
+    }
+}
+
+/// Everything a worker needs to render one tile of a frame.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct TileJob {
+    frame: u64,
+    /// The view of the whole frame.
+    view: Bookmark,
+    bailout: f32,
+    antialiasing: bool,
+    compositor: Compositor,
+    transparency: Transparency,
+    vector_mode: VectorMode,
+    curve_depth: u32,
+    annotations: Vec<Annotation>,
+    /// The size of the whole frame.
+    width: u32,
+    height: u32,
+    tile: Tile,
+}
+
+/// What the coordinator sends to a worker, as a line of JSON. A worker answers each
+/// [`Request::Render`] with the tightly packed RGBA pixels of the tile and nothing else.
+#[derive(Debug, Serialize, Deserialize)]
+enum Request {
+    Render(Box<TileJob>),
+    /// No more tiles will be handed out, so the worker may exit.
+    Finish,
+}
+
+/// Hands out the tiles of the frames submitted to the workers which connect to it, and writes
+/// each frame as a PNG file once all of its tiles are back.
+///
+/// Tiles are handed out in the order in which their frames were submitted, so that frames of an
+/// animation are completed roughly in order. A tile whose worker disconnects before returning it
+/// is handed to the next worker which asks.
+///
+/// Clones share the farm, so that one can cancel it while another waits for it to finish.
+#[derive(Clone)]
+pub(super) struct Coordinator {
+    shared: Arc<Shared>,
+    local_addr: SocketAddr,
+    tile_size: u32,
+}
+
+struct Shared {
+    state: Mutex<FarmState>,
+    changed: Condvar,
+}
+
+#[derive(Default)]
+struct FarmState {
+    pending: VecDeque<TileJob>,
+    frames: HashMap<u64, Frame>,
+    next_frame: u64,
+    /// Set once no more frames will be submitted.
+    finishing: bool,
+    errors: Vec<String>,
+    /// The rate at which tiles come back, from the first submission on.
+    throughput: Option<Throughput>,
+    tiles_submitted: u64,
+    /// The number of tiles of the frame submitted last, as an estimate for those to come.
+    tiles_per_frame: u64,
+    /// The number of frames which the script announced it will submit.
+    expected_frames: Option<u64>,
+    last_report: Option<Instant>,
+    /// Where the frames written are recorded, if the export can be resumed.
+    manifest: Option<Arc<ExportManifest>>,
+}
+
+impl FarmState {
+    /// The tiles of the frames submitted, and of those which are still to come if the number of
+    /// frames was announced.
+    fn expected_tiles(&self) -> u64 {
+        let frames_to_come = self
+            .expected_frames
+            .map_or(0, |expected| expected.saturating_sub(self.next_frame));
+        self.tiles_submitted + frames_to_come * self.tiles_per_frame
+    }
+}
+
+struct Frame {
+    path: PathBuf,
+    key: u64,
+    width: u32,
+    height: u32,
+    /// The resolution written into the image, if it is meant for print.
+    dpi: Option<u32>,
+    pixels: Vec<u8>,
+    /// The tiles still to come back, after which the frame is written.
+    remaining: usize,
+    /// The tiles come back so far, kept on disk if the export can be resumed.
+    partial: Option<PartialFrame>,
+}
+
+impl Frame {
+    /// Copies the pixels of a tile into the frame.
+    fn paste(&mut self, tile: &Tile, pixels: &[u8]) {
+        let frame_row = self.width as usize * BYTES_PER_PIXEL;
+        let tile_row = tile.width as usize * BYTES_PER_PIXEL;
+        for (row, source) in pixels.chunks_exact(tile_row).enumerate() {
+            let start = (tile.y as usize + row) * frame_row + tile.x as usize * BYTES_PER_PIXEL;
+            self.pixels[start..start + tile_row].copy_from_slice(source);
+        }
+        self.remaining -= 1;
+    }
+
+    /// Takes the pixels of the frame to be written. The frame itself stays behind with no pixels
+    /// until it is.
+    fn complete(&mut self, id: u64, manifest: &Option<Arc<ExportManifest>>) -> CompletedFrame {
+        CompletedFrame {
+            id,
+            path: self.path.clone(),
+            key: self.key,
+            width: self.width,
+            height: self.height,
+            dpi: self.dpi,
+            pixels: std::mem::take(&mut self.pixels),
+            partial: self.partial.take(),
+            manifest: manifest.clone(),
+        }
+    }
+}
+
+/// A frame whose tiles are all back, to be written outside the lock.
+struct CompletedFrame {
+    id: u64,
+    path: PathBuf,
+    key: u64,
+    width: u32,
+    height: u32,
+    dpi: Option<u32>,
+    pixels: Vec<u8>,
+    partial: Option<PartialFrame>,
+    manifest: Option<Arc<ExportManifest>>,
+}
+
+impl Coordinator {
+    /// Listens for workers on the given address, e.g. `0.0.0.0:9100`.
+    pub(super) fn bind(address: impl ToSocketAddrs, tile_size: u32) -> io::Result<Self> {
+        let listener = TcpListener::bind(address)?;
+        let local_addr = listener.local_addr()?;
+        let shared = Arc::new(Shared {
+            state: Mutex::new(FarmState::default()),
+            changed: Condvar::new(),
+        });
+        let accepting = shared.clone();
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                match stream {
+                    Ok(stream) => {
+                        let shared = accepting.clone();
+                        std::thread::spawn(move || shared.serve(stream));
+                    }
+                    Err(error) => tracing::warn!("Could not accept worker: {error}"),
+                }
+            }
+        });
+        Ok(Self {
+            shared,
+            local_addr,
+            tile_size,
+        })
+    }
+
+    pub(super) fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+
+    /// Records each frame written in the manifest, and keeps the tiles of frames being assembled
+    /// beside their images, so that an interrupted export resumes with the tiles already back.
+    pub(super) fn record_in(&self, manifest: Arc<ExportManifest>) {
+        self.shared.state.lock().unwrap().manifest = Some(manifest);
+    }
+
+    /// Queues the tiles of the view at the given size, to be written to the path once rendered,
+    /// with the resolution for print if one is given. Tiles kept from an interrupted export of the
+    /// same frame are not rendered again. Blocks while [`MAX_FRAMES_IN_FLIGHT`] frames are still
+    /// waiting for tiles.
+    pub(super) fn submit(
+        &self,
+        path: impl AsRef<Path>,
+        view: &View,
+        annotations: &[Annotation],
+        width: u32,
+        height: u32,
+        dpi: Option<u32>,
+    ) {
+        let mut state = self
+            .shared
+            .wait_until(|state| state.frames.len() < MAX_FRAMES_IN_FLIGHT);
+        let frame_id = state.next_frame;
+        state.next_frame += 1;
+        let path = path.as_ref();
+        let key = export_manifest::frame_key(view, annotations, width, height);
+        let partial = state.manifest.as_ref().and_then(|_| {
+            PartialFrame::open(path, key)
+                .inspect_err(|error| {
+                    tracing::warn!("Could not keep tiles of {}: {error}", path.display())
+                })
+                .ok()
+        });
+        let mut tiles = Tile::split(width, height, self.tile_size);
+        let mut frame = Frame {
+            path: path.to_path_buf(),
+            key,
+            width,
+            height,
+            dpi,
+            pixels: vec![0; width as usize * height as usize * BYTES_PER_PIXEL],
+            remaining: tiles.len(),
+            partial,
+        };
+        if let Some(partial) = frame.partial.clone() {
+            tiles.retain(|tile| {
+                let length = tile.width as usize * tile.height as usize * BYTES_PER_PIXEL;
+                match partial.load(tile.x, tile.y, length) {
+                    Some(pixels) => {
+                        frame.paste(tile, &pixels);
+                        false
+                    }
+                    None => true,
+                }
+            });
+        }
+        state
+            .throughput
+            .get_or_insert_with(|| Throughput::new(Instant::now()));
+        state.tiles_submitted += tiles.len() as u64;
+        state.tiles_per_frame = tiles.len() as u64;
+        // A frame whose tiles were all kept only needs to be written.
+        let completed = (frame.remaining == 0).then(|| frame.complete(frame_id, &state.manifest));
+        state.frames.insert(frame_id, frame);
+        state.pending.extend(tiles.into_iter().map(|tile| TileJob {
+            frame: frame_id,
+            view: view.bookmark(),
+            bailout: view.bailout(),
+            antialiasing: view.antialiasing(),
+            compositor: view.compositor(),
+            transparency: view.transparency(),
+            vector_mode: view.vector_mode(),
+            curve_depth: view.curve_depth(),
+            annotations: annotations.to_vec(),
+            width,
+            height,
+            tile,
+        }));
+        self.shared.changed.notify_all();
+        drop(state);
+        if let Some(completed) = completed {
+            self.shared.write(completed);
+        }
+    }
+
+    /// Announces how many frames will be submitted in all, so that the time left can be estimated
+    /// for the frames which are still to come.
+    pub(super) fn expect_frames(&self, frames: u64) {
+        self.shared.state.lock().unwrap().expected_frames = Some(frames);
+    }
+
+    /// Drops the frames still waiting for tiles and lets the workers go, so that finishing only
+    /// waits for the frames being written. Tiles which workers are rendering are discarded when
+    /// they come back.
+    pub(super) fn cancel(&self) {
+        let mut state = self.shared.state.lock().unwrap();
+        state.pending.clear();
+        // Frames whose pixels were taken are being written.
+        state.frames.retain(|_, frame| frame.pixels.is_empty());
+        state.finishing = true;
+        self.shared.changed.notify_all();
+    }
+
+    /// Waits until every frame submitted has been written, then lets the workers go. Returns why
+    /// any frames could not be written.
+    pub(super) fn finish(&self) -> Result<(), String> {
+        self.shared.state.lock().unwrap().finishing = true;
+        self.shared.changed.notify_all();
+        let mut state = self.shared.wait_until(|state| state.frames.is_empty());
+        let errors = std::mem::take(&mut state.errors);
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors.join("\n"))
+        }
+    }
+}
+
+impl Shared {
+    fn wait_until(&self, condition: impl Fn(&FarmState) -> bool) -> MutexGuard<'_, FarmState> {
+        self.changed
+            .wait_while(self.state.lock().unwrap(), |state| !condition(state))
+            .unwrap()
+    }
+
+    /// Hands tiles to the worker on the other end of the stream until the coordinator finishes or
+    /// the worker disconnects.
+    fn serve(&self, stream: TcpStream) {
+        let peer = stream
+            .peer_addr()
+            .map_or_else(|_| "unknown".into(), |peer| peer.to_string());
+        tracing::info!("Worker {peer} connected");
+        let mut writer = &stream;
+        let mut reader = &stream;
+        loop {
+            let job = {
+                let mut state = self.wait_until(|state| {
+                    !state.pending.is_empty() || (state.finishing && state.frames.is_empty())
+                });
+                state.pending.pop_front()
+            };
+            let Some(job) = job else {
+                let _ = send(&mut writer, &Request::Finish);
+                return;
+            };
+            let mut pixels =
+                vec![0; job.tile.width as usize * job.tile.height as usize * BYTES_PER_PIXEL];
+            let result = send(&mut writer, &Request::Render(Box::new(job.clone())))
+                .and_then(|()| reader.read_exact(&mut pixels));
+            if let Err(error) = result {
+                tracing::warn!("Worker {peer} disconnected: {error}");
+                self.state.lock().unwrap().pending.push_front(job);
+                self.changed.notify_all();
+                return;
+            }
+            self.keep(&job, &pixels);
+            self.place(&job, &pixels);
+        }
+    }
+
+    /// Keeps the pixels of a tile on disk if the export can be resumed.
+    fn keep(&self, job: &TileJob, pixels: &[u8]) {
+        let partial = {
+            let state = self.state.lock().unwrap();
+            let frame = state.frames.get(&job.frame);
+            frame.and_then(|frame| Some((frame.partial.clone()?, frame.path.clone())))
+        };
+        if let Some((partial, path)) = partial {
+            if let Err(error) = partial.save(job.tile.x, job.tile.y, pixels) {
+                tracing::warn!("Could not keep tile of {}: {error}", path.display());
+            }
+        }
+    }
+
+    /// Copies the pixels of a tile into its frame, and writes the frame once it is complete.
+    fn place(&self, job: &TileJob, pixels: &[u8]) {
+        let (completed, report) = {
+            let mut state = self.state.lock().unwrap();
+            let state = &mut *state;
+            let Some(frame) = state.frames.get_mut(&job.frame) else {
+                return;
+            };
+            frame.paste(&job.tile, pixels);
+            let completed =
+                (frame.remaining == 0).then(|| frame.complete(job.frame, &state.manifest));
+            let now = Instant::now();
+            let expected_tiles = state.expected_tiles();
+            let throughput = state.throughput.get_or_insert_with(|| Throughput::new(now));
+            throughput.complete(now);
+            let due = state
+                .last_report
+                .is_none_or(|last_report| now - last_report >= REPORT_INTERVAL);
+            let report = (completed.is_some() || due).then(|| {
+                state.last_report = Some(now);
+                throughput::describe_progress(throughput, Some(expected_tiles), ("tile", "tiles"))
+            });
+            (completed, report)
+        };
+        if let Some(report) = report {
+            eprintln!("{report}");
+        }
+        if let Some(completed) = completed {
+            self.write(completed);
+        }
+    }
+
+    /// Writes a frame whose tiles are all back, and records it in the manifest if there is one.
+    ///
+    /// Frames are written outside the lock, since large ones take a while to encode. They stay in
+    /// the map meanwhile, so that finishing waits for them.
+    fn write(&self, frame: CompletedFrame) {
+        let path = &frame.path;
+        let result = export::save_image(path, &frame.pixels, frame.width, frame.height, frame.dpi)
+            .map_err(|error| format!("Could not write {}: {error}", path.display()))
+            .and_then(|()| match &frame.manifest {
+                Some(manifest) => manifest
+                    .record(path, frame.key)
+                    .map_err(|error| format!("Could not record {}: {error}", path.display())),
+                None => Ok(()),
+            });
+        let mut state = self.state.lock().unwrap();
+        match result {
+            Ok(()) => {
+                tracing::info!("Wrote {}", path.display());
+                if let Some(partial) = frame.partial {
+                    if let Err(error) = partial.remove() {
+                        tracing::warn!("Could not delete tiles of {}: {error}", path.display());
+                    }
+                }
+            }
+            Err(error) => state.errors.push(error),
+        }
+        state.frames.remove(&frame.id);
+        self.changed.notify_all();
+    }
+}
+
+fn send(writer: &mut impl Write, request: &Request) -> io::Result<()> {
+    let mut line = serde_json::to_vec(request).map_err(io::Error::from)?;
+    line.push(b'\n');
+    writer.write_all(&line)
+}
+
+/// Connects a worker for each of the GPUs to the coordinator at the given address, each with a
+/// queue of its own, and renders the tiles which the coordinator hands out until it lets them go.
+///
+/// The coordinator hands tiles to whichever worker asks first, so faster GPUs render more of them.
+pub(super) fn run_workers(address: SocketAddr, gpus: Vec<Gpu>) -> Result<(), Error> {
+    let workers: Vec<_> = gpus
+        .into_iter()
+        .map(|gpu| {
+            std::thread::spawn(move || {
+                let name = gpu.adapter_info.name.clone();
+                run_worker(address, gpu)
+                    .inspect_err(|error| tracing::warn!("Worker on {name} failed: {error}"))
+            })
+        })
+        .collect();
+    // Every worker is joined, even once one failed, so that the others finish their tiles.
+    let mut result = Ok(());
+    for worker in workers {
+        result = result.and(worker.join().expect("Worker panicked"));
+    }
+    result
+}
+
+fn run_worker(address: SocketAddr, gpu: Gpu) -> Result<(), Error> {
+    let stream = TcpStream::connect(address)?;
+    let mut view = View::new(&gpu);
+    view.wait_for_pipelines();
+    let mut reader = BufReader::new(&stream);
+    let mut writer = &stream;
+    let mut line = String::new();
+    loop {
+        line.clear();
+        if reader.read_line(&mut line)? == 0 {
+            return Ok(());
+        }
+        match serde_json::from_str(&line).map_err(io::Error::from)? {
+            Request::Render(job) => {
+                writer.write_all(&render_tile(&gpu, &mut view, &job))?;
+            }
+            Request::Finish => return Ok(()),
+        }
+    }
+}
+
+fn render_tile(gpu: &Gpu, view: &mut View, job: &TileJob) -> Vec<u8> {
+    // Switching the curve frames it anew, so the curve is chosen before the bookmark places it.
+    view.set_vector_mode(gpu, job.vector_mode, job.curve_depth);
+    view.apply_bookmark(&job.view);
+    view.set_bailout(job.bailout);
+    view.set_antialiasing(gpu, job.antialiasing);
+    view.set_compositor(job.compositor);
+    view.set_transparency(job.transparency);
+    let (bottom_left, top_right) = job.tile.viewport_corners(job.width, job.height);
+    view.crop(bottom_left, top_right);
+    export::render_annotated_to_rgba(gpu, view, &job.annotations, job.tile.width, job.tile.height)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{run_workers, Coordinator, FarmState, Tile, TILE_SIZE};
+    use crate::{
+        compositor::{BlendMode, Compositor, Layer},
+        export,
+        export_manifest::{self, ExportManifest, PartialFrame},
+        fractal_view::View,
+        gpu::Gpu,
+        iteration_budget::is_too_expensive,
+        iteration_budget::MAX_ITERATIONS,
+        vector_mode::VectorMode,
+    };
+    use cgmath::Vector2;
+    use googletest::prelude::*;
+    use std::sync::Arc;
+
+    /// Renders the view on a farm with a worker on a GPU of its own into an image of the given
+    /// name, and returns the pixels written.
+    fn render_on_farm(
+        gpu: Gpu,
+        view: View,
+        width: u32,
+        height: u32,
+        name: &str,
+    ) -> Result<Vec<u8>> {
+        let coordinator = Coordinator::bind("127.0.0.1:0", 16)?;
+        let path = std::env::temp_dir().join(format!(
+            "fractal_explorer_farm_{name}_{}.png",
+            std::process::id()
+        ));
+
+        coordinator.submit(&path, &view, &[], width, height, None);
+        // OpenGL drivers may not let two devices outlive each other on different threads.
+        drop((view, gpu));
+        let address = coordinator.local_addr();
+        let gpus = vec![Gpu::new_without_surface()?];
+        let workers = std::thread::spawn(move || run_workers(address, gpus));
+        verify_that!(coordinator.finish(), ok(anything()))?;
+        workers.join().unwrap()?;
+
+        let pixels = image::open(&path)?.into_rgba8().into_raw();
+        std::fs::remove_file(&path)?;
+        Ok(pixels)
+    }
+
+    /// The number of pixels which differ between two renders.
+    fn differing_pixels(first: &[u8], second: &[u8]) -> usize {
+        first
+            .chunks(4)
+            .zip(second.chunks(4))
+            .filter(|(first, second)| first != second)
+            .count()
+    }
+
+    #[test]
+    fn tiles_cover_image_once() -> Result<()> {
+        let tiles = Tile::split(10, 5, 4);
+
+        let mut covered = vec![0; 10 * 5];
+        for tile in &tiles {
+            for y in tile.y..tile.y + tile.height {
+                for x in tile.x..tile.x + tile.width {
+                    covered[(y * 10 + x) as usize] += 1;
+                }
+            }
+        }
+        verify_that!(tiles.len(), eq(6))?;
+        verify_that!(covered, eq(vec![1; 10 * 5]))
+    }
+
+    #[test]
+    fn expected_tiles_include_announced_frames_to_come() -> Result<()> {
+        let state = FarmState {
+            next_frame: 2,
+            tiles_submitted: 12,
+            tiles_per_frame: 6,
+            expected_frames: Some(5),
+            ..FarmState::default()
+        };
+
+        verify_that!(state.expected_tiles(), eq(30))
+    }
+
+    #[test]
+    fn no_tile_is_too_expensive() -> Result<()> {
+        verify_that!(
+            is_too_expensive(TILE_SIZE, TILE_SIZE, MAX_ITERATIONS),
+            eq(false)
+        )
+    }
+
+    #[test]
+    fn render_split_across_gpus_matches_local_render() -> Result<()> {
+        let (width, height) = (40, 24);
+        let gpu = Gpu::new_without_surface()?;
+        let mut view = View::new(&gpu);
+        view.wait_for_pipelines();
+        view.look_at(Vector2::new(-0.75, 0.1), 0.5);
+        let local = export::render_to_rgba(&gpu, &view, width, height);
+
+        let farmed = render_on_farm(gpu, view, width, height, "split")?;
+
+        // The tiles map their pixels onto the plane with a transform of their own, which rounds
+        // differently, so a few pixels on the boundary may land in another band.
+        verify_that!(
+            differing_pixels(&local, &farmed),
+            le((width * height / 20) as usize)
+        )
+    }
+
+    #[test]
+    fn farm_renders_colouring_layers() -> Result<()> {
+        let (width, height) = (40, 24);
+        let gpu = Gpu::new_without_surface()?;
+        let mut view = View::new(&gpu);
+        view.wait_for_pipelines();
+        view.look_at(Vector2::new(-0.75, 0.1), 0.5);
+        let plain = export::render_to_rgba(&gpu, &view, width, height);
+        view.set_compositor(Compositor {
+            base: Layer::DistanceEstimate,
+            overlay: Layer::StripeAverage,
+            blend_mode: BlendMode::Multiply,
+            opacity: 1.0,
+        });
+        let local = export::render_to_rgba(&gpu, &view, width, height);
+
+        let farmed = render_on_farm(gpu, view, width, height, "layers")?;
+
+        verify_that!(
+            differing_pixels(&local, &plain),
+            gt((width * height / 4) as usize)
+        )?;
+        verify_that!(
+            differing_pixels(&local, &farmed),
+            le((width * height / 20) as usize)
+        )
+    }
+
+    #[test]
+    fn farm_renders_curves() -> Result<()> {
+        let (width, height) = (40, 24);
+        let gpu = Gpu::new_without_surface()?;
+        let mut view = View::new(&gpu);
+        view.wait_for_pipelines();
+        let plain = export::render_to_rgba(&gpu, &view, width, height);
+        view.set_vector_mode(&gpu, VectorMode::Hilbert, 3);
+        let local = export::render_to_rgba(&gpu, &view, width, height);
+
+        let farmed = render_on_farm(gpu, view, width, height, "curve")?;
+
+        verify_that!(
+            differing_pixels(&local, &plain),
+            gt((width * height / 4) as usize)
+        )?;
+        verify_that!(
+            differing_pixels(&local, &farmed),
+            le((width * height / 20) as usize)
+        )
+    }
+
+    #[test]
+    fn cancelled_farm_finishes_without_writing_frames() -> Result<()> {
+        let gpu = Gpu::new_without_surface()?;
+        let mut view = View::new(&gpu);
+        view.wait_for_pipelines();
+        let coordinator = Coordinator::bind("127.0.0.1:0", 16)?;
+        let path = std::env::temp_dir().join(format!(
+            "fractal_explorer_cancelled_farm_{}.png",
+            std::process::id()
+        ));
+        coordinator.submit(&path, &view, &[], 40, 24, None);
+
+        coordinator.clone().cancel();
+
+        verify_that!(coordinator.finish(), ok(anything()))?;
+        verify_that!(path.exists(), eq(false))
+    }
+
+    #[test]
+    fn frame_resumes_from_kept_tiles() -> Result<()> {
+        let (width, height) = (40, 24);
+        let gpu = Gpu::new_without_surface()?;
+        let mut view = View::new(&gpu);
+        view.wait_for_pipelines();
+        let temporary = |extension: &str| {
+            std::env::temp_dir().join(format!(
+                "fractal_explorer_resumed_{}.{extension}",
+                std::process::id()
+            ))
+        };
+        let (path, manifest_path) = (temporary("png"), temporary("json"));
+        let _ = std::fs::remove_file(&manifest_path);
+        let manifest = Arc::new(ExportManifest::open(manifest_path));
+        let key = export_manifest::frame_key(&view, &[], width, height);
+        // Every tile came back before the interruption, so no worker is needed.
+        let partial = PartialFrame::open(&path, key)?;
+        for tile in Tile::split(width, height, 16) {
+            partial.save(
+                tile.x,
+                tile.y,
+                &vec![255; tile.width as usize * tile.height as usize * 4],
+            )?;
+        }
+        let coordinator = Coordinator::bind("127.0.0.1:0", 16)?;
+        coordinator.record_in(manifest.clone());
+
+        coordinator.submit(&path, &view, &[], width, height, None);
+
+        verify_that!(coordinator.finish(), ok(anything()))?;
+        verify_that!(
+            image::open(&path)?.into_rgba8().into_raw(),
+            eq(vec![255; (width * height * 4) as usize])
+        )?;
+        verify_that!(manifest.is_written(&path, key), eq(true))
+    }
+}
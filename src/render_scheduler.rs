@@ -0,0 +1,338 @@
+use iced::time::{Duration, Instant};
+use iced_wgpu::wgpu;
+use iced_winit::winit::event_loop::ControlFlow;
+use std::{collections::BTreeSet, fmt::Display};
+
+/// Something which requires the window to be redrawn.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(super) enum Change {
+    /// The visible region of the complex plane moved.
+    View,
+    /// A parameter affecting the rendering of the fractal changed.
+    Parameters,
+    /// The window was resized or its surface must be recreated.
+    Window,
+    /// The state of the controls drawn on top of the fractal may have changed.
+    Ui,
+}
+
+/// The changes which have accumulated since the last frame was drawn.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(super) struct Changes {
+    pub(super) view: bool,
+    pub(super) parameters: bool,
+    pub(super) window: bool,
+    pub(super) ui: bool,
+}
+
+/// The highest frame rate in low-power mode while the window has focus.
+const LOW_POWER_FRAMES_PER_SECOND: u32 = 30;
+
+/// The highest frame rate in low-power mode while the window does not have focus.
+const UNFOCUSED_LOW_POWER_FRAMES_PER_SECOND: u32 = 10;
+
+/// Decides when the window is redrawn.
+///
+/// Code which changes what is shown reports the change with [`RenderScheduler::invalidate`]
+/// rather than requesting a redraw itself. Redraws are then requested once per pass through the
+/// event loop, and only if something changed or an animation is running.
+#[derive(Debug, Default)]
+pub(super) struct RenderScheduler {
+    pending: Changes,
+    animations: BTreeSet<&'static str>,
+    present_mode: PresentMode,
+    frame_rate_cap: FrameRateCap,
+    /// Whether redraws are throttled to save power, which is chosen by the user since winit does
+    /// not report whether the device runs on battery.
+    low_power: bool,
+    unfocused: bool,
+    /// Whether the window is entirely covered by other windows, in which case nothing is drawn.
+    occluded: bool,
+    /// Whether the window is minimized, which some platforms report as a size of zero.
+    minimized: bool,
+    last_frame: Option<Instant>,
+}
+
+/// A way of presenting frames to the window which can be chosen in the controls.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(super) struct PresentMode(pub(super) wgpu::PresentMode);
+
+impl PresentMode {
+    pub(super) const ALL: [Self; 4] = [
+        Self(wgpu::PresentMode::AutoVsync),
+        Self(wgpu::PresentMode::AutoNoVsync),
+        Self(wgpu::PresentMode::Fifo),
+        Self(wgpu::PresentMode::Mailbox),
+    ];
+}
+
+impl Default for PresentMode {
+    fn default() -> Self {
+        Self(wgpu::PresentMode::AutoVsync)
+    }
+}
+
+impl Display for PresentMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.0 {
+            wgpu::PresentMode::AutoVsync => write!(f, "Vsync"),
+            wgpu::PresentMode::AutoNoVsync => write!(f, "No vsync"),
+            mode => write!(f, "{mode:?}"),
+        }
+    }
+}
+
+/// The highest number of frames drawn per second, or `None` for no limit.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(super) struct FrameRateCap(pub(super) Option<u32>);
+
+impl FrameRateCap {
+    pub(super) const ALL: [Self; 4] = [Self(None), Self(Some(30)), Self(Some(60)), Self(Some(120))];
+}
+
+impl Display for FrameRateCap {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.0 {
+            Some(frames_per_second) => write!(f, "{frames_per_second} fps"),
+            None => write!(f, "Uncapped"),
+        }
+    }
+}
+
+impl RenderScheduler {
+    pub(super) fn invalidate(&mut self, change: Change) {
+        match change {
+            Change::View => self.pending.view = true,
+            Change::Parameters => self.pending.parameters = true,
+            Change::Window => self.pending.window = true,
+            Change::Ui => self.pending.ui = true,
+        }
+    }
+
+    /// Renders continuously until the animation with the same name is stopped.
+    pub(super) fn start_animation(&mut self, name: &'static str) {
+        self.animations.insert(name);
+    }
+
+    pub(super) fn stop_animation(&mut self, name: &'static str) {
+        self.animations.remove(name);
+    }
+
+    pub(super) fn is_animating(&self) -> bool {
+        !self.animations.is_empty()
+    }
+
+    /// Changes the present mode, which takes effect when the surface is reconfigured for the next
+    /// frame.
+    pub(super) fn set_present_mode(&mut self, present_mode: PresentMode) {
+        self.present_mode = present_mode;
+        self.invalidate(Change::Window);
+    }
+
+    pub(super) fn present_mode(&self) -> wgpu::PresentMode {
+        self.present_mode.0
+    }
+
+    pub(super) fn set_frame_rate_cap(&mut self, frame_rate_cap: FrameRateCap) {
+        self.frame_rate_cap = frame_rate_cap;
+    }
+
+    /// Turns low-power mode on or off, in which the frame rate is limited further, and all the more
+    /// while the window does not have focus.
+    pub(super) fn set_low_power(&mut self, low_power: bool) {
+        self.low_power = low_power;
+    }
+
+    pub(super) fn set_focused(&mut self, focused: bool) {
+        self.unfocused = !focused;
+    }
+
+    pub(super) fn is_focused(&self) -> bool {
+        !self.unfocused
+    }
+
+    pub(super) fn set_occluded(&mut self, occluded: bool) {
+        let was_visible = self.is_visible();
+        self.occluded = occluded;
+        self.resume_if_shown(was_visible);
+    }
+
+    pub(super) fn set_minimized(&mut self, minimized: bool) {
+        let was_visible = self.is_visible();
+        self.minimized = minimized;
+        self.resume_if_shown(was_visible);
+    }
+
+    /// Whether the window can be seen, so that frames should be drawn at all.
+    pub(super) fn is_visible(&self) -> bool {
+        !self.occluded && !self.minimized
+    }
+
+    /// Recreates the surface once a hidden window is shown again, since it may have been lost or
+    /// resized in the meantime.
+    fn resume_if_shown(&mut self, was_visible: bool) {
+        if !was_visible && self.is_visible() {
+            self.invalidate(Change::Window);
+        }
+    }
+
+    pub(super) fn wants_redraw(&self) -> bool {
+        self.wants_redraw_at(Instant::now())
+    }
+
+    fn wants_redraw_at(&self, now: Instant) -> bool {
+        self.is_visible() && self.has_work() && self.next_frame_due().is_none_or(|due| now >= due)
+    }
+
+    fn has_work(&self) -> bool {
+        self.pending != Changes::default() || self.is_animating()
+    }
+
+    /// The earliest time at which the frame rate cap allows the next frame.
+    fn next_frame_due(&self) -> Option<Instant> {
+        let frames_per_second = self
+            .frame_rate_cap
+            .0
+            .into_iter()
+            .chain(self.low_power_frame_rate())
+            .min()?;
+        Some(self.last_frame? + Duration::from_secs_f64(1.0 / frames_per_second as f64))
+    }
+
+    fn low_power_frame_rate(&self) -> Option<u32> {
+        match (self.low_power, self.unfocused) {
+            (false, _) => None,
+            (true, false) => Some(LOW_POWER_FRAMES_PER_SECOND),
+            (true, true) => Some(UNFOCUSED_LOW_POWER_FRAMES_PER_SECOND),
+        }
+    }
+
+    /// How the event loop should wait for the next event.
+    pub(super) fn control_flow(&self) -> ControlFlow {
+        if !self.is_visible() {
+            return ControlFlow::Wait;
+        }
+        match self.next_frame_due() {
+            Some(due) if self.has_work() && Instant::now() < due => ControlFlow::WaitUntil(due),
+            _ if self.is_animating() => ControlFlow::Poll,
+            _ => ControlFlow::Wait,
+        }
+    }
+
+    /// Returns the changes to be drawn in the frame which is about to be rendered.
+    pub(super) fn begin_frame(&mut self) -> Changes {
+        self.last_frame = Some(Instant::now());
+        std::mem::take(&mut self.pending)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Change, FrameRateCap, RenderScheduler};
+    use googletest::prelude::*;
+    use iced::time::Duration;
+    use iced_winit::winit::event_loop::ControlFlow;
+
+    #[test]
+    fn does_not_want_redraw_without_changes() -> Result<()> {
+        let scheduler = RenderScheduler::default();
+
+        verify_that!(scheduler.wants_redraw(), eq(false))
+    }
+
+    #[test]
+    fn wants_redraw_until_frame_begins() -> Result<()> {
+        let mut scheduler = RenderScheduler::default();
+        scheduler.invalidate(Change::View);
+        verify_that!(scheduler.wants_redraw(), eq(true))?;
+
+        let changes = scheduler.begin_frame();
+
+        verify_that!(changes.view, eq(true))?;
+        verify_that!(scheduler.wants_redraw(), eq(false))
+    }
+
+    #[test]
+    fn wants_redraw_while_animating() -> Result<()> {
+        let mut scheduler = RenderScheduler::default();
+        scheduler.start_animation("test");
+        scheduler.begin_frame();
+        verify_that!(scheduler.wants_redraw(), eq(true))?;
+
+        scheduler.stop_animation("test");
+
+        verify_that!(scheduler.wants_redraw(), eq(false))
+    }
+
+    #[test]
+    fn frame_rate_cap_delays_next_frame() -> Result<()> {
+        let mut scheduler = RenderScheduler::default();
+        scheduler.set_frame_rate_cap(FrameRateCap(Some(10)));
+        scheduler.begin_frame();
+        scheduler.invalidate(Change::View);
+        let last_frame = scheduler.last_frame.expect("Frame was not recorded");
+
+        verify_that!(
+            (
+                scheduler.wants_redraw_at(last_frame + Duration::from_millis(50)),
+                scheduler.wants_redraw_at(last_frame + Duration::from_millis(100))
+            ),
+            (eq(false), eq(true))
+        )
+    }
+
+    #[test]
+    fn low_power_throttles_unfocused_window() -> Result<()> {
+        let mut scheduler = RenderScheduler::default();
+        scheduler.set_frame_rate_cap(FrameRateCap(Some(60)));
+        scheduler.set_low_power(true);
+        scheduler.set_focused(false);
+        scheduler.begin_frame();
+        scheduler.invalidate(Change::View);
+        let last_frame = scheduler.last_frame.expect("Frame was not recorded");
+
+        verify_that!(
+            (
+                scheduler.wants_redraw_at(last_frame + Duration::from_millis(50)),
+                scheduler.wants_redraw_at(last_frame + Duration::from_millis(100))
+            ),
+            (eq(false), eq(true))
+        )
+    }
+
+    #[test]
+    fn unfocused_window_is_not_throttled_without_low_power() -> Result<()> {
+        let mut scheduler = RenderScheduler::default();
+        scheduler.set_focused(false);
+        scheduler.begin_frame();
+        scheduler.invalidate(Change::View);
+
+        verify_that!(scheduler.wants_redraw(), eq(true))
+    }
+
+    #[test]
+    fn does_not_redraw_occluded_window() -> Result<()> {
+        let mut scheduler = RenderScheduler::default();
+        scheduler.start_animation("test");
+        scheduler.set_occluded(true);
+
+        verify_that!(
+            (scheduler.wants_redraw(), scheduler.control_flow()),
+            (eq(false), eq(ControlFlow::Wait))
+        )
+    }
+
+    #[test]
+    fn recreates_surface_when_window_is_restored() -> Result<()> {
+        let mut scheduler = RenderScheduler::default();
+        scheduler.set_minimized(true);
+        scheduler.begin_frame();
+
+        scheduler.set_minimized(false);
+
+        verify_that!(
+            (scheduler.wants_redraw(), scheduler.begin_frame().window),
+            (eq(true), eq(true))
+        )
+    }
+}
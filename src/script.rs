@@ -0,0 +1,971 @@
+use crate::{
+    annotation::Annotation,
+    bookmark::Bookmark,
+    colour_palette::Palette,
+    compositor::{BlendMode, Compositor, Layer},
+    controls::FractalType,
+    cosine_palette::CosinePalette,
+    error::Error,
+    export,
+    export_manifest::{self, ExportManifest, PartialFrame},
+    fractal_definition::EXPONENT,
+    fractal_view::View,
+    gpu::Gpu,
+    image_palette,
+    iteration_budget::MAX_ITERATIONS,
+    jobs::CancellationToken,
+    julia_atlas::{JuliaAtlas, MAX_CELLS_PER_SIDE},
+    julia_preview,
+    modulation::Sweep,
+    plane::Plane,
+    print_preset::{Orientation, PaperSize, PrintPreset, DEFAULT_DPI, MAX_DPI},
+    random_palette,
+    render_farm::Coordinator,
+    svg_export::{self, SvgStroke},
+    throughput::{self, Throughput},
+    thumbnail::THUMBNAIL_SIZE,
+    transparency::Transparency,
+    vector_mode::VectorMode,
+};
+use cgmath::Vector2;
+use iced::time::Instant;
+use rhai::{Array, Dynamic, Engine, EvalAltResult};
+use std::{
+    cell::{Cell, RefCell},
+    collections::HashMap,
+    path::Path,
+    rc::Rc,
+    sync::Arc,
+};
+
+/// Runs a Rhai script against a headless renderer.
+///
+/// Scripts drive the view through the following functions:
+///
+/// * `pan(dx, dy)` moves the view by the given fraction of the viewport width and height.
+/// * `zoom(factor)` magnifies the centre of the view by `factor`; values below one zoom out.
+/// * `zoom_at(factor, x, y)` magnifies about a point given in viewport coordinates, where the
+///   viewport spans `[-0.5, 0.5]` on both axes.
+/// * `reset_view()` restores the framing with which the current fractal starts.
+/// * `set_max_iterations(n)` sets the number of iterations after which a point is considered to
+///   lie in the Mandelbrot set, at most 50,000.
+/// * `set_fractal(name)` selects a fractal type by its display name, e.g. `"Newton"`, and resets
+///   its parameters, framing, bailout, iteration budget and palette to its defaults.
+/// * `set_parameter(name, value)` sets a parameter of the current fractal by the name shown in the
+///   controls, e.g. `set_parameter("Iterations", 50.0)`.
+/// * `set_critical_point(index)` starts the orbits of a rational map from its critical point with
+///   the given index, counted outwards from zero as the controls list them.
+/// * `set_dynamic_plane(re, im)` shows the dynamic plane of escape-time fractals, in which each
+///   point is where its orbit starts and the constant is `re + im i`, like a Julia set, framed
+///   whole. `set_parameter_plane()` returns to the parameter plane.
+/// * `set_vector_mode(name, depth)` draws the curve of the given name to the given depth instead
+///   of the fractal, e.g. `set_vector_mode("Hilbert curve", 6)`, or the fractal again with
+///   `"Shaded fractal"`. The depth is limited to those which the controls offer.
+/// * `set_bailout(radius)` sets the radius beyond which an orbit is considered to escape, which must
+///   be at least 2.
+/// * `set_palette_offset(offset)` rotates the colours of escaping points by the given fraction of
+///   the palette.
+/// * `set_palette(name)` colours the fractal with the palette of the given name, e.g. `"Cividis"`.
+/// * `set_random_palette(seed)` colours the fractal with the palette which the palette randomizer
+///   generates from the seed, so that a loop over seeds renders a sample of random palettes.
+/// * `set_cosine_palette(offset, amplitude, frequency, phase)` colours the fractal with the cosine
+///   palette of the given coefficients, each an array of the red, green and blue channels, e.g.
+///   `set_cosine_palette([0.5, 0.5, 0.5], [0.5, 0.5, 0.5], [1.0, 1.0, 1.0], [0.0, 0.33, 0.67])`.
+/// * `set_colouring_layers(base, overlay, blend_mode, opacity)` colours escape-time fractals by two
+///   layers, named as in the controls, e.g.
+///   `set_colouring_layers("Smooth iteration", "Stripe average", "Overlay", 0.6)`.
+/// * `set_palette_from_image(path)` colours the fractal with the palette extracted from the PNG
+///   image at the path.
+/// * `set_transparency(part)` renders `"Interior"` or `"Exterior"` points of escape-time fractals
+///   with an alpha of zero, or none of them with `"Opaque"`.
+/// * `set_antialiasing(enabled)` turns adaptive anti-aliasing of edges on or off.
+/// * `set_low_power(enabled)` turns rendering at half the resolution on or off.
+/// * `set_seed(seed)` seeds the random positions of the samples of anti-aliasing, so that renders
+///   with the same seed are identical, from 0 to 4,294,967,295.
+/// * `save_bookmark(name)` remembers the current fractal, view and colouring under the name.
+/// * `morph(from, to, t)` shows the state the fraction `t` of the way between two bookmarks, so
+///   that a loop rendering each step exports the morph as an animation.
+/// * `sweep_exponent(t)` sets the exponent of the Multibrot set to where the exponent animation is
+///   after `t` sweeps there and back, so that it can be exported in the same way.
+/// * `annotate(text, x, y)` pins a note to the point `x + y i` of the plane.
+/// * `annotate_arrow(text, x, y, target_x, target_y)` pins a note with an arrow from it to the
+///   point `target_x + target_y i`.
+/// * `clear_annotations()` removes every note.
+/// * `render(path, width, height)` renders the current view to a PNG file, or a TIFF file if the
+///   path ends in `.tif` or `.tiff`, with the notes drawn over it. Renders too large for a texture
+///   of the GPU, or whose size and iteration count could keep it busy for long enough that the
+///   driver resets it, are rendered in tiles instead, as a render farm would.
+/// * `render_print(path, paper, orientation, dpi)` renders like `render` at the size of a page of
+///   paper at the given dots per inch, written into the image, e.g.
+///   `render_print("poster.tiff", "A3", "Landscape", 600)`, or at 300 if they are left out. The
+///   paper is one of A4, A3, A2, A1, Letter and Tabloid, and the orientation Portrait or
+///   Landscape.
+/// * `render_svg(path, width, height, stroke_width, coloured)` writes the curve of the vector mode
+///   as it is shown to an SVG file, with lines `stroke_width` pixels wide, either coloured from the
+///   palette or as a single black polyline for laser cutters and plotters. It is written at once,
+///   even with a render farm.
+/// * `set_frame_count(n)` announces how many frames the script will render, so that the progress
+///   reported after each frame estimates the time left.
+/// * `julia_preview(path, cx, cy, size)` writes a square point-cloud preview of the Julia set for
+///   the parameter `cx + cy i`, computed by inverse iteration on the CPU.
+/// * `julia_atlas(path, columns, rows, re_min, im_min, re_max, im_max, cell_size)` writes a poster
+///   of the Julia sets of the current escape-time fractal in a grid of `columns` by `rows` cells
+///   of `cell_size` pixels, or 256 if it is left out, whose constants are sampled over the given
+///   rectangle of the parameter plane and laid out as the plane is, each labelled with its
+///   constant. It is rendered by the script even with a render farm, and an interrupted atlas
+///   resumes from the cells already rendered. Cells too large for the GPU to render in one pass
+///   fail.
+///
+/// With a render farm, `render` and `render_print` queue the frame for the workers of the farm
+/// instead, and the script ends once every frame is written.
+///
+/// Once the cancellation token is cancelled, the script stops before its next statement or render,
+/// keeping the images already written. Cancelling the farm as well drops the frames still waiting
+/// for tiles.
+///
+/// Until the script completes, the images written are recorded in an [`ExportManifest`] beside it.
+/// Running a script which was interrupted again skips the renders of images which were written
+/// with the same settings, and a farm resumes images with the tiles which came back before.
+pub(super) fn run_file(
+    path: impl AsRef<Path>,
+    farm: Option<Coordinator>,
+    cancellation: CancellationToken,
+) -> Result<(), Box<EvalAltResult>> {
+    let path = path.as_ref();
+    let manifest = Arc::new(ExportManifest::for_script(path));
+    if manifest.written() > 0 {
+        eprintln!(
+            "Resuming {}, whose last run had written {} images",
+            path.display(),
+            manifest.written()
+        );
+    }
+    let session = Rc::new(
+        Session::new(farm, cancellation, manifest.clone()).map_err(|error| error.to_string())?,
+    );
+    let engine = create_engine(session.clone());
+    engine.run_file(path.to_path_buf())?;
+    if let Some(farm) = &session.farm {
+        farm.finish()?;
+    }
+    manifest
+        .remove()
+        .map_err(|error| format!("Could not delete the progress of the export: {error}"))?;
+    Ok(())
+}
+
+struct Session {
+    gpu: Gpu,
+    farm: Option<Coordinator>,
+    cancellation: CancellationToken,
+    /// The number of frames which the script announced with `set_frame_count`.
+    frame_count: Cell<Option<u64>>,
+    /// The number of frames which were written before the script was interrupted.
+    skipped: Cell<u64>,
+    throughput: RefCell<Throughput>,
+    manifest: Arc<ExportManifest>,
+    view: RefCell<View>,
+    bookmarks: RefCell<HashMap<String, Bookmark>>,
+    annotations: RefCell<Vec<Annotation>>,
+}
+
+impl Session {
+    fn new(
+        farm: Option<Coordinator>,
+        cancellation: CancellationToken,
+        manifest: Arc<ExportManifest>,
+    ) -> Result<Self, Error> {
+        let gpu = Gpu::new_without_surface()?;
+        let mut view = View::new(&gpu);
+        view.wait_for_pipelines();
+        if let Some(farm) = &farm {
+            farm.record_in(manifest.clone());
+        }
+        Ok(Self {
+            gpu,
+            farm,
+            cancellation,
+            frame_count: Cell::new(None),
+            skipped: Cell::new(0),
+            throughput: RefCell::new(Throughput::new(Instant::now())),
+            manifest,
+            view: RefCell::new(view),
+            bookmarks: RefCell::new(HashMap::new()),
+            annotations: RefCell::new(vec![]),
+        })
+    }
+
+    /// The number of frames which the script announced, less those which were written before it
+    /// was interrupted, so that the time left is estimated for the frames still to render.
+    fn frames_to_render(&self) -> Option<u64> {
+        Some(self.frame_count.get()?.saturating_sub(self.skipped.get()))
+    }
+
+    fn announce_frames_to_farm(&self) {
+        if let (Some(farm), Some(frames)) = (&self.farm, self.frames_to_render()) {
+            farm.expect_frames(frames);
+        }
+    }
+}
+
+fn create_engine(session: Rc<Session>) -> Engine {
+    let mut engine = Engine::new();
+
+    let s = session.clone();
+    engine.on_progress(move |_| s.cancellation.is_cancelled().then_some(Dynamic::UNIT));
+
+    let s = session.clone();
+    engine.register_fn("pan", move |dx: f64, dy: f64| {
+        s.view
+            .borrow_mut()
+            .translate(Vector2::new(dx as f32, dy as f32));
+    });
+
+    let s = session.clone();
+    engine.register_fn(
+        "zoom",
+        move |factor: f64| -> Result<(), Box<EvalAltResult>> { zoom(&s, factor, 0.0, 0.0) },
+    );
+
+    let s = session.clone();
+    engine.register_fn(
+        "zoom_at",
+        move |factor: f64, x: f64, y: f64| -> Result<(), Box<EvalAltResult>> {
+            zoom(&s, factor, x, y)
+        },
+    );
+
+    let s = session.clone();
+    engine.register_fn("reset_view", move || s.view.borrow_mut().reset_transform());
+
+    let s = session.clone();
+    engine.register_fn(
+        "set_max_iterations",
+        move |max_iterations: i64| -> Result<(), Box<EvalAltResult>> {
+            let max_iterations = u32::try_from(max_iterations)
+                .ok()
+                .filter(|max_iterations| (1..=MAX_ITERATIONS).contains(max_iterations))
+                .ok_or_else(|| {
+                    format!(
+                        "Iteration count must lie between 1 and {MAX_ITERATIONS}, got \
+                        {max_iterations}"
+                    )
+                })?;
+            s.view.borrow_mut().set_max_iterations(max_iterations);
+            Ok(())
+        },
+    );
+
+    let s = session.clone();
+    engine.register_fn(
+        "set_fractal",
+        move |name: &str| -> Result<(), Box<EvalAltResult>> {
+            let fractal_type = name.parse::<FractalType>().map_err(|_| {
+                format!(
+                    "Unknown fractal type \"{name}\", expected one of {:?}",
+                    FractalType::ALL.map(|t| t.to_string())
+                )
+            })?;
+            s.view.borrow_mut().set_fractal_type(fractal_type);
+            Ok(())
+        },
+    );
+
+    let s = session.clone();
+    engine.register_fn(
+        "set_vector_mode",
+        move |name: &str, depth: i64| -> Result<(), Box<EvalAltResult>> {
+            let mode = named(&VectorMode::ALL, name, "vector mode")?;
+            let depth = u32::try_from(depth)
+                .map_err(|_| format!("Curve depth must not be negative, got {depth}"))?;
+            s.view
+                .borrow_mut()
+                .set_vector_mode(&s.gpu, mode, mode.clamp_depth(depth));
+            Ok(())
+        },
+    );
+
+    engine.register_fn(
+        "julia_preview",
+        |path: &str, cx: f64, cy: f64, size: i64| -> Result<(), Box<EvalAltResult>> {
+            julia_preview::export_png(Vector2::new(cx, cy), path, image_dimension(size)?)
+                .map_err(|error| format!("Could not export to {path}: {error}").into())
+        },
+    );
+
+    let s = session.clone();
+    engine.register_fn(
+        "set_parameter",
+        move |name: &str, value: f64| -> Result<(), Box<EvalAltResult>> {
+            let mut view = s.view.borrow_mut();
+            let definition = view.definition();
+            let index = definition.parameter_index(name).ok_or_else(|| {
+                format!(
+                    "{} has no parameter \"{name}\", expected one of {:?}",
+                    definition.name,
+                    definition
+                        .parameters
+                        .iter()
+                        .map(|parameter| parameter.name)
+                        .collect::<Vec<_>>()
+                )
+            })?;
+            let parameter = &definition.parameters[index];
+            let value = value as f32;
+            if !(parameter.min..=parameter.max).contains(&value) {
+                return Err(format!(
+                    "{} must lie between {} and {}, got {value}",
+                    parameter.name, parameter.min, parameter.max
+                )
+                .into());
+            }
+            view.set_parameter(index, value);
+            Ok(())
+        },
+    );
+
+    let s = session.clone();
+    engine.register_fn(
+        "set_critical_point",
+        move |index: i64| -> Result<(), Box<EvalAltResult>> {
+            let index = usize::try_from(index)
+                .map_err(|_| format!("Critical point index must not be negative, got {index}"))?;
+            s.view.borrow_mut().set_critical_point(index);
+            Ok(())
+        },
+    );
+
+    let s = session.clone();
+    engine.register_fn("set_dynamic_plane", move |re: f64, im: f64| {
+        s.view
+            .borrow_mut()
+            .set_plane(Plane::Dynamic(Vector2::new(re as f32, im as f32)))
+    });
+
+    let s = session.clone();
+    engine.register_fn("set_parameter_plane", move || {
+        s.view.borrow_mut().set_plane(Plane::Parameter)
+    });
+
+    let s = session.clone();
+    engine.register_fn(
+        "set_bailout",
+        move |bailout: f64| -> Result<(), Box<EvalAltResult>> {
+            if bailout < 2.0 {
+                return Err(format!("Bailout radius must be at least 2, got {bailout}").into());
+            }
+            s.view.borrow_mut().set_bailout(bailout as f32);
+            Ok(())
+        },
+    );
+
+    let s = session.clone();
+    engine.register_fn("set_palette_offset", move |offset: f64| {
+        s.view.borrow_mut().set_palette_offset(offset as f32)
+    });
+
+    let s = session.clone();
+    engine.register_fn(
+        "set_palette",
+        move |name: &str| -> Result<(), Box<EvalAltResult>> {
+            let palette = name.parse::<Palette>().map_err(|_| {
+                format!(
+                    "Unknown palette \"{name}\", expected one of {:?}",
+                    Palette::ALL.map(|p| p.to_string())
+                )
+            })?;
+            s.view.borrow_mut().set_palette(palette);
+            Ok(())
+        },
+    );
+
+    let s = session.clone();
+    engine.register_fn("set_random_palette", move |seed: i64| {
+        s.view
+            .borrow_mut()
+            .set_palette(Palette::Custom(random_palette::generate(seed as u64)))
+    });
+
+    let s = session.clone();
+    engine.register_fn(
+        "set_cosine_palette",
+        move |offset: Array,
+              amplitude: Array,
+              frequency: Array,
+              phase: Array|
+              -> Result<(), Box<EvalAltResult>> {
+            let palette = CosinePalette {
+                offset: channels(offset)?,
+                amplitude: channels(amplitude)?,
+                frequency: channels(frequency)?,
+                phase: channels(phase)?,
+            };
+            s.view.borrow_mut().set_palette(Palette::Cosine(palette));
+            Ok(())
+        },
+    );
+
+    let s = session.clone();
+    engine.register_fn(
+        "set_colouring_layers",
+        move |base: &str,
+              overlay: &str,
+              blend_mode: &str,
+              opacity: f64|
+              -> Result<(), Box<EvalAltResult>> {
+            let compositor = Compositor {
+                base: named(&Layer::ALL, base, "layer")?,
+                overlay: named(&Layer::ALL, overlay, "layer")?,
+                blend_mode: named(&BlendMode::ALL, blend_mode, "blend mode")?,
+                ..Compositor::default()
+            };
+            s.view
+                .borrow_mut()
+                .set_compositor(compositor.with_opacity(opacity as f32));
+            Ok(())
+        },
+    );
+
+    let s = session.clone();
+    engine.register_fn(
+        "set_palette_from_image",
+        move |path: &str| -> Result<(), Box<EvalAltResult>> {
+            let palette = image_palette::load(Path::new(path))
+                .map_err(|error| format!("Could not load {path}: {error}"))?;
+            s.view.borrow_mut().set_palette(Palette::Custom(palette));
+            Ok(())
+        },
+    );
+
+    let s = session.clone();
+    engine.register_fn(
+        "set_transparency",
+        move |part: &str| -> Result<(), Box<EvalAltResult>> {
+            let transparency = named(&Transparency::ALL, part, "transparency")?;
+            s.view.borrow_mut().set_transparency(transparency);
+            Ok(())
+        },
+    );
+
+    let s = session.clone();
+    engine.register_fn("set_antialiasing", move |enabled: bool| {
+        s.view.borrow_mut().set_antialiasing(&s.gpu, enabled)
+    });
+
+    let s = session.clone();
+    engine.register_fn("set_low_power", move |enabled: bool| {
+        s.view.borrow_mut().set_low_power(&s.gpu, enabled)
+    });
+
+    let s = session.clone();
+    engine.register_fn(
+        "set_seed",
+        move |seed: i64| -> Result<(), Box<EvalAltResult>> {
+            let seed = u32::try_from(seed)
+                .map_err(|_| format!("Seed must lie between 0 and {}, got {seed}", u32::MAX))?;
+            s.view.borrow_mut().set_seed(seed);
+            Ok(())
+        },
+    );
+
+    let s = session.clone();
+    engine.register_fn("save_bookmark", move |name: &str| {
+        let bookmark = s.view.borrow().bookmark();
+        s.bookmarks.borrow_mut().insert(name.into(), bookmark);
+    });
+
+    let s = session.clone();
+    engine.register_fn(
+        "morph",
+        move |from: &str, to: &str, t: f64| -> Result<(), Box<EvalAltResult>> {
+            let bookmarks = s.bookmarks.borrow();
+            let bookmark = |name: &str| {
+                bookmarks
+                    .get(name)
+                    .ok_or_else(|| format!("No bookmark named \"{name}\""))
+            };
+            s.view
+                .borrow_mut()
+                .morph(bookmark(from)?, bookmark(to)?, t as f32);
+            Ok(())
+        },
+    );
+
+    let s = session.clone();
+    engine.register_fn(
+        "sweep_exponent",
+        move |t: f64| -> Result<(), Box<EvalAltResult>> {
+            let mut view = s.view.borrow_mut();
+            let definition = view.definition();
+            let index = definition
+                .parameter_index(EXPONENT)
+                .ok_or_else(|| format!("{} has no exponent", definition.name))?;
+            let parameter = &definition.parameters[index];
+            let exponent =
+                parameter.min + Sweep::position(t as f32) * (parameter.max - parameter.min);
+            view.set_parameter(index, exponent);
+            Ok(())
+        },
+    );
+
+    let s = session.clone();
+    engine.register_fn("annotate", move |text: &str, x: f64, y: f64| {
+        s.annotations.borrow_mut().push(Annotation {
+            text: text.into(),
+            position: Vector2::new(x, y),
+            target: None,
+        });
+    });
+
+    let s = session.clone();
+    engine.register_fn(
+        "annotate_arrow",
+        move |text: &str, x: f64, y: f64, target_x: f64, target_y: f64| {
+            s.annotations.borrow_mut().push(Annotation {
+                text: text.into(),
+                position: Vector2::new(x, y),
+                target: Some(Vector2::new(target_x, target_y)),
+            });
+        },
+    );
+
+    let s = session.clone();
+    engine.register_fn("clear_annotations", move || {
+        s.annotations.borrow_mut().clear()
+    });
+
+    let s = session.clone();
+    engine.register_fn(
+        "set_frame_count",
+        move |frames: i64| -> Result<(), Box<EvalAltResult>> {
+            let frames = u64::try_from(frames)
+                .map_err(|_| format!("Frame count must not be negative, got {frames}"))?;
+            s.frame_count.set(Some(frames));
+            s.announce_frames_to_farm();
+            Ok(())
+        },
+    );
+
+    let s = session.clone();
+    engine.register_fn(
+        "render_svg",
+        move |path: &str,
+              width: i64,
+              height: i64,
+              stroke_width: f64,
+              coloured: bool|
+              -> Result<(), Box<EvalAltResult>> {
+            let (width, height) = (image_dimension(width)?, image_dimension(height)?);
+            if stroke_width <= 0.0 {
+                return Err(format!("Stroke width must be positive, got {stroke_width}").into());
+            }
+            let stroke = SvgStroke {
+                width: stroke_width as f32,
+                coloured,
+            };
+            let svg = svg_export::svg(&s.view.borrow(), width, height, stroke)
+                .ok_or("No curve to export; select one with set_vector_mode first")?;
+            std::fs::write(path, svg)
+                .map_err(|error| format!("Could not export to {path}: {error}").into())
+        },
+    );
+
+    let s = session.clone();
+    engine.register_fn(
+        "render",
+        move |path: &str, width: i64, height: i64| -> Result<(), Box<EvalAltResult>> {
+            render(
+                &s,
+                path,
+                image_dimension(width)?,
+                image_dimension(height)?,
+                None,
+            )
+        },
+    );
+
+    let s = session.clone();
+    engine.register_fn(
+        "render_print",
+        move |path: &str, paper: &str, orientation: &str, dpi: i64| {
+            render_print(&s, path, paper, orientation, dpi)
+        },
+    );
+
+    let s = session.clone();
+    engine.register_fn(
+        "render_print",
+        move |path: &str, paper: &str, orientation: &str| {
+            render_print(&s, path, paper, orientation, DEFAULT_DPI.into())
+        },
+    );
+
+    let s = session.clone();
+    engine.register_fn(
+        "julia_atlas",
+        move |path: &str,
+              columns: i64,
+              rows: i64,
+              re_min: f64,
+              im_min: f64,
+              re_max: f64,
+              im_max: f64,
+              cell_size: i64| {
+            let cell_size = image_dimension(cell_size)?;
+            julia_atlas(
+                &s,
+                path,
+                (columns, rows),
+                (re_min, im_min, re_max, im_max),
+                cell_size,
+            )
+        },
+    );
+
+    let s = session;
+    engine.register_fn(
+        "julia_atlas",
+        move |path: &str,
+              columns: i64,
+              rows: i64,
+              re_min: f64,
+              im_min: f64,
+              re_max: f64,
+              im_max: f64| {
+            julia_atlas(
+                &s,
+                path,
+                (columns, rows),
+                (re_min, im_min, re_max, im_max),
+                THUMBNAIL_SIZE,
+            )
+        },
+    );
+
+    engine
+}
+
+/// Renders the current view to an image file as `render` does, with the resolution for print if
+/// one is given.
+fn render(
+    session: &Session,
+    path: &str,
+    width: u32,
+    height: u32,
+    dpi: Option<u32>,
+) -> Result<(), Box<EvalAltResult>> {
+    if session.cancellation.is_cancelled() {
+        return Err(format!("Cancelled before rendering {path}").into());
+    }
+    let annotations = session.annotations.borrow();
+    let key = export_manifest::frame_key(&session.view.borrow(), &annotations, width, height);
+    if session.manifest.is_written(path, key) {
+        eprintln!("Skipping {path}, which was written before the interruption");
+        session.skipped.set(session.skipped.get() + 1);
+        session.announce_frames_to_farm();
+        return Ok(());
+    }
+    if let Some(farm) = &session.farm {
+        farm.submit(
+            path,
+            &session.view.borrow(),
+            &annotations,
+            width,
+            height,
+            dpi,
+        );
+        return Ok(());
+    }
+    export::export_image(
+        &session.gpu,
+        &mut session.view.borrow_mut(),
+        &annotations,
+        path,
+        width,
+        height,
+        dpi,
+    )
+    .map_err(|error| format!("Could not export to {path}: {error}"))?;
+    session
+        .manifest
+        .record(path, key)
+        .map_err(|error| format!("Could not record {path}: {error}"))?;
+    let mut throughput = session.throughput.borrow_mut();
+    throughput.complete(Instant::now());
+    eprintln!(
+        "{}",
+        throughput::describe_progress(&throughput, session.frames_to_render(), ("frame", "frames"))
+    );
+    Ok(())
+}
+
+/// Renders the current view at the size of the page as `render_print` does.
+fn render_print(
+    session: &Session,
+    path: &str,
+    paper: &str,
+    orientation: &str,
+    dpi: i64,
+) -> Result<(), Box<EvalAltResult>> {
+    let dpi = u32::try_from(dpi)
+        .ok()
+        .filter(|dpi| (1..=MAX_DPI).contains(dpi))
+        .ok_or_else(|| format!("DPI must lie between 1 and {MAX_DPI}, got {dpi}"))?;
+    let preset = PrintPreset {
+        paper: named(&PaperSize::ALL, paper, "paper size")?,
+        orientation: named(&Orientation::ALL, orientation, "orientation")?,
+        dpi,
+    };
+    let (width, height) = preset.pixel_size();
+    render(session, path, width, height, Some(dpi))
+}
+
+/// Renders a Julia atlas of the current fractal as `julia_atlas` does, of the given number of
+/// columns and rows over the rectangle of constants given by its least and greatest real and
+/// imaginary parts.
+fn julia_atlas(
+    session: &Session,
+    path: &str,
+    (columns, rows): (i64, i64),
+    (re_min, im_min, re_max, im_max): (f64, f64, f64, f64),
+    cell_size: u32,
+) -> Result<(), Box<EvalAltResult>> {
+    let cells = |count: i64| {
+        u32::try_from(count)
+            .ok()
+            .filter(|count| (1..=MAX_CELLS_PER_SIDE).contains(count))
+            .ok_or_else(|| {
+                format!("An atlas has between 1 and {MAX_CELLS_PER_SIDE} cells a side, got {count}")
+            })
+    };
+    if re_min >= re_max || im_min >= im_max {
+        return Err(format!(
+            "The rectangle of constants must have its least parts first, got \
+            {re_min}{im_min:+}i to {re_max}{im_max:+}i"
+        )
+        .into());
+    }
+    let atlas = JuliaAtlas {
+        columns: cells(columns)?,
+        rows: cells(rows)?,
+        bottom_left: Vector2::new(re_min, im_min),
+        top_right: Vector2::new(re_max, im_max),
+        cell_size,
+    };
+    let (width, height) = atlas.size().ok_or_else(|| {
+        format!(
+            "An atlas of {columns}x{rows} cells of {cell_size} pixels is too large for an image"
+        )
+    })?;
+    if session.cancellation.is_cancelled() {
+        return Err(format!("Cancelled before rendering {path}").into());
+    }
+    let key = atlas.key(&session.view.borrow());
+    if session.manifest.is_written(path, key) {
+        eprintln!("Skipping {path}, which was written before the interruption");
+        return Ok(());
+    }
+    let max_iterations = session.view.borrow().max_iterations();
+    if !export::fits_in_one_pass(&session.gpu, cell_size, cell_size, max_iterations) {
+        return Err(format!(
+            "Cells of {cell_size}x{cell_size} at {max_iterations} iterations are too large for \
+            the GPU or could hang it; use smaller cells or lower the iteration count"
+        )
+        .into());
+    }
+    let partial = PartialFrame::open(path, key)
+        .inspect_err(|error| tracing::warn!("Could not keep cells of {path}: {error}"))
+        .ok();
+    let pixels = atlas
+        .render(
+            &session.gpu,
+            &mut session.view.borrow_mut(),
+            partial.as_ref(),
+            || session.cancellation.is_cancelled(),
+        )
+        .map_err(|error| format!("Could not render {path}: {error}"))?;
+    export::save_image(path, &pixels, width, height, None)
+        .map_err(|error| format!("Could not export to {path}: {error}"))?;
+    if let Some(partial) = partial {
+        if let Err(error) = partial.remove() {
+            tracing::warn!("Could not remove the cells kept for {path}: {error}");
+        }
+    }
+    session
+        .manifest
+        .record(path, key)
+        .map_err(|error| format!("Could not record {path}: {error}").into())
+}
+
+fn zoom(session: &Session, factor: f64, x: f64, y: f64) -> Result<(), Box<EvalAltResult>> {
+    // NaN is neither positive nor negative, so it is ruled out by asking for a positive factor.
+    if !(factor > 0.0 && factor.is_finite()) {
+        return Err(format!("Zoom factor must be positive and finite, got {factor}").into());
+    }
+    session
+        .view
+        .borrow_mut()
+        .zoom(1.0 / factor as f32, Vector2::new(x as f32, y as f32));
+    Ok(())
+}
+
+fn image_dimension(value: i64) -> Result<u32, Box<EvalAltResult>> {
+    u32::try_from(value)
+        .ok()
+        .filter(|value| *value > 0)
+        .ok_or_else(|| format!("Image dimensions must be positive, got {value}").into())
+}
+
+/// The red, green and blue channels of a coefficient of a cosine palette, given as an array of
+/// three numbers.
+fn channels(array: Array) -> Result<[f32; 3], Box<EvalAltResult>> {
+    let values: Vec<f32> = array
+        .iter()
+        .filter_map(|value| {
+            let value = value
+                .as_float()
+                .or_else(|_| value.as_int().map(|value| value as f64));
+            value.ok().map(|value| value as f32)
+        })
+        .collect();
+    <[f32; 3]>::try_from(values).map_err(|_| {
+        format!("Coefficients of cosine palettes must be arrays of three numbers, got {array:?}")
+            .into()
+    })
+}
+
+/// The option whose name as shown in the controls is the given one, ignoring case.
+fn named<T: Copy + std::fmt::Display>(
+    options: &[T],
+    name: &str,
+    kind: &str,
+) -> Result<T, Box<EvalAltResult>> {
+    options
+        .iter()
+        .copied()
+        .find(|option| option.to_string().eq_ignore_ascii_case(name))
+        .ok_or_else(|| {
+            let names: Vec<String> = options.iter().map(ToString::to_string).collect();
+            format!("Unknown {kind} \"{name}\", expected one of {names:?}").into()
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{channels, create_engine, image_dimension, named, zoom, Session};
+    use crate::{controls::FractalType, export_manifest::ExportManifest, jobs::CancellationToken};
+    use googletest::prelude::*;
+    use rhai::Array;
+    use std::{rc::Rc, sync::Arc};
+
+    fn session(name: &str) -> Rc<Session> {
+        let manifest = std::env::temp_dir().join(format!(
+            "fractal_explorer_script_{name}_{}.json",
+            std::process::id()
+        ));
+        Rc::new(
+            Session::new(
+                None,
+                CancellationToken::default(),
+                Arc::new(ExportManifest::open(manifest)),
+            )
+            .unwrap(),
+        )
+    }
+
+    #[test]
+    fn image_dimension_must_be_positive_and_fit_in_u32() -> Result<()> {
+        verify_that!(image_dimension(640).ok(), eq(Some(640)))?;
+        verify_that!(image_dimension(0), err(anything()))?;
+        verify_that!(image_dimension(-1), err(anything()))?;
+        verify_that!(image_dimension(i64::from(u32::MAX) + 1), err(anything()))
+    }
+
+    #[test]
+    fn named_option_ignores_case() -> Result<()> {
+        verify_that!(
+            named(&FractalType::ALL, "nEwToN", "fractal type").ok(),
+            eq(Some(FractalType::Newton))
+        )
+    }
+
+    #[test]
+    fn unknown_name_lists_the_options() -> Result<()> {
+        let error = named(&FractalType::ALL, "Lyapunov", "fractal type").unwrap_err();
+
+        verify_that!(
+            error.to_string(),
+            all!(
+                contains_substring("Unknown fractal type \"Lyapunov\""),
+                contains_substring("\"Mandelbrot\"")
+            )
+        )
+    }
+
+    #[test]
+    fn channels_accept_integers_and_floats() -> Result<()> {
+        let array: Array = vec![1_i64.into(), 0.5_f64.into(), 0_i64.into()];
+
+        verify_that!(channels(array).ok(), eq(Some([1.0, 0.5, 0.0])))
+    }
+
+    #[test]
+    fn channels_need_three_numbers() -> Result<()> {
+        let too_few: Array = vec![1_i64.into(), 0.5_f64.into()];
+        let not_numbers: Array = vec!["red".into(), 0.5_f64.into(), 0_i64.into()];
+
+        verify_that!(channels(too_few), err(anything()))?;
+        verify_that!(channels(not_numbers), err(anything()))
+    }
+
+    #[test]
+    fn zoom_factor_must_be_positive_and_finite() -> Result<()> {
+        let session = session("zoom");
+        let half_width = session.view.borrow().transform().half_width();
+
+        verify_that!(zoom(&session, 0.0, 0.0, 0.0), err(anything()))?;
+        verify_that!(zoom(&session, -2.0, 0.0, 0.0), err(anything()))?;
+        verify_that!(zoom(&session, f64::NAN, 0.0, 0.0), err(anything()))?;
+        verify_that!(zoom(&session, f64::INFINITY, 0.0, 0.0), err(anything()))?;
+        verify_that!(zoom(&session, 2.0, 0.0, 0.0), ok(anything()))?;
+        let zoomed_half_width = session.view.borrow().transform().half_width();
+        verify_that!(zoomed_half_width, approx_eq(half_width / 2.0))
+    }
+
+    #[test]
+    fn script_selects_fractal_and_iteration_count() -> Result<()> {
+        let session = session("settings");
+        let engine = create_engine(session.clone());
+
+        engine
+            .run(r#"set_fractal("Newton"); set_max_iterations(300);"#)
+            .unwrap();
+
+        let bookmark = session.view.borrow().bookmark();
+        verify_that!(bookmark.fractal_type, eq(FractalType::Newton))?;
+        verify_that!(bookmark.max_iterations, eq(300))
+    }
+
+    #[test]
+    fn script_arguments_out_of_range_fail_with_their_reason() -> Result<()> {
+        let engine = create_engine(session("arguments"));
+        let error = |script: &str| engine.run(script).unwrap_err().to_string();
+
+        verify_that!(
+            error("set_max_iterations(0)"),
+            contains_substring("Iteration count must lie between 1 and")
+        )?;
+        verify_that!(
+            error(r#"set_fractal("Lyapunov")"#),
+            contains_substring("Unknown fractal type")
+        )?;
+        verify_that!(
+            error(r#"set_parameter("Nonexistent", 1.0)"#),
+            contains_substring("has no parameter \"Nonexistent\"")
+        )?;
+        verify_that!(
+            error(r#"render("never_written.png", 0, 10)"#),
+            contains_substring("Image dimensions must be positive")
+        )
+    }
+}
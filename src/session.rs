@@ -0,0 +1,255 @@
+use crate::{
+    annotation::Annotation,
+    bookmark::Bookmark,
+    colour_palette::Palette,
+    controls::{LayoutSetting, UiScale},
+    storage::{self, Storage},
+    workspace::Workspace,
+};
+use iced::time::Instant;
+use serde::{Deserialize, Serialize};
+use std::{sync::mpsc, time::Duration};
+
+/// The key under which the session is stored.
+const KEY: &str = "session";
+
+/// The shortest time between two saves, so that dragging the view does not write on every frame.
+const SAVE_INTERVAL: Duration = Duration::from_millis(500);
+
+/// What the explorer restores when started again, or on the web when the page is reloaded.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub(super) struct Session {
+    /// The location, fractal and palette shown.
+    pub(super) view: Bookmark,
+    pub(super) morph_start: Option<Bookmark>,
+    pub(super) morph_end: Option<Bookmark>,
+    pub(super) ui_scale: UiScale,
+    /// Missing from sessions stored before the touch layout existed.
+    #[serde(default)]
+    pub(super) layout: LayoutSetting,
+    pub(super) high_contrast: bool,
+    /// Missing from sessions stored before workspaces existed.
+    #[serde(default)]
+    pub(super) workspaces: Vec<Workspace>,
+    /// Missing from sessions stored before notes could be placed on the view.
+    #[serde(default)]
+    pub(super) annotations: Vec<Annotation>,
+    /// The palettes kept from the randomizer. Missing from sessions stored before palettes could
+    /// be kept.
+    #[serde(default)]
+    pub(super) saved_palettes: Vec<Palette>,
+}
+
+/// Loads the session from the storage when the explorer starts and saves it whenever it changes.
+pub(super) struct SessionStore<S> {
+    storage: S,
+    /// Delivers the stored session once it has loaded, or `None` if there is none. Nothing is saved
+    /// until then, lest the session with which the explorer starts replace the stored one.
+    restoring: Option<mpsc::Receiver<Option<Session>>>,
+    saved: Option<Session>,
+    last_save: Option<Instant>,
+}
+
+impl<S: Storage + Clone + 'static> SessionStore<S> {
+    /// Starts loading the stored session.
+    pub(super) fn open(storage: S) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        let loading = storage.clone();
+        storage::execute(async move {
+            let session = match loading.load(KEY).await {
+                Ok(value) => value.and_then(|value| {
+                    serde_json::from_str(&value)
+                        .inspect_err(|error| tracing::warn!("Ignored stored session: {error}"))
+                        .ok()
+                }),
+                Err(error) => {
+                    tracing::warn!("Could not load session: {error}");
+                    None
+                }
+            };
+            let _ = sender.send(session);
+        });
+        Self {
+            storage,
+            restoring: Some(receiver),
+            saved: None,
+            last_save: None,
+        }
+    }
+
+    /// Returns the stored session once it has loaded, which happens once.
+    pub(super) fn take_restored(&mut self) -> Option<Session> {
+        let receiver = self.restoring.as_ref()?;
+        match receiver.try_recv() {
+            Ok(session) => {
+                self.restoring = None;
+                self.saved.clone_from(&session);
+                session
+            }
+            Err(mpsc::TryRecvError::Empty) => None,
+            Err(mpsc::TryRecvError::Disconnected) => {
+                self.restoring = None;
+                None
+            }
+        }
+    }
+
+    /// Saves the session if it changed since it was last saved, unless that was less than
+    /// [`SAVE_INTERVAL`] ago. Returns whether the session still waits to be loaded or saved, in
+    /// which case this must be called again later.
+    pub(super) fn update(&mut self, session: Session, now: Instant) -> bool {
+        if self.restoring.is_some() {
+            return true;
+        }
+        if self.saved.as_ref() == Some(&session) {
+            return false;
+        }
+        if self
+            .last_save
+            .is_some_and(|last_save| now < last_save + SAVE_INTERVAL)
+        {
+            return true;
+        }
+        self.save(session, now);
+        false
+    }
+
+    /// Saves the session right away if it changed, as when the explorer closes.
+    pub(super) fn flush(&mut self, session: Session) {
+        if self.restoring.is_none() && self.saved.as_ref() != Some(&session) {
+            self.save(session, Instant::now());
+        }
+    }
+
+    fn save(&mut self, session: Session, now: Instant) {
+        let value = match serde_json::to_string(&session) {
+            Ok(value) => value,
+            Err(error) => {
+                tracing::error!("Could not serialize session: {error}");
+                return;
+            }
+        };
+        let storage = self.storage.clone();
+        storage::execute(async move {
+            if let Err(error) = storage.save(KEY, &value).await {
+                tracing::warn!("Could not save session: {error}");
+            }
+        });
+        self.saved = Some(session);
+        self.last_save = Some(now);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Session, SessionStore, KEY, SAVE_INTERVAL};
+    use crate::{
+        annotation::Annotation,
+        bookmark::Bookmark,
+        colour_palette::Palette,
+        controls::{FractalType, LayoutSetting, UiScale},
+        random_palette,
+        storage::{FileStorage, Storage, StorageError},
+    };
+    use cgmath::Vector2;
+    use googletest::prelude::*;
+    use iced::time::Instant;
+    use std::{cell::RefCell, rc::Rc, time::Duration};
+
+    /// Keeps the values saved under the key of the session in order.
+    #[derive(Clone, Default)]
+    struct Recording(Rc<RefCell<Vec<String>>>);
+
+    impl Storage for Recording {
+        async fn load(&self, _: &str) -> std::result::Result<Option<String>, StorageError> {
+            Ok(self.0.borrow().last().cloned())
+        }
+
+        async fn save(&self, key: &str, value: &str) -> std::result::Result<(), StorageError> {
+            assert_eq!(key, KEY);
+            self.0.borrow_mut().push(value.into());
+            Ok(())
+        }
+    }
+
+    fn session(half_width: f32) -> Session {
+        let bookmark = Bookmark {
+            fractal_type: FractalType::Multibrot,
+            parameter_values: vec![3.0],
+            max_iterations: 500,
+            palette: Palette::Viridis,
+            palette_offset: 0.25,
+            ..Bookmark::for_test(Vector2::new(-0.75, 0.1), half_width)
+        };
+        Session {
+            view: bookmark.clone(),
+            morph_start: Some(bookmark),
+            morph_end: None,
+            ui_scale: UiScale(150),
+            layout: LayoutSetting::Touch,
+            high_contrast: true,
+            workspaces: vec![],
+            annotations: vec![Annotation {
+                text: "Seahorse valley".into(),
+                position: Vector2::new(-0.7, 0.2),
+                target: Some(Vector2::new(-0.75, 0.1)),
+            }],
+            saved_palettes: vec![Palette::Custom(random_palette::generate(1))],
+        }
+    }
+
+    #[test]
+    fn restores_session_saved_in_file() -> Result<()> {
+        let directory =
+            std::env::temp_dir().join(format!("fractal_explorer_{}", std::process::id()));
+        let mut store = SessionStore::open(FileStorage::new(directory.clone()));
+        let nothing_stored = store.take_restored();
+        store.update(session(0.01), Instant::now());
+
+        let restored = SessionStore::open(FileStorage::new(directory.clone())).take_restored();
+        std::fs::remove_dir_all(directory)?;
+
+        verify_that!(nothing_stored, none())?;
+        verify_that!(restored, some(eq(session(0.01))))
+    }
+
+    #[test]
+    fn saves_nothing_until_stored_session_is_restored() -> Result<()> {
+        let storage = Recording::default();
+        let mut store = SessionStore::open(storage.clone());
+
+        let pending = store.update(session(0.01), Instant::now());
+        let saves = storage.0.borrow().len();
+
+        verify_that!((pending, saves), (eq(true), eq(0)))
+    }
+
+    #[test]
+    fn saves_changes_at_most_once_per_interval() -> Result<()> {
+        let storage = Recording::default();
+        let mut store = SessionStore::open(storage.clone());
+        store.take_restored();
+        let start = Instant::now();
+
+        store.update(session(0.01), start);
+        let pending_within_interval = store.update(session(0.02), start + SAVE_INTERVAL / 2);
+        let saves_within_interval = storage.0.borrow().len();
+        let pending_after_interval = store.update(session(0.02), start + SAVE_INTERVAL);
+        let pending_unchanged = store.update(
+            session(0.02),
+            start + SAVE_INTERVAL + Duration::from_secs(1),
+        );
+        let saves = storage.0.borrow().len();
+
+        verify_that!(
+            (
+                pending_within_interval,
+                saves_within_interval,
+                pending_after_interval,
+                pending_unchanged,
+                saves
+            ),
+            (eq(true), eq(1), eq(false), eq(false), eq(2))
+        )
+    }
+}
@@ -0,0 +1,141 @@
+
+// Entry points for analysing the set, appended to frag.wgsl so that they share its uniform and
+// iteration functions.
+
+struct MaskSize {
+    width: u32,
+    height: u32,
+}
+
+@group(1) @binding(0) var<uniform> mask_size: MaskSize;
+@group(1) @binding(1) var<storage, read_write> mask: array<u32>;
+
+// The escape time of the centre of the given pixel, counted from the top-left corner.
+fn sample_escape_time(pixel: vec2<u32>) -> f32 {
+    let size = vec2(f32(mask_size.width), f32(mask_size.height));
+    let uv = (vec2(f32(pixel.x), f32(pixel.y)) + 0.5) / size;
+    let position = u.transform * vec3(uv.x * 2.0 - 1.0, 1.0 - uv.y * 2.0, 1.0);
+    return mandelbrot_escape_time(position.xy);
+}
+
+fn sample_is_interior(pixel: vec2<u32>) -> bool {
+    return sample_escape_time(pixel) > 1.0;
+}
+
+@compute @workgroup_size(8, 8)
+fn escape_mask(@builtin(global_invocation_id) id: vec3<u32>) {
+    if (id.x >= mask_size.width || id.y >= mask_size.height) {
+        return;
+    }
+    mask[id.y * mask_size.width + id.x] = select(0u, 1u, sample_is_interior(id.xy));
+}
+
+@group(1) @binding(2) var<storage, read_write> interior_count: atomic<u32>;
+
+var<workgroup> workgroup_interior_count: atomic<u32>;
+
+@compute @workgroup_size(8, 8)
+fn count_interior(
+    @builtin(global_invocation_id) id: vec3<u32>,
+    @builtin(local_invocation_index) local_index: u32,
+) {
+    if (id.x < mask_size.width && id.y < mask_size.height && sample_is_interior(id.xy)) {
+        atomicAdd(&workgroup_interior_count, 1u);
+    }
+    workgroupBarrier();
+    if (local_index == 0u) {
+        atomicAdd(&interior_count, atomicLoad(&workgroup_interior_count));
+    }
+}
+
+// Must match HISTOGRAM_BINS in analysis.rs. The last entry of the histogram counts the samples
+// which did not escape.
+const HISTOGRAM_BINS: u32 = 50u;
+
+@group(1) @binding(3) var<storage, read_write> histogram: array<atomic<u32>, 51>;
+
+var<workgroup> workgroup_histogram: array<atomic<u32>, 51>;
+
+@compute @workgroup_size(8, 8)
+fn iteration_histogram(
+    @builtin(global_invocation_id) id: vec3<u32>,
+    @builtin(local_invocation_index) local_index: u32,
+) {
+    if (id.x < mask_size.width && id.y < mask_size.height) {
+        let escape_time = sample_escape_time(id.xy);
+        var bin = HISTOGRAM_BINS;
+        if (escape_time <= 1.0) {
+            bin = min(u32(escape_time * f32(HISTOGRAM_BINS)), HISTOGRAM_BINS - 1u);
+        }
+        atomicAdd(&workgroup_histogram[bin], 1u);
+    }
+    workgroupBarrier();
+    for (var bin = local_index; bin <= HISTOGRAM_BINS; bin += 64u) {
+        atomicAdd(&histogram[bin], atomicLoad(&workgroup_histogram[bin]));
+    }
+}
+
+// The statistics of a region, in the order of the fields of RegionAccumulator in analysis.rs.
+struct RegionAccumulator {
+    // The bitwise complement of the fewest iterations after which a sample escaped, so that the
+    // zeroed buffer starts out as if none did.
+    fewest_iterations_complement: atomic<u32>,
+    most_iterations: atomic<u32>,
+    iteration_sum: atomic<u32>,
+    escaped: atomic<u32>,
+    // The pairs of horizontally and of vertically adjacent samples of which only one is interior.
+    horizontal_edges: atomic<u32>,
+    vertical_edges: atomic<u32>,
+}
+
+@group(1) @binding(4) var<storage, read_write> region_accumulator: RegionAccumulator;
+
+var<workgroup> workgroup_region_accumulator: RegionAccumulator;
+
+@compute @workgroup_size(8, 8)
+fn region_statistics(
+    @builtin(global_invocation_id) id: vec3<u32>,
+    @builtin(local_invocation_index) local_index: u32,
+) {
+    if (id.x < mask_size.width && id.y < mask_size.height) {
+        let escape_time = sample_escape_time(id.xy);
+        let interior = escape_time > 1.0;
+        if (!interior) {
+            let iterations = u32(round(escape_time * f32(u.max_iterations)));
+            atomicMax(&workgroup_region_accumulator.fewest_iterations_complement, ~iterations);
+            atomicMax(&workgroup_region_accumulator.most_iterations, iterations);
+            atomicAdd(&workgroup_region_accumulator.iteration_sum, iterations);
+            atomicAdd(&workgroup_region_accumulator.escaped, 1u);
+        }
+        if (id.x + 1u < mask_size.width && sample_is_interior(id.xy + vec2(1u, 0u)) != interior) {
+            atomicAdd(&workgroup_region_accumulator.horizontal_edges, 1u);
+        }
+        if (id.y + 1u < mask_size.height && sample_is_interior(id.xy + vec2(0u, 1u)) != interior) {
+            atomicAdd(&workgroup_region_accumulator.vertical_edges, 1u);
+        }
+    }
+    workgroupBarrier();
+    if (local_index == 0u) {
+        atomicMax(
+            &region_accumulator.fewest_iterations_complement,
+            atomicLoad(&workgroup_region_accumulator.fewest_iterations_complement),
+        );
+        atomicMax(
+            &region_accumulator.most_iterations,
+            atomicLoad(&workgroup_region_accumulator.most_iterations),
+        );
+        atomicAdd(
+            &region_accumulator.iteration_sum,
+            atomicLoad(&workgroup_region_accumulator.iteration_sum),
+        );
+        atomicAdd(&region_accumulator.escaped, atomicLoad(&workgroup_region_accumulator.escaped));
+        atomicAdd(
+            &region_accumulator.horizontal_edges,
+            atomicLoad(&workgroup_region_accumulator.horizontal_edges),
+        );
+        atomicAdd(
+            &region_accumulator.vertical_edges,
+            atomicLoad(&workgroup_region_accumulator.vertical_edges),
+        );
+    }
+}
@@ -0,0 +1,215 @@
+
+// Entry points for adaptive anti-aliasing, appended to frag.wgsl so that they can evaluate each
+// fractal at subpixel positions.
+
+@group(1) @binding(0) var initial: texture_2d<f32>;
+@group(1) @binding(1) var resolved: texture_storage_2d<rgba8unorm, write>;
+
+// Pixels whose 3x3 neighbourhood has a larger variance of luminance than this are resampled.
+const VARIANCE_THRESHOLD: f32 = 0.002;
+
+// Resampled pixels are evaluated on a SUBPIXEL_GRID x SUBPIXEL_GRID grid.
+const SUBPIXEL_GRID: u32 = 4u;
+const SUBPIXEL_SAMPLES: u32 = 16u;
+
+fn luminance(colour: vec4<f32>) -> f32 {
+    return dot(colour.rgb, vec3(0.2126, 0.7152, 0.0722));
+}
+
+fn needs_resampling(pixel: vec2<u32>) -> bool {
+    let last = vec2<i32>(textureDimensions(initial)) - 1;
+    var sum = 0.0;
+    var sum_sq = 0.0;
+    for (var dy = -1; dy <= 1; dy++) {
+        for (var dx = -1; dx <= 1; dx++) {
+            let neighbour = clamp(vec2<i32>(pixel) + vec2(dx, dy), vec2(0), last);
+            let l = luminance(textureLoad(initial, neighbour, 0));
+            sum += l;
+            sum_sq += l * l;
+        }
+    }
+    let mean = sum / 9.0;
+    return sum_sq / 9.0 - mean * mean > VARIANCE_THRESHOLD;
+}
+
+// Scrambles the bits of the value, so that neighbouring values hash to unrelated ones. The same
+// hash as noise in modulation.rs.
+fn hash(value: u32) -> u32 {
+    var hash = value;
+    hash ^= hash >> 16u;
+    hash *= 0x7feb352du;
+    hash ^= hash >> 15u;
+    hash *= 0x846ca68bu;
+    hash ^= hash >> 16u;
+    return hash;
+}
+
+// The position in the viewport of the given sample within the pixel, counted from the top-left
+// corner.
+//
+// Each sample lies at a random position within its cell of the grid, which breaks up the moiré of
+// fine filaments which a regular grid leaves. The positions depend only on u.seed, the pixel and
+// the sample, so that renders with the same seed are identical.
+fn subpixel_position(pixel: vec2<u32>, index: u32) -> vec2<f32> {
+    let random = hash(u.seed ^ hash(pixel.x ^ hash(pixel.y ^ hash(index))));
+    let jitter = vec2(f32(random & 0xffffu), f32(random >> 16u)) / 65536.0;
+    let offset = (vec2(f32(index % SUBPIXEL_GRID), f32(index / SUBPIXEL_GRID)) + jitter)
+        / f32(SUBPIXEL_GRID);
+    let uv = (vec2<f32>(pixel) + offset) / vec2<f32>(textureDimensions(initial));
+    return vec2(uv.x * 2.0 - 1.0, 1.0 - uv.y * 2.0);
+}
+
+// Stores a resolved colour. Storage needs a format without sRGB encoding, so colours in linear
+// light are encoded to keep the precision of dark ones, and decoded by the blit onto the target.
+fn store_resolved(pixel: vec2<u32>, colour: vec4<f32>) {
+    var stored = colour;
+    if (u.linear_output != 0u) {
+        stored = vec4(linear_to_srgb(colour.rgb), colour.a);
+    }
+    textureStore(resolved, pixel, stored);
+}
+
+// Samples are averaged with their colours weighted by their alpha, so that where an export makes
+// the interior or exterior transparent, its colour does not bleed into the edges of the rest.
+fn premultiplied(colour: vec4<f32>) -> vec4<f32> {
+    return vec4(colour.rgb * colour.a, colour.a);
+}
+
+fn unpremultiplied(colour: vec4<f32>) -> vec4<f32> {
+    if (colour.a == 0.0) {
+        return colour;
+    }
+    return vec4(colour.rgb / colour.a, colour.a);
+}
+
+fn is_outside(pixel: vec2<u32>) -> bool {
+    return any(pixel >= textureDimensions(initial));
+}
+
+@compute @workgroup_size(8, 8)
+fn mandelbrot_antialiased(@builtin(global_invocation_id) id: vec3<u32>) {
+    if (is_outside(id.xy)) {
+        return;
+    }
+    var colour = textureLoad(initial, id.xy, 0);
+    if (needs_resampling(id.xy)) {
+        colour = vec4(0.0);
+        for (var i = 0u; i < SUBPIXEL_SAMPLES; i++) {
+            colour += premultiplied(mandelbrot_colour(subpixel_position(id.xy, i)));
+        }
+        colour = unpremultiplied(colour / f32(SUBPIXEL_SAMPLES));
+    }
+    store_resolved(id.xy, colour);
+}
+
+@compute @workgroup_size(8, 8)
+fn newton_antialiased(@builtin(global_invocation_id) id: vec3<u32>) {
+    if (is_outside(id.xy)) {
+        return;
+    }
+    var colour = textureLoad(initial, id.xy, 0);
+    if (needs_resampling(id.xy)) {
+        colour = vec4(0.0);
+        for (var i = 0u; i < SUBPIXEL_SAMPLES; i++) {
+            colour += premultiplied(newton_colour(subpixel_position(id.xy, i)));
+        }
+        colour = unpremultiplied(colour / f32(SUBPIXEL_SAMPLES));
+    }
+    store_resolved(id.xy, colour);
+}
+
+@compute @workgroup_size(8, 8)
+fn multibrot_antialiased(@builtin(global_invocation_id) id: vec3<u32>) {
+    if (is_outside(id.xy)) {
+        return;
+    }
+    var colour = textureLoad(initial, id.xy, 0);
+    if (needs_resampling(id.xy)) {
+        colour = vec4(0.0);
+        for (var i = 0u; i < SUBPIXEL_SAMPLES; i++) {
+            colour += premultiplied(multibrot_colour(subpixel_position(id.xy, i)));
+        }
+        colour = unpremultiplied(colour / f32(SUBPIXEL_SAMPLES));
+    }
+    store_resolved(id.xy, colour);
+}
+
+@compute @workgroup_size(8, 8)
+fn damped_newton_antialiased(@builtin(global_invocation_id) id: vec3<u32>) {
+    if (is_outside(id.xy)) {
+        return;
+    }
+    var colour = textureLoad(initial, id.xy, 0);
+    if (needs_resampling(id.xy)) {
+        colour = vec4(0.0);
+        for (var i = 0u; i < SUBPIXEL_SAMPLES; i++) {
+            colour += premultiplied(damped_newton_colour(subpixel_position(id.xy, i)));
+        }
+        colour = unpremultiplied(colour / f32(SUBPIXEL_SAMPLES));
+    }
+    store_resolved(id.xy, colour);
+}
+
+@compute @workgroup_size(8, 8)
+fn secant_antialiased(@builtin(global_invocation_id) id: vec3<u32>) {
+    if (is_outside(id.xy)) {
+        return;
+    }
+    var colour = textureLoad(initial, id.xy, 0);
+    if (needs_resampling(id.xy)) {
+        colour = vec4(0.0);
+        for (var i = 0u; i < SUBPIXEL_SAMPLES; i++) {
+            colour += premultiplied(secant_colour(subpixel_position(id.xy, i)));
+        }
+        colour = unpremultiplied(colour / f32(SUBPIXEL_SAMPLES));
+    }
+    store_resolved(id.xy, colour);
+}
+
+@compute @workgroup_size(8, 8)
+fn rational_antialiased(@builtin(global_invocation_id) id: vec3<u32>) {
+    if (is_outside(id.xy)) {
+        return;
+    }
+    var colour = textureLoad(initial, id.xy, 0);
+    if (needs_resampling(id.xy)) {
+        colour = vec4(0.0);
+        for (var i = 0u; i < SUBPIXEL_SAMPLES; i++) {
+            colour += premultiplied(rational_colour(subpixel_position(id.xy, i)));
+        }
+        colour = unpremultiplied(colour / f32(SUBPIXEL_SAMPLES));
+    }
+    store_resolved(id.xy, colour);
+}
+
+@compute @workgroup_size(8, 8)
+fn plasma_antialiased(@builtin(global_invocation_id) id: vec3<u32>) {
+    if (is_outside(id.xy)) {
+        return;
+    }
+    var colour = textureLoad(initial, id.xy, 0);
+    if (needs_resampling(id.xy)) {
+        colour = vec4(0.0);
+        for (var i = 0u; i < SUBPIXEL_SAMPLES; i++) {
+            colour += premultiplied(plasma_colour(subpixel_position(id.xy, i)));
+        }
+        colour = unpremultiplied(colour / f32(SUBPIXEL_SAMPLES));
+    }
+    store_resolved(id.xy, colour);
+}
+
+@compute @workgroup_size(8, 8)
+fn terrain_antialiased(@builtin(global_invocation_id) id: vec3<u32>) {
+    if (is_outside(id.xy)) {
+        return;
+    }
+    var colour = textureLoad(initial, id.xy, 0);
+    if (needs_resampling(id.xy)) {
+        colour = vec4(0.0);
+        for (var i = 0u; i < SUBPIXEL_SAMPLES; i++) {
+            colour += premultiplied(terrain_colour(subpixel_position(id.xy, i)));
+        }
+        colour = unpremultiplied(colour / f32(SUBPIXEL_SAMPLES));
+    }
+    store_resolved(id.xy, colour);
+}
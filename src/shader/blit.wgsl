@@ -0,0 +1,34 @@
+// Copies an image onto the render target, whose format and size may differ from the image's.
+
+@group(0) @binding(0) var image: texture_2d<f32>;
+@group(0) @binding(1) var image_sampler: sampler;
+
+struct BlitOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+}
+
+// A triangle which covers the whole viewport.
+@vertex
+fn fullscreen(@builtin(vertex_index) index: u32) -> BlitOutput {
+    let uv = vec2(f32((index << 1u) & 2u), f32(index & 2u));
+    var output: BlitOutput;
+    output.clip_position = vec4(uv.x * 2.0 - 1.0, 1.0 - uv.y * 2.0, 0.0, 1.0);
+    output.uv = uv;
+    return output;
+}
+
+@fragment
+fn blit(in: BlitOutput) -> @location(0) vec4<f32> {
+    return textureSample(image, image_sampler, in.uv);
+}
+
+// Like blit, for an image encoded in sRGB in a format which does not say so, onto a target which
+// stores linear light.
+@fragment
+fn blit_from_srgb(in: BlitOutput) -> @location(0) vec4<f32> {
+    let colour = textureSample(image, image_sampler, in.uv);
+    let low = colour.rgb / 12.92;
+    let high = pow((colour.rgb + 0.055) / 1.055, vec3(2.4));
+    return vec4(select(high, low, colour.rgb <= vec3(0.04045)), colour.a);
+}
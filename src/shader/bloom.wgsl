@@ -0,0 +1,69 @@
+// Makes the bright parts of an image glow. The bright pass keeps what exceeds a threshold at a
+// reduced resolution, two passes blur it with a separable Gaussian and the composite adds the
+// result to the image.
+
+@group(0) @binding(0) var image: texture_2d<f32>;
+@group(0) @binding(1) var image_sampler: sampler;
+// Only read by the composite, which adds it to `image`.
+@group(1) @binding(0) var glow: texture_2d<f32>;
+@group(1) @binding(1) var glow_sampler: sampler;
+
+// The brightness from which colours start to glow, reaching full glow at 1.
+const THRESHOLD = 0.6;
+const INTENSITY = 0.8;
+// The distance between the taps of the blur in texels of the reduced resolution.
+const TAP_SPACING = 1.5;
+const WEIGHTS = array<f32, 5>(0.227027, 0.1945946, 0.1216216, 0.054054, 0.016216);
+
+struct BloomOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+}
+
+// A triangle which covers the whole viewport.
+@vertex
+fn fullscreen(@builtin(vertex_index) index: u32) -> BloomOutput {
+    let uv = vec2(f32((index << 1u) & 2u), f32(index & 2u));
+    var output: BloomOutput;
+    output.clip_position = vec4(uv.x * 2.0 - 1.0, 1.0 - uv.y * 2.0, 0.0, 1.0);
+    output.uv = uv;
+    return output;
+}
+
+@fragment
+fn bright_pass(in: BloomOutput) -> @location(0) vec4<f32> {
+    let colour = textureSample(image, image_sampler, in.uv).rgb;
+    let brightness = max(colour.r, max(colour.g, colour.b));
+    return vec4(colour * smoothstep(THRESHOLD, 1.0, brightness), 1.0);
+}
+
+fn blur(uv: vec2<f32>, direction: vec2<f32>) -> vec4<f32> {
+    let step = direction * TAP_SPACING / vec2<f32>(textureDimensions(image));
+    // Constant arrays can only be indexed by constants.
+    var weights = WEIGHTS;
+    var colour = textureSample(image, image_sampler, uv).rgb * weights[0];
+    for (var i = 1; i < 5; i++) {
+        let offset = step * f32(i);
+        colour += textureSample(image, image_sampler, uv + offset).rgb * weights[i];
+        colour += textureSample(image, image_sampler, uv - offset).rgb * weights[i];
+    }
+    return vec4(colour, 1.0);
+}
+
+@fragment
+fn blur_horizontal(in: BloomOutput) -> @location(0) vec4<f32> {
+    return blur(in.uv, vec2(1.0, 0.0));
+}
+
+@fragment
+fn blur_vertical(in: BloomOutput) -> @location(0) vec4<f32> {
+    return blur(in.uv, vec2(0.0, 1.0));
+}
+
+@fragment
+fn composite(in: BloomOutput) -> @location(0) vec4<f32> {
+    let colour = textureSample(image, image_sampler, in.uv);
+    let added = textureSample(glow, glow_sampler, in.uv).rgb;
+    // Keeps the alpha of the scene, which is zero where an export makes it transparent.
+    return vec4(colour.rgb + INTENSITY * added, colour.a);
+}
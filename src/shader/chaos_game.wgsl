@@ -0,0 +1,16 @@
+// The settings of the chaos game, appended to frag.wgsl before chaos_game_walk.wgsl or
+// chaos_game_colouring.wgsl, which bind the hits with the access each needs.
+
+// Matches ChaosGameBlock in chaos_game.rs.
+struct ChaosGame {
+    // The size of the target in pixels, each of which counts the hits of the walkers on it.
+    size: vec2<u32>,
+    // The number of vertices of the regular polygon towards which the walkers jump.
+    vertices: u32,
+    // The fraction of the way to a random vertex which each jump covers.
+    jump_ratio: f32,
+    // The number of jumps which each walker counts.
+    steps: u32,
+}
+
+@group(1) @binding(0) var<uniform> game: ChaosGame;
@@ -0,0 +1,25 @@
+// Colours the hits of the chaos game, appended to frag.wgsl and chaos_game.wgsl.
+
+@group(1) @binding(1) var<storage, read> hits: array<u32>;
+@group(1) @binding(2) var<storage, read> most_hits: u32;
+
+// A triangle which covers the whole viewport.
+@vertex
+fn chaos_game_fullscreen(@builtin(vertex_index) index: u32) -> @builtin(position) vec4<f32> {
+    let uv = vec2(f32((index << 1u) & 2u), f32(index & 2u));
+    return vec4(uv.x * 2.0 - 1.0, 1.0 - uv.y * 2.0, 0.0, 1.0);
+}
+
+// Colours each pixel by the logarithm of its hits relative to the most on any pixel, so that the
+// sparse outskirts of the attractor show as well as its dense core. Pixels which were never hit
+// take the first colour of the palette.
+@fragment
+fn chaos_game_colouring(@builtin(position) position: vec4<f32>) -> @location(0) vec4<f32> {
+    let pixel = vec2<u32>(position.xy);
+    let count = hits[pixel.y * game.size.x + pixel.x];
+    if (count == 0u) {
+        return post_process(gradient_colour(0.0));
+    }
+    let density = log(1.0 + f32(count)) / log(1.0 + f32(most_hits));
+    return post_process(gradient_colour(fract(density + u.palette_offset)));
+}
@@ -0,0 +1,54 @@
+// Plays the chaos game, appended to frag.wgsl and chaos_game.wgsl.
+
+@group(1) @binding(1) var<storage, read_write> hits: array<atomic<u32>>;
+@group(1) @binding(2) var<storage, read_write> most_hits: atomic<u32>;
+
+// The jumps which each walker takes before it counts its hits, by which it has come closer to the
+// attractor than a pixel from wherever it started.
+const TRANSIENT_STEPS = 32u;
+
+const TAU = 6.283185307;
+
+// Advances the state of a permuted congruential generator and returns its next random number.
+fn next_random(state: ptr<function, u32>) -> u32 {
+    *state = *state * 747796405u + 2891336453u;
+    let word = ((*state >> ((*state >> 28u) + 4u)) ^ *state) * 277803737u;
+    return (word >> 22u) ^ word;
+}
+
+// The vertex of the regular polygon inscribed in the unit circle with the given index, counted
+// anticlockwise from the one at the top.
+fn polygon_vertex(index: u32) -> vec2<f32> {
+    let angle = 0.25 * TAU + TAU * f32(index) / f32(game.vertices);
+    return vec2(cos(angle), sin(angle));
+}
+
+// Walks from the centre of the polygon by jumping part of the way to one of its vertices at
+// random, again and again, and counts a hit on each pixel on which the walker lands. Each walker
+// draws its own random numbers, seeded by its index and the seed of the view.
+@compute @workgroup_size(64)
+fn chaos_game_walk(@builtin(global_invocation_id) id: vec3<u32>) {
+    var state = (id.x * 0x9e3779b9u) ^ (u.seed * 0x85ebca6bu);
+    next_random(&state);
+    var point = vec2(0.0);
+    for (var jump = 0u; jump < TRANSIENT_STEPS + game.steps; jump++) {
+        let vertex = polygon_vertex(next_random(&state) % game.vertices);
+        point = mix(point, vertex, game.jump_ratio);
+        if (jump < TRANSIENT_STEPS) {
+            continue;
+        }
+        let viewport = viewport_position_of(point);
+        let pixel = vec2<i32>(floor((vec2(viewport.x, -viewport.y) + 1.0) * 0.5 * vec2<f32>(game.size)));
+        if (all(pixel >= vec2(0)) && all(pixel < vec2<i32>(game.size))) {
+            atomicAdd(&hits[u32(pixel.y) * game.size.x + u32(pixel.x)], 1u);
+        }
+    }
+}
+
+// Finds the most hits on any pixel, by which the colouring scales the density of the hits.
+@compute @workgroup_size(8, 8)
+fn chaos_game_most_hits(@builtin(global_invocation_id) id: vec3<u32>) {
+    if (all(id.xy < game.size)) {
+        atomicMax(&most_hits, atomicLoad(&hits[id.y * game.size.x + id.x]));
+    }
+}
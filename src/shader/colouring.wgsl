@@ -0,0 +1,124 @@
+// Entry points which colour each pixel by the values stored by iteration_data.wgsl, appended to
+// frag.wgsl so that they share its uniform and palette functions. Changes of the colouring only
+// need this pass to run again.
+
+@group(1) @binding(0) var iteration_data: texture_2d<f32>;
+
+// A triangle which covers the whole viewport.
+@vertex
+fn fullscreen(@builtin(vertex_index) index: u32) -> @builtin(position) vec4<f32> {
+    let uv = vec2(f32((index << 1u) & 2u), f32(index & 2u));
+    return vec4(uv.x * 2.0 - 1.0, 1.0 - uv.y * 2.0, 0.0, 1.0);
+}
+
+fn iteration_data_at(position: vec4<f32>) -> vec4<f32> {
+    return textureLoad(iteration_data, vec2<i32>(position.xy), 0);
+}
+
+// Shows the raw quantity of the debug view for the stored orbit instead of the palette, as
+// colours encoded in sRGB. The interior of the set, where none of them is defined, is dark red.
+fn debug_colour(orbit: vec4<f32>) -> vec4<f32> {
+    if (orbit.x < 0.0) {
+        return output_colour(vec3(0.3, 0.0, 0.0));
+    }
+    if (u.debug_view == DEBUG_ESCAPE_RADIUS) {
+        // Orbits escape with a modulus between the bailout and about its square.
+        let t = log(orbit.w) / log(u.bailout) - 1.0;
+        return output_colour(vec3(clamp(t, 0.0, 1.0)));
+    }
+    if (u.debug_view == DEBUG_DISTANCE_ESTIMATE) {
+        let pixel_size = 2.0 * length(u.transform[0].xy) / f32(textureDimensions(iteration_data).x);
+        // Black within a pixel of the boundary, white ten doublings of that away from it.
+        let t = log2(orbit.w / pixel_size) / 10.0;
+        return output_colour(vec3(clamp(t, 0.0, 1.0)));
+    }
+    // Every whole iteration starts a band of the other tint, which brightens with the fraction.
+    var tint = vec3(0.55, 0.7, 1.0);
+    if (u32(orbit.x) % 2u == 0u) {
+        tint = vec3(1.0, 0.75, 0.45);
+    }
+    return output_colour(tint * (0.25 + 0.75 * fract(orbit.x)));
+}
+
+// The values of u.blend_mode, matching BlendMode::code in compositor.rs.
+const BLEND_NORMAL = 0u;
+const BLEND_MULTIPLY = 1u;
+const BLEND_SCREEN = 2u;
+const BLEND_OVERLAY = 3u;
+const BLEND_SOFT_LIGHT = 4u;
+
+// The colour in linear light which the given layer gives the stored orbit of an escaping point.
+fn layer_colour(layer: u32, orbit: vec4<f32>) -> vec3<f32> {
+    if (layer == LAYER_STRIPE_AVERAGE) {
+        return gradient_colour(floor(orbit.w) / (STRIPE_LEVELS - 1.0));
+    }
+    if (layer == LAYER_DISTANCE_ESTIMATE) {
+        return gradient_colour(fract(orbit.w));
+    }
+    return gradient_colour(fract(orbit.x / f32(u.max_iterations) + u.palette_offset));
+}
+
+// Blends the colour of the overlay onto that of the base by u.blend_mode.
+fn blend(base: vec3<f32>, overlay: vec3<f32>) -> vec3<f32> {
+    if (u.blend_mode == BLEND_MULTIPLY) {
+        return base * overlay;
+    }
+    if (u.blend_mode == BLEND_SCREEN) {
+        return 1.0 - (1.0 - base) * (1.0 - overlay);
+    }
+    if (u.blend_mode == BLEND_OVERLAY) {
+        let dark = 2.0 * base * overlay;
+        let light = 1.0 - 2.0 * (1.0 - base) * (1.0 - overlay);
+        return select(dark, light, base > vec3(0.5));
+    }
+    if (u.blend_mode == BLEND_SOFT_LIGHT) {
+        return (1.0 - 2.0 * overlay) * base * base + 2.0 * overlay * base;
+    }
+    return overlay;
+}
+
+// Colours the stored orbit of an escape-time fractal by the base layer with the overlay blended
+// onto it by its opacity, under the orbit trap's image. The interior is coloured as without
+// layers.
+fn composited_colour(orbit: vec4<f32>) -> vec4<f32> {
+    if (orbit.x < 0.0) {
+        return mandelbrot_colour_of(orbit.x, orbit.yz);
+    }
+    let base = layer_colour(u.base_layer, orbit);
+    var colour = base;
+    if (u.overlay_opacity > 0.0) {
+        let overlay = layer_colour(u.overlay_layer, orbit);
+        colour = mix(base, blend(base, overlay), u.overlay_opacity);
+    }
+    return with_transparency(post_process(with_trap_image(colour, orbit.yz)), true);
+}
+
+@fragment
+fn mandelbrot_colouring(@builtin(position) position: vec4<f32>) -> @location(0) vec4<f32> {
+    let orbit = iteration_data_at(position);
+    if (u.debug_view != DEBUG_OFF) {
+        return debug_colour(orbit);
+    }
+    return composited_colour(orbit);
+}
+
+@fragment
+fn newton_colouring(@builtin(position) position: vec4<f32>) -> @location(0) vec4<f32> {
+    return newton_colour_of(iteration_data_at(position).r);
+}
+
+// Colours both the secant method and damped Newton, whose iteration data have the same form.
+@fragment
+fn root_finding_colouring(@builtin(position) position: vec4<f32>) -> @location(0) vec4<f32> {
+    return root_finding_colour_of(iteration_data_at(position).rg);
+}
+
+@fragment
+fn plasma_colouring(@builtin(position) position: vec4<f32>) -> @location(0) vec4<f32> {
+    return plasma_colour_of(iteration_data_at(position).r);
+}
+
+@fragment
+fn terrain_colouring(@builtin(position) position: vec4<f32>) -> @location(0) vec4<f32> {
+    return terrain_colour_of(iteration_data_at(position).rg);
+}
@@ -0,0 +1,20 @@
+// Draws the line list of a curve of the vector mode, appended to frag.wgsl so that it shares its
+// uniform and palette functions.
+
+struct CurveVertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    // How far along the curve the point lies, from 0 at its start to 1 at its end.
+    @location(0) along: f32,
+}
+
+// Places a point of the plane in the viewport by the inverse of the view's transform.
+@vertex
+fn curve_vertex(@location(0) position: vec2<f32>, @location(1) along: f32) -> CurveVertexOutput {
+    return CurveVertexOutput(vec4(viewport_position_of(position), 0.0, 1.0), along);
+}
+
+// Colours the curve from the palette along its length, which cycles with the palette offset.
+@fragment
+fn curve_fragment(in: CurveVertexOutput) -> @location(0) vec4<f32> {
+    return post_process(gradient_colour(fract(in.along + u.palette_offset)));
+}
@@ -0,0 +1,140 @@
+// Arithmetic on double-floats (df64): pairs of f32 whose second holds the rounding error of the
+// first, which gives about 44 significant bits on hardware without f64. Appended to frag.wgsl,
+// whose uniform and variation the Mandelbrot iteration below shares. Complex numbers are stored
+// as vec4(re, im) with each part a df64.
+//
+// The algorithms are those of the double-double arithmetic of Hida, Li and Bailey, and depend on
+// every operation being rounded as written.
+
+// Splits an f32 into halves of 12 bits, whose products are exact in f32.
+const DF64_SPLITTER = 4097.0;
+
+// Returns the already rounded value unchanged, in a way which shader compilers cannot see through.
+// Some drivers, notably those for OpenGL, otherwise simplify expressions such as (a + b) - a to b,
+// which loses the rounding errors that the arithmetic keeps.
+fn df64_rounded(value: f32) -> f32 {
+    return max(value, -3.0e38);
+}
+
+// Returns a + b and the rounding error of the sum.
+fn df64_two_sum(a: f32, b: f32) -> vec2<f32> {
+    let sum = df64_rounded(a + b);
+    let b_part = sum - a;
+    return vec2(sum, (a - (sum - b_part)) + (b - b_part));
+}
+
+// Like df64_two_sum for |a| >= |b|, which makes it cheaper.
+fn df64_quick_two_sum(a: f32, b: f32) -> vec2<f32> {
+    let sum = df64_rounded(a + b);
+    return vec2(sum, b - (sum - a));
+}
+
+fn df64_split(a: f32) -> vec2<f32> {
+    let t = df64_rounded(DF64_SPLITTER * a);
+    let hi = t - df64_rounded(t - a);
+    return vec2(hi, a - hi);
+}
+
+// Returns a * b and the rounding error of the product, without relying on a fused multiply-add.
+fn df64_two_prod(a: f32, b: f32) -> vec2<f32> {
+    let product = df64_rounded(a * b);
+    let a_parts = df64_split(a);
+    let b_parts = df64_split(b);
+    let error = ((a_parts.x * b_parts.x - product) + a_parts.x * b_parts.y + a_parts.y * b_parts.x)
+        + a_parts.y * b_parts.y;
+    return vec2(product, error);
+}
+
+fn df64_add(a: vec2<f32>, b: vec2<f32>) -> vec2<f32> {
+    let sum = df64_two_sum(a.x, b.x);
+    return df64_quick_two_sum(sum.x, sum.y + a.y + b.y);
+}
+
+fn df64_sub(a: vec2<f32>, b: vec2<f32>) -> vec2<f32> {
+    return df64_add(a, -b);
+}
+
+fn df64_mul(a: vec2<f32>, b: vec2<f32>) -> vec2<f32> {
+    let product = df64_two_prod(a.x, b.x);
+    return df64_quick_two_sum(product.x, product.y + a.x * b.y + a.y * b.x);
+}
+
+fn df64_abs(a: vec2<f32>) -> vec2<f32> {
+    if (a.x < 0.0) {
+        return -a;
+    }
+    return a;
+}
+
+fn df64_complex_add(a: vec4<f32>, b: vec4<f32>) -> vec4<f32> {
+    return vec4(df64_add(a.xy, b.xy), df64_add(a.zw, b.zw));
+}
+
+fn df64_complex_mul(a: vec4<f32>, b: vec4<f32>) -> vec4<f32> {
+    return vec4(
+        df64_sub(df64_mul(a.xy, b.xy), df64_mul(a.zw, b.zw)),
+        df64_add(df64_mul(a.xy, b.zw), df64_mul(a.zw, b.xy)),
+    );
+}
+
+// Like vary for a complex df64.
+fn df64_vary(z: vec4<f32>) -> vec4<f32> {
+    var varied = z;
+    if ((u.variation & ABS_RE) != 0u) {
+        varied = vec4(df64_abs(varied.xy), varied.zw);
+    }
+    if ((u.variation & ABS_IM) != 0u) {
+        varied = vec4(varied.xy, df64_abs(varied.zw));
+    }
+    if ((u.variation & CONJUGATE) != 0u) {
+        varied = vec4(varied.xy, -varied.zw);
+    }
+    if ((u.variation & SPIN) != 0u) {
+        varied = df64_complex_mul(varied, vec4(cos(u.time), 0.0, sin(u.time), 0.0));
+    }
+    return varied;
+}
+
+// Like mandelbrot_orbit, iterating in df64. Only the escape test, the smoothing and the orbit trap
+// use the leading parts, which suffice for them.
+fn mandelbrot_orbit_df64(c: vec4<f32>) -> vec3<f32> {
+    let trap_enabled = u.trap.w > 0.0;
+    var trap_hit = NO_TRAP_HIT;
+    var z = vec4(0.0);
+    var re2 = vec2(0.0);
+    var im2 = vec2(0.0);
+    var n = 0u;
+    while (n <= u.max_iterations) {
+        let varied = df64_vary(z);
+        let cross = df64_mul(varied.xy, varied.zw);
+        if ((u.variation & SPIN) != 0u) {
+            re2 = df64_mul(varied.xy, varied.xy);
+            im2 = df64_mul(varied.zw, varied.zw);
+        }
+        z = df64_complex_add(vec4(df64_sub(re2, im2), 2.0 * cross), c);
+        re2 = df64_mul(z.xy, z.xy);
+        im2 = df64_mul(z.zw, z.zw);
+
+        if (trap_enabled && trap_hit.x < 0.0) {
+            trap_hit = trap_coordinates(z.xz);
+        }
+
+        let modulus_sq = re2.x + im2.x;
+        if (modulus_sq > u.bailout * u.bailout) {
+            let log_modulus = 0.5 * log(modulus_sq);
+            return vec3(f32(n) + 1.0 - log2(log_modulus / log(u.bailout)), trap_hit);
+        }
+
+        n += 1u;
+    }
+    return vec3(-1.0, trap_hit);
+}
+
+// Returns the position on the plane of a point of the viewport, which spans [-1, 1] x [-1, 1], as
+// a complex df64. The centre of the view is the sum of the translation of the transform and its
+// residual, while the offset from it is small enough for f32.
+fn df64_position(viewport_position: vec2<f32>) -> vec4<f32> {
+    let offset = (u.transform * vec3(viewport_position, 0.0)).xy;
+    let centre = vec4(u.transform[2].x, u.centre_residual.x, u.transform[2].y, u.centre_residual.y);
+    return df64_complex_add(centre, vec4(offset.x, 0.0, offset.y, 0.0));
+}
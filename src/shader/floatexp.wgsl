@@ -0,0 +1,98 @@
+// Complex numbers with an f32 mantissa and a separate i32 exponent (floatexp), after Kalles
+// Fraktaler. They hold the deltas of perturbed orbits from their reference orbit, which shrink
+// with the pixel spacing and at deep zooms fall below the smallest normal f32 of about 1e-38.
+// Appended to frag.wgsl, whose complex multiplication they use.
+//
+// Both parts share the exponent, which is chosen so that the larger part of the mantissa has a
+// magnitude in [0.5, 1). The exponent is only ever changed by powers of two, which scale the
+// mantissa exactly, and without ldexp and frexp, which WebGL2 lacks.
+struct ComplexExp {
+    mantissa: vec2<f32>,
+    exponent: i32,
+}
+
+// The exponent of zero, which is so small that zero is negligible in any sum while products of it
+// cannot overflow.
+const FLOATEXP_ZERO_EXPONENT = -0x40000000;
+
+// Beyond this difference of exponents, the smaller of two summands vanishes in the rounding of the
+// larger.
+const FLOATEXP_NEGLIGIBLE_EXPONENT_DIFFERENCE = 32;
+
+fn floatexp_zero() -> ComplexExp {
+    return ComplexExp(vec2(0.0), FLOATEXP_ZERO_EXPONENT);
+}
+
+// Returns 2^exponent for exponents in the range of normal f32, [-126, 127].
+fn floatexp_pow2(exponent: i32) -> f32 {
+    return bitcast<f32>(u32(exponent + 127) << 23u);
+}
+
+// Returns the mantissa times 2^exponent in normal form.
+fn floatexp_normalize(mantissa: vec2<f32>, exponent: i32) -> ComplexExp {
+    let larger = max(abs(mantissa.x), abs(mantissa.y));
+    if (larger == 0.0) {
+        return floatexp_zero();
+    }
+    // The biased exponent of an f32 in [0.5, 1) is 126.
+    let shift = i32((bitcast<u32>(larger) >> 23u) & 0xffu) - 126;
+    return ComplexExp(mantissa * floatexp_pow2(-shift), exponent + shift);
+}
+
+fn floatexp_from(value: vec2<f32>) -> ComplexExp {
+    return floatexp_normalize(value, 0);
+}
+
+// Rounds the number to f32, which underflows to zero below the smallest f32.
+fn floatexp_to_f32(a: ComplexExp) -> vec2<f32> {
+    // Scales in two steps so that each power of two is normal.
+    let exponent = clamp(a.exponent, -252, 254);
+    let half = exponent / 2;
+    return a.mantissa * floatexp_pow2(half) * floatexp_pow2(exponent - half);
+}
+
+fn floatexp_add(a: ComplexExp, b: ComplexExp) -> ComplexExp {
+    var larger = a;
+    var smaller = b;
+    if (b.exponent > a.exponent) {
+        larger = b;
+        smaller = a;
+    }
+    let difference = larger.exponent - smaller.exponent;
+    if (difference > FLOATEXP_NEGLIGIBLE_EXPONENT_DIFFERENCE) {
+        return larger;
+    }
+    return floatexp_normalize(
+        larger.mantissa + smaller.mantissa * floatexp_pow2(-difference),
+        larger.exponent,
+    );
+}
+
+fn floatexp_mul(a: ComplexExp, b: ComplexExp) -> ComplexExp {
+    return floatexp_normalize(mul(a.mantissa, b.mantissa), a.exponent + b.exponent);
+}
+
+// Returns the delta from the reference point of the point at the given offset from the centre of
+// the view, in the coordinates which the transform maps onto the plane. At deep zooms both the
+// offset on the plane and that of the centre from the reference point are smaller than the
+// smallest f32, so they are scaled by their exponents and summed in floatexp.
+fn floatexp_delta_c(offset: vec2<f32>) -> ComplexExp {
+    let scaled = (u.transform * vec3(offset, 0.0)).xy;
+    return floatexp_add(
+        floatexp_normalize(scaled, u.scale_exponent),
+        floatexp_normalize(u.reference_offset, u.reference_offset_exponent),
+    );
+}
+
+// Advances the delta of a perturbed Mandelbrot orbit from the reference orbit by one iteration,
+// given the point through which the reference orbit passes and the delta of the pixel from the
+// reference point. This follows from (Z + delta)^2 + (C + delta_c) = Z^2 + C + delta', which gives
+// delta' = delta (2 Z + delta) + delta_c.
+fn floatexp_mandelbrot_delta_step(
+    delta: ComplexExp,
+    reference: vec2<f32>,
+    delta_c: ComplexExp,
+) -> ComplexExp {
+    let factor = floatexp_add(floatexp_from(2.0 * reference), delta);
+    return floatexp_add(floatexp_mul(delta, factor), delta_c);
+}
@@ -3,27 +3,178 @@ struct VertexOutput {
     @location(0) position: vec2<f32>,
 }
 
+// Packed on the CPU by ParameterBlock in fractal_view.rs, whose layout tests check the offsets of
+// these fields.
 struct Uniform {
     transform: mat3x3<f32>,
+    max_iterations: u32,
+    bailout: f32,
+    palette_offset: f32,
+    // The parameters declared in the fractal's definition, four to a vector.
+    params: array<vec4<f32>, 2>,
+    // The palette's gradient, whose first colour is also that of the interior of the set.
+    gradient: array<vec4<f32>, 8>,
+    // The colours of the basins of attraction of the roots of the Newton fractal.
+    root_colours: array<vec4<f32>, 3>,
+    // The exposure in stops, gamma, contrast and saturation applied by post_process.
+    post_processing: vec4<f32>,
+    // The centre of the orbit trap on the plane, its half size and whether it is enabled.
+    trap: vec4<f32>,
+    // The flags of the variation applied by vary.
+    variation: u32,
+    // The part of the centre of the view beyond the precision of f32, which the translation of the
+    // transform leaves out. Added by df64_position in df64.wgsl.
+    centre_residual: vec2<f32>,
+    // The mantissa of the offset of the centre of the view from the point of the reference orbit,
+    // from which iteration_data_perturbation.wgsl iterates the pixels by their deltas. Its
+    // exponent is reference_offset_exponent.
+    reference_offset: vec2<f32>,
+    // The seconds for which the time has played, by which the SPIN variation rotates.
+    time: f32,
+    // Which raw quantity of the colouring is shown instead of the palette, one of DEBUG_*.
+    debug_view: u32,
+    // Seeds the random positions of the samples of adaptive anti-aliasing and the walks of the
+    // chaos game.
+    seed: u32,
+    // Whether the render target stores linear light, which the GPU encodes for display, rather
+    // than colours encoded in sRGB. Applied by output_colour.
+    linear_output: u32,
+    // How post_process brings back colours brightened beyond white, one of TONEMAP_*.
+    tonemapper: u32,
+    // The critical point from which the orbits of a rational map start.
+    critical_point: vec2<f32>,
+    // The constant c of the dynamic plane.
+    fixed_c: vec2<f32>,
+    // Which plane escape-time fractals show, one of PLANE_*.
+    plane: u32,
+    // What the two layers of the colouring show, each one of LAYER_* in colouring.wgsl, how the
+    // overlay is blended onto the base, one of BLEND_*, and how opaque it is.
+    base_layer: u32,
+    overlay_layer: u32,
+    blend_mode: u32,
+    overlay_opacity: f32,
+    // Whether the interior or exterior of escape-time fractals is transparent, one of
+    // TRANSPARENT_*.
+    transparency: u32,
+    // The power of two by which the scale and rotation of transform are multiplied, which is only
+    // non-zero for views too small for f32 and only applied by perturbation.
+    scale_exponent: i32,
+    reference_offset_exponent: i32,
 }
 
 @group(0) @binding(0) var<uniform> u: Uniform;
+@group(0) @binding(1) var trap_image: texture_2d<f32>;
+@group(0) @binding(2) var trap_sampler: sampler;
 
-fn mandelbrot_iterations(c: vec2<f32>) -> f32 {
+// The values of u.debug_view, matching DebugView::code in debug_view.rs. The escape radius and
+// the distance estimate are stored in the fourth channel of the iteration data.
+const DEBUG_OFF = 0u;
+const DEBUG_ITERATION_BANDS = 1u;
+const DEBUG_ESCAPE_RADIUS = 2u;
+const DEBUG_DISTANCE_ESTIMATE = 3u;
+
+// The values of u.base_layer and u.overlay_layer, matching Layer::code in compositor.rs.
+const LAYER_SMOOTH_ITERATION = 0u;
+const LAYER_STRIPE_AVERAGE = 1u;
+const LAYER_DISTANCE_ESTIMATE = 2u;
+
+// The values of u.transparency, matching Transparency::code in transparency.rs.
+const TRANSPARENT_NONE = 0u;
+const TRANSPARENT_INTERIOR = 1u;
+const TRANSPARENT_EXTERIOR = 2u;
+
+// The number of levels to which the stripe average is rounded, so that it shares the fourth
+// channel of the iteration data with the distance estimate: the whole part holds the level of the
+// stripe average and the fraction the distance estimate.
+const STRIPE_LEVELS = 1024.0;
+
+// Whether a visible layer of the colouring shows the stripe average or the distance estimate,
+// which must then be stored with the iteration data unless the debug view takes their place.
+fn layers_need_statistics() -> bool {
+    return u.base_layer != LAYER_SMOOTH_ITERATION
+        || (u.overlay_opacity > 0.0 && u.overlay_layer != LAYER_SMOOTH_ITERATION);
+}
+
+fn parameter(offset: u32) -> f32 {
+    return u.params[offset / 4u][offset % 4u];
+}
+
+// The point of the viewport at which a point of the plane is shown, by the inverse of the view's
+// transform.
+fn viewport_position_of(point: vec2<f32>) -> vec2<f32> {
+    let linear = mat2x2(u.transform[0].xy, u.transform[1].xy);
+    let adjugate = mat2x2(linear[1].y, -linear[0].y, -linear[1].x, linear[0].x);
+    return adjugate * (point - u.transform[2].xy) * (1.0 / determinant(linear));
+}
+
+// The bits of u.variation, matching VariationFlag::bit in variation.rs.
+const ABS_RE = 1u;
+const ABS_IM = 2u;
+const CONJUGATE = 4u;
+const SPIN = 8u;
+
+// Changes the iterated value before a step according to the flags of the variation, so that any
+// fractal becomes one of its variants.
+fn vary(z: vec2<f32>) -> vec2<f32> {
+    var varied = z;
+    if ((u.variation & ABS_RE) != 0u) {
+        varied.x = abs(varied.x);
+    }
+    if ((u.variation & ABS_IM) != 0u) {
+        varied.y = abs(varied.y);
+    }
+    if ((u.variation & CONJUGATE) != 0u) {
+        varied.y = -varied.y;
+    }
+    if ((u.variation & SPIN) != 0u) {
+        varied = mul(varied, vec2(cos(u.time), sin(u.time)));
+    }
+    return varied;
+}
+
+// The values of u.plane, matching Plane::code in plane.rs.
+const PLANE_PARAMETER = 0u;
+const PLANE_DYNAMIC = 1u;
+
+// Where the orbit of a point of the plane of an escape-time fractal starts, and the constant added
+// at each step.
+struct OrbitStart {
+    z: vec2<f32>,
+    c: vec2<f32>,
+}
+
+// In the parameter plane the point is the constant and the orbit starts from the given critical
+// point of the iteration. In the dynamic plane the point is where the orbit starts and the
+// constant is fixed.
+fn orbit_start(point: vec2<f32>, critical_point: vec2<f32>) -> OrbitStart {
+    if (u.plane == PLANE_DYNAMIC) {
+        return OrbitStart(point, u.fixed_c);
+    }
+    return OrbitStart(critical_point, point);
+}
+
+// Returns the fraction of the iteration budget after which the orbit of c escaped, or a value
+// greater than one if it did not escape.
+fn mandelbrot_escape_time(c: vec2<f32>) -> f32 {
     var z = vec2(0.0, 0.0);
     var z2 = vec2(0.0, 0.0);
-    var i = 0.0;
-    while (i <= 1.0) {
+    var n = 0u;
+    while (n <= u.max_iterations) {
         z = vec2(z2.x - z2.y + c.x, 2.0 * z.x * z.y + c.y);
         z2 = vec2(z.x * z.x, z.y * z.y);
 
-        if (z2.x + z2.y > 4.0) {
+        if (z2.x + z2.y > u.bailout * u.bailout) {
             break;
         }
 
-        i += 0.001;
+        n += 1u;
     }
 
+    return f32(n) / f32(u.max_iterations);
+}
+
+fn mandelbrot_iterations(c: vec2<f32>) -> f32 {
+    let i = mandelbrot_escape_time(c);
     if i > 1.0 {
         return 0.0;
     } else {
@@ -31,10 +182,208 @@ fn mandelbrot_iterations(c: vec2<f32>) -> f32 {
     }
 }
 
+// Decodes a colour encoded in sRGB, such as those of the palettes, into linear light.
+fn srgb_to_linear(colour: vec3<f32>) -> vec3<f32> {
+    let low = colour / 12.92;
+    let high = pow((colour + 0.055) / 1.055, vec3(2.4));
+    return select(high, low, colour <= vec3(0.04045));
+}
+
+// Encodes a colour in linear light in sRGB.
+fn linear_to_srgb(colour: vec3<f32>) -> vec3<f32> {
+    let low = colour * 12.92;
+    let high = 1.055 * pow(colour, vec3(1.0 / 2.4)) - 0.055;
+    return select(high, low, colour <= vec3(0.0031308));
+}
+
+// Interpolates the palette's gradient at t in [0, 1] in linear light, so that the colours between
+// two stops are as bright as both.
+fn gradient_colour(t: f32) -> vec3<f32> {
+    let position = clamp(t, 0.0, 1.0) * 7.0;
+    let stop = min(u32(position), 6u);
+    return mix(
+        srgb_to_linear(u.gradient[stop].rgb),
+        srgb_to_linear(u.gradient[stop + 1u].rgb),
+        position - f32(stop)
+    );
+}
+
+// The values of u.tonemapper, matching Tonemapper::code in post_processing.rs.
+const TONEMAP_CLIP = 0u;
+const TONEMAP_REINHARD = 1u;
+const TONEMAP_ACES = 2u;
+
+// Maps a colour in linear light which may lie beyond white back towards the range which can be
+// shown, by the tonemapper of the uniform block. Clipping leaves it to the caller.
+fn tonemap(colour: vec3<f32>) -> vec3<f32> {
+    if (u.tonemapper == TONEMAP_REINHARD) {
+        return colour / (1.0 + dot(colour, vec3(0.2126, 0.7152, 0.0722)));
+    }
+    if (u.tonemapper == TONEMAP_ACES) {
+        return colour * (2.51 * colour + 0.03) / (colour * (2.43 * colour + 0.59) + 0.14);
+    }
+    return colour;
+}
+
+// Adjusts a colour of the palette in linear light by the exposure and tonemapper, then encoded in
+// sRGB by the contrast, saturation and gamma, which are perceptual, in that order. Returns the
+// colour to output.
+fn post_process(colour: vec3<f32>) -> vec4<f32> {
+    let exposure = u.post_processing.x;
+    let gamma = u.post_processing.y;
+    let contrast = u.post_processing.z;
+    let saturation = u.post_processing.w;
+    let exposed = tonemap(colour * exp2(exposure));
+    let encoded = linear_to_srgb(clamp(exposed, vec3(0.0), vec3(1.0)));
+    var adjusted = (encoded - 0.5) * contrast + 0.5;
+    let luminance = dot(adjusted, vec3(0.2126, 0.7152, 0.0722));
+    adjusted = clamp(mix(vec3(luminance), adjusted, saturation), vec3(0.0), vec3(1.0));
+    return output_colour(pow(adjusted, vec3(1.0 / gamma)));
+}
+
+// The opaque colour to output for a colour encoded in sRGB, in linear light if the render target
+// stores that.
+fn output_colour(colour: vec3<f32>) -> vec4<f32> {
+    if (u.linear_output != 0u) {
+        return vec4(srgb_to_linear(colour), 1.0);
+    }
+    return vec4(colour, 1.0);
+}
+
+// Stands for the texture coordinates of an orbit which never landed in the trap.
+const NO_TRAP_HIT = vec2(-1.0, -1.0);
+
+// Returns the texture coordinates at which z lies in the orbit trap, or NO_TRAP_HIT if it lies
+// outside of it.
+fn trap_coordinates(z: vec2<f32>) -> vec2<f32> {
+    let offset = (z - u.trap.xy) / u.trap.z;
+    if (any(abs(offset) > vec2(1.0))) {
+        return NO_TRAP_HIT;
+    }
+    return vec2(offset.x, -offset.y) * 0.5 + 0.5;
+}
+
+// Returns the number of iterations after which the orbit of the point escaped, interpolated
+// between whole iterations by how far beyond the bailout it landed, or a negative value if it did
+// not escape, followed by the texture coordinates at which the orbit first landed in the orbit
+// trap, or NO_TRAP_HIT if it never did or the trap is disabled.
+fn mandelbrot_orbit(point: vec2<f32>) -> vec3<f32> {
+    let trap_enabled = u.trap.w > 0.0;
+    var trap_hit = NO_TRAP_HIT;
+    let start = orbit_start(point, vec2(0.0, 0.0));
+    let c = start.c;
+    var z = start.z;
+    var z2 = z * z;
+    var n = 0u;
+    while (n <= u.max_iterations) {
+        // The squares of the parts are the same for every variation but spinning.
+        let varied = vary(z);
+        var squares = z2;
+        if ((u.variation & SPIN) != 0u) {
+            squares = varied * varied;
+        }
+        z = vec2(squares.x - squares.y + c.x, 2.0 * varied.x * varied.y + c.y);
+        z2 = vec2(z.x * z.x, z.y * z.y);
+
+        if (trap_enabled && trap_hit.x < 0.0) {
+            trap_hit = trap_coordinates(z);
+        }
+
+        if (z2.x + z2.y > u.bailout * u.bailout) {
+            let log_modulus = 0.5 * log(z2.x + z2.y);
+            return vec3(f32(n) + 1.0 - log2(log_modulus / log(u.bailout)), trap_hit);
+        }
+
+        n += 1u;
+    }
+    return vec3(-1.0, trap_hit);
+}
+
+// The colour of a point of the Mandelbrot plane by its smooth iteration count, which is negative
+// in the interior of the set, and the texture coordinates of its orbit in the trap, over which the
+// trap's image is laid by its alpha.
+fn mandelbrot_colour_of(iterations: f32, trap_hit: vec2<f32>) -> vec4<f32> {
+    var t = 0.0;
+    if (iterations > 0.0) {
+        t = fract(iterations / f32(u.max_iterations) + u.palette_offset);
+    }
+    let colour = post_process(with_trap_image(gradient_colour(t), trap_hit));
+    return with_transparency(colour, iterations >= 0.0);
+}
+
+// Clears the alpha of an output colour of an escape-time fractal if u.transparency makes the part
+// of the plane to which the point belongs transparent, which is the exterior if it escaped.
+fn with_transparency(colour: vec4<f32>, escaped: bool) -> vec4<f32> {
+    if ((escaped && u.transparency == TRANSPARENT_EXTERIOR)
+        || (!escaped && u.transparency == TRANSPARENT_INTERIOR)) {
+        return vec4(colour.rgb, 0.0);
+    }
+    return colour;
+}
+
+// Lays the image of the orbit trap over a colour in linear light by its alpha at the given texture
+// coordinates, unless they are NO_TRAP_HIT.
+fn with_trap_image(colour: vec3<f32>, trap_hit: vec2<f32>) -> vec3<f32> {
+    if (trap_hit.x < 0.0) {
+        return colour;
+    }
+    let texel = textureSampleLevel(trap_image, trap_sampler, trap_hit, 0.0);
+    return mix(colour, srgb_to_linear(texel.rgb), texel.a);
+}
+
+// The colour of the Mandelbrot set at the given position in the viewport, which spans
+// [-1, 1] x [-1, 1].
+fn mandelbrot_colour(viewport_position: vec2<f32>) -> vec4<f32> {
+    let position = u.transform * vec3(viewport_position, 1.0);
+    let orbit = mandelbrot_orbit(position.xy);
+    return mandelbrot_colour_of(orbit.x, orbit.yz);
+}
+
 @fragment
 fn mandelbrot(in: VertexOutput) -> @location(0) vec4<f32> {
-    let position = u.transform * vec3(in.position, 1.0);
-    return vec4(vec3(mandelbrot_iterations(vec2(position.x, position.y))), 1.0);
+    return mandelbrot_colour(in.position);
+}
+
+// Raises z to the real power d through its polar form.
+fn cpow(z: vec2<f32>, d: f32) -> vec2<f32> {
+    let modulus_sq = dot(z, z);
+    if (modulus_sq == 0.0) {
+        return vec2(0.0, 0.0);
+    }
+    let angle = d * atan2(z.y, z.x);
+    return pow(modulus_sq, 0.5 * d) * vec2(cos(angle), sin(angle));
+}
+
+// Like mandelbrot_orbit without the orbit trap, for the iteration z^d + c of the Multibrot set
+// with the exponent d in its first parameter.
+fn multibrot_smooth_iterations(point: vec2<f32>) -> f32 {
+    let d = parameter(0u);
+    let start = orbit_start(point, vec2(0.0, 0.0));
+    let c = start.c;
+    var z = start.z;
+    var n = 0u;
+    while (n <= u.max_iterations) {
+        z = cpow(vary(z), d) + c;
+
+        let modulus_sq = dot(z, z);
+        if (modulus_sq > u.bailout * u.bailout) {
+            let log_modulus = 0.5 * log(modulus_sq);
+            return f32(n) + 1.0 - log(log_modulus / log(u.bailout)) / log(d);
+        }
+
+        n += 1u;
+    }
+    return -1.0;
+}
+
+fn multibrot_colour(viewport_position: vec2<f32>) -> vec4<f32> {
+    let position = u.transform * vec3(viewport_position, 1.0);
+    return mandelbrot_colour_of(multibrot_smooth_iterations(position.xy), NO_TRAP_HIT);
+}
+
+@fragment
+fn multibrot(in: VertexOutput) -> @location(0) vec4<f32> {
+    return multibrot_colour(in.position);
 }
 
 fn mul(a: vec2<f32>, b: vec2<f32>) -> vec2<f32> {
@@ -55,13 +404,14 @@ fn eval_poly(z: vec2<f32>, coeffs: vec4<f32>) -> vec2<f32> {
     return f_z + vec2(coeffs[3], 0.0);
 }
 
-const ITERATIONS = 100;
 const COEFFS = vec4<f32>(1.0, 0.0, 0.0, -1.0);
 const DERIVATIVE_COEFFS = vec4<f32>(0.0, 3.0, 0.0, 0.0);
 
 fn newton_iterate(position: vec3<f32>) -> vec2<f32> {
     var z = vec2(position.x, position.y);
-    for (var i: i32 = 0; i < ITERATIONS; i += 1) {
+    let iterations = i32(parameter(0u));
+    for (var i: i32 = 0; i < iterations; i += 1) {
+        z = vary(z);
         let f_z = eval_poly(z, COEFFS);
         let fp_z = eval_poly(z, DERIVATIVE_COEFFS);
         let fp_z_inv = inv(fp_z);
@@ -82,28 +432,353 @@ const ROOTS = array<vec2<f32>, 3>(
     vec2<f32>(-0.5, 0.866025),
     vec2<f32>(-0.5, -0.866025),
 );
-const COLOURS = array<vec3<f32>, 3>(
-    vec3<f32>(1.0, 0.0, 0.0),
-    vec3<f32>(0.0, 1.0, 0.0),
-    vec3<f32>(0.0, 0.0, 1.0),
-);
-
 fn point_colour(z: vec2<f32>) -> vec3<f32> {
     var colour = vec3(0.0);
     if distance_sq(z, ROOTS[0]) < EPSILON {
-        colour = COLOURS[0];
+        colour = u.root_colours[0].rgb;
     }
     if distance_sq(z, ROOTS[1]) < EPSILON {
-        colour = COLOURS[1];
+        colour = u.root_colours[1].rgb;
     }
     if distance_sq(z, ROOTS[2]) < EPSILON {
-        colour = COLOURS[2];
+        colour = u.root_colours[2].rgb;
     }
     return colour;
 }
 
+// Returns one more than the index of the root to which Newton's method converges from the given
+// point, or zero if it does not converge to any.
+fn newton_root(position: vec3<f32>) -> f32 {
+    let z = newton_iterate(position);
+    // Constant arrays can only be indexed by constants.
+    var roots = ROOTS;
+    for (var i = 0u; i < 3u; i++) {
+        if distance_sq(z, roots[i]) < EPSILON {
+            return f32(i + 1u);
+        }
+    }
+    return 0.0;
+}
+
+// The colour of a point of the Newton fractal by the value returned by newton_root.
+fn newton_colour_of(root: f32) -> vec4<f32> {
+    let index = u32(root);
+    var colour = vec3(0.0);
+    if (index > 0u) {
+        colour = srgb_to_linear(u.root_colours[index - 1u].rgb);
+    }
+    return post_process(colour);
+}
+
+fn newton_colour(viewport_position: vec2<f32>) -> vec4<f32> {
+    let position = u.transform * vec3(viewport_position, 1.0);
+    return newton_colour_of(newton_root(position));
+}
+
 @fragment
 fn newton(in: VertexOutput) -> @location(0) vec4<f32> {
-    let position = u.transform * vec3(in.position, 1.0);
-    return vec4(point_colour(newton_iterate(position)), 1.0);
+    return newton_colour(in.position);
+}
+
+// The root nearest to z, numbered as by newton_root, if it lies within EPSILON, or else zero, and
+// the squared distance to it.
+fn nearest_root(z: vec2<f32>) -> vec2<f32> {
+    var roots = ROOTS;
+    var nearest = vec2(0.0, distance_sq(z, roots[0]));
+    for (var i = 1u; i < 3u; i++) {
+        nearest.y = min(nearest.y, distance_sq(z, roots[i]));
+    }
+    for (var i = 0u; i < 3u; i++) {
+        if distance_sq(z, roots[i]) < EPSILON {
+            nearest.x = f32(i + 1u);
+        }
+    }
+    return nearest;
+}
+
+// The number of steps a root-finding method took to come within EPSILON of a root, which it
+// came after step n from the given squared distances before and after. The step is counted in
+// part, by how far the distance fell beyond EPSILON, so that the shading by convergence is
+// smooth.
+fn convergence_steps(n: u32, previous_distance_sq: f32, distance_sq: f32) -> f32 {
+    let overshoot = log(EPSILON / distance_sq) / log(previous_distance_sq / distance_sq);
+    return f32(n) + 1.0 - clamp(overshoot, 0.0, 1.0);
+}
+
+// Newton's method for z^3 - 1 with each step shortened by the relaxation factor in the second
+// parameter, which grows and blurs the basins of the roots below one and breaks them up above.
+// Returns the root to which it converges from the given point, numbered as by newton_root, and
+// the steps it took, or zero for both if it does not converge within the iterations in the first
+// parameter.
+fn damped_newton_convergence(position: vec3<f32>) -> vec2<f32> {
+    let iterations = u32(parameter(0u));
+    let relaxation = parameter(1u);
+    var z = position.xy;
+    var previous_distance_sq = nearest_root(z).y;
+    for (var n = 0u; n < iterations; n++) {
+        z = vary(z);
+        z -= relaxation * mul(eval_poly(z, COEFFS), inv(eval_poly(z, DERIVATIVE_COEFFS)));
+        let nearest = nearest_root(z);
+        if (nearest.x > 0.0) {
+            return vec2(nearest.x, convergence_steps(n, previous_distance_sq, nearest.y));
+        }
+        previous_distance_sq = nearest.y;
+    }
+    return vec2(0.0);
+}
+
+// The secant method for z^3 - 1, which replaces the derivative of Newton's method with the slope
+// through the last two points. It starts from the given point and a second point offset from it
+// along the real axis by the second parameter. Returns what damped_newton_convergence does.
+fn secant_convergence(position: vec3<f32>) -> vec2<f32> {
+    let iterations = u32(parameter(0u));
+    var previous = position.xy + vec2(parameter(1u), 0.0);
+    var f_previous = eval_poly(previous, COEFFS);
+    var z = position.xy;
+    var previous_distance_sq = nearest_root(z).y;
+    for (var n = 0u; n < iterations; n++) {
+        z = vary(z);
+        let f_z = eval_poly(z, COEFFS);
+        let next = z - mul(f_z, mul(z - previous, inv(f_z - f_previous)));
+        previous = z;
+        f_previous = f_z;
+        z = next;
+        let nearest = nearest_root(z);
+        if (nearest.x > 0.0) {
+            return vec2(nearest.x, convergence_steps(n, previous_distance_sq, nearest.y));
+        }
+        previous_distance_sq = nearest.y;
+    }
+    return vec2(0.0);
+}
+
+// The number of steps to convergence over which the colour of a basin halves in brightness.
+const CONVERGENCE_HALVING_STEPS = 4.0;
+
+// The colour of a point of a root-finding fractal by the root to which it converges and the steps
+// it took, as returned by damped_newton_convergence, shaded darker the slower it converged.
+fn root_finding_colour_of(convergence: vec2<f32>) -> vec4<f32> {
+    let index = u32(convergence.x);
+    var colour = vec3(0.0);
+    if (index > 0u) {
+        let shade = exp2(-convergence.y / CONVERGENCE_HALVING_STEPS);
+        colour = srgb_to_linear(u.root_colours[index - 1u].rgb) * shade;
+    }
+    return post_process(colour);
+}
+
+fn damped_newton_colour(viewport_position: vec2<f32>) -> vec4<f32> {
+    let position = u.transform * vec3(viewport_position, 1.0);
+    return root_finding_colour_of(damped_newton_convergence(position));
+}
+
+@fragment
+fn damped_newton(in: VertexOutput) -> @location(0) vec4<f32> {
+    return damped_newton_colour(in.position);
+}
+
+fn secant_colour(viewport_position: vec2<f32>) -> vec4<f32> {
+    let position = u.transform * vec3(viewport_position, 1.0);
+    return root_finding_colour_of(secant_convergence(position));
+}
+
+@fragment
+fn secant(in: VertexOutput) -> @location(0) vec4<f32> {
+    return secant_colour(in.position);
+}
+
+// The degree of the polynomial with the given coefficients, from the cube down to the constant.
+fn poly_degree(coeffs: vec4<f32>) -> f32 {
+    for (var i = 0u; i < 3u; i++) {
+        if (coeffs[i] != 0.0) {
+            return f32(3u - i);
+        }
+    }
+    return 0.0;
+}
+
+// Like multibrot_smooth_iterations for the iteration p(z) / q(z) + c of a rational map, whose
+// numerator and denominator take their coefficients from the first and second four parameters.
+// Infinity attracts only if p has a higher degree than q, by the difference of which the escape
+// time is smoothed. In the parameter plane the orbits start from the chosen critical point of
+// p / q.
+fn rational_smooth_iterations(point: vec2<f32>) -> f32 {
+    let numerator = u.params[0];
+    let denominator = u.params[1];
+    let degree = poly_degree(numerator) - poly_degree(denominator);
+    let start = orbit_start(point, u.critical_point);
+    let c = start.c;
+    var z = start.z;
+    var n = 0u;
+    while (n <= u.max_iterations) {
+        let varied = vary(z);
+        z = mul(eval_poly(varied, numerator), inv(eval_poly(varied, denominator))) + c;
+
+        // Written so that landing on a pole, which makes z infinite or NaN, counts as escaping.
+        let modulus_sq = dot(z, z);
+        if (!(modulus_sq <= u.bailout * u.bailout)) {
+            // Nor can the escape time be smoothed where infinity attracts only linearly.
+            if (degree < 2.0 || !(modulus_sq < 1e30)) {
+                return f32(n) + 1.0;
+            }
+            let log_modulus = 0.5 * log(modulus_sq);
+            return f32(n) + 1.0 - log(log_modulus / log(u.bailout)) / log(degree);
+        }
+
+        n += 1u;
+    }
+    return -1.0;
+}
+
+fn rational_colour(viewport_position: vec2<f32>) -> vec4<f32> {
+    let position = u.transform * vec3(viewport_position, 1.0);
+    return mandelbrot_colour_of(rational_smooth_iterations(position.xy), NO_TRAP_HIT);
+}
+
+@fragment
+fn rational(in: VertexOutput) -> @location(0) vec4<f32> {
+    return rational_colour(in.position);
+}
+
+// Hashes a point of the integer lattice and a seed to the unit gradient of the noise there, with
+// integer arithmetic so that the noise is the same on every GPU.
+fn lattice_gradient(point: vec2<i32>, seed: u32) -> vec2<f32> {
+    var h = (bitcast<u32>(point.x) * 0x8da6b343u) ^ (bitcast<u32>(point.y) * 0xd8163841u)
+        ^ (seed * 0xcb1ab31fu);
+    h = (h ^ (h >> 16u)) * 0x7feb352du;
+    h = (h ^ (h >> 15u)) * 0x846ca68bu;
+    h = h ^ (h >> 16u);
+    let angle = f32(h) * (6.2831853 / 4294967296.0);
+    return vec2(cos(angle), sin(angle));
+}
+
+// Perlin's gradient noise at p with the given seed, scaled to [-1, 1]. Features are about a unit
+// apart, and the noise is zero at every point of the integer lattice.
+fn gradient_noise(p: vec2<f32>, seed: u32) -> f32 {
+    let cell = floor(p);
+    let f = p - cell;
+    let i = vec2<i32>(cell);
+    // The quintic fade has continuous second derivatives, which keeps the hillshading smooth.
+    let w = f * f * f * (f * (f * 6.0 - 15.0) + 10.0);
+    let a = dot(lattice_gradient(i, seed), f);
+    let b = dot(lattice_gradient(i + vec2(1, 0), seed), f - vec2(1.0, 0.0));
+    let c = dot(lattice_gradient(i + vec2(0, 1), seed), f - vec2(0.0, 1.0));
+    let d = dot(lattice_gradient(i + vec2(1, 1), seed), f - vec2(1.0, 1.0));
+    // Two-dimensional gradient noise reaches at most half the square root of two.
+    return 1.4142135 * mix(mix(a, b, w.x), mix(c, d, w.x), w.y);
+}
+
+// The seed of the given octave of the noise with the seed in the parameters, so that each octave
+// and each seed has noise of its own.
+fn octave_seed(octave: u32) -> u32 {
+    return u32(parameter(3u)) * 16u + octave;
+}
+
+// Turns each octave of the noise against the one before by the angle of a 3-4-5 triangle, so that
+// the lattices of the octaves, where the noise is zero, never line up.
+const OCTAVE_ROTATION = mat2x2<f32>(0.8, 0.6, -0.6, 0.8);
+
+// Fractional Brownian motion: octaves of gradient noise, each finer by the lacunarity and fainter
+// by the gain than the one before, up to the number of octaves, all in the parameters. Returns a
+// value in [-1, 1].
+fn fbm(p: vec2<f32>) -> f32 {
+    let octaves = u32(parameter(0u));
+    let lacunarity = parameter(1u);
+    let gain = parameter(2u);
+    var q = p;
+    var amplitude = 1.0;
+    var sum = 0.0;
+    var total = 0.0;
+    for (var octave = 0u; octave < octaves; octave++) {
+        sum += amplitude * gradient_noise(q, octave_seed(octave));
+        total += amplitude;
+        q = OCTAVE_ROTATION * q * lacunarity;
+        amplitude *= gain;
+    }
+    return sum / total;
+}
+
+// Musgrave's ridged multifractal: octaves of gradient noise folded into sharp ridges where they
+// cross zero, with each octave weighted by the ones before, so that the valleys stay smooth while
+// the ridges grow rough. Shaped by the same parameters as fbm. Returns a value in [0, 1].
+fn ridged_multifractal(p: vec2<f32>) -> f32 {
+    let octaves = u32(parameter(0u));
+    let lacunarity = parameter(1u);
+    let gain = parameter(2u);
+    var q = p;
+    var amplitude = 1.0;
+    var weight = 1.0;
+    var sum = 0.0;
+    var total = 0.0;
+    for (var octave = 0u; octave < octaves; octave++) {
+        let ridge = 1.0 - abs(gradient_noise(q, octave_seed(octave)));
+        let weighted = ridge * ridge * weight;
+        weight = clamp(2.0 * weighted, 0.0, 1.0);
+        sum += amplitude * weighted;
+        total += amplitude;
+        q = OCTAVE_ROTATION * q * lacunarity;
+        amplitude *= gain;
+    }
+    return sum / total;
+}
+
+// The plasma at a point of the plane, in [0, 1].
+fn plasma_value(p: vec2<f32>) -> f32 {
+    return 0.5 + 0.5 * fbm(p);
+}
+
+// The colour of the plasma by the value returned by plasma_value. The palette wraps around as its
+// offset cycles, as plasma traditionally does.
+fn plasma_colour_of(value: f32) -> vec4<f32> {
+    return post_process(gradient_colour(fract(value + u.palette_offset)));
+}
+
+fn plasma_colour(viewport_position: vec2<f32>) -> vec4<f32> {
+    let position = u.transform * vec3(viewport_position, 1.0);
+    return plasma_colour_of(plasma_value(position.xy));
+}
+
+@fragment
+fn plasma(in: VertexOutput) -> @location(0) vec4<f32> {
+    return plasma_colour(in.position);
+}
+
+// Returns the height of the terrain at a point of the plane, from ridged_multifractal, and how
+// brightly the sun lights its slope there, by the relief, which exaggerates the slopes, and the
+// azimuth and elevation of the sun in degrees, in the parameters.
+fn terrain_height_and_shade(p: vec2<f32>) -> vec2<f32> {
+    let height = ridged_multifractal(p);
+    // The slope is taken across about a thousandth of the view, so that the shading keeps its
+    // detail at every zoom.
+    let delta = 1e-3 * length(u.transform[0].xy);
+    let rise = vec2(
+        ridged_multifractal(p + vec2(delta, 0.0)),
+        ridged_multifractal(p + vec2(0.0, delta))
+    ) - height;
+    let normal = normalize(vec3(-parameter(4u) * rise / delta, 1.0));
+    // The azimuth turns clockwise from north, which is up.
+    let azimuth = radians(parameter(5u));
+    let elevation = radians(parameter(6u));
+    let sun = vec3(cos(elevation) * vec2(sin(azimuth), cos(azimuth)), sin(elevation));
+    return vec2(height, max(dot(normal, sun), 0.0));
+}
+
+// The light which reaches slopes facing away from the sun, so that they are not black.
+const TERRAIN_AMBIENT = 0.2;
+
+// The colour of the terrain by the value returned by terrain_height_and_shade: the palette by
+// height, darkened by the shade.
+fn terrain_colour_of(height_and_shade: vec2<f32>) -> vec4<f32> {
+    let light = mix(TERRAIN_AMBIENT, 1.0, height_and_shade.y);
+    return post_process(gradient_colour(height_and_shade.x) * light);
+}
+
+fn terrain_colour(viewport_position: vec2<f32>) -> vec4<f32> {
+    let position = u.transform * vec3(viewport_position, 1.0);
+    return terrain_colour_of(terrain_height_and_shade(position.xy));
+}
+
+@fragment
+fn terrain(in: VertexOutput) -> @location(0) vec4<f32> {
+    return terrain_colour(in.position);
 }
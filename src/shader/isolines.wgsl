@@ -0,0 +1,46 @@
+// Draws lines over the rendered fractal where the escape time crosses selected iteration counts.
+
+// Must match MAX_ISOLINES in isolines.rs.
+struct Levels {
+    values: array<vec4<f32>, 2>,
+    count: u32,
+}
+
+@group(0) @binding(0) var iteration_data: texture_2d<f32>;
+@group(1) @binding(0) var<uniform> levels: Levels;
+
+const LINE_COLOUR = vec4(1.0, 1.0, 1.0, 0.8);
+// Stands in for the escape time of the interior of the set, which lies above every level.
+const INTERIOR = 1e30;
+
+// A triangle which covers the whole viewport.
+@vertex
+fn fullscreen(@builtin(vertex_index) index: u32) -> @builtin(position) vec4<f32> {
+    let uv = vec2(f32((index << 1u) & 2u), f32(index & 2u));
+    return vec4(uv.x * 2.0 - 1.0, 1.0 - uv.y * 2.0, 0.0, 1.0);
+}
+
+fn iterations_at(pixel: vec2<i32>) -> f32 {
+    let last = vec2<i32>(textureDimensions(iteration_data)) - 1;
+    let iterations = textureLoad(iteration_data, clamp(pixel, vec2(0), last), 0).r;
+    return select(iterations, INTERIOR, iterations < 0.0);
+}
+
+// Marks the pixels just below a level which have a neighbour at or above it, so that each line
+// is one pixel wide.
+@fragment
+fn isolines(@builtin(position) position: vec4<f32>) -> @location(0) vec4<f32> {
+    let pixel = vec2<i32>(position.xy);
+    let value = iterations_at(pixel);
+    let highest_neighbour = max(
+        max(iterations_at(pixel + vec2(1, 0)), iterations_at(pixel - vec2(1, 0))),
+        max(iterations_at(pixel + vec2(0, 1)), iterations_at(pixel - vec2(0, 1))),
+    );
+    for (var i = 0u; i < levels.count; i++) {
+        let level = levels.values[i / 4u][i % 4u];
+        if (value < level && highest_neighbour >= level) {
+            return LINE_COLOUR;
+        }
+    }
+    discard;
+}
@@ -0,0 +1,163 @@
+// Entry points which store the result of iterating each pixel instead of its colour, appended to
+// frag.wgsl so that they share its uniform and iteration functions. colouring.wgsl then maps the
+// stored values to colours.
+
+@group(1) @binding(0) var iteration_data: texture_storage_2d<rgba32float, write>;
+
+// Returns the position on the plane of the centre of the given pixel.
+fn pixel_position(id: vec2<u32>) -> vec3<f32> {
+    let uv = (vec2<f32>(id) + 0.5) / vec2<f32>(textureDimensions(iteration_data));
+    return u.transform * vec3(uv.x * 2.0 - 1.0, 1.0 - uv.y * 2.0, 1.0);
+}
+
+// How many times the stripes of the stripe average repeat around the origin.
+const STRIPE_DENSITY = 5.0;
+
+// Returns the modulus with which the orbit of the point under z^d + c escaped, the distance from
+// the point to the set estimated from the derivative of the orbit with respect to it, and the
+// stripe average of the orbit, or zeros if the orbit does not escape.
+//
+// The stripe average is the mean of 0.5 + 0.5 sin(STRIPE_DENSITY arg z) over the orbit but its
+// first point, interpolated between the means with and without the point which escaped by the
+// fraction of the smooth iteration count, so that it is continuous across the bands of whole
+// iterations.
+fn escape_quantities(point: vec2<f32>, d: f32) -> vec3<f32> {
+    let start = orbit_start(point, vec2(0.0, 0.0));
+    let c = start.c;
+    var z = start.z;
+    // The point is c in the parameter plane, which adds to the derivative at every step, and the
+    // start of the orbit in the dynamic plane.
+    let dynamic = u.plane == PLANE_DYNAMIC;
+    let dc = select(vec2(1.0, 0.0), vec2(0.0, 0.0), dynamic);
+    var dz = select(vec2(0.0, 0.0), vec2(1.0, 0.0), dynamic);
+    var stripes = 0.0;
+    var previous_stripes = 0.0;
+    var n = 0u;
+    while (n <= u.max_iterations) {
+        dz = d * mul(cpow(z, d - 1.0), dz) + dc;
+        z = cpow(vary(z), d) + c;
+        if (n > 0u) {
+            previous_stripes = stripes;
+            stripes += 0.5 + 0.5 * sin(STRIPE_DENSITY * atan2(z.y, z.x));
+        }
+        let modulus = length(z);
+        if (modulus > u.bailout) {
+            let average = stripes / max(f32(n), 1.0);
+            let previous_average = previous_stripes / max(f32(n) - 1.0, 1.0);
+            let fraction = log(log(modulus) / log(u.bailout)) / log(d);
+            let stripe_average = mix(average, previous_average, clamp(fraction, 0.0, 1.0));
+            return vec3(modulus, modulus * log(modulus) / length(dz), stripe_average);
+        }
+        n += 1u;
+    }
+    return vec3(0.0, 0.0, 0.0);
+}
+
+// The quantity stored besides the escape time, for fractals iterating z^d + c: that which the
+// debug view shows, or else the statistics of the layers of the colouring if they show them,
+// packed as described by STRIPE_LEVELS. Only computed while needed, since it iterates the orbit
+// again.
+fn extra_quantity(point: vec2<f32>, d: f32) -> f32 {
+    if (u.debug_view == DEBUG_ESCAPE_RADIUS) {
+        return escape_quantities(point, d).x;
+    }
+    if (u.debug_view == DEBUG_DISTANCE_ESTIMATE) {
+        return escape_quantities(point, d).y;
+    }
+    if (u.debug_view == DEBUG_OFF && layers_need_statistics()) {
+        let quantities = escape_quantities(point, d);
+        let pixel_size =
+            2.0 * length(u.transform[0].xy) / f32(textureDimensions(iteration_data).x);
+        // From within a pixel of the boundary to ten doublings of that away from it, like the
+        // debug view.
+        let distance = clamp(log2(quantities.y / pixel_size) / 10.0, 0.0, 0.999);
+        return round(quantities.z * (STRIPE_LEVELS - 1.0)) + distance;
+    }
+    return 0.0;
+}
+
+// Stores the orbit of each pixel as returned by mandelbrot_orbit.
+@compute @workgroup_size(8, 8)
+fn mandelbrot_iteration_data(@builtin(global_invocation_id) id: vec3<u32>) {
+    if (any(id.xy >= textureDimensions(iteration_data))) {
+        return;
+    }
+    let c = pixel_position(id.xy).xy;
+    textureStore(iteration_data, id.xy, vec4(mandelbrot_orbit(c), extra_quantity(c, 2.0)));
+}
+
+// Stores the escape time of each pixel like mandelbrot_iteration_data, so that the Multibrot set
+// is coloured by mandelbrot_colouring.
+@compute @workgroup_size(8, 8)
+fn multibrot_iteration_data(@builtin(global_invocation_id) id: vec3<u32>) {
+    if (any(id.xy >= textureDimensions(iteration_data))) {
+        return;
+    }
+    let c = pixel_position(id.xy).xy;
+    let iterations = multibrot_smooth_iterations(c);
+    let extra = extra_quantity(c, parameter(0u));
+    textureStore(iteration_data, id.xy, vec4(iterations, NO_TRAP_HIT, extra));
+}
+
+// Stores the root to which each pixel converges and the steps it took, as returned by
+// damped_newton_convergence.
+@compute @workgroup_size(8, 8)
+fn damped_newton_iteration_data(@builtin(global_invocation_id) id: vec3<u32>) {
+    if (any(id.xy >= textureDimensions(iteration_data))) {
+        return;
+    }
+    let convergence = damped_newton_convergence(pixel_position(id.xy));
+    textureStore(iteration_data, id.xy, vec4(convergence, 0.0, 0.0));
+}
+
+// Stores the root to which each pixel converges and the steps it took, as returned by
+// secant_convergence.
+@compute @workgroup_size(8, 8)
+fn secant_iteration_data(@builtin(global_invocation_id) id: vec3<u32>) {
+    if (any(id.xy >= textureDimensions(iteration_data))) {
+        return;
+    }
+    let convergence = secant_convergence(pixel_position(id.xy));
+    textureStore(iteration_data, id.xy, vec4(convergence, 0.0, 0.0));
+}
+
+// Stores the escape time of each pixel under the rational map like mandelbrot_iteration_data.
+// The quantities of the debug views are only defined for z^d + c, so none is stored.
+@compute @workgroup_size(8, 8)
+fn rational_iteration_data(@builtin(global_invocation_id) id: vec3<u32>) {
+    if (any(id.xy >= textureDimensions(iteration_data))) {
+        return;
+    }
+    let iterations = rational_smooth_iterations(pixel_position(id.xy).xy);
+    textureStore(iteration_data, id.xy, vec4(iterations, NO_TRAP_HIT, 0.0));
+}
+
+// Stores the root to which each pixel converges, as returned by newton_root.
+@compute @workgroup_size(8, 8)
+fn newton_iteration_data(@builtin(global_invocation_id) id: vec3<u32>) {
+    if (any(id.xy >= textureDimensions(iteration_data))) {
+        return;
+    }
+    textureStore(iteration_data, id.xy, vec4(newton_root(pixel_position(id.xy)), 0.0, 0.0, 0.0));
+}
+
+// Stores the value of the plasma at each pixel, as returned by plasma_value.
+@compute @workgroup_size(8, 8)
+fn plasma_iteration_data(@builtin(global_invocation_id) id: vec3<u32>) {
+    if (any(id.xy >= textureDimensions(iteration_data))) {
+        return;
+    }
+    let value = plasma_value(pixel_position(id.xy).xy);
+    textureStore(iteration_data, id.xy, vec4(value, 0.0, 0.0, 0.0));
+}
+
+// Stores the height and shade of the terrain at each pixel, as returned by
+// terrain_height_and_shade.
+@compute @workgroup_size(8, 8)
+fn terrain_iteration_data(@builtin(global_invocation_id) id: vec3<u32>) {
+    if (any(id.xy >= textureDimensions(iteration_data))) {
+        return;
+    }
+    let height_and_shade = terrain_height_and_shade(pixel_position(id.xy).xy);
+    textureStore(iteration_data, id.xy, vec4(height_and_shade, 0.0, 0.0));
+}
@@ -0,0 +1,13 @@
+// The entry points of iteration_data.wgsl which iterate in df64, appended to it and df64.wgsl.
+
+// Stores the orbit of each pixel as returned by mandelbrot_orbit_df64.
+@compute @workgroup_size(8, 8)
+fn mandelbrot_iteration_data_df64(@builtin(global_invocation_id) id: vec3<u32>) {
+    if (any(id.xy >= textureDimensions(iteration_data))) {
+        return;
+    }
+    let uv = (vec2<f32>(id.xy) + 0.5) / vec2<f32>(textureDimensions(iteration_data));
+    let c = df64_position(vec2(uv.x * 2.0 - 1.0, 1.0 - uv.y * 2.0));
+    let extra = extra_quantity(c.xz, 2.0);
+    textureStore(iteration_data, id.xy, vec4(mandelbrot_orbit_df64(c), extra));
+}
@@ -0,0 +1,55 @@
+// The entry points of iteration_data.wgsl which iterate by perturbation, appended to it and
+// floatexp.wgsl.
+
+// The orbit of the reference point from reference_orbit.rs, around which the pixels are iterated.
+@group(2) @binding(0) var<storage, read> reference_orbit: array<vec2<f32>>;
+
+// Like mandelbrot_orbit for the point at delta_c from the reference point, iterating the delta of
+// its orbit from the reference orbit. The variation does not apply.
+//
+// Where the orbit comes closer to zero than its delta, the delta has lost its precision relative
+// to the orbit, so the orbit is rebased onto the start of the reference orbit, which passes through
+// zero, after Zhuoran. The same happens once the reference orbit ends.
+fn mandelbrot_orbit_perturbed(delta_c: ComplexExp) -> vec3<f32> {
+    let trap_enabled = u.trap.w > 0.0;
+    var trap_hit = NO_TRAP_HIT;
+    let last = arrayLength(&reference_orbit) - 1u;
+    var delta = floatexp_zero();
+    var m = 0u;
+    var n = 0u;
+    while (n <= u.max_iterations) {
+        delta = floatexp_mandelbrot_delta_step(delta, reference_orbit[m], delta_c);
+        m += 1u;
+        let small_delta = floatexp_to_f32(delta);
+        let z = reference_orbit[m] + small_delta;
+
+        if (trap_enabled && trap_hit.x < 0.0) {
+            trap_hit = trap_coordinates(z);
+        }
+
+        let modulus_sq = dot(z, z);
+        if (modulus_sq > u.bailout * u.bailout) {
+            let log_modulus = 0.5 * log(modulus_sq);
+            return vec3(f32(n) + 1.0 - log2(log_modulus / log(u.bailout)), trap_hit);
+        }
+
+        if (m >= last || modulus_sq < dot(small_delta, small_delta)) {
+            delta = floatexp_from(z);
+            m = 0u;
+        }
+        n += 1u;
+    }
+    return vec3(-1.0, trap_hit);
+}
+
+// Stores the orbit of each pixel as returned by mandelbrot_orbit_perturbed.
+@compute @workgroup_size(8, 8)
+fn mandelbrot_iteration_data_perturbation(@builtin(global_invocation_id) id: vec3<u32>) {
+    if (any(id.xy >= textureDimensions(iteration_data))) {
+        return;
+    }
+    let uv = (vec2<f32>(id.xy) + 0.5) / vec2<f32>(textureDimensions(iteration_data));
+    let delta_c = floatexp_delta_c(vec2(uv.x * 2.0 - 1.0, 1.0 - uv.y * 2.0));
+    let extra = extra_quantity(pixel_position(id.xy).xy, 2.0);
+    textureStore(iteration_data, id.xy, vec4(mandelbrot_orbit_perturbed(delta_c), extra));
+}
@@ -0,0 +1,365 @@
+// The colouring of frag.wgsl in GLSL ES 3.0, as snippet.wgsl is in WGSL. snippet.rs places the
+// constants before this, and fractal_colour and Shadertoy's mainImage after it.
+
+float parameter(int offset) {
+    return PARAMS[offset];
+}
+
+// GLSL needs functions to be declared before they are called.
+vec2 mul(vec2 a, vec2 b) {
+    return vec2(a.x * b.x - a.y * b.y, a.x * b.y + a.y * b.x);
+}
+
+const uint ABS_RE = 1u;
+const uint ABS_IM = 2u;
+const uint CONJUGATE = 4u;
+const uint SPIN = 8u;
+
+vec2 vary(vec2 z) {
+    vec2 varied = z;
+    if ((VARIATION & ABS_RE) != 0u) {
+        varied.x = abs(varied.x);
+    }
+    if ((VARIATION & ABS_IM) != 0u) {
+        varied.y = abs(varied.y);
+    }
+    if ((VARIATION & CONJUGATE) != 0u) {
+        varied.y = -varied.y;
+    }
+    if ((VARIATION & SPIN) != 0u) {
+        varied = mul(varied, vec2(cos(TIME), sin(TIME)));
+    }
+    return varied;
+}
+
+vec3 srgb_to_linear(vec3 colour) {
+    vec3 low = colour / 12.92;
+    vec3 high = pow((colour + 0.055) / 1.055, vec3(2.4));
+    return mix(high, low, lessThanEqual(colour, vec3(0.04045)));
+}
+
+vec3 linear_to_srgb(vec3 colour) {
+    vec3 low = colour * 12.92;
+    vec3 high = 1.055 * pow(colour, vec3(1.0 / 2.4)) - 0.055;
+    return mix(high, low, lessThanEqual(colour, vec3(0.0031308)));
+}
+
+vec3 gradient_colour(float t) {
+    float position = clamp(t, 0.0, 1.0) * 7.0;
+    int stop = min(int(position), 6);
+    return mix(
+        srgb_to_linear(GRADIENT[stop]),
+        srgb_to_linear(GRADIENT[stop + 1]),
+        position - float(stop)
+    );
+}
+
+vec3 tonemap(vec3 colour) {
+    if (TONEMAPPER == 1u) {
+        return colour / (1.0 + dot(colour, vec3(0.2126, 0.7152, 0.0722)));
+    }
+    if (TONEMAPPER == 2u) {
+        return colour * (2.51 * colour + 0.03) / (colour * (2.43 * colour + 0.59) + 0.14);
+    }
+    return colour;
+}
+
+// Returns the colour encoded in sRGB, as a render target without sRGB encoding expects.
+vec3 post_process(vec3 colour) {
+    float exposure = POST_PROCESSING.x;
+    float gamma = POST_PROCESSING.y;
+    float contrast = POST_PROCESSING.z;
+    float saturation = POST_PROCESSING.w;
+    vec3 exposed = tonemap(colour * exp2(exposure));
+    vec3 encoded = linear_to_srgb(clamp(exposed, vec3(0.0), vec3(1.0)));
+    vec3 adjusted = (encoded - 0.5) * contrast + 0.5;
+    float luminance = dot(adjusted, vec3(0.2126, 0.7152, 0.0722));
+    adjusted = clamp(mix(vec3(luminance), adjusted, saturation), vec3(0.0), vec3(1.0));
+    return pow(adjusted, vec3(1.0 / gamma));
+}
+
+vec4 escape_colour(float iterations) {
+    float t = 0.0;
+    if (iterations > 0.0) {
+        t = fract(iterations / float(MAX_ITERATIONS) + PALETTE_OFFSET);
+    }
+    return vec4(post_process(gradient_colour(t)), 1.0);
+}
+
+const uint PLANE_DYNAMIC = 1u;
+
+struct OrbitStart {
+    vec2 z;
+    vec2 c;
+};
+
+// Where the orbit of a point starts and the constant added at each step: the point is the constant
+// in the parameter plane and where the orbit starts in the dynamic plane.
+OrbitStart orbit_start(vec2 point, vec2 critical_point) {
+    if (PLANE == PLANE_DYNAMIC) {
+        return OrbitStart(point, FIXED_C);
+    }
+    return OrbitStart(critical_point, point);
+}
+
+vec4 mandelbrot_colour(vec2 point) {
+    OrbitStart start = orbit_start(point, vec2(0.0));
+    vec2 c = start.c;
+    vec2 z = start.z;
+    vec2 z2 = z * z;
+    for (uint n = 0u; n <= MAX_ITERATIONS; n++) {
+        vec2 varied = vary(z);
+        vec2 squares = (VARIATION & SPIN) != 0u ? varied * varied : z2;
+        z = vec2(squares.x - squares.y + c.x, 2.0 * varied.x * varied.y + c.y);
+        z2 = z * z;
+        if (z2.x + z2.y > BAILOUT * BAILOUT) {
+            float log_modulus = 0.5 * log(z2.x + z2.y);
+            return escape_colour(float(n) + 1.0 - log2(log_modulus / log(BAILOUT)));
+        }
+    }
+    return escape_colour(-1.0);
+}
+
+vec2 cpow(vec2 z, float d) {
+    float modulus_sq = dot(z, z);
+    if (modulus_sq == 0.0) {
+        return vec2(0.0);
+    }
+    float angle = d * atan(z.y, z.x);
+    return pow(modulus_sq, 0.5 * d) * vec2(cos(angle), sin(angle));
+}
+
+vec4 multibrot_colour(vec2 point) {
+    float d = parameter(0);
+    OrbitStart start = orbit_start(point, vec2(0.0));
+    vec2 c = start.c;
+    vec2 z = start.z;
+    for (uint n = 0u; n <= MAX_ITERATIONS; n++) {
+        z = cpow(vary(z), d) + c;
+        float modulus_sq = dot(z, z);
+        if (modulus_sq > BAILOUT * BAILOUT) {
+            float log_modulus = 0.5 * log(modulus_sq);
+            return escape_colour(float(n) + 1.0 - log(log_modulus / log(BAILOUT)) / log(d));
+        }
+    }
+    return escape_colour(-1.0);
+}
+
+vec2 inv(vec2 z) {
+    return vec2(z.x, -z.y) / dot(z, z);
+}
+
+const float EPSILON = 1e-4;
+const vec2 ROOTS[3] = vec2[3](vec2(1.0, 0.0), vec2(-0.5, 0.866025), vec2(-0.5, -0.866025));
+
+vec4 newton_colour(vec2 c) {
+    vec2 z = c;
+    int iterations = int(parameter(0));
+    for (int i = 0; i < iterations; i++) {
+        z = vary(z);
+        vec2 z2 = mul(z, z);
+        z = z - mul(mul(z2, z) - vec2(1.0, 0.0), inv(3.0 * z2));
+    }
+    vec3 colour = vec3(0.0);
+    for (int i = 0; i < 3; i++) {
+        vec2 d = z - ROOTS[i];
+        if (dot(d, d) < EPSILON) {
+            colour = srgb_to_linear(ROOT_COLOURS[i]);
+        }
+    }
+    return vec4(post_process(colour), 1.0);
+}
+
+vec2 eval_poly(vec2 z, vec4 coeffs) {
+    vec2 f_z = vec2(coeffs[0], 0.0);
+    f_z = mul(f_z, z) + vec2(coeffs[1], 0.0);
+    f_z = mul(f_z, z) + vec2(coeffs[2], 0.0);
+    return mul(f_z, z) + vec2(coeffs[3], 0.0);
+}
+
+float poly_degree(vec4 coeffs) {
+    for (int i = 0; i < 3; i++) {
+        if (coeffs[i] != 0.0) {
+            return float(3 - i);
+        }
+    }
+    return 0.0;
+}
+
+// Iterates p(z) / q(z) + c with the coefficients of p and q, from the cube down to the constant,
+// in the first and second four parameters, in the parameter plane from the chosen critical point
+// of p / q.
+vec4 rational_colour(vec2 point) {
+    vec4 numerator = vec4(parameter(0), parameter(1), parameter(2), parameter(3));
+    vec4 denominator = vec4(parameter(4), parameter(5), parameter(6), parameter(7));
+    float degree = poly_degree(numerator) - poly_degree(denominator);
+    OrbitStart start = orbit_start(point, CRITICAL_POINT);
+    vec2 c = start.c;
+    vec2 z = start.z;
+    for (uint n = 0u; n <= MAX_ITERATIONS; n++) {
+        vec2 varied = vary(z);
+        z = mul(eval_poly(varied, numerator), inv(eval_poly(varied, denominator))) + c;
+        float modulus_sq = dot(z, z);
+        if (!(modulus_sq <= BAILOUT * BAILOUT)) {
+            if (degree < 2.0 || !(modulus_sq < 1e30)) {
+                return escape_colour(float(n) + 1.0);
+            }
+            float log_modulus = 0.5 * log(modulus_sq);
+            return escape_colour(float(n) + 1.0 - log(log_modulus / log(BAILOUT)) / log(degree));
+        }
+    }
+    return escape_colour(-1.0);
+}
+
+const vec4 CUBIC = vec4(1.0, 0.0, 0.0, -1.0);
+const vec4 CUBIC_DERIVATIVE = vec4(0.0, 3.0, 0.0, 0.0);
+const float CONVERGENCE_HALVING_STEPS = 4.0;
+
+float root_distance_sq(vec2 z) {
+    float nearest = dot(z - ROOTS[0], z - ROOTS[0]);
+    for (int i = 1; i < 3; i++) {
+        nearest = min(nearest, dot(z - ROOTS[i], z - ROOTS[i]));
+    }
+    return nearest;
+}
+
+// The steps taken to come within EPSILON of a root after step n, counted in part by how far the
+// distance fell beyond EPSILON.
+float convergence_steps(uint n, float previous_distance_sq, float distance_sq) {
+    float overshoot = log(EPSILON / distance_sq) / log(previous_distance_sq / distance_sq);
+    return float(n) + 1.0 - clamp(overshoot, 0.0, 1.0);
+}
+
+// The colour of the basin of the root near z, shaded darker the more steps it took to converge.
+vec4 basin_colour(vec2 z, float steps) {
+    vec3 colour = vec3(0.0);
+    for (int i = 0; i < 3; i++) {
+        vec2 d = z - ROOTS[i];
+        if (dot(d, d) < EPSILON) {
+            colour = srgb_to_linear(ROOT_COLOURS[i]) * exp2(-steps / CONVERGENCE_HALVING_STEPS);
+        }
+    }
+    return vec4(post_process(colour), 1.0);
+}
+
+// Newton's method for z^3 - 1 with each step shortened by the relaxation factor.
+vec4 damped_newton_colour(vec2 c) {
+    uint iterations = uint(parameter(0));
+    float relaxation = parameter(1);
+    vec2 z = c;
+    float previous_distance_sq = root_distance_sq(z);
+    for (uint n = 0u; n < iterations; n++) {
+        z = vary(z);
+        z -= relaxation * mul(eval_poly(z, CUBIC), inv(eval_poly(z, CUBIC_DERIVATIVE)));
+        float distance_sq = root_distance_sq(z);
+        if (distance_sq < EPSILON) {
+            return basin_colour(z, convergence_steps(n, previous_distance_sq, distance_sq));
+        }
+        previous_distance_sq = distance_sq;
+    }
+    return vec4(post_process(vec3(0.0)), 1.0);
+}
+
+// The secant method for z^3 - 1, starting from c and a second point offset along the real axis.
+vec4 secant_colour(vec2 c) {
+    uint iterations = uint(parameter(0));
+    vec2 previous = c + vec2(parameter(1), 0.0);
+    vec2 f_previous = eval_poly(previous, CUBIC);
+    vec2 z = c;
+    float previous_distance_sq = root_distance_sq(z);
+    for (uint n = 0u; n < iterations; n++) {
+        z = vary(z);
+        vec2 f_z = eval_poly(z, CUBIC);
+        vec2 next = z - mul(f_z, mul(z - previous, inv(f_z - f_previous)));
+        previous = z;
+        f_previous = f_z;
+        z = next;
+        float distance_sq = root_distance_sq(z);
+        if (distance_sq < EPSILON) {
+            return basin_colour(z, convergence_steps(n, previous_distance_sq, distance_sq));
+        }
+        previous_distance_sq = distance_sq;
+    }
+    return vec4(post_process(vec3(0.0)), 1.0);
+}
+
+vec2 lattice_gradient(ivec2 point, uint seed) {
+    uint h = (uint(point.x) * 0x8da6b343u) ^ (uint(point.y) * 0xd8163841u) ^ (seed * 0xcb1ab31fu);
+    h = (h ^ (h >> 16u)) * 0x7feb352du;
+    h = (h ^ (h >> 15u)) * 0x846ca68bu;
+    h = h ^ (h >> 16u);
+    float angle = float(h) * (6.2831853 / 4294967296.0);
+    return vec2(cos(angle), sin(angle));
+}
+
+float gradient_noise(vec2 p, uint seed) {
+    vec2 cell = floor(p);
+    vec2 f = p - cell;
+    ivec2 i = ivec2(cell);
+    vec2 w = f * f * f * (f * (f * 6.0 - 15.0) + 10.0);
+    float a = dot(lattice_gradient(i, seed), f);
+    float b = dot(lattice_gradient(i + ivec2(1, 0), seed), f - vec2(1.0, 0.0));
+    float c = dot(lattice_gradient(i + ivec2(0, 1), seed), f - vec2(0.0, 1.0));
+    float d = dot(lattice_gradient(i + ivec2(1, 1), seed), f - vec2(1.0, 1.0));
+    return 1.4142135 * mix(mix(a, b, w.x), mix(c, d, w.x), w.y);
+}
+
+uint octave_seed(uint octave) {
+    return uint(parameter(3)) * 16u + octave;
+}
+
+const mat2 OCTAVE_ROTATION = mat2(0.8, 0.6, -0.6, 0.8);
+
+float fbm(vec2 p) {
+    uint octaves = uint(parameter(0));
+    vec2 q = p;
+    float amplitude = 1.0;
+    float sum = 0.0;
+    float total = 0.0;
+    for (uint octave = 0u; octave < octaves; octave++) {
+        sum += amplitude * gradient_noise(q, octave_seed(octave));
+        total += amplitude;
+        q = OCTAVE_ROTATION * q * parameter(1);
+        amplitude *= parameter(2);
+    }
+    return sum / total;
+}
+
+float ridged_multifractal(vec2 p) {
+    uint octaves = uint(parameter(0));
+    vec2 q = p;
+    float amplitude = 1.0;
+    float weight = 1.0;
+    float sum = 0.0;
+    float total = 0.0;
+    for (uint octave = 0u; octave < octaves; octave++) {
+        float ridge = 1.0 - abs(gradient_noise(q, octave_seed(octave)));
+        float weighted = ridge * ridge * weight;
+        weight = clamp(2.0 * weighted, 0.0, 1.0);
+        sum += amplitude * weighted;
+        total += amplitude;
+        q = OCTAVE_ROTATION * q * parameter(1);
+        amplitude *= parameter(2);
+    }
+    return sum / total;
+}
+
+vec4 plasma_colour(vec2 c) {
+    float value = 0.5 + 0.5 * fbm(c);
+    return vec4(post_process(gradient_colour(fract(value + PALETTE_OFFSET))), 1.0);
+}
+
+vec4 terrain_colour(vec2 c) {
+    float height = ridged_multifractal(c);
+    float delta = 1e-3 * length(TRANSFORM[0].xy);
+    vec2 rise = vec2(
+        ridged_multifractal(c + vec2(delta, 0.0)),
+        ridged_multifractal(c + vec2(0.0, delta))
+    ) - height;
+    vec3 normal = normalize(vec3(-parameter(4) * rise / delta, 1.0));
+    float azimuth = radians(parameter(5));
+    float elevation = radians(parameter(6));
+    vec3 sun = vec3(cos(elevation) * vec2(sin(azimuth), cos(azimuth)), sin(elevation));
+    float light = mix(0.2, 1.0, max(dot(normal, sun), 0.0));
+    return vec4(post_process(gradient_colour(height) * light), 1.0);
+}
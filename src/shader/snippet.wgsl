@@ -0,0 +1,391 @@
+// The colouring of frag.wgsl with the settings of the uniform block baked into constants, which
+// snippet.rs places before this, and without the orbit trap, whose image a standalone shader does
+// not have. Call fractal_colour, which snippet.rs places after this, from any fragment shader.
+
+fn parameter(offset: u32) -> f32 {
+    // Constant arrays can only be indexed by constants.
+    var params = PARAMS;
+    return params[offset];
+}
+
+const ABS_RE = 1u;
+const ABS_IM = 2u;
+const CONJUGATE = 4u;
+const SPIN = 8u;
+
+fn vary(z: vec2<f32>) -> vec2<f32> {
+    var varied = z;
+    if ((VARIATION & ABS_RE) != 0u) {
+        varied.x = abs(varied.x);
+    }
+    if ((VARIATION & ABS_IM) != 0u) {
+        varied.y = abs(varied.y);
+    }
+    if ((VARIATION & CONJUGATE) != 0u) {
+        varied.y = -varied.y;
+    }
+    if ((VARIATION & SPIN) != 0u) {
+        varied = mul(varied, vec2(cos(TIME), sin(TIME)));
+    }
+    return varied;
+}
+
+fn srgb_to_linear(colour: vec3<f32>) -> vec3<f32> {
+    let low = colour / 12.92;
+    let high = pow((colour + 0.055) / 1.055, vec3(2.4));
+    return select(high, low, colour <= vec3(0.04045));
+}
+
+fn linear_to_srgb(colour: vec3<f32>) -> vec3<f32> {
+    let low = colour * 12.92;
+    let high = 1.055 * pow(colour, vec3(1.0 / 2.4)) - 0.055;
+    return select(high, low, colour <= vec3(0.0031308));
+}
+
+fn gradient_colour(t: f32) -> vec3<f32> {
+    var gradient = GRADIENT;
+    let position = clamp(t, 0.0, 1.0) * 7.0;
+    let stop = min(u32(position), 6u);
+    return mix(
+        srgb_to_linear(gradient[stop]),
+        srgb_to_linear(gradient[stop + 1u]),
+        position - f32(stop)
+    );
+}
+
+fn tonemap(colour: vec3<f32>) -> vec3<f32> {
+    if (TONEMAPPER == 1u) {
+        return colour / (1.0 + dot(colour, vec3(0.2126, 0.7152, 0.0722)));
+    }
+    if (TONEMAPPER == 2u) {
+        return colour * (2.51 * colour + 0.03) / (colour * (2.43 * colour + 0.59) + 0.14);
+    }
+    return colour;
+}
+
+// Returns the colour encoded in sRGB, as a render target without sRGB encoding expects.
+fn post_process(colour: vec3<f32>) -> vec3<f32> {
+    let exposure = POST_PROCESSING.x;
+    let gamma = POST_PROCESSING.y;
+    let contrast = POST_PROCESSING.z;
+    let saturation = POST_PROCESSING.w;
+    let exposed = tonemap(colour * exp2(exposure));
+    let encoded = linear_to_srgb(clamp(exposed, vec3(0.0), vec3(1.0)));
+    var adjusted = (encoded - 0.5) * contrast + 0.5;
+    let luminance = dot(adjusted, vec3(0.2126, 0.7152, 0.0722));
+    adjusted = clamp(mix(vec3(luminance), adjusted, saturation), vec3(0.0), vec3(1.0));
+    return pow(adjusted, vec3(1.0 / gamma));
+}
+
+fn escape_colour(iterations: f32) -> vec4<f32> {
+    var t = 0.0;
+    if (iterations > 0.0) {
+        t = fract(iterations / f32(MAX_ITERATIONS) + PALETTE_OFFSET);
+    }
+    return vec4(post_process(gradient_colour(t)), 1.0);
+}
+
+const PLANE_DYNAMIC = 1u;
+
+struct OrbitStart {
+    z: vec2<f32>,
+    c: vec2<f32>,
+}
+
+// Where the orbit of a point starts and the constant added at each step: the point is the constant
+// in the parameter plane and where the orbit starts in the dynamic plane.
+fn orbit_start(point: vec2<f32>, critical_point: vec2<f32>) -> OrbitStart {
+    if (PLANE == PLANE_DYNAMIC) {
+        return OrbitStart(point, FIXED_C);
+    }
+    return OrbitStart(critical_point, point);
+}
+
+fn mandelbrot_colour(point: vec2<f32>) -> vec4<f32> {
+    let start = orbit_start(point, vec2(0.0, 0.0));
+    let c = start.c;
+    var z = start.z;
+    var z2 = z * z;
+    var n = 0u;
+    while (n <= MAX_ITERATIONS) {
+        let varied = vary(z);
+        var squares = z2;
+        if ((VARIATION & SPIN) != 0u) {
+            squares = varied * varied;
+        }
+        z = vec2(squares.x - squares.y + c.x, 2.0 * varied.x * varied.y + c.y);
+        z2 = vec2(z.x * z.x, z.y * z.y);
+        if (z2.x + z2.y > BAILOUT * BAILOUT) {
+            let log_modulus = 0.5 * log(z2.x + z2.y);
+            return escape_colour(f32(n) + 1.0 - log2(log_modulus / log(BAILOUT)));
+        }
+        n += 1u;
+    }
+    return escape_colour(-1.0);
+}
+
+fn cpow(z: vec2<f32>, d: f32) -> vec2<f32> {
+    let modulus_sq = dot(z, z);
+    if (modulus_sq == 0.0) {
+        return vec2(0.0, 0.0);
+    }
+    let angle = d * atan2(z.y, z.x);
+    return pow(modulus_sq, 0.5 * d) * vec2(cos(angle), sin(angle));
+}
+
+fn multibrot_colour(point: vec2<f32>) -> vec4<f32> {
+    let d = parameter(0u);
+    let start = orbit_start(point, vec2(0.0, 0.0));
+    let c = start.c;
+    var z = start.z;
+    var n = 0u;
+    while (n <= MAX_ITERATIONS) {
+        z = cpow(vary(z), d) + c;
+        let modulus_sq = dot(z, z);
+        if (modulus_sq > BAILOUT * BAILOUT) {
+            let log_modulus = 0.5 * log(modulus_sq);
+            return escape_colour(f32(n) + 1.0 - log(log_modulus / log(BAILOUT)) / log(d));
+        }
+        n += 1u;
+    }
+    return escape_colour(-1.0);
+}
+
+fn mul(a: vec2<f32>, b: vec2<f32>) -> vec2<f32> {
+    return vec2(a.x * b.x - a.y * b.y, a.x * b.y + a.y * b.x);
+}
+
+fn inv(z: vec2<f32>) -> vec2<f32> {
+    return vec2(z.x, -z.y) / (z.x * z.x + z.y * z.y);
+}
+
+const EPSILON = 1e-4;
+const ROOTS = array<vec2<f32>, 3>(
+    vec2<f32>(1.0, 0.0),
+    vec2<f32>(-0.5, 0.866025),
+    vec2<f32>(-0.5, -0.866025),
+);
+
+// Newton's method for z^3 - 1, coloured by the root to which it converges.
+fn newton_colour(c: vec2<f32>) -> vec4<f32> {
+    var z = c;
+    let iterations = i32(parameter(0u));
+    for (var i: i32 = 0; i < iterations; i += 1) {
+        z = vary(z);
+        let z2 = mul(z, z);
+        z = z - mul(mul(z2, z) - vec2(1.0, 0.0), inv(3.0 * z2));
+    }
+    var roots = ROOTS;
+    var root_colours = ROOT_COLOURS;
+    var colour = vec3(0.0);
+    for (var i = 0u; i < 3u; i++) {
+        let d = z - roots[i];
+        if (dot(d, d) < EPSILON) {
+            colour = srgb_to_linear(root_colours[i]);
+        }
+    }
+    return vec4(post_process(colour), 1.0);
+}
+
+fn eval_poly(z: vec2<f32>, coeffs: vec4<f32>) -> vec2<f32> {
+    var f_z = vec2(coeffs[0], 0.0);
+    f_z = mul(f_z, z) + vec2(coeffs[1], 0.0);
+    f_z = mul(f_z, z) + vec2(coeffs[2], 0.0);
+    return mul(f_z, z) + vec2(coeffs[3], 0.0);
+}
+
+fn poly_degree(coeffs: vec4<f32>) -> f32 {
+    for (var i = 0u; i < 3u; i++) {
+        if (coeffs[i] != 0.0) {
+            return f32(3u - i);
+        }
+    }
+    return 0.0;
+}
+
+// Iterates p(z) / q(z) + c with the coefficients of p and q, from the cube down to the constant,
+// in the first and second four parameters, in the parameter plane from the chosen critical point
+// of p / q.
+fn rational_colour(point: vec2<f32>) -> vec4<f32> {
+    let numerator = vec4(parameter(0u), parameter(1u), parameter(2u), parameter(3u));
+    let denominator = vec4(parameter(4u), parameter(5u), parameter(6u), parameter(7u));
+    let degree = poly_degree(numerator) - poly_degree(denominator);
+    let start = orbit_start(point, CRITICAL_POINT);
+    let c = start.c;
+    var z = start.z;
+    var n = 0u;
+    while (n <= MAX_ITERATIONS) {
+        let varied = vary(z);
+        z = mul(eval_poly(varied, numerator), inv(eval_poly(varied, denominator))) + c;
+        let modulus_sq = dot(z, z);
+        if (!(modulus_sq <= BAILOUT * BAILOUT)) {
+            if (degree < 2.0 || !(modulus_sq < 1e30)) {
+                return escape_colour(f32(n) + 1.0);
+            }
+            let log_modulus = 0.5 * log(modulus_sq);
+            return escape_colour(f32(n) + 1.0 - log(log_modulus / log(BAILOUT)) / log(degree));
+        }
+        n += 1u;
+    }
+    return escape_colour(-1.0);
+}
+
+const CUBIC = vec4(1.0, 0.0, 0.0, -1.0);
+const CUBIC_DERIVATIVE = vec4(0.0, 3.0, 0.0, 0.0);
+const CONVERGENCE_HALVING_STEPS = 4.0;
+
+fn root_distance_sq(z: vec2<f32>) -> f32 {
+    var roots = ROOTS;
+    var nearest = dot(z - roots[0], z - roots[0]);
+    for (var i = 1u; i < 3u; i++) {
+        nearest = min(nearest, dot(z - roots[i], z - roots[i]));
+    }
+    return nearest;
+}
+
+// The steps taken to come within EPSILON of a root after step n, counted in part by how far the
+// distance fell beyond EPSILON.
+fn convergence_steps(n: u32, previous_distance_sq: f32, distance_sq: f32) -> f32 {
+    let overshoot = log(EPSILON / distance_sq) / log(previous_distance_sq / distance_sq);
+    return f32(n) + 1.0 - clamp(overshoot, 0.0, 1.0);
+}
+
+// The colour of the basin of the root near z, shaded darker the more steps it took to converge.
+fn basin_colour(z: vec2<f32>, steps: f32) -> vec4<f32> {
+    var roots = ROOTS;
+    var root_colours = ROOT_COLOURS;
+    var colour = vec3(0.0);
+    for (var i = 0u; i < 3u; i++) {
+        let d = z - roots[i];
+        if (dot(d, d) < EPSILON) {
+            colour = srgb_to_linear(root_colours[i]) * exp2(-steps / CONVERGENCE_HALVING_STEPS);
+        }
+    }
+    return vec4(post_process(colour), 1.0);
+}
+
+// Newton's method for z^3 - 1 with each step shortened by the relaxation factor.
+fn damped_newton_colour(c: vec2<f32>) -> vec4<f32> {
+    let iterations = u32(parameter(0u));
+    let relaxation = parameter(1u);
+    var z = c;
+    var previous_distance_sq = root_distance_sq(z);
+    for (var n = 0u; n < iterations; n++) {
+        z = vary(z);
+        z -= relaxation * mul(eval_poly(z, CUBIC), inv(eval_poly(z, CUBIC_DERIVATIVE)));
+        let distance_sq = root_distance_sq(z);
+        if (distance_sq < EPSILON) {
+            return basin_colour(z, convergence_steps(n, previous_distance_sq, distance_sq));
+        }
+        previous_distance_sq = distance_sq;
+    }
+    return vec4(post_process(vec3(0.0)), 1.0);
+}
+
+// The secant method for z^3 - 1, starting from c and a second point offset along the real axis.
+fn secant_colour(c: vec2<f32>) -> vec4<f32> {
+    let iterations = u32(parameter(0u));
+    var previous = c + vec2(parameter(1u), 0.0);
+    var f_previous = eval_poly(previous, CUBIC);
+    var z = c;
+    var previous_distance_sq = root_distance_sq(z);
+    for (var n = 0u; n < iterations; n++) {
+        z = vary(z);
+        let f_z = eval_poly(z, CUBIC);
+        let next = z - mul(f_z, mul(z - previous, inv(f_z - f_previous)));
+        previous = z;
+        f_previous = f_z;
+        z = next;
+        let distance_sq = root_distance_sq(z);
+        if (distance_sq < EPSILON) {
+            return basin_colour(z, convergence_steps(n, previous_distance_sq, distance_sq));
+        }
+        previous_distance_sq = distance_sq;
+    }
+    return vec4(post_process(vec3(0.0)), 1.0);
+}
+
+fn lattice_gradient(point: vec2<i32>, seed: u32) -> vec2<f32> {
+    var h = (bitcast<u32>(point.x) * 0x8da6b343u) ^ (bitcast<u32>(point.y) * 0xd8163841u)
+        ^ (seed * 0xcb1ab31fu);
+    h = (h ^ (h >> 16u)) * 0x7feb352du;
+    h = (h ^ (h >> 15u)) * 0x846ca68bu;
+    h = h ^ (h >> 16u);
+    let angle = f32(h) * (6.2831853 / 4294967296.0);
+    return vec2(cos(angle), sin(angle));
+}
+
+fn gradient_noise(p: vec2<f32>, seed: u32) -> f32 {
+    let cell = floor(p);
+    let f = p - cell;
+    let i = vec2<i32>(cell);
+    let w = f * f * f * (f * (f * 6.0 - 15.0) + 10.0);
+    let a = dot(lattice_gradient(i, seed), f);
+    let b = dot(lattice_gradient(i + vec2(1, 0), seed), f - vec2(1.0, 0.0));
+    let c = dot(lattice_gradient(i + vec2(0, 1), seed), f - vec2(0.0, 1.0));
+    let d = dot(lattice_gradient(i + vec2(1, 1), seed), f - vec2(1.0, 1.0));
+    return 1.4142135 * mix(mix(a, b, w.x), mix(c, d, w.x), w.y);
+}
+
+fn octave_seed(octave: u32) -> u32 {
+    return u32(parameter(3u)) * 16u + octave;
+}
+
+const OCTAVE_ROTATION = mat2x2<f32>(0.8, 0.6, -0.6, 0.8);
+
+// Fractional Brownian motion of gradient noise, in [-1, 1].
+fn fbm(p: vec2<f32>) -> f32 {
+    let octaves = u32(parameter(0u));
+    var q = p;
+    var amplitude = 1.0;
+    var sum = 0.0;
+    var total = 0.0;
+    for (var octave = 0u; octave < octaves; octave++) {
+        sum += amplitude * gradient_noise(q, octave_seed(octave));
+        total += amplitude;
+        q = OCTAVE_ROTATION * q * parameter(1u);
+        amplitude *= parameter(2u);
+    }
+    return sum / total;
+}
+
+// Musgrave's ridged multifractal of gradient noise, in [0, 1].
+fn ridged_multifractal(p: vec2<f32>) -> f32 {
+    let octaves = u32(parameter(0u));
+    var q = p;
+    var amplitude = 1.0;
+    var weight = 1.0;
+    var sum = 0.0;
+    var total = 0.0;
+    for (var octave = 0u; octave < octaves; octave++) {
+        let ridge = 1.0 - abs(gradient_noise(q, octave_seed(octave)));
+        let weighted = ridge * ridge * weight;
+        weight = clamp(2.0 * weighted, 0.0, 1.0);
+        sum += amplitude * weighted;
+        total += amplitude;
+        q = OCTAVE_ROTATION * q * parameter(1u);
+        amplitude *= parameter(2u);
+    }
+    return sum / total;
+}
+
+fn plasma_colour(c: vec2<f32>) -> vec4<f32> {
+    let value = 0.5 + 0.5 * fbm(c);
+    return vec4(post_process(gradient_colour(fract(value + PALETTE_OFFSET))), 1.0);
+}
+
+// The ridged multifractal as a heightmap tinted by the palette and hillshaded.
+fn terrain_colour(c: vec2<f32>) -> vec4<f32> {
+    let height = ridged_multifractal(c);
+    let delta = 1e-3 * length(TRANSFORM[0].xy);
+    let rise = vec2(
+        ridged_multifractal(c + vec2(delta, 0.0)),
+        ridged_multifractal(c + vec2(0.0, delta))
+    ) - height;
+    let normal = normalize(vec3(-parameter(4u) * rise / delta, 1.0));
+    let azimuth = radians(parameter(5u));
+    let elevation = radians(parameter(6u));
+    let sun = vec3(cos(elevation) * vec2(sin(azimuth), cos(azimuth)), sin(elevation));
+    let light = mix(0.2, 1.0, max(dot(normal, sun), 0.0));
+    return vec4(post_process(gradient_colour(height) * light), 1.0);
+}
@@ -0,0 +1,176 @@
+use iced_wgpu::wgpu;
+#[cfg(test)]
+use std::fmt::Display;
+
+/// A WGSL shader which is concatenated from several files, since WGSL has no includes.
+///
+/// wgpu reports errors by their position in the concatenation, which matches none of the files,
+/// so the tests parse and validate each shader with naga on the CPU, mapping any error back to the
+/// file in which it lies. The explorer itself leaves validation to wgpu rather than parse every
+/// shader twice.
+pub(super) struct ComposedShader {
+    pub(super) label: &'static str,
+    /// The name and contents of each file, in the order in which they are concatenated.
+    pub(super) parts: &'static [(&'static str, &'static str)],
+}
+
+impl ComposedShader {
+    pub(super) fn source(&self) -> String {
+        self.parts.iter().map(|(_, contents)| *contents).collect()
+    }
+
+    /// Parses and validates the shader as wgpu would, without needing a device.
+    #[cfg(test)]
+    pub(super) fn validate(&self) -> Result<(), ShaderDiagnostic> {
+        let source = self.source();
+        let module = naga::front::wgsl::parse_str(&source).map_err(|error| ShaderDiagnostic {
+            location: error
+                .location(&source)
+                .map(|location| self.locate(location.offset as usize)),
+            message: error.message().to_string(),
+        })?;
+        naga::valid::Validator::new(
+            naga::valid::ValidationFlags::all(),
+            naga::valid::Capabilities::default(),
+        )
+        .validate(&module)
+        .map_err(|error| {
+            // The outermost error only names the function or entry point which is invalid.
+            let mut message = error.as_inner().to_string();
+            let mut source_error = std::error::Error::source(error.as_inner());
+            while let Some(inner) = source_error {
+                message += &format!(": {inner}");
+                source_error = inner.source();
+            }
+            // The spans narrow down from the function to the offending expression.
+            ShaderDiagnostic {
+                location: error
+                    .spans()
+                    .last()
+                    .map(|(span, _)| self.locate(span.location(&source).offset as usize)),
+                message,
+            }
+        })?;
+        Ok(())
+    }
+
+    pub(super) fn create_module(&self, device: &wgpu::Device) -> wgpu::ShaderModule {
+        device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some(self.label),
+            source: wgpu::ShaderSource::Wgsl(self.source().into()),
+        })
+    }
+
+    /// Finds the file, line and column at the given byte offset into the concatenation.
+    #[cfg(test)]
+    fn locate(&self, mut offset: usize) -> SourceLocation {
+        for (file, contents) in self.parts {
+            if offset < contents.len() {
+                let prefix = &contents[..offset];
+                let line_start = prefix.rfind('\n').map_or(0, |position| position + 1);
+                return SourceLocation {
+                    file,
+                    line: prefix.matches('\n').count() + 1,
+                    column: prefix[line_start..].chars().count() + 1,
+                };
+            }
+            offset -= contents.len();
+        }
+        // An error at the very end, such as an unclosed brace, lies past the last file.
+        let (file, contents) = self.parts.last().copied().unwrap_or(("", ""));
+        SourceLocation {
+            file,
+            line: contents.matches('\n').count() + 1,
+            column: 1,
+        }
+    }
+}
+
+/// An error in a [`ComposedShader`], with its location in the file in which it lies.
+#[cfg(test)]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(super) struct ShaderDiagnostic {
+    pub(super) location: Option<SourceLocation>,
+    pub(super) message: String,
+}
+
+#[cfg(test)]
+impl Display for ShaderDiagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.location {
+            Some(location) => write!(f, "{location}: {}", self.message),
+            None => write!(f, "{}", self.message),
+        }
+    }
+}
+
+/// A position in one of the files of a [`ComposedShader`], with 1-based line and column.
+#[cfg(test)]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(super) struct SourceLocation {
+    pub(super) file: &'static str,
+    pub(super) line: usize,
+    pub(super) column: usize,
+}
+
+#[cfg(test)]
+impl Display for SourceLocation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}:{}", self.file, self.line, self.column)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ComposedShader;
+    use crate::pipeline_compiler::COMPOSED_SHADERS;
+    use googletest::prelude::*;
+
+    #[test]
+    fn shaders_of_explorer_are_valid() -> Result<()> {
+        let errors = COMPOSED_SHADERS
+            .iter()
+            .filter_map(|shader| {
+                shader
+                    .validate()
+                    .err()
+                    .map(|diagnostic| format!("{}: {diagnostic}", shader.label))
+            })
+            .collect::<Vec<_>>();
+
+        verify_that!(errors, empty())
+    }
+
+    #[test]
+    fn parse_error_is_located_in_its_file() -> Result<()> {
+        let shader = ComposedShader {
+            label: "test",
+            parts: &[
+                ("first.wgsl", "fn first() -> f32 {\n    return 1.0;\n}\n"),
+                (
+                    "second.wgsl",
+                    "fn second() -> f32 {\n    return 1.0 +;\n}\n",
+                ),
+            ],
+        };
+
+        let diagnostic = shader.validate().unwrap_err();
+
+        verify_that!(diagnostic.to_string(), starts_with("second.wgsl:2:17: "))
+    }
+
+    #[test]
+    fn validation_error_is_located_in_its_file() -> Result<()> {
+        let shader = ComposedShader {
+            label: "test",
+            parts: &[
+                ("first.wgsl", "fn first() -> f32 {\n    return 1.0;\n}\n"),
+                ("second.wgsl", "fn second() -> f32 {\n    return 1u;\n}\n"),
+            ],
+        };
+
+        let diagnostic = shader.validate().unwrap_err();
+
+        verify_that!(diagnostic.to_string(), starts_with("second.wgsl:2:"))
+    }
+}
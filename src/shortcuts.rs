@@ -0,0 +1,388 @@
+use iced::keyboard::{key::Named, Key, Modifiers};
+use std::fmt::Display;
+
+/// What a key press asks the controls to do.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(super) enum KeyAction {
+    FocusNext,
+    FocusPrevious,
+    /// Pans the view, or adjusts the focused control, where right and up increase its value.
+    Move(Direction),
+    /// Presses the focused button or toggles the focused checkbox.
+    Activate,
+    /// Moves the focus off the controls and closes the help overlay.
+    ClearFocus,
+    ZoomIn,
+    ZoomOut,
+    ToggleHelp,
+    /// Moves to the next or previous stop of the presentation.
+    NextStop,
+    PreviousStop,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(super) enum Direction {
+    Left,
+    Right,
+    Up,
+    Down,
+}
+
+/// The keys and what they do, as listed in the help overlay, with the actions which the
+/// configuration file may bind to other keys instead.
+const SHORTCUTS: &[(&str, &str, &[KeyAction])] = &[
+    ("Tab / Shift+Tab", "Move focus between controls", &[]),
+    (
+        "Arrow keys",
+        "Pan the view, or adjust the focused control",
+        &[
+            KeyAction::Move(Direction::Left),
+            KeyAction::Move(Direction::Right),
+            KeyAction::Move(Direction::Up),
+            KeyAction::Move(Direction::Down),
+        ],
+    ),
+    ("Enter / Space", "Activate the focused control", &[]),
+    (
+        "+ / -",
+        "Zoom in or out",
+        &[KeyAction::ZoomIn, KeyAction::ZoomOut],
+    ),
+    ("Shift+drag", "Show statistics of a region", &[]),
+    ("Right click", "Move the locked zoom target", &[]),
+    (
+        "Page Down / Page Up",
+        "Next or previous stop of a presentation",
+        &[KeyAction::NextStop, KeyAction::PreviousStop],
+    ),
+    (
+        "Esc",
+        "Clear the focus, close this help or end a presentation",
+        &[KeyAction::ClearFocus],
+    ),
+    ("?", "Show or hide this help", &[KeyAction::ToggleHelp]),
+];
+
+/// An action which the configuration file may bind to other keys.
+struct Bindable {
+    /// The name of the action in the `[keys]` table of the configuration file.
+    name: &'static str,
+    action: KeyAction,
+    /// The keys bound to the action by default, as the help overlay lists them on a line of
+    /// their own.
+    default_keys: &'static str,
+    /// What the help overlay lists for the action while it is bound to other keys.
+    description: &'static str,
+}
+
+const BINDABLE: &[Bindable] = &[
+    Bindable {
+        name: "pan_left",
+        default_keys: "Left",
+        action: KeyAction::Move(Direction::Left),
+        description: "Pan left, or decrease the focused control",
+    },
+    Bindable {
+        name: "pan_right",
+        default_keys: "Right",
+        action: KeyAction::Move(Direction::Right),
+        description: "Pan right, or increase the focused control",
+    },
+    Bindable {
+        name: "pan_up",
+        default_keys: "Up",
+        action: KeyAction::Move(Direction::Up),
+        description: "Pan up, or increase the focused control",
+    },
+    Bindable {
+        name: "pan_down",
+        default_keys: "Down",
+        action: KeyAction::Move(Direction::Down),
+        description: "Pan down, or decrease the focused control",
+    },
+    Bindable {
+        name: "zoom_in",
+        default_keys: "+",
+        action: KeyAction::ZoomIn,
+        description: "Zoom in",
+    },
+    Bindable {
+        name: "zoom_out",
+        default_keys: "-",
+        action: KeyAction::ZoomOut,
+        description: "Zoom out",
+    },
+    Bindable {
+        name: "next_stop",
+        default_keys: "Page Down",
+        action: KeyAction::NextStop,
+        description: "Next stop of a presentation",
+    },
+    Bindable {
+        name: "previous_stop",
+        default_keys: "Page Up",
+        action: KeyAction::PreviousStop,
+        description: "Previous stop of a presentation",
+    },
+    Bindable {
+        name: "clear_focus",
+        default_keys: "Esc",
+        action: KeyAction::ClearFocus,
+        description: "Clear the focus, close this help or end a presentation",
+    },
+    Bindable {
+        name: "toggle_help",
+        default_keys: "?",
+        action: KeyAction::ToggleHelp,
+        description: "Show or hide this help",
+    },
+];
+
+/// The keys which the configuration file names by words rather than by the character they type.
+const NAMED_KEYS: &[(&str, Named)] = &[
+    ("ArrowLeft", Named::ArrowLeft),
+    ("ArrowRight", Named::ArrowRight),
+    ("ArrowUp", Named::ArrowUp),
+    ("ArrowDown", Named::ArrowDown),
+    ("PageUp", Named::PageUp),
+    ("PageDown", Named::PageDown),
+    ("Home", Named::Home),
+    ("End", Named::End),
+    ("Insert", Named::Insert),
+    ("Delete", Named::Delete),
+    ("Backspace", Named::Backspace),
+    ("Enter", Named::Enter),
+    ("Space", Named::Space),
+    ("Tab", Named::Tab),
+    ("Escape", Named::Escape),
+    ("F1", Named::F1),
+    ("F2", Named::F2),
+    ("F3", Named::F3),
+    ("F4", Named::F4),
+    ("F5", Named::F5),
+    ("F6", Named::F6),
+    ("F7", Named::F7),
+    ("F8", Named::F8),
+    ("F9", Named::F9),
+    ("F10", Named::F10),
+    ("F11", Named::F11),
+    ("F12", Named::F12),
+];
+
+/// Returns the action which the configuration file calls by the given name, if any.
+pub(super) fn bindable_action(name: &str) -> Option<KeyAction> {
+    BINDABLE
+        .iter()
+        .find(|bindable| bindable.name == name)
+        .map(|bindable| bindable.action)
+}
+
+/// A key to which the configuration file binds an action, whatever modifiers are held with it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(super) enum BoundKey {
+    Named(Named),
+    /// A key by the character it types, such as `"j"`, or `"J"` with shift.
+    Character(String),
+}
+
+impl BoundKey {
+    /// Parses a key as the configuration file writes it: one of [`NAMED_KEYS`], such as
+    /// `"PageDown"`, or a single character.
+    pub(super) fn parse(text: &str) -> Option<Self> {
+        if let Some((_, named)) = NAMED_KEYS.iter().find(|(name, _)| *name == text) {
+            return Some(Self::Named(*named));
+        }
+        let mut characters = text.chars();
+        match (characters.next(), characters.next()) {
+            (Some(character), None) if !character.is_whitespace() => {
+                Some(Self::Character(text.into()))
+            }
+            _ => None,
+        }
+    }
+
+    fn matches(&self, key: &Key<&str>) -> bool {
+        match (self, key) {
+            (Self::Named(bound), Key::Named(pressed)) => bound == pressed,
+            (Self::Character(bound), Key::Character(pressed)) => bound == pressed,
+            _ => false,
+        }
+    }
+}
+
+impl Display for BoundKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BoundKey::Named(named) => {
+                let name = NAMED_KEYS
+                    .iter()
+                    .find(|(_, key)| key == named)
+                    .map_or("?", |(name, _)| name);
+                write!(f, "{name}")
+            }
+            BoundKey::Character(character) => write!(f, "{character}"),
+        }
+    }
+}
+
+/// The keys bound to actions: those of [`action_for`], except for actions which the configuration
+/// file binds to other keys instead. Their default keys then do nothing.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub(super) struct Keymap {
+    bindings: Vec<(KeyAction, Vec<BoundKey>)>,
+}
+
+impl Keymap {
+    /// Binds the action to the given keys instead of its default ones, or to none at all.
+    pub(super) fn bind(&mut self, action: KeyAction, keys: Vec<BoundKey>) {
+        self.bindings.retain(|(bound, _)| *bound != action);
+        self.bindings.push((action, keys));
+    }
+
+    /// Returns the action bound to the key, if any.
+    pub(super) fn action_for(&self, key: Key<&str>, modifiers: Modifiers) -> Option<KeyAction> {
+        let rebound = self
+            .bindings
+            .iter()
+            .find(|(_, keys)| keys.iter().any(|bound| bound.matches(&key)));
+        match rebound {
+            Some((action, _)) => Some(*action),
+            None => action_for(key, modifiers).filter(|action| !self.is_rebound(*action)),
+        }
+    }
+
+    /// The keys and what they do, as listed in the help overlay. An action bound to other keys
+    /// gets a line of its own, as do the others which share a line with it by default.
+    pub(super) fn help(&self) -> Vec<(String, &'static str)> {
+        let mut lines = vec![];
+        for (keys, description, actions) in SHORTCUTS {
+            if !actions.iter().any(|action| self.is_rebound(*action)) {
+                lines.push((keys.to_string(), *description));
+                continue;
+            }
+            for bindable in BINDABLE
+                .iter()
+                .filter(|bindable| actions.contains(&bindable.action))
+            {
+                let keys = match self
+                    .bindings
+                    .iter()
+                    .find(|(action, _)| *action == bindable.action)
+                {
+                    Some((_, keys)) if keys.is_empty() => "(none)".to_string(),
+                    Some((_, keys)) => keys
+                        .iter()
+                        .map(ToString::to_string)
+                        .collect::<Vec<_>>()
+                        .join(" / "),
+                    None => bindable.default_keys.to_string(),
+                };
+                lines.push((keys, bindable.description));
+            }
+        }
+        lines
+    }
+
+    fn is_rebound(&self, action: KeyAction) -> bool {
+        self.bindings.iter().any(|(bound, _)| *bound == action)
+    }
+}
+
+/// Returns the action bound to the key by default, if any.
+fn action_for(key: Key<&str>, modifiers: Modifiers) -> Option<KeyAction> {
+    match key {
+        Key::Named(Named::Tab) if modifiers.shift() => Some(KeyAction::FocusPrevious),
+        Key::Named(Named::Tab) => Some(KeyAction::FocusNext),
+        Key::Named(Named::ArrowLeft) => Some(KeyAction::Move(Direction::Left)),
+        Key::Named(Named::ArrowRight) => Some(KeyAction::Move(Direction::Right)),
+        Key::Named(Named::ArrowUp) => Some(KeyAction::Move(Direction::Up)),
+        Key::Named(Named::ArrowDown) => Some(KeyAction::Move(Direction::Down)),
+        Key::Named(Named::Enter | Named::Space) => Some(KeyAction::Activate),
+        Key::Named(Named::Escape) => Some(KeyAction::ClearFocus),
+        // Presentation clickers send these.
+        Key::Named(Named::PageDown) => Some(KeyAction::NextStop),
+        Key::Named(Named::PageUp) => Some(KeyAction::PreviousStop),
+        // "=" shares a key with "+" on many layouts, so that zooming in does not need shift.
+        Key::Character("+" | "=") => Some(KeyAction::ZoomIn),
+        Key::Character("-") => Some(KeyAction::ZoomOut),
+        Key::Character("?") => Some(KeyAction::ToggleHelp),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{action_for, BoundKey, Direction, KeyAction, Keymap};
+    use googletest::prelude::*;
+    use iced::keyboard::{key::Named, Key, Modifiers};
+
+    #[test]
+    fn shift_tab_moves_focus_backwards() -> Result<()> {
+        verify_that!(
+            (
+                action_for(Key::Named(Named::Tab), Modifiers::empty()),
+                action_for(Key::Named(Named::Tab), Modifiers::SHIFT)
+            ),
+            (
+                some(eq(KeyAction::FocusNext)),
+                some(eq(KeyAction::FocusPrevious))
+            )
+        )
+    }
+
+    #[test]
+    fn maps_arrows_and_characters() -> Result<()> {
+        verify_that!(
+            (
+                action_for(Key::Named(Named::ArrowUp), Modifiers::empty()),
+                action_for(Key::Character("="), Modifiers::empty()),
+                action_for(Key::Character("?"), Modifiers::SHIFT),
+                action_for(Key::Character("x"), Modifiers::empty())
+            ),
+            (
+                some(eq(KeyAction::Move(Direction::Up))),
+                some(eq(KeyAction::ZoomIn)),
+                some(eq(KeyAction::ToggleHelp)),
+                none()
+            )
+        )
+    }
+
+    #[test]
+    fn rebinding_action_frees_its_default_key() -> Result<()> {
+        let mut keymap = Keymap::default();
+        keymap.bind(
+            KeyAction::ZoomIn,
+            vec![
+                BoundKey::parse("i").unwrap(),
+                BoundKey::parse("F5").unwrap(),
+            ],
+        );
+
+        verify_that!(
+            (
+                keymap.action_for(Key::Character("i"), Modifiers::empty()),
+                keymap.action_for(Key::Named(Named::F5), Modifiers::empty()),
+                keymap.action_for(Key::Character("+"), Modifiers::SHIFT),
+                keymap.action_for(Key::Character("-"), Modifiers::empty())
+            ),
+            (
+                some(eq(KeyAction::ZoomIn)),
+                some(eq(KeyAction::ZoomIn)),
+                none(),
+                some(eq(KeyAction::ZoomOut))
+            )
+        )
+    }
+
+    #[test]
+    fn help_lists_rebound_actions_on_lines_of_their_own() -> Result<()> {
+        let mut keymap = Keymap::default();
+        keymap.bind(KeyAction::ZoomOut, vec![BoundKey::parse("o").unwrap()]);
+        let help = keymap.help();
+
+        verify_that!(help.len(), eq(Keymap::default().help().len() + 1))?;
+        verify_that!(help.iter().any(|(keys, _)| keys == "o"), eq(true))?;
+        verify_that!(help.iter().any(|(keys, _)| keys == "+"), eq(true))
+    }
+}
@@ -0,0 +1,246 @@
+use crate::{controls::FractalType, fractal_view::ParameterBlock};
+use std::fmt::Write;
+
+const WGSL_TEMPLATE: &str = include_str!("shader/snippet.wgsl");
+const GLSL_TEMPLATE: &str = include_str!("shader/snippet.glsl");
+
+/// The language of an exported snippet.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(super) enum SnippetLanguage {
+    /// A WGSL function which colours a point of the viewport, to be called from any fragment
+    /// shader.
+    Wgsl,
+    /// A GLSL shader which can be pasted into Shadertoy as it is.
+    Shadertoy,
+}
+
+impl SnippetLanguage {
+    pub(super) const ALL: [SnippetLanguage; 2] = [Self::Wgsl, Self::Shadertoy];
+
+    pub(super) fn extension(self) -> &'static str {
+        match self {
+            Self::Wgsl => "wgsl",
+            Self::Shadertoy => "glsl",
+        }
+    }
+}
+
+impl std::fmt::Display for SnippetLanguage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Wgsl => write!(f, "WGSL"),
+            Self::Shadertoy => write!(f, "Shadertoy"),
+        }
+    }
+}
+
+/// Writes a self-contained shader which draws the fractal as the given uniform block does: its
+/// location, parameters, palette and post-processing baked in as constants.
+///
+/// The snippet iterates in `f32`, so deep zooms which the explorer iterates in higher precision
+/// come out blocky, and it leaves out the orbit trap, whose image it does not have.
+pub(super) fn generate(
+    fractal_type: FractalType,
+    block: &ParameterBlock,
+    language: SnippetLanguage,
+) -> String {
+    let definition = fractal_type.definition();
+    // The colour functions of the templates are named after the entry points of frag.wgsl.
+    let colour_function = format!("{}_colour", definition.entry_point);
+    let mut snippet = format!("// {} exported from fractal_explorer.\n\n", definition.name);
+    let constants = constants(block, language);
+    match language {
+        SnippetLanguage::Wgsl => {
+            snippet += &constants;
+            snippet += "\n";
+            snippet += WGSL_TEMPLATE;
+            let _ = write!(
+                snippet,
+                "
+// The colour of the fractal at the given position in the viewport, which spans [-1, 1] x [-1, 1]
+// with y pointing up.
+fn fractal_colour(viewport_position: vec2<f32>) -> vec4<f32> {{
+    let c = (TRANSFORM * vec3(viewport_position, 1.0)).xy;
+    return {colour_function}(c);
+}}
+"
+            );
+        }
+        SnippetLanguage::Shadertoy => {
+            snippet += &constants;
+            snippet += "\n";
+            snippet += GLSL_TEMPLATE;
+            let _ = write!(
+                snippet,
+                "
+void mainImage(out vec4 fragColor, in vec2 fragCoord) {{
+    vec2 viewport_position = fragCoord / iResolution.xy * 2.0 - 1.0;
+    vec2 c = (TRANSFORM * vec3(viewport_position, 1.0)).xy;
+    fragColor = {colour_function}(c);
+}}
+"
+            );
+        }
+    }
+    snippet
+}
+
+/// Declares the settings of the uniform block as constants of the given language.
+fn constants(block: &ParameterBlock, language: SnippetLanguage) -> String {
+    let transform: &[f32; 9] = block.transform.as_ref();
+    let params = block.params.map(float);
+    let vector = |values: &[f32]| {
+        let values = values.iter().copied().map(float).collect::<Vec<_>>();
+        format!("vec{}({})", values.len(), values.join(", "))
+    };
+    let gradient = block.gradient.map(|colour| vector(&colour[..3]));
+    let root_colours = block.root_colours.map(|colour| vector(&colour[..3]));
+    let post_processing = vector(&block.post_processing);
+    let tonemapper = block.tonemapper;
+    let critical_point = vector(&block.critical_point);
+    let (fixed_c, plane) = (vector(&block.fixed_c), block.plane);
+    let transform = transform.map(float).join(", ");
+    let (max_iterations, bailout, palette_offset, variation, time) = (
+        block.max_iterations,
+        float(block.bailout),
+        float(block.palette_offset),
+        block.variation,
+        float(block.time),
+    );
+    match language {
+        SnippetLanguage::Wgsl => format!(
+            "const TRANSFORM = mat3x3<f32>({transform});
+const MAX_ITERATIONS = {max_iterations}u;
+const BAILOUT = {bailout};
+const PALETTE_OFFSET = {palette_offset};
+const PARAMS = array<f32, {}>({});
+const GRADIENT = array<vec3<f32>, {}>({});
+const ROOT_COLOURS = array<vec3<f32>, {}>({});
+const POST_PROCESSING = {post_processing};
+const TONEMAPPER = {tonemapper}u;
+const VARIATION = {variation}u;
+const TIME = {time};
+const CRITICAL_POINT = {critical_point};
+const FIXED_C = {fixed_c};
+const PLANE = {plane}u;
+",
+            params.len(),
+            params.join(", "),
+            gradient.len(),
+            gradient.join(", "),
+            root_colours.len(),
+            root_colours.join(", "),
+        ),
+        SnippetLanguage::Shadertoy => format!(
+            "const mat3 TRANSFORM = mat3({transform});
+const uint MAX_ITERATIONS = {max_iterations}u;
+const float BAILOUT = {bailout};
+const float PALETTE_OFFSET = {palette_offset};
+const float PARAMS[{0}] = float[{0}]({1});
+const vec3 GRADIENT[{2}] = vec3[{2}]({3});
+const vec3 ROOT_COLOURS[{4}] = vec3[{4}]({5});
+const vec4 POST_PROCESSING = {post_processing};
+const uint TONEMAPPER = {tonemapper}u;
+const uint VARIATION = {variation}u;
+const vec2 CRITICAL_POINT = {critical_point};
+const vec2 FIXED_C = {fixed_c};
+const uint PLANE = {plane}u;
+// Spinning follows the time of the shader rather than that at which the snippet was exported.
+#define TIME iTime
+",
+            params.len(),
+            params.join(", "),
+            gradient.len(),
+            gradient.join(", "),
+            root_colours.len(),
+            root_colours.join(", "),
+        ),
+    }
+}
+
+/// Writes the value as a float literal, which both languages read, with a decimal point or an
+/// exponent so that it is not taken for an integer.
+fn float(value: f32) -> String {
+    format!("{value:?}")
+}
+
+/// Asks the user where to export the snippet, returning `None` if they cancel.
+#[cfg(not(target_arch = "wasm32"))]
+pub(super) fn choose_export_path(
+    fractal_type: FractalType,
+    language: SnippetLanguage,
+) -> Option<std::path::PathBuf> {
+    let extension = language.extension();
+    native_dialog::FileDialog::new()
+        .set_title(&format!("Export {language} snippet"))
+        .set_filename(&format!(
+            "{}.{extension}",
+            fractal_type.to_string().to_lowercase()
+        ))
+        .add_filter(&language.to_string(), &[extension])
+        .show_save_single_file()
+        .ok()
+        .flatten()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{float, generate, SnippetLanguage};
+    use crate::{controls::FractalType, fractal_view::ParameterBlock, gpu::Gpu};
+    use googletest::prelude::*;
+    use iced_wgpu::wgpu;
+
+    #[test]
+    fn wgsl_snippets_compile() -> Result<()> {
+        let gpu = Gpu::new_without_surface()?;
+
+        let mut errors = vec![];
+        for fractal_type in FractalType::ALL {
+            let snippet = generate(
+                fractal_type,
+                &ParameterBlock::default(),
+                SnippetLanguage::Wgsl,
+            );
+            gpu.device.push_error_scope(wgpu::ErrorFilter::Validation);
+            gpu.device
+                .create_shader_module(wgpu::ShaderModuleDescriptor {
+                    label: Some("Snippet"),
+                    source: wgpu::ShaderSource::Wgsl(snippet.into()),
+                });
+            if let Some(error) = futures::executor::block_on(gpu.device.pop_error_scope()) {
+                errors.push(format!("{fractal_type}: {error}"));
+            }
+        }
+
+        verify_that!(errors, empty())
+    }
+
+    #[test]
+    fn shadertoy_snippet_bakes_settings_into_constants() -> Result<()> {
+        let block = ParameterBlock {
+            max_iterations: 250,
+            params: [3.5, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0],
+            ..ParameterBlock::default()
+        };
+
+        let snippet = generate(FractalType::Multibrot, &block, SnippetLanguage::Shadertoy);
+
+        verify_that!(
+            snippet,
+            all!(
+                starts_with("// Multibrot exported from fractal_explorer."),
+                contains_substring("const uint MAX_ITERATIONS = 250u;"),
+                contains_substring("float[8](3.5, 0.0,"),
+                contains_substring("fragColor = multibrot_colour(c);")
+            )
+        )
+    }
+
+    #[test]
+    fn floats_are_never_written_as_integers() -> Result<()> {
+        verify_that!(
+            [1.0, 0.25, 1e-7].map(float),
+            eq(["1.0".to_string(), "0.25".to_string(), "1e-7".to_string()])
+        )
+    }
+}
@@ -0,0 +1,137 @@
+use cgmath::Matrix3;
+
+/// Packs values into a byte buffer following the std140 layout rules which WGSL applies to the
+/// uniform address space.
+///
+/// Each value is placed at the next offset which is a multiple of its alignment, and the end of
+/// the buffer is padded to a multiple of 16 bytes, as for a WGSL struct containing vectors.
+#[derive(Default)]
+pub(super) struct Std140Writer {
+    bytes: Vec<u8>,
+}
+
+impl Std140Writer {
+    /// Writes a `u32` and returns its offset.
+    pub(super) fn write_u32(&mut self, value: u32) -> usize {
+        self.write(4, bytemuck::bytes_of(&value))
+    }
+
+    /// Writes an `i32` and returns its offset.
+    pub(super) fn write_i32(&mut self, value: i32) -> usize {
+        self.write(4, bytemuck::bytes_of(&value))
+    }
+
+    /// Writes an `f32` and returns its offset.
+    pub(super) fn write_f32(&mut self, value: f32) -> usize {
+        self.write(4, bytemuck::bytes_of(&value))
+    }
+
+    /// Writes a `vec2<f32>`, which is aligned to 8 bytes, and returns its offset.
+    pub(super) fn write_vec2(&mut self, value: [f32; 2]) -> usize {
+        self.write(8, bytemuck::cast_slice(&value))
+    }
+
+    /// Writes a `mat3x3<f32>`, whose columns are each padded to the size of a `vec4<f32>`, and
+    /// returns its offset.
+    pub(super) fn write_mat3x3(&mut self, value: Matrix3<f32>) -> usize {
+        let offset = self.align_to(16);
+        for column in [value.x, value.y, value.z] {
+            self.write(
+                16,
+                bytemuck::cast_slice(&[column.x, column.y, column.z, 0.0]),
+            );
+        }
+        offset
+    }
+
+    /// Writes an `array<vec4<f32>, N>` and returns its offset.
+    pub(super) fn write_vec4_array(&mut self, values: &[[f32; 4]]) -> usize {
+        self.write(16, bytemuck::cast_slice(values))
+    }
+
+    /// Returns the packed bytes, padded to the alignment of a struct containing vectors.
+    pub(super) fn finish(mut self) -> Vec<u8> {
+        self.align_to(16);
+        self.bytes
+    }
+
+    fn write(&mut self, alignment: usize, bytes: &[u8]) -> usize {
+        let offset = self.align_to(alignment);
+        self.bytes.extend_from_slice(bytes);
+        offset
+    }
+
+    fn align_to(&mut self, alignment: usize) -> usize {
+        let offset = self.bytes.len().next_multiple_of(alignment);
+        self.bytes.resize(offset, 0);
+        offset
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Std140Writer;
+    use cgmath::Matrix3;
+    use googletest::prelude::*;
+
+    #[test]
+    fn scalars_are_packed_tightly() -> Result<()> {
+        let mut writer = Std140Writer::default();
+
+        let offsets = (
+            writer.write_u32(1),
+            writer.write_f32(2.0),
+            writer.write_u32(3),
+        );
+
+        verify_that!(
+            (offsets, writer.finish().len()),
+            ((eq(0), eq(4), eq(8)), eq(16))
+        )
+    }
+
+    #[test]
+    fn vectors_are_aligned_to_16_bytes() -> Result<()> {
+        let mut writer = Std140Writer::default();
+
+        let offsets = (
+            writer.write_f32(1.0),
+            writer.write_vec4_array(&[[2.0; 4]; 2]),
+            writer.write_u32(3),
+            writer.write_mat3x3(Matrix3::from_scale(1.0)),
+        );
+
+        verify_that!(
+            (offsets, writer.finish().len()),
+            ((eq(0), eq(16), eq(48), eq(64)), eq(112))
+        )
+    }
+
+    #[test]
+    fn two_component_vectors_are_aligned_to_8_bytes() -> Result<()> {
+        let mut writer = Std140Writer::default();
+
+        let offsets = (writer.write_u32(1), writer.write_vec2([2.0, 3.0]));
+
+        verify_that!((offsets, writer.finish().len()), ((eq(0), eq(8)), eq(16)))
+    }
+
+    #[test]
+    fn matrix_columns_are_padded() -> Result<()> {
+        let mut writer = Std140Writer::default();
+        writer.write_mat3x3(Matrix3::new(1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0, 9.0));
+
+        let floats: [f32; 12] = writer
+            .finish()
+            .chunks_exact(4)
+            .map(|bytes| f32::from_ne_bytes(bytes.try_into().unwrap()))
+            .collect::<Vec<_>>()
+            .try_into()
+            .unwrap();
+
+        verify_that!(
+            floats,
+            eq([1.0, 2.0, 3.0, 0.0, 4.0, 5.0, 6.0, 0.0, 7.0, 8.0, 9.0, 0.0])
+        )
+    }
+}
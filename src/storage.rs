@@ -0,0 +1,208 @@
+use std::future::Future;
+
+/// Where the explorer keeps what must survive a restart, as text under a key.
+///
+/// Access is asynchronous, since browsers only offer IndexedDB as such. The desktop stores a file
+/// per key in the configuration directory of the user.
+pub(super) trait Storage {
+    /// Returns the value stored under the key, or `None` if nothing is.
+    async fn load(&self, key: &str) -> Result<Option<String>, StorageError>;
+
+    /// Replaces the value stored under the key.
+    async fn save(&self, key: &str, value: &str) -> Result<(), StorageError>;
+}
+
+#[derive(Debug, thiserror::Error)]
+pub(super) enum StorageError {
+    #[cfg(not(target_arch = "wasm32"))]
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    /// The browser offers no IndexedDB, as in some private windows.
+    #[cfg(target_arch = "wasm32")]
+    #[error("IndexedDB is not available")]
+    Unavailable,
+    #[cfg(target_arch = "wasm32")]
+    #[error("IndexedDB failed: {0}")]
+    IndexedDb(String),
+}
+
+#[cfg(target_arch = "wasm32")]
+impl From<wasm_bindgen::JsValue> for StorageError {
+    fn from(error: wasm_bindgen::JsValue) -> Self {
+        Self::IndexedDb(format!("{error:?}"))
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub(super) type PlatformStorage = FileStorage;
+#[cfg(target_arch = "wasm32")]
+pub(super) type PlatformStorage = indexed_db::IndexedDbStorage;
+
+/// Returns the storage of the platform, or `None` if the desktop has no configuration directory.
+#[cfg(not(target_arch = "wasm32"))]
+pub(super) fn open() -> Option<PlatformStorage> {
+    config_directory().map(FileStorage::new)
+}
+
+#[cfg(target_arch = "wasm32")]
+pub(super) fn open() -> Option<PlatformStorage> {
+    Some(indexed_db::IndexedDbStorage)
+}
+
+/// Runs an access to the storage to completion. Files are read and written right away, since they
+/// are small, whereas IndexedDB only answers once the page yields.
+pub(super) fn execute(future: impl Future<Output = ()> + 'static) {
+    #[cfg(not(target_arch = "wasm32"))]
+    futures::executor::block_on(future);
+    #[cfg(target_arch = "wasm32")]
+    wasm_bindgen_futures::spawn_local(future);
+}
+
+/// The directory of the explorer within the configuration directory of the user, following the
+/// conventions of the platform.
+#[cfg(not(target_arch = "wasm32"))]
+pub(super) fn config_directory() -> Option<std::path::PathBuf> {
+    use std::{env, path::PathBuf};
+
+    let base = if cfg!(windows) {
+        env::var_os("APPDATA").map(PathBuf::from)
+    } else if cfg!(target_os = "macos") {
+        env::var_os("HOME").map(|home| PathBuf::from(home).join("Library/Application Support"))
+    } else {
+        env::var_os("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .filter(|directory| directory.is_absolute())
+            .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+    };
+    base.map(|base| base.join(env!("CARGO_PKG_NAME")))
+}
+
+/// The directory of the explorer within the cache directory of the user, for what can be computed
+/// again if the platform clears it.
+#[cfg(not(target_arch = "wasm32"))]
+pub(super) fn cache_directory() -> Option<std::path::PathBuf> {
+    use std::{env, path::PathBuf};
+
+    let base = if cfg!(windows) {
+        env::var_os("LOCALAPPDATA").map(PathBuf::from)
+    } else if cfg!(target_os = "macos") {
+        env::var_os("HOME").map(|home| PathBuf::from(home).join("Library/Caches"))
+    } else {
+        env::var_os("XDG_CACHE_HOME")
+            .map(PathBuf::from)
+            .filter(|directory| directory.is_absolute())
+            .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".cache")))
+    };
+    base.map(|base| base.join(env!("CARGO_PKG_NAME")))
+}
+
+/// Stores each key as a JSON file in a directory.
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Clone, Debug)]
+pub(super) struct FileStorage {
+    directory: std::path::PathBuf,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl FileStorage {
+    pub(super) fn new(directory: std::path::PathBuf) -> Self {
+        Self { directory }
+    }
+
+    fn path(&self, key: &str) -> std::path::PathBuf {
+        self.directory.join(format!("{key}.json"))
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl Storage for FileStorage {
+    async fn load(&self, key: &str) -> Result<Option<String>, StorageError> {
+        match std::fs::read_to_string(self.path(key)) {
+            Ok(value) => Ok(Some(value)),
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(error) => Err(error.into()),
+        }
+    }
+
+    async fn save(&self, key: &str, value: &str) -> Result<(), StorageError> {
+        std::fs::create_dir_all(&self.directory)?;
+        // Renaming the complete file over the old one keeps the old value if the explorer stops
+        // while writing.
+        let path = self.path(key);
+        let temporary = path.with_extension("json.tmp");
+        std::fs::write(&temporary, value)?;
+        std::fs::rename(temporary, path)?;
+        Ok(())
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+mod indexed_db {
+    use super::{Storage, StorageError};
+    use wasm_bindgen::{closure::Closure, JsCast, JsValue};
+    use wasm_bindgen_futures::JsFuture;
+    use web_sys::{Event, IdbDatabase, IdbOpenDbRequest, IdbRequest, IdbTransactionMode};
+
+    const DATABASE: &str = "fractal_explorer";
+    const VERSION: u32 = 1;
+    /// The object store which holds the values by their keys.
+    const STORE: &str = "state";
+
+    /// Stores each key in an object store of an IndexedDB database of the page's origin, which
+    /// also serves the explorer when installed as an offline web app.
+    #[derive(Clone, Debug)]
+    pub(crate) struct IndexedDbStorage;
+
+    impl Storage for IndexedDbStorage {
+        async fn load(&self, key: &str) -> Result<Option<String>, StorageError> {
+            let store = open()
+                .await?
+                .transaction_with_str(STORE)?
+                .object_store(STORE)?;
+            Ok(complete(&store.get(&JsValue::from_str(key))?)
+                .await?
+                .as_string())
+        }
+
+        async fn save(&self, key: &str, value: &str) -> Result<(), StorageError> {
+            let store = open()
+                .await?
+                .transaction_with_str_and_mode(STORE, IdbTransactionMode::Readwrite)?
+                .object_store(STORE)?;
+            complete(&store.put_with_key(&JsValue::from_str(value), &JsValue::from_str(key))?)
+                .await?;
+            Ok(())
+        }
+    }
+
+    /// Opens the database, creating its object store on first use.
+    async fn open() -> Result<IdbDatabase, StorageError> {
+        let factory = web_sys::window()
+            .ok_or(StorageError::Unavailable)?
+            .indexed_db()?
+            .ok_or(StorageError::Unavailable)?;
+        let request = factory.open_with_u32(DATABASE, VERSION)?;
+        let onupgradeneeded = Closure::once_into_js(|event: Event| {
+            let Some(request) = event.target() else {
+                return;
+            };
+            if let Ok(database) = request.unchecked_into::<IdbOpenDbRequest>().result() {
+                let _ = database
+                    .unchecked_into::<IdbDatabase>()
+                    .create_object_store(STORE);
+            }
+        });
+        request.set_onupgradeneeded(Some(onupgradeneeded.unchecked_ref()));
+        Ok(complete(&request).await?.unchecked_into())
+    }
+
+    /// Waits for the request to succeed and returns its result.
+    async fn complete(request: &IdbRequest) -> Result<JsValue, StorageError> {
+        let promise = js_sys::Promise::new(&mut |resolve, reject| {
+            request.set_onsuccess(Some(resolve.unchecked_ref()));
+            request.set_onerror(Some(reject.unchecked_ref()));
+        });
+        JsFuture::from(promise).await?;
+        Ok(request.result()?)
+    }
+}
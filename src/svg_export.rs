@@ -0,0 +1,192 @@
+use crate::{
+    colour_palette::{hex, Palette},
+    fractal_view::View,
+    transform::{self, ViewTransform},
+};
+use cgmath::Vector2;
+use iced::Size;
+use std::fmt::Write;
+
+/// The number of polylines into which a curve coloured from the palette is split, each stroked in
+/// the colour at its middle, since SVG cannot vary the colour of a stroke along its length.
+const COLOURED_RUNS: usize = 256;
+
+/// How the lines of a curve are stroked in an SVG export.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(super) struct SvgStroke {
+    /// The width of the lines in pixels of the image.
+    pub(super) width: f32,
+    /// Whether the curve is coloured from the palette along its length, as on screen, or drawn in
+    /// black as a single polyline, as laser cutters and plotters expect.
+    pub(super) coloured: bool,
+}
+
+impl Default for SvgStroke {
+    fn default() -> Self {
+        Self {
+            width: 1.0,
+            coloured: true,
+        }
+    }
+}
+
+/// Writes the curve of the view's vector mode as an SVG image of the given size, showing what the
+/// view shows, or returns `None` if no curve is drawn.
+///
+/// The curve is written at the depth to which it recurses rather than the shallower one drawn on
+/// screen when zoomed out, so that the image keeps its detail however far it is enlarged. Parts
+/// of the curve beyond the view are written too and clipped by the image. The colours are those
+/// of the palette before post-processing, and the background is left transparent.
+pub(super) fn svg(view: &View, width: u32, height: u32, stroke: SvgStroke) -> Option<String> {
+    let points = view.vector_mode().polyline(view.curve_depth());
+    if points.len() < 2 {
+        return None;
+    }
+    Some(svg_of_polyline(
+        &points,
+        &view.transform(),
+        Colouring {
+            palette: view.palette(),
+            offset: view.palette_offset(),
+        },
+        width,
+        height,
+        stroke,
+    ))
+}
+
+/// The palette and its rotation, by which a curve is coloured along its length.
+struct Colouring {
+    palette: Palette,
+    offset: f32,
+}
+
+fn svg_of_polyline(
+    points: &[Vector2<f64>],
+    view_transform: &ViewTransform,
+    colouring: Colouring,
+    width: u32,
+    height: u32,
+    stroke: SvgStroke,
+) -> String {
+    let size = Size::new(width as f32, height as f32);
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{width}\" height=\"{height}\" \
+        viewBox=\"0 0 {width} {height}\">\n"
+    );
+    let mut push_polyline = |points: &[Vector2<f64>], colour: &str| {
+        svg.push_str("<polyline points=\"");
+        for (index, point) in points.iter().enumerate() {
+            let screen = transform::to_screen(view_transform.offset_of(*point), size);
+            let separator = if index == 0 { "" } else { " " };
+            let _ = write!(svg, "{separator}{:.2},{:.2}", screen.x, screen.y);
+        }
+        let _ = writeln!(
+            svg,
+            "\" fill=\"none\" stroke=\"{colour}\" stroke-width=\"{}\" \
+            stroke-linecap=\"round\" stroke-linejoin=\"round\"/>",
+            stroke.width
+        );
+    };
+    if stroke.coloured {
+        let segments = points.len() - 1;
+        let runs = COLOURED_RUNS.min(segments);
+        for run in 0..runs {
+            // Neighbouring runs share their end points, so that the curve has no gaps.
+            let (start, end) = (run * segments / runs, (run + 1) * segments / runs);
+            let along = (start + end) as f32 / (2 * segments) as f32;
+            let colour = colouring
+                .palette
+                .colour_at((along + colouring.offset).rem_euclid(1.0));
+            push_polyline(&points[start..=end], &hex(colour));
+        }
+    } else {
+        push_polyline(points, "#000000");
+    }
+    svg.push_str("</svg>\n");
+    svg
+}
+
+/// Asks the user where to export the curve as SVG, returning `None` if they cancel.
+#[cfg(not(target_arch = "wasm32"))]
+pub(super) fn choose_export_path(name: &str) -> Option<std::path::PathBuf> {
+    native_dialog::FileDialog::new()
+        .set_title("Export SVG")
+        .set_filename(&format!("{}.svg", name.to_lowercase().replace(' ', "_")))
+        .add_filter("SVG image", &["svg"])
+        .show_save_single_file()
+        .ok()
+        .flatten()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{svg_of_polyline, Colouring, SvgStroke};
+    use crate::{colour_palette::Palette, transform::ViewTransform};
+    use cgmath::Vector2;
+    use googletest::prelude::*;
+
+    fn square() -> Vec<Vector2<f64>> {
+        vec![
+            Vector2::new(-1.0, -1.0),
+            Vector2::new(1.0, -1.0),
+            Vector2::new(1.0, 1.0),
+            Vector2::new(-1.0, 1.0),
+        ]
+    }
+
+    fn greyscale() -> Colouring {
+        Colouring {
+            palette: Palette::Greyscale,
+            offset: 0.0,
+        }
+    }
+
+    #[test]
+    fn black_stroke_is_one_polyline_in_image_coordinates() -> Result<()> {
+        let svg = svg_of_polyline(
+            &square(),
+            &ViewTransform::looking_at(Vector2::new(0.0, 0.0), 2.0),
+            greyscale(),
+            100,
+            100,
+            SvgStroke {
+                width: 0.5,
+                coloured: false,
+            },
+        );
+
+        verify_that!(svg.matches("<polyline").count(), eq(1))?;
+        verify_that!(
+            svg,
+            contains_substring(
+                "points=\"25.00,75.00 75.00,75.00 75.00,25.00 25.00,25.00\" fill=\"none\" \
+                stroke=\"#000000\" stroke-width=\"0.5\""
+            )
+        )
+    }
+
+    #[test]
+    fn coloured_runs_share_end_points() -> Result<()> {
+        let svg = svg_of_polyline(
+            &square(),
+            &ViewTransform::looking_at(Vector2::new(0.0, 0.0), 2.0),
+            greyscale(),
+            100,
+            100,
+            SvgStroke::default(),
+        );
+
+        verify_that!(
+            svg.lines()
+                .filter(|line| line.starts_with("<polyline"))
+                .map(|line| line.split('"').nth(1).unwrap().to_string())
+                .collect::<Vec<_>>(),
+            elements_are![
+                eq("25.00,75.00 75.00,75.00"),
+                eq("75.00,75.00 75.00,25.00"),
+                eq("75.00,25.00 25.00,25.00")
+            ]
+        )
+    }
+}
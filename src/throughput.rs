@@ -0,0 +1,168 @@
+use iced::time::Instant;
+use std::{collections::VecDeque, time::Duration};
+
+/// The number of most recent units over which the rate is averaged, so that the estimate follows
+/// a render into regions which are slower or faster to compute.
+const WINDOW: usize = 16;
+
+/// Measures how fast units of work, such as the tiles or frames of an export, are completed, and
+/// estimates from it how long the rest will take.
+pub(super) struct Throughput {
+    started: Instant,
+    /// When the most recent units were completed, oldest first.
+    recent: VecDeque<Instant>,
+    completed: u64,
+}
+
+impl Throughput {
+    pub(super) fn new(now: Instant) -> Self {
+        Self {
+            started: now,
+            recent: VecDeque::with_capacity(WINDOW + 1),
+            completed: 0,
+        }
+    }
+
+    pub(super) fn complete(&mut self, now: Instant) {
+        self.completed += 1;
+        self.recent.push_back(now);
+        if self.recent.len() > WINDOW + 1 {
+            self.recent.pop_front();
+        }
+    }
+
+    pub(super) fn completed(&self) -> u64 {
+        self.completed
+    }
+
+    /// The units completed per second over the last [`WINDOW`] units, or since the start while
+    /// only one is done. Returns `None` before then.
+    pub(super) fn per_second(&self) -> Option<f64> {
+        let last = *self.recent.back()?;
+        let (first, units) = match self.recent.front() {
+            Some(first) if self.recent.len() > 1 => (*first, self.recent.len() - 1),
+            _ => (self.started, 1),
+        };
+        let seconds = (last - first).as_secs_f64();
+        (seconds > 0.0).then(|| units as f64 / seconds)
+    }
+
+    /// How long the units still to come out of `total` will take at the current rate.
+    pub(super) fn remaining(&self, total: u64) -> Option<Duration> {
+        let rate = self.per_second()?;
+        Some(Duration::from_secs_f64(
+            total.saturating_sub(self.completed) as f64 / rate,
+        ))
+    }
+}
+
+/// Describes how far an export has got in units with the given singular and plural names, e.g.
+/// "Tile 120 of 480 · 35.2 tiles/s · 10 s left". Units slower than one a second are described by
+/// the seconds each takes instead.
+pub(super) fn describe_progress(
+    throughput: &Throughput,
+    total: Option<u64>,
+    (unit, units): (&str, &str),
+) -> String {
+    let mut description = format!(
+        "{}{} {}",
+        unit[..1].to_uppercase(),
+        &unit[1..],
+        throughput.completed()
+    );
+    if let Some(total) = total {
+        description += &format!(" of {total}");
+    }
+    match throughput.per_second() {
+        Some(rate) if rate >= 1.0 => description += &format!(" · {rate:.1} {units}/s"),
+        Some(rate) => description += &format!(" · {:.1} s/{unit}", 1.0 / rate),
+        None => {}
+    }
+    if let Some(remaining) = total.and_then(|total| throughput.remaining(total)) {
+        description += &format!(" · {} left", describe_duration(remaining));
+    }
+    description
+}
+
+/// Describes a duration to the second in its two largest units, e.g. "1 h 02 min", "3 min 05 s"
+/// or "42 s".
+pub(super) fn describe_duration(duration: Duration) -> String {
+    let seconds = duration.as_secs_f64().ceil() as u64;
+    match (seconds / 3600, seconds / 60 % 60, seconds % 60) {
+        (0, 0, seconds) => format!("{seconds} s"),
+        (0, minutes, seconds) => format!("{minutes} min {seconds:02} s"),
+        (hours, minutes, _) => format!("{hours} h {minutes:02} min"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{describe_duration, describe_progress, Throughput, WINDOW};
+    use googletest::prelude::*;
+    use iced::time::Instant;
+    use std::time::Duration;
+
+    #[test]
+    fn rate_follows_recent_units() -> Result<()> {
+        let start = Instant::now();
+        let mut throughput = Throughput::new(start);
+        // Slow units at first, then twice as many per second.
+        for unit in 1..=WINDOW as u64 {
+            throughput.complete(start + Duration::from_secs(unit));
+        }
+        let slow = throughput.per_second();
+        let fast_start = start + Duration::from_secs(WINDOW as u64);
+        for unit in 1..=WINDOW as u64 {
+            throughput.complete(fast_start + Duration::from_millis(500 * unit));
+        }
+
+        verify_that!(
+            (slow, throughput.per_second()),
+            (some(near(1.0, 1e-9)), some(near(2.0, 1e-9)))
+        )
+    }
+
+    #[test]
+    fn remaining_time_is_estimated_from_rate() -> Result<()> {
+        let start = Instant::now();
+        let mut throughput = Throughput::new(start);
+
+        let before = throughput.remaining(10);
+        throughput.complete(start + Duration::from_secs(2));
+
+        verify_that!(
+            (before, throughput.remaining(10)),
+            (none(), some(eq(Duration::from_secs(18))))
+        )
+    }
+
+    #[test]
+    fn progress_is_described_with_rate_and_remaining_time() -> Result<()> {
+        let start = Instant::now();
+        let mut fast = Throughput::new(start);
+        let mut slow = Throughput::new(start);
+        for unit in 1..=4 {
+            fast.complete(start + Duration::from_millis(250 * unit));
+            slow.complete(start + Duration::from_secs(5 * unit));
+        }
+
+        verify_that!(
+            (
+                describe_progress(&fast, Some(12), ("tile", "tiles")),
+                describe_progress(&slow, None, ("frame", "frames"))
+            ),
+            (
+                eq("Tile 4 of 12 · 4.0 tiles/s · 2 s left"),
+                eq("Frame 4 · 5.0 s/frame")
+            )
+        )
+    }
+
+    #[test]
+    fn durations_are_described_in_two_units() -> Result<()> {
+        verify_that!(
+            [42, 185, 3720].map(|seconds| describe_duration(Duration::from_secs(seconds))),
+            eq(["42 s", "3 min 05 s", "1 h 02 min"].map(String::from))
+        )
+    }
+}
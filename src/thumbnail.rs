@@ -0,0 +1,356 @@
+use crate::{
+    blit::Blit, controls::FractalType, fractal_view::ParameterBlock, gpu::Gpu, gpu_memory,
+    pipeline_compiler::FractalPipelines,
+};
+use iced_wgpu::wgpu;
+use std::{
+    cell::{Cell, RefCell},
+    collections::HashMap,
+    hash::{DefaultHasher, Hash, Hasher},
+    rc::Rc,
+    sync::Arc,
+};
+
+/// The width and height of every thumbnail in pixels.
+pub(super) const THUMBNAIL_SIZE: u32 = 256;
+
+/// How many thumbnails are kept before the least recently used is discarded.
+const CACHE_CAPACITY: usize = 64;
+
+/// How many thumbnails are rendered per frame, so that asking for many does not stall one.
+const RENDERS_PER_FRAME: usize = 4;
+
+/// What a thumbnail shows: a fractal rendered with the given uniform block.
+#[derive(Clone, Copy, Debug)]
+pub(super) struct ThumbnailRequest {
+    pub(super) fractal_type: FractalType,
+    pub(super) block: ParameterBlock,
+}
+
+impl ThumbnailRequest {
+    /// Identifies the thumbnail by a hash of everything which determines it.
+    fn key(&self) -> ThumbnailKey {
+        let mut hasher = DefaultHasher::new();
+        self.fractal_type.hash(&mut hasher);
+        self.block.std140_bytes().hash(&mut hasher);
+        ThumbnailKey(hasher.finish())
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+struct ThumbnailKey(u64);
+
+/// A rendered thumbnail, which [`ThumbnailRenderer::draw`] copies onto a render target.
+pub(super) struct Thumbnail {
+    bind_group: wgpu::BindGroup,
+}
+
+/// Renders thumbnails of a fixed size offscreen and keeps the most recently used.
+///
+/// Thumbnails are asked for on every frame on which they are shown. Those not rendered yet are
+/// rendered a few per frame, in the order asked for, and [`Self::is_pending`] tells whether frames
+/// must keep coming until all are. Requests which are not repeated on the next frame are dropped.
+///
+/// Thumbnails are rendered in a single pass without iteration data, so they iterate in `f32`.
+pub(super) struct ThumbnailRenderer {
+    device: Arc<wgpu::Device>,
+    texture_format: wgpu::TextureFormat,
+    blit: Blit,
+    /// The uniform buffers into which the thumbnails rendered on one frame are uploaded, and the
+    /// bind groups through which they are read.
+    slots: Vec<(wgpu::Buffer, wgpu::BindGroup)>,
+    cache: RefCell<LruCache<ThumbnailKey, Rc<Thumbnail>>>,
+    /// The thumbnails asked for since the last upload which are not cached.
+    requested: RefCell<Vec<ThumbnailRequest>>,
+    /// The thumbnails uploaded into the slots with the same indices, to be rendered next.
+    uploaded: RefCell<Vec<ThumbnailRequest>>,
+    /// Whether more thumbnails were asked for than were uploaded last.
+    pending: Cell<bool>,
+}
+
+impl ThumbnailRenderer {
+    /// Creates the uniform buffers into which thumbnails are uploaded, which are read through the
+    /// bind groups which `bind` creates for them.
+    pub(super) fn new(gpu: &Gpu, bind: impl Fn(&wgpu::Buffer) -> wgpu::BindGroup) -> Self {
+        let slots = (0..RENDERS_PER_FRAME)
+            .map(|_| {
+                let buffer = gpu.device.create_buffer(&wgpu::BufferDescriptor {
+                    label: Some("Thumbnail uniform buffer"),
+                    usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::UNIFORM,
+                    size: ParameterBlock::layout().size as u64,
+                    mapped_at_creation: false,
+                });
+                let bind_group = bind(&buffer);
+                (buffer, bind_group)
+            })
+            .collect();
+        Self {
+            device: gpu.device.clone(),
+            texture_format: gpu.texture_format,
+            blit: Blit::new(gpu),
+            slots,
+            cache: RefCell::new(LruCache::new(CACHE_CAPACITY)),
+            requested: RefCell::new(vec![]),
+            uploaded: RefCell::new(vec![]),
+            pending: Cell::new(false),
+        }
+    }
+
+    /// Returns the thumbnail if it has been rendered, or else asks for it to be rendered.
+    pub(super) fn request(&self, request: ThumbnailRequest) -> Option<Rc<Thumbnail>> {
+        let key = request.key();
+        if let Some(thumbnail) = self.cache.borrow_mut().get(&key) {
+            return Some(thumbnail.clone());
+        }
+        let mut requested = self.requested.borrow_mut();
+        if !requested.iter().any(|other| other.key() == key) {
+            requested.push(request);
+        }
+        None
+    }
+
+    /// Whether thumbnails asked for are still waiting to be rendered on later frames.
+    pub(super) fn is_pending(&self) -> bool {
+        self.pending.get()
+    }
+
+    /// Uploads the uniform blocks of the first thumbnails asked for since the last call, which
+    /// [`Self::render`] then renders.
+    pub(super) fn upload(&self, queue: &wgpu::Queue) {
+        let mut requested = self.requested.take();
+        self.pending.set(requested.len() > self.slots.len());
+        requested.truncate(self.slots.len());
+        for (request, (buffer, _)) in requested.iter().zip(&self.slots) {
+            queue.write_buffer(buffer, 0, &request.block.std140_bytes());
+        }
+        self.uploaded.replace(requested);
+    }
+
+    /// Renders the uploaded thumbnails with the given pipelines into textures of their own and
+    /// caches them. Those whose pipelines are not compiled yet are asked for again.
+    pub(super) fn render(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        pipelines: &HashMap<FractalType, FractalPipelines>,
+        vertex_buffer: &wgpu::Buffer,
+        index_buffer: &wgpu::Buffer,
+    ) {
+        for (request, (_, bind_group)) in self.uploaded.take().into_iter().zip(&self.slots) {
+            let Some(pipelines) = pipelines.get(&request.fractal_type) else {
+                self.requested.borrow_mut().push(request);
+                self.pending.set(true);
+                continue;
+            };
+            let texture = self.device.create_texture(&wgpu::TextureDescriptor {
+                label: Some("Thumbnail"),
+                size: wgpu::Extent3d {
+                    width: THUMBNAIL_SIZE,
+                    height: THUMBNAIL_SIZE,
+                    depth_or_array_layers: 1,
+                },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format: self.texture_format,
+                usage: wgpu::TextureUsages::RENDER_ATTACHMENT
+                    | wgpu::TextureUsages::TEXTURE_BINDING,
+                view_formats: &[],
+            });
+            let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Thumbnail pass"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: &view,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: None,
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+            render_pass.set_pipeline(&pipelines.render);
+            render_pass.set_bind_group(0, bind_group, &[]);
+            render_pass.set_vertex_buffer(0, vertex_buffer.slice(..));
+            render_pass.set_index_buffer(index_buffer.slice(..), wgpu::IndexFormat::Uint16);
+            render_pass.draw_indexed(0..6, 0, 0..1);
+            drop(render_pass);
+            self.cache.borrow_mut().insert(
+                request.key(),
+                Rc::new(Thumbnail {
+                    bind_group: self.blit.bind(&view),
+                }),
+            );
+        }
+    }
+
+    /// The size in bytes of the cached thumbnails.
+    pub(super) fn gpu_memory(&self) -> u64 {
+        self.cache.borrow().len() as u64 * self.thumbnail_bytes()
+    }
+
+    /// Discards the least recently used thumbnails until at least the given number of bytes is
+    /// freed or none are left. They are rendered again when next asked for.
+    pub(super) fn evict(&self, bytes: u64) {
+        let mut cache = self.cache.borrow_mut();
+        let mut freed = 0;
+        while freed < bytes && cache.remove_least_recently_used() {
+            freed += self.thumbnail_bytes();
+        }
+    }
+
+    fn thumbnail_bytes(&self) -> u64 {
+        let size = wgpu::Extent3d {
+            width: THUMBNAIL_SIZE,
+            height: THUMBNAIL_SIZE,
+            depth_or_array_layers: 1,
+        };
+        gpu_memory::texture_bytes(size, self.texture_format)
+    }
+
+    /// Draws the thumbnail over the viewport of the render pass, scaling it to fit.
+    pub(super) fn draw<'a>(
+        &'a self,
+        render_pass: &mut wgpu::RenderPass<'a>,
+        thumbnail: &'a Thumbnail,
+    ) {
+        self.blit.draw_in(render_pass, &thumbnail.bind_group);
+    }
+}
+
+/// Keeps up to a number of values, discarding the one used least recently to make room for
+/// another.
+struct LruCache<K, V> {
+    capacity: usize,
+    /// The values and when they were last used, by the value of `clock` then.
+    entries: HashMap<K, (V, u64)>,
+    clock: u64,
+}
+
+impl<K: Copy + Eq + Hash, V> LruCache<K, V> {
+    fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            entries: HashMap::new(),
+            clock: 0,
+        }
+    }
+
+    /// Returns the value under the key, which counts as using it.
+    fn get(&mut self, key: &K) -> Option<&V> {
+        self.clock += 1;
+        let (value, last_used) = self.entries.get_mut(key)?;
+        *last_used = self.clock;
+        Some(value)
+    }
+
+    fn insert(&mut self, key: K, value: V) {
+        if self.entries.len() >= self.capacity && !self.entries.contains_key(&key) {
+            self.remove_least_recently_used();
+        }
+        self.clock += 1;
+        self.entries.insert(key, (value, self.clock));
+    }
+
+    fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Discards the value used least recently, returning whether there was one.
+    fn remove_least_recently_used(&mut self) -> bool {
+        let least_recently_used = self
+            .entries
+            .iter()
+            .min_by_key(|(_, (_, last_used))| *last_used)
+            .map(|(key, _)| *key);
+        least_recently_used.is_some_and(|key| self.entries.remove(&key).is_some())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{LruCache, ThumbnailRequest};
+    use crate::{colour_palette::Palette, controls::FractalType, fractal_view::ParameterBlock};
+    use googletest::prelude::*;
+
+    #[test]
+    fn cache_discards_least_recently_used() -> Result<()> {
+        let mut cache = LruCache::new(2);
+        cache.insert(1, "one");
+        cache.insert(2, "two");
+        cache.get(&1);
+
+        cache.insert(3, "three");
+
+        verify_that!(
+            (
+                cache.get(&1).copied(),
+                cache.get(&2).copied(),
+                cache.get(&3).copied()
+            ),
+            (some(eq("one")), none(), some(eq("three")))
+        )
+    }
+
+    #[test]
+    fn removing_least_recently_used_empties_cache_in_order_of_use() -> Result<()> {
+        let mut cache = LruCache::new(3);
+        cache.insert(1, "one");
+        cache.insert(2, "two");
+        cache.get(&1);
+
+        let removed = cache.remove_least_recently_used();
+        let remaining = (cache.len(), cache.get(&1).copied());
+        cache.remove_least_recently_used();
+        let exhausted = cache.remove_least_recently_used();
+
+        verify_that!(removed, eq(true))?;
+        verify_that!(remaining, (eq(1), some(eq("one"))))?;
+        verify_that!(exhausted, eq(false))
+    }
+
+    #[test]
+    fn replacing_value_keeps_others() -> Result<()> {
+        let mut cache = LruCache::new(2);
+        cache.insert(1, "one");
+        cache.insert(2, "two");
+
+        cache.insert(2, "zwei");
+
+        verify_that!(
+            (cache.get(&1).copied(), cache.get(&2).copied()),
+            (some(eq("one")), some(eq("zwei")))
+        )
+    }
+
+    #[test]
+    fn key_depends_on_fractal_and_every_setting() -> Result<()> {
+        let request = ThumbnailRequest {
+            fractal_type: FractalType::Mandelbrot,
+            block: ParameterBlock::default(),
+        };
+        let same = ThumbnailRequest {
+            fractal_type: FractalType::Mandelbrot,
+            block: ParameterBlock::default(),
+        };
+        let other_fractal = ThumbnailRequest {
+            fractal_type: FractalType::Multibrot,
+            ..request
+        };
+        let other_palette = ThumbnailRequest {
+            block: ParameterBlock::with_palette(Palette::Viridis),
+            ..request
+        };
+
+        verify_that!(
+            (
+                same.key(),
+                other_fractal.key() != request.key(),
+                other_palette.key() != request.key()
+            ),
+            (eq(request.key()), eq(true), eq(true))
+        )
+    }
+}
@@ -0,0 +1,154 @@
+use std::io::{self, Write};
+
+const HEADER_SIZE: u32 = 8;
+const ENTRY_SIZE: u32 = 12;
+
+// The types of the values of the fields of a TIFF directory.
+const SHORT: u16 = 3;
+const LONG: u16 = 4;
+const RATIONAL: u16 = 5;
+
+/// The value of ResolutionUnit for inches.
+const INCH: u32 = 2;
+/// The value of ExtraSamples for an alpha channel which is not premultiplied.
+const UNASSOCIATED_ALPHA: u32 = 2;
+
+/// Writes the tightly packed RGBA pixels as a baseline TIFF image, uncompressed in a single
+/// strip, which print shops accept everywhere, with its resolution in dots per inch.
+///
+/// The alpha channel is only written if some pixel is not opaque, since some print software
+/// treats it as a spot colour.
+pub(super) fn write(
+    writer: &mut impl Write,
+    pixels: &[u8],
+    width: u32,
+    height: u32,
+    dpi: u32,
+) -> io::Result<()> {
+    let alpha = pixels.chunks_exact(4).any(|pixel| pixel[3] < u8::MAX);
+    let samples: u16 = if alpha { 4 } else { 3 };
+    let data: Vec<u8> = if alpha {
+        pixels.to_vec()
+    } else {
+        pixels
+            .chunks_exact(4)
+            .flat_map(|pixel| [pixel[0], pixel[1], pixel[2]])
+            .collect()
+    };
+    let too_large = || io::Error::new(io::ErrorKind::InvalidInput, "Image too large for TIFF");
+    let data_size = u32::try_from(data.len()).map_err(|_| too_large())?;
+
+    let entry_count = if alpha { 14 } else { 13 };
+    let bits_offset = HEADER_SIZE + 2 + entry_count * ENTRY_SIZE + 4;
+    let x_resolution_offset = bits_offset + 2 * u32::from(samples);
+    let y_resolution_offset = x_resolution_offset + 8;
+    let data_offset = y_resolution_offset + 8;
+    data_offset.checked_add(data_size).ok_or_else(too_large)?;
+
+    // The fields in ascending order of their tags, as TIFF requires.
+    let mut entries = vec![
+        (256, LONG, 1, width),
+        (257, LONG, 1, height),
+        (258, SHORT, u32::from(samples), bits_offset),
+        // No compression.
+        (259, SHORT, 1, 1),
+        // RGB.
+        (262, SHORT, 1, 2),
+        (273, LONG, 1, data_offset),
+        (277, SHORT, 1, u32::from(samples)),
+        (278, LONG, 1, height),
+        (279, LONG, 1, data_size),
+        (282, RATIONAL, 1, x_resolution_offset),
+        (283, RATIONAL, 1, y_resolution_offset),
+        // The channels of each pixel lie together.
+        (284, SHORT, 1, 1),
+        (296, SHORT, 1, INCH),
+    ];
+    if alpha {
+        entries.push((338, SHORT, 1, UNASSOCIATED_ALPHA));
+    }
+
+    writer.write_all(b"II")?;
+    writer.write_all(&42u16.to_le_bytes())?;
+    writer.write_all(&HEADER_SIZE.to_le_bytes())?;
+    writer.write_all(&(entries.len() as u16).to_le_bytes())?;
+    for (tag, field_type, count, value) in entries {
+        writer.write_all(&u16::to_le_bytes(tag))?;
+        writer.write_all(&field_type.to_le_bytes())?;
+        writer.write_all(&u32::to_le_bytes(count))?;
+        // A single short lies in the first two bytes of the value, which little-endian puts
+        // there.
+        writer.write_all(&value.to_le_bytes())?;
+    }
+    // There is no further directory.
+    writer.write_all(&0u32.to_le_bytes())?;
+    for _ in 0..samples {
+        writer.write_all(&8u16.to_le_bytes())?;
+    }
+    for _ in 0..2 {
+        writer.write_all(&dpi.to_le_bytes())?;
+        writer.write_all(&1u32.to_le_bytes())?;
+    }
+    writer.write_all(&data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::write;
+    use googletest::prelude::*;
+
+    fn u32_at(bytes: &[u8], offset: usize) -> u32 {
+        u32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap())
+    }
+
+    /// The count and value or offset of the field of the first directory with the given tag.
+    fn field(bytes: &[u8], tag: u16) -> Option<(u32, u32)> {
+        let directory = u32_at(bytes, 4) as usize;
+        let count = u16::from_le_bytes([bytes[directory], bytes[directory + 1]]) as usize;
+        (0..count)
+            .map(|index| directory + 2 + 12 * index)
+            .find(|&entry| u16::from_le_bytes([bytes[entry], bytes[entry + 1]]) == tag)
+            .map(|entry| (u32_at(bytes, entry + 4), u32_at(bytes, entry + 8)))
+    }
+
+    #[test]
+    fn opaque_image_is_written_as_rgb_with_resolution() -> Result<()> {
+        let mut bytes = vec![];
+        write(&mut bytes, &[1, 2, 3, 255, 4, 5, 6, 255], 2, 1, 300)?;
+
+        let (_, strip) = field(&bytes, 273).unwrap();
+        let (_, x_resolution) = field(&bytes, 282).unwrap();
+        verify_that!(
+            (
+                &bytes[..4],
+                &bytes[strip as usize..],
+                field(&bytes, 277),
+                u32_at(&bytes, x_resolution as usize),
+                field(&bytes, 338)
+            ),
+            (
+                eq(b"II*\0"),
+                eq(&[1, 2, 3, 4, 5, 6]),
+                some(eq((1, 3))),
+                eq(300),
+                none()
+            )
+        )
+    }
+
+    #[test]
+    fn transparent_image_keeps_alpha() -> Result<()> {
+        let mut bytes = vec![];
+        write(&mut bytes, &[1, 2, 3, 0], 1, 1, 72)?;
+
+        let (_, strip) = field(&bytes, 273).unwrap();
+        verify_that!(
+            (
+                &bytes[strip as usize..],
+                field(&bytes, 277),
+                field(&bytes, 338)
+            ),
+            (eq(&[1, 2, 3, 0]), some(eq((1, 4))), some(eq((1, 2))))
+        )
+    }
+}
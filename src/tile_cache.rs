@@ -0,0 +1,350 @@
+use crate::{
+    gpu::Gpu,
+    iteration_data::{self, IterationData, BYTES_PER_PIXEL},
+};
+use iced::time::Instant;
+use iced_wgpu::wgpu;
+use std::{
+    cell::RefCell,
+    io::{self, Write},
+    path::PathBuf,
+    sync::mpsc,
+    time::{Duration, SystemTime},
+};
+
+/// How much disk space the tiles may take up before the least recently used are deleted.
+const DEFAULT_CAPACITY: u64 = 1 << 30;
+
+/// How long a view must rest before its iteration data is written to the cache, so that panning
+/// and zooming through a deep region does not write every frame of the way.
+const SETTLE_TIME: Duration = Duration::from_secs(1);
+
+/// Marks a tile file and the version of its layout.
+const MAGIC: &[u8; 4] = b"FXT1";
+const HEADER_LENGTH: usize = 12;
+
+/// Identifies the iteration data of one view by a hash of a description of everything on which
+/// it depends.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(super) struct TileKey(u64);
+
+impl TileKey {
+    pub(super) fn of(description: &str) -> Self {
+        Self(fnv1a(description))
+    }
+
+    fn file_name(self) -> String {
+        format!("{:016x}.tile", self.0)
+    }
+}
+
+/// Hashes the text with FNV-1a, which unlike the hasher of the standard library is the same in
+/// every build, so that files written by one version of the explorer are found by the next.
+pub(super) fn fnv1a(text: &str) -> u64 {
+    text.bytes().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x100000001b3)
+    })
+}
+
+/// Stores the iteration data of views as files in a directory, deleting those used least recently
+/// once they take up more than the capacity.
+#[derive(Clone, Debug)]
+pub(super) struct TileCache {
+    directory: PathBuf,
+    capacity: u64,
+}
+
+impl TileCache {
+    pub(super) fn new(directory: PathBuf, capacity: u64) -> Self {
+        Self {
+            directory,
+            capacity,
+        }
+    }
+
+    /// Opens the cache in the cache directory of the user, or returns `None` if there is none, as
+    /// on the web.
+    pub(super) fn open() -> Option<Self> {
+        #[cfg(not(target_arch = "wasm32"))]
+        return crate::storage::cache_directory()
+            .map(|directory| Self::new(directory.join("tiles"), DEFAULT_CAPACITY));
+        #[cfg(target_arch = "wasm32")]
+        None
+    }
+
+    pub(super) fn contains(&self, key: TileKey) -> bool {
+        self.path(key).exists()
+    }
+
+    /// Returns the tightly packed data stored under the key, if there is data for a target of the
+    /// given size. Reading a tile counts as using it.
+    pub(super) fn load(&self, key: TileKey, size: wgpu::Extent3d) -> Option<Vec<u8>> {
+        let path = self.path(key);
+        let mut data = std::fs::read(&path).ok()?;
+        let expected_length = (size.width * size.height * BYTES_PER_PIXEL) as usize;
+        let header = data.get(..HEADER_LENGTH)?;
+        if &header[..4] != MAGIC
+            || header[4..8] != size.width.to_le_bytes()
+            || header[8..12] != size.height.to_le_bytes()
+            || data.len() != HEADER_LENGTH + expected_length
+        {
+            return None;
+        }
+        let _ = std::fs::File::options()
+            .write(true)
+            .open(&path)
+            .and_then(|file| file.set_modified(SystemTime::now()));
+        data.drain(..HEADER_LENGTH);
+        Some(data)
+    }
+
+    /// Stores the tightly packed data for a target of the given size under the key, then deletes
+    /// the least recently used tiles beyond the capacity.
+    pub(super) fn save(&self, key: TileKey, size: wgpu::Extent3d, data: &[u8]) -> io::Result<()> {
+        std::fs::create_dir_all(&self.directory)?;
+        // Renaming the complete file keeps a partly written one from being read if the explorer
+        // stops while writing.
+        let path = self.path(key);
+        let temporary = path.with_extension("tile.tmp");
+        let mut file = std::fs::File::create(&temporary)?;
+        file.write_all(MAGIC)?;
+        file.write_all(&size.width.to_le_bytes())?;
+        file.write_all(&size.height.to_le_bytes())?;
+        file.write_all(data)?;
+        drop(file);
+        std::fs::rename(temporary, path)?;
+        self.evict()
+    }
+
+    fn evict(&self) -> io::Result<()> {
+        let mut tiles = vec![];
+        for entry in std::fs::read_dir(&self.directory)? {
+            let entry = entry?;
+            if entry
+                .path()
+                .extension()
+                .is_some_and(|extension| extension == "tile")
+            {
+                let metadata = entry.metadata()?;
+                tiles.push((metadata.modified()?, metadata.len(), entry.path()));
+            }
+        }
+        tiles.sort();
+        let mut total: u64 = tiles.iter().map(|(_, length, _)| length).sum();
+        for (_, length, path) in tiles {
+            if total <= self.capacity {
+                break;
+            }
+            std::fs::remove_file(path)?;
+            total -= length;
+        }
+        Ok(())
+    }
+
+    fn path(&self, key: TileKey) -> PathBuf {
+        self.directory.join(key.file_name())
+    }
+}
+
+/// Writes the iteration data of the view to a [`TileCache`] once the view has rested on it for
+/// [`SETTLE_TIME`], and finds it there when the same view is rendered again.
+pub(super) struct TilePersistence {
+    cache: TileCache,
+    state: RefCell<PersistenceState>,
+}
+
+#[derive(Default)]
+enum PersistenceState {
+    #[default]
+    Idle,
+    /// The data with the key became current at the given time.
+    Settling(TileKey, Instant),
+    /// The data with the key is being read back from the GPU.
+    Reading(ReadBack),
+    /// The data with the key is in the cache.
+    Stored(TileKey),
+}
+
+struct ReadBack {
+    key: TileKey,
+    size: wgpu::Extent3d,
+    buffer: wgpu::Buffer,
+    mapped: mpsc::Receiver<Result<(), wgpu::BufferAsyncError>>,
+}
+
+impl TilePersistence {
+    pub(super) fn new(cache: TileCache) -> Self {
+        Self {
+            cache,
+            state: RefCell::new(PersistenceState::Idle),
+        }
+    }
+
+    /// Returns the tightly packed data stored under the key for a target of the given size, if
+    /// any.
+    pub(super) fn restore(&self, key: TileKey, size: wgpu::Extent3d) -> Option<Vec<u8>> {
+        self.cache.load(key, size)
+    }
+
+    /// Advances the writing of the current iteration data, whose key and size are given if it is
+    /// up to date and worth storing. Returns whether the writing still needs frames to come.
+    ///
+    /// The file is written on a thread of its own, since a large view takes long enough to write
+    /// to stall a frame.
+    pub(super) fn poll(
+        &self,
+        gpu: &Gpu,
+        iteration_data: &IterationData,
+        current: Option<(TileKey, wgpu::Extent3d)>,
+        now: Instant,
+    ) -> bool {
+        let (state, pending) = match (self.state.take(), current) {
+            (PersistenceState::Reading(read_back), _) => {
+                gpu.device.poll(wgpu::Maintain::Poll);
+                match read_back.mapped.try_recv() {
+                    Err(mpsc::TryRecvError::Empty) => (PersistenceState::Reading(read_back), true),
+                    Ok(Ok(())) => {
+                        self.write(&read_back);
+                        (PersistenceState::Stored(read_back.key), false)
+                    }
+                    Ok(Err(_)) | Err(mpsc::TryRecvError::Disconnected) => {
+                        (PersistenceState::Idle, false)
+                    }
+                }
+            }
+            (_, None) => (PersistenceState::Idle, false),
+            (PersistenceState::Stored(stored), Some((key, _))) if stored == key => {
+                (PersistenceState::Stored(stored), false)
+            }
+            (PersistenceState::Settling(settling, since), Some((key, size)))
+                if settling == key && now - since >= SETTLE_TIME =>
+            {
+                match self.read_back(gpu, iteration_data, key, size) {
+                    Some(read_back) => (PersistenceState::Reading(read_back), true),
+                    None => (PersistenceState::Idle, false),
+                }
+            }
+            (PersistenceState::Settling(settling, since), Some((key, _))) if settling == key => {
+                (PersistenceState::Settling(settling, since), true)
+            }
+            (_, Some((key, _))) if self.cache.contains(key) => {
+                (PersistenceState::Stored(key), false)
+            }
+            (_, Some((key, _))) => (PersistenceState::Settling(key, now), true),
+        };
+        self.state.replace(state);
+        pending
+    }
+
+    fn read_back(
+        &self,
+        gpu: &Gpu,
+        iteration_data: &IterationData,
+        key: TileKey,
+        size: wgpu::Extent3d,
+    ) -> Option<ReadBack> {
+        let mut encoder = gpu
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+        let buffer = iteration_data.read_back(&mut encoder)?;
+        gpu.queue.submit(Some(encoder.finish()));
+        let (sender, mapped) = mpsc::channel();
+        buffer
+            .slice(..)
+            .map_async(wgpu::MapMode::Read, move |result| {
+                let _ = sender.send(result);
+            });
+        Some(ReadBack {
+            key,
+            size,
+            buffer,
+            mapped,
+        })
+    }
+
+    fn write(&self, read_back: &ReadBack) {
+        let unpadded_bytes_per_row = (read_back.size.width * BYTES_PER_PIXEL) as usize;
+        let padded_bytes_per_row = iteration_data::padded_bytes_per_row(read_back.size.width);
+        let data: Vec<u8> = read_back
+            .buffer
+            .slice(..)
+            .get_mapped_range()
+            .chunks(padded_bytes_per_row as usize)
+            .flat_map(|row| &row[..unpadded_bytes_per_row])
+            .copied()
+            .collect();
+        read_back.buffer.unmap();
+        let (cache, key, size) = (self.cache.clone(), read_back.key, read_back.size);
+        std::thread::spawn(move || {
+            if let Err(error) = cache.save(key, size, &data) {
+                tracing::warn!("Could not write tile to cache: {error}");
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{TileCache, TileKey};
+    use googletest::prelude::*;
+    use iced_wgpu::wgpu;
+
+    const SIZE: wgpu::Extent3d = wgpu::Extent3d {
+        width: 2,
+        height: 1,
+        depth_or_array_layers: 1,
+    };
+
+    fn cache_in(name: &str, capacity: u64) -> TileCache {
+        let directory = std::env::temp_dir().join(format!(
+            "fractal_explorer_tiles_{name}_{}",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_dir_all(&directory);
+        TileCache::new(directory, capacity)
+    }
+
+    #[test]
+    fn key_is_same_in_every_build() -> Result<()> {
+        verify_that!(TileKey::of("a"), eq(TileKey(0xaf63dc4c8601ec8c)))
+    }
+
+    #[test]
+    fn saved_tile_loads_for_same_size_only() -> Result<()> {
+        let cache = cache_in("round_trip", u64::MAX);
+        let data: Vec<u8> = (0..32).collect();
+        let key = TileKey::of("view");
+
+        cache.save(key, SIZE, &data)?;
+
+        let other_size = wgpu::Extent3d {
+            width: 1,
+            height: 2,
+            ..SIZE
+        };
+        verify_that!(cache.load(key, SIZE), some(eq(data.clone())))?;
+        verify_that!(cache.load(key, other_size), none())?;
+        verify_that!(cache.load(TileKey::of("other view"), SIZE), none())
+    }
+
+    #[test]
+    fn saving_beyond_capacity_deletes_least_recently_used() -> Result<()> {
+        // Room for two tiles of two pixels with their headers.
+        let cache = cache_in("eviction", 2 * (12 + 32));
+        let data = vec![0; 32];
+        let [first, second, third] = ["first", "second", "third"].map(TileKey::of);
+
+        cache.save(first, SIZE, &data)?;
+        std::thread::sleep(std::time::Duration::from_millis(20));
+        cache.save(second, SIZE, &data)?;
+        std::thread::sleep(std::time::Duration::from_millis(20));
+        cache.load(first, SIZE);
+        std::thread::sleep(std::time::Duration::from_millis(20));
+        cache.save(third, SIZE, &data)?;
+
+        verify_that!(
+            [first, second, third].map(|key| cache.contains(key)),
+            eq([true, false, true])
+        )
+    }
+}
@@ -0,0 +1,452 @@
+use crate::df64;
+use cgmath::{InnerSpace, Matrix2, Matrix3, Rad, SquareMatrix, Vector2};
+use iced::{Point, Size};
+
+/// The size of the viewport in the coordinates which a view transform maps onto the plane, in
+/// which it spans `[-1, 1]` on both axes with y pointing up.
+const VIEWPORT_SIZE: f32 = 2.0;
+
+/// The smallest scale which the matrix holds as it is. Below it, the squares of the entries of the
+/// matrix approach the smallest normal `f32`, so the scale moves into the exponent.
+const MIN_UNSCALED: f64 = 1.0 / (1u64 << 60) as f64;
+
+/// Maps the viewport onto the complex plane, keeping the digits of the centre beyond the
+/// precision of `f32` for the double-float mode.
+///
+/// Points of the viewport are given as offsets from its centre in fractions of its width and
+/// height, so that they span `[-0.5, 0.5]` on both axes with y pointing up.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(super) struct ViewTransform {
+    /// Maps the viewport in the coordinates of [`VIEWPORT_SIZE`] onto the plane, as uploaded to
+    /// the GPU.
+    matrix: Matrix3<f32>,
+    /// The part of the centre of the view which `matrix` leaves out.
+    centre_residual: Vector2<f32>,
+    /// The power of two by which the scale and rotation of `matrix` are multiplied, which is only
+    /// non-zero for views too small for the scale to be held in `f32`.
+    scale_exponent: i32,
+}
+
+impl ViewTransform {
+    pub(super) fn new(matrix: Matrix3<f32>) -> Self {
+        Self {
+            matrix,
+            centre_residual: Vector2::new(0.0, 0.0),
+            scale_exponent: 0,
+        }
+    }
+
+    /// Frames the view on the given point of the plane, with `half_width` being the distance from
+    /// the centre to the edges of the viewport.
+    pub(super) fn looking_at(centre: Vector2<f64>, half_width: f64) -> Self {
+        let (translation, centre_residual) = df64::split_point(centre);
+        let (scale, scale_exponent) = split_scale(half_width);
+        Self {
+            matrix: Matrix3::from_translation(translation) * Matrix3::from_scale(scale),
+            centre_residual,
+            scale_exponent,
+        }
+    }
+
+    /// Takes the scale and rotation of the matrix, multiplied by two to the power of the exponent,
+    /// but centres the view on the given point, keeping its digits beyond the precision of `f32` in
+    /// the residual.
+    pub(super) fn centred_on(
+        matrix: Matrix3<f32>,
+        scale_exponent: i32,
+        centre: Vector2<f64>,
+    ) -> Self {
+        let (translation, centre_residual) = df64::split_point(centre);
+        let mut transform = Self {
+            matrix: Matrix3 {
+                z: translation.extend(1.0),
+                ..matrix
+            },
+            centre_residual,
+            scale_exponent,
+        };
+        transform.normalize_scale();
+        transform
+    }
+
+    pub(super) fn matrix(&self) -> Matrix3<f32> {
+        self.matrix
+    }
+
+    pub(super) fn centre_residual(&self) -> Vector2<f32> {
+        self.centre_residual
+    }
+
+    pub(super) fn scale_exponent(&self) -> i32 {
+        self.scale_exponent
+    }
+
+    /// The centre of the view on the plane, including the residual beyond the precision of the
+    /// matrix.
+    pub(super) fn centre(&self) -> Vector2<f64> {
+        self.matrix.z.truncate().cast::<f64>().unwrap() + self.centre_residual.cast().unwrap()
+    }
+
+    /// The distance on the plane from the centre of the view to the edges of the viewport.
+    pub(super) fn half_width(&self) -> f64 {
+        self.matrix.x.truncate().cast::<f64>().unwrap().magnitude() * 2f64.powi(self.scale_exponent)
+    }
+
+    /// Moves the view by the given fraction of the width and height of the viewport.
+    pub(super) fn translate(&mut self, displacement: Vector2<f32>) {
+        self.move_centre(VIEWPORT_SIZE * displacement);
+    }
+
+    /// Scales the view by the given factor about the given point of the viewport, which stays in
+    /// place.
+    pub(super) fn zoom(&mut self, factor: f32, on_point: Vector2<f32>) {
+        // Scaling about the point moves the centre towards it by the complement of the factor.
+        self.move_centre((1.0 - factor) * VIEWPORT_SIZE * on_point);
+        self.matrix.x *= factor;
+        self.matrix.y *= factor;
+        self.normalize_scale();
+    }
+
+    /// Narrows the view to the rectangle of the viewport between the given corners, which then
+    /// fills the whole viewport.
+    ///
+    /// The axes are scaled apart, so the view is only undistorted on a target whose aspect ratio
+    /// is that of the rectangle, as is the case for a tile of a larger image.
+    pub(super) fn crop(&mut self, bottom_left: Vector2<f32>, top_right: Vector2<f32>) {
+        self.move_centre(VIEWPORT_SIZE * (bottom_left + top_right) / 2.0);
+        let size = top_right - bottom_left;
+        self.matrix.x *= size.x;
+        self.matrix.y *= size.y;
+        self.normalize_scale();
+    }
+
+    /// Moves the centre of the view by the given offset in the coordinates of the matrix, keeping
+    /// the part beyond the precision of `f32` in the residual.
+    fn move_centre(&mut self, offset: Vector2<f32>) {
+        let centre = self.centre() + self.scale() * offset.cast().unwrap();
+        let (translation, residual) = df64::split_point(centre);
+        self.matrix.z = translation.extend(1.0);
+        self.centre_residual = residual;
+    }
+
+    /// Moves as much of the scale between the matrix and the exponent as keeps the matrix within
+    /// the precision of `f32`, leaving the exponent at zero wherever possible.
+    fn normalize_scale(&mut self) {
+        let (_, scale_exponent) = split_scale(self.half_width());
+        let factor = 2f64.powi(self.scale_exponent - scale_exponent);
+        for column in [&mut self.matrix.x, &mut self.matrix.y] {
+            *column = (column.cast::<f64>().unwrap() * factor).cast().unwrap();
+        }
+        self.scale_exponent = scale_exponent;
+    }
+
+    /// The point of the plane at the given point of the viewport.
+    pub(super) fn point_at(&self, offset: Vector2<f32>) -> Vector2<f64> {
+        let offset = (VIEWPORT_SIZE * offset).cast::<f64>().unwrap();
+        self.centre() + self.scale() * offset
+    }
+
+    /// The point of the viewport at the given point of the plane, the inverse of
+    /// [`Self::point_at`]. Degenerate transforms map every point onto the centre.
+    pub(super) fn offset_of(&self, point: Vector2<f64>) -> Vector2<f32> {
+        let offset = self
+            .scale()
+            .invert()
+            .map_or(Vector2::new(0.0, 0.0), |inverse| {
+                inverse * (point - self.centre())
+            });
+        offset.cast::<f32>().unwrap() / VIEWPORT_SIZE
+    }
+
+    /// The part of the matrix which scales and rotates the view, including the exponent.
+    fn scale(&self) -> Matrix2<f64> {
+        Matrix2::from_cols(
+            self.matrix.x.truncate().cast().unwrap(),
+            self.matrix.y.truncate().cast().unwrap(),
+        ) * 2f64.powi(self.scale_exponent)
+    }
+}
+
+/// Splits a scale into the part held by the matrix of a view transform and the power of two by
+/// which it is multiplied. Scales too small for the matrix are split into a mantissa in `[1, 2)`
+/// and an exponent, while all others are held as they are.
+pub(super) fn split_scale(scale: f64) -> (f32, i32) {
+    if scale >= MIN_UNSCALED || scale <= 0.0 {
+        return (scale as f32, 0);
+    }
+    let exponent = scale.log2().floor() as i32;
+    ((scale * 2f64.powi(-exponent)) as f32, exponent)
+}
+
+/// The point of the viewport at the given point of a screen of the given size, such as a canvas or
+/// the window, whose y axis points down.
+pub(super) fn from_screen(point: Point, size: Size) -> Vector2<f32> {
+    Vector2::new(point.x / size.width - 0.5, 0.5 - point.y / size.height)
+}
+
+/// The point of a screen of the given size at the given point of the viewport, the inverse of
+/// [`from_screen`].
+pub(super) fn to_screen(offset: Vector2<f32>, size: Size) -> Point {
+    Point::new(
+        (offset.x + 0.5) * size.width,
+        (0.5 - offset.y) * size.height,
+    )
+}
+
+/// The centre, scale and rotation of a view transform, which maps the viewport onto the complex
+/// plane by a similarity transformation.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(super) struct Similarity {
+    pub(super) centre: Vector2<f32>,
+    pub(super) scale: f32,
+    pub(super) angle: f32,
+}
+
+impl From<Matrix3<f32>> for Similarity {
+    fn from(transform: Matrix3<f32>) -> Self {
+        let x = transform.x.truncate();
+        Self {
+            centre: transform.z.truncate(),
+            scale: x.magnitude(),
+            angle: x.y.atan2(x.x),
+        }
+    }
+}
+
+impl From<Similarity> for Matrix3<f32> {
+    fn from(similarity: Similarity) -> Self {
+        Matrix3::from_translation(similarity.centre)
+            * Matrix3::from_angle_z(Rad(similarity.angle))
+            * Matrix3::from_scale(similarity.scale)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{from_screen, to_screen, Similarity, ViewTransform};
+    use cgmath::{Matrix3, Rad, Vector2};
+    use googletest::prelude::*;
+    use iced::{Point, Size};
+    use proptest::{
+        prop_assert,
+        test_runner::{Config, TestRunner},
+    };
+
+    const PROPERTY_TEST_CASES: u32 = 256;
+
+    fn rotated(angle: f32) -> ViewTransform {
+        ViewTransform::new(
+            Matrix3::from_translation(Vector2::new(-0.75, 0.1))
+                * Matrix3::from_angle_z(Rad(angle))
+                * Matrix3::from_scale(0.5),
+        )
+    }
+
+    #[test]
+    fn translation_moves_by_fraction_of_viewport() -> Result<()> {
+        let mut transform = ViewTransform::looking_at(Vector2::new(-0.75, 0.0), 1.0);
+
+        transform.translate(Vector2::new(0.25, -0.5));
+
+        verify_that!(
+            (transform.centre().x, transform.centre().y),
+            (near(-0.25, 1e-6), near(-1.0, 1e-6))
+        )
+    }
+
+    #[test]
+    fn translation_follows_rotation() -> Result<()> {
+        let mut transform = rotated(std::f32::consts::FRAC_PI_2);
+
+        transform.translate(Vector2::new(0.5, 0.0));
+
+        verify_that!(
+            (transform.centre().x, transform.centre().y),
+            (near(-0.75, 1e-6), near(0.6, 1e-6))
+        )
+    }
+
+    #[test]
+    fn small_translations_accumulate_in_residual() -> Result<()> {
+        let mut transform = ViewTransform::looking_at(Vector2::new(-0.75, 0.0), 1e-9);
+
+        for _ in 0..4 {
+            transform.translate(Vector2::new(0.25, 0.0));
+        }
+
+        verify_that!(
+            (
+                transform.centre().x,
+                transform.matrix().z.x,
+                transform.centre_residual().x
+            ),
+            (near(-0.75 + 2e-9, 1e-15), eq(-0.75), near(2e-9, 1e-15))
+        )
+    }
+
+    #[test]
+    fn zoom_scales_about_point() -> Result<()> {
+        let mut transform = ViewTransform::looking_at(Vector2::new(-0.75, 0.1), 0.01);
+        let point = transform.point_at(Vector2::new(0.5, 0.5));
+
+        transform.zoom(0.5, Vector2::new(0.5, 0.5));
+
+        verify_that!(
+            (
+                transform.half_width(),
+                transform.centre().x,
+                transform.centre().y,
+                transform.point_at(Vector2::new(0.5, 0.5)).x,
+                transform.point_at(Vector2::new(0.5, 0.5)).y
+            ),
+            (
+                near(0.005, 1e-9),
+                near(-0.745, 1e-6),
+                near(0.105, 1e-6),
+                near(point.x, 1e-6),
+                near(point.y, 1e-6)
+            )
+        )
+    }
+
+    #[test]
+    fn zoom_keeps_any_point_in_place() -> Result<()> {
+        let result = TestRunner::new(Config::with_cases(PROPERTY_TEST_CASES)).run(
+            &(-0.5f32..0.5, -0.5f32..0.5, 0.1f32..10.0, -3.0f32..3.0),
+            |(x, y, factor, angle)| {
+                let mut transform = rotated(angle);
+                let on_point = Vector2::new(x, y);
+                let before = transform.point_at(on_point);
+                transform.zoom(factor, on_point);
+                let after = transform.point_at(on_point);
+                prop_assert!(
+                    (before - after).x.abs() < 1e-5 && (before - after).y.abs() < 1e-5,
+                    "{before:?} moved to {after:?}"
+                );
+                Ok(())
+            },
+        );
+
+        verify_that!(result, ok(anything()))
+    }
+
+    #[test]
+    fn crop_maps_viewport_onto_rectangle() -> Result<()> {
+        let mut transform = rotated(0.5);
+        let (bottom_left, top_right) = (Vector2::new(-0.5, 0.0), Vector2::new(-0.25, 0.5));
+        let expected = [bottom_left, top_right].map(|corner| transform.point_at(corner));
+
+        transform.crop(bottom_left, top_right);
+
+        let actual = [Vector2::new(-0.5, -0.5), Vector2::new(0.5, 0.5)]
+            .map(|corner| transform.point_at(corner));
+        verify_that!(
+            (actual[0].x, actual[0].y, actual[1].x, actual[1].y),
+            (
+                near(expected[0].x, 1e-6),
+                near(expected[0].y, 1e-6),
+                near(expected[1].x, 1e-6),
+                near(expected[1].y, 1e-6)
+            )
+        )
+    }
+
+    #[test]
+    fn offset_of_inverts_point_at() -> Result<()> {
+        let result = TestRunner::new(Config::with_cases(PROPERTY_TEST_CASES)).run(
+            &(-0.5f32..0.5, -0.5f32..0.5, -3.0f32..3.0),
+            |(x, y, angle)| {
+                let transform = rotated(angle);
+                let offset = transform.offset_of(transform.point_at(Vector2::new(x, y)));
+                prop_assert!(
+                    (offset.x - x).abs() < 1e-5 && (offset.y - y).abs() < 1e-5,
+                    "({x}, {y}) came back as {offset:?}"
+                );
+                Ok(())
+            },
+        );
+
+        verify_that!(result, ok(anything()))
+    }
+
+    #[test]
+    fn point_at_resolves_digits_beyond_f32() -> Result<()> {
+        let transform = ViewTransform::looking_at(Vector2::new(-0.75 + 1e-12, 0.1), 1e-12);
+
+        let right_edge = transform.point_at(Vector2::new(0.5, 0.0));
+
+        verify_that!(right_edge.x, near(-0.75 + 2e-12, 1e-18))
+    }
+
+    #[test]
+    fn scale_below_f32_range_is_kept_in_exponent() -> Result<()> {
+        let mut transform = ViewTransform::looking_at(Vector2::new(2e-50, -1e-50), 1e-50);
+
+        transform.zoom(0.5, Vector2::new(0.0, 0.0));
+
+        verify_that!(
+            (
+                transform.half_width(),
+                transform.point_at(Vector2::new(0.5, 0.0)).x - transform.centre().x,
+                transform.scale_exponent()
+            ),
+            (near(0.5e-50, 1e-57), near(0.5e-50, 1e-57), lt(-150))
+        )
+    }
+
+    #[test]
+    fn degenerate_transform_maps_points_onto_centre() -> Result<()> {
+        let transform = ViewTransform::looking_at(Vector2::new(-0.75, 0.1), 0.0);
+
+        let offset = transform.offset_of(Vector2::new(1.0, 1.0));
+
+        verify_that!((offset.x, offset.y), (eq(0.0), eq(0.0)))
+    }
+
+    #[test]
+    fn screen_corners_map_onto_viewport_corners() -> Result<()> {
+        let size = Size::new(200.0, 100.0);
+
+        verify_that!(
+            (
+                from_screen(Point::ORIGIN, size),
+                from_screen(Point::new(200.0, 100.0), size),
+                from_screen(Point::new(100.0, 50.0), size)
+            ),
+            (
+                eq(Vector2::new(-0.5, 0.5)),
+                eq(Vector2::new(0.5, -0.5)),
+                eq(Vector2::new(0.0, 0.0))
+            )
+        )
+    }
+
+    #[test]
+    fn to_screen_inverts_from_screen() -> Result<()> {
+        let size = Size::new(640.0, 480.0);
+        let point = Point::new(123.0, 456.0);
+
+        let round_trip = to_screen(from_screen(point, size), size);
+
+        verify_that!(
+            (round_trip.x, round_trip.y),
+            (near(point.x, 1e-4), near(point.y, 1e-4))
+        )
+    }
+
+    #[test]
+    fn similarity_recovers_centre_scale_and_angle() -> Result<()> {
+        let similarity = Similarity {
+            centre: Vector2::new(-0.75, 0.1),
+            scale: 0.5,
+            angle: 1.0,
+        };
+
+        let recovered = Similarity::from(Matrix3::from(similarity));
+
+        verify_that!(
+            (recovered.centre, recovered.scale, recovered.angle),
+            (eq(similarity.centre), near(0.5, 1e-6), near(1.0, 1e-6))
+        )
+    }
+}
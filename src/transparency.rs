@@ -0,0 +1,42 @@
+use serde::{Deserialize, Serialize};
+use std::fmt::Display;
+
+/// Which points of an escape-time fractal are exported with an alpha of zero, so that the image
+/// can be laid over others as a silhouette of the set or of its surroundings.
+///
+/// Applied by `with_transparency` in `frag.wgsl`, after the post-processing. The colour of a
+/// transparent point is kept, so that tools which ignore the alpha still show the whole image.
+/// Other fractals stay opaque.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub(super) enum Transparency {
+    #[default]
+    Opaque,
+    /// The points which never escape, leaving the glow around the set.
+    Interior,
+    /// The points which escape, leaving the set itself.
+    Exterior,
+}
+
+impl Transparency {
+    pub(super) const ALL: [Transparency; 3] = [Self::Opaque, Self::Interior, Self::Exterior];
+
+    /// The value of `transparency` in the uniform block. Must match the constants
+    /// `TRANSPARENT_*` in `frag.wgsl`.
+    pub(super) fn code(self) -> u32 {
+        match self {
+            Transparency::Opaque => 0,
+            Transparency::Interior => 1,
+            Transparency::Exterior => 2,
+        }
+    }
+}
+
+impl Display for Transparency {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Transparency::Opaque => write!(f, "Opaque"),
+            Transparency::Interior => write!(f, "Interior"),
+            Transparency::Exterior => write!(f, "Exterior"),
+        }
+    }
+}
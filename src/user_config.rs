@@ -0,0 +1,317 @@
+use crate::{
+    controls::{FractalType, UiScale},
+    shortcuts::{self, BoundKey, Keymap},
+};
+use serde::Deserialize;
+use std::{
+    collections::BTreeMap,
+    io,
+    ops::RangeInclusive,
+    path::{Path, PathBuf},
+};
+
+/// The name of the file within the configuration directory of the user.
+#[cfg_attr(target_arch = "wasm32", allow(dead_code))]
+pub(super) const FILE_NAME: &str = "config.toml";
+
+const ZOOM_SPEED_RANGE: RangeInclusive<f32> = 0.1..=10.0;
+
+/// The scales of the controls in percent which the file may choose, beyond the few which the
+/// controls offer.
+const UI_SCALE_RANGE: RangeInclusive<u32> = 50..=300;
+
+/// Settings for power users, which are kept in a TOML file rather than offered by the controls,
+/// and applied again whenever the file changes while the explorer runs.
+///
+/// The file looks as follows, where every setting may be left out:
+///
+/// ```toml
+/// default_fractal = "Multibrot"
+/// zoom_speed = 1.5
+/// palette_directory = "~/palettes"
+/// ui_scale = 110
+///
+/// [keys]
+/// zoom_in = ["i", "PageUp"]
+/// zoom_out = "o"
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub(super) struct UserConfig {
+    pub(super) keymap: Keymap,
+    /// The fractal shown when the explorer starts without a stored session, which it also
+    /// switches to whenever this changes in the file.
+    pub(super) default_fractal: Option<FractalType>,
+    /// How far each turn of the scroll wheel and each press of a zoom key zooms, as a power of
+    /// the default zoom.
+    pub(super) zoom_speed: f32,
+    /// Where the dialog for the image of a palette opens.
+    pub(super) palette_directory: Option<PathBuf>,
+    /// The scale of the controls, which replaces the one chosen in the controls whenever this
+    /// changes in the file.
+    pub(super) ui_scale: Option<UiScale>,
+}
+
+impl Default for UserConfig {
+    fn default() -> Self {
+        Self {
+            keymap: Keymap::default(),
+            default_fractal: None,
+            zoom_speed: 1.0,
+            palette_directory: None,
+            ui_scale: None,
+        }
+    }
+}
+
+/// The file as written, before its values are checked.
+#[derive(Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct ConfigFile {
+    default_fractal: Option<String>,
+    zoom_speed: Option<f32>,
+    palette_directory: Option<PathBuf>,
+    ui_scale: Option<u32>,
+    /// The keys bound to each action by its name, as one key or a list of them.
+    keys: BTreeMap<String, Keys>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Keys {
+    One(String),
+    Several(Vec<String>),
+}
+
+#[derive(Debug, thiserror::Error)]
+pub(super) enum ConfigError {
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    #[error("{}", .0.message())]
+    Syntax(#[from] toml::de::Error),
+    #[error("Unknown fractal \"{0}\"")]
+    UnknownFractal(String),
+    #[error("The zoom speed must lie between 0.1 and 10, got {0}")]
+    ZoomSpeed(f32),
+    #[error("The UI scale must lie between 50 and 300 percent, got {0}")]
+    UiScale(u32),
+    #[error("Unknown action \"{0}\" in [keys]")]
+    UnknownAction(String),
+    #[error("Unknown key \"{key}\" for {action}")]
+    UnknownKey { action: String, key: String },
+}
+
+/// Reads the file at the path, whose absence leaves every setting at its default.
+#[cfg_attr(target_arch = "wasm32", allow(dead_code))]
+pub(super) fn load(path: &Path) -> Result<UserConfig, ConfigError> {
+    match std::fs::read_to_string(path) {
+        Ok(text) => parse(&text, path.parent().unwrap_or(Path::new(""))),
+        Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(UserConfig::default()),
+        Err(error) => Err(error.into()),
+    }
+}
+
+/// Parses the contents of a file in the given directory, against which relative paths are
+/// resolved. A leading `~` stands for the home directory.
+fn parse(text: &str, directory: &Path) -> Result<UserConfig, ConfigError> {
+    let file: ConfigFile = toml::from_str(text)?;
+    let default_fractal = file
+        .default_fractal
+        .map(|name| {
+            FractalType::ALL
+                .into_iter()
+                .find(|fractal_type| fractal_type.to_string().eq_ignore_ascii_case(&name))
+                .ok_or(ConfigError::UnknownFractal(name))
+        })
+        .transpose()?;
+    let zoom_speed = file.zoom_speed.unwrap_or(1.0);
+    if !ZOOM_SPEED_RANGE.contains(&zoom_speed) {
+        return Err(ConfigError::ZoomSpeed(zoom_speed));
+    }
+    let ui_scale = file
+        .ui_scale
+        .map(|percent| {
+            UI_SCALE_RANGE
+                .contains(&percent)
+                .then_some(UiScale(percent))
+                .ok_or(ConfigError::UiScale(percent))
+        })
+        .transpose()?;
+    let mut keymap = Keymap::default();
+    for (name, keys) in file.keys {
+        let action = shortcuts::bindable_action(&name)
+            .ok_or_else(|| ConfigError::UnknownAction(name.clone()))?;
+        let keys = match keys {
+            Keys::One(key) => vec![key],
+            Keys::Several(keys) => keys,
+        };
+        let keys = keys
+            .into_iter()
+            .map(|key| {
+                BoundKey::parse(&key).ok_or_else(|| ConfigError::UnknownKey {
+                    action: name.clone(),
+                    key,
+                })
+            })
+            .collect::<Result<_, _>>()?;
+        keymap.bind(action, keys);
+    }
+    Ok(UserConfig {
+        keymap,
+        default_fractal,
+        zoom_speed,
+        palette_directory: file.palette_directory.map(|path| resolve(&path, directory)),
+        ui_scale,
+    })
+}
+
+fn resolve(path: &Path, directory: &Path) -> PathBuf {
+    if let Ok(relative) = path.strip_prefix("~") {
+        if let Some(home) = std::env::var_os("HOME").or_else(|| std::env::var_os("USERPROFILE")) {
+            return PathBuf::from(home).join(relative);
+        }
+    }
+    directory.join(path)
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub(super) use watcher::ConfigWatcher;
+
+#[cfg(not(target_arch = "wasm32"))]
+mod watcher {
+    use super::load;
+    use crate::controls::Message;
+    use std::{
+        io,
+        path::PathBuf,
+        sync::mpsc,
+        thread::{self, JoinHandle},
+        time::Duration,
+    };
+
+    /// How often the file is checked for changes. Polling the time at which it was modified needs
+    /// no support from the platform, and is quick enough for someone editing the file.
+    const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+    /// Loads the configuration file and loads it again whenever it is modified, created or
+    /// deleted.
+    pub(crate) struct ConfigWatcher {
+        receiver: mpsc::Receiver<Message>,
+        _worker: JoinHandle<()>,
+    }
+
+    impl ConfigWatcher {
+        /// Starts watching the file at the path, calling `wake` whenever it was loaded so that the
+        /// event loop picks it up with [`ConfigWatcher::poll`].
+        pub(crate) fn start(path: PathBuf, wake: impl Fn() + Send + 'static) -> io::Result<Self> {
+            let (sender, receiver) = mpsc::channel();
+            let worker = thread::Builder::new()
+                .name("Config watcher".into())
+                .spawn(move || {
+                    let mut loaded_version = None;
+                    loop {
+                        let version = std::fs::metadata(&path)
+                            .and_then(|metadata| metadata.modified())
+                            .ok();
+                        if loaded_version != Some(version) {
+                            loaded_version = Some(version);
+                            let message = match load(&path) {
+                                Ok(config) => Message::UserConfigLoaded(Box::new(config)),
+                                Err(error) => {
+                                    tracing::warn!("Ignoring {}: {error}", path.display());
+                                    Message::UserConfigFailed(format!(
+                                        "Ignored {}: {error}",
+                                        path.display()
+                                    ))
+                                }
+                            };
+                            if sender.send(message).is_err() {
+                                return;
+                            }
+                            wake();
+                        }
+                        thread::sleep(POLL_INTERVAL);
+                    }
+                })?;
+            Ok(Self {
+                receiver,
+                _worker: worker,
+            })
+        }
+
+        /// Returns the messages for the controls about the loads since the last call.
+        pub(crate) fn poll(&self) -> Vec<Message> {
+            self.receiver.try_iter().collect()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse, ConfigError, UserConfig};
+    use crate::{
+        controls::{FractalType, UiScale},
+        shortcuts::{BoundKey, KeyAction, Keymap},
+    };
+    use googletest::prelude::*;
+    use std::path::{Path, PathBuf};
+
+    #[test]
+    fn empty_file_keeps_defaults() -> Result<()> {
+        verify_that!(parse("", Path::new("/config"))?, eq(UserConfig::default()))
+    }
+
+    #[test]
+    fn parses_every_setting() -> Result<()> {
+        let config = parse(
+            r#"
+            default_fractal = "multibrot"
+            zoom_speed = 2.0
+            palette_directory = "palettes"
+            ui_scale = 110
+
+            [keys]
+            zoom_in = ["i", "PageUp"]
+            "#,
+            Path::new("/config"),
+        )?;
+        let mut keymap = Keymap::default();
+        keymap.bind(
+            KeyAction::ZoomIn,
+            vec![
+                BoundKey::Character("i".into()),
+                BoundKey::parse("PageUp").unwrap(),
+            ],
+        );
+
+        verify_that!(
+            config,
+            eq(UserConfig {
+                keymap,
+                default_fractal: Some(FractalType::Multibrot),
+                zoom_speed: 2.0,
+                palette_directory: Some(PathBuf::from("/config/palettes")),
+                ui_scale: Some(UiScale(110)),
+            })
+        )
+    }
+
+    #[test]
+    fn rejects_unknown_names() -> Result<()> {
+        let error = |text| parse(text, Path::new("/config")).err();
+
+        verify_that!(
+            (
+                error("zoom_sped = 2.0"),
+                error("[keys]\nzoom = \"z\""),
+                error("[keys]\nzoom_in = \"Shift+Z\""),
+                error("zoom_speed = 0.0")
+            ),
+            (
+                some(matches_pattern!(ConfigError::Syntax(_))),
+                some(matches_pattern!(ConfigError::UnknownAction(eq("zoom")))),
+                some(matches_pattern!(ConfigError::UnknownKey { .. })),
+                some(matches_pattern!(ConfigError::ZoomSpeed(_)))
+            )
+        )
+    }
+}
@@ -0,0 +1,110 @@
+use std::fmt::Display;
+
+/// Changes to the iterated value before each step, which turn every fractal into a family of
+/// variants. Taking the absolute value of both parts turns the Mandelbrot set into the Burning
+/// Ship, and conjugating it into the Tricorn. Spinning it by the time of the uniform block animates
+/// the fractal while the time plays. Applied by `vary` in `frag.wgsl`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(super) struct Variation {
+    pub(super) abs_re: bool,
+    pub(super) abs_im: bool,
+    /// Applied after the absolute values.
+    pub(super) conjugate: bool,
+    /// Rotates the value by as many radians as seconds of time have played, after the other flags.
+    pub(super) spin: bool,
+}
+
+impl Variation {
+    pub(super) fn get(&self, flag: VariationFlag) -> bool {
+        match flag {
+            VariationFlag::AbsRe => self.abs_re,
+            VariationFlag::AbsIm => self.abs_im,
+            VariationFlag::Conjugate => self.conjugate,
+            VariationFlag::Spin => self.spin,
+        }
+    }
+
+    /// Returns the variation with the given flag set to `value`.
+    pub(super) fn with(self, flag: VariationFlag, value: bool) -> Self {
+        match flag {
+            VariationFlag::AbsRe => Self {
+                abs_re: value,
+                ..self
+            },
+            VariationFlag::AbsIm => Self {
+                abs_im: value,
+                ..self
+            },
+            VariationFlag::Conjugate => Self {
+                conjugate: value,
+                ..self
+            },
+            VariationFlag::Spin => Self {
+                spin: value,
+                ..self
+            },
+        }
+    }
+
+    /// Packs the flags into the bits of `variation` in the uniform block.
+    pub(super) fn bits(&self) -> u32 {
+        VariationFlag::ALL
+            .into_iter()
+            .filter(|flag| self.get(*flag))
+            .map(VariationFlag::bit)
+            .sum()
+    }
+}
+
+/// One of the checkboxes of [`Variation`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(super) enum VariationFlag {
+    AbsRe,
+    AbsIm,
+    Conjugate,
+    Spin,
+}
+
+impl VariationFlag {
+    pub(super) const ALL: [VariationFlag; 4] =
+        [Self::AbsRe, Self::AbsIm, Self::Conjugate, Self::Spin];
+
+    /// Must match the constants of the same name in `frag.wgsl`.
+    fn bit(self) -> u32 {
+        match self {
+            VariationFlag::AbsRe => 1,
+            VariationFlag::AbsIm => 2,
+            VariationFlag::Conjugate => 4,
+            VariationFlag::Spin => 8,
+        }
+    }
+}
+
+impl Display for VariationFlag {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            VariationFlag::AbsRe => write!(f, "Absolute real part"),
+            VariationFlag::AbsIm => write!(f, "Absolute imaginary part"),
+            VariationFlag::Conjugate => write!(f, "Conjugate"),
+            VariationFlag::Spin => write!(f, "Spin with time"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Variation, VariationFlag};
+    use googletest::prelude::*;
+
+    #[test]
+    fn flags_are_packed_into_distinct_bits() -> Result<()> {
+        let variation = Variation::default()
+            .with(VariationFlag::AbsIm, true)
+            .with(VariationFlag::Conjugate, true);
+
+        verify_that!(
+            (Variation::default().bits(), variation.bits()),
+            (eq(0), eq(0b110))
+        )
+    }
+}
@@ -0,0 +1,465 @@
+use crate::{gpu::Gpu, pipeline_compiler};
+use bytemuck::{Pod, Zeroable};
+use cgmath::{Angle, Deg, Vector2};
+use iced_wgpu::wgpu::{self, util::DeviceExt};
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, fmt::Display};
+
+/// The most segments with which a curve is drawn, which bounds the depth to which it recurses.
+const MAX_SEGMENTS: u64 = 1 << 19;
+
+/// How long the segments of a curve may be on the screen, in pixels, before the next depth is
+/// drawn. Deeper curves only add detail too fine to see.
+const LOD_PIXELS: f32 = 2.0;
+
+/// Draws one of the classic geometric fractal curves as lines instead of shading the fractal.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub(super) enum VectorMode {
+    #[default]
+    Off,
+    /// Koch's snowflake, whose sides fold outwards into ever smaller triangles.
+    Koch,
+    /// The Heighway dragon, made by folding a strip of paper in half again and again.
+    Dragon,
+    /// Hilbert's space-filling curve through the square.
+    Hilbert,
+    /// The Gosper curve, which fills a flowsnake-shaped island.
+    Gosper,
+}
+
+impl VectorMode {
+    pub(super) const ALL: [VectorMode; 5] = [
+        Self::Off,
+        Self::Koch,
+        Self::Dragon,
+        Self::Hilbert,
+        Self::Gosper,
+    ];
+
+    fn definition(self) -> Option<&'static CurveDefinition> {
+        match self {
+            VectorMode::Off => None,
+            VectorMode::Koch => Some(&KOCH),
+            VectorMode::Dragon => Some(&DRAGON),
+            VectorMode::Hilbert => Some(&HILBERT),
+            VectorMode::Gosper => Some(&GOSPER),
+        }
+    }
+
+    /// The deepest recursion which is drawn with at most [`MAX_SEGMENTS`].
+    pub(super) fn max_depth(self) -> u32 {
+        self.definition().map_or(0, |definition| {
+            (1..)
+                .take_while(|depth| definition.segment_count(*depth) <= MAX_SEGMENTS)
+                .last()
+                .unwrap_or(1)
+        })
+    }
+
+    /// The points of the curve at the given depth, from its start to its end, framed to fit the
+    /// square `[-1, 1] x [-1, 1]` of the plane as it is drawn. Empty while no curve is drawn.
+    pub(super) fn polyline(self, depth: u32) -> Vec<Vector2<f64>> {
+        self.definition()
+            .map_or(vec![], |definition| definition.framed_points(depth).0)
+    }
+
+    /// Limits a depth to those to which the curve recurses, from one to its maximum. Curves which
+    /// grow faster recurse less deeply. The depth is kept while no curve is drawn.
+    pub(super) fn clamp_depth(self, depth: u32) -> u32 {
+        match self {
+            VectorMode::Off => depth,
+            _ => depth.clamp(1, self.max_depth()),
+        }
+    }
+}
+
+impl Display for VectorMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            VectorMode::Off => write!(f, "Shaded fractal"),
+            VectorMode::Koch => write!(f, "Koch snowflake"),
+            VectorMode::Dragon => write!(f, "Dragon curve"),
+            VectorMode::Hilbert => write!(f, "Hilbert curve"),
+            VectorMode::Gosper => write!(f, "Gosper curve"),
+        }
+    }
+}
+
+/// A curve drawn by a turtle from an L-system. The axiom is rewritten by the rules once per depth,
+/// then each drawing symbol moves the turtle forward by a segment, while `+` and `-` turn it left
+/// and right by the angle. Other symbols only steer the rewriting.
+struct CurveDefinition {
+    axiom: &'static str,
+    rules: &'static [(char, &'static str)],
+    drawing: &'static [char],
+    angle: Deg<f64>,
+}
+
+const KOCH: CurveDefinition = CurveDefinition {
+    axiom: "F--F--F",
+    rules: &[('F', "F+F--F+F")],
+    drawing: &['F'],
+    angle: Deg(60.0),
+};
+
+const DRAGON: CurveDefinition = CurveDefinition {
+    axiom: "FX",
+    rules: &[('X', "X+YF+"), ('Y', "-FX-Y")],
+    drawing: &['F'],
+    angle: Deg(90.0),
+};
+
+const HILBERT: CurveDefinition = CurveDefinition {
+    axiom: "A",
+    rules: &[('A', "+BF-AFA-FB+"), ('B', "-AF+BFB+FA-")],
+    drawing: &['F'],
+    angle: Deg(90.0),
+};
+
+const GOSPER: CurveDefinition = CurveDefinition {
+    axiom: "A",
+    rules: &[('A', "A-B--B+A++AA+B-"), ('B', "+A-AA--B-A++A+B")],
+    drawing: &['A', 'B'],
+    angle: Deg(60.0),
+};
+
+/// A vertex of the line list of a curve, matching the inputs of `curve_vertex` in `curve.wgsl`.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Pod, Zeroable)]
+struct CurveVertex {
+    /// The point of the plane.
+    position: [f32; 2],
+    /// How far along the curve the point lies, from 0 at its start to 1 at its end, by which it is
+    /// coloured from the palette.
+    along: f32,
+}
+
+/// The segments of a curve at one depth, framed to fit the square `[-1, 1] x [-1, 1]`.
+struct CurveMesh {
+    /// Two vertices for each segment, from the start of the curve to its end.
+    vertices: Vec<CurveVertex>,
+    segment_length: f32,
+}
+
+impl CurveDefinition {
+    fn rewrite(&self, depth: u32) -> String {
+        let mut symbols = self.axiom.to_string();
+        for _ in 0..depth {
+            symbols = symbols
+                .chars()
+                .map(
+                    |symbol| match self.rules.iter().find(|(from, _)| *from == symbol) {
+                        Some((_, to)) => to.to_string(),
+                        None => symbol.to_string(),
+                    },
+                )
+                .collect();
+        }
+        symbols
+    }
+
+    /// The number of segments at the depth, counted without rewriting the axiom, whose length
+    /// grows exponentially.
+    fn segment_count(&self, depth: u32) -> u64 {
+        let mut counts: HashMap<char, u64> = HashMap::new();
+        for symbol in self.axiom.chars() {
+            *counts.entry(symbol).or_default() += 1;
+        }
+        for _ in 0..depth {
+            let mut rewritten: HashMap<char, u64> = HashMap::new();
+            for (symbol, count) in counts {
+                match self.rules.iter().find(|(from, _)| *from == symbol) {
+                    Some((_, to)) => {
+                        for produced in to.chars() {
+                            *rewritten.entry(produced).or_default() += count;
+                        }
+                    }
+                    None => *rewritten.entry(symbol).or_default() += count,
+                }
+            }
+            counts = rewritten;
+        }
+        self.drawing
+            .iter()
+            .map(|symbol| counts.get(symbol).copied().unwrap_or(0))
+            .sum()
+    }
+
+    /// The points visited by the turtle at the depth, framed to fit the square `[-1, 1] x [-1, 1]`,
+    /// and the length of a segment after framing.
+    fn framed_points(&self, depth: u32) -> (Vec<Vector2<f64>>, f64) {
+        let mut position = Vector2::new(0.0, 0.0);
+        let mut heading = Deg(0.0);
+        let mut points = vec![position];
+        for symbol in self.rewrite(depth).chars() {
+            match symbol {
+                '+' => heading += self.angle,
+                '-' => heading -= self.angle,
+                symbol if self.drawing.contains(&symbol) => {
+                    position += Vector2::new(heading.cos(), heading.sin());
+                    points.push(position);
+                }
+                _ => {}
+            }
+        }
+        let (min, max) = points.iter().fold(
+            (points[0], points[0]),
+            |(min, max): (Vector2<f64>, Vector2<f64>), point| {
+                (
+                    Vector2::new(min.x.min(point.x), min.y.min(point.y)),
+                    Vector2::new(max.x.max(point.x), max.y.max(point.y)),
+                )
+            },
+        );
+        let centre = (min + max) / 2.0;
+        let scale = 2.0 / (max.x - min.x).max(max.y - min.y).max(f64::EPSILON);
+        let framed = points
+            .into_iter()
+            .map(|point| (point - centre) * scale)
+            .collect();
+        (framed, scale)
+    }
+
+    fn mesh(&self, depth: u32) -> CurveMesh {
+        let (points, segment_length) = self.framed_points(depth);
+        let segments = (points.len() - 1) as f32;
+        let vertex = |index: usize| CurveVertex {
+            position: [points[index].x as f32, points[index].y as f32],
+            along: index as f32 / segments,
+        };
+        CurveMesh {
+            vertices: (1..points.len())
+                .flat_map(|end| [vertex(end - 1), vertex(end)])
+                .collect(),
+            segment_length: segment_length as f32,
+        }
+    }
+}
+
+/// The shallowest of the depths, given the lengths of their segments on the plane, whose segments
+/// are at most [`LOD_PIXELS`] long on the screen, or else the deepest.
+fn level_of_detail(segment_lengths: &[f32], pixel_size: f32) -> usize {
+    segment_lengths
+        .iter()
+        .position(|length| *length <= LOD_PIXELS * pixel_size)
+        .unwrap_or(segment_lengths.len().saturating_sub(1))
+}
+
+/// The segments of a curve at one depth on the GPU.
+struct CurveBuffer {
+    vertices: wgpu::Buffer,
+    vertex_count: u32,
+    segment_length: f32,
+}
+
+/// Draws the curve of a [`VectorMode`] as a line list, coloured from the palette along its length
+/// and post-processed like the fractals.
+///
+/// The curve is kept at every depth up to the one asked for, and each frame draws the shallowest
+/// whose segments are no longer than a couple of pixels, so that a deep curve zoomed out far
+/// costs no more than the detail which can be seen.
+pub(super) struct CurveRenderer {
+    mode: VectorMode,
+    /// The segments of the curve at each depth from one.
+    buffers: Vec<CurveBuffer>,
+    pipeline: wgpu::RenderPipeline,
+}
+
+impl CurveRenderer {
+    /// Builds the pipeline, which reads the uniform block of the view through a bind group with
+    /// the given layout.
+    pub(super) fn new(gpu: &Gpu, bind_group_layout: &wgpu::BindGroupLayout) -> Self {
+        let module = pipeline_compiler::CURVE.create_module(&gpu.device);
+        let layout = gpu
+            .device
+            .create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some("Curve pipeline layout"),
+                bind_group_layouts: &[bind_group_layout],
+                push_constant_ranges: &[],
+            });
+        let pipeline = gpu
+            .device
+            .create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                label: Some("Curve pipeline"),
+                layout: Some(&layout),
+                vertex: wgpu::VertexState {
+                    module: &module,
+                    entry_point: "curve_vertex",
+                    buffers: &[wgpu::VertexBufferLayout {
+                        array_stride: std::mem::size_of::<CurveVertex>() as u64,
+                        step_mode: wgpu::VertexStepMode::Vertex,
+                        attributes: &wgpu::vertex_attr_array![0 => Float32x2, 1 => Float32],
+                    }],
+                },
+                fragment: Some(wgpu::FragmentState {
+                    module: &module,
+                    entry_point: "curve_fragment",
+                    targets: &[Some(wgpu::ColorTargetState {
+                        format: gpu.texture_format,
+                        blend: None,
+                        write_mask: wgpu::ColorWrites::ALL,
+                    })],
+                }),
+                primitive: wgpu::PrimitiveState {
+                    topology: wgpu::PrimitiveTopology::LineList,
+                    ..Default::default()
+                },
+                depth_stencil: None,
+                multisample: wgpu::MultisampleState::default(),
+                multiview: None,
+            });
+        Self {
+            mode: VectorMode::Off,
+            buffers: vec![],
+            pipeline,
+        }
+    }
+
+    /// Sets the curve and the depth to which it recurses, at most [`VectorMode::max_depth`].
+    /// Depths already built for the curve are kept.
+    pub(super) fn set(&mut self, gpu: &Gpu, mode: VectorMode, depth: u32) {
+        if mode != self.mode {
+            self.mode = mode;
+            self.buffers.clear();
+        }
+        let Some(definition) = mode.definition() else {
+            return;
+        };
+        let depth = mode.clamp_depth(depth) as usize;
+        self.buffers.truncate(depth);
+        for depth in self.buffers.len() + 1..=depth {
+            let mesh = definition.mesh(depth as u32);
+            let vertices = gpu
+                .device
+                .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                    label: Some("Curve vertex buffer"),
+                    contents: bytemuck::cast_slice(&mesh.vertices),
+                    usage: wgpu::BufferUsages::VERTEX,
+                });
+            self.buffers.push(CurveBuffer {
+                vertices,
+                vertex_count: mesh.vertices.len() as u32,
+                segment_length: mesh.segment_length,
+            });
+        }
+    }
+
+    pub(super) fn mode(&self) -> VectorMode {
+        self.mode
+    }
+
+    /// The depth to which the curve recurses, or zero while no curve is drawn.
+    pub(super) fn depth(&self) -> u32 {
+        self.buffers.len() as u32
+    }
+
+    /// Clears the target to black and draws the curve onto it, at the depth of detail for pixels
+    /// of the given size on the plane.
+    pub(super) fn render(
+        &self,
+        target: &wgpu::Texture,
+        bind_group: &wgpu::BindGroup,
+        pixel_size: f32,
+        encoder: &mut wgpu::CommandEncoder,
+    ) {
+        let target = target.create_view(&wgpu::TextureViewDescriptor::default());
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Curve pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: &target,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+        let segment_lengths: Vec<f32> = self
+            .buffers
+            .iter()
+            .map(|buffer| buffer.segment_length)
+            .collect();
+        let Some(buffer) = self
+            .buffers
+            .get(level_of_detail(&segment_lengths, pixel_size))
+        else {
+            return;
+        };
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_bind_group(0, bind_group, &[]);
+        render_pass.set_vertex_buffer(0, buffer.vertices.slice(..));
+        render_pass.draw(0..buffer.vertex_count, 0..1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{level_of_detail, VectorMode, KOCH, MAX_SEGMENTS};
+    use googletest::prelude::*;
+
+    #[test]
+    fn segments_are_counted_without_rewriting() -> Result<()> {
+        for mode in VectorMode::ALL {
+            let Some(definition) = mode.definition() else {
+                continue;
+            };
+            for depth in 1..=3 {
+                let mesh = definition.mesh(depth);
+                verify_that!(
+                    definition.segment_count(depth),
+                    eq(mesh.vertices.len() as u64 / 2)
+                )?;
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn deepest_curves_stay_within_segment_budget() -> Result<()> {
+        verify_that!(
+            VectorMode::ALL.map(|mode| mode
+                .definition()
+                .map(|definition| definition.segment_count(mode.max_depth()))),
+            eq([
+                None,
+                Some(3 * 4u64.pow(8)),
+                Some(1 << 19),
+                Some(4u64.pow(9) - 1),
+                Some(7u64.pow(6))
+            ])
+        )?;
+        verify_that!(KOCH.segment_count(9), gt(MAX_SEGMENTS))
+    }
+
+    #[test]
+    fn koch_snowflake_is_framed_to_fit_square() -> Result<()> {
+        let mesh = KOCH.mesh(2);
+
+        let extent = mesh
+            .vertices
+            .iter()
+            .flat_map(|vertex| vertex.position)
+            .fold(0.0f32, |extent, coordinate| extent.max(coordinate.abs()));
+        verify_that!(
+            (
+                mesh.vertices.len(),
+                extent,
+                mesh.vertices.last().unwrap().along
+            ),
+            (eq(2 * 48), near(1.0, 1e-6), eq(1.0))
+        )
+    }
+
+    #[test]
+    fn zooming_out_draws_shallower_depth() -> Result<()> {
+        let segment_lengths = [0.5, 0.1, 0.02, 0.004];
+
+        verify_that!(
+            [0.3, 0.01, 0.0001].map(|pixel_size| level_of_detail(&segment_lengths, pixel_size)),
+            eq([0, 2, 3])
+        )
+    }
+}
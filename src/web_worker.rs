@@ -0,0 +1,339 @@
+use crate::{
+    jobs::{Job, Progress},
+    minibrot::FindMinibrot,
+    reference_orbit::ComputeReferenceOrbit,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    cell::{Cell, RefCell},
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicU32, Ordering},
+        mpsc, Arc,
+    },
+};
+use wasm_bindgen::{closure::Closure, prelude::wasm_bindgen, JsCast, JsValue};
+use web_sys::{
+    Blob, BlobPropertyBag, DedicatedWorkerGlobalScope, ErrorEvent, MessageEvent, Url, Worker,
+    WorkerOptions, WorkerType,
+};
+
+/// The jobs which web workers can run, by their names. Jobs missing here fail on the web.
+const JOBS: &[(&str, RunJob)] = &[
+    (
+        ComputeReferenceOrbit::NAME,
+        run_job::<ComputeReferenceOrbit>,
+    ),
+    (FindMinibrot::NAME, run_job::<FindMinibrot>),
+];
+
+type RunJob = fn(serde_json::Value) -> Option<serde_json::Value>;
+
+#[wasm_bindgen]
+extern "C" {
+    /// The URL of the JavaScript which wasm-bindgen generated for the explorer, from which the
+    /// workers load the same module as the page.
+    #[wasm_bindgen(thread_local_v2, js_namespace = ["import", "meta"], js_name = url)]
+    static SCRIPT_URL: String;
+}
+
+/// A job sent from the page to a worker.
+#[derive(Serialize, Deserialize)]
+struct Request {
+    id: u32,
+    name: String,
+    job: serde_json::Value,
+}
+
+/// A message from a worker to the page.
+#[derive(Serialize, Deserialize)]
+enum Reply {
+    /// The worker has loaded the module and accepts requests.
+    Ready,
+    Progress {
+        id: u32,
+        fraction: f32,
+    },
+    Finished {
+        id: u32,
+        output: serde_json::Value,
+    },
+    /// The job is unknown or its message could not be read.
+    Failed {
+        id: u32,
+    },
+}
+
+thread_local! {
+    static POOL: RefCell<Option<Pool>> = const { RefCell::new(None) };
+    /// In a worker, the job being run and the progress last reported for it in percent.
+    static CURRENT_JOB: Cell<Option<(u32, Option<u8>)>> = const { Cell::new(None) };
+}
+
+/// The web workers of the page and the jobs they were sent.
+struct Pool {
+    workers: Vec<WorkerSlot>,
+    next_id: u32,
+    pending: HashMap<u32, PendingJob>,
+}
+
+struct WorkerSlot {
+    worker: Worker,
+    /// Requests sent before the worker loaded the module, which it would miss.
+    queued: Option<Vec<String>>,
+}
+
+struct PendingJob {
+    worker: usize,
+    progress: Arc<AtomicU32>,
+    /// Delivers the output of the job to its handle. Dropping it fails the job.
+    finish: Box<dyn FnOnce(serde_json::Value)>,
+}
+
+impl Pool {
+    /// Starts one worker per core but the one of the page. Browsers which cannot start module
+    /// workers get none, in which case jobs run on the page.
+    fn start() -> Self {
+        let cores =
+            web_sys::window().map_or(1.0, |window| window.navigator().hardware_concurrency());
+        let workers = (0..(cores as usize).saturating_sub(1).max(1))
+            .map_while(|index| {
+                create_worker(index)
+                    .inspect_err(|error| log::warn!("Could not start web worker: {error:?}"))
+                    .ok()
+            })
+            .map(|worker| WorkerSlot {
+                worker,
+                queued: Some(vec![]),
+            })
+            .collect();
+        Self {
+            workers,
+            next_id: 0,
+            pending: HashMap::new(),
+        }
+    }
+
+    fn send(&mut self, index: usize, message: String) {
+        let slot = &mut self.workers[index];
+        match &mut slot.queued {
+            Some(queued) => queued.push(message),
+            None => {
+                let _ = slot.worker.post_message(&JsValue::from_str(&message));
+            }
+        }
+    }
+
+    /// The worker with the fewest jobs.
+    fn least_busy_worker(&self) -> usize {
+        (0..self.workers.len())
+            .min_by_key(|index| {
+                self.pending
+                    .values()
+                    .filter(|job| job.worker == *index)
+                    .count()
+            })
+            .unwrap_or(0)
+    }
+}
+
+fn create_worker(index: usize) -> Result<Worker, JsValue> {
+    // The module runs `main`, which serves requests once it finds itself in a worker.
+    let script = SCRIPT_URL.with(|url| {
+        format!(
+            "import init from {};\nawait init();\n",
+            serde_json::to_string(url).unwrap_or_default()
+        )
+    });
+    let mut blob_options = BlobPropertyBag::new();
+    blob_options.type_("text/javascript");
+    let blob = Blob::new_with_str_sequence_and_options(
+        &js_sys::Array::of1(&JsValue::from_str(&script)),
+        &blob_options,
+    )?;
+    let mut worker_options = WorkerOptions::new();
+    worker_options
+        .type_(WorkerType::Module)
+        .name(&format!("worker {index}"));
+    let worker =
+        Worker::new_with_options(&Url::create_object_url_with_blob(&blob)?, &worker_options)?;
+
+    let onmessage = Closure::<dyn FnMut(MessageEvent)>::new(move |event: MessageEvent| {
+        receive(index, event);
+    });
+    worker.set_onmessage(Some(onmessage.as_ref().unchecked_ref()));
+    onmessage.forget();
+    // Panics abort the module of the worker, which then needs replacing.
+    let onerror = Closure::<dyn FnMut(ErrorEvent)>::new(move |event: ErrorEvent| {
+        log::error!("Web worker {index} failed: {}", event.message());
+        restart(index);
+    });
+    worker.set_onerror(Some(onerror.as_ref().unchecked_ref()));
+    onerror.forget();
+    Ok(worker)
+}
+
+/// Whether jobs can run in web workers, starting them on first use.
+pub(super) fn is_available() -> bool {
+    POOL.with_borrow_mut(|pool| !pool.get_or_insert_with(Pool::start).workers.is_empty())
+}
+
+/// Sends the job to the least busy worker. The result of `finish` is sent through `sender` once
+/// the worker returns the output, and the progress it reports is stored in `progress`.
+pub(super) fn submit<J: Job, T: Send + 'static>(
+    job: J,
+    finish: fn(J::Output) -> T,
+    sender: mpsc::Sender<T>,
+    progress: Arc<AtomicU32>,
+) {
+    let Ok(job) = serde_json::to_value(job) else {
+        log::error!("Could not send job \"{}\" to a web worker", J::NAME);
+        return;
+    };
+    POOL.with_borrow_mut(|pool| {
+        let pool = pool.get_or_insert_with(Pool::start);
+        let id = pool.next_id;
+        pool.next_id = pool.next_id.wrapping_add(1);
+        let request = Request {
+            id,
+            name: J::NAME.into(),
+            job,
+        };
+        let Ok(message) = serde_json::to_string(&request) else {
+            return;
+        };
+        let worker = pool.least_busy_worker();
+        pool.pending.insert(
+            id,
+            PendingJob {
+                worker,
+                progress,
+                finish: Box::new(move |output| {
+                    if let Ok(output) = serde_json::from_value(output) {
+                        let _ = sender.send(finish(output));
+                    }
+                }),
+            },
+        );
+        pool.send(worker, message);
+    });
+}
+
+/// Handles a reply from the worker with the given index on the page.
+fn receive(index: usize, event: MessageEvent) {
+    let Some(reply) = event
+        .data()
+        .as_string()
+        .and_then(|data| serde_json::from_str::<Reply>(&data).ok())
+    else {
+        return;
+    };
+    POOL.with_borrow_mut(|pool| {
+        let Some(pool) = pool else {
+            return;
+        };
+        match reply {
+            Reply::Ready => {
+                for message in pool.workers[index].queued.take().unwrap_or_default() {
+                    pool.send(index, message);
+                }
+            }
+            Reply::Progress { id, fraction } => {
+                if let Some(job) = pool.pending.get(&id) {
+                    job.progress.store(fraction.to_bits(), Ordering::Relaxed);
+                }
+            }
+            Reply::Finished { id, output } => {
+                if let Some(job) = pool.pending.remove(&id) {
+                    (job.finish)(output);
+                }
+            }
+            Reply::Failed { id } => {
+                pool.pending.remove(&id);
+            }
+        }
+    });
+}
+
+/// Replaces the worker with the given index after it failed, which fails the jobs it was sent.
+fn restart(index: usize) {
+    POOL.with_borrow_mut(|pool| {
+        let Some(pool) = pool else {
+            return;
+        };
+        pool.pending.retain(|_, job| job.worker != index);
+        pool.workers[index].worker.terminate();
+        match create_worker(index) {
+            Ok(worker) => {
+                pool.workers[index] = WorkerSlot {
+                    worker,
+                    queued: Some(vec![]),
+                };
+            }
+            Err(error) => log::error!("Could not restart web worker {index}: {error:?}"),
+        }
+    });
+}
+
+/// Whether the module runs in a web worker rather than on the page.
+pub(super) fn is_worker() -> bool {
+    js_sys::global()
+        .dyn_into::<DedicatedWorkerGlobalScope>()
+        .is_ok()
+}
+
+/// Runs the jobs which the page sends to this worker until the page terminates it.
+pub(super) fn serve() {
+    let onmessage = Closure::<dyn FnMut(MessageEvent)>::new(|event: MessageEvent| {
+        let Some(request) = event
+            .data()
+            .as_string()
+            .and_then(|data| serde_json::from_str::<Request>(&data).ok())
+        else {
+            log::warn!("Web worker received an unreadable request");
+            return;
+        };
+        let id = request.id;
+        CURRENT_JOB.set(Some((id, None)));
+        let output = JOBS
+            .iter()
+            .find(|(name, _)| *name == request.name)
+            .and_then(|(_, run)| run(request.job));
+        CURRENT_JOB.set(None);
+        post(&match output {
+            Some(output) => Reply::Finished { id, output },
+            None => Reply::Failed { id },
+        });
+    });
+    worker_scope().set_onmessage(Some(onmessage.as_ref().unchecked_ref()));
+    onmessage.forget();
+    post(&Reply::Ready);
+}
+
+/// Passes the progress of the job being run in this worker on to the page, whenever it reaches
+/// another percent. Does nothing on the page.
+pub(super) fn report_progress(fraction: f32) {
+    let Some((id, reported)) = CURRENT_JOB.get() else {
+        return;
+    };
+    let percent = (fraction * 100.0) as u8;
+    if reported != Some(percent) {
+        CURRENT_JOB.set(Some((id, Some(percent))));
+        post(&Reply::Progress { id, fraction });
+    }
+}
+
+fn run_job<J: Job>(job: serde_json::Value) -> Option<serde_json::Value> {
+    let job: J = serde_json::from_value(job).ok()?;
+    serde_json::to_value(job.run(&Progress::new())).ok()
+}
+
+fn post(reply: &Reply) {
+    if let Ok(message) = serde_json::to_string(reply) {
+        let _ = worker_scope().post_message(&JsValue::from_str(&message));
+    }
+}
+
+fn worker_scope() -> DedicatedWorkerGlobalScope {
+    js_sys::global().unchecked_into()
+}
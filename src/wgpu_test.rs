@@ -1,6 +1,8 @@
+use crate::export::padded_bytes_per_row;
 use bytemuck::Pod;
 use iced_wgpu::wgpu::{self, util::DeviceExt};
-use std::{marker::PhantomData, num::NonZeroU64};
+use palette::{color_difference::Ciede2000, IntoColor, Lab, Srgb};
+use std::{marker::PhantomData, num::NonZeroU64, path::PathBuf};
 
 #[macro_export]
 macro_rules! wgsl_shader_test {
@@ -45,17 +47,100 @@ pub trait DescribableStruct {
     }
 }
 
+/// Runs compute shaders against test inputs and reads back their outputs.
+///
+/// The input struct `T` is bound at `@group(1) @binding(0)`. Further resources added with the
+/// builder methods [`Self::with_buffer`], [`Self::with_output_texture`], [`Self::with_texture`]
+/// and [`Self::with_sampler`] are bound at the following bindings of group 1 in the order in which
+/// they were added. Buffers and output textures may be read back by name after the shader has run.
 pub struct GpuTestHarness<'a, T: DescribableStruct + Pod> {
     device: &'a wgpu::Device,
     queue: &'a wgpu::Queue,
     staging_buffer: wgpu::Buffer,
     storage_buffer: wgpu::Buffer,
-    bind_group_layout: wgpu::BindGroupLayout,
-    bind_group: wgpu::BindGroup,
+    resources: Vec<(&'static str, TestResource)>,
     other_bind_groups: Vec<(u32, &'a wgpu::BindGroup, &'a wgpu::BindGroupLayout)>,
+    workgroups: (u32, u32, u32),
     phantom: PhantomData<T>,
 }
 
+enum TestResource {
+    Buffer {
+        storage: wgpu::Buffer,
+        staging: wgpu::Buffer,
+    },
+    OutputTexture {
+        texture: wgpu::Texture,
+        view: wgpu::TextureView,
+        staging: wgpu::Buffer,
+    },
+    /// A texture which the shader only reads, which is not read back.
+    Texture(wgpu::TextureView),
+    Sampler(wgpu::Sampler),
+}
+
+impl TestResource {
+    fn binding_type(&self) -> wgpu::BindingType {
+        match self {
+            TestResource::Buffer { .. } => wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Storage { read_only: false },
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            TestResource::OutputTexture { .. } => wgpu::BindingType::StorageTexture {
+                access: wgpu::StorageTextureAccess::WriteOnly,
+                format: TEST_TEXTURE_FORMAT,
+                view_dimension: wgpu::TextureViewDimension::D2,
+            },
+            TestResource::Texture(_) => wgpu::BindingType::Texture {
+                sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                view_dimension: wgpu::TextureViewDimension::D2,
+                multisampled: false,
+            },
+            TestResource::Sampler(_) => {
+                wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering)
+            }
+        }
+    }
+
+    fn binding_resource(&self) -> wgpu::BindingResource<'_> {
+        match self {
+            TestResource::Buffer { storage, .. } => storage.as_entire_binding(),
+            TestResource::OutputTexture { view, .. } | TestResource::Texture(view) => {
+                wgpu::BindingResource::TextureView(view)
+            }
+            TestResource::Sampler(sampler) => wgpu::BindingResource::Sampler(sampler),
+        }
+    }
+
+    fn copy_to_staging(&self, encoder: &mut wgpu::CommandEncoder) {
+        match self {
+            TestResource::Buffer { storage, staging } => {
+                encoder.copy_buffer_to_buffer(storage, 0, staging, 0, storage.size());
+            }
+            TestResource::OutputTexture {
+                texture, staging, ..
+            } => {
+                encoder.copy_texture_to_buffer(
+                    texture.as_image_copy(),
+                    wgpu::ImageCopyBuffer {
+                        buffer: staging,
+                        layout: wgpu::ImageDataLayout {
+                            offset: 0,
+                            bytes_per_row: Some(padded_bytes_per_row(texture.width())),
+                            rows_per_image: None,
+                        },
+                    },
+                    texture.size(),
+                );
+            }
+            TestResource::Texture(_) | TestResource::Sampler(_) => {}
+        }
+    }
+}
+
+const TEST_TEXTURE_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8Unorm;
+
 impl<'a, T: DescribableStruct + Pod> GpuTestHarness<'a, T> {
     pub fn new(device: &'a wgpu::Device, queue: &'a wgpu::Queue, input: &T) -> Self {
         let staging_buffer = device.create_buffer(&T::descriptor());
@@ -66,26 +151,14 @@ impl<'a, T: DescribableStruct + Pod> GpuTestHarness<'a, T> {
                 | wgpu::BufferUsages::COPY_DST
                 | wgpu::BufferUsages::COPY_SRC,
         });
-        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-            label: None,
-            entries: &[T::layout_entry()],
-        });
-        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
-            label: None,
-            layout: &bind_group_layout,
-            entries: &[wgpu::BindGroupEntry {
-                binding: 0,
-                resource: storage_buffer.as_entire_binding(),
-            }],
-        });
         Self {
             device,
             queue,
             staging_buffer,
             storage_buffer,
-            bind_group_layout,
-            bind_group,
+            resources: Default::default(),
             other_bind_groups: Default::default(),
+            workgroups: (1, 1, 1),
             phantom: Default::default(),
         }
     }
@@ -101,18 +174,164 @@ impl<'a, T: DescribableStruct + Pod> GpuTestHarness<'a, T> {
         self
     }
 
+    /// Adds a read-write storage buffer initialised with `contents`, which may be declared as a
+    /// runtime-sized array in the shader.
+    pub fn with_buffer<U: Pod>(mut self, name: &'static str, contents: &[U]) -> Self {
+        let contents = bytemuck::cast_slice(contents);
+        let storage = self
+            .device
+            .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some(name),
+                contents,
+                usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
+            });
+        let staging = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some(name),
+            size: contents.len() as u64,
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        self.resources
+            .push((name, TestResource::Buffer { storage, staging }));
+        self
+    }
+
+    /// Adds an RGBA8 output texture, to be declared as `texture_storage_2d<rgba8unorm, write>` in
+    /// the shader.
+    pub fn with_output_texture(mut self, name: &'static str, width: u32, height: u32) -> Self {
+        let texture = self.device.create_texture(&wgpu::TextureDescriptor {
+            label: Some(name),
+            size: wgpu::Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: TEST_TEXTURE_FORMAT,
+            usage: wgpu::TextureUsages::STORAGE_BINDING | wgpu::TextureUsages::COPY_SRC,
+            view_formats: &[],
+        });
+        let view = texture.create_view(&Default::default());
+        let staging = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some(name),
+            size: (padded_bytes_per_row(width) * height) as u64,
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        self.resources.push((
+            name,
+            TestResource::OutputTexture {
+                texture,
+                view,
+                staging,
+            },
+        ));
+        self
+    }
+
+    /// Adds an RGBA8 texture holding the tightly packed `pixels`, to be declared as
+    /// `texture_2d<f32>` in the shader and read with `textureLoad` or through a sampler added with
+    /// [`Self::with_sampler`].
+    pub fn with_texture(
+        mut self,
+        name: &'static str,
+        width: u32,
+        height: u32,
+        pixels: &[u8],
+    ) -> Self {
+        let size = wgpu::Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        };
+        let texture = self.device.create_texture(&wgpu::TextureDescriptor {
+            label: Some(name),
+            size,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: TEST_TEXTURE_FORMAT,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+            view_formats: &[],
+        });
+        self.queue.write_texture(
+            texture.as_image_copy(),
+            pixels,
+            wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(width * 4),
+                rows_per_image: None,
+            },
+            size,
+        );
+        let view = texture.create_view(&Default::default());
+        self.resources.push((name, TestResource::Texture(view)));
+        self
+    }
+
+    /// Adds a sampler which filters linearly and clamps to the edge, to be declared as `sampler`
+    /// in the shader.
+    pub fn with_sampler(mut self, name: &'static str) -> Self {
+        let sampler = self.device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some(name),
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+        self.resources.push((name, TestResource::Sampler(sampler)));
+        self
+    }
+
+    /// Sets the number of workgroups dispatched in each dimension. The default is a single
+    /// workgroup.
+    pub fn with_workgroups(mut self, x: u32, y: u32, z: u32) -> Self {
+        self.workgroups = (x, y, z);
+        self
+    }
+
     pub fn run_compute_shader(
         &self,
         shader_test_descriptor: wgpu::ShaderModuleDescriptor,
         entry_point: &'static str,
     ) {
         let module = self.device.create_shader_module(shader_test_descriptor);
+        let mut layout_entries = vec![T::layout_entry()];
+        let mut bind_group_entries = vec![wgpu::BindGroupEntry {
+            binding: 0,
+            resource: self.storage_buffer.as_entire_binding(),
+        }];
+        for (binding, (_, resource)) in (1..).zip(&self.resources) {
+            layout_entries.push(wgpu::BindGroupLayoutEntry {
+                binding,
+                visibility: wgpu::ShaderStages::COMPUTE,
+                ty: resource.binding_type(),
+                count: None,
+            });
+            bind_group_entries.push(wgpu::BindGroupEntry {
+                binding,
+                resource: resource.binding_resource(),
+            });
+        }
+        let bind_group_layout =
+            self.device
+                .create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                    label: None,
+                    entries: &layout_entries,
+                });
+        let bind_group = self.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: None,
+            layout: &bind_group_layout,
+            entries: &bind_group_entries,
+        });
+
         let mut bind_group_layouts: Vec<_> = self
             .other_bind_groups
             .iter()
             .map(|(_, _, layout)| *layout)
             .collect();
-        bind_group_layouts.push(&self.bind_group_layout);
+        bind_group_layouts.push(&bind_group_layout);
         let pipeline_layout = self
             .device
             .create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
@@ -135,9 +354,10 @@ impl<'a, T: DescribableStruct + Pod> GpuTestHarness<'a, T> {
             for (index, bind_group, _) in &self.other_bind_groups {
                 compute_pass.set_bind_group(*index, bind_group, &[]);
             }
-            compute_pass.set_bind_group(1, &self.bind_group, &[]);
+            compute_pass.set_bind_group(1, &bind_group, &[]);
             compute_pass.set_pipeline(&pipeline);
-            compute_pass.dispatch_workgroups(1, 1, 1);
+            let (x, y, z) = self.workgroups;
+            compute_pass.dispatch_workgroups(x, y, z);
         }
         self.copy(&mut encoder);
         self.queue.submit(Some(encoder.finish()));
@@ -151,18 +371,158 @@ impl<'a, T: DescribableStruct + Pod> GpuTestHarness<'a, T> {
             0,
             std::mem::size_of::<T>() as u64,
         );
+        for (_, resource) in &self.resources {
+            resource.copy_to_staging(encoder);
+        }
     }
 
     pub async fn fetch_result(&self, device: &wgpu::Device) -> T {
-        let buffer_slice = self.staging_buffer.slice(..);
-        let (sender, receiver) = futures_intrusive::channel::shared::oneshot_channel();
-        buffer_slice.map_async(wgpu::MapMode::Read, move |v| sender.send(v).unwrap());
-        device.poll(wgpu::Maintain::Wait);
-        receiver.receive().await;
-        let data = buffer_slice.get_mapped_range();
-        let result = *bytemuck::from_bytes::<T>(&data);
-        drop(data);
-        self.staging_buffer.unmap();
-        result
+        let data = read_staging_buffer(device, &self.staging_buffer).await;
+        bytemuck::pod_read_unaligned(&data)
+    }
+
+    /// Reads back the contents of the buffer added under `name` as an array of `U`.
+    pub async fn fetch_buffer<U: Pod>(&self, name: &str) -> Vec<U> {
+        let TestResource::Buffer { staging, .. } = self.resource(name) else {
+            panic!("{name} is not a buffer");
+        };
+        bytemuck::pod_collect_to_vec(&read_staging_buffer(self.device, staging).await)
+    }
+
+    /// Reads back the output texture added under `name` as tightly packed RGBA8 pixels.
+    pub async fn fetch_texture(&self, name: &str) -> Vec<u8> {
+        let TestResource::OutputTexture {
+            texture, staging, ..
+        } = self.resource(name)
+        else {
+            panic!("{name} is not an output texture");
+        };
+        let data = read_staging_buffer(self.device, staging).await;
+        let row_length = (texture.width() * 4) as usize;
+        data.chunks(padded_bytes_per_row(texture.width()) as usize)
+            .flat_map(|row| &row[..row_length])
+            .copied()
+            .collect()
+    }
+
+    fn resource(&self, name: &str) -> &TestResource {
+        self.resources
+            .iter()
+            .find(|(resource_name, _)| *resource_name == name)
+            .map(|(_, resource)| resource)
+            .unwrap_or_else(|| panic!("No resource named {name}"))
+    }
+}
+
+async fn read_staging_buffer(device: &wgpu::Device, staging_buffer: &wgpu::Buffer) -> Vec<u8> {
+    let buffer_slice = staging_buffer.slice(..);
+    let (sender, receiver) = futures_intrusive::channel::shared::oneshot_channel();
+    buffer_slice.map_async(wgpu::MapMode::Read, move |v| sender.send(v).unwrap());
+    device.poll(wgpu::Maintain::Wait);
+    receiver.receive().await;
+    let data = buffer_slice.get_mapped_range().to_vec();
+    staging_buffer.unmap();
+    data
+}
+
+/// How far a rendered image may deviate from its reference snapshot.
+///
+/// Pixels are compared perceptually via the CIEDE2000 colour difference. Small deviations along
+/// the boundary of a fractal are expected between GPU drivers, so a fraction of the pixels is
+/// allowed to differ by more than `max_delta_e`.
+#[derive(Clone, Copy, Debug)]
+pub struct SnapshotTolerance {
+    pub max_delta_e: f32,
+    pub max_differing_fraction: f32,
+}
+
+impl Default for SnapshotTolerance {
+    fn default() -> Self {
+        Self {
+            // Roughly one just-noticeable difference.
+            max_delta_e: 2.3,
+            max_differing_fraction: 0.01,
+        }
+    }
+}
+
+/// Compares the given RGBA image with the reference stored under `src/snapshots/<name>.png`.
+///
+/// Setting the environment variable `UPDATE_SNAPSHOTS=1` writes the image as the new reference
+/// instead. On a mismatch, the rendered image is written to `target/snapshots/<name>.png` so that
+/// it can be inspected.
+pub fn compare_with_snapshot(
+    name: &str,
+    width: u32,
+    height: u32,
+    rgba: &[u8],
+    tolerance: SnapshotTolerance,
+) -> Result<(), String> {
+    let reference_path = snapshot_dir().join(format!("{name}.png"));
+    if std::env::var_os("UPDATE_SNAPSHOTS").is_some_and(|value| value == "1") {
+        let _ = std::fs::create_dir_all(snapshot_dir());
+        return image::save_buffer(
+            &reference_path,
+            rgba,
+            width,
+            height,
+            image::ColorType::Rgba8,
+        )
+        .map_err(|error| format!("Could not write {}: {error}", reference_path.display()));
+    }
+
+    let reference = image::open(&reference_path)
+        .map_err(|error| {
+            format!(
+                "Could not read reference snapshot {}: {error}. \
+                Run the tests with UPDATE_SNAPSHOTS=1 to create it.",
+                reference_path.display()
+            )
+        })?
+        .into_rgba8();
+    if reference.dimensions() != (width, height) {
+        return Err(format!(
+            "Snapshot {name} has size {:?} but the rendered image has size {:?}",
+            reference.dimensions(),
+            (width, height)
+        ));
+    }
+
+    let differing_pixels = reference
+        .as_raw()
+        .chunks(4)
+        .zip(rgba.chunks(4))
+        .filter(|(expected, actual)| delta_e(expected, actual) > tolerance.max_delta_e)
+        .count();
+    let differing_fraction = differing_pixels as f32 / (width * height) as f32;
+    if differing_fraction <= tolerance.max_differing_fraction {
+        return Ok(());
     }
+
+    let actual_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("target/snapshots")
+        .join(format!("{name}.png"));
+    let _ = std::fs::create_dir_all(actual_path.parent().unwrap());
+    let _ = image::save_buffer(&actual_path, rgba, width, height, image::ColorType::Rgba8);
+    Err(format!(
+        "{:.2}% of pixels differ from snapshot {name} by more than \u{0394}E {}, \
+        allowed are {:.2}%. The rendered image was written to {}.",
+        differing_fraction * 100.0,
+        tolerance.max_delta_e,
+        tolerance.max_differing_fraction * 100.0,
+        actual_path.display()
+    ))
+}
+
+fn snapshot_dir() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("src/snapshots")
+}
+
+fn delta_e(expected: &[u8], actual: &[u8]) -> f32 {
+    let to_lab = |pixel: &[u8]| -> Lab {
+        Srgb::new(pixel[0], pixel[1], pixel[2])
+            .into_format::<f32>()
+            .into_color()
+    };
+    to_lab(expected).difference(to_lab(actual))
 }
@@ -0,0 +1,100 @@
+use crate::{
+    bookmark::Bookmark,
+    controls::{LayoutSetting, Panel, UiScale},
+};
+use serde::{Deserialize, Serialize};
+
+/// Which of the optional parts of the controls are shown.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub(super) struct VisiblePanels {
+    /// The panel which the touch layout shows above its toolbar, if any.
+    pub(super) open_panel: Option<Panel>,
+    /// Whether the joystick and buttons of the on-screen navigation are shown.
+    pub(super) navigation: bool,
+    pub(super) histogram: bool,
+}
+
+/// A named setup of the explorer, such as one for deep zooms and another for presenting, between
+/// which the user switches.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub(super) struct Workspace {
+    pub(super) name: String,
+    /// The location, fractal and palette shown.
+    pub(super) view: Bookmark,
+    pub(super) ui_scale: UiScale,
+    pub(super) layout: LayoutSetting,
+    pub(super) panels: VisiblePanels,
+}
+
+/// Adds the workspace to the list, replacing the one of the same name if there is one, and keeps
+/// the list sorted by name.
+pub(super) fn save(workspaces: &mut Vec<Workspace>, workspace: Workspace) {
+    workspaces.retain(|other| other.name != workspace.name);
+    let index = workspaces.partition_point(|other| other.name < workspace.name);
+    workspaces.insert(index, workspace);
+}
+
+/// The names of the workspaces, in the order of the list.
+pub(super) fn names(workspaces: &[Workspace]) -> Vec<String> {
+    workspaces
+        .iter()
+        .map(|workspace| workspace.name.clone())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{names, save, VisiblePanels, Workspace};
+    use crate::{
+        bookmark::Bookmark,
+        controls::{LayoutSetting, UiScale},
+    };
+    use cgmath::Vector2;
+    use googletest::prelude::*;
+
+    fn workspace(name: &str, max_iterations: u32) -> Workspace {
+        Workspace {
+            name: name.into(),
+            view: Bookmark {
+                max_iterations,
+                ..Bookmark::for_test(Vector2::new(0.0, 0.0), 2.0)
+            },
+            ui_scale: UiScale(100),
+            layout: LayoutSetting::Desktop,
+            panels: VisiblePanels::default(),
+        }
+    }
+
+    #[test]
+    fn keeps_workspaces_sorted_by_name() -> Result<()> {
+        let mut workspaces = vec![];
+
+        save(&mut workspaces, workspace("Presentation", 100));
+        save(&mut workspaces, workspace("Deep zoom", 100));
+        save(&mut workspaces, workspace("Julia morphs", 100));
+
+        verify_that!(
+            names(&workspaces),
+            eq(vec![
+                "Deep zoom".to_string(),
+                "Julia morphs".to_string(),
+                "Presentation".to_string()
+            ])
+        )
+    }
+
+    #[test]
+    fn saving_under_existing_name_replaces_workspace() -> Result<()> {
+        let mut workspaces = vec![workspace("Deep zoom", 100), workspace("Presentation", 100)];
+
+        save(&mut workspaces, workspace("Deep zoom", 5000));
+
+        verify_that!(
+            workspaces
+                .iter()
+                .map(|workspace| workspace.view.max_iterations)
+                .collect::<Vec<_>>(),
+            eq(vec![5000, 100])
+        )
+    }
+}